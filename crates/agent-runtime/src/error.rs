@@ -0,0 +1,27 @@
+use agentmarket_sdk::{Pubkey, SdkError};
+use thiserror::Error;
+
+/// Errors surfaced by the agent runtime
+#[derive(Debug, Error)]
+pub enum RuntimeError {
+    #[error(transparent)]
+    Sdk(#[from] SdkError),
+    #[error("handler failed for request {request}: {message}")]
+    Handler { request: Pubkey, message: String },
+    #[error("result for request {request} is {len} bytes, over the {max} byte limit")]
+    ResultTooLong {
+        request: Pubkey,
+        len: usize,
+        max: usize,
+    },
+    #[error("gave up submitting request {request} after {attempts} attempts: {last_error}")]
+    RetriesExhausted {
+        request: Pubkey,
+        attempts: u32,
+        last_error: String,
+    },
+    #[error("websocket error: {0}")]
+    Websocket(String),
+    #[error("ledger error: {0}")]
+    Ledger(#[from] std::io::Error),
+}
@@ -0,0 +1,30 @@
+use std::future::Future;
+
+use agentmarket_sdk::accounts::ServiceRequest;
+
+/// Error type handlers may return; it is logged and the request is retried on the next scan
+pub type HandlerError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Operator logic that turns a service request into its result data
+///
+/// Implemented for any `Fn(ServiceRequest) -> impl Future<Output = Result<String, HandlerError>>`,
+/// so a plain async closure can be passed to [`AgentRuntime`](crate::AgentRuntime).
+pub trait RequestHandler: Send + Sync + 'static {
+    fn handle(
+        &self,
+        request: ServiceRequest,
+    ) -> impl Future<Output = Result<String, HandlerError>> + Send;
+}
+
+impl<F, Fut> RequestHandler for F
+where
+    F: Fn(ServiceRequest) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<String, HandlerError>> + Send,
+{
+    fn handle(
+        &self,
+        request: ServiceRequest,
+    ) -> impl Future<Output = Result<String, HandlerError>> + Send {
+        self(request)
+    }
+}
@@ -0,0 +1,65 @@
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use agentmarket_sdk::Pubkey;
+
+use crate::error::RuntimeError;
+
+/// Set of requests this operator has already answered
+///
+/// A file-backed ledger appends one request id per line, so answered
+/// requests survive restarts; an in-memory ledger only dedupes within a run.
+pub struct Ledger {
+    completed: Mutex<HashSet<Pubkey>>,
+    file: Option<Mutex<File>>,
+}
+
+impl Ledger {
+    pub fn in_memory() -> Self {
+        Self {
+            completed: Mutex::new(HashSet::new()),
+            file: None,
+        }
+    }
+
+    /// Open (or create) a ledger file, loading the ids already recorded in it
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, RuntimeError> {
+        let path = path.as_ref();
+        let mut completed = HashSet::new();
+
+        if path.exists() {
+            for line in BufReader::new(File::open(path)?).lines() {
+                if let Ok(id) = Pubkey::from_str(line?.trim()) {
+                    completed.insert(id);
+                }
+            }
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            completed: Mutex::new(completed),
+            file: Some(Mutex::new(file)),
+        })
+    }
+
+    pub fn contains(&self, request: &Pubkey) -> bool {
+        self.completed.lock().unwrap().contains(request)
+    }
+
+    /// Mark a request as answered; recording the same id twice is a no-op
+    pub fn record(&self, request: Pubkey) -> Result<(), RuntimeError> {
+        if !self.completed.lock().unwrap().insert(request) {
+            return Ok(());
+        }
+        if let Some(file) = &self.file {
+            let mut file = file.lock().unwrap();
+            writeln!(file, "{request}")?;
+            file.sync_data()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,18 @@
+//! Operator runtime for AgentMarket agents.
+//!
+//! [`AgentRuntime`] watches the escrow program for `ServiceRequestCreated`
+//! events addressed to one agent, loads each request, runs the operator's
+//! [`RequestHandler`], and submits the result on-chain. Submissions are
+//! retried with backoff and recorded in a [`Ledger`] so a request is never
+//! answered twice, even across restarts or overlapping event sources.
+
+mod error;
+mod handler;
+mod ledger;
+mod runtime;
+
+pub use agentmarket_sdk::accounts::ServiceRequest;
+pub use error::RuntimeError;
+pub use handler::{HandlerError, RequestHandler};
+pub use ledger::Ledger;
+pub use runtime::{AgentRuntime, RuntimeConfig, MAX_RESULT_LEN};
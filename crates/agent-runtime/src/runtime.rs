@@ -0,0 +1,438 @@
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use agentmarket_metrics::Metrics;
+use agentmarket_sdk::accounts::{AgentProfile, RequestStatus, ServiceRequest};
+use agentmarket_sdk::events::{parse_logs, ProgramEvent};
+use agentmarket_sdk::instructions::escrow;
+use agentmarket_sdk::{pda, Instruction, Pubkey, RpcClient, ESCROW_PROGRAM_ID};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use solana_keypair::Keypair;
+use solana_signature::Signature;
+use solana_signer::Signer;
+use tokio::sync::{mpsc, Semaphore};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, error, info, warn};
+
+use crate::error::RuntimeError;
+use crate::handler::RequestHandler;
+use crate::ledger::Ledger;
+
+/// Largest result the escrow program accepts in `submit_result`
+pub const MAX_RESULT_LEN: usize = 2000;
+
+// Byte offset of `ServiceRequest::agent_id` (discriminator + request_id)
+const AGENT_ID_OFFSET: usize = 8 + 32;
+
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// How often the cluster tip is polled for the lag metric
+const TIP_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Connection and retry settings for an [`AgentRuntime`]
+#[derive(Debug, Clone)]
+pub struct RuntimeConfig {
+    pub rpc_url: String,
+    pub ws_url: String,
+    /// Agent whose requests are served
+    pub agent_id: Pubkey,
+    /// Manager that registered the signer as one of its operators; `None`
+    /// when the signer is the agent's authority or NFT holder itself
+    pub manager: Option<Pubkey>,
+    /// Submission attempts per request before giving up
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubles on each further attempt
+    pub retry_delay: Duration,
+    /// How long to wait for a submitted result to show up on-chain
+    pub confirm_timeout: Duration,
+    /// Requests handled at the same time
+    pub concurrency: usize,
+    /// Serve Prometheus metrics on this address
+    pub metrics_addr: Option<SocketAddr>,
+}
+
+impl RuntimeConfig {
+    /// Defaults for serving `agent_id` against the given endpoints
+    pub fn new(rpc_url: impl Into<String>, ws_url: impl Into<String>, agent_id: Pubkey) -> Self {
+        Self {
+            rpc_url: rpc_url.into(),
+            ws_url: ws_url.into(),
+            agent_id,
+            manager: None,
+            max_attempts: 5,
+            retry_delay: Duration::from_secs(2),
+            confirm_timeout: Duration::from_secs(30),
+            concurrency: 4,
+            metrics_addr: None,
+        }
+    }
+
+    /// Defaults for the agent registered by `creator`
+    pub fn for_creator(
+        rpc_url: impl Into<String>,
+        ws_url: impl Into<String>,
+        creator: &Pubkey,
+    ) -> Self {
+        Self::new(rpc_url, ws_url, pda::agent_profile(creator).0)
+    }
+}
+
+/// Serves one agent's requests until the process exits
+pub struct AgentRuntime<H> {
+    inner: Arc<Inner<H>>,
+}
+
+struct Inner<H> {
+    config: RuntimeConfig,
+    rpc: RpcClient,
+    signer: Keypair,
+    handler: H,
+    ledger: Ledger,
+    in_flight: Mutex<HashSet<Pubkey>>,
+    metrics: Metrics,
+}
+
+impl<H: RequestHandler> AgentRuntime<H> {
+    /// `signer` signs and pays for result submissions
+    pub fn new(config: RuntimeConfig, signer: Keypair, handler: H, ledger: Ledger) -> Self {
+        let rpc = RpcClient::new(config.rpc_url.clone());
+        Self {
+            inner: Arc::new(Inner {
+                config,
+                rpc,
+                signer,
+                handler,
+                ledger,
+                in_flight: Mutex::new(HashSet::new()),
+                metrics: Metrics::new("agent-runtime"),
+            }),
+        }
+    }
+
+    /// Serve requests forever: stream new ones and rescan open ones on every reconnect
+    pub async fn run(self) -> Result<(), RuntimeError> {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let semaphore = Arc::new(Semaphore::new(self.inner.config.concurrency.max(1)));
+
+        let watcher = self.inner.clone();
+        tokio::spawn(async move { watcher.watch(tx).await });
+
+        if let Some(addr) = self.inner.config.metrics_addr {
+            tokio::spawn(async move {
+                if let Err(e) = agentmarket_metrics::serve(addr).await {
+                    error!("metrics server failed: {e}");
+                }
+            });
+            let metrics = self.inner.metrics;
+            tokio::spawn(metrics.track_tip(self.inner.rpc.clone(), TIP_POLL_INTERVAL));
+        }
+
+        info!(agent = %self.inner.config.agent_id, "agent runtime started");
+
+        while let Some(request) = rx.recv().await {
+            if self.inner.ledger.contains(&request)
+                || !self.inner.in_flight.lock().unwrap().insert(request)
+            {
+                continue;
+            }
+
+            let permit = semaphore
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("semaphore is never closed");
+            let inner = self.inner.clone();
+            tokio::spawn(async move {
+                match inner.serve(request).await {
+                    Ok(Some(signature)) => info!(%request, %signature, "result submitted"),
+                    Ok(None) => debug!(%request, "nothing to do"),
+                    Err(e) => error!(%request, "request failed: {e}"),
+                }
+                inner.in_flight.lock().unwrap().remove(&request);
+                drop(permit);
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl<H: RequestHandler> Inner<H> {
+    // Helper function to feed request ids from scans and the websocket, reconnecting with backoff
+    async fn watch(&self, tx: mpsc::UnboundedSender<Pubkey>) {
+        let mut backoff = Duration::from_secs(1);
+
+        loop {
+            // Rescan so nothing created while disconnected is missed
+            match self.open_requests().await {
+                Ok(open) => {
+                    for request in open {
+                        if tx.send(request).is_err() {
+                            return;
+                        }
+                    }
+                }
+                Err(e) => warn!("failed to scan open requests: {e}"),
+            }
+
+            match self.stream_once(&tx).await {
+                Ok(()) => {
+                    warn!("websocket closed by server");
+                    backoff = Duration::from_secs(1);
+                }
+                Err(e) => warn!("{e}"),
+            }
+            if tx.is_closed() {
+                return;
+            }
+
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    // Helper function to list this agent's requests still awaiting a result
+    async fn open_requests(&self) -> Result<Vec<Pubkey>, RuntimeError> {
+        let agent_id = self.config.agent_id.to_bytes();
+        let requests = self
+            .rpc
+            .fetch_all_matching::<ServiceRequest>(
+                &ESCROW_PROGRAM_ID,
+                &[(AGENT_ID_OFFSET, agent_id.as_slice())],
+            )
+            .await?;
+
+        Ok(requests
+            .into_iter()
+            .filter(|(_, request)| is_open(&request.status))
+            .map(|(address, _)| address)
+            .collect())
+    }
+
+    // Helper function to hold one logsSubscribe session until it drops
+    async fn stream_once(&self, tx: &mpsc::UnboundedSender<Pubkey>) -> Result<(), RuntimeError> {
+        let websocket =
+            |e: tokio_tungstenite::tungstenite::Error| RuntimeError::Websocket(e.to_string());
+        let (mut socket, _) = connect_async(&self.config.ws_url)
+            .await
+            .map_err(websocket)?;
+
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "logsSubscribe",
+            "params": [{"mentions": [ESCROW_PROGRAM_ID.to_string()]}, {"commitment": "confirmed"}],
+        });
+        socket
+            .send(Message::Text(request.to_string().into()))
+            .await
+            .map_err(websocket)?;
+
+        while let Some(message) = socket.next().await {
+            let text = match message.map_err(websocket)? {
+                Message::Text(text) => text,
+                Message::Ping(payload) => {
+                    socket
+                        .send(Message::Pong(payload))
+                        .await
+                        .map_err(websocket)?;
+                    continue;
+                }
+                Message::Close(_) => return Ok(()),
+                _ => continue,
+            };
+
+            let Ok(value) = serde_json::from_str::<Value>(&text) else {
+                continue;
+            };
+            if let Some(error) = value.get("error") {
+                return Err(RuntimeError::Websocket(format!(
+                    "subscription rejected: {error}"
+                )));
+            }
+
+            for request in self.created_requests(&value) {
+                if tx.send(request).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Helper function to pull this agent's new request ids out of a logsNotification
+    fn created_requests(&self, value: &Value) -> Vec<Pubkey> {
+        let Some(notification) = value.pointer("/params/result/value") else {
+            return Vec::new();
+        };
+        if !notification.get("err").is_none_or(Value::is_null) {
+            return Vec::new();
+        }
+        if let Some(slot) = value
+            .pointer("/params/result/context/slot")
+            .and_then(Value::as_u64)
+        {
+            self.metrics.slot_processed(slot);
+        }
+
+        let logs: Vec<&str> = notification
+            .get("logs")
+            .and_then(Value::as_array)
+            .map(|logs| logs.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+
+        parse_logs(&logs)
+            .into_iter()
+            .filter_map(|(_, event)| match event {
+                ProgramEvent::ServiceRequestCreated(e) if e.agent_id == self.config.agent_id => {
+                    self.metrics.event_processed("ServiceRequestCreated");
+                    Some(e.request_id)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Helper function to answer one request; returns None if it needed no submission
+    async fn serve(&self, request: Pubkey) -> Result<Option<Signature>, RuntimeError> {
+        let Some(account) = self.rpc.fetch_optional::<ServiceRequest>(&request).await? else {
+            return Ok(None);
+        };
+        if account.agent_id != self.config.agent_id {
+            return Ok(None);
+        }
+        if !is_open(&account.status) {
+            self.ledger.record(request)?;
+            return Ok(None);
+        }
+
+        let result = self
+            .handler
+            .handle(account)
+            .await
+            .map_err(|e| RuntimeError::Handler {
+                request,
+                message: e.to_string(),
+            })?;
+        if result.len() > MAX_RESULT_LEN {
+            return Err(RuntimeError::ResultTooLong {
+                request,
+                len: result.len(),
+                max: MAX_RESULT_LEN,
+            });
+        }
+
+        let signature = self.submit(request, result).await?;
+        self.ledger.record(request)?;
+        Ok(signature)
+    }
+
+    // Helper function to submit a result with retries, treating an already-landed result as success
+    async fn submit(
+        &self,
+        request: Pubkey,
+        result: String,
+    ) -> Result<Option<Signature>, RuntimeError> {
+        let mut delay = self.config.retry_delay;
+        let mut last_error = String::new();
+
+        for attempt in 1..=self.config.max_attempts {
+            if attempt > 1 {
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_BACKOFF);
+            }
+
+            // A previous attempt may have landed after its confirmation timed out
+            match self.is_answered(&request).await {
+                Ok(true) => return Ok(None),
+                Ok(false) => {}
+                Err(e) => {
+                    last_error = e.to_string();
+                    continue;
+                }
+            }
+
+            let instruction = match self.submit_instruction(request, result.clone()).await {
+                Ok(instruction) => instruction,
+                Err(e) => {
+                    last_error = e.to_string();
+                    continue;
+                }
+            };
+            let signature = match self
+                .rpc
+                .send_instructions(&[instruction], &self.signer.pubkey(), &[&self.signer])
+                .await
+            {
+                Ok(signature) => signature,
+                Err(e) => {
+                    warn!(%request, attempt, "submission failed: {e}");
+                    self.metrics.transaction_failed();
+                    last_error = e.to_string();
+                    continue;
+                }
+            };
+
+            if self.wait_until_answered(&request).await {
+                return Ok(Some(signature));
+            }
+            warn!(%request, attempt, %signature, "submission not confirmed in time");
+            self.metrics.transaction_failed();
+            last_error = format!("transaction {signature} was not confirmed");
+        }
+
+        Err(RuntimeError::RetriesExhausted {
+            request,
+            attempts: self.config.max_attempts,
+            last_error,
+        })
+    }
+
+    // Helper function to build the submission, signed as the agent's manager
+    // or as an operator of `config.manager`
+    async fn submit_instruction(
+        &self,
+        request: Pubkey,
+        result: String,
+    ) -> Result<Instruction, RuntimeError> {
+        let agent_id = self.config.agent_id;
+        let nft_mint = self.rpc.fetch::<AgentProfile>(&agent_id).await?.nft_mint;
+        let signer = self.signer.pubkey();
+        Ok(match self.config.manager {
+            Some(manager) => escrow::submit_result_as_operator(
+                request, agent_id, nft_mint, manager, signer, result,
+            ),
+            None => escrow::submit_result(request, agent_id, nft_mint, signer, result),
+        })
+    }
+
+    // Helper function to check whether the request already carries a result
+    async fn is_answered(&self, request: &Pubkey) -> Result<bool, RuntimeError> {
+        let account = self.rpc.fetch::<ServiceRequest>(request).await?;
+        Ok(!is_open(&account.status))
+    }
+
+    // Helper function to poll the request until it leaves the open states or the timeout passes
+    async fn wait_until_answered(&self, request: &Pubkey) -> bool {
+        let poll = Duration::from_millis(500);
+        let deadline = tokio::time::Instant::now() + self.config.confirm_timeout;
+
+        while tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(poll).await;
+            if let Ok(true) = self.is_answered(request).await {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+// Helper function for the statuses in which the escrow accepts `submit_result`
+fn is_open(status: &RequestStatus) -> bool {
+    matches!(status, RequestStatus::Pending | RequestStatus::InProgress)
+}
@@ -0,0 +1,125 @@
+//! Alerting: confirm violations across passes, then log them and post them to a webhook.
+
+use std::collections::HashMap;
+
+use agentmarket_sdk::Pubkey;
+use anyhow::{Context, Result};
+use serde_json::json;
+use tracing::{error, info};
+
+use crate::invariants::{Invariant, Violation};
+
+pub struct Alerter {
+    http: reqwest::Client,
+    webhook: Option<String>,
+    /// Consecutive passes a violation must show up in before it alerts
+    confirmations: u32,
+    /// Consecutive passes each current violation has shown up in
+    streaks: HashMap<(Invariant, Pubkey), u32>,
+}
+
+/// What changed since the previous pass
+#[derive(Default)]
+pub struct Changes {
+    /// Violations that just reached the confirmation count
+    pub raised: Vec<Violation>,
+    /// Confirmed violations that no longer show up
+    pub resolved: Vec<(Invariant, Pubkey)>,
+}
+
+impl Alerter {
+    pub fn new(webhook: Option<String>, confirmations: u32) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            webhook,
+            confirmations: confirmations.max(1),
+            streaks: HashMap::new(),
+        }
+    }
+
+    /// Fold in one pass's violations
+    ///
+    /// Accounts are read one type at a time, so a transaction landing
+    /// mid-pass can look like a violation; requiring it on consecutive passes
+    /// keeps those from paging anyone.
+    pub fn observe(&mut self, violations: &[Violation]) -> Changes {
+        let mut changes = Changes::default();
+        let mut streaks = HashMap::new();
+        for violation in violations {
+            let key = (violation.invariant, violation.account);
+            if streaks.contains_key(&key) {
+                continue;
+            }
+            let streak = self.streaks.get(&key).copied().unwrap_or_default() + 1;
+            if streak == self.confirmations {
+                changes.raised.push(violation.clone());
+            }
+            streaks.insert(key, streak);
+        }
+        for (key, streak) in &self.streaks {
+            if *streak >= self.confirmations && !streaks.contains_key(key) {
+                changes.resolved.push(*key);
+            }
+        }
+        self.streaks = streaks;
+        changes
+    }
+
+    /// Log the changes and post them to the webhook, if one is configured
+    pub async fn notify(&self, changes: &Changes) -> Result<()> {
+        for violation in &changes.raised {
+            error!(
+                invariant = violation.invariant.name(),
+                account = %violation.account,
+                "invariant violated: {}",
+                violation.detail
+            );
+        }
+        for (invariant, account) in &changes.resolved {
+            info!(invariant = invariant.name(), %account, "violation resolved");
+        }
+
+        let Some(webhook) = &self.webhook else {
+            return Ok(());
+        };
+        if changes.raised.is_empty() && changes.resolved.is_empty() {
+            return Ok(());
+        }
+
+        // `text` lets chat webhooks render the alert without a custom template
+        let mut text = format!(
+            "AgentMarket audit: {} new violation(s), {} resolved",
+            changes.raised.len(),
+            changes.resolved.len()
+        );
+        for violation in &changes.raised {
+            text.push_str(&format!(
+                "\n- {} {}: {}",
+                violation.invariant.name(),
+                violation.account,
+                violation.detail
+            ));
+        }
+        let body = json!({
+            "text": text,
+            "violations": changes.raised,
+            "resolved": changes
+                .resolved
+                .iter()
+                .map(|(invariant, account)| json!({
+                    "invariant": invariant.name(),
+                    "account": account.to_string(),
+                }))
+                .collect::<Vec<_>>(),
+        });
+
+        self.http
+            .post(webhook)
+            .json(&body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("posting to the alert webhook")?;
+        Ok(())
+    }
+}
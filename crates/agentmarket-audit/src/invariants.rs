@@ -0,0 +1,392 @@
+//! The invariants: each reads a snapshot and returns the accounts that break it.
+
+use std::collections::HashMap;
+
+use agent_workflow::{Workflow, WorkflowStatus};
+use agentmarket_sdk::accounts::RequestStatus;
+use agentmarket_sdk::{pda, Pubkey};
+use anchor_lang::prelude::Rent;
+use anchor_lang::Space;
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::snapshot::{is_open, payout_vault_address, Snapshot};
+
+/// Share totals the programs enforce when configs are written
+const PERCENT_TOTAL: u64 = 100;
+const BPS_TOTAL: u64 = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Invariant {
+    /// Royalty creator, platform, and treasury shares sum to 100
+    RoyaltyShares,
+    /// Leaderboard prize weights, in the config and every epoch, sum to 10_000
+    PrizeWeights,
+    /// Every open service request's escrow vault holds at least its amount
+    EscrowCoverage,
+    /// Reputation counts and averages match the ratings they aggregate
+    ReputationAggregates,
+    /// Royalty totals match the distribution records, and each record's parts sum to its total
+    RoyaltyLedger,
+    /// Agent earnings match approved escrow payouts plus royalty distributions to the creator
+    RegistryEarnings,
+    /// Workflow allocations add up and active workflows hold what they still owe
+    WorkflowCoverage,
+    /// Payout batches never over-claim and their vaults hold the unclaimed remainder
+    PayoutCoverage,
+}
+
+impl Invariant {
+    pub const ALL: [Invariant; 8] = [
+        Invariant::RoyaltyShares,
+        Invariant::PrizeWeights,
+        Invariant::EscrowCoverage,
+        Invariant::ReputationAggregates,
+        Invariant::RoyaltyLedger,
+        Invariant::RegistryEarnings,
+        Invariant::WorkflowCoverage,
+        Invariant::PayoutCoverage,
+    ];
+
+    /// Label for logs, metrics, and alerts
+    pub fn name(self) -> &'static str {
+        match self {
+            Invariant::RoyaltyShares => "royalty-shares",
+            Invariant::PrizeWeights => "prize-weights",
+            Invariant::EscrowCoverage => "escrow-coverage",
+            Invariant::ReputationAggregates => "reputation-aggregates",
+            Invariant::RoyaltyLedger => "royalty-ledger",
+            Invariant::RegistryEarnings => "registry-earnings",
+            Invariant::WorkflowCoverage => "workflow-coverage",
+            Invariant::PayoutCoverage => "payout-coverage",
+        }
+    }
+
+    fn check(self, snapshot: &Snapshot, options: &Options) -> Vec<Violation> {
+        let mut found = Findings {
+            invariant: self,
+            violations: Vec::new(),
+        };
+        match self {
+            Invariant::RoyaltyShares => royalty_shares(snapshot, &mut found),
+            Invariant::PrizeWeights => prize_weights(snapshot, &mut found),
+            Invariant::EscrowCoverage => escrow_coverage(snapshot, &mut found),
+            Invariant::ReputationAggregates => reputation_aggregates(snapshot, options, &mut found),
+            Invariant::RoyaltyLedger => royalty_ledger(snapshot, &mut found),
+            Invariant::RegistryEarnings => registry_earnings(snapshot, &mut found),
+            Invariant::WorkflowCoverage => workflow_coverage(snapshot, &mut found),
+            Invariant::PayoutCoverage => payout_coverage(snapshot, &mut found),
+        }
+        found.violations
+    }
+}
+
+/// One account breaking one invariant
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+pub struct Violation {
+    pub invariant: Invariant,
+    #[serde(serialize_with = "as_string")]
+    pub account: Pubkey,
+    pub detail: String,
+}
+
+pub struct Options {
+    pub skip: Vec<Invariant>,
+    /// Stars a reputation average may drift from the exact average of its ratings
+    ///
+    /// The program keeps a running integer average, so it loses a fraction of
+    /// a star to rounding on every update.
+    pub rating_tolerance: u32,
+}
+
+/// Run every invariant not skipped; violations come back grouped by invariant
+pub fn check_all(snapshot: &Snapshot, options: &Options) -> Vec<(Invariant, Vec<Violation>)> {
+    Invariant::ALL
+        .into_iter()
+        .filter(|invariant| !options.skip.contains(invariant))
+        .map(|invariant| (invariant, invariant.check(snapshot, options)))
+        .collect()
+}
+
+struct Findings {
+    invariant: Invariant,
+    violations: Vec<Violation>,
+}
+
+impl Findings {
+    fn flag(&mut self, account: Pubkey, detail: String) {
+        self.violations.push(Violation {
+            invariant: self.invariant,
+            account,
+            detail,
+        });
+    }
+}
+
+fn royalty_shares(snapshot: &Snapshot, found: &mut Findings) {
+    let Some(config) = &snapshot.royalty_config else {
+        return;
+    };
+    let total =
+        config.creator_share as u64 + config.platform_share as u64 + config.treasury_share as u64;
+    if total != PERCENT_TOTAL {
+        found.flag(
+            pda::royalty_config().0,
+            format!(
+                "shares {}/{}/{} sum to {total}",
+                config.creator_share, config.platform_share, config.treasury_share
+            ),
+        );
+    }
+}
+
+fn prize_weights(snapshot: &Snapshot, found: &mut Findings) {
+    let config = snapshot.leaderboard_config.as_ref().map(|config| {
+        (
+            Pubkey::find_program_address(&[b"leaderboard_config"], &leaderboard::ID).0,
+            &config.prize_weights_bps,
+        )
+    });
+    let epochs = snapshot
+        .epochs
+        .iter()
+        .map(|(address, epoch)| (*address, &epoch.prize_weights_bps));
+
+    for (address, weights) in config.into_iter().chain(epochs) {
+        let total: u64 = weights.iter().map(|weight| *weight as u64).sum();
+        if total != BPS_TOTAL {
+            found.flag(address, format!("prize weights {weights:?} sum to {total}"));
+        }
+    }
+}
+
+fn escrow_coverage(snapshot: &Snapshot, found: &mut Findings) {
+    for (address, request) in &snapshot.requests {
+        if !is_open(&request.status) {
+            continue;
+        }
+        let escrow = pda::escrow_account(address).0;
+        let held = snapshot.balance(&escrow);
+        if held < request.amount {
+            found.flag(
+                *address,
+                format!(
+                    "escrow {escrow} holds {held} lamports for an open request of {}",
+                    request.amount
+                ),
+            );
+        }
+    }
+}
+
+fn reputation_aggregates(snapshot: &Snapshot, options: &Options, found: &mut Findings) {
+    // Ratings point at the reputation account they were folded into
+    let mut stars: HashMap<Pubkey, Vec<u64>> = HashMap::new();
+    for (_, rating) in &snapshot.ratings {
+        // Only a moderator's "invalid" verdict takes a rating back out of the aggregate
+        if !rating.is_moderated || rating.is_valid {
+            stars
+                .entry(rating.agent_id)
+                .or_default()
+                .push(rating.stars as u64);
+        }
+    }
+
+    for (address, reputation) in &snapshot.reputations {
+        let counted = stars.get(address).map(Vec::as_slice).unwrap_or_default();
+        if reputation.total_ratings != counted.len() as u64 {
+            found.flag(
+                *address,
+                format!(
+                    "total_ratings is {} but {} ratings count toward it",
+                    reputation.total_ratings,
+                    counted.len()
+                ),
+            );
+            continue;
+        }
+
+        if reputation.total_ratings > 0 {
+            let scores = [
+                reputation.quality_score,
+                reputation.speed_score,
+                reputation.value_score,
+            ];
+            if scores.iter().any(|score| !(1..=5).contains(score)) {
+                found.flag(
+                    *address,
+                    format!("quality/speed/value scores {scores:?} leave the 1-5 scale"),
+                );
+            }
+        }
+
+        let exact = counted
+            .iter()
+            .sum::<u64>()
+            .checked_div(counted.len() as u64)
+            .unwrap_or(0);
+        if (reputation.average_rating as u64).abs_diff(exact) > options.rating_tolerance as u64 {
+            found.flag(
+                *address,
+                format!(
+                    "average_rating is {} but its ratings average {exact}",
+                    reputation.average_rating
+                ),
+            );
+        }
+    }
+}
+
+fn royalty_ledger(snapshot: &Snapshot, found: &mut Findings) {
+    for (address, record) in &snapshot.distributions {
+        let parts = record.creator_amount as u128
+            + record.platform_amount as u128
+            + record.treasury_amount as u128;
+        if parts != record.total_amount as u128 {
+            found.flag(
+                *address,
+                format!(
+                    "parts {}/{}/{} sum to {parts}, not {}",
+                    record.creator_amount,
+                    record.platform_amount,
+                    record.treasury_amount,
+                    record.total_amount
+                ),
+            );
+        }
+    }
+
+    let Some(config) = &snapshot.royalty_config else {
+        return;
+    };
+    let config_address = pda::royalty_config().0;
+    let recorded: u128 = snapshot
+        .distributions
+        .iter()
+        .map(|(_, record)| record.total_amount as u128)
+        .sum();
+    if config.total_distributed as u128 != recorded {
+        found.flag(
+            config_address,
+            format!(
+                "total_distributed is {} but the records total {recorded}",
+                config.total_distributed
+            ),
+        );
+    }
+    if config.total_transactions != snapshot.distributions.len() as u64 {
+        found.flag(
+            config_address,
+            format!(
+                "total_transactions is {} but {} records exist",
+                config.total_transactions,
+                snapshot.distributions.len()
+            ),
+        );
+    }
+}
+
+fn registry_earnings(snapshot: &Snapshot, found: &mut Findings) {
+    let mut paid: HashMap<Pubkey, u128> = HashMap::new();
+    for (_, request) in &snapshot.requests {
+        if request.status == RequestStatus::Approved {
+            *paid.entry(request.agent_id).or_default() +=
+                agentmarket_payouts::escrow_release(request.amount).creator as u128;
+        }
+    }
+    let mut distributed: HashMap<Pubkey, u128> = HashMap::new();
+    for (_, record) in &snapshot.distributions {
+        *distributed.entry(record.creator).or_default() += record.creator_amount as u128;
+    }
+
+    // Royalties are keyed by the wallet paid, the agent's payout wallet; agents
+    // sharing one payout wallet are not told apart
+    for (address, profile) in &snapshot.profiles {
+        let escrow = paid.get(&profile.agent_id).copied().unwrap_or_default();
+        let royalties = distributed
+            .get(&profile.payout_wallet)
+            .copied()
+            .unwrap_or_default();
+        if profile.total_earnings as u128 != escrow + royalties {
+            found.flag(
+                *address,
+                format!(
+                    "total_earnings is {} but the ledgers show {escrow} from escrow and {royalties} from royalties",
+                    profile.total_earnings
+                ),
+            );
+        }
+    }
+}
+
+fn workflow_coverage(snapshot: &Snapshot, found: &mut Findings) {
+    let rent_reserve = Rent::default().minimum_balance(8 + Workflow::INIT_SPACE);
+    for (address, workflow) in &snapshot.workflows {
+        let allocated: u128 = workflow.steps.iter().map(|step| step.amount as u128).sum();
+        if allocated != workflow.total_amount as u128 {
+            found.flag(
+                *address,
+                format!(
+                    "steps allocate {allocated} of a {} total",
+                    workflow.total_amount
+                ),
+            );
+        }
+
+        let settled = workflow.released_amount as u128 + workflow.refunded_amount as u128;
+        if settled > workflow.total_amount as u128 {
+            found.flag(
+                *address,
+                format!(
+                    "released {} and refunded {} exceed the {} total",
+                    workflow.released_amount, workflow.refunded_amount, workflow.total_amount
+                ),
+            );
+            continue;
+        }
+
+        if workflow.status == WorkflowStatus::Active {
+            let owed = workflow.total_amount - workflow.released_amount - workflow.refunded_amount;
+            let held = snapshot.balance(address).saturating_sub(rent_reserve);
+            if held < owed {
+                found.flag(
+                    *address,
+                    format!("holds {held} lamports above rent but still owes {owed}"),
+                );
+            }
+        }
+    }
+}
+
+fn payout_coverage(snapshot: &Snapshot, found: &mut Findings) {
+    for (address, batch) in &snapshot.batches {
+        if batch.claimed_amount > batch.total_amount || batch.claimed_count > batch.num_recipients {
+            found.flag(
+                *address,
+                format!(
+                    "claimed {} of {} lamports across {} of {} recipients",
+                    batch.claimed_amount,
+                    batch.total_amount,
+                    batch.claimed_count,
+                    batch.num_recipients
+                ),
+            );
+            continue;
+        }
+
+        let vault = payout_vault_address(address);
+        let unclaimed = batch.total_amount - batch.claimed_amount;
+        let held = snapshot.balance(&vault);
+        if held < unclaimed {
+            found.flag(
+                *address,
+                format!("vault {vault} holds {held} lamports but {unclaimed} are unclaimed"),
+            );
+        }
+    }
+}
+
+fn as_string<S: serde::Serializer>(address: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(address)
+}
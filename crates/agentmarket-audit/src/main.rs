@@ -0,0 +1,152 @@
+//! AgentMarket auditor: check cross-program invariants over all on-chain state.
+//!
+//! Every `--interval` seconds the auditor loads every registry, escrow,
+//! reputation, royalty, leaderboard, workflow, and bulk payout account and
+//! checks the properties the programs are meant to preserve together:
+//! shares and prize weights sum to 100 and 10_000, escrow and workflow
+//! accounts hold what they still owe, reputation aggregates match the
+//! ratings under them, the royalty ledger adds up, and agent earnings match
+//! the escrow and royalty payouts recorded for them. `--skip` leaves out
+//! individual invariants.
+//!
+//! A violation alerts once it shows up on `--confirmations` consecutive
+//! passes: it is logged at error level and, with `--webhook-url`, posted as
+//! JSON. Resolved violations are reported the same way. With
+//! `--metrics-addr` the per-invariant violation counts are exported for
+//! Prometheus. `--once` runs a single pass, prints every violation, and
+//! exits non-zero if there are any, for CI and ad-hoc audits.
+
+mod alert;
+mod invariants;
+mod snapshot;
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use agentmarket_metrics::Metrics;
+use agentmarket_sdk::RpcClient;
+use anyhow::{bail, Result};
+use clap::Parser;
+use tracing::{error, info};
+use tracing_subscriber::EnvFilter;
+
+use crate::alert::Alerter;
+use crate::invariants::{Invariant, Options, Violation};
+use crate::snapshot::Snapshot;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "agentmarket-audit",
+    about = "Check AgentMarket's cross-program invariants and alert on violations"
+)]
+struct Args {
+    /// JSON-RPC endpoint of the cluster to audit
+    #[arg(
+        long,
+        env = "AGENTMARKET_RPC_URL",
+        default_value = "http://127.0.0.1:8899"
+    )]
+    url: String,
+
+    /// Seconds between passes
+    #[arg(long, default_value_t = 300)]
+    interval: u64,
+
+    /// Run one pass, print every violation, and exit non-zero if any were found
+    #[arg(long)]
+    once: bool,
+
+    /// Invariants to leave out, comma separated
+    #[arg(long, value_enum, value_delimiter = ',')]
+    skip: Vec<Invariant>,
+
+    /// Stars a reputation average may drift from the exact average of its ratings
+    #[arg(long, default_value_t = 1)]
+    rating_tolerance: u32,
+
+    /// Consecutive passes a violation must persist before it alerts
+    #[arg(long, default_value_t = 2)]
+    confirmations: u32,
+
+    /// URL that new and resolved violations are posted to as JSON
+    #[arg(long, env = "AGENTMARKET_ALERT_WEBHOOK")]
+    webhook_url: Option<String>,
+
+    /// Serve Prometheus metrics on this address
+    #[arg(long, env = "AGENTMARKET_METRICS_ADDR")]
+    metrics_addr: Option<SocketAddr>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
+        .init();
+
+    let args = Args::parse();
+    let rpc = RpcClient::new(&args.url);
+    let options = Options {
+        skip: args.skip.clone(),
+        rating_tolerance: args.rating_tolerance,
+    };
+
+    if args.once {
+        let violations = audit(&rpc, &options, None).await?;
+        for violation in &violations {
+            println!(
+                "{:<22} {:<44} {}",
+                violation.invariant.name(),
+                violation.account,
+                violation.detail
+            );
+        }
+        if !violations.is_empty() {
+            bail!("{} invariant violations", violations.len());
+        }
+        println!("all invariants hold");
+        return Ok(());
+    }
+
+    let metrics = Metrics::new("audit");
+    if let Some(addr) = args.metrics_addr {
+        tokio::spawn(async move {
+            if let Err(e) = agentmarket_metrics::serve(addr).await {
+                error!("metrics server failed: {e}");
+            }
+        });
+    }
+
+    info!(url = %args.url, interval = args.interval, "auditing");
+    let mut alerter = Alerter::new(args.webhook_url.clone(), args.confirmations);
+    let mut ticker = tokio::time::interval(Duration::from_secs(args.interval.max(1)));
+    loop {
+        ticker.tick().await;
+        match audit(&rpc, &options, Some(metrics)).await {
+            Ok(violations) => {
+                let changes = alerter.observe(&violations);
+                if let Err(e) = alerter.notify(&changes).await {
+                    error!("alert failed: {e:#}");
+                }
+            }
+            Err(e) => error!("pass failed: {e:#}"),
+        }
+    }
+}
+
+// Helper function to run one pass over a fresh snapshot, exporting the counts when metrics are on
+async fn audit(
+    rpc: &RpcClient,
+    options: &Options,
+    metrics: Option<Metrics>,
+) -> Result<Vec<Violation>> {
+    let snapshot = Snapshot::load(rpc).await?;
+    let mut all = Vec::new();
+    for (invariant, violations) in invariants::check_all(&snapshot, options) {
+        if let Some(metrics) = metrics {
+            metrics.set_invariant_violations(invariant.name(), violations.len());
+        }
+        all.extend(violations);
+    }
+    info!(violations = all.len(), "pass complete");
+    Ok(all)
+}
@@ -0,0 +1,102 @@
+//! Snapshot: every account the invariants read, plus the balances of the accounts holding funds.
+
+use std::collections::HashMap;
+
+use agent_workflow::Workflow;
+use agentmarket_sdk::accounts::{
+    AgentProfile, AgentReputationProfile, DistributionRecord, Rating, RequestStatus, RoyaltyConfig,
+    ServiceRequest,
+};
+use agentmarket_sdk::{
+    pda, Pubkey, RpcClient, ESCROW_PROGRAM_ID, REGISTRY_PROGRAM_ID, REPUTATION_PROGRAM_ID,
+    ROYALTY_PROGRAM_ID,
+};
+use anyhow::Result;
+use bulk_payout::PayoutBatch;
+use leaderboard::{Epoch, LeaderboardConfig};
+
+/// `getMultipleAccounts` accepts at most this many addresses
+const MAX_BATCH: usize = 100;
+
+pub struct Snapshot {
+    pub profiles: Vec<(Pubkey, AgentProfile)>,
+    pub requests: Vec<(Pubkey, ServiceRequest)>,
+    pub reputations: Vec<(Pubkey, AgentReputationProfile)>,
+    pub ratings: Vec<(Pubkey, Rating)>,
+    pub royalty_config: Option<RoyaltyConfig>,
+    pub distributions: Vec<(Pubkey, DistributionRecord)>,
+    pub leaderboard_config: Option<LeaderboardConfig>,
+    pub epochs: Vec<(Pubkey, Epoch)>,
+    pub workflows: Vec<(Pubkey, Workflow)>,
+    pub batches: Vec<(Pubkey, PayoutBatch)>,
+    /// Lamports held by escrow vaults of open requests, workflows, and payout vaults
+    pub balances: HashMap<Pubkey, u64>,
+}
+
+impl Snapshot {
+    /// Load every account the invariants need
+    ///
+    /// Accounts are read one type at a time, so a transaction landing mid-load
+    /// can make a snapshot inconsistent; callers confirm violations over
+    /// more than one pass.
+    pub async fn load(rpc: &RpcClient) -> Result<Self> {
+        let mut snapshot = Self {
+            profiles: rpc.fetch_all(&REGISTRY_PROGRAM_ID).await?,
+            requests: rpc.fetch_all(&ESCROW_PROGRAM_ID).await?,
+            reputations: rpc.fetch_all(&REPUTATION_PROGRAM_ID).await?,
+            ratings: rpc.fetch_all(&REPUTATION_PROGRAM_ID).await?,
+            royalty_config: rpc.fetch_optional(&pda::royalty_config().0).await?,
+            distributions: rpc.fetch_all(&ROYALTY_PROGRAM_ID).await?,
+            leaderboard_config: rpc.fetch_optional(&leaderboard_config_address()).await?,
+            epochs: rpc.fetch_all(&leaderboard::ID).await?,
+            workflows: rpc.fetch_all(&agent_workflow::ID).await?,
+            batches: rpc.fetch_all(&bulk_payout::ID).await?,
+            balances: HashMap::new(),
+        };
+
+        let funded: Vec<Pubkey> = snapshot
+            .requests
+            .iter()
+            .filter(|(_, request)| is_open(&request.status))
+            .map(|(address, _)| pda::escrow_account(address).0)
+            .chain(snapshot.workflows.iter().map(|(address, _)| *address))
+            .chain(
+                snapshot
+                    .batches
+                    .iter()
+                    .map(|(address, _)| payout_vault_address(address)),
+            )
+            .collect();
+        for chunk in funded.chunks(MAX_BATCH) {
+            let balances = rpc.get_multiple_balances(chunk).await?;
+            snapshot
+                .balances
+                .extend(chunk.iter().copied().zip(balances));
+        }
+
+        Ok(snapshot)
+    }
+
+    pub fn balance(&self, address: &Pubkey) -> u64 {
+        self.balances.get(address).copied().unwrap_or_default()
+    }
+}
+
+/// Whether a request's payment is still held in escrow
+pub fn is_open(status: &RequestStatus) -> bool {
+    matches!(
+        status,
+        RequestStatus::Pending
+            | RequestStatus::InProgress
+            | RequestStatus::Completed
+            | RequestStatus::Disputed
+    )
+}
+
+pub fn payout_vault_address(batch: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"payout_vault", batch.as_ref()], &bulk_payout::ID).0
+}
+
+fn leaderboard_config_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"leaderboard_config"], &leaderboard::ID).0
+}
@@ -0,0 +1,300 @@
+//! `inspect`: resolve an account by address or seeds and print its decoded fields.
+
+use std::str::FromStr;
+
+use agentmarket_sdk::accounts::{
+    decode, is_account, AgentEndpoint, AgentProfile, AgentReputationProfile, DistributionRecord,
+    GuardianConfig, PricingModel, Rating, ReputationConfig, RequestStatus, RoyaltyConfig,
+    ServiceRequest,
+};
+use agentmarket_sdk::{
+    Pubkey, RpcClient, ESCROW_PROGRAM_ID, GUARDIAN_PROGRAM_ID, REGISTRY_PROGRAM_ID,
+    REPUTATION_PROGRAM_ID, ROYALTY_PROGRAM_ID,
+};
+use anchor_lang::{AccountDeserialize, Discriminator};
+use anyhow::{bail, Result};
+
+/// Program named on the command line, either by alias or by id
+#[derive(Clone, Copy, Debug)]
+pub struct ProgramArg(pub Pubkey);
+
+impl FromStr for ProgramArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let id = match s {
+            "registry" => REGISTRY_PROGRAM_ID,
+            "escrow" => ESCROW_PROGRAM_ID,
+            "reputation" => REPUTATION_PROGRAM_ID,
+            "royalty" => ROYALTY_PROGRAM_ID,
+            "guardian" => GUARDIAN_PROGRAM_ID,
+            other => {
+                Pubkey::from_str(other).map_err(|e| format!("unknown program {other}: {e}"))?
+            }
+        };
+        Ok(Self(id))
+    }
+}
+
+/// One PDA seed, typed by prefix
+#[derive(Clone, Debug)]
+pub struct Seed(Vec<u8>);
+
+impl FromStr for Seed {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, value) = s
+            .split_once(':')
+            .ok_or_else(|| format!("seed {s} must be <kind>:<value>"))?;
+
+        let bytes = match kind {
+            "str" => value.as_bytes().to_vec(),
+            "pubkey" => Pubkey::from_str(value)
+                .map_err(|e| e.to_string())?
+                .to_bytes()
+                .to_vec(),
+            "u8" => vec![value.parse::<u8>().map_err(|e| e.to_string())?],
+            "u64" => value
+                .parse::<u64>()
+                .map_err(|e| e.to_string())?
+                .to_le_bytes()
+                .to_vec(),
+            "hex" => hex::decode(value).map_err(|e| e.to_string())?,
+            other => return Err(format!("unknown seed kind {other}")),
+        };
+
+        if bytes.len() > 32 {
+            return Err(format!("seed {s} is longer than 32 bytes"));
+        }
+        Ok(Self(bytes))
+    }
+}
+
+pub async fn run(
+    rpc: &RpcClient,
+    address: Option<Pubkey>,
+    program: Option<ProgramArg>,
+    seeds: &[Seed],
+) -> Result<()> {
+    let address = match (address, program) {
+        (Some(address), _) => address,
+        (None, Some(ProgramArg(program_id))) => {
+            let seeds: Vec<&[u8]> = seeds.iter().map(|s| s.0.as_slice()).collect();
+            let (pda, bump) = Pubkey::find_program_address(&seeds, &program_id);
+            println!("program: {program_id}");
+            println!("bump: {bump}");
+            pda
+        }
+        (None, None) => bail!("pass an address or --program with --seed"),
+    };
+    println!("address: {address}");
+
+    let Some(data) = rpc.get_account_data(&address).await? else {
+        println!("account does not exist");
+        return Ok(());
+    };
+    println!("lamports: {}", rpc.get_balance(&address).await?);
+    println!("data length: {}", data.len());
+
+    match describe(&data)? {
+        Some((name, fields)) => {
+            println!("type: {name}");
+            for (field, value) in fields {
+                println!("  {field}: {value}");
+            }
+        }
+        None => println!("data: {}", hex::encode(&data)),
+    }
+
+    Ok(())
+}
+
+type Fields = Vec<(&'static str, String)>;
+
+// Helper function to decode `T` only when its discriminator matches
+fn try_decode<T: AccountDeserialize + Discriminator>(data: &[u8]) -> Result<Option<T>> {
+    if !is_account::<T>(data) {
+        return Ok(None);
+    }
+    Ok(Some(decode(data)?))
+}
+
+// Helper macro to list named fields of a decoded account
+macro_rules! fields {
+    ($account:expr, $($field:ident),+ $(,)?) => {
+        vec![$((stringify!($field), format!("{:?}", $account.$field))),+]
+    };
+}
+
+// Helper function to recognize any known AgentMarket account type
+fn describe(data: &[u8]) -> Result<Option<(&'static str, Fields)>> {
+    if let Some(a) = try_decode::<AgentProfile>(data)? {
+        let mut fields = fields!(
+            a,
+            agent_id,
+            creator,
+            name,
+            description,
+            capabilities,
+            ipfs_hash,
+            reputation_score,
+            total_services,
+            total_earnings,
+            created_at,
+            is_active,
+            nft_mint,
+        );
+        fields.push(("pricing_model", pricing(&a.pricing_model)));
+        fields.push(("endpoints", endpoints(&a.endpoints)));
+        return Ok(Some(("AgentProfile", fields)));
+    }
+    if let Some(a) = try_decode::<ServiceRequest>(data)? {
+        let mut fields = fields!(
+            a,
+            request_id,
+            agent_id,
+            user,
+            amount,
+            request_data,
+            result_data,
+            created_at,
+            completed_at,
+            escrow_account,
+        );
+        fields.push(("status", status(&a.status).to_owned()));
+        return Ok(Some(("ServiceRequest", fields)));
+    }
+    if let Some(a) = try_decode::<Rating>(data)? {
+        let fields = fields!(
+            a,
+            rating_id,
+            agent_id,
+            user,
+            request_id,
+            stars,
+            quality,
+            speed,
+            value,
+            review_text,
+            created_at,
+            is_reported,
+            report_reason,
+            is_moderated,
+            is_valid,
+            admin_note,
+        );
+        return Ok(Some(("Rating", fields)));
+    }
+    if let Some(a) = try_decode::<AgentReputationProfile>(data)? {
+        let fields = fields!(
+            a,
+            agent_id,
+            total_ratings,
+            average_rating,
+            quality_score,
+            speed_score,
+            value_score,
+            created_at,
+            last_rating_at,
+        );
+        return Ok(Some(("AgentReputationProfile", fields)));
+    }
+    if let Some(a) = try_decode::<ReputationConfig>(data)? {
+        return Ok(Some(("ReputationConfig", fields!(a, admin))));
+    }
+    if let Some(a) = try_decode::<RoyaltyConfig>(data)? {
+        let fields = fields!(
+            a,
+            creator_share,
+            platform_share,
+            treasury_share,
+            platform_wallet,
+            treasury_wallet,
+            admin,
+            total_distributed,
+            total_transactions,
+            created_at,
+            updated_at,
+            is_paused,
+        );
+        return Ok(Some(("RoyaltyConfig", fields)));
+    }
+    if let Some(a) = try_decode::<DistributionRecord>(data)? {
+        let fields = fields!(
+            a,
+            distribution_id,
+            creator,
+            total_amount,
+            creator_amount,
+            platform_amount,
+            treasury_amount,
+            timestamp,
+        );
+        return Ok(Some(("DistributionRecord", fields)));
+    }
+    if let Some(a) = try_decode::<GuardianConfig>(data)? {
+        let fields = fields!(
+            a,
+            authority,
+            guardians,
+            is_paused,
+            last_changed_at,
+            last_changed_by,
+        );
+        return Ok(Some(("GuardianConfig", fields)));
+    }
+
+    Ok(None)
+}
+
+// Helper function to render a pricing model (program enums do not derive Debug)
+fn pricing(model: &PricingModel) -> String {
+    let terms = match model {
+        PricingModel::PerQuery { price, .. } => format!("PerQuery {{ price: {price} }}"),
+        PricingModel::Subscription { monthly, .. } => {
+            format!("Subscription {{ monthly: {monthly} }}")
+        }
+        PricingModel::Custom { base, variable, .. } => {
+            format!("Custom {{ base: {base}, variable: {variable} }}")
+        }
+        PricingModel::UsdPegged { usd_cents, feed } => {
+            format!("UsdPegged {{ usd_cents: {usd_cents}, feed: {feed} }}")
+        }
+        PricingModel::Surge {
+            base,
+            min,
+            max,
+            target_open,
+            step_bps,
+        } => format!(
+            "Surge {{ base: {base}, min: {min}, max: {max}, target_open: {target_open}, step_bps: {step_bps} }}"
+        ),
+    };
+    match model.payment_mint() {
+        Some(mint) => format!("{terms} in {mint}"),
+        None => format!("{terms} in lamports"),
+    }
+}
+
+// Helper function to render an endpoint list in failover order
+fn endpoints(endpoints: &[AgentEndpoint]) -> String {
+    let rendered: Vec<String> = endpoints
+        .iter()
+        .map(|e| format!("{} {} ({})", e.priority, e.url, e.region))
+        .collect();
+    format!("[{}]", rendered.join(", "))
+}
+
+// Helper function to render a request status
+fn status(status: &RequestStatus) -> &'static str {
+    match status {
+        RequestStatus::Pending => "Pending",
+        RequestStatus::InProgress => "InProgress",
+        RequestStatus::Completed => "Completed",
+        RequestStatus::Approved => "Approved",
+        RequestStatus::Disputed => "Disputed",
+        RequestStatus::Cancelled => "Cancelled",
+        RequestStatus::Resolved => "Resolved",
+    }
+}
@@ -0,0 +1,517 @@
+//! AgentMarket admin CLI: initialize and update program configs, pause the
+//! protocol, moderate ratings, inspect any program account, and export
+//! snapshots of every decoded account.
+//!
+//! Transactions are signed with a Solana keypair file and sent to the chosen
+//! cluster; `inspect` and `snapshot` are read-only and need no keypair.
+
+mod inspect;
+mod snapshot;
+
+use std::path::PathBuf;
+
+use agentmarket_sdk::accounts::{AgentProfile, Rating, RoyaltyConfig, ServiceRequest};
+use agentmarket_sdk::instructions::royalty::Shares;
+use agentmarket_sdk::instructions::{guardian, registry, reputation, royalty};
+use agentmarket_sdk::{pda, Instruction, Pubkey, RpcClient, ACCOUNT_COMPRESSION_PROGRAM_ID};
+use anchor_lang::prelude::Rent;
+use anchor_lang::solana_program::system_instruction;
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use solana_keypair::{read_keypair_file, Keypair};
+use solana_signer::Signer;
+
+use crate::inspect::{ProgramArg, Seed};
+use crate::snapshot::Format;
+
+#[derive(Parser, Debug)]
+#[command(name = "agentmarket", about = "Administer the AgentMarket programs")]
+struct Args {
+    /// Cluster to talk to
+    #[arg(long, value_enum, default_value_t = Cluster::Localnet)]
+    cluster: Cluster,
+
+    /// Custom JSON-RPC endpoint; overrides --cluster
+    #[arg(long, env = "AGENTMARKET_RPC_URL")]
+    url: Option<String>,
+
+    /// Keypair file used to sign and pay for transactions
+    #[arg(long, env = "AGENTMARKET_KEYPAIR")]
+    keypair: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Cluster {
+    Localnet,
+    Devnet,
+    Mainnet,
+}
+
+impl Cluster {
+    fn url(self) -> &'static str {
+        match self {
+            Cluster::Localnet => "http://127.0.0.1:8899",
+            Cluster::Devnet => "https://api.devnet.solana.com",
+            Cluster::Mainnet => "https://api.mainnet-beta.solana.com",
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Create a program's singleton config with the signer as admin
+    #[command(subcommand)]
+    Init(InitCommand),
+
+    /// Change the royalty split and/or payout wallets
+    UpdateShares {
+        #[arg(long, requires_all = ["platform_share", "treasury_share"])]
+        creator_share: Option<u8>,
+        #[arg(long, requires_all = ["creator_share", "treasury_share"])]
+        platform_share: Option<u8>,
+        #[arg(long, requires_all = ["creator_share", "platform_share"])]
+        treasury_share: Option<u8>,
+        #[arg(long)]
+        platform_wallet: Option<Pubkey>,
+        #[arg(long)]
+        treasury_wallet: Option<Pubkey>,
+    },
+
+    /// Pause the whole protocol or a single program
+    Pause {
+        #[arg(long, value_enum, default_value_t = PauseTarget::Protocol)]
+        target: PauseTarget,
+    },
+
+    /// Lift a pause set with `pause`
+    Unpause {
+        #[arg(long, value_enum, default_value_t = PauseTarget::Protocol)]
+        target: PauseTarget,
+    },
+
+    /// Change the listing stake, the key that may slash it, the featured listing price, the verifier,
+    /// the heartbeat window, and/or the registration fee
+    UpdateRegistry {
+        /// Lamports each new agent locks
+        #[arg(long)]
+        listing_stake: Option<u64>,
+        #[arg(long)]
+        slash_authority: Option<Pubkey>,
+        /// Lamports a featured listing costs per day; zero stops promotions
+        #[arg(long)]
+        featured_price_per_day: Option<u64>,
+        /// Key that grants and revokes the verified-agent badge
+        #[arg(long)]
+        verifier: Option<Pubkey>,
+        /// Seconds an agent may go without a heartbeat before it can be marked stale; zero for no limit
+        #[arg(long)]
+        heartbeat_window: Option<i64>,
+        /// Lamports each new agent pays to register; zero for free registration
+        #[arg(long)]
+        registration_fee: Option<u64>,
+    },
+
+    /// Route the collected registration fees through the royalty splitter
+    SweepFees,
+
+    /// Slash an agent's listing stake over a disputed request, paying the requester
+    SlashStake {
+        /// Disputed service request address
+        request: Pubkey,
+        /// Lamports to slash
+        amount: u64,
+    },
+
+    /// Grant an audited agent the verified badge (signed by the verifier)
+    VerifyAgent { agent: Pubkey },
+
+    /// Take an agent's verified badge away (signed by the verifier)
+    RevokeVerification { agent: Pubkey },
+
+    /// Mark a reported rating valid or invalid
+    ModerateRating {
+        /// Rating account address
+        rating: Pubkey,
+        /// Reject the rating instead of upholding it
+        #[arg(long)]
+        invalid: bool,
+        #[arg(long, default_value = "")]
+        note: String,
+    },
+
+    /// Deactivate an agent listing
+    ///
+    /// The registry has no admin suspension path, so this must be signed by
+    /// the keypair holding the agent's NFT.
+    SuspendAgent {
+        /// Agent profile address; defaults to the agent the signer registered
+        agent: Option<Pubkey>,
+    },
+
+    /// Reactivate an agent listing
+    ReinstateAgent {
+        /// Agent profile address; defaults to the agent the signer registered
+        agent: Option<Pubkey>,
+    },
+
+    /// Add a capability to the taxonomy agents pick their capabilities from
+    AddCapability {
+        /// Short id agents list, e.g. `summarization`
+        id: String,
+        #[arg(long)]
+        name: String,
+        #[arg(long, default_value = "")]
+        description: String,
+    },
+
+    /// Retire a capability so new agents can no longer list it
+    DeprecateCapability { id: String },
+
+    /// Accept an SPL token that agents may price their services in
+    AcceptMint { mint: Pubkey },
+
+    /// Stop accepting an SPL token for new agent pricing
+    RemoveMint { mint: Pubkey },
+
+    /// Take over an agent whose NFT the signer now holds, becoming its creator
+    ClaimAgent {
+        /// Agent profile address
+        agent: Pubkey,
+    },
+
+    /// Fetch and decode an account by address or by program and seeds
+    Inspect {
+        /// Account address; omit to derive it from --program and --seed
+        #[arg(required_unless_present = "program", conflicts_with_all = ["program", "seeds"])]
+        address: Option<Pubkey>,
+
+        /// Program owning the PDA: registry, escrow, reputation, royalty, guardian, or an id
+        #[arg(long, requires = "seeds")]
+        program: Option<ProgramArg>,
+
+        /// PDA seed as `str:<text>`, `pubkey:<base58>`, `u8:<n>`, `u64:<n>`, or `hex:<bytes>`
+        #[arg(long = "seed")]
+        seeds: Vec<Seed>,
+    },
+
+    /// Export every decoded program account for audits and migration dry-runs
+    Snapshot {
+        /// Directory to write one file per account type plus `manifest.json`
+        #[arg(long, default_value = "snapshot")]
+        out_dir: PathBuf,
+
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum InitCommand {
+    /// Protocol guardian config holding the global pause switch
+    Guardian {
+        /// Keys allowed to pause the protocol
+        #[arg(long = "guardian")]
+        guardians: Vec<Pubkey>,
+    },
+
+    /// Royalty split config
+    Royalty {
+        #[arg(long)]
+        creator_share: u8,
+        #[arg(long)]
+        platform_share: u8,
+        #[arg(long)]
+        treasury_share: u8,
+        #[arg(long)]
+        platform_wallet: Pubkey,
+        #[arg(long)]
+        treasury_wallet: Pubkey,
+    },
+
+    /// Reputation moderation config
+    Reputation,
+
+    /// Registry config with the listing stake agents lock at registration
+    Registry {
+        /// Lamports each new agent locks
+        #[arg(long, default_value_t = 0)]
+        listing_stake: u64,
+        /// Key that may slash stakes over disputes; defaults to the signer
+        #[arg(long)]
+        slash_authority: Option<Pubkey>,
+        /// Lamports a featured listing costs per day; zero disables promotions
+        #[arg(long, default_value_t = 0)]
+        featured_price_per_day: u64,
+        /// Key that grants and revokes the verified-agent badge; defaults to the signer
+        #[arg(long)]
+        verifier: Option<Pubkey>,
+        /// Seconds an agent may go without a heartbeat before it can be marked stale; zero for no limit
+        #[arg(long, default_value_t = 0)]
+        heartbeat_window: i64,
+        /// Lamports each new agent pays to register; zero for free registration
+        #[arg(long, default_value_t = 0)]
+        registration_fee: u64,
+    },
+
+    /// Platform collection NFT that agent NFTs are verified into
+    Collection {
+        #[arg(long, default_value = "AgentMarket Agents")]
+        name: String,
+        #[arg(long, default_value = "AGENT")]
+        symbol: String,
+        /// Collection metadata JSON URI
+        #[arg(long)]
+        uri: String,
+    },
+
+    /// Bubblegum tree that compressed agent NFTs are minted into
+    AgentTree {
+        /// Tree depth; the tree holds 2^depth agents
+        #[arg(long, default_value_t = 14)]
+        max_depth: u32,
+        /// Concurrent changes the tree accepts per slot
+        #[arg(long, default_value_t = 64)]
+        max_buffer_size: u32,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum PauseTarget {
+    /// Every guarded instruction, through the protocol guardian
+    Protocol,
+    /// Royalty distributions only
+    Royalty,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    let rpc = RpcClient::new(
+        args.url
+            .clone()
+            .unwrap_or_else(|| args.cluster.url().to_owned()),
+    );
+
+    if let Command::Inspect {
+        address,
+        program,
+        seeds,
+    } = &args.command
+    {
+        return inspect::run(&rpc, *address, *program, seeds).await;
+    }
+    if let Command::Snapshot { out_dir, format } = &args.command {
+        return snapshot::run(&rpc, out_dir, *format).await;
+    }
+
+    let signer = load_keypair(args.keypair.as_deref())?;
+    let admin = signer.pubkey();
+
+    if let Command::Init(InitCommand::AgentTree {
+        max_depth,
+        max_buffer_size,
+    }) = args.command
+    {
+        return init_agent_tree(&rpc, &signer, max_depth, max_buffer_size).await;
+    }
+
+    let instruction = match args.command {
+        Command::Init(InitCommand::Guardian { guardians }) => {
+            guardian::initialize_guardian(admin, guardians)
+        }
+        Command::Init(InitCommand::Royalty {
+            creator_share,
+            platform_share,
+            treasury_share,
+            platform_wallet,
+            treasury_wallet,
+        }) => royalty::initialize_config(
+            admin,
+            Shares {
+                creator: creator_share,
+                platform: platform_share,
+                treasury: treasury_share,
+            },
+            platform_wallet,
+            treasury_wallet,
+        ),
+        Command::Init(InitCommand::Reputation) => reputation::initialize_config(admin),
+        Command::Init(InitCommand::Registry {
+            listing_stake,
+            slash_authority,
+            featured_price_per_day,
+            verifier,
+            heartbeat_window,
+            registration_fee,
+        }) => registry::initialize_registry_config(
+            admin,
+            listing_stake,
+            slash_authority.unwrap_or(admin),
+            featured_price_per_day,
+            verifier.unwrap_or(admin),
+            heartbeat_window,
+            registration_fee,
+        ),
+        Command::Init(InitCommand::Collection { name, symbol, uri }) => {
+            registry::initialize_collection(admin, name, symbol, uri)
+        }
+        Command::UpdateShares {
+            creator_share,
+            platform_share,
+            treasury_share,
+            platform_wallet,
+            treasury_wallet,
+        } => {
+            let shares = match (creator_share, platform_share, treasury_share) {
+                (Some(creator), Some(platform), Some(treasury)) => Some(Shares {
+                    creator,
+                    platform,
+                    treasury,
+                }),
+                _ => None,
+            };
+            royalty::update_config(admin, shares, platform_wallet, treasury_wallet)
+        }
+        Command::Pause { target } => match target {
+            PauseTarget::Protocol => guardian::pause_protocol(admin),
+            PauseTarget::Royalty => royalty::set_pause_state(admin, true),
+        },
+        Command::Unpause { target } => match target {
+            PauseTarget::Protocol => guardian::unpause_protocol(admin),
+            PauseTarget::Royalty => royalty::set_pause_state(admin, false),
+        },
+        Command::UpdateRegistry {
+            listing_stake,
+            slash_authority,
+            featured_price_per_day,
+            verifier,
+            heartbeat_window,
+            registration_fee,
+        } => registry::update_registry_config(
+            admin,
+            listing_stake,
+            slash_authority,
+            featured_price_per_day,
+            verifier,
+            heartbeat_window,
+            registration_fee,
+        ),
+        Command::SweepFees => {
+            let config: RoyaltyConfig = rpc
+                .fetch(&pda::royalty_config().0)
+                .await
+                .context("failed to load royalty config")?;
+            registry::sweep_registration_fees(admin, &config)
+        }
+        Command::VerifyAgent { agent } => registry::verify_agent(admin, agent),
+        Command::RevokeVerification { agent } => registry::revoke_verification(admin, agent),
+        Command::SlashStake { request, amount } => {
+            let account: ServiceRequest = rpc
+                .fetch(&request)
+                .await
+                .context("failed to load request")?;
+            registry::slash_stake(admin, request, &account, amount)
+        }
+        Command::ModerateRating {
+            rating,
+            invalid,
+            note,
+        } => {
+            let account: Rating = rpc.fetch(&rating).await.context("failed to load rating")?;
+            reputation::moderate_rating(admin, rating, account.agent_id, !invalid, note)
+        }
+        Command::SuspendAgent { agent } => set_agent_active(&rpc, admin, agent, false).await?,
+        Command::ReinstateAgent { agent } => set_agent_active(&rpc, admin, agent, true).await?,
+        Command::AddCapability {
+            id,
+            name,
+            description,
+        } => registry::add_capability(admin, id, name, description),
+        Command::DeprecateCapability { id } => registry::deprecate_capability(admin, &id),
+        Command::AcceptMint { mint } => registry::add_accepted_mint(admin, mint),
+        Command::RemoveMint { mint } => registry::remove_accepted_mint(admin, mint),
+        Command::ClaimAgent { agent } => {
+            let profile: AgentProfile = rpc.fetch(&agent).await.context("failed to load agent")?;
+            registry::transfer_ownership(admin, agent, profile.nft_mint)
+        }
+        Command::Inspect { .. }
+        | Command::Snapshot { .. }
+        | Command::Init(InitCommand::AgentTree { .. }) => unreachable!("handled above"),
+    };
+
+    send(&rpc, &signer, instruction).await
+}
+
+// Helper function to toggle an agent listing as the holder of its NFT
+async fn set_agent_active(
+    rpc: &RpcClient,
+    holder: Pubkey,
+    agent: Option<Pubkey>,
+    is_active: bool,
+) -> Result<Instruction> {
+    let agent = agent.unwrap_or_else(|| pda::agent_profile(&holder).0);
+    let profile: AgentProfile = rpc.fetch(&agent).await.context("failed to load agent")?;
+    Ok(registry::update_agent(
+        holder,
+        agent,
+        profile.nft_mint,
+        registry::UpdateAgentArgs {
+            name: None,
+            description: None,
+            pricing: None,
+            is_active: Some(is_active),
+        },
+    ))
+}
+
+// Helper function to allocate a fresh Merkle tree account and register it as the agent tree
+async fn init_agent_tree(
+    rpc: &RpcClient,
+    signer: &Keypair,
+    max_depth: u32,
+    max_buffer_size: u32,
+) -> Result<()> {
+    let tree = Keypair::new();
+    let space = registry::merkle_tree_space(max_depth, max_buffer_size);
+    let allocate = system_instruction::create_account(
+        &signer.pubkey(),
+        &tree.pubkey(),
+        Rent::default().minimum_balance(space as usize),
+        space,
+        &ACCOUNT_COMPRESSION_PROGRAM_ID,
+    );
+    let initialize =
+        registry::initialize_agent_tree(signer.pubkey(), tree.pubkey(), max_depth, max_buffer_size);
+
+    let signature = rpc
+        .send_instructions(&[allocate, initialize], &signer.pubkey(), &[signer, &tree])
+        .await?;
+    println!("tree {} in {signature}", tree.pubkey());
+    Ok(())
+}
+
+// Helper function to read the signing keypair, defaulting to the Solana CLI's
+fn load_keypair(path: Option<&std::path::Path>) -> Result<Keypair> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => {
+            let home = std::env::var_os("HOME").context("HOME is not set; pass --keypair")?;
+            PathBuf::from(home).join(".config/solana/id.json")
+        }
+    };
+
+    read_keypair_file(&path)
+        .map_err(|e| anyhow::anyhow!("failed to read keypair {}: {e}", path.display()))
+}
+
+// Helper function to sign, submit, and report a single instruction
+async fn send(rpc: &RpcClient, signer: &Keypair, instruction: Instruction) -> Result<()> {
+    let signature = rpc
+        .send_instructions(&[instruction], &signer.pubkey(), &[signer])
+        .await?;
+    println!("{signature}");
+    Ok(())
+}
@@ -0,0 +1,160 @@
+//! `snapshot`: export every decoded program account as JSON or CSV files.
+//!
+//! Each account type lands in its own file, sorted by address so two snapshots
+//! diff cleanly, with a `manifest.json` recording the cluster, the slot range
+//! the scan ran over, and per-file row counts.
+
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use agentmarket_sdk::accounts::{
+    AgentProfile, AgentReputationProfile, DistributionRecord, GuardianConfig, Rating,
+    ReputationConfig, RoyaltyConfig, ServiceRequest,
+};
+use agentmarket_sdk::json::ToJson;
+use agentmarket_sdk::{
+    Pubkey, RpcClient, ESCROW_PROGRAM_ID, GUARDIAN_PROGRAM_ID, REGISTRY_PROGRAM_ID,
+    REPUTATION_PROGRAM_ID, ROYALTY_PROGRAM_ID,
+};
+use anchor_lang::{AccountDeserialize, Discriminator};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+
+/// Output file format
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Format {
+    /// One JSON array per account type
+    Json,
+    /// One CSV table per account type; nested values are JSON-encoded
+    Csv,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Csv => "csv",
+        }
+    }
+}
+
+pub async fn run(rpc: &RpcClient, out_dir: &Path, format: Format) -> Result<()> {
+    fs::create_dir_all(out_dir).with_context(|| format!("creating {}", out_dir.display()))?;
+
+    // Accounts are fetched one type at a time, so record the range the scan spans
+    let start_slot = slot(rpc).await?;
+    let mut counts = Map::new();
+    let mut export = |name: &str, rows: Vec<Value>| -> Result<()> {
+        let path = out_dir.join(format!("{name}.{}", format.extension()));
+        match format {
+            Format::Json => fs::write(&path, serde_json::to_vec_pretty(&rows)?)?,
+            Format::Csv => write_csv(&path, &rows)?,
+        }
+        println!("{:>6}  {}", rows.len(), path.display());
+        counts.insert(name.to_owned(), rows.len().into());
+        Ok(())
+    };
+
+    export(
+        "agents",
+        rows::<AgentProfile>(rpc, REGISTRY_PROGRAM_ID).await?,
+    )?;
+    export(
+        "requests",
+        rows::<ServiceRequest>(rpc, ESCROW_PROGRAM_ID).await?,
+    )?;
+    export("ratings", rows::<Rating>(rpc, REPUTATION_PROGRAM_ID).await?)?;
+    export(
+        "reputation_profiles",
+        rows::<AgentReputationProfile>(rpc, REPUTATION_PROGRAM_ID).await?,
+    )?;
+    export(
+        "reputation_config",
+        rows::<ReputationConfig>(rpc, REPUTATION_PROGRAM_ID).await?,
+    )?;
+    export(
+        "royalty_config",
+        rows::<RoyaltyConfig>(rpc, ROYALTY_PROGRAM_ID).await?,
+    )?;
+    export(
+        "distributions",
+        rows::<DistributionRecord>(rpc, ROYALTY_PROGRAM_ID).await?,
+    )?;
+    export(
+        "guardian_config",
+        rows::<GuardianConfig>(rpc, GUARDIAN_PROGRAM_ID).await?,
+    )?;
+
+    let end_slot = slot(rpc).await?;
+    let manifest = json!({
+        "cluster": rpc.url(),
+        "start_slot": start_slot,
+        "end_slot": end_slot,
+        "generated_at": SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        "format": format.extension(),
+        "counts": counts,
+    });
+    fs::write(
+        out_dir.join("manifest.json"),
+        serde_json::to_vec_pretty(&manifest)?,
+    )?;
+    Ok(())
+}
+
+// Helper function to fetch every account of one type as JSON rows, sorted by address
+async fn rows<T>(rpc: &RpcClient, program_id: Pubkey) -> Result<Vec<Value>>
+where
+    T: AccountDeserialize + Discriminator + ToJson,
+{
+    let mut accounts = rpc.fetch_all::<T>(&program_id).await?;
+    accounts.sort_by_key(|(address, _)| address.to_string());
+
+    Ok(accounts
+        .into_iter()
+        .map(|(address, account)| {
+            let mut row = Map::new();
+            row.insert("address".into(), address.to_string().into());
+            if let Value::Object(fields) = account.to_json() {
+                row.extend(fields);
+            }
+            Value::Object(row)
+        })
+        .collect())
+}
+
+// Helper function to write rows as a CSV table, `address` first then fields by name
+fn write_csv(path: &Path, rows: &[Value]) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    let Some(Value::Object(first)) = rows.first() else {
+        return Ok(());
+    };
+
+    let mut headers: Vec<&str> = vec!["address"];
+    headers.extend(first.keys().map(String::as_str).filter(|k| *k != "address"));
+    writer.write_record(&headers)?;
+
+    for row in rows {
+        writer.write_record(headers.iter().map(|key| cell(&row[*key])))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// Helper function to flatten one JSON value into a CSV cell
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        _ => value.to_string(),
+    }
+}
+
+// Helper function to read the current slot
+async fn slot(rpc: &RpcClient) -> Result<u64> {
+    rpc.call("getSlot", json!([]))
+        .await?
+        .as_u64()
+        .context("getSlot returned a non-integer")
+}
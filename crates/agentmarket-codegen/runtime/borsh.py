@@ -0,0 +1,122 @@
+"""Borsh reader and writer shared by the generated AgentMarket bindings.
+
+Generated by agentmarket-codegen; do not edit.
+"""
+
+from __future__ import annotations
+
+import base64
+from typing import Callable, List, Optional, Sequence, TypeVar
+
+from solders.pubkey import Pubkey
+
+T = TypeVar("T")
+
+_INT_SIZES = {
+    "u8": (1, False),
+    "i8": (1, True),
+    "u16": (2, False),
+    "i16": (2, True),
+    "u32": (4, False),
+    "i32": (4, True),
+    "u64": (8, False),
+    "i64": (8, True),
+    "u128": (16, False),
+    "i128": (16, True),
+}
+
+
+class Writer:
+    def __init__(self) -> None:
+        self._buf = bytearray()
+
+    def raw(self, data: bytes) -> None:
+        self._buf.extend(data)
+
+    def bool(self, value: bool) -> None:
+        self._buf.append(1 if value else 0)
+
+    def int(self, kind: str, value: int) -> None:
+        size, signed = _INT_SIZES[kind]
+        self._buf.extend(value.to_bytes(size, "little", signed=signed))
+
+    def string(self, value: str) -> None:
+        self.bytes_with_length(value.encode("utf-8"))
+
+    def bytes_with_length(self, value: bytes) -> None:
+        self.int("u32", len(value))
+        self._buf.extend(value)
+
+    def pubkey(self, value: Pubkey) -> None:
+        self._buf.extend(bytes(value))
+
+    def vec(self, items: Sequence[T], write: Callable[["Writer", T], None]) -> None:
+        self.int("u32", len(items))
+        for item in items:
+            write(self, item)
+
+    def array(self, items: Sequence[T], length: int, write: Callable[["Writer", T], None]) -> None:
+        if len(items) != length:
+            raise ValueError(f"expected {length} items, got {len(items)}")
+        for item in items:
+            write(self, item)
+
+    def option(self, value: Optional[T], write: Callable[["Writer", T], None]) -> None:
+        if value is None:
+            self._buf.append(0)
+        else:
+            self._buf.append(1)
+            write(self, value)
+
+    def to_bytes(self) -> bytes:
+        return bytes(self._buf)
+
+
+class Reader:
+    def __init__(self, data: bytes) -> None:
+        self._data = data
+        self._offset = 0
+
+    def raw(self, length: int) -> bytes:
+        if self._offset + length > len(self._data):
+            raise ValueError("unexpected end of data")
+        chunk = self._data[self._offset : self._offset + length]
+        self._offset += length
+        return chunk
+
+    def bool(self) -> bool:
+        return self.raw(1)[0] != 0
+
+    def int(self, kind: str) -> int:
+        size, signed = _INT_SIZES[kind]
+        return int.from_bytes(self.raw(size), "little", signed=signed)
+
+    def string(self) -> str:
+        return self.bytes_with_length().decode("utf-8")
+
+    def bytes_with_length(self) -> bytes:
+        return self.raw(self.int("u32"))
+
+    def pubkey(self) -> Pubkey:
+        return Pubkey.from_bytes(self.raw(32))
+
+    def vec(self, read: Callable[["Reader"], T]) -> List[T]:
+        return [read(self) for _ in range(self.int("u32"))]
+
+    def array(self, length: int, read: Callable[["Reader"], T]) -> List[T]:
+        return [read(self) for _ in range(length)]
+
+    def option(self, read: Callable[["Reader"], T]) -> Optional[T]:
+        return None if self.raw(1)[0] == 0 else read(self)
+
+
+def expect_discriminator(data: bytes, discriminator: bytes, name: str) -> None:
+    """Raise unless `data` starts with the expected 8-byte discriminator."""
+    if not data.startswith(discriminator):
+        raise ValueError(f"data is not a {name}")
+
+
+def event_payloads(logs: Sequence[str]) -> List[bytes]:
+    """Base64 payloads of `Program data:` log lines, where Anchor emits events."""
+    prefix = "Program data: "
+    return [base64.b64decode(line[len(prefix) :]) for line in logs if line.startswith(prefix)]
@@ -0,0 +1,190 @@
+// Borsh reader and writer shared by the generated AgentMarket bindings.
+// Generated by agentmarket-codegen; do not edit.
+
+import { BN } from '@coral-xyz/anchor'
+import { PublicKey } from '@solana/web3.js'
+
+export class Writer {
+  private bytes: number[] = []
+
+  raw(data: ArrayLike<number>): void {
+    for (let i = 0; i < data.length; i++) this.bytes.push(data[i])
+  }
+
+  bool(value: boolean): void {
+    this.bytes.push(value ? 1 : 0)
+  }
+
+  u8(value: number): void {
+    this.int(new BN(value), 1, false)
+  }
+  i8(value: number): void {
+    this.int(new BN(value), 1, true)
+  }
+  u16(value: number): void {
+    this.int(new BN(value), 2, false)
+  }
+  i16(value: number): void {
+    this.int(new BN(value), 2, true)
+  }
+  u32(value: number): void {
+    this.int(new BN(value), 4, false)
+  }
+  i32(value: number): void {
+    this.int(new BN(value), 4, true)
+  }
+  u64(value: BN): void {
+    this.int(value, 8, false)
+  }
+  i64(value: BN): void {
+    this.int(value, 8, true)
+  }
+  u128(value: BN): void {
+    this.int(value, 16, false)
+  }
+  i128(value: BN): void {
+    this.int(value, 16, true)
+  }
+
+  string(value: string): void {
+    this.bytesWithLength(new TextEncoder().encode(value))
+  }
+
+  bytesWithLength(value: Uint8Array): void {
+    this.u32(value.length)
+    this.raw(value)
+  }
+
+  pubkey(value: PublicKey): void {
+    this.raw(value.toBytes())
+  }
+
+  vec<T>(items: T[], write: (w: Writer, item: T) => void): void {
+    this.u32(items.length)
+    items.forEach((item) => write(this, item))
+  }
+
+  array<T>(items: T[], length: number, write: (w: Writer, item: T) => void): void {
+    if (items.length !== length) throw new Error(`expected ${length} items, got ${items.length}`)
+    items.forEach((item) => write(this, item))
+  }
+
+  option<T>(value: T | null, write: (w: Writer, item: T) => void): void {
+    if (value === null) {
+      this.bytes.push(0)
+    } else {
+      this.bytes.push(1)
+      write(this, value)
+    }
+  }
+
+  toBuffer(): Buffer {
+    return Buffer.from(this.bytes)
+  }
+
+  private int(value: BN, size: number, signed: boolean): void {
+    const bits = size * 8
+    const encoded = signed ? value.toTwos(bits) : value
+    this.raw(encoded.toArray('le', size))
+  }
+}
+
+export class Reader {
+  private offset = 0
+
+  constructor(private readonly data: Uint8Array) {}
+
+  raw(length: number): Uint8Array {
+    if (this.offset + length > this.data.length) throw new Error('unexpected end of data')
+    const slice = this.data.slice(this.offset, this.offset + length)
+    this.offset += length
+    return slice
+  }
+
+  bool(): boolean {
+    return this.raw(1)[0] !== 0
+  }
+
+  u8(): number {
+    return this.int(1, false).toNumber()
+  }
+  i8(): number {
+    return this.int(1, true).toNumber()
+  }
+  u16(): number {
+    return this.int(2, false).toNumber()
+  }
+  i16(): number {
+    return this.int(2, true).toNumber()
+  }
+  u32(): number {
+    return this.int(4, false).toNumber()
+  }
+  i32(): number {
+    return this.int(4, true).toNumber()
+  }
+  u64(): BN {
+    return this.int(8, false)
+  }
+  i64(): BN {
+    return this.int(8, true)
+  }
+  u128(): BN {
+    return this.int(16, false)
+  }
+  i128(): BN {
+    return this.int(16, true)
+  }
+
+  string(): string {
+    return new TextDecoder().decode(this.bytesWithLength())
+  }
+
+  bytesWithLength(): Uint8Array {
+    return this.raw(this.u32())
+  }
+
+  pubkey(): PublicKey {
+    return new PublicKey(this.raw(32))
+  }
+
+  vec<T>(read: (r: Reader) => T): T[] {
+    const length = this.u32()
+    const items: T[] = []
+    for (let i = 0; i < length; i++) items.push(read(this))
+    return items
+  }
+
+  array<T>(length: number, read: (r: Reader) => T): T[] {
+    const items: T[] = []
+    for (let i = 0; i < length; i++) items.push(read(this))
+    return items
+  }
+
+  option<T>(read: (r: Reader) => T): T | null {
+    return this.raw(1)[0] === 0 ? null : read(this)
+  }
+
+  private int(size: number, signed: boolean): BN {
+    const value = new BN(this.raw(size), 'le')
+    return signed ? value.fromTwos(size * 8) : value
+  }
+}
+
+/** Whether `data` starts with `discriminator` */
+export function hasDiscriminator(data: Uint8Array, discriminator: number[]): boolean {
+  return discriminator.every((byte, i) => data[i] === byte)
+}
+
+/** Throw unless `data` starts with the expected 8-byte discriminator */
+export function expectDiscriminator(data: Uint8Array, discriminator: number[], name: string): void {
+  if (!hasDiscriminator(data, discriminator)) throw new Error(`data is not a ${name}`)
+}
+
+/** Base64 payloads of `Program data:` log lines, where Anchor emits events */
+export function eventPayloads(logs: string[]): Uint8Array[] {
+  const prefix = 'Program data: '
+  return logs
+    .filter((line) => line.indexOf(prefix) === 0)
+    .map((line) => new Uint8Array(Buffer.from(line.slice(prefix.length), 'base64')))
+}
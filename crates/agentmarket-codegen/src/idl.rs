@@ -0,0 +1,184 @@
+//! The subset of the Anchor (0.30+) IDL format the generators read.
+
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct Idl {
+    pub address: String,
+    pub metadata: Metadata,
+    pub instructions: Vec<Instruction>,
+    #[serde(default)]
+    pub accounts: Vec<Discriminated>,
+    #[serde(default)]
+    pub events: Vec<Discriminated>,
+    #[serde(default)]
+    pub errors: Vec<ErrorCode>,
+    #[serde(default)]
+    pub types: Vec<TypeDef>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Metadata {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Instruction {
+    pub name: String,
+    #[serde(default)]
+    pub docs: Vec<String>,
+    pub discriminator: Vec<u8>,
+    pub accounts: Vec<AccountItem>,
+    pub args: Vec<Field>,
+}
+
+/// An instruction account, or a nested `Accounts` struct
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum AccountItem {
+    Composite {
+        name: String,
+        accounts: Vec<AccountItem>,
+    },
+    Single(InstructionAccount),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InstructionAccount {
+    pub name: String,
+    #[serde(default)]
+    pub docs: Vec<String>,
+    #[serde(default)]
+    pub writable: bool,
+    #[serde(default)]
+    pub signer: bool,
+    #[serde(default)]
+    pub optional: bool,
+    /// Fixed address, e.g. a program or sysvar
+    pub address: Option<String>,
+}
+
+/// An account or event: its name and 8-byte discriminator; the layout lives in `types`
+#[derive(Debug, Deserialize)]
+pub struct Discriminated {
+    pub name: String,
+    pub discriminator: Vec<u8>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ErrorCode {
+    pub code: u32,
+    pub name: String,
+    pub msg: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TypeDef {
+    pub name: String,
+    #[serde(default)]
+    pub docs: Vec<String>,
+    #[serde(rename = "type")]
+    pub ty: TypeDefKind,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum TypeDefKind {
+    Struct {
+        #[serde(default)]
+        fields: Option<Fields>,
+    },
+    Enum {
+        variants: Vec<Variant>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Variant {
+    pub name: String,
+    #[serde(default)]
+    pub fields: Option<Fields>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Fields {
+    Named(Vec<Field>),
+    Tuple(Vec<Type>),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Field {
+    pub name: String,
+    #[serde(default)]
+    pub docs: Vec<String>,
+    #[serde(rename = "type")]
+    pub ty: Type,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Type {
+    Primitive(Primitive),
+    Complex(ComplexType),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Primitive {
+    Bool,
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    U128,
+    I128,
+    Bytes,
+    String,
+    Pubkey,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComplexType {
+    Vec(Box<Type>),
+    Option(Box<Type>),
+    Array(Box<Type>, usize),
+    Defined { name: String },
+}
+
+impl Idl {
+    /// Layout of an account or event, which the IDL lists under `types`
+    pub fn type_def(&self, name: &str) -> Option<&TypeDef> {
+        self.types.iter().find(|t| t.name == name)
+    }
+}
+
+impl Instruction {
+    /// Accounts in the order the program expects, nested structs flattened
+    pub fn flat_accounts(&self) -> Vec<InstructionAccount> {
+        fn flatten(items: &[AccountItem], prefix: &str, out: &mut Vec<InstructionAccount>) {
+            for item in items {
+                match item {
+                    AccountItem::Single(account) => {
+                        let mut account = account.clone();
+                        account.name = format!("{prefix}{}", account.name);
+                        out.push(account);
+                    }
+                    AccountItem::Composite { name, accounts } => {
+                        flatten(accounts, &format!("{prefix}{name}_"), out)
+                    }
+                }
+            }
+        }
+
+        let mut out = Vec::new();
+        flatten(&self.accounts, "", &mut out);
+        out
+    }
+}
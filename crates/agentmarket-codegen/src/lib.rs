@@ -0,0 +1,7 @@
+//! Anchor IDL model and the TypeScript and Python emitters behind the
+//! `agentmarket-codegen` binary, exposed so other tools can read IDLs too.
+
+pub mod idl;
+pub mod names;
+pub mod python;
+pub mod typescript;
@@ -0,0 +1,138 @@
+//! AgentMarket codegen: client bindings generated from the programs' Anchor IDLs.
+//!
+//! Reads the IDL that `anchor build` writes for each program and emits a
+//! TypeScript module and a Python module per program: instruction builders
+//! with typed account and argument structs, account decoders, event decoders
+//! and log parsers, and the custom error table. Each output directory also
+//! gets the small Borsh runtime the modules share and an index that
+//! re-exports every program.
+//!
+//! Output is deterministic, so `--check` can run in CI to fail when the
+//! committed IDLs and the generated bindings drift apart.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use agentmarket_codegen::idl::Idl;
+use agentmarket_codegen::{python, typescript};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "agentmarket-codegen",
+    about = "Generate TypeScript and Python bindings from the AgentMarket IDLs"
+)]
+struct Args {
+    /// Directory holding `<program>.json` IDLs, as written by `anchor build`
+    #[arg(
+        long,
+        env = "AGENTMARKET_IDL_DIR",
+        default_value = "programs/target/idl"
+    )]
+    idl_dir: PathBuf,
+
+    /// Programs to generate bindings for
+    #[arg(
+        long = "program",
+        value_delimiter = ',',
+        default_values_t = [
+            "agent_registry".to_string(),
+            "marketplace_escrow".to_string(),
+            "reputation_system".to_string(),
+            "royalty_splitter".to_string(),
+        ]
+    )]
+    programs: Vec<String>,
+
+    /// Output directory for the TypeScript bindings
+    #[arg(long)]
+    ts_out: Option<PathBuf>,
+
+    /// Output directory for the Python package
+    #[arg(long)]
+    py_out: Option<PathBuf>,
+
+    /// Compare against the files on disk instead of writing, failing if any are stale
+    #[arg(long)]
+    check: bool,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    if args.ts_out.is_none() && args.py_out.is_none() {
+        bail!("nothing to generate; pass --ts-out and/or --py-out");
+    }
+
+    let mut idls = Vec::with_capacity(args.programs.len());
+    for program in &args.programs {
+        let path = args.idl_dir.join(format!("{program}.json"));
+        let raw =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        let idl: Idl =
+            serde_json::from_str(&raw).with_context(|| format!("parsing {}", path.display()))?;
+        idls.push((program.clone(), idl));
+    }
+
+    let mut files = BTreeMap::new();
+    if let Some(dir) = &args.ts_out {
+        files.insert(dir.join("borsh.ts"), typescript::RUNTIME.to_string());
+        for (program, idl) in &idls {
+            let source = typescript::generate(idl)
+                .with_context(|| format!("generating TypeScript for {program}"))?;
+            files.insert(dir.join(format!("{program}.ts")), source);
+        }
+        files.insert(dir.join("index.ts"), typescript::index(&args.programs));
+    }
+    if let Some(dir) = &args.py_out {
+        files.insert(dir.join("_borsh.py"), python::RUNTIME.to_string());
+        for (program, idl) in &idls {
+            let source = python::generate(idl)
+                .with_context(|| format!("generating Python for {program}"))?;
+            files.insert(dir.join(format!("{program}.py")), source);
+        }
+        files.insert(
+            dir.join("__init__.py"),
+            python::package_init(&args.programs),
+        );
+    }
+
+    if args.check {
+        check(&files)
+    } else {
+        write(&files)
+    }
+}
+
+// Helper function to write every generated file, creating directories as needed
+fn write(files: &BTreeMap<PathBuf, String>) -> Result<()> {
+    for (path, contents) in files {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("creating {}", parent.display()))?;
+        }
+        fs::write(path, contents).with_context(|| format!("writing {}", path.display()))?;
+        println!("wrote {}", path.display());
+    }
+    Ok(())
+}
+
+// Helper function to fail if any generated file differs from what is on disk
+fn check(files: &BTreeMap<PathBuf, String>) -> Result<()> {
+    let stale: Vec<&Path> = files
+        .iter()
+        .filter(|(path, contents)| fs::read_to_string(path).ok().as_deref() != Some(contents))
+        .map(|(path, _)| path.as_path())
+        .collect();
+    if stale.is_empty() {
+        println!("{} generated files up to date", files.len());
+        return Ok(());
+    }
+    for path in &stale {
+        eprintln!("stale: {}", path.display());
+    }
+    bail!(
+        "{} generated files are out of date; run `npm run codegen`",
+        stale.len()
+    )
+}
@@ -0,0 +1,476 @@
+//! Python emitter: one module per program on top of `solders`.
+
+use std::fmt::{self, Write};
+
+use crate::idl::{ComplexType, Fields, Idl, Instruction, Primitive, Type, TypeDef, TypeDefKind};
+use crate::names::{pascal, python_ident, screaming, snake};
+
+/// Borsh reader/writer the generated modules import
+pub const RUNTIME: &str = include_str!("../runtime/borsh.py");
+
+/// Package `__init__` importing every program module
+pub fn package_init(programs: &[String]) -> String {
+    let mut out = String::from("# Generated by agentmarket-codegen; do not edit.\n\n");
+    out.push_str(&format!("from . import {}\n", programs.join(", ")));
+    out.push_str(&format!(
+        "\n__all__ = [{}]\n",
+        programs
+            .iter()
+            .map(|p| format!("\"{p}\""))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ));
+    out
+}
+
+/// Module source for one program's IDL
+pub fn generate(idl: &Idl) -> Result<String, fmt::Error> {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "# Generated by agentmarket-codegen from the {} IDL (v{}); do not edit.\n",
+        idl.metadata.name, idl.metadata.version
+    )?;
+    writeln!(out, "from __future__ import annotations\n")?;
+    writeln!(out, "from dataclasses import dataclass")?;
+    writeln!(
+        out,
+        "from typing import List, Optional, Sequence, Tuple, Union\n"
+    )?;
+    writeln!(
+        out,
+        "from solders.instruction import AccountMeta, Instruction"
+    )?;
+    writeln!(out, "from solders.pubkey import Pubkey\n")?;
+    writeln!(
+        out,
+        "from ._borsh import Reader, Writer, event_payloads, expect_discriminator\n"
+    )?;
+    writeln!(out, "PROGRAM_ID = Pubkey.from_string(\"{}\")", idl.address)?;
+
+    for def in &idl.types {
+        writeln!(out, "\n")?;
+        type_def(&mut out, idl, def)?;
+    }
+    for account in &idl.accounts {
+        writeln!(out, "\n")?;
+        discriminated(&mut out, &account.name, &account.discriminator)?;
+        writeln!(out, "\n")?;
+        writeln!(
+            out,
+            "def decode_{}(data: bytes) -> {}:",
+            snake(&account.name),
+            account.name
+        )?;
+        writeln!(
+            out,
+            "    expect_discriminator(data, {}_DISCRIMINATOR, \"{}\")",
+            screaming(&account.name),
+            account.name
+        )?;
+        writeln!(
+            out,
+            "    return {}",
+            read_defined(idl, &account.name, "Reader(data[8:])")
+        )?;
+    }
+    events(&mut out, idl)?;
+    for instruction in &idl.instructions {
+        writeln!(out, "\n")?;
+        builder(&mut out, instruction)?;
+    }
+    errors(&mut out, idl)?;
+    Ok(out)
+}
+
+// Helper function to emit a dataclass (struct) or variant dataclasses plus a reader (enum)
+fn type_def(out: &mut String, idl: &Idl, def: &TypeDef) -> fmt::Result {
+    let name = &def.name;
+    match &def.ty {
+        TypeDefKind::Struct { fields } => {
+            let fields = named(fields.as_ref());
+            writeln!(out, "@dataclass")?;
+            writeln!(out, "class {name}:")?;
+            docstring(out, "    ", &def.docs)?;
+            for (field, ty) in &fields {
+                writeln!(out, "    {field}: {}", py_type(ty))?;
+            }
+            if !fields.is_empty() {
+                writeln!(out)?;
+            }
+            writeln!(out, "    def write(self, w: Writer) -> None:")?;
+            if fields.is_empty() {
+                writeln!(out, "        pass")?;
+            }
+            for (field, ty) in &fields {
+                writeln!(out, "        {}", encode(ty, &format!("self.{field}")))?;
+            }
+            writeln!(out)?;
+            writeln!(out, "    @classmethod")?;
+            writeln!(out, "    def read(cls, r: Reader) -> {name}:")?;
+            let args: Vec<String> = fields
+                .iter()
+                .map(|(field, ty)| format!("{field}={}", decode(idl, ty)))
+                .collect();
+            writeln!(out, "        return cls({})", args.join(", "))?;
+        }
+        TypeDefKind::Enum { variants } => {
+            let mut classes = Vec::with_capacity(variants.len());
+            for (index, variant) in variants.iter().enumerate() {
+                let class = format!("{name}{}", pascal(&variant.name));
+                let fields = named(variant.fields.as_ref());
+                writeln!(out, "@dataclass")?;
+                writeln!(out, "class {class}:")?;
+                for (field, ty) in &fields {
+                    writeln!(out, "    {field}: {}", py_type(ty))?;
+                }
+                if !fields.is_empty() {
+                    writeln!(out)?;
+                }
+                writeln!(out, "    def write(self, w: Writer) -> None:")?;
+                writeln!(out, "        w.int(\"u8\", {index})")?;
+                for (field, ty) in &fields {
+                    writeln!(out, "        {}", encode(ty, &format!("self.{field}")))?;
+                }
+                writeln!(out, "\n")?;
+                classes.push((class, fields));
+            }
+
+            let union: Vec<&str> = classes.iter().map(|(class, _)| class.as_str()).collect();
+            writeln!(out, "{name} = Union[{}]", union.join(", "))?;
+            writeln!(out, "\n")?;
+
+            writeln!(out, "def read_{}(r: Reader) -> {name}:", snake(name))?;
+            docstring(out, "    ", &def.docs)?;
+            writeln!(out, "    variant = r.int(\"u8\")")?;
+            for (index, (class, fields)) in classes.iter().enumerate() {
+                let args: Vec<String> = fields
+                    .iter()
+                    .map(|(field, ty)| format!("{field}={}", decode(idl, ty)))
+                    .collect();
+                writeln!(out, "    if variant == {index}:")?;
+                writeln!(out, "        return {class}({})", args.join(", "))?;
+            }
+            writeln!(
+                out,
+                "    raise ValueError(f\"unknown {name} variant {{variant}}\")"
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// Helper function to emit the event discriminators, decoder, and log parser
+fn events(out: &mut String, idl: &Idl) -> fmt::Result {
+    if idl.events.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(out, "\n")?;
+    for event in &idl.events {
+        discriminated(out, &event.name, &event.discriminator)?;
+    }
+
+    let union: Vec<&str> = idl.events.iter().map(|e| e.name.as_str()).collect();
+    writeln!(out, "\n")?;
+    writeln!(
+        out,
+        "def decode_event(data: bytes) -> Optional[Tuple[str, Union[{}]]]:",
+        union.join(", ")
+    )?;
+    writeln!(
+        out,
+        "    \"\"\"Decode an event payload, or None if it is not one of this program's.\"\"\""
+    )?;
+    for event in &idl.events {
+        writeln!(
+            out,
+            "    if data.startswith({}_DISCRIMINATOR):",
+            screaming(&event.name)
+        )?;
+        writeln!(
+            out,
+            "        return \"{}\", {}",
+            event.name,
+            read_defined(idl, &event.name, "Reader(data[8:])")
+        )?;
+    }
+    writeln!(out, "    return None")?;
+
+    writeln!(out, "\n")?;
+    writeln!(
+        out,
+        "def parse_events(logs: Sequence[str]) -> List[Tuple[str, Union[{}]]]:",
+        union.join(", ")
+    )?;
+    writeln!(
+        out,
+        "    \"\"\"Every event of this program in a transaction's log messages.\"\"\""
+    )?;
+    writeln!(
+        out,
+        "    decoded = (decode_event(payload) for payload in event_payloads(logs))"
+    )?;
+    writeln!(
+        out,
+        "    return [event for event in decoded if event is not None]"
+    )
+}
+
+// Helper function to emit an instruction's account and argument dataclasses and its builder
+fn builder(out: &mut String, ix: &Instruction) -> fmt::Result {
+    let name = pascal(&ix.name);
+    let accounts = ix.flat_accounts();
+
+    discriminated(out, &name, &ix.discriminator)?;
+    writeln!(out, "\n")?;
+
+    // Dataclass fields with defaults must follow the required ones
+    writeln!(out, "@dataclass")?;
+    writeln!(out, "class {name}Accounts:")?;
+    if accounts.is_empty() {
+        writeln!(out, "    pass")?;
+    }
+    for account in accounts
+        .iter()
+        .filter(|a| !a.optional && a.address.is_none())
+    {
+        writeln!(out, "    {}: Pubkey", python_ident(&account.name))?;
+    }
+    for account in accounts
+        .iter()
+        .filter(|a| a.optional || a.address.is_some())
+    {
+        match &account.address {
+            Some(address) if !account.optional => writeln!(
+                out,
+                "    {}: Pubkey = Pubkey.from_string(\"{address}\")",
+                python_ident(&account.name)
+            )?,
+            _ => writeln!(
+                out,
+                "    {}: Optional[Pubkey] = None",
+                python_ident(&account.name)
+            )?,
+        }
+    }
+
+    let has_args = !ix.args.is_empty();
+    if has_args {
+        writeln!(out, "\n")?;
+        writeln!(out, "@dataclass")?;
+        writeln!(out, "class {name}Args:")?;
+        for arg in &ix.args {
+            writeln!(out, "    {}: {}", python_ident(&arg.name), py_type(&arg.ty))?;
+        }
+    }
+
+    writeln!(out, "\n")?;
+    let args_param = if has_args {
+        format!("args: {name}Args, ")
+    } else {
+        String::new()
+    };
+    writeln!(
+        out,
+        "def {}(accounts: {name}Accounts, {args_param}program_id: Pubkey = PROGRAM_ID) -> Instruction:",
+        snake(&ix.name)
+    )?;
+    docstring(out, "    ", &ix.docs)?;
+    writeln!(out, "    w = Writer()")?;
+    writeln!(out, "    w.raw({}_DISCRIMINATOR)", screaming(&name))?;
+    for arg in &ix.args {
+        writeln!(
+            out,
+            "    {}",
+            encode(&arg.ty, &format!("args.{}", python_ident(&arg.name)))
+        )?;
+    }
+
+    writeln!(out, "    keys = [")?;
+    for account in &accounts {
+        let field = format!("accounts.{}", python_ident(&account.name));
+        let flags = format!(
+            "is_signer={}, is_writable={}",
+            py_bool(account.signer),
+            py_bool(account.writable)
+        );
+        if account.optional {
+            // Anchor reads the program id in an optional slot as `None`
+            writeln!(
+                out,
+                "        AccountMeta({field}, {flags}) if {field} is not None else AccountMeta(program_id, is_signer=False, is_writable=False),"
+            )?;
+        } else {
+            writeln!(out, "        AccountMeta({field}, {flags}),")?;
+        }
+    }
+    writeln!(out, "    ]")?;
+    writeln!(
+        out,
+        "    return Instruction(program_id, w.to_bytes(), keys)"
+    )
+}
+
+// Helper function to emit the program's error table
+fn errors(out: &mut String, idl: &Idl) -> fmt::Result {
+    if idl.errors.is_empty() {
+        return Ok(());
+    }
+    writeln!(out, "\n")?;
+    writeln!(out, "# Custom program errors by code: (name, message)")?;
+    writeln!(out, "ERRORS = {{")?;
+    for error in &idl.errors {
+        writeln!(
+            out,
+            "    {}: (\"{}\", {}),",
+            error.code,
+            error.name,
+            quote(error.msg.as_deref().unwrap_or(""))
+        )?;
+    }
+    writeln!(out, "}}")
+}
+
+// Helper function to emit an 8-byte discriminator constant
+fn discriminated(out: &mut String, name: &str, discriminator: &[u8]) -> fmt::Result {
+    let bytes: Vec<String> = discriminator.iter().map(u8::to_string).collect();
+    writeln!(
+        out,
+        "{}_DISCRIMINATOR = bytes([{}])",
+        screaming(name),
+        bytes.join(", ")
+    )
+}
+
+// Helper function to emit a docstring, if there is one
+fn docstring(out: &mut String, indent: &str, docs: &[String]) -> fmt::Result {
+    if docs.is_empty() {
+        return Ok(());
+    }
+    let text: Vec<&str> = docs.iter().map(|line| line.trim()).collect();
+    writeln!(
+        out,
+        "{indent}\"\"\"{}\"\"\"",
+        text.join(" ").replace("\"\"\"", "'''")
+    )
+}
+
+// Helper function to list fields as (Python name, type); tuple fields become `field0`...
+fn named(fields: Option<&Fields>) -> Vec<(String, &Type)> {
+    match fields {
+        None => Vec::new(),
+        Some(Fields::Named(fields)) => fields
+            .iter()
+            .map(|f| (python_ident(&f.name), &f.ty))
+            .collect(),
+        Some(Fields::Tuple(types)) => types
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| (format!("field{i}"), ty))
+            .collect(),
+    }
+}
+
+fn py_type(ty: &Type) -> String {
+    match ty {
+        Type::Primitive(p) => match p {
+            Primitive::Bool => "bool".into(),
+            Primitive::Bytes => "bytes".into(),
+            Primitive::String => "str".into(),
+            Primitive::Pubkey => "Pubkey".into(),
+            _ => "int".into(),
+        },
+        Type::Complex(c) => match c {
+            ComplexType::Vec(inner) | ComplexType::Array(inner, _) => {
+                format!("List[{}]", py_type(inner))
+            }
+            ComplexType::Option(inner) => format!("Optional[{}]", py_type(inner)),
+            ComplexType::Defined { name } => name.clone(),
+        },
+    }
+}
+
+// Helper function to build the statement writing `value` of type `ty` to `w`
+fn encode(ty: &Type, value: &str) -> String {
+    match ty {
+        Type::Primitive(Primitive::Bool) => format!("w.bool({value})"),
+        Type::Primitive(Primitive::Bytes) => format!("w.bytes_with_length({value})"),
+        Type::Primitive(Primitive::String) => format!("w.string({value})"),
+        Type::Primitive(Primitive::Pubkey) => format!("w.pubkey({value})"),
+        Type::Primitive(int) => format!("w.int(\"{}\", {value})", int_kind(*int)),
+        Type::Complex(ComplexType::Vec(inner)) => {
+            format!("w.vec({value}, lambda w, x: {})", encode(inner, "x"))
+        }
+        Type::Complex(ComplexType::Array(inner, len)) => {
+            format!(
+                "w.array({value}, {len}, lambda w, x: {})",
+                encode(inner, "x")
+            )
+        }
+        Type::Complex(ComplexType::Option(inner)) => {
+            format!("w.option({value}, lambda w, x: {})", encode(inner, "x"))
+        }
+        Type::Complex(ComplexType::Defined { .. }) => format!("{value}.write(w)"),
+    }
+}
+
+// Helper function to build the expression reading a value of type `ty` from `r`
+fn decode(idl: &Idl, ty: &Type) -> String {
+    match ty {
+        Type::Primitive(Primitive::Bool) => "r.bool()".into(),
+        Type::Primitive(Primitive::Bytes) => "r.bytes_with_length()".into(),
+        Type::Primitive(Primitive::String) => "r.string()".into(),
+        Type::Primitive(Primitive::Pubkey) => "r.pubkey()".into(),
+        Type::Primitive(int) => format!("r.int(\"{}\")", int_kind(*int)),
+        Type::Complex(ComplexType::Vec(inner)) => {
+            format!("r.vec(lambda r: {})", decode(idl, inner))
+        }
+        Type::Complex(ComplexType::Array(inner, len)) => {
+            format!("r.array({len}, lambda r: {})", decode(idl, inner))
+        }
+        Type::Complex(ComplexType::Option(inner)) => {
+            format!("r.option(lambda r: {})", decode(idl, inner))
+        }
+        Type::Complex(ComplexType::Defined { name }) => read_defined(idl, name, "r"),
+    }
+}
+
+// Helper function to read a defined type: structs have a `read` classmethod, enums a function
+fn read_defined(idl: &Idl, name: &str, reader: &str) -> String {
+    match idl.type_def(name).map(|def| &def.ty) {
+        Some(TypeDefKind::Enum { .. }) => format!("read_{}({reader})", snake(name)),
+        _ => format!("{name}.read({reader})"),
+    }
+}
+
+fn int_kind(p: Primitive) -> &'static str {
+    match p {
+        Primitive::U8 => "u8",
+        Primitive::I8 => "i8",
+        Primitive::U16 => "u16",
+        Primitive::I16 => "i16",
+        Primitive::U32 => "u32",
+        Primitive::I32 => "i32",
+        Primitive::U64 => "u64",
+        Primitive::I64 => "i64",
+        Primitive::U128 => "u128",
+        Primitive::I128 => "i128",
+        Primitive::Bool | Primitive::Bytes | Primitive::String | Primitive::Pubkey => {
+            unreachable!("not an integer")
+        }
+    }
+}
+
+fn py_bool(value: bool) -> &'static str {
+    if value {
+        "True"
+    } else {
+        "False"
+    }
+}
+
+// Helper function to quote free text as a Python string literal
+fn quote(text: &str) -> String {
+    serde_json::to_string(text).expect("strings always serialize")
+}
@@ -0,0 +1,478 @@
+//! TypeScript emitter: one module per program on top of `@solana/web3.js` and `BN`.
+
+use std::fmt::{self, Write};
+
+use crate::idl::{ComplexType, Fields, Idl, Instruction, Primitive, Type, TypeDef, TypeDefKind};
+use crate::names::{camel, pascal, screaming};
+
+/// Borsh reader/writer the generated modules import
+pub const RUNTIME: &str = include_str!("../runtime/borsh.ts");
+
+/// Module re-exporting every program under a camelCase namespace
+pub fn index(programs: &[String]) -> String {
+    let mut out = String::from("// Generated by agentmarket-codegen; do not edit.\n\n");
+    for program in programs {
+        out.push_str(&format!(
+            "export * as {} from './{program}'\n",
+            camel(program)
+        ));
+    }
+    out
+}
+
+/// Module source for one program's IDL
+pub fn generate(idl: &Idl) -> Result<String, fmt::Error> {
+    let mut body = String::new();
+    writeln!(
+        body,
+        "export const PROGRAM_ID = new PublicKey('{}')",
+        idl.address
+    )?;
+
+    for def in &idl.types {
+        writeln!(body)?;
+        type_def(&mut body, def)?;
+    }
+    for account in &idl.accounts {
+        writeln!(body)?;
+        discriminated(&mut body, &account.name, &account.discriminator)?;
+        writeln!(
+            body,
+            "export function decode{0}(data: Uint8Array): {0} {{",
+            account.name
+        )?;
+        writeln!(
+            body,
+            "  expectDiscriminator(data, {}_DISCRIMINATOR, '{}')",
+            screaming(&account.name),
+            account.name
+        )?;
+        writeln!(
+            body,
+            "  return read{}(new Reader(data.subarray(8)))",
+            account.name
+        )?;
+        writeln!(body, "}}")?;
+    }
+    events(&mut body, idl)?;
+    for instruction in &idl.instructions {
+        writeln!(body)?;
+        builder(&mut body, instruction)?;
+    }
+    errors(&mut body, idl)?;
+
+    let mut out = format!(
+        "// Generated by agentmarket-codegen from the {} IDL (v{}); do not edit.\n\n",
+        idl.metadata.name, idl.metadata.version
+    );
+    if body.contains("BN") {
+        out.push_str("import { BN } from '@coral-xyz/anchor'\n");
+    }
+    let web3: Vec<&str> = ["AccountMeta", "PublicKey", "TransactionInstruction"]
+        .into_iter()
+        .filter(|name| body.contains(name))
+        .collect();
+    writeln!(
+        out,
+        "import {{ {} }} from '@solana/web3.js'",
+        web3.join(", ")
+    )?;
+    let runtime: Vec<&str> = [
+        "Reader",
+        "Writer",
+        "eventPayloads",
+        "expectDiscriminator",
+        "hasDiscriminator",
+    ]
+    .into_iter()
+    .filter(|name| body.contains(name))
+    .collect();
+    writeln!(out, "import {{ {} }} from './borsh'\n", runtime.join(", "))?;
+    out.push_str(&body);
+    Ok(out)
+}
+
+// Helper function to emit a struct or enum with its reader and writer
+fn type_def(out: &mut String, def: &TypeDef) -> fmt::Result {
+    let name = &def.name;
+    docs(out, "", &def.docs)?;
+    match &def.ty {
+        TypeDefKind::Struct { fields } => {
+            let fields = named(fields.as_ref());
+            writeln!(out, "export interface {name} {{")?;
+            for (field, ty, field_docs) in &fields {
+                docs(out, "  ", field_docs)?;
+                writeln!(out, "  {field}: {}", ts_type(ty))?;
+            }
+            writeln!(out, "}}\n")?;
+
+            writeln!(
+                out,
+                "export function write{name}(w: Writer, v: {name}): void {{"
+            )?;
+            for (field, ty, _) in &fields {
+                writeln!(out, "  {}", encode(ty, &format!("v.{field}")))?;
+            }
+            writeln!(out, "}}\n")?;
+
+            writeln!(out, "export function read{name}(r: Reader): {name} {{")?;
+            writeln!(out, "  return {{")?;
+            for (field, ty, _) in &fields {
+                writeln!(out, "    {field}: {},", decode(ty))?;
+            }
+            writeln!(out, "  }}")?;
+            writeln!(out, "}}")?;
+        }
+        TypeDefKind::Enum { variants } => {
+            writeln!(out, "export type {name} =")?;
+            for variant in variants {
+                let fields = named(variant.fields.as_ref());
+                let mut members = vec![format!("kind: '{}'", variant.name)];
+                members.extend(
+                    fields
+                        .iter()
+                        .map(|(f, ty, _)| format!("{f}: {}", ts_type(ty))),
+                );
+                writeln!(out, "  | {{ {} }}", members.join("; "))?;
+            }
+            writeln!(out)?;
+
+            writeln!(
+                out,
+                "export function write{name}(w: Writer, v: {name}): void {{"
+            )?;
+            writeln!(out, "  switch (v.kind) {{")?;
+            for (index, variant) in variants.iter().enumerate() {
+                writeln!(out, "    case '{}':", variant.name)?;
+                writeln!(out, "      w.u8({index})")?;
+                for (field, ty, _) in named(variant.fields.as_ref()) {
+                    writeln!(out, "      {}", encode(ty, &format!("v.{field}")))?;
+                }
+                writeln!(out, "      break")?;
+            }
+            writeln!(out, "  }}")?;
+            writeln!(out, "}}\n")?;
+
+            writeln!(out, "export function read{name}(r: Reader): {name} {{")?;
+            writeln!(out, "  const variant = r.u8()")?;
+            writeln!(out, "  switch (variant) {{")?;
+            for (index, variant) in variants.iter().enumerate() {
+                let mut members = vec![format!("kind: '{}'", variant.name)];
+                members.extend(
+                    named(variant.fields.as_ref())
+                        .iter()
+                        .map(|(f, ty, _)| format!("{f}: {}", decode(ty))),
+                );
+                writeln!(out, "    case {index}:")?;
+                writeln!(out, "      return {{ {} }}", members.join(", "))?;
+            }
+            writeln!(out, "    default:")?;
+            writeln!(
+                out,
+                "      throw new Error(`unknown {name} variant ${{variant}}`)"
+            )?;
+            writeln!(out, "  }}")?;
+            writeln!(out, "}}")?;
+        }
+    }
+    Ok(())
+}
+
+// Helper function to emit the event discriminators, decoder, and log parser
+fn events(out: &mut String, idl: &Idl) -> fmt::Result {
+    if idl.events.is_empty() {
+        return Ok(());
+    }
+    let union = format!("{}Event", pascal(&idl.metadata.name));
+
+    for event in &idl.events {
+        writeln!(out)?;
+        discriminated(out, &event.name, &event.discriminator)?;
+    }
+
+    writeln!(out)?;
+    writeln!(out, "export type {union} =")?;
+    for event in &idl.events {
+        writeln!(out, "  | {{ name: '{0}'; data: {0} }}", event.name)?;
+    }
+    writeln!(out)?;
+
+    writeln!(
+        out,
+        "/** Decode an event payload, or `null` if it is not one of this program's */"
+    )?;
+    writeln!(
+        out,
+        "export function decodeEvent(data: Uint8Array): {union} | null {{"
+    )?;
+    for event in &idl.events {
+        writeln!(
+            out,
+            "  if (hasDiscriminator(data, {}_DISCRIMINATOR)) {{",
+            screaming(&event.name)
+        )?;
+        writeln!(
+            out,
+            "    return {{ name: '{0}', data: read{0}(new Reader(data.subarray(8))) }}",
+            event.name
+        )?;
+        writeln!(out, "  }}")?;
+    }
+    writeln!(out, "  return null")?;
+    writeln!(out, "}}\n")?;
+
+    writeln!(
+        out,
+        "/** Every event of this program in a transaction's log messages */"
+    )?;
+    writeln!(
+        out,
+        "export function parseEvents(logs: string[]): {union}[] {{"
+    )?;
+    writeln!(out, "  const events: {union}[] = []")?;
+    writeln!(out, "  for (const payload of eventPayloads(logs)) {{")?;
+    writeln!(out, "    const event = decodeEvent(payload)")?;
+    writeln!(out, "    if (event) events.push(event)")?;
+    writeln!(out, "  }}")?;
+    writeln!(out, "  return events")?;
+    writeln!(out, "}}")
+}
+
+// Helper function to emit an instruction's account and argument types and its builder
+fn builder(out: &mut String, ix: &Instruction) -> fmt::Result {
+    let name = pascal(&ix.name);
+    let accounts = ix.flat_accounts();
+
+    discriminated(out, &name, &ix.discriminator)?;
+
+    writeln!(out, "export interface {name}Accounts {{")?;
+    for account in &accounts {
+        docs(out, "  ", &account.docs)?;
+        let optional = account.optional || account.address.is_some();
+        writeln!(
+            out,
+            "  {}{}: PublicKey",
+            camel(&account.name),
+            if optional { "?" } else { "" }
+        )?;
+    }
+    writeln!(out, "}}\n")?;
+
+    let has_args = !ix.args.is_empty();
+    if has_args {
+        writeln!(out, "export interface {name}Args {{")?;
+        for arg in &ix.args {
+            docs(out, "  ", &arg.docs)?;
+            writeln!(out, "  {}: {}", camel(&arg.name), ts_type(&arg.ty))?;
+        }
+        writeln!(out, "}}\n")?;
+    }
+
+    docs(out, "", &ix.docs)?;
+    let args_param = if has_args {
+        format!("args: {name}Args, ")
+    } else {
+        String::new()
+    };
+    writeln!(
+        out,
+        "export function {}(accounts: {name}Accounts, {args_param}programId: PublicKey = PROGRAM_ID): TransactionInstruction {{",
+        camel(&ix.name)
+    )?;
+    writeln!(out, "  const w = new Writer()")?;
+    writeln!(out, "  w.raw({}_DISCRIMINATOR)", screaming(&name))?;
+    for arg in &ix.args {
+        writeln!(
+            out,
+            "  {}",
+            encode(&arg.ty, &format!("args.{}", camel(&arg.name)))
+        )?;
+    }
+
+    writeln!(out, "  const keys: AccountMeta[] = [")?;
+    for account in &accounts {
+        let field = format!("accounts.{}", camel(&account.name));
+        let flags = format!(
+            "isSigner: {}, isWritable: {}",
+            account.signer, account.writable
+        );
+        if account.optional {
+            // Anchor reads the program id in an optional slot as `None`
+            writeln!(
+                out,
+                "    {field} ? {{ pubkey: {field}, {flags} }} : {{ pubkey: programId, isSigner: false, isWritable: false }},"
+            )?;
+        } else if let Some(address) = &account.address {
+            writeln!(
+                out,
+                "    {{ pubkey: {field} ?? new PublicKey('{address}'), {flags} }},"
+            )?;
+        } else {
+            writeln!(out, "    {{ pubkey: {field}, {flags} }},")?;
+        }
+    }
+    writeln!(out, "  ]")?;
+    writeln!(
+        out,
+        "  return new TransactionInstruction({{ programId, keys, data: w.toBuffer() }})"
+    )?;
+    writeln!(out, "}}")
+}
+
+// Helper function to emit the program's error table
+fn errors(out: &mut String, idl: &Idl) -> fmt::Result {
+    if idl.errors.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(out, "/** Custom program errors by code */")?;
+    writeln!(
+        out,
+        "export const ERRORS: {{ [code: number]: {{ name: string; msg: string }} }} = {{"
+    )?;
+    for error in &idl.errors {
+        writeln!(
+            out,
+            "  {}: {{ name: '{}', msg: {} }},",
+            error.code,
+            error.name,
+            quote(error.msg.as_deref().unwrap_or(""))
+        )?;
+    }
+    writeln!(out, "}}")
+}
+
+// Helper function to emit an 8-byte discriminator constant
+fn discriminated(out: &mut String, name: &str, discriminator: &[u8]) -> fmt::Result {
+    let bytes: Vec<String> = discriminator.iter().map(u8::to_string).collect();
+    writeln!(
+        out,
+        "export const {}_DISCRIMINATOR = [{}]",
+        screaming(name),
+        bytes.join(", ")
+    )
+}
+
+// Helper function to emit a doc comment, if there is one
+fn docs(out: &mut String, indent: &str, docs: &[String]) -> fmt::Result {
+    match docs {
+        [] => Ok(()),
+        [line] => writeln!(out, "{indent}/** {} */", line.trim()),
+        lines => {
+            writeln!(out, "{indent}/**")?;
+            for line in lines {
+                writeln!(out, "{indent} * {}", line.trim())?;
+            }
+            writeln!(out, "{indent} */")
+        }
+    }
+}
+
+// Helper function to list fields as (camelCase name, type, docs); tuple fields become `field0`...
+fn named(fields: Option<&Fields>) -> Vec<(String, &Type, &[String])> {
+    match fields {
+        None => Vec::new(),
+        Some(Fields::Named(fields)) => fields
+            .iter()
+            .map(|f| (camel(&f.name), &f.ty, f.docs.as_slice()))
+            .collect(),
+        Some(Fields::Tuple(types)) => types
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| (format!("field{i}"), ty, &[] as &[String]))
+            .collect(),
+    }
+}
+
+fn ts_type(ty: &Type) -> String {
+    match ty {
+        Type::Primitive(p) => match p {
+            Primitive::Bool => "boolean".into(),
+            Primitive::U8
+            | Primitive::I8
+            | Primitive::U16
+            | Primitive::I16
+            | Primitive::U32
+            | Primitive::I32 => "number".into(),
+            Primitive::U64 | Primitive::I64 | Primitive::U128 | Primitive::I128 => "BN".into(),
+            Primitive::Bytes => "Uint8Array".into(),
+            Primitive::String => "string".into(),
+            Primitive::Pubkey => "PublicKey".into(),
+        },
+        Type::Complex(c) => match c {
+            ComplexType::Vec(inner) | ComplexType::Array(inner, _) => {
+                format!("{}[]", wrap(ts_type(inner)))
+            }
+            ComplexType::Option(inner) => format!("{} | null", ts_type(inner)),
+            ComplexType::Defined { name } => name.clone(),
+        },
+    }
+}
+
+// Helper function to parenthesize union types before appending `[]`
+fn wrap(ty: String) -> String {
+    if ty.contains('|') {
+        format!("({ty})")
+    } else {
+        ty
+    }
+}
+
+// Helper function to build the statement writing `value` of type `ty` to `w`
+fn encode(ty: &Type, value: &str) -> String {
+    match ty {
+        Type::Primitive(p) => format!("w.{}({value})", primitive(*p)),
+        Type::Complex(ComplexType::Vec(inner)) => {
+            format!("w.vec({value}, (w, x) => {})", encode(inner, "x"))
+        }
+        Type::Complex(ComplexType::Array(inner, len)) => {
+            format!("w.array({value}, {len}, (w, x) => {})", encode(inner, "x"))
+        }
+        Type::Complex(ComplexType::Option(inner)) => {
+            format!("w.option({value}, (w, x) => {})", encode(inner, "x"))
+        }
+        Type::Complex(ComplexType::Defined { name }) => format!("write{name}(w, {value})"),
+    }
+}
+
+// Helper function to build the expression reading a value of type `ty` from `r`
+fn decode(ty: &Type) -> String {
+    match ty {
+        Type::Primitive(p) => format!("r.{}()", primitive(*p)),
+        Type::Complex(ComplexType::Vec(inner)) => format!("r.vec((r) => {})", decode(inner)),
+        Type::Complex(ComplexType::Array(inner, len)) => {
+            format!("r.array({len}, (r) => {})", decode(inner))
+        }
+        Type::Complex(ComplexType::Option(inner)) => format!("r.option((r) => {})", decode(inner)),
+        Type::Complex(ComplexType::Defined { name }) => format!("read{name}(r)"),
+    }
+}
+
+// Helper function to name the reader/writer method for a primitive
+fn primitive(p: Primitive) -> &'static str {
+    match p {
+        Primitive::Bool => "bool",
+        Primitive::U8 => "u8",
+        Primitive::I8 => "i8",
+        Primitive::U16 => "u16",
+        Primitive::I16 => "i16",
+        Primitive::U32 => "u32",
+        Primitive::I32 => "i32",
+        Primitive::U64 => "u64",
+        Primitive::I64 => "i64",
+        Primitive::U128 => "u128",
+        Primitive::I128 => "i128",
+        Primitive::Bytes => "bytesWithLength",
+        Primitive::String => "string",
+        Primitive::Pubkey => "pubkey",
+    }
+}
+
+// Helper function to quote free text as a single-quoted TypeScript string
+fn quote(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('\'', "\\'")
+        .replace('\n', "\\n");
+    format!("'{escaped}'")
+}
@@ -0,0 +1,277 @@
+//! Transaction layer: size the compute budget by simulation, price it against recent fees, send, and confirm.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use agentmarket_sdk::{Instruction, Pubkey, RpcClient};
+use anyhow::{bail, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Value};
+use solana_hash::Hash;
+use solana_keypair::Keypair;
+use solana_signature::Signature;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+use tokio::time::Instant;
+
+use crate::jobs::{Job, Priority};
+
+/// Compute budget program, which sets a transaction's unit limit and price
+pub const COMPUTE_BUDGET_PROGRAM_ID: Pubkey =
+    anchor_lang::pubkey!("ComputeBudget111111111111111111111111111111");
+
+/// Most compute units a transaction may request
+const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+
+/// Units the two compute budget instructions themselves consume
+const COMPUTE_BUDGET_UNITS: u32 = 300;
+
+/// Base fee per signature
+const SIGNATURE_FEE: u64 = 5_000;
+
+/// Window the spending budget applies to
+const BUDGET_WINDOW: Duration = Duration::from_secs(3_600);
+
+/// How often signature statuses are polled while confirming
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How the crank bids for block space and how much it may spend
+pub struct FeePolicy {
+    /// Percentile of recent prioritization fees on the job's writable accounts to bid
+    pub percentile: u8,
+    /// Ceiling on the compute unit price, in micro-lamports
+    pub max_unit_price: u64,
+    /// Compute units requested on top of the simulated usage, in percent
+    pub compute_margin: u32,
+    /// Lamports the crank may spend in any rolling hour, rent it fronts included
+    pub hourly_budget: u64,
+}
+
+/// What happened to a job
+pub enum Outcome {
+    /// Landed and confirmed; `cost` is what the payer's balance dropped by
+    Executed { signature: Signature, cost: u64 },
+    /// Would have been sent with this budget, but this is a dry run
+    Simulated { units: u32, unit_price: u64 },
+    /// Not sent: simulation failed (usually someone else got there first) or the budget is spent
+    Skipped(String),
+}
+
+pub struct Executor {
+    pub rpc: RpcClient,
+    pub payer: Keypair,
+    pub policy: FeePolicy,
+    pub confirm_timeout: Duration,
+    pub dry_run: bool,
+    /// Recent spending, oldest first
+    spent: VecDeque<(Instant, u64)>,
+}
+
+impl Executor {
+    pub fn new(
+        rpc: RpcClient,
+        payer: Keypair,
+        policy: FeePolicy,
+        confirm_timeout: Duration,
+        dry_run: bool,
+    ) -> Self {
+        Self {
+            rpc,
+            payer,
+            policy,
+            confirm_timeout,
+            dry_run,
+            spent: VecDeque::new(),
+        }
+    }
+
+    /// Lamports spent within the budget window
+    pub fn spent_last_hour(&mut self) -> u64 {
+        while let Some((at, _)) = self.spent.front() {
+            if at.elapsed() < BUDGET_WINDOW {
+                break;
+            }
+            self.spent.pop_front();
+        }
+        self.spent.iter().map(|(_, lamports)| lamports).sum()
+    }
+
+    /// Simulate, budget, send, and confirm one job
+    ///
+    /// Errors mean the transaction was sent and did not land.
+    pub async fn execute(&mut self, job: &Job) -> Result<Outcome> {
+        let priority = job.kind.priority();
+        let spent = self.spent_last_hour();
+        // Housekeeping only runs while at least half of the budget is left
+        if priority == Priority::Low && spent > self.policy.hourly_budget / 2 {
+            return Ok(Outcome::Skipped(
+                "deferred: over half the fee budget spent".into(),
+            ));
+        }
+
+        let blockhash = self.rpc.get_latest_blockhash().await?;
+        let consumed = match self.simulate(&job.instruction, blockhash).await? {
+            Ok(consumed) => consumed,
+            Err(reason) => return Ok(Outcome::Skipped(format!("simulation failed: {reason}"))),
+        };
+        let units = (consumed * (100 + self.policy.compute_margin as u64) / 100)
+            .min(MAX_COMPUTE_UNITS as u64) as u32
+            + COMPUTE_BUDGET_UNITS;
+        let unit_price = match priority {
+            Priority::Low => 0,
+            Priority::Normal | Priority::High => self
+                .recent_unit_price(&job.instruction)
+                .await?
+                .min(self.policy.max_unit_price),
+        };
+
+        let estimate = SIGNATURE_FEE + (unit_price * units as u64).div_ceil(1_000_000);
+        if spent + estimate > self.policy.hourly_budget {
+            return Ok(Outcome::Skipped("hourly fee budget exhausted".into()));
+        }
+        if self.dry_run {
+            return Ok(Outcome::Simulated { units, unit_price });
+        }
+
+        let mut instructions = vec![set_compute_unit_limit(units)];
+        if unit_price > 0 {
+            instructions.push(set_compute_unit_price(unit_price));
+        }
+        instructions.push(job.instruction.clone());
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&self.payer.pubkey()),
+            &[&self.payer],
+            blockhash,
+        );
+
+        let before = self.rpc.get_balance(&self.payer.pubkey()).await?;
+        let signature = self.rpc.send_transaction(&transaction).await?;
+        self.confirm(&signature).await?;
+        let after = self.rpc.get_balance(&self.payer.pubkey()).await?;
+
+        let cost = before.saturating_sub(after);
+        self.spent.push_back((Instant::now(), cost));
+        Ok(Outcome::Executed { signature, cost })
+    }
+
+    // Helper function to simulate with the largest budget; Ok(Err) carries why the job cannot run
+    async fn simulate(
+        &self,
+        instruction: &Instruction,
+        blockhash: Hash,
+    ) -> Result<Result<u64, String>> {
+        let transaction = Transaction::new_signed_with_payer(
+            &[
+                set_compute_unit_limit(MAX_COMPUTE_UNITS),
+                instruction.clone(),
+            ],
+            Some(&self.payer.pubkey()),
+            &[&self.payer],
+            blockhash,
+        );
+        let wire = bincode::serialize(&transaction)?;
+        let result = self
+            .rpc
+            .call(
+                "simulateTransaction",
+                json!([
+                    BASE64.encode(wire),
+                    {"encoding": "base64", "commitment": "confirmed", "sigVerify": false}
+                ]),
+            )
+            .await?;
+        let value = result.get("value").unwrap_or(&Value::Null);
+
+        if let Some(err) = value.get("err").filter(|e| !e.is_null()) {
+            // The program's own error message says more than the instruction error code
+            let logged = value
+                .get("logs")
+                .and_then(Value::as_array)
+                .and_then(|logs| {
+                    logs.iter()
+                        .filter_map(Value::as_str)
+                        .find(|line| line.contains("Error Message:"))
+                });
+            return Ok(Err(logged.map_or_else(|| err.to_string(), str::to_string)));
+        }
+        Ok(Ok(value
+            .get("unitsConsumed")
+            .and_then(Value::as_u64)
+            .unwrap_or(MAX_COMPUTE_UNITS as u64)))
+    }
+
+    // Helper function to pick the configured percentile of recent fees paid to write the same accounts
+    async fn recent_unit_price(&self, instruction: &Instruction) -> Result<u64> {
+        let writable: Vec<String> = instruction
+            .accounts
+            .iter()
+            .filter(|meta| meta.is_writable)
+            .map(|meta| meta.pubkey.to_string())
+            .collect();
+        let result = self
+            .rpc
+            .call("getRecentPrioritizationFees", json!([writable]))
+            .await?;
+
+        let mut fees: Vec<u64> = result
+            .as_array()
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter_map(|entry| entry.get("prioritizationFee")?.as_u64())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if fees.is_empty() {
+            return Ok(0);
+        }
+        fees.sort_unstable();
+        Ok(fees[(fees.len() - 1) * self.policy.percentile.min(100) as usize / 100])
+    }
+
+    // Helper function to poll until `signature` is confirmed or fails
+    async fn confirm(&self, signature: &Signature) -> Result<()> {
+        let deadline = Instant::now() + self.confirm_timeout;
+
+        loop {
+            let result = self
+                .rpc
+                .call("getSignatureStatuses", json!([[signature.to_string()]]))
+                .await?;
+            let status = result.pointer("/value/0").unwrap_or(&Value::Null);
+
+            if !status.is_null() {
+                if let Some(err) = status.get("err").filter(|e| !e.is_null()) {
+                    bail!("transaction {signature} failed: {err}");
+                }
+                let level = status.get("confirmationStatus").and_then(Value::as_str);
+                if matches!(level, Some("confirmed" | "finalized")) {
+                    return Ok(());
+                }
+            }
+
+            if Instant::now() >= deadline {
+                bail!("transaction {signature} not confirmed in time");
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+fn set_compute_unit_limit(units: u32) -> Instruction {
+    Instruction {
+        program_id: COMPUTE_BUDGET_PROGRAM_ID,
+        accounts: Vec::new(),
+        data: [&[2u8][..], &units.to_le_bytes()].concat(),
+    }
+}
+
+fn set_compute_unit_price(micro_lamports: u64) -> Instruction {
+    Instruction {
+        program_id: COMPUTE_BUDGET_PROGRAM_ID,
+        accounts: Vec::new(),
+        data: [&[3u8][..], &micro_lamports.to_le_bytes()].concat(),
+    }
+}
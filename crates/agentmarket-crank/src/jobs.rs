@@ -0,0 +1,776 @@
+//! Job discovery: read program state and list every permissionless instruction that is due.
+
+use std::cmp::Reverse;
+
+use agent_workflow::{Workflow, WorkflowStatus};
+use agentmarket_sdk::accounts::{
+    decode, AgentIndex, AgentProfile, CoCreatorSplit, DisputePanel, RegistryConfig, RequestStatus,
+    RoyaltyConfig, ServiceRequest,
+};
+use agentmarket_sdk::instructions::{escrow, registry};
+use agentmarket_sdk::{pda, Instruction, Pubkey, RpcClient, ESCROW_PROGRAM_ID};
+use anchor_lang::prelude::Rent;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, Space, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anyhow::Result;
+use bulk_payout::PayoutBatch;
+use clap::ValueEnum;
+use fractional_vault::FractionalVault;
+use leaderboard::{AgentEpochStats, Epoch, LeaderboardConfig};
+use marketplace_escrow::REVIEW_WINDOW;
+use promo_raffle::{RaffleConfig, RaffleRound, RoundStatus};
+use protocol_analytics::{AgentActivity, AnalyticsConfig};
+use sla_oracle::{OracleConfig, SlaRound};
+use tracing::warn;
+
+use crate::payouts::PayoutBatchFile;
+
+/// `getMultipleAccounts` accepts at most this many addresses
+const MAX_BATCH: usize = 100;
+
+/// Reason recorded on workflows rolled back for a timed-out step
+const TIMEOUT_REASON: &str = "step timed out";
+
+/// Permissionless instructions the crank drives
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum JobKind {
+    /// `agent_workflow::rollback_workflow` once the current step has timed out
+    WorkflowTimeout,
+    /// `bulk_payout::claim` for unclaimed leaves of the `--payout-batch` files
+    PayoutClaim,
+    /// `marketplace_escrow::expire_request` once a request's deadline passes without a result
+    ExpireRequest,
+    /// `marketplace_escrow::auto_release` once a result has gone unreviewed for the review window
+    AutoRelease,
+    /// `marketplace_escrow::settle_panel` once a dispute's panel has voted or its window has ended
+    SettlePanel,
+    /// `leaderboard::start_epoch` once the current epoch has ended
+    StartEpoch,
+    /// `leaderboard::submit_score` for agents with volume in an ended epoch
+    SubmitScore,
+    /// `leaderboard::finalize_epoch` once the submission window has closed
+    FinalizeEpoch,
+    /// `promo_raffle::request_draw` once the open round has ended
+    RequestDraw,
+    /// `sla_oracle::finalize_round` for closed rounds that reached quorum
+    FinalizeSlaRound,
+    /// `fractional_vault::sync_revenue` when a vault holds unaccounted revenue
+    VaultSync,
+    /// `protocol_analytics::close_agent_activity` for markers from finished epochs
+    CloseActivity,
+    /// `agent_registry::mark_stale` for active agents past the heartbeat window
+    MarkStale,
+}
+
+impl JobKind {
+    /// Label for logs and metrics
+    pub fn name(self) -> &'static str {
+        match self {
+            JobKind::WorkflowTimeout => "workflow_timeout",
+            JobKind::PayoutClaim => "payout_claim",
+            JobKind::ExpireRequest => "expire_request",
+            JobKind::AutoRelease => "auto_release",
+            JobKind::SettlePanel => "settle_panel",
+            JobKind::StartEpoch => "start_epoch",
+            JobKind::SubmitScore => "submit_score",
+            JobKind::FinalizeEpoch => "finalize_epoch",
+            JobKind::RequestDraw => "request_draw",
+            JobKind::FinalizeSlaRound => "finalize_sla_round",
+            JobKind::VaultSync => "vault_sync",
+            JobKind::CloseActivity => "close_activity",
+            JobKind::MarkStale => "mark_stale",
+        }
+    }
+
+    /// Jobs that return funds to users come first, housekeeping last
+    pub fn priority(self) -> Priority {
+        match self {
+            JobKind::WorkflowTimeout | JobKind::PayoutClaim | JobKind::ExpireRequest => {
+                Priority::High
+            }
+            JobKind::AutoRelease | JobKind::SettlePanel => Priority::Normal,
+            JobKind::StartEpoch
+            | JobKind::SubmitScore
+            | JobKind::FinalizeEpoch
+            | JobKind::RequestDraw
+            | JobKind::FinalizeSlaRound
+            | JobKind::MarkStale => Priority::Normal,
+            JobKind::VaultSync | JobKind::CloseActivity => Priority::Low,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Low,
+    Normal,
+    High,
+}
+
+/// One instruction that is due
+pub struct Job {
+    pub kind: JobKind,
+    /// Account the instruction acts on, for logs
+    pub target: Pubkey,
+    /// Unix time the instruction became executable
+    pub due_since: i64,
+    pub instruction: Instruction,
+}
+
+/// One discovery pass over the programs' accounts
+pub struct Scan<'a> {
+    pub rpc: &'a RpcClient,
+    /// Signs and pays for every job
+    pub payer: Pubkey,
+    /// Cluster time from the clock sysvar
+    pub now: i64,
+    /// Job kinds to look for; empty means all
+    pub kinds: &'a [JobKind],
+    pub payout_batches: &'a [PayoutBatchFile],
+    /// Smallest unaccounted vault revenue worth a sync, in lamports
+    pub min_vault_sync: u64,
+}
+
+impl Scan<'_> {
+    /// Every due job, highest priority first and most overdue first within a priority
+    pub async fn discover(&self) -> Vec<Job> {
+        let results = [
+            ("agent-workflow", self.workflow_timeouts().await),
+            ("bulk-payout", self.payout_claims().await),
+            ("marketplace-escrow", self.service_requests().await),
+            ("leaderboard", self.leaderboard().await),
+            ("promo-raffle", self.raffle_draw().await),
+            ("sla-oracle", self.sla_rounds().await),
+            ("fractional-vault", self.vault_syncs().await),
+            ("protocol-analytics", self.closed_activity().await),
+            ("agent-registry", self.stale_agents().await),
+        ];
+
+        let mut jobs = Vec::new();
+        for (program, result) in results {
+            match result {
+                Ok(found) => jobs.extend(found.into_iter().filter(|job| self.wants(job.kind))),
+                Err(e) => warn!(program, "discovery failed: {e:#}"),
+            }
+        }
+        jobs.sort_by_key(|job| (Reverse(job.kind.priority()), job.due_since));
+        jobs
+    }
+
+    fn wants(&self, kind: JobKind) -> bool {
+        self.kinds.is_empty() || self.kinds.contains(&kind)
+    }
+
+    async fn workflow_timeouts(&self) -> Result<Vec<Job>> {
+        if !self.wants(JobKind::WorkflowTimeout) {
+            return Ok(Vec::new());
+        }
+
+        let guardian_config = pda::guardian_config().0;
+        let mut jobs = Vec::new();
+        for (address, workflow) in self.rpc.fetch_all::<Workflow>(&agent_workflow::ID).await? {
+            if workflow.status != WorkflowStatus::Active {
+                continue;
+            }
+            let Some(step) = workflow.steps.get(workflow.current_step as usize) else {
+                continue;
+            };
+            let due_since = step.started_at.saturating_add(workflow.step_timeout);
+            if self.now < due_since {
+                continue;
+            }
+
+            jobs.push(Job {
+                kind: JobKind::WorkflowTimeout,
+                target: address,
+                due_since,
+                instruction: build(
+                    agent_workflow::ID,
+                    agent_workflow::accounts::RollbackWorkflow {
+                        workflow: address,
+                        user: workflow.user,
+                        caller: self.payer,
+                        guardian_config,
+                    },
+                    agent_workflow::instruction::RollbackWorkflow {
+                        reason: TIMEOUT_REASON.to_string(),
+                    },
+                ),
+            });
+        }
+        Ok(jobs)
+    }
+
+    async fn payout_claims(&self) -> Result<Vec<Job>> {
+        if !self.wants(JobKind::PayoutClaim) {
+            return Ok(Vec::new());
+        }
+
+        let guardian_config = pda::guardian_config().0;
+        let mut jobs = Vec::new();
+        for file in self.payout_batches {
+            // A reclaimed batch is closed, and nothing is left to pay
+            let Some(batch) = self.rpc.fetch_optional::<PayoutBatch>(&file.batch).await? else {
+                continue;
+            };
+            if batch.merkle_root != file.root() {
+                warn!(batch = %file.batch, "leaf file does not match the batch's merkle root");
+                continue;
+            }
+            let payout_vault =
+                program_address(&[b"payout_vault", file.batch.as_ref()], &bulk_payout::ID);
+
+            let receipts: Vec<Pubkey> = (0..file.leaves.len() as u32)
+                .map(|index| {
+                    program_address(
+                        &[b"claim", file.batch.as_ref(), &index.to_le_bytes()],
+                        &bulk_payout::ID,
+                    )
+                })
+                .collect();
+            let mut index = 0;
+            for chunk in receipts.chunks(MAX_BATCH) {
+                let existing = self.rpc.get_multiple_account_data(chunk).await?;
+                for (claim_receipt, data) in chunk.iter().zip(existing) {
+                    let leaf = &file.leaves[index];
+                    if data.is_none() {
+                        jobs.push(Job {
+                            kind: JobKind::PayoutClaim,
+                            target: leaf.recipient,
+                            due_since: batch.created_at,
+                            instruction: build(
+                                bulk_payout::ID,
+                                bulk_payout::accounts::Claim {
+                                    batch: file.batch,
+                                    payout_vault,
+                                    claim_receipt: *claim_receipt,
+                                    recipient: leaf.recipient,
+                                    payer: self.payer,
+                                    system_program: system_program::ID,
+                                    guardian_config,
+                                },
+                                bulk_payout::instruction::Claim {
+                                    index: index as u32,
+                                    amount: leaf.amount,
+                                    proof: file.proof(index),
+                                },
+                            ),
+                        });
+                    }
+                    index += 1;
+                }
+            }
+        }
+        Ok(jobs)
+    }
+
+    async fn service_requests(&self) -> Result<Vec<Job>> {
+        if ![
+            JobKind::ExpireRequest,
+            JobKind::AutoRelease,
+            JobKind::SettlePanel,
+        ]
+        .into_iter()
+        .any(|kind| self.wants(kind))
+        {
+            return Ok(Vec::new());
+        }
+
+        let mut jobs = Vec::new();
+        // Requests due a payout, with the panel's payer for those settled by vote
+        let mut payouts = Vec::new();
+        for (address, request) in self
+            .rpc
+            .fetch_all::<ServiceRequest>(&ESCROW_PROGRAM_ID)
+            .await?
+        {
+            match request.status {
+                RequestStatus::Pending | RequestStatus::InProgress => {
+                    let Some(deadline) = request.deadline else {
+                        continue;
+                    };
+                    if self.now <= deadline {
+                        continue;
+                    }
+
+                    let instruction = match request.payment_mint {
+                        Some(mint) => escrow::expire_token_request(
+                            self.payer,
+                            address,
+                            request.agent_id,
+                            request.user,
+                            mint,
+                        ),
+                        None => escrow::expire_request(
+                            self.payer,
+                            address,
+                            request.agent_id,
+                            request.user,
+                        ),
+                    };
+                    jobs.push(Job {
+                        kind: JobKind::ExpireRequest,
+                        target: address,
+                        due_since: deadline,
+                        instruction,
+                    });
+                }
+                RequestStatus::Completed => {
+                    let Some(completed_at) = request.completed_at else {
+                        continue;
+                    };
+                    let due_since = completed_at.saturating_add(REVIEW_WINDOW);
+                    if self.now >= due_since && self.wants(JobKind::AutoRelease) {
+                        payouts.push((address, request, due_since, None));
+                    }
+                }
+                RequestStatus::Disputed if self.wants(JobKind::SettlePanel) => {
+                    let Some(panel) = self
+                        .rpc
+                        .fetch_optional::<DisputePanel>(&pda::dispute_panel(&address).0)
+                        .await?
+                    else {
+                        continue;
+                    };
+                    if self.now >= panel.voting_ends_at || panel.votes.iter().all(Option::is_some) {
+                        payouts.push((address, request, panel.voting_ends_at, Some(panel.payer)));
+                    }
+                }
+                _ => {}
+            }
+        }
+        if payouts.is_empty() {
+            return Ok(jobs);
+        }
+
+        let Some(royalty) = self
+            .rpc
+            .fetch_optional::<RoyaltyConfig>(&pda::royalty_config().0)
+            .await?
+        else {
+            return Ok(jobs);
+        };
+        for (address, request, due_since, panel_payer) in payouts {
+            let agent_id = request.agent_id;
+            // Requests against deregistered agents can't be paid out
+            let Some(profile) = self.rpc.fetch_optional::<AgentProfile>(&agent_id).await? else {
+                continue;
+            };
+            let co_creators: Vec<Pubkey> = self
+                .rpc
+                .fetch_optional::<CoCreatorSplit>(&pda::co_creator_split(&agent_id).0)
+                .await?
+                .map(|split| split.co_creators.iter().map(|c| c.key).collect())
+                .unwrap_or_default();
+
+            let user = request.user;
+            let (payout, platform, treasury) = (
+                profile.payout_wallet,
+                royalty.platform_wallet,
+                royalty.treasury_wallet,
+            );
+            let (kind, instruction) = match (panel_payer, request.payment_mint) {
+                (None, Some(mint)) => (
+                    JobKind::AutoRelease,
+                    escrow::auto_release_token(
+                        self.payer, address, agent_id, user, mint, payout, platform, treasury,
+                    ),
+                ),
+                (None, None) => (
+                    JobKind::AutoRelease,
+                    escrow::auto_release(
+                        self.payer, address, agent_id, user, payout, platform, treasury,
+                    ),
+                ),
+                (Some(panel_payer), Some(mint)) => (
+                    JobKind::SettlePanel,
+                    escrow::settle_token_panel(
+                        self.payer,
+                        address,
+                        agent_id,
+                        user,
+                        panel_payer,
+                        mint,
+                        payout,
+                        platform,
+                        treasury,
+                    ),
+                ),
+                (Some(panel_payer), None) => (
+                    JobKind::SettlePanel,
+                    escrow::settle_panel(
+                        self.payer,
+                        address,
+                        agent_id,
+                        user,
+                        panel_payer,
+                        payout,
+                        platform,
+                        treasury,
+                    ),
+                ),
+            };
+            // Token payments list the co-creators' associated token accounts
+            let co_creators: Vec<Pubkey> = match request.payment_mint {
+                Some(mint) => co_creators
+                    .iter()
+                    .map(|co_creator| get_associated_token_address(co_creator, &mint))
+                    .collect(),
+                None => co_creators,
+            };
+            jobs.push(Job {
+                kind,
+                target: address,
+                due_since,
+                instruction: escrow::with_co_creators(instruction, &co_creators),
+            });
+        }
+        Ok(jobs)
+    }
+
+    async fn leaderboard(&self) -> Result<Vec<Job>> {
+        if ![
+            JobKind::StartEpoch,
+            JobKind::SubmitScore,
+            JobKind::FinalizeEpoch,
+        ]
+        .into_iter()
+        .any(|kind| self.wants(kind))
+        {
+            return Ok(Vec::new());
+        }
+
+        let config_address = program_address(&[b"leaderboard_config"], &leaderboard::ID);
+        let Some(config) = self
+            .rpc
+            .fetch_optional::<LeaderboardConfig>(&config_address)
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+
+        let mut jobs = Vec::new();
+        if self.now >= config.current_epoch_end {
+            let epoch = program_address(
+                &[b"epoch", &(config.current_epoch + 1).to_le_bytes()],
+                &leaderboard::ID,
+            );
+            jobs.push(Job {
+                kind: JobKind::StartEpoch,
+                target: epoch,
+                due_since: config.current_epoch_end,
+                instruction: build(
+                    leaderboard::ID,
+                    leaderboard::accounts::StartEpoch {
+                        config: config_address,
+                        epoch,
+                        payer: self.payer,
+                        system_program: system_program::ID,
+                    },
+                    leaderboard::instruction::StartEpoch {},
+                ),
+            });
+        }
+
+        for (address, epoch) in self.rpc.fetch_all::<Epoch>(&leaderboard::ID).await? {
+            if epoch.is_finalized || self.now < epoch.end_ts {
+                continue;
+            }
+
+            // Zero-volume agents score zero and can never place, so they are left alone
+            let unsubmitted: Vec<Pubkey> = self
+                .rpc
+                .fetch_all_matching::<AgentEpochStats>(&leaderboard::ID, &[(8, address.as_ref())])
+                .await?
+                .into_iter()
+                .filter(|(_, stats)| !stats.is_submitted && stats.volume > 0)
+                .map(|(stats_address, _)| stats_address)
+                .collect();
+
+            // Finalizing locks out late scores, so wait until every score is in
+            if unsubmitted.is_empty() && self.now >= epoch.finalize_after {
+                jobs.push(Job {
+                    kind: JobKind::FinalizeEpoch,
+                    target: address,
+                    due_since: epoch.finalize_after,
+                    instruction: build(
+                        leaderboard::ID,
+                        leaderboard::accounts::FinalizeEpoch { epoch: address },
+                        leaderboard::instruction::FinalizeEpoch {},
+                    ),
+                });
+            }
+            for agent_stats in unsubmitted {
+                jobs.push(Job {
+                    kind: JobKind::SubmitScore,
+                    target: agent_stats,
+                    due_since: epoch.end_ts,
+                    instruction: build(
+                        leaderboard::ID,
+                        leaderboard::accounts::SubmitScore {
+                            epoch: address,
+                            agent_stats,
+                        },
+                        leaderboard::instruction::SubmitScore {},
+                    ),
+                });
+            }
+        }
+        Ok(jobs)
+    }
+
+    async fn raffle_draw(&self) -> Result<Vec<Job>> {
+        if !self.wants(JobKind::RequestDraw) {
+            return Ok(Vec::new());
+        }
+
+        let raffle_config = program_address(&[b"raffle_config"], &promo_raffle::ID);
+        let Some(config) = self
+            .rpc
+            .fetch_optional::<RaffleConfig>(&raffle_config)
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+        let round = raffle_address(config.current_round);
+        let Some(open) = self.rpc.fetch_optional::<RaffleRound>(&round).await? else {
+            return Ok(Vec::new());
+        };
+        if open.status != RoundStatus::Open || self.now < open.ends_at {
+            return Ok(Vec::new());
+        }
+
+        Ok(vec![Job {
+            kind: JobKind::RequestDraw,
+            target: round,
+            due_since: open.ends_at,
+            instruction: build(
+                promo_raffle::ID,
+                promo_raffle::accounts::RequestDraw {
+                    raffle_config,
+                    round,
+                    next_round: raffle_address(config.current_round + 1),
+                    marketing_vault: program_address(&[b"marketing_vault"], &promo_raffle::ID),
+                    payer: self.payer,
+                    system_program: system_program::ID,
+                },
+                promo_raffle::instruction::RequestDraw {},
+            ),
+        }])
+    }
+
+    async fn sla_rounds(&self) -> Result<Vec<Job>> {
+        if !self.wants(JobKind::FinalizeSlaRound) {
+            return Ok(Vec::new());
+        }
+
+        let oracle_config = program_address(&[b"sla_oracle_config"], &sla_oracle::ID);
+        let Some(config) = self
+            .rpc
+            .fetch_optional::<OracleConfig>(&oracle_config)
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+        let params = &config.params;
+        let current = sla_oracle::current_round(self.now, params.round_duration);
+
+        let mut jobs = Vec::new();
+        for (address, round) in self.rpc.fetch_all::<SlaRound>(&sla_oracle::ID).await? {
+            // Rounds short of quorum can never be finalized
+            if round.is_finalized
+                || round.round >= current
+                || round.observations.len() < params.min_observations as usize
+            {
+                continue;
+            }
+
+            jobs.push(Job {
+                kind: JobKind::FinalizeSlaRound,
+                target: address,
+                due_since: (round.round as i64 + 1) * params.round_duration,
+                instruction: build(
+                    sla_oracle::ID,
+                    sla_oracle::accounts::FinalizeRound {
+                        oracle_config,
+                        sla_round: address,
+                        agent_sla: program_address(
+                            &[b"agent_sla", round.agent_id.as_ref()],
+                            &sla_oracle::ID,
+                        ),
+                        payer: self.payer,
+                        system_program: system_program::ID,
+                    },
+                    sla_oracle::instruction::FinalizeRound {},
+                ),
+            });
+        }
+        Ok(jobs)
+    }
+
+    async fn vault_syncs(&self) -> Result<Vec<Job>> {
+        if !self.wants(JobKind::VaultSync) {
+            return Ok(Vec::new());
+        }
+
+        let rent_reserve = Rent::default().minimum_balance(8 + FractionalVault::INIT_SPACE);
+        let mut jobs = Vec::new();
+        for (address, vault) in self
+            .rpc
+            .fetch_all::<FractionalVault>(&fractional_vault::ID)
+            .await?
+        {
+            // Revenue synced with nothing staked only piles up as undistributed
+            if vault.is_redeemed || vault.total_staked == 0 {
+                continue;
+            }
+            let balance = self.rpc.get_balance(&address).await?;
+            let unaccounted = balance
+                .saturating_sub(rent_reserve)
+                .saturating_sub(vault.accounted_lamports);
+            if unaccounted < self.min_vault_sync.max(1) {
+                continue;
+            }
+
+            jobs.push(Job {
+                kind: JobKind::VaultSync,
+                target: address,
+                due_since: self.now,
+                instruction: build(
+                    fractional_vault::ID,
+                    fractional_vault::accounts::SyncRevenue { vault: address },
+                    fractional_vault::instruction::SyncRevenue {},
+                ),
+            });
+        }
+        Ok(jobs)
+    }
+
+    async fn closed_activity(&self) -> Result<Vec<Job>> {
+        if !self.wants(JobKind::CloseActivity) {
+            return Ok(Vec::new());
+        }
+
+        let analytics_config = program_address(&[b"analytics_config"], &protocol_analytics::ID);
+        let Some(config) = self
+            .rpc
+            .fetch_optional::<AnalyticsConfig>(&analytics_config)
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+        let current = config.epoch_at(self.now);
+
+        let mut jobs = Vec::new();
+        for (address, activity) in self
+            .rpc
+            .fetch_all::<AgentActivity>(&protocol_analytics::ID)
+            .await?
+        {
+            if activity.epoch >= current {
+                continue;
+            }
+
+            jobs.push(Job {
+                kind: JobKind::CloseActivity,
+                target: address,
+                due_since: config.epoch_start(activity.epoch + 1),
+                instruction: build(
+                    protocol_analytics::ID,
+                    protocol_analytics::accounts::CloseAgentActivity {
+                        analytics_config,
+                        agent_activity: address,
+                        payer: activity.payer,
+                    },
+                    protocol_analytics::instruction::CloseAgentActivity {},
+                ),
+            });
+        }
+        Ok(jobs)
+    }
+
+    async fn stale_agents(&self) -> Result<Vec<Job>> {
+        if !self.wants(JobKind::MarkStale) {
+            return Ok(Vec::new());
+        }
+
+        let Some(config) = self
+            .rpc
+            .fetch_optional::<RegistryConfig>(&pda::registry_config().0)
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+        if config.heartbeat_window <= 0 {
+            return Ok(Vec::new());
+        }
+
+        // Walk the agent index rather than scanning every registry account
+        let entries: Vec<Pubkey> = (0..config.agent_count)
+            .map(|index| pda::agent_index(index).0)
+            .collect();
+        let mut agents = Vec::with_capacity(entries.len());
+        for chunk in entries.chunks(MAX_BATCH) {
+            for data in self
+                .rpc
+                .get_multiple_account_data(chunk)
+                .await?
+                .into_iter()
+                .flatten()
+            {
+                agents.push(decode::<AgentIndex>(&data)?.agent_id);
+            }
+        }
+
+        let mut profiles = Vec::with_capacity(agents.len());
+        for chunk in agents.chunks(MAX_BATCH) {
+            let existing = self.rpc.get_multiple_account_data(chunk).await?;
+            for (address, data) in chunk.iter().zip(existing) {
+                // Deregistered agents keep their entry but not their profile
+                if let Some(data) = data {
+                    profiles.push((*address, decode::<AgentProfile>(&data)?));
+                }
+            }
+        }
+
+        let mut jobs = Vec::new();
+        for (address, profile) in profiles {
+            let quiet_since = profile.last_seen_at.max(profile.paused_until.unwrap_or(0));
+            let due_since = quiet_since.saturating_add(config.heartbeat_window);
+            if !profile.is_active || self.now <= due_since {
+                continue;
+            }
+
+            jobs.push(Job {
+                kind: JobKind::MarkStale,
+                target: address,
+                due_since,
+                instruction: registry::mark_stale(address),
+            });
+        }
+        Ok(jobs)
+    }
+}
+
+// Helper function to assemble an Anchor instruction from its accounts and args
+fn build(
+    program_id: Pubkey,
+    accounts: impl ToAccountMetas,
+    data: impl InstructionData,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+// Helper function to derive a PDA without its bump
+fn program_address(seeds: &[&[u8]], program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(seeds, program_id).0
+}
+
+fn raffle_address(round: u64) -> Pubkey {
+    program_address(&[b"raffle_round", &round.to_le_bytes()], &promo_raffle::ID)
+}
@@ -0,0 +1,263 @@
+//! AgentMarket crank: execute the programs' permissionless time-based instructions.
+//!
+//! Every `--interval` seconds the crank reads the programs' accounts, works
+//! out which permissionless instructions have come due against the cluster
+//! clock, and sends them:
+//!
+//! - timed-out workflow steps are rolled back and refunded (`agent-workflow`)
+//! - unclaimed leaves of bulk payout batches are paid out (`bulk-payout`, given `--payout-batch` leaf files)
+//! - requests past their deadline without a result are refunded (`marketplace-escrow`)
+//! - results the user left unreviewed for the review window are paid out (`marketplace-escrow`)
+//! - leaderboard epochs are rolled over, scored, and finalized (`leaderboard`)
+//! - ended raffle rounds are closed for drawing (`promo-raffle`)
+//! - closed SLA rounds with quorum are finalized (`sla-oracle`)
+//! - revenue credited to fractional vaults is synced to stakers (`fractional-vault`)
+//! - activity markers from finished analytics epochs are closed (`protocol-analytics`)
+//! - agents that missed their heartbeat window are deactivated (`agent-registry`)
+//!
+//! Refunds and payouts run first, epoch and round transitions next, and
+//! housekeeping last; within a priority the most overdue job goes first.
+//! Each job is simulated before sending, so jobs another crank has already
+//! executed are skipped for free, and the simulated usage sizes the compute
+//! unit limit. The unit price follows recent prioritization fees on the
+//! job's writable accounts up to `--max-unit-price`; housekeeping never bids
+//! and is deferred once half of `--hourly-budget` is spent. Sending stops
+//! while the payer holds less than `--min-balance`.
+
+mod executor;
+mod jobs;
+mod payouts;
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use agentmarket_metrics::Metrics;
+use agentmarket_sdk::RpcClient;
+use anchor_lang::solana_program::sysvar;
+use anyhow::{Context, Result};
+use clap::Parser;
+use solana_keypair::{read_keypair_file, Keypair};
+use solana_signer::Signer;
+use tracing::{debug, error, info, warn};
+use tracing_subscriber::EnvFilter;
+
+use crate::executor::{Executor, FeePolicy, Outcome};
+use crate::jobs::{JobKind, Scan};
+use crate::payouts::PayoutBatchFile;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "agentmarket-crank",
+    about = "Execute AgentMarket's permissionless time-based instructions"
+)]
+struct Args {
+    /// JSON-RPC endpoint of the cluster
+    #[arg(
+        long,
+        env = "AGENTMARKET_RPC_URL",
+        default_value = "http://127.0.0.1:8899"
+    )]
+    url: String,
+
+    /// Keypair that signs and pays for every job
+    #[arg(long, env = "AGENTMARKET_KEYPAIR")]
+    keypair: Option<PathBuf>,
+
+    /// Seconds between discovery passes
+    #[arg(long, default_value_t = 30)]
+    interval: u64,
+
+    /// Run one pass and exit
+    #[arg(long)]
+    once: bool,
+
+    /// Job kinds to run, comma separated; all by default
+    #[arg(long, value_enum, value_delimiter = ',')]
+    jobs: Vec<JobKind>,
+
+    /// Most jobs sent per pass
+    #[arg(long, default_value_t = 50)]
+    max_jobs: usize,
+
+    /// Leaf file of a bulk payout batch to drain; repeatable
+    #[arg(long = "payout-batch")]
+    payout_batches: Vec<PathBuf>,
+
+    /// Smallest unaccounted vault revenue worth a sync, in lamports
+    #[arg(long, default_value_t = 10_000_000)]
+    min_vault_sync: u64,
+
+    /// Percentile of recent prioritization fees to bid
+    #[arg(long, default_value_t = 75, value_parser = clap::value_parser!(u8).range(0..=100))]
+    fee_percentile: u8,
+
+    /// Ceiling on the compute unit price, in micro-lamports
+    #[arg(long, default_value_t = 100_000)]
+    max_unit_price: u64,
+
+    /// Compute units requested on top of the simulated usage, in percent
+    #[arg(long, default_value_t = 20)]
+    compute_margin: u32,
+
+    /// Lamports the crank may spend in any rolling hour, including rent it fronts
+    #[arg(long, default_value_t = 100_000_000)]
+    hourly_budget: u64,
+
+    /// Stop sending while the payer holds fewer lamports than this
+    #[arg(long, default_value_t = 50_000_000)]
+    min_balance: u64,
+
+    /// Seconds to wait for each transaction to confirm
+    #[arg(long, default_value_t = 60)]
+    confirm_timeout: u64,
+
+    /// Discover and simulate every job without sending anything
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Serve Prometheus metrics on this address
+    #[arg(long, env = "AGENTMARKET_METRICS_ADDR")]
+    metrics_addr: Option<SocketAddr>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
+        .init();
+
+    let args = Args::parse();
+    let payout_batches = args
+        .payout_batches
+        .iter()
+        .map(|path| PayoutBatchFile::read(path))
+        .collect::<Result<Vec<_>>>()?;
+
+    let rpc = RpcClient::new(&args.url);
+    let metrics = Metrics::new("crank");
+    if let Some(addr) = args.metrics_addr {
+        tokio::spawn(async move {
+            if let Err(e) = agentmarket_metrics::serve(addr).await {
+                error!("metrics server failed: {e}");
+            }
+        });
+    }
+
+    let payer = load_keypair(args.keypair.as_deref())?;
+    info!(payer = %payer.pubkey(), url = %args.url, dry_run = args.dry_run, "starting");
+    let mut executor = Executor::new(
+        rpc.clone(),
+        payer,
+        FeePolicy {
+            percentile: args.fee_percentile,
+            max_unit_price: args.max_unit_price,
+            compute_margin: args.compute_margin,
+            hourly_budget: args.hourly_budget,
+        },
+        Duration::from_secs(args.confirm_timeout),
+        args.dry_run,
+    );
+
+    let mut ticker = tokio::time::interval(Duration::from_secs(args.interval.max(1)));
+    loop {
+        ticker.tick().await;
+        if let Err(e) = run_pass(&args, &rpc, &mut executor, &payout_batches, metrics).await {
+            error!("pass failed: {e:#}");
+        }
+        if args.once {
+            return Ok(());
+        }
+    }
+}
+
+// Helper function to discover due jobs and work through them in priority order
+async fn run_pass(
+    args: &Args,
+    rpc: &RpcClient,
+    executor: &mut Executor,
+    payout_batches: &[PayoutBatchFile],
+    metrics: Metrics,
+) -> Result<()> {
+    let payer = executor.payer.pubkey();
+    let scan = Scan {
+        rpc,
+        payer,
+        now: cluster_time(rpc).await?,
+        kinds: &args.jobs,
+        payout_batches,
+        min_vault_sync: args.min_vault_sync,
+    };
+    let jobs = scan.discover().await;
+    metrics.set_pending_jobs(jobs.len());
+    if jobs.is_empty() {
+        debug!("nothing due");
+        return Ok(());
+    }
+    info!(due = jobs.len(), "jobs due");
+
+    let mut balance = rpc.get_balance(&payer).await?;
+    metrics.set_payer_balance(balance);
+    for job in jobs.iter().take(args.max_jobs) {
+        if balance < args.min_balance && !args.dry_run {
+            warn!(
+                balance,
+                min_balance = args.min_balance,
+                "payer balance too low; not sending"
+            );
+            break;
+        }
+
+        let kind = job.kind.name();
+        match executor.execute(job).await {
+            Ok(Outcome::Executed { signature, cost }) => {
+                info!(job = kind, target = %job.target, %signature, cost, "executed");
+                metrics.job_finished(kind, "executed");
+                balance = balance.saturating_sub(cost);
+                metrics.set_payer_balance(balance);
+            }
+            Ok(Outcome::Simulated { units, unit_price }) => {
+                info!(job = kind, target = %job.target, units, unit_price, "would execute");
+            }
+            Ok(Outcome::Skipped(reason)) => {
+                debug!(job = kind, target = %job.target, "skipped: {reason}");
+                metrics.job_finished(kind, "skipped");
+            }
+            Err(e) => {
+                warn!(job = kind, target = %job.target, "failed: {e:#}");
+                metrics.job_finished(kind, "failed");
+                metrics.transaction_failed();
+            }
+        }
+    }
+    info!(
+        spent_last_hour = executor.spent_last_hour(),
+        "pass complete"
+    );
+    Ok(())
+}
+
+// Helper function to read the cluster's unix time, which is what the programs compare against
+async fn cluster_time(rpc: &RpcClient) -> Result<i64> {
+    let data = rpc
+        .get_account_data(&sysvar::clock::ID)
+        .await?
+        .context("clock sysvar not found")?;
+    // Clock is slot, epoch_start_timestamp, epoch, leader_schedule_epoch, unix_timestamp
+    let bytes = data.get(32..40).context("clock sysvar too short")?;
+    Ok(i64::from_le_bytes(bytes.try_into()?))
+}
+
+// Helper function to read the payer keypair, defaulting to the Solana CLI's
+fn load_keypair(path: Option<&Path>) -> Result<Keypair> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => {
+            let home = std::env::var_os("HOME").context("HOME is not set; pass --keypair")?;
+            PathBuf::from(home).join(".config/solana/id.json")
+        }
+    };
+
+    read_keypair_file(&path)
+        .map_err(|e| anyhow::anyhow!("failed to read keypair {}: {e}", path.display()))
+}
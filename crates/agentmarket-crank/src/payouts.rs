@@ -0,0 +1,121 @@
+//! Leaf files for `bulk_payout` batches and the merkle proofs built from them.
+//!
+//! The program stores only a batch's merkle root, so the crank needs the
+//! leaves from whoever created the batch. A leaf file is JSON:
+//!
+//! ```json
+//! {"batch": "<batch address>", "leaves": [{"recipient": "<address>", "amount": 1000}]}
+//! ```
+//!
+//! Leaf `i` has index `i`. The tree pairs nodes left to right, level by
+//! level, carrying an odd node up unchanged, and hashes each pair in sorted
+//! order the way the program's `verify_proof` expects.
+
+use std::fs;
+use std::path::Path;
+
+use agentmarket_sdk::Pubkey;
+use anyhow::{ensure, Context, Result};
+use bulk_payout::{leaf_hash, NODE_PREFIX};
+use serde::Deserialize;
+use solana_sha256_hasher::hashv;
+
+#[derive(Deserialize)]
+struct RawFile {
+    batch: String,
+    leaves: Vec<RawLeaf>,
+}
+
+#[derive(Deserialize)]
+struct RawLeaf {
+    recipient: String,
+    amount: u64,
+}
+
+/// One payout leaf
+pub struct Leaf {
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+/// A batch's leaves with the tree built over them
+pub struct PayoutBatchFile {
+    pub batch: Pubkey,
+    pub leaves: Vec<Leaf>,
+    /// Tree levels from the leaf hashes up to the root
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl PayoutBatchFile {
+    pub fn read(path: &Path) -> Result<Self> {
+        let raw =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let file: RawFile =
+            serde_json::from_str(&raw).with_context(|| format!("parsing {}", path.display()))?;
+        ensure!(!file.leaves.is_empty(), "{} has no leaves", path.display());
+
+        let batch = file
+            .batch
+            .parse()
+            .with_context(|| format!("{}: invalid batch address", path.display()))?;
+        let leaves = file
+            .leaves
+            .into_iter()
+            .map(|leaf| {
+                Ok(Leaf {
+                    recipient: leaf.recipient.parse().with_context(|| {
+                        format!("{}: invalid recipient {}", path.display(), leaf.recipient)
+                    })?,
+                    amount: leaf.amount,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            batch,
+            levels: build_levels(&leaves),
+            leaves,
+        })
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.levels.last().expect("at least one leaf")[0]
+    }
+
+    /// Sibling hashes from leaf `index` up to the root
+    pub fn proof(&self, mut index: usize) -> Vec<[u8; 32]> {
+        let mut proof = Vec::new();
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        proof
+    }
+}
+
+// Helper function to hash the leaves and every level above them
+fn build_levels(leaves: &[Leaf]) -> Vec<Vec<[u8; 32]>> {
+    let mut levels = vec![leaves
+        .iter()
+        .enumerate()
+        .map(|(index, leaf)| leaf_hash(index as u32, &leaf.recipient, leaf.amount))
+        .collect::<Vec<_>>()];
+
+    while levels.last().expect("non-empty").len() > 1 {
+        let next = levels
+            .last()
+            .expect("non-empty")
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] if left <= right => hashv(&[NODE_PREFIX, left, right]).to_bytes(),
+                [left, right] => hashv(&[NODE_PREFIX, right, left]).to_bytes(),
+                [odd] => *odd,
+                _ => unreachable!("chunks of two"),
+            })
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
@@ -0,0 +1,1713 @@
+//! Every event the indexed programs emit, with each layout it has had on the wire.
+
+use agentmarket_sdk::events::*;
+use agentmarket_sdk::{
+    Pubkey, ESCROW_PROGRAM_ID, REGISTRY_PROGRAM_ID, REPUTATION_PROGRAM_ID, ROYALTY_PROGRAM_ID,
+};
+use anchor_lang::{AnchorDeserialize, Discriminator};
+
+use crate::legacy::{
+    AgentRegisteredV1, AgentUpdatedV1, EndpointChallengeIssuedV1, EndpointVerifiedV1,
+    ServiceRequestCreatedV1,
+};
+
+/// One event type of one program
+pub struct EventSchema {
+    pub program_id: Pubkey,
+    /// Program crate name, e.g. `agent_registry`
+    pub program: &'static str,
+    /// Type name as declared in the program
+    pub name: &'static str,
+    /// Leading bytes of every payload; Anchor derives them from `name` alone
+    pub discriminator: &'static [u8],
+    /// Oldest first; the last is what the deployed program emits
+    pub versions: &'static [Version],
+}
+
+impl EventSchema {
+    /// The layout the deployed program emits
+    pub fn current(&self) -> &Version {
+        self.versions.last().expect("every schema has a version")
+    }
+}
+
+/// One layout an event has had
+pub struct Version {
+    pub version: u8,
+    /// Field names in wire order
+    pub fields: &'static [&'static str],
+    /// Fields of the current layout this one lacks, filled with defaults on upgrade
+    pub defaulted: &'static [&'static str],
+    /// Decode a payload body, advancing past what this layout consumes
+    pub(crate) decode: fn(&mut &[u8]) -> Option<ProgramEvent>,
+}
+
+// Helper macro for a layout that decodes straight into the current event type
+macro_rules! current {
+    ($version:literal, $event:ident { $($field:ident),+ $(,)? }) => {
+        Version {
+            version: $version,
+            fields: &[$(stringify!($field)),+],
+            defaulted: &[],
+            decode: |body| $event::deserialize(body).ok().map(ProgramEvent::$event),
+        }
+    };
+}
+
+// Helper macro for an older layout upgraded through its `From` impl
+macro_rules! legacy {
+    (
+        $version:literal,
+        $legacy:ident => $event:ident { $($field:ident),+ $(,)? },
+        defaulted: [$($missing:ident),+ $(,)?] $(,)?
+    ) => {
+        Version {
+            version: $version,
+            fields: &[$(stringify!($field)),+],
+            defaulted: &[$(stringify!($missing)),+],
+            decode: |body| {
+                $legacy::deserialize(body)
+                    .ok()
+                    .map(|event| ProgramEvent::$event(event.into()))
+            },
+        }
+    };
+}
+
+// Helper macro for one event's catalog entry
+macro_rules! schema {
+    ($program_id:expr, $program:literal, $event:ident, [$($version:expr),+ $(,)?]) => {
+        EventSchema {
+            program_id: $program_id,
+            program: $program,
+            name: stringify!($event),
+            discriminator: $event::DISCRIMINATOR,
+            versions: &[$($version),+],
+        }
+    };
+}
+
+/// The full catalog, grouped by program
+pub static CATALOG: &[EventSchema] = &[
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentRegistered,
+        [
+            legacy!(
+                1,
+                AgentRegisteredV1 => AgentRegistered { agent_id, creator, name, nft_mint, timestamp },
+                defaulted: [capabilities],
+            ),
+            current!(
+                2,
+                AgentRegistered {
+                    agent_id,
+                    creator,
+                    name,
+                    capabilities,
+                    nft_mint,
+                    timestamp
+                }
+            ),
+        ]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentUpdated,
+        [
+            legacy!(
+                1,
+                AgentUpdatedV1 => AgentUpdated { agent_id, creator, timestamp },
+                defaulted: [name, is_active],
+            ),
+            current!(
+                2,
+                AgentUpdated {
+                    agent_id,
+                    creator,
+                    name,
+                    is_active,
+                    timestamp
+                }
+            ),
+        ]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentOwnershipTransferred,
+        [current!(
+            1,
+            AgentOwnershipTransferred {
+                agent_id,
+                previous_owner,
+                new_owner,
+                nft_mint,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentListedForSale,
+        [current!(
+            1,
+            AgentListedForSale {
+                agent_id,
+                seller,
+                nft_mint,
+                price,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentSaleCancelled,
+        [current!(
+            1,
+            AgentSaleCancelled {
+                agent_id,
+                seller,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentSold,
+        [current!(
+            1,
+            AgentSold {
+                agent_id,
+                seller,
+                buyer,
+                price,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentVersionPublished,
+        [current!(
+            1,
+            AgentVersionPublished {
+                agent_id,
+                agent_version,
+                major,
+                minor,
+                patch,
+                ipfs_hash,
+                changelog_hash,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        ServiceOfferingAdded,
+        [current!(
+            1,
+            ServiceOfferingAdded {
+                agent_id,
+                service_offering,
+                offering_id,
+                name,
+                price,
+                turnaround_secs,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        ServiceOfferingUpdated,
+        [current!(
+            1,
+            ServiceOfferingUpdated {
+                agent_id,
+                service_offering,
+                price,
+                turnaround_secs,
+                is_active,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        ServiceOfferingRemoved,
+        [current!(
+            1,
+            ServiceOfferingRemoved {
+                agent_id,
+                service_offering,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        CapabilityPriceSet,
+        [current!(
+            1,
+            CapabilityPriceSet {
+                agent_id,
+                capability_price,
+                capability_id,
+                price,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        CapabilityPriceRemoved,
+        [current!(
+            1,
+            CapabilityPriceRemoved {
+                agent_id,
+                capability_price,
+                capability_id,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        BundleCreated,
+        [current!(
+            1,
+            BundleCreated {
+                bundle,
+                curator,
+                bundle_id,
+                name,
+                agents,
+                price,
+                discount_bps,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        BundleUpdated,
+        [current!(
+            1,
+            BundleUpdated {
+                bundle,
+                curator,
+                price,
+                discount_bps,
+                is_active,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        SubscriptionTierCreated,
+        [current!(
+            1,
+            SubscriptionTierCreated {
+                agent_id,
+                subscription_tier,
+                tier_id,
+                name,
+                monthly_price,
+                requests_per_month,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        SubscriptionTierRetired,
+        [current!(
+            1,
+            SubscriptionTierRetired {
+                agent_id,
+                subscription_tier,
+                tier_id,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AccessGrantIssued,
+        [current!(
+            1,
+            AccessGrantIssued {
+                agent_id,
+                user,
+                subscription_tier,
+                months,
+                amount,
+                expires_at,
+                remaining_quota,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AccessConsumed,
+        [current!(
+            1,
+            AccessConsumed {
+                agent_id,
+                user,
+                units,
+                remaining_quota,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AccessGrantRevoked,
+        [current!(
+            1,
+            AccessGrantRevoked {
+                agent_id,
+                user,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentDeregistered,
+        [current!(
+            1,
+            AgentDeregistered {
+                agent_id,
+                creator,
+                nft_mint,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentRetired,
+        [current!(
+            1,
+            AgentRetired {
+                agent_id,
+                successor,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentAuthoritySet,
+        [current!(
+            1,
+            AgentAuthoritySet {
+                agent_id,
+                authority,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        CollectionInitialized,
+        [current!(
+            1,
+            CollectionInitialized {
+                collection_mint,
+                authority,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        CapabilityAdded,
+        [current!(
+            1,
+            CapabilityAdded {
+                capability,
+                id,
+                name,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        CapabilityDeprecated,
+        [current!(
+            1,
+            CapabilityDeprecated {
+                capability,
+                id,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AcceptedMintAdded,
+        [current!(
+            1,
+            AcceptedMintAdded {
+                mint,
+                decimals,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AcceptedMintRemoved,
+        [current!(1, AcceptedMintRemoved { mint, timestamp }),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        HealthCheckerAdded,
+        [current!(1, HealthCheckerAdded { checker, timestamp }),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        HealthCheckerRemoved,
+        [current!(1, HealthCheckerRemoved { checker, timestamp }),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        HealthCheckSubmitted,
+        [current!(
+            1,
+            HealthCheckSubmitted {
+                agent_id,
+                checker,
+                healthy,
+                latency_ms,
+                uptime_bps,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        UsdPriceResolved,
+        [current!(
+            1,
+            UsdPriceResolved {
+                agent_id,
+                usd_cents,
+                price,
+                expo,
+                lamports,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        SurgePriceResolved,
+        [current!(
+            1,
+            SurgePriceResolved {
+                agent_id,
+                open_requests,
+                lamports,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        EndpointChallengeIssued,
+        [
+            legacy!(
+                1,
+                EndpointChallengeIssuedV1 => EndpointChallengeIssued {
+                    agent_id,
+                    endpoint_url,
+                    nonce,
+                    timestamp
+                },
+                defaulted: [endpoints],
+            ),
+            current!(
+                2,
+                EndpointChallengeIssued {
+                    agent_id,
+                    endpoints,
+                    nonce,
+                    timestamp
+                }
+            ),
+        ]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        EndpointVerified,
+        [
+            legacy!(
+                1,
+                EndpointVerifiedV1 => EndpointVerified {
+                    agent_id,
+                    endpoint_url,
+                    endpoint_key,
+                    timestamp
+                },
+                defaulted: [endpoints],
+            ),
+            current!(
+                2,
+                EndpointVerified {
+                    agent_id,
+                    endpoints,
+                    endpoint_key,
+                    timestamp
+                }
+            ),
+        ]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        EndpointsRotated,
+        [current!(
+            1,
+            EndpointsRotated {
+                agent_id,
+                endpoints,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        PayoutWalletSet,
+        [current!(
+            1,
+            PayoutWalletSet {
+                agent_id,
+                payout_wallet,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        ProfileResized,
+        [current!(
+            1,
+            ProfileResized {
+                agent_id,
+                capability_limit,
+                description_limit,
+                size,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentCapabilitiesAdded,
+        [current!(
+            1,
+            AgentCapabilitiesAdded {
+                agent_id,
+                capabilities,
+                count,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentCapabilitiesRemoved,
+        [current!(
+            1,
+            AgentCapabilitiesRemoved {
+                agent_id,
+                capabilities,
+                count,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        RentToppedUp,
+        [current!(
+            1,
+            RentToppedUp {
+                agent_id,
+                account,
+                target,
+                funder,
+                amount,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        ProfileMigrated,
+        [current!(
+            1,
+            ProfileMigrated {
+                agent_id,
+                from_version,
+                version,
+                size,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        RegistryConfigUpdated,
+        [current!(
+            1,
+            RegistryConfigUpdated {
+                listing_stake,
+                slash_authority,
+                featured_price_per_day,
+                verifier,
+                heartbeat_window,
+                registration_fee,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        RegistrationFeesSwept,
+        [current!(1, RegistrationFeesSwept { amount, timestamp }),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        ReferralCodeCreated,
+        [current!(
+            1,
+            ReferralCodeCreated {
+                referral_code,
+                referrer,
+                code,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentReferred,
+        [current!(
+            1,
+            AgentReferred {
+                agent_id,
+                referral_code,
+                referrer,
+                share,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        ReferralFeesSwept,
+        [current!(
+            1,
+            ReferralFeesSwept {
+                referral_code,
+                referrer,
+                amount,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentHeartbeat,
+        [current!(
+            1,
+            AgentHeartbeat {
+                agent_id,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentMarkedStale,
+        [current!(
+            1,
+            AgentMarkedStale {
+                agent_id,
+                last_seen_at,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentCapacityUpdated,
+        [current!(
+            1,
+            AgentCapacityUpdated {
+                agent_id,
+                max_concurrent_requests,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentListingsPaused,
+        [current!(
+            1,
+            AgentListingsPaused {
+                agent_id,
+                paused_until,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentListingsResumed,
+        [current!(
+            1,
+            AgentListingsResumed {
+                agent_id,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentVisibilitySet,
+        [current!(
+            1,
+            AgentVisibilitySet {
+                agent_id,
+                visibility,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        TrialRequestsSet,
+        [current!(
+            1,
+            TrialRequestsSet {
+                agent_id,
+                trial_requests,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        LanguagesSet,
+        [current!(
+            1,
+            LanguagesSet {
+                agent_id,
+                languages,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        DependenciesSet,
+        [current!(
+            1,
+            DependenciesSet {
+                agent_id,
+                dependencies,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        MetadataAuthorityMigrated,
+        [current!(
+            1,
+            MetadataAuthorityMigrated {
+                agent_id,
+                previous_authority,
+                metadata_authority,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentMetadataUpdated,
+        [current!(
+            1,
+            AgentMetadataUpdated {
+                agent_id,
+                name,
+                symbol,
+                uri,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AllowlistEntryAdded,
+        [current!(
+            1,
+            AllowlistEntryAdded {
+                agent_id,
+                user,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AllowlistEntryRemoved,
+        [current!(
+            1,
+            AllowlistEntryRemoved {
+                agent_id,
+                user,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        UserBlocked,
+        [current!(
+            1,
+            UserBlocked {
+                agent_id,
+                user,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        UserUnblocked,
+        [current!(
+            1,
+            UserUnblocked {
+                agent_id,
+                user,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        CapabilitySchemaSet,
+        [current!(
+            1,
+            CapabilitySchemaSet {
+                agent_id,
+                capability_id,
+                input_schema,
+                output_schema,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        CapabilitySchemaCleared,
+        [current!(
+            1,
+            CapabilitySchemaCleared {
+                agent_id,
+                capability_id,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentForked,
+        [current!(
+            1,
+            AgentForked {
+                agent_id,
+                parent_agent,
+                lineage_royalty_bps,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentManifestRegistered,
+        [current!(
+            1,
+            AgentManifestRegistered {
+                agent_id,
+                operator,
+                manifest_hash,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        OperatorRegistered,
+        [current!(
+            1,
+            OperatorRegistered {
+                creator,
+                operator,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        OperatorRevoked,
+        [current!(
+            1,
+            OperatorRevoked {
+                creator,
+                operator,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentSoulbound,
+        [current!(
+            1,
+            AgentSoulbound {
+                agent_id,
+                token_account,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentVerified,
+        [current!(
+            1,
+            AgentVerified {
+                agent_id,
+                verifier,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentVerificationRevoked,
+        [current!(
+            1,
+            AgentVerificationRevoked {
+                agent_id,
+                verifier,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentFrozen,
+        [current!(
+            1,
+            AgentFrozen {
+                agent_id,
+                authority,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentUnfrozen,
+        [current!(
+            1,
+            AgentUnfrozen {
+                agent_id,
+                authority,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentAttestationIssued,
+        [current!(
+            1,
+            AgentAttestationIssued {
+                attestation,
+                agent_id,
+                issuer,
+                schema,
+                data_hash,
+                expires_at,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentAttestationRevoked,
+        [current!(
+            1,
+            AgentAttestationRevoked {
+                attestation,
+                agent_id,
+                issuer,
+                schema,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        CoCreatorsUpdated,
+        [current!(
+            1,
+            CoCreatorsUpdated {
+                agent_id,
+                co_creators,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        ListingStaked,
+        [current!(
+            1,
+            ListingStaked {
+                agent_id,
+                amount,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        StakeSlashed,
+        [current!(
+            1,
+            StakeSlashed {
+                agent_id,
+                request_id,
+                user,
+                amount,
+                remaining,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentPromoted,
+        [current!(
+            1,
+            AgentPromoted {
+                agent_id,
+                holder,
+                days,
+                amount,
+                featured_until,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AgentTreeInitialized,
+        [current!(
+            1,
+            AgentTreeInitialized {
+                merkle_tree,
+                max_depth,
+                max_buffer_size,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REGISTRY_PROGRAM_ID,
+        "agent_registry",
+        AccountMigrated,
+        [current!(
+            1,
+            AccountMigrated {
+                account,
+                size,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        ServiceRequestCreated,
+        [
+            legacy!(
+                1,
+                ServiceRequestCreatedV1 => ServiceRequestCreated {
+                    request_id,
+                    agent_id,
+                    user,
+                    amount,
+                    timestamp
+                },
+                defaulted: [payment_mint],
+            ),
+            current!(
+                2,
+                ServiceRequestCreated {
+                    request_id,
+                    agent_id,
+                    user,
+                    amount,
+                    payment_mint,
+                    timestamp
+                }
+            ),
+        ]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        ResultSubmitted,
+        [current!(
+            1,
+            ResultSubmitted {
+                request_id,
+                agent_id,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        PaymentReleased,
+        [current!(
+            1,
+            PaymentReleased {
+                request_id,
+                creator,
+                creator_amount,
+                platform_amount,
+                treasury_amount,
+                timestamp,
+            }
+        ),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        CoCreatorPaid,
+        [current!(
+            1,
+            CoCreatorPaid {
+                request_id,
+                co_creator,
+                amount
+            }
+        ),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        ResultDisputed,
+        [current!(
+            1,
+            ResultDisputed {
+                request_id,
+                user,
+                reason,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        RequestCancelled,
+        [current!(
+            1,
+            RequestCancelled {
+                request_id,
+                user,
+                refund_amount,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        RequestExpired,
+        [current!(
+            1,
+            RequestExpired {
+                request_id,
+                agent_id,
+                user,
+                refund_amount,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        RequestClosed,
+        [current!(
+            1,
+            RequestClosed {
+                request_id,
+                agent_id,
+                user,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        ArbiterSet,
+        [current!(1, ArbiterSet { arbiter, timestamp }),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        DisputeResolved,
+        [current!(
+            1,
+            DisputeResolved {
+                request_id,
+                agent_id,
+                arbiter,
+                outcome,
+                agent_amount,
+                refund_amount,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        JurorRegistered,
+        [current!(
+            1,
+            JurorRegistered {
+                juror,
+                stake,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        JurorLeft,
+        [current!(
+            1,
+            JurorLeft {
+                juror,
+                stake,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        PanelDrawn,
+        [current!(
+            1,
+            PanelDrawn {
+                request_id,
+                agent_id,
+                jurors,
+                voting_ends_at
+            }
+        ),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        JurorVoted,
+        [current!(
+            1,
+            JurorVoted {
+                request_id,
+                juror,
+                verdict,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        PanelSettled,
+        [current!(
+            1,
+            PanelSettled {
+                request_id,
+                agent_id,
+                votes_for_agent,
+                votes_for_user,
+                agent_amount,
+                refund_amount,
+                juror_rewards,
+                forfeited,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        AgentVersionPinned,
+        [current!(
+            1,
+            AgentVersionPinned {
+                request_id,
+                agent_id,
+                agent_version,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        ServiceOfferingSelected,
+        [current!(
+            1,
+            ServiceOfferingSelected {
+                request_id,
+                agent_id,
+                service_offering,
+                price,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        BundleSelected,
+        [current!(
+            1,
+            BundleSelected {
+                request_id,
+                agent_id,
+                bundle,
+                share_price,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        CapabilitySelected,
+        [current!(
+            1,
+            CapabilitySelected {
+                request_id,
+                agent_id,
+                capability_price,
+                price,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        ESCROW_PROGRAM_ID,
+        "marketplace_escrow",
+        AccountMigrated,
+        [current!(
+            1,
+            AccountMigrated {
+                account,
+                size,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        REPUTATION_PROGRAM_ID,
+        "reputation_system",
+        AgentReputationInitialized,
+        [current!(1, AgentReputationInitialized { agent_id }),]
+    ),
+    schema!(
+        REPUTATION_PROGRAM_ID,
+        "reputation_system",
+        RatingSubmitted,
+        [current!(
+            1,
+            RatingSubmitted {
+                rating_id,
+                agent_id,
+                user,
+                stars,
+                new_average
+            }
+        ),]
+    ),
+    schema!(
+        REPUTATION_PROGRAM_ID,
+        "reputation_system",
+        RatingReported,
+        [current!(
+            1,
+            RatingReported {
+                rating_id,
+                reporter,
+                reason
+            }
+        ),]
+    ),
+    schema!(
+        REPUTATION_PROGRAM_ID,
+        "reputation_system",
+        RatingModerated,
+        [current!(
+            1,
+            RatingModerated {
+                rating_id,
+                is_valid,
+                moderator
+            }
+        ),]
+    ),
+    schema!(
+        REPUTATION_PROGRAM_ID,
+        "reputation_system",
+        ReputationAdminChanged,
+        [current!(
+            1,
+            ReputationAdminChanged {
+                previous_admin,
+                new_admin
+            }
+        ),]
+    ),
+    schema!(
+        REPUTATION_PROGRAM_ID,
+        "reputation_system",
+        AccountMigrated,
+        [current!(
+            1,
+            AccountMigrated {
+                account,
+                size,
+                timestamp
+            }
+        ),]
+    ),
+    schema!(
+        ROYALTY_PROGRAM_ID,
+        "royalty_splitter",
+        RoyaltyConfigInitialized,
+        [current!(
+            1,
+            RoyaltyConfigInitialized {
+                creator_share,
+                platform_share,
+                treasury_share,
+                platform_wallet,
+                treasury_wallet,
+            }
+        ),]
+    ),
+    schema!(
+        ROYALTY_PROGRAM_ID,
+        "royalty_splitter",
+        RoyaltyConfigUpdated,
+        [current!(
+            1,
+            RoyaltyConfigUpdated {
+                creator_share,
+                platform_share,
+                treasury_share,
+                platform_wallet,
+                treasury_wallet,
+            }
+        ),]
+    ),
+    schema!(
+        ROYALTY_PROGRAM_ID,
+        "royalty_splitter",
+        PaymentDistributed,
+        [current!(
+            1,
+            PaymentDistributed {
+                distribution_id,
+                creator,
+                total_amount,
+                creator_amount,
+                platform_amount,
+                treasury_amount,
+            }
+        ),]
+    ),
+    schema!(
+        ROYALTY_PROGRAM_ID,
+        "royalty_splitter",
+        RoyaltyAdminChanged,
+        [current!(
+            1,
+            RoyaltyAdminChanged {
+                previous_admin,
+                new_admin
+            }
+        ),]
+    ),
+    schema!(
+        ROYALTY_PROGRAM_ID,
+        "royalty_splitter",
+        PlatformFeesWithdrawn,
+        [current!(
+            1,
+            PlatformFeesWithdrawn {
+                amount,
+                destination,
+                withdrawn_by
+            }
+        ),]
+    ),
+    schema!(
+        ROYALTY_PROGRAM_ID,
+        "royalty_splitter",
+        PauseStateChanged,
+        [current!(
+            1,
+            PauseStateChanged {
+                is_paused,
+                changed_by
+            }
+        ),]
+    ),
+    schema!(
+        ROYALTY_PROGRAM_ID,
+        "royalty_splitter",
+        LineageRoyaltyPaid,
+        [current!(
+            1,
+            LineageRoyaltyPaid {
+                agent_id,
+                parent_agent,
+                parent_creator,
+                amount
+            }
+        ),]
+    ),
+];
+
+/// The schema `program_id` emits under `name`
+pub fn schema(program_id: &Pubkey, name: &str) -> Option<&'static EventSchema> {
+    CATALOG
+        .iter()
+        .find(|schema| schema.program_id == *program_id && schema.name == name)
+}
+
+/// The schema of a payload emitted by `program_id`, by its discriminator
+pub fn lookup(program_id: &Pubkey, data: &[u8]) -> Option<&'static EventSchema> {
+    CATALOG
+        .iter()
+        .find(|schema| schema.program_id == *program_id && data.starts_with(schema.discriminator))
+}
@@ -0,0 +1,125 @@
+//! Event layouts the programs no longer emit, and how each maps onto the current one.
+
+use agentmarket_sdk::accounts::AgentEndpoint;
+use agentmarket_sdk::events::{
+    AgentRegistered, AgentUpdated, EndpointChallengeIssued, EndpointVerified,
+    ServiceRequestCreated,
+};
+use agentmarket_sdk::Pubkey;
+use anchor_lang::prelude::borsh;
+use anchor_lang::AnchorDeserialize;
+
+/// `AgentRegistered` before it carried the agent's capabilities
+#[derive(AnchorDeserialize)]
+pub struct AgentRegisteredV1 {
+    pub agent_id: Pubkey,
+    pub creator: Pubkey,
+    pub name: String,
+    pub nft_mint: Pubkey,
+    pub timestamp: i64,
+}
+
+impl From<AgentRegisteredV1> for AgentRegistered {
+    fn from(v1: AgentRegisteredV1) -> Self {
+        Self {
+            agent_id: v1.agent_id,
+            creator: v1.creator,
+            name: v1.name,
+            capabilities: Vec::new(),
+            nft_mint: v1.nft_mint,
+            timestamp: v1.timestamp,
+        }
+    }
+}
+
+/// `AgentUpdated` before it carried the agent's name and active flag
+#[derive(AnchorDeserialize)]
+pub struct AgentUpdatedV1 {
+    pub agent_id: Pubkey,
+    pub creator: Pubkey,
+    pub timestamp: i64,
+}
+
+impl From<AgentUpdatedV1> for AgentUpdated {
+    fn from(v1: AgentUpdatedV1) -> Self {
+        Self {
+            agent_id: v1.agent_id,
+            creator: v1.creator,
+            name: String::new(),
+            is_active: true,
+            timestamp: v1.timestamp,
+        }
+    }
+}
+
+/// `EndpointChallengeIssued` from when an agent had a single endpoint
+#[derive(AnchorDeserialize)]
+pub struct EndpointChallengeIssuedV1 {
+    pub agent_id: Pubkey,
+    pub endpoint_url: String,
+    pub nonce: [u8; 32],
+    pub timestamp: i64,
+}
+
+impl From<EndpointChallengeIssuedV1> for EndpointChallengeIssued {
+    fn from(v1: EndpointChallengeIssuedV1) -> Self {
+        Self {
+            agent_id: v1.agent_id,
+            endpoints: sole_endpoint(v1.endpoint_url),
+            nonce: v1.nonce,
+            timestamp: v1.timestamp,
+        }
+    }
+}
+
+/// `EndpointVerified` from when an agent had a single endpoint
+#[derive(AnchorDeserialize)]
+pub struct EndpointVerifiedV1 {
+    pub agent_id: Pubkey,
+    pub endpoint_url: String,
+    pub endpoint_key: Pubkey,
+    pub timestamp: i64,
+}
+
+impl From<EndpointVerifiedV1> for EndpointVerified {
+    fn from(v1: EndpointVerifiedV1) -> Self {
+        Self {
+            agent_id: v1.agent_id,
+            endpoints: sole_endpoint(v1.endpoint_url),
+            endpoint_key: v1.endpoint_key,
+            timestamp: v1.timestamp,
+        }
+    }
+}
+
+/// `ServiceRequestCreated` from when every request was paid in lamports
+#[derive(AnchorDeserialize)]
+pub struct ServiceRequestCreatedV1 {
+    pub request_id: Pubkey,
+    pub agent_id: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+impl From<ServiceRequestCreatedV1> for ServiceRequestCreated {
+    fn from(v1: ServiceRequestCreatedV1) -> Self {
+        Self {
+            request_id: v1.request_id,
+            agent_id: v1.agent_id,
+            user: v1.user,
+            amount: v1.amount,
+            payment_mint: None,
+            timestamp: v1.timestamp,
+        }
+    }
+}
+
+// Helper function to carry a single-endpoint url over as the only endpoint, with no region
+fn sole_endpoint(url: String) -> Vec<AgentEndpoint> {
+    vec![AgentEndpoint {
+        url,
+        region: String::new(),
+        priority: 0,
+    }]
+}
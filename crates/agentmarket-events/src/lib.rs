@@ -0,0 +1,84 @@
+//! Versioned schema registry for the AgentMarket program events.
+//!
+//! [`CATALOG`] lists every event the registry, escrow, reputation, and
+//! royalty programs emit, keyed by program and discriminator, together with
+//! each layout the event has had since the programs were first deployed.
+//! Anchor derives a discriminator from the type name only, so adding a field
+//! keeps the discriminator and silently changes the payload; the SDK's
+//! decoder only understands the layout it was compiled against.
+//!
+//! [`decode_event`] picks the layout a payload was written with and upgrades
+//! it to the SDK's current event type, reporting the version it found and
+//! which fields were filled with defaults so callers can avoid overwriting
+//! real data with them. Payloads from a program newer than this catalog,
+//! with fields appended that it does not know yet, still decode as the
+//! current version with the extra bytes ignored.
+//!
+//! When a program changes an event, copy the old struct into `legacy` with a
+//! `From` impl into the new one and add it to the schema's versions.
+
+mod catalog;
+mod legacy;
+
+use agentmarket_sdk::events::{parse_logs_with, ProgramEvent};
+use agentmarket_sdk::Pubkey;
+
+pub use catalog::{lookup, schema, EventSchema, Version, CATALOG};
+
+/// An event upgraded to its current layout
+pub struct Decoded {
+    pub event: ProgramEvent,
+    /// Version of the layout the payload was written with
+    pub version: u8,
+    /// Fields not present in that layout, holding defaults rather than emitted values
+    pub defaulted: &'static [&'static str],
+    /// Trailing bytes of fields newer than this catalog, skipped
+    pub ignored_bytes: usize,
+}
+
+impl Decoded {
+    /// Whether `field` was emitted by the program rather than defaulted
+    pub fn has(&self, field: &str) -> bool {
+        !self.defaulted.contains(&field)
+    }
+}
+
+/// Decode one event payload emitted by `program_id`, whatever version wrote it
+pub fn decode_event(program_id: &Pubkey, data: &[u8]) -> Option<Decoded> {
+    let schema = lookup(program_id, data)?;
+    let body = &data[schema.discriminator.len()..];
+
+    // Exactly one layout consumes the whole payload; try the newest first
+    // since it is by far the most common
+    for version in schema.versions.iter().rev() {
+        let mut rest = body;
+        if let Some(event) = (version.decode)(&mut rest) {
+            if rest.is_empty() {
+                return Some(Decoded {
+                    event,
+                    version: version.version,
+                    defaulted: version.defaulted,
+                    ignored_bytes: 0,
+                });
+            }
+        }
+    }
+
+    // A program upgraded past this catalog appended fields we cannot name yet
+    let current = schema.current();
+    let mut rest = body;
+    let event = (current.decode)(&mut rest)?;
+    Some(Decoded {
+        event,
+        version: current.version,
+        defaulted: current.defaulted,
+        ignored_bytes: rest.len(),
+    })
+}
+
+/// Decode every known event in a transaction's log messages, in emission order
+///
+/// The versioned counterpart of `agentmarket_sdk::events::parse_logs`.
+pub fn parse_logs<S: AsRef<str>>(logs: &[S]) -> Vec<(Pubkey, Decoded)> {
+    parse_logs_with(logs, decode_event)
+}
@@ -0,0 +1,199 @@
+//! Decoding payloads written by every known layout, plus layouts from the future.
+
+use agentmarket_events::{decode_event, parse_logs, CATALOG};
+use agentmarket_sdk::events::{
+    AgentRegistered, AgentUpdated, EndpointVerified, ProgramEvent, RatingSubmitted,
+    ServiceRequestCreated,
+};
+use agentmarket_sdk::{Pubkey, ESCROW_PROGRAM_ID, REGISTRY_PROGRAM_ID, REPUTATION_PROGRAM_ID};
+use anchor_lang::{AnchorSerialize, Discriminator, Event};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+// Helper function to frame a payload the way `emit!` does
+fn payload(discriminator: &[u8], body: impl AnchorSerialize) -> Vec<u8> {
+    let mut data = discriminator.to_vec();
+    body.serialize(&mut data).unwrap();
+    data
+}
+
+#[test]
+fn discriminators_are_unique_per_program() {
+    for (i, a) in CATALOG.iter().enumerate() {
+        assert!(!a.versions.is_empty(), "{} has no versions", a.name);
+        for b in &CATALOG[i + 1..] {
+            assert!(
+                a.program_id != b.program_id || a.discriminator != b.discriminator,
+                "{} and {} collide",
+                a.name,
+                b.name
+            );
+        }
+    }
+}
+
+#[test]
+fn current_layout_decodes_as_latest_version() {
+    let event = AgentRegistered {
+        agent_id: Pubkey::new_unique(),
+        creator: Pubkey::new_unique(),
+        name: "Summarizer".into(),
+        capabilities: vec!["text".into(), "pdf".into()],
+        nft_mint: Pubkey::new_unique(),
+        timestamp: 1_700_000_000,
+    };
+
+    let decoded = decode_event(&REGISTRY_PROGRAM_ID, &event.data()).unwrap();
+    assert_eq!(decoded.version, 2);
+    assert!(decoded.defaulted.is_empty());
+    assert_eq!(decoded.ignored_bytes, 0);
+    let ProgramEvent::AgentRegistered(e) = decoded.event else {
+        panic!("wrong event");
+    };
+    assert_eq!(e.capabilities, event.capabilities);
+}
+
+#[test]
+fn agent_registered_v1_is_upgraded() {
+    let agent_id = Pubkey::new_unique();
+    let nft_mint = Pubkey::new_unique();
+    let data = payload(
+        AgentRegistered::DISCRIMINATOR,
+        (
+            agent_id,
+            Pubkey::new_unique(),
+            "Summarizer".to_string(),
+            nft_mint,
+            1_700_000_000i64,
+        ),
+    );
+
+    let decoded = decode_event(&REGISTRY_PROGRAM_ID, &data).unwrap();
+    assert_eq!(decoded.version, 1);
+    assert!(!decoded.has("capabilities"));
+    let ProgramEvent::AgentRegistered(e) = decoded.event else {
+        panic!("wrong event");
+    };
+    assert_eq!(e.agent_id, agent_id);
+    assert_eq!(e.nft_mint, nft_mint);
+    assert_eq!(e.timestamp, 1_700_000_000);
+    assert!(e.capabilities.is_empty());
+}
+
+#[test]
+fn agent_updated_v1_is_upgraded() {
+    let agent_id = Pubkey::new_unique();
+    let data = payload(
+        AgentUpdated::DISCRIMINATOR,
+        (agent_id, Pubkey::new_unique(), 1_700_000_000i64),
+    );
+
+    let decoded = decode_event(&REGISTRY_PROGRAM_ID, &data).unwrap();
+    assert_eq!(decoded.version, 1);
+    assert!(!decoded.has("name"));
+    assert!(!decoded.has("is_active"));
+    assert!(decoded.has("timestamp"));
+    let ProgramEvent::AgentUpdated(e) = decoded.event else {
+        panic!("wrong event");
+    };
+    assert_eq!(e.agent_id, agent_id);
+    assert_eq!(e.timestamp, 1_700_000_000);
+}
+
+#[test]
+fn single_endpoint_v1_becomes_the_only_endpoint() {
+    let endpoint_key = Pubkey::new_unique();
+    let data = payload(
+        EndpointVerified::DISCRIMINATOR,
+        (
+            Pubkey::new_unique(),
+            "https://agents.example/summarizer".to_string(),
+            endpoint_key,
+            1_700_000_000i64,
+        ),
+    );
+
+    let decoded = decode_event(&REGISTRY_PROGRAM_ID, &data).unwrap();
+    assert_eq!(decoded.version, 1);
+    assert!(!decoded.has("endpoints"));
+    let ProgramEvent::EndpointVerified(e) = decoded.event else {
+        panic!("wrong event");
+    };
+    assert_eq!(e.endpoint_key, endpoint_key);
+    assert_eq!(e.endpoints.len(), 1);
+    assert_eq!(e.endpoints[0].url, "https://agents.example/summarizer");
+    assert!(e.endpoints[0].region.is_empty());
+}
+
+#[test]
+fn service_request_created_v1_was_paid_in_lamports() {
+    let request_id = Pubkey::new_unique();
+    let data = payload(
+        ServiceRequestCreated::DISCRIMINATOR,
+        (
+            request_id,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            1_000_000_000u64,
+            1_700_000_000i64,
+        ),
+    );
+
+    let decoded = decode_event(&ESCROW_PROGRAM_ID, &data).unwrap();
+    assert_eq!(decoded.version, 1);
+    assert!(!decoded.has("payment_mint"));
+    let ProgramEvent::ServiceRequestCreated(e) = decoded.event else {
+        panic!("wrong event");
+    };
+    assert_eq!(e.request_id, request_id);
+    assert_eq!(e.amount, 1_000_000_000);
+    assert_eq!(e.payment_mint, None);
+    assert_eq!(e.timestamp, 1_700_000_000);
+}
+
+#[test]
+fn appended_fields_are_ignored() {
+    let event = RatingSubmitted {
+        rating_id: Pubkey::new_unique(),
+        agent_id: Pubkey::new_unique(),
+        user: Pubkey::new_unique(),
+        stars: 4,
+        new_average: 420,
+    };
+    let mut data = event.data();
+    data.extend_from_slice(&7u64.to_le_bytes());
+
+    let decoded = decode_event(&REPUTATION_PROGRAM_ID, &data).unwrap();
+    assert_eq!(decoded.version, 1);
+    assert_eq!(decoded.ignored_bytes, 8);
+    let ProgramEvent::RatingSubmitted(e) = decoded.event else {
+        panic!("wrong event");
+    };
+    assert_eq!(e.new_average, 420);
+}
+
+#[test]
+fn events_are_attributed_to_the_program_that_emitted_them() {
+    let event = AgentUpdated {
+        agent_id: Pubkey::new_unique(),
+        creator: Pubkey::new_unique(),
+        name: "Summarizer".into(),
+        is_active: false,
+        timestamp: 1_700_000_000,
+    };
+    let logs = [
+        format!("Program {REGISTRY_PROGRAM_ID} invoke [1]"),
+        format!("Program data: {}", BASE64.encode(event.data())),
+        format!("Program {REGISTRY_PROGRAM_ID} success"),
+        format!("Program {REPUTATION_PROGRAM_ID} invoke [1]"),
+        // The same payload from a program that does not emit it is ignored
+        format!("Program data: {}", BASE64.encode(event.data())),
+        format!("Program {REPUTATION_PROGRAM_ID} success"),
+    ];
+
+    let events = parse_logs(&logs);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].0, REGISTRY_PROGRAM_ID);
+    assert_eq!(events[0].1.version, 2);
+    assert_eq!(events[0].1.event.name(), "AgentUpdated");
+}
@@ -0,0 +1,270 @@
+//! Read-only queries against the indexer's Postgres schema.
+
+use anyhow::Result;
+use async_graphql::SimpleObject;
+use deadpool_postgres::Pool;
+use serde::Serialize;
+use tokio_postgres::Row;
+
+/// Largest page any list endpoint returns
+pub const MAX_PAGE_SIZE: i64 = 200;
+/// Page size when the caller does not ask for one
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+
+#[derive(Debug, Serialize, SimpleObject)]
+pub struct Agent {
+    pub agent_id: String,
+    pub creator: String,
+    pub name: String,
+    pub capabilities: Vec<String>,
+    pub nft_mint: String,
+    pub is_active: bool,
+    pub total_ratings: i64,
+    /// Whole stars, 1-5
+    pub average_rating: i64,
+    /// Share of recent health checks that passed, in bps; `None` until checked
+    pub uptime_bps: Option<i32>,
+    pub registered_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, SimpleObject)]
+pub struct AgentStats {
+    pub agent_id: String,
+    pub total_requests: i64,
+    pub pending_requests: i64,
+    pub completed_requests: i64,
+    pub approved_requests: i64,
+    pub disputed_requests: i64,
+    pub cancelled_requests: i64,
+    /// Lamports paid by users for approved requests
+    pub total_volume: i64,
+    /// Creator's share of `total_volume`
+    pub total_earnings: i64,
+    pub total_ratings: i64,
+    pub average_rating: i64,
+}
+
+#[derive(Debug, Serialize, SimpleObject)]
+pub struct Request {
+    pub request_id: String,
+    pub agent_id: String,
+    pub user: String,
+    pub amount: i64,
+    pub status: String,
+    pub dispute_reason: Option<String>,
+    pub creator_amount: Option<i64>,
+    pub platform_amount: Option<i64>,
+    pub treasury_amount: Option<i64>,
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+    pub settled_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, SimpleObject)]
+pub struct Distribution {
+    pub distribution_id: String,
+    pub creator: String,
+    pub total_amount: i64,
+    pub creator_amount: i64,
+    pub platform_amount: i64,
+    pub treasury_amount: i64,
+    pub slot: i64,
+    pub created_at: Option<i64>,
+}
+
+/// `limit`/`offset` pagination, clamped to sane bounds
+#[derive(Clone, Copy, Debug)]
+pub struct Page {
+    pub limit: i64,
+    pub offset: i64,
+}
+
+impl Page {
+    pub fn new(limit: Option<i64>, offset: Option<i64>) -> Self {
+        Self {
+            limit: limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE),
+            offset: offset.unwrap_or(0).max(0),
+        }
+    }
+}
+
+/// Pooled handle to the indexer database
+#[derive(Clone)]
+pub struct Db {
+    pool: Pool,
+}
+
+const AGENT_COLUMNS: &str = "agent_id, creator, name, capabilities, nft_mint, is_active,
+    total_ratings, average_rating, uptime_bps, registered_at, updated_at";
+
+const REQUEST_COLUMNS: &str = "request_id, agent_id, user_key, amount, status, dispute_reason,
+    creator_amount, platform_amount, treasury_amount, created_at, completed_at, settled_at";
+
+impl Db {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+
+    /// Agents, newest first, optionally only those advertising `category` or
+    /// with at least `min_uptime_bps` uptime
+    pub async fn agents(
+        &self,
+        category: Option<&str>,
+        min_uptime_bps: Option<i32>,
+        include_inactive: bool,
+        page: Page,
+    ) -> Result<Vec<Agent>> {
+        let client = self.pool.get().await?;
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT {AGENT_COLUMNS} FROM agents
+                     WHERE ($1::TEXT IS NULL OR capabilities @> ARRAY[$1::TEXT])
+                       AND ($2::INTEGER IS NULL OR uptime_bps >= $2)
+                       AND ($3 OR is_active)
+                     ORDER BY registered_at DESC, agent_id
+                     LIMIT $4 OFFSET $5"
+                ),
+                &[
+                    &category,
+                    &min_uptime_bps,
+                    &include_inactive,
+                    &page.limit,
+                    &page.offset,
+                ],
+            )
+            .await?;
+
+        Ok(rows.iter().map(agent).collect())
+    }
+
+    pub async fn agent(&self, agent_id: &str) -> Result<Option<Agent>> {
+        let client = self.pool.get().await?;
+        let row = client
+            .query_opt(
+                &format!("SELECT {AGENT_COLUMNS} FROM agents WHERE agent_id = $1"),
+                &[&agent_id],
+            )
+            .await?;
+
+        Ok(row.as_ref().map(agent))
+    }
+
+    /// Request counts and earnings for one agent; `None` if the agent is unknown
+    pub async fn agent_stats(&self, agent_id: &str) -> Result<Option<AgentStats>> {
+        let client = self.pool.get().await?;
+        let row = client
+            .query_opt(
+                "SELECT a.agent_id, a.total_ratings, a.average_rating,
+                     COUNT(r.request_id),
+                     COUNT(r.request_id) FILTER (WHERE r.status = 'pending'),
+                     COUNT(r.request_id) FILTER (WHERE r.status = 'completed'),
+                     COUNT(r.request_id) FILTER (WHERE r.status = 'approved'),
+                     COUNT(r.request_id) FILTER (WHERE r.status = 'disputed'),
+                     COUNT(r.request_id) FILTER (WHERE r.status = 'cancelled'),
+                     COALESCE(SUM(r.amount) FILTER (WHERE r.status = 'approved'), 0)::BIGINT,
+                     COALESCE(SUM(r.creator_amount) FILTER (WHERE r.status = 'approved'), 0)::BIGINT
+                 FROM agents a LEFT JOIN requests r ON r.agent_id = a.agent_id
+                 WHERE a.agent_id = $1
+                 GROUP BY a.agent_id, a.total_ratings, a.average_rating",
+                &[&agent_id],
+            )
+            .await?;
+
+        Ok(row.map(|row| AgentStats {
+            agent_id: row.get(0),
+            total_ratings: row.get(1),
+            average_rating: row.get(2),
+            total_requests: row.get(3),
+            pending_requests: row.get(4),
+            completed_requests: row.get(5),
+            approved_requests: row.get(6),
+            disputed_requests: row.get(7),
+            cancelled_requests: row.get(8),
+            total_volume: row.get(9),
+            total_earnings: row.get(10),
+        }))
+    }
+
+    pub async fn request(&self, request_id: &str) -> Result<Option<Request>> {
+        let client = self.pool.get().await?;
+        let row = client
+            .query_opt(
+                &format!("SELECT {REQUEST_COLUMNS} FROM requests WHERE request_id = $1"),
+                &[&request_id],
+            )
+            .await?;
+
+        Ok(row.as_ref().map(request))
+    }
+
+    /// Royalty distributions, newest first, optionally for one creator
+    pub async fn distributions(
+        &self,
+        creator: Option<&str>,
+        page: Page,
+    ) -> Result<Vec<Distribution>> {
+        let client = self.pool.get().await?;
+        let rows = client
+            .query(
+                "SELECT distribution_id, creator, total_amount, creator_amount, platform_amount,
+                     treasury_amount, slot, created_at
+                 FROM distributions
+                 WHERE ($1::TEXT IS NULL OR creator = $1)
+                 ORDER BY slot DESC, distribution_id
+                 LIMIT $2 OFFSET $3",
+                &[&creator, &page.limit, &page.offset],
+            )
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| Distribution {
+                distribution_id: row.get(0),
+                creator: row.get(1),
+                total_amount: row.get(2),
+                creator_amount: row.get(3),
+                platform_amount: row.get(4),
+                treasury_amount: row.get(5),
+                slot: row.get(6),
+                created_at: row.get(7),
+            })
+            .collect())
+    }
+}
+
+// Helper function to map an `AGENT_COLUMNS` row
+fn agent(row: &Row) -> Agent {
+    Agent {
+        agent_id: row.get(0),
+        creator: row.get(1),
+        name: row.get(2),
+        capabilities: row.get(3),
+        nft_mint: row.get(4),
+        is_active: row.get(5),
+        total_ratings: row.get(6),
+        average_rating: row.get(7),
+        uptime_bps: row.get(8),
+        registered_at: row.get(9),
+        updated_at: row.get(10),
+    }
+}
+
+// Helper function to map a `REQUEST_COLUMNS` row
+fn request(row: &Row) -> Request {
+    Request {
+        request_id: row.get(0),
+        agent_id: row.get(1),
+        user: row.get(2),
+        amount: row.get(3),
+        status: row.get(4),
+        dispute_reason: row.get(5),
+        creator_amount: row.get(6),
+        platform_amount: row.get(7),
+        treasury_amount: row.get(8),
+        created_at: row.get(9),
+        completed_at: row.get(10),
+        settled_at: row.get(11),
+    }
+}
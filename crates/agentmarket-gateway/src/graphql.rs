@@ -0,0 +1,100 @@
+//! GraphQL schema exposing the same reads as the REST routes.
+
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Schema};
+use axum::extract::State;
+use axum::response::{Html, IntoResponse};
+use axum::routing::get;
+use axum::{Json, Router};
+
+use crate::db::{Agent, AgentStats, Db, Distribution, Page, Request};
+
+pub type GatewaySchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Agents, newest first, optionally only those advertising `category` or
+    /// with at least `min_uptime_bps` uptime
+    async fn agents(
+        &self,
+        ctx: &Context<'_>,
+        category: Option<String>,
+        min_uptime_bps: Option<i32>,
+        #[graphql(default = false)] include_inactive: bool,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> async_graphql::Result<Vec<Agent>> {
+        let db = ctx.data::<Db>()?;
+        Ok(db
+            .agents(
+                category.as_deref(),
+                min_uptime_bps,
+                include_inactive,
+                Page::new(limit, offset),
+            )
+            .await?)
+    }
+
+    async fn agent(
+        &self,
+        ctx: &Context<'_>,
+        agent_id: String,
+    ) -> async_graphql::Result<Option<Agent>> {
+        Ok(ctx.data::<Db>()?.agent(&agent_id).await?)
+    }
+
+    async fn agent_stats(
+        &self,
+        ctx: &Context<'_>,
+        agent_id: String,
+    ) -> async_graphql::Result<Option<AgentStats>> {
+        Ok(ctx.data::<Db>()?.agent_stats(&agent_id).await?)
+    }
+
+    async fn request(
+        &self,
+        ctx: &Context<'_>,
+        request_id: String,
+    ) -> async_graphql::Result<Option<Request>> {
+        Ok(ctx.data::<Db>()?.request(&request_id).await?)
+    }
+
+    /// Royalty distributions, newest first, optionally for one creator
+    async fn distributions(
+        &self,
+        ctx: &Context<'_>,
+        creator: Option<String>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+    ) -> async_graphql::Result<Vec<Distribution>> {
+        let db = ctx.data::<Db>()?;
+        Ok(db
+            .distributions(creator.as_deref(), Page::new(limit, offset))
+            .await?)
+    }
+}
+
+/// `/graphql`: POST executes queries, GET serves GraphiQL
+pub fn router(db: Db) -> Router {
+    let schema = Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(db)
+        .limit_depth(8)
+        .finish();
+
+    Router::new()
+        .route("/graphql", get(graphiql).post(execute))
+        .with_state(schema)
+}
+
+async fn execute(
+    State(schema): State<GatewaySchema>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema.execute(request).await)
+}
+
+async fn graphiql() -> impl IntoResponse {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
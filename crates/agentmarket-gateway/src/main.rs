@@ -0,0 +1,81 @@
+//! AgentMarket gateway: read-only REST and GraphQL API over the indexer database.
+//!
+//! Web frontends query agents, agent stats, request status, and royalty
+//! distribution history here instead of hitting RPC directly. The data is
+//! whatever `agentmarket-indexer` has written, so it trails the chain by the
+//! indexer's lag.
+//!
+//! - `GET /v1/agents?category=&include_inactive=&limit=&offset=`
+//! - `GET /v1/agents/{agent_id}` and `/v1/agents/{agent_id}/stats`
+//! - `GET /v1/requests/{request_id}`
+//! - `GET /v1/distributions?creator=&limit=&offset=`
+//! - `POST /graphql` (GraphiQL on `GET /graphql`)
+
+mod db;
+mod graphql;
+mod rest;
+
+use std::net::SocketAddr;
+
+use anyhow::Result;
+use axum::routing::get;
+use axum::Router;
+use clap::Parser;
+use deadpool_postgres::{Manager, Pool};
+use tokio_postgres::NoTls;
+use tower_http::cors::CorsLayer;
+use tower_http::trace::TraceLayer;
+use tracing::info;
+use tracing_subscriber::EnvFilter;
+
+use crate::db::Db;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "agentmarket-gateway",
+    about = "Serve AgentMarket data from the indexer database"
+)]
+struct Args {
+    /// Address to listen on
+    #[arg(long, env = "AGENTMARKET_GATEWAY_ADDR", default_value = "0.0.0.0:8080")]
+    listen: SocketAddr,
+
+    /// Postgres connection string of the indexer database
+    #[arg(long, env = "DATABASE_URL")]
+    database_url: String,
+
+    /// Maximum pooled database connections
+    #[arg(long, default_value_t = 16)]
+    pool_size: usize,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
+        .init();
+
+    let args = Args::parse();
+    let manager = Manager::new(args.database_url.parse()?, NoTls);
+    let pool = Pool::builder(manager).max_size(args.pool_size).build()?;
+    // Fail at startup rather than on the first request if the database is unreachable
+    drop(pool.get().await?);
+    let db = Db::new(pool);
+
+    let app = Router::new()
+        .route("/health", get(|| async { "ok" }))
+        .nest("/v1", rest::router().with_state(db.clone()))
+        .merge(graphql::router(db))
+        .layer(CorsLayer::permissive())
+        .layer(TraceLayer::new_for_http());
+
+    let listener = tokio::net::TcpListener::bind(args.listen).await?;
+    info!("listening on {}", args.listen);
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+//! REST endpoints; every response is JSON.
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use tracing::error;
+
+use crate::db::{Agent, AgentStats, Db, Distribution, Page, Request};
+
+/// Routes mounted under `/v1`
+pub fn router() -> Router<Db> {
+    Router::new()
+        .route("/agents", get(list_agents))
+        .route("/agents/{agent_id}", get(get_agent))
+        .route("/agents/{agent_id}/stats", get(get_agent_stats))
+        .route("/requests/{request_id}", get(get_request))
+        .route("/distributions", get(list_distributions))
+}
+
+/// Error body returned with every non-2xx response
+pub enum ApiError {
+    NotFound(&'static str),
+    Internal(anyhow::Error),
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            Self::NotFound(what) => (StatusCode::NOT_FOUND, format!("{what} not found")),
+            Self::Internal(e) => {
+                error!("query failed: {e:#}");
+                // Database details stay in the logs
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal error".into())
+            }
+        };
+        (status, Json(serde_json::json!({ "error": message }))).into_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AgentQuery {
+    /// Only agents advertising this capability
+    category: Option<String>,
+    /// Only agents whose health checks passed at least this often, in bps
+    min_uptime_bps: Option<i32>,
+    #[serde(default)]
+    include_inactive: bool,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DistributionQuery {
+    creator: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+async fn list_agents(
+    State(db): State<Db>,
+    Query(query): Query<AgentQuery>,
+) -> Result<Json<Vec<Agent>>, ApiError> {
+    let agents = db
+        .agents(
+            query.category.as_deref(),
+            query.min_uptime_bps,
+            query.include_inactive,
+            Page::new(query.limit, query.offset),
+        )
+        .await?;
+    Ok(Json(agents))
+}
+
+async fn get_agent(
+    State(db): State<Db>,
+    Path(agent_id): Path<String>,
+) -> Result<Json<Agent>, ApiError> {
+    db.agent(&agent_id)
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound("agent"))
+}
+
+async fn get_agent_stats(
+    State(db): State<Db>,
+    Path(agent_id): Path<String>,
+) -> Result<Json<AgentStats>, ApiError> {
+    db.agent_stats(&agent_id)
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound("agent"))
+}
+
+async fn get_request(
+    State(db): State<Db>,
+    Path(request_id): Path<String>,
+) -> Result<Json<Request>, ApiError> {
+    db.request(&request_id)
+        .await?
+        .map(Json)
+        .ok_or(ApiError::NotFound("request"))
+}
+
+async fn list_distributions(
+    State(db): State<Db>,
+    Query(query): Query<DistributionQuery>,
+) -> Result<Json<Vec<Distribution>>, ApiError> {
+    let distributions = db
+        .distributions(
+            query.creator.as_deref(),
+            Page::new(query.limit, query.offset),
+        )
+        .await?;
+    Ok(Json(distributions))
+}
@@ -0,0 +1,140 @@
+//! Message bus backends the publisher thread writes to.
+
+use crate::config::BusConfig;
+
+/// A connected message bus
+pub trait Publisher: Send {
+    /// Publish `payload` under `subject`; `key` is the account address or slot
+    fn publish(&mut self, subject: &str, key: &str, payload: Vec<u8>) -> Result<(), String>;
+
+    /// Block until everything published so far has been handed to the bus
+    fn flush(&mut self) -> Result<(), String>;
+}
+
+/// Connect the backend named by `config`
+pub fn connect(config: &BusConfig) -> Result<Box<dyn Publisher>, String> {
+    match config {
+        #[cfg(feature = "nats")]
+        BusConfig::Nats { url } => Ok(Box::new(nats::NatsPublisher::connect(url)?)),
+        #[cfg(feature = "kafka")]
+        BusConfig::Kafka {
+            brokers,
+            topic,
+            properties,
+        } => Ok(Box::new(kafka::KafkaPublisher::connect(
+            brokers, topic, properties,
+        )?)),
+        #[allow(unreachable_patterns)]
+        other => Err(format!(
+            "{other:?} requires a plugin built with the matching cargo feature"
+        )),
+    }
+}
+
+#[cfg(feature = "nats")]
+mod nats {
+    use tokio::runtime::Runtime;
+
+    use super::Publisher;
+
+    /// Core NATS publisher; the subject carries the account type
+    pub struct NatsPublisher {
+        runtime: Runtime,
+        client: async_nats::Client,
+    }
+
+    impl NatsPublisher {
+        pub fn connect(url: &str) -> Result<Self, String> {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| e.to_string())?;
+            let client = runtime
+                .block_on(async_nats::connect(url))
+                .map_err(|e| format!("connecting to {url}: {e}"))?;
+            Ok(Self { runtime, client })
+        }
+    }
+
+    impl Publisher for NatsPublisher {
+        fn publish(&mut self, subject: &str, _key: &str, payload: Vec<u8>) -> Result<(), String> {
+            self.runtime
+                .block_on(self.client.publish(subject.to_string(), payload.into()))
+                .map_err(|e| e.to_string())
+        }
+
+        fn flush(&mut self) -> Result<(), String> {
+            self.runtime
+                .block_on(self.client.flush())
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+#[cfg(feature = "kafka")]
+mod kafka {
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    use rdkafka::config::ClientConfig;
+    use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+    use rdkafka::producer::{BaseProducer, BaseRecord, Producer};
+
+    use super::Publisher;
+
+    /// How long `flush` waits for outstanding deliveries
+    const FLUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Kafka producer writing every payload to one topic, keyed by account
+    pub struct KafkaPublisher {
+        producer: BaseProducer,
+        topic: String,
+    }
+
+    impl KafkaPublisher {
+        pub fn connect(
+            brokers: &str,
+            topic: &str,
+            properties: &BTreeMap<String, String>,
+        ) -> Result<Self, String> {
+            let mut config = ClientConfig::new();
+            config.set("bootstrap.servers", brokers);
+            for (key, value) in properties {
+                config.set(key, value);
+            }
+            let producer = config.create().map_err(|e| e.to_string())?;
+            Ok(Self {
+                producer,
+                topic: topic.to_string(),
+            })
+        }
+    }
+
+    impl Publisher for KafkaPublisher {
+        fn publish(&mut self, subject: &str, key: &str, payload: Vec<u8>) -> Result<(), String> {
+            // Prefix the key with the subject so consumers can route without decoding;
+            // the account type never changes, so per-account ordering is kept
+            let key = format!("{subject}/{key}");
+            let mut record = BaseRecord::to(&self.topic).key(&key).payload(&payload);
+            loop {
+                match self.producer.send(record) {
+                    Ok(()) => break,
+                    // Local queue full: serve delivery callbacks, then retry
+                    Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), r)) => {
+                        record = r;
+                        self.producer.poll(Duration::from_millis(100));
+                    }
+                    Err((e, _)) => return Err(e.to_string()),
+                }
+            }
+            self.producer.poll(Duration::ZERO);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), String> {
+            self.producer
+                .flush(FLUSH_TIMEOUT)
+                .map_err(|e| e.to_string())
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Plugin configuration, read from the JSON file passed to `on_load`.
+
+use serde::Deserialize;
+
+/// Top-level plugin config; `libpath` is consumed by the validator itself
+#[derive(Debug, Deserialize)]
+pub struct PluginConfig {
+    pub bus: BusConfig,
+    /// Prefix for subjects/topic keys, e.g. `agentmarket.accounts.service_request`
+    #[serde(default = "default_subject_prefix")]
+    pub subject_prefix: String,
+    /// Updates buffered between the validator and the publisher thread
+    #[serde(default = "default_queue_capacity")]
+    pub queue_capacity: usize,
+    /// Publish the accounts replayed from the snapshot at startup
+    #[serde(default = "default_true")]
+    pub publish_startup: bool,
+    /// Publish slot status changes so consumers can track commitment
+    #[serde(default = "default_true")]
+    pub publish_slots: bool,
+}
+
+/// Where payloads are published
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BusConfig {
+    /// One subject per account type under the configured prefix
+    Nats { url: String },
+    /// A single topic, keyed by account address so updates stay ordered per account
+    Kafka {
+        brokers: String,
+        topic: String,
+        /// Extra librdkafka producer properties
+        #[serde(default)]
+        properties: std::collections::BTreeMap<String, String>,
+    },
+}
+
+impl PluginConfig {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let raw = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+        let config: Self = serde_json::from_str(&raw).map_err(|e| format!("{path}: {e}"))?;
+        if config.queue_capacity == 0 {
+            return Err(format!("{path}: queue_capacity must be greater than zero"));
+        }
+        Ok(config)
+    }
+}
+
+fn default_subject_prefix() -> String {
+    "agentmarket".into()
+}
+
+fn default_queue_capacity() -> usize {
+    100_000
+}
+
+fn default_true() -> bool {
+    true
+}
@@ -0,0 +1,43 @@
+//! AgentMarket Geyser plugin.
+//!
+//! Loaded by a validator (or RPC node) through its `--geyser-plugin-config`
+//! file, the plugin decodes every account update owned by the registry,
+//! escrow, reputation, or royalty program into a typed JSON payload and
+//! publishes it to NATS or Kafka. Consumers get real-time marketplace state
+//! without polling RPC. Publishing happens on a worker thread behind a bounded
+//! queue so a slow bus never stalls the validator; when the queue is full,
+//! updates are dropped and counted.
+//!
+//! Example config:
+//!
+//! ```json
+//! {
+//!   "libpath": "/path/to/libagentmarket_geyser.so",
+//!   "bus": { "kind": "nats", "url": "nats://127.0.0.1:4222" },
+//!   "subject_prefix": "agentmarket"
+//! }
+//! ```
+
+mod bus;
+mod config;
+mod payload;
+mod plugin;
+
+pub use config::{BusConfig, PluginConfig};
+pub use payload::{AccountUpdate, MarketplaceAccount, SlotUpdate};
+pub use plugin::MarketplacePlugin;
+
+use agave_geyser_plugin_interface::geyser_plugin_interface::GeyserPlugin;
+
+/// Entry point the validator resolves when loading the plugin library
+///
+/// # Safety
+///
+/// The validator takes ownership of the returned pointer and must be built
+/// with the same Rust toolchain and interface version as this library.
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn _create_plugin() -> *mut dyn GeyserPlugin {
+    let plugin: Box<dyn GeyserPlugin> = Box::new(MarketplacePlugin::default());
+    Box::into_raw(plugin)
+}
@@ -0,0 +1,407 @@
+//! Typed, JSON-serializable payloads for decoded program accounts.
+
+use agentmarket_sdk::accounts::{
+    self, AgentProfile, AgentReputationProfile, DistributionRecord, PricingModel, Rating,
+    ReputationConfig, RequestStatus, RoyaltyConfig, ServiceRequest,
+};
+use agentmarket_sdk::{
+    Pubkey, ESCROW_PROGRAM_ID, REGISTRY_PROGRAM_ID, REPUTATION_PROGRAM_ID, ROYALTY_PROGRAM_ID,
+};
+use serde::Serialize;
+
+/// One account write, as published on the bus
+#[derive(Debug, Serialize)]
+pub struct AccountUpdate {
+    pub pubkey: String,
+    pub owner: String,
+    pub slot: u64,
+    pub lamports: u64,
+    /// Orders writes to the same account within a slot
+    pub write_version: u64,
+    /// Whether the update was replayed from the snapshot at startup
+    pub is_startup: bool,
+    /// Transaction that caused the write, when the validator reports it
+    pub signature: Option<String>,
+    pub account: MarketplaceAccount,
+}
+
+/// A slot status change, published so consumers can wait for commitment
+#[derive(Debug, Serialize)]
+pub struct SlotUpdate {
+    pub slot: u64,
+    pub parent: Option<u64>,
+    pub status: String,
+}
+
+/// Decoded account state, tagged with its type
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum MarketplaceAccount {
+    AgentProfile(AgentProfilePayload),
+    ServiceRequest(ServiceRequestPayload),
+    AgentReputation(AgentReputationPayload),
+    Rating(RatingPayload),
+    ReputationConfig(ReputationConfigPayload),
+    RoyaltyConfig(RoyaltyConfigPayload),
+    DistributionRecord(DistributionRecordPayload),
+}
+
+#[derive(Debug, Serialize)]
+pub struct AgentProfilePayload {
+    pub agent_id: String,
+    pub creator: String,
+    pub name: String,
+    pub description: String,
+    pub capabilities: Vec<String>,
+    pub pricing_model: PricingPayload,
+    pub endpoints: Vec<EndpointPayload>,
+    pub ipfs_hash: String,
+    pub reputation_score: u32,
+    pub total_services: u64,
+    pub total_earnings: u64,
+    pub created_at: i64,
+    pub is_active: bool,
+    pub nft_mint: String,
+    pub is_verified: bool,
+    pub last_seen_at: i64,
+    pub max_concurrent_requests: u32,
+    pub paused_until: Option<i64>,
+    pub parent_agent: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EndpointPayload {
+    pub url: String,
+    pub region: String,
+    pub priority: u8,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PricingPayload {
+    PerQuery {
+        price: u64,
+        payment_mint: Option<String>,
+    },
+    Subscription {
+        monthly: u64,
+        payment_mint: Option<String>,
+    },
+    Custom {
+        base: u64,
+        variable: u8,
+        payment_mint: Option<String>,
+    },
+    UsdPegged {
+        usd_cents: u64,
+        feed: String,
+    },
+    Surge {
+        base: u64,
+        min: u64,
+        max: u64,
+        target_open: u32,
+        step_bps: u16,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServiceRequestPayload {
+    pub request_id: String,
+    pub agent_id: String,
+    pub user: String,
+    pub amount: u64,
+    pub status: &'static str,
+    pub request_data: String,
+    pub result_data: String,
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+    pub escrow_account: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AgentReputationPayload {
+    pub agent_id: String,
+    pub total_ratings: u64,
+    pub average_rating: u32,
+    pub quality_score: u32,
+    pub speed_score: u32,
+    pub value_score: u32,
+    pub created_at: i64,
+    pub last_rating_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RatingPayload {
+    pub rating_id: String,
+    pub agent_id: String,
+    pub user: String,
+    pub request_id: String,
+    pub stars: u8,
+    pub quality: u8,
+    pub speed: u8,
+    pub value: u8,
+    pub review_text: String,
+    pub created_at: i64,
+    pub is_reported: bool,
+    pub report_reason: Option<String>,
+    pub is_moderated: bool,
+    pub is_valid: bool,
+    pub admin_note: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReputationConfigPayload {
+    pub admin: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RoyaltyConfigPayload {
+    pub creator_share: u8,
+    pub platform_share: u8,
+    pub treasury_share: u8,
+    pub platform_wallet: String,
+    pub treasury_wallet: String,
+    pub admin: String,
+    pub total_distributed: u64,
+    pub total_transactions: u64,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub is_paused: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DistributionRecordPayload {
+    pub distribution_id: String,
+    pub creator: String,
+    pub total_amount: u64,
+    pub creator_amount: u64,
+    pub platform_amount: u64,
+    pub treasury_amount: u64,
+    pub timestamp: i64,
+}
+
+impl MarketplaceAccount {
+    /// Decode account data owned by `owner`; `None` for other programs or unknown layouts
+    pub fn decode(owner: &Pubkey, data: &[u8]) -> Option<Self> {
+        if *owner == REGISTRY_PROGRAM_ID {
+            decode_with(data, |a: AgentProfile| Self::AgentProfile(a.into()))
+        } else if *owner == ESCROW_PROGRAM_ID {
+            decode_with(data, |r: ServiceRequest| Self::ServiceRequest(r.into()))
+        } else if *owner == REPUTATION_PROGRAM_ID {
+            decode_with(data, |p: AgentReputationProfile| {
+                Self::AgentReputation(p.into())
+            })
+            .or_else(|| decode_with(data, |r: Rating| Self::Rating(r.into())))
+            .or_else(|| {
+                decode_with(data, |c: ReputationConfig| {
+                    Self::ReputationConfig(ReputationConfigPayload {
+                        admin: c.admin.to_string(),
+                    })
+                })
+            })
+        } else if *owner == ROYALTY_PROGRAM_ID {
+            decode_with(data, |c: RoyaltyConfig| Self::RoyaltyConfig(c.into())).or_else(|| {
+                decode_with(data, |d: DistributionRecord| {
+                    Self::DistributionRecord(d.into())
+                })
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Subject suffix / message type name
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::AgentProfile(_) => "agent_profile",
+            Self::ServiceRequest(_) => "service_request",
+            Self::AgentReputation(_) => "agent_reputation",
+            Self::Rating(_) => "rating",
+            Self::ReputationConfig(_) => "reputation_config",
+            Self::RoyaltyConfig(_) => "royalty_config",
+            Self::DistributionRecord(_) => "distribution_record",
+        }
+    }
+}
+
+// Helper function to decode `T` when the discriminator matches
+fn decode_with<T, F>(data: &[u8], wrap: F) -> Option<MarketplaceAccount>
+where
+    T: anchor_lang::AccountDeserialize + anchor_lang::Discriminator,
+    F: FnOnce(T) -> MarketplaceAccount,
+{
+    if !accounts::is_account::<T>(data) {
+        return None;
+    }
+    accounts::decode::<T>(data).ok().map(wrap)
+}
+
+impl From<AgentProfile> for AgentProfilePayload {
+    fn from(a: AgentProfile) -> Self {
+        Self {
+            agent_id: a.agent_id.to_string(),
+            creator: a.creator.to_string(),
+            name: a.name,
+            description: a.description,
+            capabilities: a.capabilities,
+            pricing_model: match a.pricing_model {
+                PricingModel::PerQuery {
+                    price,
+                    payment_mint,
+                } => PricingPayload::PerQuery {
+                    price,
+                    payment_mint: payment_mint.map(|mint| mint.to_string()),
+                },
+                PricingModel::Subscription {
+                    monthly,
+                    payment_mint,
+                } => PricingPayload::Subscription {
+                    monthly,
+                    payment_mint: payment_mint.map(|mint| mint.to_string()),
+                },
+                PricingModel::Custom {
+                    base,
+                    variable,
+                    payment_mint,
+                } => PricingPayload::Custom {
+                    base,
+                    variable,
+                    payment_mint: payment_mint.map(|mint| mint.to_string()),
+                },
+                PricingModel::UsdPegged { usd_cents, feed } => PricingPayload::UsdPegged {
+                    usd_cents,
+                    feed: feed.to_string(),
+                },
+                PricingModel::Surge {
+                    base,
+                    min,
+                    max,
+                    target_open,
+                    step_bps,
+                } => PricingPayload::Surge {
+                    base,
+                    min,
+                    max,
+                    target_open,
+                    step_bps,
+                },
+            },
+            endpoints: a
+                .endpoints
+                .into_iter()
+                .map(|e| EndpointPayload {
+                    url: e.url,
+                    region: e.region,
+                    priority: e.priority,
+                })
+                .collect(),
+            ipfs_hash: a.ipfs_hash,
+            reputation_score: a.reputation_score,
+            total_services: a.total_services,
+            total_earnings: a.total_earnings,
+            created_at: a.created_at,
+            is_active: a.is_active,
+            nft_mint: a.nft_mint.to_string(),
+            is_verified: a.is_verified,
+            last_seen_at: a.last_seen_at,
+            max_concurrent_requests: a.max_concurrent_requests,
+            paused_until: a.paused_until,
+            parent_agent: a.parent_agent.map(|parent| parent.to_string()),
+        }
+    }
+}
+
+impl From<ServiceRequest> for ServiceRequestPayload {
+    fn from(r: ServiceRequest) -> Self {
+        Self {
+            request_id: r.request_id.to_string(),
+            agent_id: r.agent_id.to_string(),
+            user: r.user.to_string(),
+            amount: r.amount,
+            status: match r.status {
+                RequestStatus::Pending => "pending",
+                RequestStatus::InProgress => "in_progress",
+                RequestStatus::Completed => "completed",
+                RequestStatus::Approved => "approved",
+                RequestStatus::Disputed => "disputed",
+                RequestStatus::Cancelled => "cancelled",
+                RequestStatus::Resolved => "resolved",
+            },
+            request_data: r.request_data,
+            result_data: r.result_data,
+            created_at: r.created_at,
+            completed_at: r.completed_at,
+            escrow_account: r.escrow_account.to_string(),
+        }
+    }
+}
+
+impl From<AgentReputationProfile> for AgentReputationPayload {
+    fn from(p: AgentReputationProfile) -> Self {
+        Self {
+            agent_id: p.agent_id.to_string(),
+            total_ratings: p.total_ratings,
+            average_rating: p.average_rating,
+            quality_score: p.quality_score,
+            speed_score: p.speed_score,
+            value_score: p.value_score,
+            created_at: p.created_at,
+            last_rating_at: p.last_rating_at,
+        }
+    }
+}
+
+impl From<Rating> for RatingPayload {
+    fn from(r: Rating) -> Self {
+        Self {
+            rating_id: r.rating_id.to_string(),
+            agent_id: r.agent_id.to_string(),
+            user: r.user.to_string(),
+            request_id: r.request_id.to_string(),
+            stars: r.stars,
+            quality: r.quality,
+            speed: r.speed,
+            value: r.value,
+            review_text: r.review_text,
+            created_at: r.created_at,
+            is_reported: r.is_reported,
+            report_reason: r.report_reason,
+            is_moderated: r.is_moderated,
+            is_valid: r.is_valid,
+            admin_note: r.admin_note,
+        }
+    }
+}
+
+impl From<RoyaltyConfig> for RoyaltyConfigPayload {
+    fn from(c: RoyaltyConfig) -> Self {
+        Self {
+            creator_share: c.creator_share,
+            platform_share: c.platform_share,
+            treasury_share: c.treasury_share,
+            platform_wallet: c.platform_wallet.to_string(),
+            treasury_wallet: c.treasury_wallet.to_string(),
+            admin: c.admin.to_string(),
+            total_distributed: c.total_distributed,
+            total_transactions: c.total_transactions,
+            created_at: c.created_at,
+            updated_at: c.updated_at,
+            is_paused: c.is_paused,
+        }
+    }
+}
+
+impl From<DistributionRecord> for DistributionRecordPayload {
+    fn from(d: DistributionRecord) -> Self {
+        Self {
+            distribution_id: d.distribution_id.to_string(),
+            creator: d.creator.to_string(),
+            total_amount: d.total_amount,
+            creator_amount: d.creator_amount,
+            platform_amount: d.platform_amount,
+            treasury_amount: d.treasury_amount,
+            timestamp: d.timestamp,
+        }
+    }
+}
@@ -0,0 +1,247 @@
+//! The `GeyserPlugin` implementation: filters, decodes, and queues updates for publishing.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use agave_geyser_plugin_interface::geyser_plugin_interface::{
+    GeyserPlugin, GeyserPluginError, ReplicaAccountInfoVersions, Result, SlotStatus,
+};
+use agentmarket_sdk::events::INDEXED_PROGRAMS;
+use agentmarket_sdk::Pubkey;
+use log::{error, info, warn};
+
+use crate::bus::{self, Publisher};
+use crate::config::PluginConfig;
+use crate::payload::{AccountUpdate, MarketplaceAccount, SlotUpdate};
+
+/// Work handed from the validator threads to the publisher thread
+enum Outgoing {
+    Account(AccountUpdate),
+    Slot(SlotUpdate),
+}
+
+/// Streams AgentMarket account updates to the configured bus
+#[derive(Default)]
+pub struct MarketplacePlugin {
+    running: Option<Running>,
+}
+
+struct Running {
+    sender: SyncSender<Outgoing>,
+    worker: JoinHandle<()>,
+    /// Updates discarded because the queue was full
+    dropped: Arc<AtomicU64>,
+    publish_startup: bool,
+    publish_slots: bool,
+}
+
+impl std::fmt::Debug for MarketplacePlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MarketplacePlugin")
+            .field("running", &self.running.is_some())
+            .finish()
+    }
+}
+
+impl GeyserPlugin for MarketplacePlugin {
+    fn name(&self) -> &'static str {
+        "agentmarket-geyser"
+    }
+
+    fn on_load(&mut self, config_file: &str, _is_reload: bool) -> Result<()> {
+        let config = PluginConfig::load(config_file)
+            .map_err(|msg| GeyserPluginError::ConfigFileReadError { msg })?;
+        // Connect up front so a bad bus config fails the validator start, not the first update
+        let publisher = bus::connect(&config.bus).map_err(custom_error)?;
+
+        let (sender, receiver) = mpsc::sync_channel(config.queue_capacity);
+        let prefix = config.subject_prefix.clone();
+        let worker = std::thread::Builder::new()
+            .name("agentmarketGeyser".into())
+            .spawn(move || publish_loop(publisher, &prefix, receiver))
+            .map_err(custom_error)?;
+
+        info!("agentmarket-geyser publishing to {:?}", config.bus);
+        self.running = Some(Running {
+            sender,
+            worker,
+            dropped: Arc::new(AtomicU64::new(0)),
+            publish_startup: config.publish_startup,
+            publish_slots: config.publish_slots,
+        });
+        Ok(())
+    }
+
+    fn on_unload(&mut self) {
+        if let Some(running) = self.running.take() {
+            // Closing the channel lets the worker drain the queue, flush, and exit
+            drop(running.sender);
+            if running.worker.join().is_err() {
+                error!("agentmarket-geyser publisher thread panicked");
+            }
+            let dropped = running.dropped.load(Ordering::Relaxed);
+            if dropped > 0 {
+                warn!("agentmarket-geyser dropped {dropped} updates on a full queue");
+            }
+        }
+    }
+
+    fn update_account(
+        &self,
+        account: ReplicaAccountInfoVersions,
+        slot: u64,
+        is_startup: bool,
+    ) -> Result<()> {
+        let Some(running) = &self.running else {
+            return Ok(());
+        };
+        if is_startup && !running.publish_startup {
+            return Ok(());
+        }
+
+        let (pubkey, owner, lamports, data, write_version, signature) = match account {
+            ReplicaAccountInfoVersions::V0_0_1(a) => {
+                (a.pubkey, a.owner, a.lamports, a.data, a.write_version, None)
+            }
+            ReplicaAccountInfoVersions::V0_0_2(a) => (
+                a.pubkey,
+                a.owner,
+                a.lamports,
+                a.data,
+                a.write_version,
+                a.txn_signature.map(|s| s.to_string()),
+            ),
+            ReplicaAccountInfoVersions::V0_0_3(a) => (
+                a.pubkey,
+                a.owner,
+                a.lamports,
+                a.data,
+                a.write_version,
+                a.txn.map(|t| t.signature().to_string()),
+            ),
+        };
+
+        let Ok(owner) = Pubkey::try_from(owner) else {
+            return Ok(());
+        };
+        if !INDEXED_PROGRAMS.contains(&owner) {
+            return Ok(());
+        }
+        // Closed accounts and non-Anchor data have nothing to publish
+        let Some(decoded) = MarketplaceAccount::decode(&owner, data) else {
+            return Ok(());
+        };
+        let Ok(pubkey) = Pubkey::try_from(pubkey) else {
+            return Ok(());
+        };
+
+        running.enqueue(Outgoing::Account(AccountUpdate {
+            pubkey: pubkey.to_string(),
+            owner: owner.to_string(),
+            slot,
+            lamports,
+            write_version,
+            is_startup,
+            signature,
+            account: decoded,
+        }))
+    }
+
+    fn update_slot_status(
+        &self,
+        slot: u64,
+        parent: Option<u64>,
+        status: &SlotStatus,
+    ) -> Result<()> {
+        let Some(running) = &self.running else {
+            return Ok(());
+        };
+        if !running.publish_slots {
+            return Ok(());
+        }
+        let status = match status {
+            SlotStatus::Processed => "processed",
+            SlotStatus::Confirmed => "confirmed",
+            SlotStatus::Rooted => "rooted",
+            // Only commitment levels are useful to consumers
+            _ => return Ok(()),
+        };
+
+        running.enqueue(Outgoing::Slot(SlotUpdate {
+            slot,
+            parent,
+            status: status.into(),
+        }))
+    }
+
+    fn notify_end_of_startup(&self) -> Result<()> {
+        info!("agentmarket-geyser finished startup replay");
+        Ok(())
+    }
+
+    fn account_data_notifications_enabled(&self) -> bool {
+        true
+    }
+
+    fn transaction_notifications_enabled(&self) -> bool {
+        false
+    }
+}
+
+impl Running {
+    // Helper function to queue work without ever blocking the validator
+    fn enqueue(&self, outgoing: Outgoing) -> Result<()> {
+        match self.sender.try_send(outgoing) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                if dropped.is_power_of_two() {
+                    warn!("agentmarket-geyser queue full, {dropped} updates dropped so far");
+                }
+                Ok(())
+            }
+            Err(TrySendError::Disconnected(_)) => Err(GeyserPluginError::AccountsUpdateError {
+                msg: "agentmarket-geyser publisher thread has stopped".into(),
+            }),
+        }
+    }
+}
+
+// Helper function to serialize and publish queued updates until the plugin unloads
+fn publish_loop(mut publisher: Box<dyn Publisher>, prefix: &str, receiver: Receiver<Outgoing>) {
+    for outgoing in receiver {
+        let (subject, key, payload) = match &outgoing {
+            Outgoing::Account(update) => (
+                format!("{prefix}.accounts.{}", update.account.kind()),
+                update.pubkey.clone(),
+                serde_json::to_vec(update),
+            ),
+            Outgoing::Slot(update) => (
+                format!("{prefix}.slots"),
+                update.slot.to_string(),
+                serde_json::to_vec(update),
+            ),
+        };
+        let payload = match payload {
+            Ok(payload) => payload,
+            Err(e) => {
+                error!("agentmarket-geyser failed to serialize {subject}: {e}");
+                continue;
+            }
+        };
+        if let Err(e) = publisher.publish(&subject, &key, payload) {
+            error!("agentmarket-geyser failed to publish {subject} {key}: {e}");
+        }
+    }
+
+    if let Err(e) = publisher.flush() {
+        error!("agentmarket-geyser failed to flush on unload: {e}");
+    }
+}
+
+// Helper function to wrap an error for the plugin interface
+fn custom_error(e: impl std::fmt::Display) -> GeyserPluginError {
+    GeyserPluginError::Custom(e.to_string().into())
+}
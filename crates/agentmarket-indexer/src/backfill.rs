@@ -0,0 +1,84 @@
+//! Historical replay from `getSignaturesForAddress`.
+
+use agentmarket_events::parse_logs;
+use agentmarket_metrics::Metrics;
+use agentmarket_sdk::rpc::SignatureInfo;
+use agentmarket_sdk::{Pubkey, RpcClient};
+use anyhow::Result;
+use tracing::info;
+
+use crate::store::{IndexedTransaction, Store};
+
+/// Index every transaction for `program_id` newer than its stored cursor, oldest first
+pub async fn backfill_program(
+    rpc: &RpcClient,
+    store: &Store,
+    program_id: &Pubkey,
+    page_size: usize,
+    metrics: &Metrics,
+) -> Result<()> {
+    let cursor = store.cursor(program_id).await?;
+    let mut pending: Vec<SignatureInfo> = Vec::new();
+    let mut before = None;
+
+    // Page backwards from the tip until the cursor (or the program's first transaction)
+    'paging: loop {
+        let page = rpc
+            .get_signatures_for_address(program_id, before.as_ref(), page_size)
+            .await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        before = Some(last.signature);
+        let exhausted = page.len() < page_size;
+
+        for info in page {
+            if cursor.as_deref() == Some(info.signature.to_string().as_str()) {
+                break 'paging;
+            }
+            pending.push(info);
+        }
+
+        if exhausted {
+            break;
+        }
+    }
+
+    let Some(newest) = pending.first().map(|info| info.signature.to_string()) else {
+        return Ok(());
+    };
+
+    info!(program = %program_id, transactions = pending.len(), "backfilling");
+
+    let mut indexed = 0usize;
+    for info in pending.iter().rev() {
+        if info.failed {
+            metrics.transaction_failed();
+            continue;
+        }
+        let Some(tx) = rpc.get_transaction_logs(&info.signature).await? else {
+            continue;
+        };
+
+        let events = parse_logs(&tx.logs);
+        if events.is_empty() {
+            continue;
+        }
+
+        let transaction = IndexedTransaction {
+            signature: info.signature.to_string(),
+            slot: tx.slot,
+            block_time: tx.block_time,
+            events,
+        };
+        if store.apply(&transaction).await? {
+            transaction.record(metrics);
+            indexed += 1;
+        }
+    }
+
+    store.set_cursor(program_id, &newest).await?;
+    info!(program = %program_id, indexed, "backfill complete");
+
+    Ok(())
+}
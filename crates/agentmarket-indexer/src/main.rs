@@ -0,0 +1,145 @@
+//! AgentMarket indexer: decodes program events and writes normalized rows to Postgres.
+//!
+//! On start it backfills each program from its last indexed signature, then
+//! follows new transactions over the `logsSubscribe` websocket. Writes are
+//! keyed by transaction signature, so replays and overlapping sources are safe.
+//! With `--metrics-addr` it serves Prometheus metrics, including the number
+//! of currently disputed requests.
+
+mod backfill;
+mod store;
+mod stream;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use agentmarket_metrics::Metrics;
+use agentmarket_sdk::events::INDEXED_PROGRAMS;
+use agentmarket_sdk::RpcClient;
+use anyhow::Result;
+use clap::Parser;
+use tracing::{error, info, warn};
+use tracing_subscriber::EnvFilter;
+
+use crate::store::Store;
+
+/// How often the cluster tip and the dispute count are refreshed for metrics
+const METRICS_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "agentmarket-indexer",
+    about = "Index AgentMarket program events into Postgres"
+)]
+struct Args {
+    /// JSON-RPC endpoint
+    #[arg(
+        long,
+        env = "AGENTMARKET_RPC_URL",
+        default_value = "http://127.0.0.1:8899"
+    )]
+    rpc_url: String,
+
+    /// Websocket endpoint; derived from the RPC URL when omitted
+    #[arg(long, env = "AGENTMARKET_WS_URL")]
+    ws_url: Option<String>,
+
+    /// Postgres connection string
+    #[arg(long, env = "DATABASE_URL")]
+    database_url: String,
+
+    /// Signatures requested per backfill page
+    #[arg(long, default_value_t = 1000)]
+    page_size: usize,
+
+    /// Skip the historical backfill
+    #[arg(long)]
+    no_backfill: bool,
+
+    /// Exit after the backfill instead of streaming
+    #[arg(long, conflicts_with = "no_backfill")]
+    backfill_only: bool,
+
+    /// Serve Prometheus metrics on this address
+    #[arg(long, env = "AGENTMARKET_METRICS_ADDR")]
+    metrics_addr: Option<SocketAddr>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
+        .init();
+
+    let args = Args::parse();
+    let store = Arc::new(Store::connect(&args.database_url).await?);
+    store.migrate().await?;
+
+    let rpc = RpcClient::new(args.rpc_url.clone());
+    let metrics = Metrics::new("indexer");
+    if let Some(addr) = args.metrics_addr {
+        spawn_metrics(addr, metrics, rpc.clone(), store.clone());
+    }
+    let ws_url = args
+        .ws_url
+        .clone()
+        .unwrap_or_else(|| websocket_url(&args.rpc_url));
+
+    // Subscribe before backfilling so nothing lands between the two
+    let streamer = (!args.backfill_only).then(|| {
+        let store = store.clone();
+        tokio::spawn(async move { stream::run(&ws_url, &store, &INDEXED_PROGRAMS, metrics).await })
+    });
+
+    if !args.no_backfill {
+        for program_id in &INDEXED_PROGRAMS {
+            if let Err(e) =
+                backfill::backfill_program(&rpc, &store, program_id, args.page_size, &metrics).await
+            {
+                error!(program = %program_id, "backfill failed: {e:#}");
+            }
+        }
+    }
+
+    if let Some(streamer) = streamer {
+        info!("following new transactions");
+        streamer.await?;
+    }
+
+    Ok(())
+}
+
+// Helper function to serve metrics and keep the polled ones fresh in the background
+fn spawn_metrics(addr: SocketAddr, metrics: Metrics, rpc: RpcClient, store: Arc<Store>) {
+    tokio::spawn(async move {
+        if let Err(e) = agentmarket_metrics::serve(addr).await {
+            error!("metrics server failed: {e}");
+        }
+    });
+    tokio::spawn(metrics.track_tip(rpc, METRICS_INTERVAL));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(METRICS_INTERVAL);
+        loop {
+            interval.tick().await;
+            match store.pending_disputes().await {
+                Ok(count) => metrics.set_pending_disputes(count),
+                Err(e) => warn!("failed to count pending disputes: {e:#}"),
+            }
+        }
+    });
+}
+
+// Helper function to derive the default websocket endpoint (port + 1, ws scheme)
+fn websocket_url(rpc_url: &str) -> String {
+    let url = rpc_url
+        .replacen("https://", "wss://", 1)
+        .replacen("http://", "ws://", 1);
+    let Some((host, port)) = url.rsplit_once(':') else {
+        return url;
+    };
+    match port.parse::<u16>() {
+        Ok(port) => format!("{host}:{}", port + 1),
+        Err(_) => url,
+    }
+}
@@ -0,0 +1,1224 @@
+//! Postgres schema and writes for normalized program activity.
+
+use agentmarket_events::Decoded;
+use agentmarket_metrics::Metrics;
+use agentmarket_sdk::accounts::Visibility;
+use agentmarket_sdk::events::ProgramEvent;
+use agentmarket_sdk::instructions::registry::RentTarget;
+use agentmarket_sdk::Pubkey;
+use anyhow::Result;
+use tokio::sync::Mutex;
+use tokio_postgres::{Client, NoTls, Transaction};
+use tracing::{debug, error};
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS agents (
+    agent_id TEXT PRIMARY KEY,
+    creator TEXT NOT NULL,
+    name TEXT NOT NULL,
+    nft_mint TEXT NOT NULL,
+    total_ratings BIGINT NOT NULL DEFAULT 0,
+    average_rating BIGINT NOT NULL DEFAULT 0,
+    registered_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS capabilities TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS is_active BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS is_verified BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS last_seen_at BIGINT;
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS max_concurrent_requests BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS paused_until BIGINT;
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS parent_agent TEXT;
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS lineage_royalty_bps INTEGER;
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS visibility TEXT NOT NULL DEFAULT 'public';
+-- NULL while payouts go to the creator
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS payout_wallet TEXT;
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS capability_limit INTEGER NOT NULL DEFAULT 10;
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS description_limit INTEGER NOT NULL DEFAULT 500;
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS trial_requests INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS languages TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS dependencies TEXT[] NOT NULL DEFAULT '{}';
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS soulbound BOOLEAN NOT NULL DEFAULT FALSE;
+-- Set once the NFT metadata can only change through the registry
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS metadata_locked BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS retired BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS successor TEXT;
+-- NULL while the NFT holder manages the agent
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS authority TEXT;
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS frozen BOOLEAN NOT NULL DEFAULT FALSE;
+-- Last profile layout seen through a migration; NULL until one is
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS profile_version INTEGER;
+-- Operational key and manifest hash for agents registered from a signed manifest
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS manifest_operator TEXT;
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS manifest_hash TEXT;
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS referral_code TEXT;
+-- Share of the agent's logged health checks that passed; NULL until checked
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS uptime_bps INTEGER;
+-- Asking price while the agent is listed for sale
+ALTER TABLE agents ADD COLUMN IF NOT EXISTS sale_price BIGINT;
+CREATE INDEX IF NOT EXISTS agents_capabilities_idx ON agents USING GIN (capabilities);
+CREATE INDEX IF NOT EXISTS agents_languages_idx ON agents USING GIN (languages);
+
+CREATE TABLE IF NOT EXISTS agent_versions (
+    agent_version TEXT PRIMARY KEY,
+    agent_id TEXT NOT NULL,
+    version TEXT NOT NULL,
+    ipfs_hash TEXT NOT NULL,
+    changelog_hash TEXT NOT NULL,
+    published_at BIGINT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS agent_versions_agent_idx ON agent_versions (agent_id);
+
+CREATE TABLE IF NOT EXISTS service_offerings (
+    service_offering TEXT PRIMARY KEY,
+    agent_id TEXT NOT NULL,
+    offering_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    price BIGINT NOT NULL,
+    turnaround_secs BIGINT NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL,
+    removed_at BIGINT
+);
+CREATE INDEX IF NOT EXISTS service_offerings_agent_idx ON service_offerings (agent_id);
+
+CREATE TABLE IF NOT EXISTS capability_prices (
+    capability_price TEXT PRIMARY KEY,
+    agent_id TEXT NOT NULL,
+    capability_id TEXT NOT NULL,
+    price BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS capability_prices_agent_idx ON capability_prices (agent_id);
+
+CREATE TABLE IF NOT EXISTS referral_codes (
+    referral_code TEXT PRIMARY KEY,
+    referrer TEXT NOT NULL,
+    code TEXT NOT NULL,
+    referrals BIGINT NOT NULL DEFAULT 0,
+    earned BIGINT NOT NULL DEFAULT 0,
+    swept BIGINT NOT NULL DEFAULT 0,
+    created_at BIGINT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS referral_codes_referrer_idx ON referral_codes (referrer);
+
+CREATE TABLE IF NOT EXISTS agent_sales (
+    agent_id TEXT NOT NULL,
+    seller TEXT NOT NULL,
+    buyer TEXT NOT NULL,
+    price BIGINT NOT NULL,
+    sold_at BIGINT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS agent_sales_agent_idx ON agent_sales (agent_id);
+
+CREATE TABLE IF NOT EXISTS bundles (
+    bundle TEXT PRIMARY KEY,
+    curator TEXT NOT NULL,
+    bundle_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    agents TEXT[] NOT NULL,
+    price BIGINT NOT NULL,
+    discount_bps INTEGER NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS bundles_agents_idx ON bundles USING GIN (agents);
+
+CREATE TABLE IF NOT EXISTS subscription_tiers (
+    subscription_tier TEXT PRIMARY KEY,
+    agent_id TEXT NOT NULL,
+    tier_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    monthly_price BIGINT NOT NULL,
+    requests_per_month BIGINT NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at BIGINT NOT NULL,
+    retired_at BIGINT
+);
+CREATE INDEX IF NOT EXISTS subscription_tiers_agent_idx ON subscription_tiers (agent_id);
+
+CREATE TABLE IF NOT EXISTS access_grants (
+    agent_id TEXT NOT NULL,
+    user_key TEXT NOT NULL,
+    subscription_tier TEXT NOT NULL,
+    expires_at BIGINT NOT NULL,
+    remaining_quota BIGINT NOT NULL,
+    is_revoked BOOLEAN NOT NULL DEFAULT FALSE,
+    updated_at BIGINT NOT NULL,
+    PRIMARY KEY (agent_id, user_key)
+);
+CREATE INDEX IF NOT EXISTS access_grants_user_idx ON access_grants (user_key);
+
+CREATE TABLE IF NOT EXISTS allowlist_entries (
+    agent_id TEXT NOT NULL,
+    user_key TEXT NOT NULL,
+    added_at BIGINT NOT NULL,
+    PRIMARY KEY (agent_id, user_key)
+);
+CREATE INDEX IF NOT EXISTS allowlist_entries_user_idx ON allowlist_entries (user_key);
+
+CREATE TABLE IF NOT EXISTS blocked_users (
+    agent_id TEXT NOT NULL,
+    user_key TEXT NOT NULL,
+    blocked_at BIGINT NOT NULL,
+    PRIMARY KEY (agent_id, user_key)
+);
+
+CREATE TABLE IF NOT EXISTS agent_attestations (
+    attestation TEXT PRIMARY KEY,
+    agent_id TEXT NOT NULL,
+    issuer TEXT NOT NULL,
+    schema TEXT NOT NULL,
+    data_hash TEXT NOT NULL,
+    issued_at BIGINT NOT NULL,
+    expires_at BIGINT,
+    revoked_at BIGINT
+);
+CREATE INDEX IF NOT EXISTS agent_attestations_agent_idx ON agent_attestations (agent_id);
+CREATE INDEX IF NOT EXISTS agent_attestations_schema_idx ON agent_attestations (schema, issuer);
+
+CREATE TABLE IF NOT EXISTS co_creators (
+    agent_id TEXT NOT NULL,
+    co_creator TEXT NOT NULL,
+    bps INTEGER NOT NULL,
+    PRIMARY KEY (agent_id, co_creator)
+);
+
+CREATE TABLE IF NOT EXISTS capability_schemas (
+    agent_id TEXT NOT NULL,
+    capability_id TEXT NOT NULL,
+    input_schema TEXT NOT NULL,
+    output_schema TEXT NOT NULL,
+    updated_at BIGINT NOT NULL,
+    PRIMARY KEY (agent_id, capability_id)
+);
+CREATE INDEX IF NOT EXISTS capability_schemas_input_idx ON capability_schemas (input_schema);
+
+CREATE TABLE IF NOT EXISTS requests (
+    request_id TEXT PRIMARY KEY,
+    agent_id TEXT NOT NULL,
+    user_key TEXT NOT NULL,
+    amount BIGINT NOT NULL,
+    status TEXT NOT NULL,
+    dispute_reason TEXT,
+    creator_amount BIGINT,
+    platform_amount BIGINT,
+    treasury_amount BIGINT,
+    created_at BIGINT NOT NULL,
+    completed_at BIGINT,
+    settled_at BIGINT
+);
+ALTER TABLE requests ADD COLUMN IF NOT EXISTS agent_version TEXT;
+ALTER TABLE requests ADD COLUMN IF NOT EXISTS service_offering TEXT;
+ALTER TABLE requests ADD COLUMN IF NOT EXISTS bundle TEXT;
+ALTER TABLE requests ADD COLUMN IF NOT EXISTS capability_price TEXT;
+ALTER TABLE requests ADD COLUMN IF NOT EXISTS payment_mint TEXT;
+CREATE INDEX IF NOT EXISTS requests_agent_idx ON requests (agent_id);
+CREATE INDEX IF NOT EXISTS requests_user_idx ON requests (user_key);
+
+CREATE TABLE IF NOT EXISTS ratings (
+    rating_id TEXT PRIMARY KEY,
+    agent_id TEXT NOT NULL,
+    user_key TEXT NOT NULL,
+    stars SMALLINT NOT NULL,
+    is_reported BOOLEAN NOT NULL DEFAULT FALSE,
+    report_reason TEXT,
+    is_moderated BOOLEAN NOT NULL DEFAULT FALSE,
+    is_valid BOOLEAN,
+    moderated_by TEXT,
+    slot BIGINT NOT NULL,
+    created_at BIGINT
+);
+CREATE INDEX IF NOT EXISTS ratings_agent_idx ON ratings (agent_id);
+
+CREATE TABLE IF NOT EXISTS rent_top_ups (
+    agent_id TEXT NOT NULL,
+    account TEXT NOT NULL,
+    target TEXT NOT NULL,
+    funder TEXT NOT NULL,
+    amount BIGINT NOT NULL,
+    funded_at BIGINT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS rent_top_ups_agent_idx ON rent_top_ups (agent_id);
+
+CREATE TABLE IF NOT EXISTS distributions (
+    distribution_id TEXT PRIMARY KEY,
+    creator TEXT NOT NULL,
+    total_amount BIGINT NOT NULL,
+    creator_amount BIGINT NOT NULL,
+    platform_amount BIGINT NOT NULL,
+    treasury_amount BIGINT NOT NULL,
+    slot BIGINT NOT NULL,
+    created_at BIGINT
+);
+
+CREATE TABLE IF NOT EXISTS indexed_transactions (
+    signature TEXT PRIMARY KEY,
+    slot BIGINT NOT NULL,
+    block_time BIGINT,
+    event_count INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS indexer_cursors (
+    program_id TEXT PRIMARY KEY,
+    newest_signature TEXT NOT NULL
+);
+"#;
+
+/// A confirmed transaction's decoded events, ready to be written
+pub struct IndexedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    /// Upgraded to their current layout, whichever program version emitted them
+    pub events: Vec<(Pubkey, Decoded)>,
+}
+
+impl IndexedTransaction {
+    /// Count a newly written transaction toward the service metrics
+    pub fn record(&self, metrics: &Metrics) {
+        metrics.slot_processed(self.slot);
+        for (_, decoded) in &self.events {
+            metrics.event_processed(decoded.event.name());
+        }
+    }
+}
+
+/// Shared Postgres handle; writes are serialized so each transaction commits atomically
+pub struct Store {
+    client: Mutex<Client>,
+}
+
+impl Store {
+    pub async fn connect(database_url: &str) -> Result<Self> {
+        let (client, connection) = tokio_postgres::connect(database_url, NoTls).await?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                error!("postgres connection closed: {e}");
+            }
+        });
+
+        Ok(Self {
+            client: Mutex::new(client),
+        })
+    }
+
+    /// Create tables and indexes if they do not exist
+    pub async fn migrate(&self) -> Result<()> {
+        self.client.lock().await.batch_execute(SCHEMA).await?;
+        Ok(())
+    }
+
+    pub async fn cursor(&self, program_id: &Pubkey) -> Result<Option<String>> {
+        let row = self
+            .client
+            .lock()
+            .await
+            .query_opt(
+                "SELECT newest_signature FROM indexer_cursors WHERE program_id = $1",
+                &[&program_id.to_string()],
+            )
+            .await?;
+
+        Ok(row.map(|r| r.get(0)))
+    }
+
+    pub async fn set_cursor(&self, program_id: &Pubkey, signature: &str) -> Result<()> {
+        self.client
+            .lock()
+            .await
+            .execute(
+                "INSERT INTO indexer_cursors (program_id, newest_signature) VALUES ($1, $2)
+                 ON CONFLICT (program_id) DO UPDATE SET newest_signature = EXCLUDED.newest_signature",
+                &[&program_id.to_string(), &signature],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Requests whose result is currently disputed
+    pub async fn pending_disputes(&self) -> Result<u64> {
+        let row = self
+            .client
+            .lock()
+            .await
+            .query_one(
+                "SELECT COUNT(*) FROM requests WHERE status = 'disputed'",
+                &[],
+            )
+            .await?;
+
+        Ok(row.get::<_, i64>(0) as u64)
+    }
+
+    /// Write a transaction's events once; returns false if it was already indexed
+    pub async fn apply(&self, indexed: &IndexedTransaction) -> Result<bool> {
+        let mut client = self.client.lock().await;
+        let tx = client.transaction().await?;
+
+        let inserted = tx
+            .execute(
+                "INSERT INTO indexed_transactions (signature, slot, block_time, event_count)
+                 VALUES ($1, $2, $3, $4) ON CONFLICT (signature) DO NOTHING",
+                &[
+                    &indexed.signature,
+                    &(indexed.slot as i64),
+                    &indexed.block_time,
+                    &(indexed.events.len() as i32),
+                ],
+            )
+            .await?;
+
+        if inserted == 0 {
+            return Ok(false);
+        }
+
+        for (_, decoded) in &indexed.events {
+            apply_event(&tx, decoded, indexed.slot as i64, indexed.block_time).await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+}
+
+// Helper function to map one decoded event onto the normalized tables
+async fn apply_event(
+    tx: &Transaction<'_>,
+    decoded: &Decoded,
+    slot: i64,
+    block_time: Option<i64>,
+) -> Result<()> {
+    match &decoded.event {
+        ProgramEvent::AgentRegistered(e) => {
+            tx.execute(
+                "INSERT INTO agents (agent_id, creator, name, capabilities, nft_mint,
+                     registered_at, updated_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $6)
+                 ON CONFLICT (agent_id) DO UPDATE SET creator = EXCLUDED.creator,
+                     name = EXCLUDED.name, capabilities = EXCLUDED.capabilities,
+                     nft_mint = EXCLUDED.nft_mint, registered_at = EXCLUDED.registered_at",
+                &[
+                    &e.agent_id.to_string(),
+                    &e.creator.to_string(),
+                    &e.name,
+                    &e.capabilities,
+                    &e.nft_mint.to_string(),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        // Updates emitted before the event carried the name and flag only move the timestamp
+        ProgramEvent::AgentUpdated(e) if !decoded.has("name") => {
+            tx.execute(
+                "UPDATE agents SET updated_at = $2 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentUpdated(e) => {
+            tx.execute(
+                "UPDATE agents SET name = $2, is_active = $3, updated_at = $4 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &e.name, &e.is_active, &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentOwnershipTransferred(e) => {
+            tx.execute(
+                "UPDATE agents SET creator = $2, payout_wallet = NULL, authority = NULL,
+                 updated_at = $3 WHERE agent_id = $1",
+                &[
+                    &e.agent_id.to_string(),
+                    &e.new_owner.to_string(),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentListedForSale(e) => {
+            tx.execute(
+                "UPDATE agents SET sale_price = $2 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &(e.price as i64)],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentSaleCancelled(e) => {
+            tx.execute(
+                "UPDATE agents SET sale_price = NULL WHERE agent_id = $1",
+                &[&e.agent_id.to_string()],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentSold(e) => {
+            tx.execute(
+                "UPDATE agents SET sale_price = NULL WHERE agent_id = $1",
+                &[&e.agent_id.to_string()],
+            )
+            .await?;
+            tx.execute(
+                "INSERT INTO agent_sales (agent_id, seller, buyer, price, sold_at)
+                 VALUES ($1, $2, $3, $4, $5)",
+                &[
+                    &e.agent_id.to_string(),
+                    &e.seller.to_string(),
+                    &e.buyer.to_string(),
+                    &(e.price as i64),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentVersionPublished(e) => {
+            tx.execute(
+                "INSERT INTO agent_versions
+                     (agent_version, agent_id, version, ipfs_hash, changelog_hash, published_at)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (agent_version) DO NOTHING",
+                &[
+                    &e.agent_version.to_string(),
+                    &e.agent_id.to_string(),
+                    &format!("{}.{}.{}", e.major, e.minor, e.patch),
+                    &e.ipfs_hash,
+                    &hex(&e.changelog_hash),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        // Offering ids can be reused once removed, so a re-add starts the row over
+        ProgramEvent::ServiceOfferingAdded(e) => {
+            tx.execute(
+                "INSERT INTO service_offerings (service_offering, agent_id, offering_id, name,
+                     price, turnaround_secs, created_at, updated_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $7)
+                 ON CONFLICT (service_offering) DO UPDATE SET name = EXCLUDED.name,
+                     price = EXCLUDED.price, turnaround_secs = EXCLUDED.turnaround_secs,
+                     is_active = TRUE, created_at = EXCLUDED.created_at,
+                     updated_at = EXCLUDED.updated_at, removed_at = NULL",
+                &[
+                    &e.service_offering.to_string(),
+                    &e.agent_id.to_string(),
+                    &(e.offering_id as i32),
+                    &e.name,
+                    &(e.price as i64),
+                    &(e.turnaround_secs as i64),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::ServiceOfferingUpdated(e) => {
+            tx.execute(
+                "UPDATE service_offerings SET price = $2, turnaround_secs = $3, is_active = $4,
+                     updated_at = $5 WHERE service_offering = $1",
+                &[
+                    &e.service_offering.to_string(),
+                    &(e.price as i64),
+                    &(e.turnaround_secs as i64),
+                    &e.is_active,
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::ServiceOfferingRemoved(e) => {
+            tx.execute(
+                "UPDATE service_offerings SET is_active = FALSE, removed_at = $2
+                 WHERE service_offering = $1",
+                &[&e.service_offering.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::CapabilityPriceSet(e) => {
+            tx.execute(
+                "INSERT INTO capability_prices (capability_price, agent_id, capability_id, price,
+                     updated_at)
+                 VALUES ($1, $2, $3, $4, $5)
+                 ON CONFLICT (capability_price) DO UPDATE SET price = EXCLUDED.price,
+                     updated_at = EXCLUDED.updated_at",
+                &[
+                    &e.capability_price.to_string(),
+                    &e.agent_id.to_string(),
+                    &e.capability_id,
+                    &(e.price as i64),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::CapabilityPriceRemoved(e) => {
+            tx.execute(
+                "DELETE FROM capability_prices WHERE capability_price = $1",
+                &[&e.capability_price.to_string()],
+            )
+            .await?;
+        }
+        ProgramEvent::BundleCreated(e) => {
+            let agents: Vec<String> = e.agents.iter().map(|agent| agent.to_string()).collect();
+            tx.execute(
+                "INSERT INTO bundles (bundle, curator, bundle_id, name, agents, price,
+                     discount_bps, created_at, updated_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)
+                 ON CONFLICT (bundle) DO NOTHING",
+                &[
+                    &e.bundle.to_string(),
+                    &e.curator.to_string(),
+                    &(e.bundle_id as i32),
+                    &e.name,
+                    &agents,
+                    &(e.price as i64),
+                    &(e.discount_bps as i32),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::BundleUpdated(e) => {
+            tx.execute(
+                "UPDATE bundles SET price = $2, discount_bps = $3, is_active = $4, updated_at = $5
+                 WHERE bundle = $1",
+                &[
+                    &e.bundle.to_string(),
+                    &(e.price as i64),
+                    &(e.discount_bps as i32),
+                    &e.is_active,
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::SubscriptionTierCreated(e) => {
+            tx.execute(
+                "INSERT INTO subscription_tiers (subscription_tier, agent_id, tier_id, name,
+                     monthly_price, requests_per_month, created_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)
+                 ON CONFLICT (subscription_tier) DO NOTHING",
+                &[
+                    &e.subscription_tier.to_string(),
+                    &e.agent_id.to_string(),
+                    &(e.tier_id as i32),
+                    &e.name,
+                    &(e.monthly_price as i64),
+                    &(e.requests_per_month as i64),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::SubscriptionTierRetired(e) => {
+            tx.execute(
+                "UPDATE subscription_tiers SET is_active = FALSE, retired_at = $2
+                 WHERE subscription_tier = $1",
+                &[&e.subscription_tier.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        // A purchase either starts the grant over or extends it, so the event
+        // carries the resulting expiry and quota
+        ProgramEvent::AccessGrantIssued(e) => {
+            tx.execute(
+                "INSERT INTO access_grants (agent_id, user_key, subscription_tier, expires_at,
+                     remaining_quota, updated_at)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (agent_id, user_key) DO UPDATE
+                 SET subscription_tier = $3, expires_at = $4, remaining_quota = $5,
+                     is_revoked = FALSE, updated_at = $6",
+                &[
+                    &e.agent_id.to_string(),
+                    &e.user.to_string(),
+                    &e.subscription_tier.to_string(),
+                    &e.expires_at,
+                    &(e.remaining_quota as i64),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::AccessConsumed(e) => {
+            tx.execute(
+                "UPDATE access_grants SET remaining_quota = $3, updated_at = $4
+                 WHERE agent_id = $1 AND user_key = $2",
+                &[
+                    &e.agent_id.to_string(),
+                    &e.user.to_string(),
+                    &(e.remaining_quota as i64),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::AccessGrantRevoked(e) => {
+            tx.execute(
+                "UPDATE access_grants SET is_revoked = TRUE, updated_at = $3
+                 WHERE agent_id = $1 AND user_key = $2",
+                &[&e.agent_id.to_string(), &e.user.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        // The profile account is closed, but its history stays queryable
+        ProgramEvent::AgentDeregistered(e) => {
+            tx.execute(
+                "UPDATE agents SET is_active = FALSE, updated_at = $2 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentRetired(e) => {
+            tx.execute(
+                "UPDATE agents SET is_active = FALSE, retired = TRUE, successor = $2, updated_at = $3
+                 WHERE agent_id = $1",
+                &[
+                    &e.agent_id.to_string(),
+                    &e.successor.map(|successor| successor.to_string()),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentAuthoritySet(e) => {
+            tx.execute(
+                "UPDATE agents SET authority = $2, updated_at = $3 WHERE agent_id = $1",
+                &[
+                    &e.agent_id.to_string(),
+                    &e.authority.map(|authority| authority.to_string()),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::HealthCheckSubmitted(e) => {
+            tx.execute(
+                "UPDATE agents SET uptime_bps = $2 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &(e.uptime_bps as i32)],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentHeartbeat(e) => {
+            tx.execute(
+                "UPDATE agents SET last_seen_at = $2 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentMarkedStale(e) => {
+            tx.execute(
+                "UPDATE agents SET is_active = FALSE, updated_at = $2 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentCapacityUpdated(e) => {
+            tx.execute(
+                "UPDATE agents SET max_concurrent_requests = $2, updated_at = $3 WHERE agent_id = $1",
+                &[
+                    &e.agent_id.to_string(),
+                    &(e.max_concurrent_requests as i64),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentListingsPaused(e) => {
+            tx.execute(
+                "UPDATE agents SET paused_until = $2, updated_at = $3 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &e.paused_until, &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentListingsResumed(e) => {
+            tx.execute(
+                "UPDATE agents SET paused_until = NULL, updated_at = $2 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentVisibilitySet(e) => {
+            let visibility = match e.visibility {
+                Visibility::Public => "public",
+                Visibility::Allowlist => "allowlist",
+            };
+            tx.execute(
+                "UPDATE agents SET visibility = $2, updated_at = $3 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &visibility, &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::TrialRequestsSet(e) => {
+            tx.execute(
+                "UPDATE agents SET trial_requests = $2, updated_at = $3 WHERE agent_id = $1",
+                &[
+                    &e.agent_id.to_string(),
+                    &(e.trial_requests as i32),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::LanguagesSet(e) => {
+            tx.execute(
+                "UPDATE agents SET languages = $2, updated_at = $3 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &e.languages, &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::DependenciesSet(e) => {
+            let dependencies: Vec<String> = e
+                .dependencies
+                .iter()
+                .map(|dependency| dependency.to_string())
+                .collect();
+            tx.execute(
+                "UPDATE agents SET dependencies = $2, updated_at = $3 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &dependencies, &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::PayoutWalletSet(e) => {
+            tx.execute(
+                "UPDATE agents SET payout_wallet = $2, updated_at = $3 WHERE agent_id = $1",
+                &[
+                    &e.agent_id.to_string(),
+                    &e.payout_wallet.to_string(),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::ProfileResized(e) => {
+            tx.execute(
+                "UPDATE agents SET capability_limit = $2, description_limit = $3, updated_at = $4
+                 WHERE agent_id = $1",
+                &[
+                    &e.agent_id.to_string(),
+                    &(e.capability_limit as i32),
+                    &(e.description_limit as i32),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentCapabilitiesAdded(e) => {
+            tx.execute(
+                "UPDATE agents SET capabilities = capabilities || $2, updated_at = $3
+                 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &e.capabilities, &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentCapabilitiesRemoved(e) => {
+            tx.execute(
+                "UPDATE agents SET capabilities = ARRAY(
+                     SELECT id FROM unnest(capabilities) AS id WHERE id <> ALL($2)
+                 ), updated_at = $3
+                 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &e.capabilities, &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::RentToppedUp(e) => {
+            let target = match e.target {
+                RentTarget::Profile => "profile",
+                RentTarget::Capabilities => "capabilities",
+                RentTarget::HealthLog => "health_log",
+                RentTarget::Analytics => "analytics",
+            };
+            tx.execute(
+                "INSERT INTO rent_top_ups (agent_id, account, target, funder, amount, funded_at)
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+                &[
+                    &e.agent_id.to_string(),
+                    &e.account.to_string(),
+                    &target,
+                    &e.funder.to_string(),
+                    &(e.amount as i64),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::ProfileMigrated(e) => {
+            tx.execute(
+                "UPDATE agents SET profile_version = $2, updated_at = $3 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &(e.version as i32), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::AllowlistEntryAdded(e) => {
+            tx.execute(
+                "INSERT INTO allowlist_entries (agent_id, user_key, added_at)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (agent_id, user_key) DO UPDATE SET added_at = $3",
+                &[&e.agent_id.to_string(), &e.user.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::AllowlistEntryRemoved(e) => {
+            tx.execute(
+                "DELETE FROM allowlist_entries WHERE agent_id = $1 AND user_key = $2",
+                &[&e.agent_id.to_string(), &e.user.to_string()],
+            )
+            .await?;
+        }
+        ProgramEvent::UserBlocked(e) => {
+            tx.execute(
+                "INSERT INTO blocked_users (agent_id, user_key, blocked_at)
+                 VALUES ($1, $2, $3)
+                 ON CONFLICT (agent_id, user_key) DO UPDATE SET blocked_at = $3",
+                &[&e.agent_id.to_string(), &e.user.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::UserUnblocked(e) => {
+            tx.execute(
+                "DELETE FROM blocked_users WHERE agent_id = $1 AND user_key = $2",
+                &[&e.agent_id.to_string(), &e.user.to_string()],
+            )
+            .await?;
+        }
+        ProgramEvent::ReferralCodeCreated(e) => {
+            tx.execute(
+                "INSERT INTO referral_codes (referral_code, referrer, code, created_at)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (referral_code) DO NOTHING",
+                &[
+                    &e.referral_code.to_string(),
+                    &e.referrer.to_string(),
+                    &e.code,
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentReferred(e) => {
+            tx.execute(
+                "UPDATE referral_codes SET referrals = referrals + 1, earned = earned + $2
+                 WHERE referral_code = $1",
+                &[&e.referral_code.to_string(), &(e.share as i64)],
+            )
+            .await?;
+            tx.execute(
+                "UPDATE agents SET referral_code = $2 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &e.referral_code.to_string()],
+            )
+            .await?;
+        }
+        ProgramEvent::ReferralFeesSwept(e) => {
+            tx.execute(
+                "UPDATE referral_codes SET swept = swept + $2 WHERE referral_code = $1",
+                &[&e.referral_code.to_string(), &(e.amount as i64)],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentManifestRegistered(e) => {
+            tx.execute(
+                "UPDATE agents SET manifest_operator = $2, manifest_hash = $3 WHERE agent_id = $1",
+                &[
+                    &e.agent_id.to_string(),
+                    &e.operator.to_string(),
+                    &hex(&e.manifest_hash),
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentForked(e) => {
+            tx.execute(
+                "UPDATE agents SET parent_agent = $2, lineage_royalty_bps = $3 WHERE agent_id = $1",
+                &[
+                    &e.agent_id.to_string(),
+                    &e.parent_agent.to_string(),
+                    &(e.lineage_royalty_bps as i32),
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::MetadataAuthorityMigrated(e) => {
+            tx.execute(
+                "UPDATE agents SET metadata_locked = TRUE, updated_at = $2 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentSoulbound(e) => {
+            tx.execute(
+                "UPDATE agents SET soulbound = TRUE WHERE agent_id = $1",
+                &[&e.agent_id.to_string()],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentVerified(e) => {
+            tx.execute(
+                "UPDATE agents SET is_verified = TRUE, updated_at = $2 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentVerificationRevoked(e) => {
+            tx.execute(
+                "UPDATE agents SET is_verified = FALSE, updated_at = $2 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentFrozen(e) => {
+            tx.execute(
+                "UPDATE agents SET frozen = TRUE, updated_at = $2 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentUnfrozen(e) => {
+            tx.execute(
+                "UPDATE agents SET frozen = FALSE, updated_at = $2 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        // Issuing again renews the attestation in place
+        ProgramEvent::AgentAttestationIssued(e) => {
+            tx.execute(
+                "INSERT INTO agent_attestations (attestation, agent_id, issuer, schema, data_hash,
+                     issued_at, expires_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)
+                 ON CONFLICT (attestation) DO UPDATE SET data_hash = EXCLUDED.data_hash,
+                     issued_at = EXCLUDED.issued_at, expires_at = EXCLUDED.expires_at,
+                     revoked_at = NULL",
+                &[
+                    &e.attestation.to_string(),
+                    &e.agent_id.to_string(),
+                    &e.issuer.to_string(),
+                    &hex(&e.schema),
+                    &hex(&e.data_hash),
+                    &e.timestamp,
+                    &e.expires_at,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentAttestationRevoked(e) => {
+            tx.execute(
+                "UPDATE agent_attestations SET revoked_at = $2 WHERE attestation = $1",
+                &[&e.attestation.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::CoCreatorsUpdated(e) => {
+            let agent_id = e.agent_id.to_string();
+            tx.execute("DELETE FROM co_creators WHERE agent_id = $1", &[&agent_id])
+                .await?;
+            for co_creator in &e.co_creators {
+                tx.execute(
+                    "INSERT INTO co_creators (agent_id, co_creator, bps) VALUES ($1, $2, $3)",
+                    &[
+                        &agent_id,
+                        &co_creator.key.to_string(),
+                        &(co_creator.bps as i32),
+                    ],
+                )
+                .await?;
+            }
+        }
+        ProgramEvent::CapabilitySchemaSet(e) => {
+            tx.execute(
+                "INSERT INTO capability_schemas
+                     (agent_id, capability_id, input_schema, output_schema, updated_at)
+                 VALUES ($1, $2, $3, $4, $5)
+                 ON CONFLICT (agent_id, capability_id) DO UPDATE
+                 SET input_schema = $3, output_schema = $4, updated_at = $5",
+                &[
+                    &e.agent_id.to_string(),
+                    &e.capability_id,
+                    &e.input_schema,
+                    &e.output_schema,
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::CapabilitySchemaCleared(e) => {
+            tx.execute(
+                "DELETE FROM capability_schemas WHERE agent_id = $1 AND capability_id = $2",
+                &[&e.agent_id.to_string(), &e.capability_id],
+            )
+            .await?;
+        }
+        ProgramEvent::ServiceRequestCreated(e) => {
+            tx.execute(
+                "INSERT INTO requests (request_id, agent_id, user_key, amount, payment_mint,
+                     status, created_at)
+                 VALUES ($1, $2, $3, $4, $5, 'pending', $6)
+                 ON CONFLICT (request_id) DO UPDATE SET agent_id = EXCLUDED.agent_id,
+                     user_key = EXCLUDED.user_key, amount = EXCLUDED.amount,
+                     payment_mint = EXCLUDED.payment_mint, status = 'pending', dispute_reason = NULL, creator_amount = NULL,
+                     platform_amount = NULL, treasury_amount = NULL,
+                     created_at = EXCLUDED.created_at, completed_at = NULL, settled_at = NULL,
+                     agent_version = NULL, service_offering = NULL, bundle = NULL,
+                     capability_price = NULL",
+                &[
+                    &e.request_id.to_string(),
+                    &e.agent_id.to_string(),
+                    &e.user.to_string(),
+                    &(e.amount as i64),
+                    &e.payment_mint.map(|mint| mint.to_string()),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::AgentVersionPinned(e) => {
+            tx.execute(
+                "UPDATE requests SET agent_version = $2 WHERE request_id = $1",
+                &[&e.request_id.to_string(), &e.agent_version.to_string()],
+            )
+            .await?;
+        }
+        ProgramEvent::ServiceOfferingSelected(e) => {
+            tx.execute(
+                "UPDATE requests SET service_offering = $2 WHERE request_id = $1",
+                &[&e.request_id.to_string(), &e.service_offering.to_string()],
+            )
+            .await?;
+        }
+        ProgramEvent::CapabilitySelected(e) => {
+            tx.execute(
+                "UPDATE requests SET capability_price = $2 WHERE request_id = $1",
+                &[&e.request_id.to_string(), &e.capability_price.to_string()],
+            )
+            .await?;
+        }
+        ProgramEvent::BundleSelected(e) => {
+            tx.execute(
+                "UPDATE requests SET bundle = $2 WHERE request_id = $1",
+                &[&e.request_id.to_string(), &e.bundle.to_string()],
+            )
+            .await?;
+        }
+        ProgramEvent::ResultSubmitted(e) => {
+            tx.execute(
+                "UPDATE requests SET status = 'completed', completed_at = $2 WHERE request_id = $1",
+                &[&e.request_id.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::PaymentReleased(e) => {
+            tx.execute(
+                "UPDATE requests SET status = 'approved', creator_amount = $2,
+                     platform_amount = $3, treasury_amount = $4, settled_at = $5
+                 WHERE request_id = $1",
+                &[
+                    &e.request_id.to_string(),
+                    &(e.creator_amount as i64),
+                    &(e.platform_amount as i64),
+                    &(e.treasury_amount as i64),
+                    &e.timestamp,
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::ResultDisputed(e) => {
+            tx.execute(
+                "UPDATE requests SET status = 'disputed', dispute_reason = $2 WHERE request_id = $1",
+                &[&e.request_id.to_string(), &e.reason],
+            )
+            .await?;
+        }
+        ProgramEvent::RequestCancelled(e) => {
+            tx.execute(
+                "UPDATE requests SET status = 'cancelled', settled_at = $2 WHERE request_id = $1",
+                &[&e.request_id.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::RequestExpired(e) => {
+            tx.execute(
+                "UPDATE requests SET status = 'expired', settled_at = $2 WHERE request_id = $1",
+                &[&e.request_id.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::DisputeResolved(e) => {
+            tx.execute(
+                "UPDATE requests SET status = 'resolved', settled_at = $2 WHERE request_id = $1",
+                &[&e.request_id.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::PanelSettled(e) => {
+            tx.execute(
+                "UPDATE requests SET status = 'resolved', settled_at = $2 WHERE request_id = $1",
+                &[&e.request_id.to_string(), &e.timestamp],
+            )
+            .await?;
+        }
+        ProgramEvent::RatingSubmitted(e) => {
+            tx.execute(
+                "INSERT INTO ratings (rating_id, agent_id, user_key, stars, slot, created_at)
+                 VALUES ($1, $2, $3, $4, $5, $6) ON CONFLICT (rating_id) DO NOTHING",
+                &[
+                    &e.rating_id.to_string(),
+                    &e.agent_id.to_string(),
+                    &e.user.to_string(),
+                    &(e.stars as i16),
+                    &slot,
+                    &block_time,
+                ],
+            )
+            .await?;
+            tx.execute(
+                "UPDATE agents SET total_ratings = total_ratings + 1, average_rating = $2
+                 WHERE agent_id = $1",
+                &[&e.agent_id.to_string(), &(e.new_average as i64)],
+            )
+            .await?;
+        }
+        ProgramEvent::RatingReported(e) => {
+            tx.execute(
+                "UPDATE ratings SET is_reported = TRUE, report_reason = $2 WHERE rating_id = $1",
+                &[&e.rating_id.to_string(), &e.reason],
+            )
+            .await?;
+        }
+        ProgramEvent::RatingModerated(e) => {
+            tx.execute(
+                "UPDATE ratings SET is_moderated = TRUE, is_valid = $2, moderated_by = $3
+                 WHERE rating_id = $1",
+                &[
+                    &e.rating_id.to_string(),
+                    &e.is_valid,
+                    &e.moderator.to_string(),
+                ],
+            )
+            .await?;
+        }
+        ProgramEvent::PaymentDistributed(e) => {
+            tx.execute(
+                "INSERT INTO distributions (distribution_id, creator, total_amount, creator_amount,
+                     platform_amount, treasury_amount, slot, created_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8) ON CONFLICT (distribution_id) DO NOTHING",
+                &[
+                    &e.distribution_id.to_string(),
+                    &e.creator.to_string(),
+                    &(e.total_amount as i64),
+                    &(e.creator_amount as i64),
+                    &(e.platform_amount as i64),
+                    &(e.treasury_amount as i64),
+                    &slot,
+                    &block_time,
+                ],
+            )
+            .await?;
+        }
+        // Config and admin events are not part of the normalized schema
+        _ => debug!("skipping non-normalized event at slot {slot}"),
+    }
+
+    Ok(())
+}
+
+// Helper function to render a hash as lowercase hex
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
@@ -0,0 +1,117 @@
+//! Live indexing over the `logsSubscribe` websocket.
+
+use std::time::Duration;
+
+use agentmarket_events::parse_logs;
+use agentmarket_metrics::Metrics;
+use agentmarket_sdk::Pubkey;
+use anyhow::{anyhow, Result};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{info, warn};
+
+use crate::store::{IndexedTransaction, Store};
+
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Stream program logs forever, reconnecting with backoff on failure
+pub async fn run(ws_url: &str, store: &Store, programs: &[Pubkey], metrics: Metrics) {
+    let mut backoff = Duration::from_secs(1);
+
+    loop {
+        match stream_once(ws_url, store, programs, &metrics).await {
+            Ok(()) => warn!("websocket closed by server"),
+            Err(e) => warn!("websocket error: {e:#}"),
+        }
+
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+// Helper function to hold one websocket session until it drops
+async fn stream_once(
+    ws_url: &str,
+    store: &Store,
+    programs: &[Pubkey],
+    metrics: &Metrics,
+) -> Result<()> {
+    let (mut socket, _) = connect_async(ws_url).await?;
+
+    for (id, program_id) in programs.iter().enumerate() {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "logsSubscribe",
+            "params": [{"mentions": [program_id.to_string()]}, {"commitment": "confirmed"}],
+        });
+        socket
+            .send(Message::Text(request.to_string().into()))
+            .await?;
+    }
+
+    info!(
+        url = ws_url,
+        programs = programs.len(),
+        "subscribed to program logs"
+    );
+
+    while let Some(message) = socket.next().await {
+        let text = match message? {
+            Message::Text(text) => text,
+            Message::Ping(payload) => {
+                socket.send(Message::Pong(payload)).await?;
+                continue;
+            }
+            Message::Close(_) => return Ok(()),
+            _ => continue,
+        };
+
+        let value: Value = serde_json::from_str(&text)?;
+        if value.get("method").and_then(Value::as_str) != Some("logsNotification") {
+            if let Some(error) = value.get("error") {
+                return Err(anyhow!("subscription rejected: {error}"));
+            }
+            continue;
+        }
+
+        if let Some(indexed) = parse_notification(&value, metrics) {
+            if store.apply(&indexed).await? {
+                indexed.record(metrics);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Helper function to turn a logsNotification into decoded events, skipping failed transactions
+fn parse_notification(value: &Value, metrics: &Metrics) -> Option<IndexedTransaction> {
+    let result = value.pointer("/params/result")?;
+    let notification = result.get("value")?;
+
+    if !notification.get("err").is_none_or(Value::is_null) {
+        metrics.transaction_failed();
+        return None;
+    }
+
+    let logs: Vec<&str> = notification
+        .get("logs")?
+        .as_array()?
+        .iter()
+        .filter_map(Value::as_str)
+        .collect();
+    let events = parse_logs(&logs);
+    if events.is_empty() {
+        return None;
+    }
+
+    Some(IndexedTransaction {
+        signature: notification.get("signature")?.as_str()?.to_owned(),
+        slot: result.pointer("/context/slot")?.as_u64()?,
+        block_time: None,
+        events,
+    })
+}
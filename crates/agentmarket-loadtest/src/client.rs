@@ -0,0 +1,139 @@
+//! Thin transaction layer: send, confirm, and fetch landed transactions for analysis.
+
+use std::time::Duration;
+
+use agentmarket_sdk::{Instruction, Pubkey, RpcClient, SdkError};
+use serde_json::{json, Value};
+use solana_keypair::Keypair;
+use solana_signature::Signature;
+use solana_signer::Signer;
+
+/// How often signature statuses are polled while confirming
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Why a transaction did not land
+#[derive(Debug)]
+pub enum TxError {
+    /// Rejected before landing, usually by preflight simulation
+    Rejected(String),
+    /// Landed with an error
+    Failed(String),
+    /// Not confirmed before the timeout
+    Timeout,
+}
+
+impl TxError {
+    /// Grouping key for the failure breakdown
+    pub fn describe(&self) -> String {
+        match self {
+            TxError::Rejected(message) => format!("rejected: {message}"),
+            TxError::Failed(err) => format!("failed: {err}"),
+            TxError::Timeout => "not confirmed in time".into(),
+        }
+    }
+}
+
+impl From<SdkError> for TxError {
+    fn from(e: SdkError) -> Self {
+        TxError::Rejected(e.to_string())
+    }
+}
+
+/// A landed transaction's cost and write set
+pub struct TxInfo {
+    pub slot: u64,
+    pub compute_units: Option<u64>,
+    pub writable: Vec<Pubkey>,
+}
+
+pub struct Client {
+    pub rpc: RpcClient,
+    pub funder: Keypair,
+    pub confirm_timeout: Duration,
+}
+
+impl Client {
+    /// Send instructions with `payer` paying fees and wait for `confirmed`
+    pub async fn send(
+        &self,
+        instructions: &[Instruction],
+        payer: &Keypair,
+        signers: &[&Keypair],
+    ) -> Result<Signature, TxError> {
+        let mut all: Vec<&Keypair> = vec![payer];
+        all.extend(signers.iter().filter(|s| s.pubkey() != payer.pubkey()));
+        let signature = self
+            .rpc
+            .send_instructions(instructions, &payer.pubkey(), &all)
+            .await?;
+        self.confirm(&signature).await?;
+        Ok(signature)
+    }
+
+    /// Poll until `signature` is confirmed or fails
+    pub async fn confirm(&self, signature: &Signature) -> Result<(), TxError> {
+        let deadline = tokio::time::Instant::now() + self.confirm_timeout;
+
+        loop {
+            let result = self
+                .rpc
+                .call("getSignatureStatuses", json!([[signature.to_string()]]))
+                .await?;
+            let status = result.pointer("/value/0").unwrap_or(&Value::Null);
+
+            if !status.is_null() {
+                if let Some(err) = status.get("err").filter(|e| !e.is_null()) {
+                    return Err(TxError::Failed(err.to_string()));
+                }
+                let level = status.get("confirmationStatus").and_then(Value::as_str);
+                if matches!(level, Some("confirmed" | "finalized")) {
+                    return Ok(());
+                }
+            }
+
+            if tokio::time::Instant::now() >= deadline {
+                return Err(TxError::Timeout);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Slot, compute units, and writable accounts of a landed transaction
+    pub async fn transaction(&self, signature: &Signature) -> Result<Option<TxInfo>, SdkError> {
+        let result = self
+            .rpc
+            .call(
+                "getTransaction",
+                json!([
+                    signature.to_string(),
+                    {"encoding": "jsonParsed", "commitment": "confirmed", "maxSupportedTransactionVersion": 0}
+                ]),
+            )
+            .await?;
+        if result.is_null() {
+            return Ok(None);
+        }
+
+        let writable = result
+            .pointer("/transaction/message/accountKeys")
+            .and_then(Value::as_array)
+            .map(|keys| {
+                keys.iter()
+                    .filter(|key| key.get("writable").and_then(Value::as_bool) == Some(true))
+                    .filter_map(|key| key.get("pubkey")?.as_str()?.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Some(TxInfo {
+            slot: result
+                .get("slot")
+                .and_then(Value::as_u64)
+                .unwrap_or_default(),
+            compute_units: result
+                .pointer("/meta/computeUnitsConsumed")
+                .and_then(Value::as_u64),
+            writable,
+        }))
+    }
+}
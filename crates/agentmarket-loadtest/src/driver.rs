@@ -0,0 +1,133 @@
+//! Open-loop load generator: starts create/submit/approve cycles at a fixed rate.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use agentmarket_sdk::instructions::escrow;
+use agentmarket_sdk::pda;
+use solana_signer::Signer;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::MissedTickBehavior;
+
+use crate::client::Client;
+use crate::setup::Fixture;
+use crate::stats::{Collector, Stage};
+
+pub struct LoadConfig {
+    /// Cycles started per second
+    pub rate: f64,
+    pub duration: Duration,
+    /// Cycles allowed in flight before new ones are dropped as throttled
+    pub max_in_flight: usize,
+    /// Lamports escrowed per request
+    pub amount: u64,
+}
+
+/// Drive load for the configured duration, then wait for in-flight cycles to finish
+pub async fn drive(
+    client: Arc<Client>,
+    fixture: Arc<Fixture>,
+    config: &LoadConfig,
+) -> (Collector, Duration) {
+    let collector = Arc::new(Mutex::new(Collector::default()));
+    let in_flight = Arc::new(Semaphore::new(config.max_in_flight));
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / config.rate));
+    // Keep the offered rate honest rather than bursting to catch up after a stall
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    let started = Instant::now();
+    let mut tasks = JoinSet::new();
+    for index in 0..fixture.users.len() {
+        ticker.tick().await;
+        if started.elapsed() >= config.duration {
+            break;
+        }
+        let Ok(permit) = in_flight.clone().try_acquire_owned() else {
+            collector.lock().unwrap().throttled += 1;
+            continue;
+        };
+
+        let (client, fixture, collector) = (client.clone(), fixture.clone(), collector.clone());
+        let amount = config.amount;
+        tasks.spawn(async move {
+            cycle(&client, &fixture, index, amount, &collector).await;
+            drop(permit);
+        });
+    }
+    while tasks.join_next().await.is_some() {}
+
+    let elapsed = started.elapsed();
+    let collector = Arc::try_unwrap(collector)
+        .ok()
+        .expect("all cycle tasks have finished")
+        .into_inner()
+        .unwrap();
+    (collector, elapsed)
+}
+
+// Helper function to run one request through create, submit, and approve, stopping at the first failure
+async fn cycle(
+    client: &Client,
+    fixture: &Fixture,
+    index: usize,
+    amount: u64,
+    collector: &Mutex<Collector>,
+) {
+    let user = &fixture.users[index];
+    let agent = &fixture.agents[index % fixture.agents.len()];
+    let request = pda::service_request(&user.pubkey(), &agent.agent_id).0;
+    collector.lock().unwrap().cycles_started += 1;
+
+    let steps = [
+        (
+            Stage::Create,
+            escrow::create_service_request(
+                user.pubkey(),
+                agent.agent_id,
+                amount,
+                format!("load-{index}"),
+            ),
+            vec![],
+        ),
+        (
+            Stage::Submit,
+            escrow::submit_result(
+                request,
+                agent.agent_id,
+                agent.nft_mint,
+                agent.creator.pubkey(),
+                "ok".into(),
+            ),
+            vec![&agent.creator],
+        ),
+        (
+            Stage::Approve,
+            escrow::approve_result(
+                request,
+                agent.agent_id,
+                user.pubkey(),
+                agent.creator.pubkey(),
+                fixture.platform_wallet,
+                fixture.treasury_wallet,
+            ),
+            vec![],
+        ),
+    ];
+
+    // The user pays every fee so the shared funder never shows up as a write-lock hotspot
+    for (stage, instruction, signers) in steps {
+        let sent = Instant::now();
+        match client.send(&[instruction], user, &signers).await {
+            Ok(signature) => collector
+                .lock()
+                .unwrap()
+                .success(stage, sent.elapsed(), signature),
+            Err(e) => {
+                collector.lock().unwrap().failure(stage, e.describe());
+                return;
+            }
+        }
+    }
+    collector.lock().unwrap().cycles_completed += 1;
+}
@@ -0,0 +1,163 @@
+//! AgentMarket load test: drive escrow request cycles at a fixed rate against a local validator.
+//!
+//! Registers a handful of agents, funds one fresh user per cycle, then starts
+//! create → submit → approve cycles at `--rate` per second for `--duration`.
+//! Once the load stops, every landed transaction is fetched back to report
+//! compute-unit usage per stage, and the write-locked accounts are ranked by
+//! how many transactions touched them in the same slot — the accounts that
+//! serialize the workload. Failures are grouped by stage and error.
+//!
+//! The protocol guardian must already be initialized (see `agentmarket-seed`).
+
+mod client;
+mod driver;
+mod setup;
+mod stats;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use agentmarket_sdk::RpcClient;
+use anyhow::{ensure, Context, Result};
+use clap::Parser;
+use futures_util::{stream, StreamExt};
+use solana_keypair::{read_keypair_file, Keypair};
+use solana_signer::Signer;
+use tracing::{info, warn};
+use tracing_subscriber::EnvFilter;
+
+use crate::client::Client;
+use crate::driver::LoadConfig;
+use crate::stats::Report;
+
+/// Concurrent `getTransaction` calls during analysis
+const FETCH_CONCURRENCY: usize = 16;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "agentmarket-loadtest",
+    about = "Load-test the AgentMarket escrow flow against a validator"
+)]
+struct Args {
+    /// JSON-RPC endpoint of the validator
+    #[arg(
+        long,
+        env = "AGENTMARKET_RPC_URL",
+        default_value = "http://127.0.0.1:8899"
+    )]
+    url: String,
+
+    /// Keypair that funds the agents and users
+    #[arg(long, env = "AGENTMARKET_KEYPAIR")]
+    keypair: Option<PathBuf>,
+
+    /// Request cycles started per second; each cycle is three transactions
+    #[arg(long, default_value_t = 10.0)]
+    rate: f64,
+
+    /// Seconds to generate load for
+    #[arg(long, default_value_t = 30)]
+    duration: u64,
+
+    /// Agents the cycles are spread across; fewer agents means more contention
+    #[arg(long, default_value_t = 4)]
+    agents: usize,
+
+    /// Cycles in flight before new ones are dropped as throttled
+    #[arg(long, default_value_t = 512)]
+    max_in_flight: usize,
+
+    /// Lamports escrowed per request
+    #[arg(long, default_value_t = 1_000_000)]
+    amount: u64,
+
+    /// Seconds to wait for each transaction to confirm
+    #[arg(long, default_value_t = 30)]
+    confirm_timeout: u64,
+
+    /// Also write the report here as JSON
+    #[arg(long)]
+    report: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
+        .init();
+
+    let args = Args::parse();
+    ensure!(args.rate > 0.0, "--rate must be positive");
+    ensure!(args.agents > 0, "--agents must be at least 1");
+    ensure!(args.max_in_flight > 0, "--max-in-flight must be at least 1");
+
+    let client = Arc::new(Client {
+        rpc: RpcClient::new(&args.url),
+        funder: load_keypair(args.keypair.as_deref())?,
+        confirm_timeout: Duration::from_secs(args.confirm_timeout),
+    });
+    let cycles = (args.rate * args.duration as f64).ceil() as usize;
+    info!(
+        funder = %client.funder.pubkey(),
+        cycles,
+        rate = args.rate,
+        duration = args.duration,
+        "preparing load test"
+    );
+
+    let fixture = Arc::new(setup::prepare(&client, args.agents, cycles, args.amount).await?);
+    let config = LoadConfig {
+        rate: args.rate,
+        duration: Duration::from_secs(args.duration),
+        max_in_flight: args.max_in_flight,
+        amount: args.amount,
+    };
+
+    info!("generating load");
+    let (collector, elapsed) = driver::drive(client.clone(), fixture.clone(), &config).await;
+
+    info!(
+        transactions = collector.landed().len(),
+        "fetching landed transactions"
+    );
+    let transactions: Vec<_> = stream::iter(collector.landed().iter().copied())
+        .map(|(stage, signature)| {
+            let client = client.clone();
+            async move {
+                match client.transaction(&signature).await {
+                    Ok(info) => info.map(|info| (stage, info)),
+                    Err(e) => {
+                        warn!(%signature, "failed to fetch transaction: {e}");
+                        None
+                    }
+                }
+            }
+        })
+        .buffer_unordered(FETCH_CONCURRENCY)
+        .filter_map(|tx| async move { tx })
+        .collect()
+        .await;
+
+    let report = Report::build(&collector, &transactions, &fixture.labels, elapsed);
+    report.print();
+    if let Some(path) = &args.report {
+        std::fs::write(path, serde_json::to_vec_pretty(&report)?)
+            .with_context(|| format!("writing {}", path.display()))?;
+    }
+    Ok(())
+}
+
+// Helper function to read the funding keypair, defaulting to the Solana CLI's
+fn load_keypair(path: Option<&Path>) -> Result<Keypair> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => {
+            let home = std::env::var_os("HOME").context("HOME is not set; pass --keypair")?;
+            PathBuf::from(home).join(".config/solana/id.json")
+        }
+    };
+
+    read_keypair_file(&path)
+        .map_err(|e| anyhow::anyhow!("failed to read keypair {}: {e}", path.display()))
+}
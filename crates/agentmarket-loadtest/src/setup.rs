@@ -0,0 +1,160 @@
+//! One-off fixture: fee wallets, agents, and a funded user per cycle.
+
+use std::collections::HashMap;
+
+use agentmarket_sdk::accounts::{GuardianConfig, PricingModel, RegistryConfig};
+use agentmarket_sdk::instructions::registry::{self, RegisterAgentArgs};
+use agentmarket_sdk::{pda, Pubkey};
+use anchor_lang::solana_program::system_instruction;
+use anyhow::{anyhow, bail, Result};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use tracing::info;
+
+use crate::client::Client;
+
+/// Balance for wallets that only receive payouts, so small splits stay rent-exempt
+const RECEIVER_FLOOR: u64 = 10_000_000;
+/// Balance for each agent creator: NFT mint, metadata, and profile rent, on top of the listing stake
+const CREATOR_FUNDING: u64 = 100_000_000;
+/// Per-user headroom beyond the escrowed amount: request rent and fees
+const USER_OVERHEAD: u64 = 10_000_000;
+/// Transfers packed into one funding transaction
+const TRANSFERS_PER_TX: usize = 16;
+
+pub struct Agent {
+    pub creator: Keypair,
+    pub agent_id: Pubkey,
+    pub nft_mint: Pubkey,
+}
+
+/// Accounts shared by the whole run
+pub struct Fixture {
+    pub agents: Vec<Agent>,
+    /// One fresh user per cycle, since a user can hold one request per agent
+    pub users: Vec<Keypair>,
+    pub platform_wallet: Pubkey,
+    pub treasury_wallet: Pubkey,
+    /// Human-readable names for accounts that show up in the hotspot report
+    pub labels: HashMap<Pubkey, String>,
+}
+
+pub async fn prepare(
+    client: &Client,
+    agents: usize,
+    cycles: usize,
+    amount: u64,
+) -> Result<Fixture> {
+    match client
+        .rpc
+        .fetch_optional::<GuardianConfig>(&pda::guardian_config().0)
+        .await?
+    {
+        None => {
+            bail!("protocol guardian is not initialized; run agentmarket-seed or the CLI first")
+        }
+        Some(config) if config.is_paused => bail!("the protocol is paused"),
+        Some(_) => {}
+    }
+    let listing_stake = client
+        .rpc
+        .fetch_optional::<RegistryConfig>(&pda::registry_config().0)
+        .await?
+        .ok_or_else(|| {
+            anyhow!("registry config is not initialized; run agentmarket-seed or the CLI first")
+        })?
+        .listing_stake;
+
+    let platform_wallet = Keypair::new().pubkey();
+    let treasury_wallet = Keypair::new().pubkey();
+    let creators: Vec<Keypair> = (0..agents).map(|_| Keypair::new()).collect();
+    let users: Vec<Keypair> = (0..cycles).map(|_| Keypair::new()).collect();
+
+    let mut labels = HashMap::from([
+        (platform_wallet, "platform wallet".to_owned()),
+        (treasury_wallet, "treasury wallet".to_owned()),
+        (client.funder.pubkey(), "funder".to_owned()),
+    ]);
+
+    let transfers: Vec<(Pubkey, u64)> = [platform_wallet, treasury_wallet]
+        .into_iter()
+        .map(|wallet| (wallet, RECEIVER_FLOOR))
+        .chain(
+            creators
+                .iter()
+                .map(|c| (c.pubkey(), CREATOR_FUNDING + listing_stake)),
+        )
+        .chain(users.iter().map(|u| (u.pubkey(), amount + USER_OVERHEAD)))
+        .collect();
+    info!(wallets = transfers.len(), "funding wallets");
+    for chunk in transfers.chunks(TRANSFERS_PER_TX) {
+        let instructions: Vec<_> = chunk
+            .iter()
+            .map(|(to, lamports)| {
+                system_instruction::transfer(&client.funder.pubkey(), to, *lamports)
+            })
+            .collect();
+        client
+            .send(&instructions, &client.funder, &[])
+            .await
+            .map_err(|e| anyhow!("funding failed: {}", e.describe()))?;
+    }
+
+    let mut registered = Vec::with_capacity(agents);
+    for (index, creator) in creators.into_iter().enumerate() {
+        let mint = Keypair::new();
+        // Each registration takes the next agent index, so read the count fresh
+        let config = client
+            .rpc
+            .fetch::<RegistryConfig>(&pda::registry_config().0)
+            .await?;
+        let args = RegisterAgentArgs {
+            name: format!("Load Agent {index}"),
+            description: "Load-test agent".into(),
+            capabilities: Vec::new(),
+            pricing: PricingModel::PerQuery {
+                price: amount,
+                payment_mint: None,
+            },
+            endpoints: Vec::new(),
+            ipfs_hash: String::new(),
+            symbol: "LOAD".into(),
+            uri: String::new(),
+        };
+        client
+            .send(
+                &[registry::register_agent(
+                    creator.pubkey(),
+                    mint.pubkey(),
+                    &config,
+                    args,
+                )],
+                &creator,
+                &[&mint],
+            )
+            .await
+            .map_err(|e| anyhow!("registering agent {index} failed: {}", e.describe()))?;
+
+        let agent_id = pda::agent_profile(&creator.pubkey()).0;
+        labels.insert(creator.pubkey(), format!("agent {index} creator"));
+        labels.insert(agent_id, format!("agent {index} profile"));
+        registered.push(Agent {
+            creator,
+            agent_id,
+            nft_mint: mint.pubkey(),
+        });
+    }
+    info!(
+        agents = registered.len(),
+        users = users.len(),
+        "fixture ready"
+    );
+
+    Ok(Fixture {
+        agents: registered,
+        users,
+        platform_wallet,
+        treasury_wallet,
+        labels,
+    })
+}
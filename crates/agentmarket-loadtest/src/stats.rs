@@ -0,0 +1,287 @@
+//! Run statistics: per-stage outcomes while driving, then compute and contention analysis.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+use agentmarket_sdk::Pubkey;
+use serde::Serialize;
+use solana_signature::Signature;
+
+use crate::client::TxInfo;
+
+/// Hotspot and error rows shown in the report
+const TOP_N: usize = 10;
+
+/// One transaction in the create/submit/approve cycle
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Create,
+    Submit,
+    Approve,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 3] = [Stage::Create, Stage::Submit, Stage::Approve];
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Stage::Create => "create",
+            Stage::Submit => "submit",
+            Stage::Approve => "approve",
+        })
+    }
+}
+
+/// Raw outcomes gathered while the load runs
+#[derive(Default)]
+pub struct Collector {
+    pub cycles_started: usize,
+    pub cycles_completed: usize,
+    /// Cycles skipped because `max_in_flight` were already running
+    pub throttled: usize,
+    latencies: HashMap<Stage, Vec<Duration>>,
+    failures: HashMap<Stage, usize>,
+    errors: HashMap<(Stage, String), usize>,
+    landed: Vec<(Stage, Signature)>,
+}
+
+impl Collector {
+    pub fn success(&mut self, stage: Stage, latency: Duration, signature: Signature) {
+        self.latencies.entry(stage).or_default().push(latency);
+        self.landed.push((stage, signature));
+    }
+
+    pub fn failure(&mut self, stage: Stage, error: String) {
+        *self.failures.entry(stage).or_default() += 1;
+        *self.errors.entry((stage, error)).or_default() += 1;
+    }
+
+    /// Signatures of every transaction that landed, for post-run analysis
+    pub fn landed(&self) -> &[(Stage, Signature)] {
+        &self.landed
+    }
+}
+
+#[derive(Serialize)]
+pub struct Report {
+    pub elapsed_secs: f64,
+    pub cycles_started: usize,
+    pub cycles_completed: usize,
+    pub cycles_throttled: usize,
+    /// Completed cycles per second of wall time
+    pub throughput: f64,
+    pub stages: Vec<StageReport>,
+    pub errors: Vec<ErrorReport>,
+    pub hotspots: Vec<Hotspot>,
+}
+
+#[derive(Serialize)]
+pub struct StageReport {
+    pub stage: Stage,
+    pub ok: usize,
+    pub failed: usize,
+    pub failure_rate: f64,
+    pub latency_ms_p50: u64,
+    pub latency_ms_p95: u64,
+    pub latency_ms_p99: u64,
+    pub compute_units_min: u64,
+    pub compute_units_avg: u64,
+    pub compute_units_max: u64,
+}
+
+#[derive(Serialize)]
+pub struct ErrorReport {
+    pub stage: Stage,
+    pub error: String,
+    pub count: usize,
+}
+
+/// A writable account many landed transactions locked
+#[derive(Serialize)]
+pub struct Hotspot {
+    pub account: String,
+    pub label: Option<String>,
+    /// Landed transactions that took a write lock on it
+    pub writes: usize,
+    /// Most write-locking transactions in any single slot
+    pub max_writes_per_slot: usize,
+}
+
+impl Report {
+    /// Combine the collector with fetched transaction details
+    pub fn build(
+        collector: &Collector,
+        transactions: &[(Stage, TxInfo)],
+        labels: &HashMap<Pubkey, String>,
+        elapsed: Duration,
+    ) -> Self {
+        let stages = Stage::ALL
+            .into_iter()
+            .map(|stage| {
+                let mut latencies = collector.latencies.get(&stage).cloned().unwrap_or_default();
+                latencies.sort();
+                let ok = latencies.len();
+                let failed = collector.failures.get(&stage).copied().unwrap_or(0);
+                let units: Vec<u64> = transactions
+                    .iter()
+                    .filter(|(s, _)| *s == stage)
+                    .filter_map(|(_, tx)| tx.compute_units)
+                    .collect();
+
+                StageReport {
+                    stage,
+                    ok,
+                    failed,
+                    failure_rate: ratio(failed, ok + failed),
+                    latency_ms_p50: percentile(&latencies, 50),
+                    latency_ms_p95: percentile(&latencies, 95),
+                    latency_ms_p99: percentile(&latencies, 99),
+                    compute_units_min: units.iter().copied().min().unwrap_or(0),
+                    compute_units_avg: units.iter().sum::<u64>() / units.len().max(1) as u64,
+                    compute_units_max: units.iter().copied().max().unwrap_or(0),
+                }
+            })
+            .collect();
+
+        let mut errors: Vec<ErrorReport> = collector
+            .errors
+            .iter()
+            .map(|((stage, error), count)| ErrorReport {
+                stage: *stage,
+                error: error.clone(),
+                count: *count,
+            })
+            .collect();
+        errors.sort_by_key(|e| Reverse(e.count));
+        errors.truncate(TOP_N);
+
+        Self {
+            elapsed_secs: elapsed.as_secs_f64(),
+            cycles_started: collector.cycles_started,
+            cycles_completed: collector.cycles_completed,
+            cycles_throttled: collector.throttled,
+            throughput: collector.cycles_completed as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+            stages,
+            errors,
+            hotspots: hotspots(transactions, labels),
+        }
+    }
+
+    pub fn print(&self) {
+        println!(
+            "cycles: {} started, {} completed in {:.1}s ({:.2}/s), {} throttled",
+            self.cycles_started,
+            self.cycles_completed,
+            self.elapsed_secs,
+            self.throughput,
+            self.cycles_throttled
+        );
+
+        println!();
+        println!(
+            "{:<8} {:>6} {:>6} {:>7}  {:>7} {:>7} {:>7}  {:>7} {:>7} {:>7}",
+            "stage",
+            "ok",
+            "failed",
+            "fail%",
+            "p50ms",
+            "p95ms",
+            "p99ms",
+            "cu.min",
+            "cu.avg",
+            "cu.max"
+        );
+        for s in &self.stages {
+            println!(
+                "{:<8} {:>6} {:>6} {:>6.2}%  {:>7} {:>7} {:>7}  {:>7} {:>7} {:>7}",
+                s.stage.to_string(),
+                s.ok,
+                s.failed,
+                s.failure_rate * 100.0,
+                s.latency_ms_p50,
+                s.latency_ms_p95,
+                s.latency_ms_p99,
+                s.compute_units_min,
+                s.compute_units_avg,
+                s.compute_units_max
+            );
+        }
+
+        if !self.errors.is_empty() {
+            println!();
+            println!("top errors:");
+            for e in &self.errors {
+                println!("  {:>6}  {:<8} {}", e.count, e.stage.to_string(), e.error);
+            }
+        }
+
+        if !self.hotspots.is_empty() {
+            println!();
+            println!("write-lock hotspots (max per slot / total):");
+            for h in &self.hotspots {
+                println!(
+                    "  {:>4} / {:<6} {} {}",
+                    h.max_writes_per_slot,
+                    h.writes,
+                    h.account,
+                    h.label
+                        .as_deref()
+                        .map(|l| format!("({l})"))
+                        .unwrap_or_default()
+                );
+            }
+        }
+    }
+}
+
+// Helper function to rank writable accounts by how many transactions locked them in one slot
+fn hotspots(transactions: &[(Stage, TxInfo)], labels: &HashMap<Pubkey, String>) -> Vec<Hotspot> {
+    let mut per_account: HashMap<Pubkey, HashMap<u64, usize>> = HashMap::new();
+    for (_, tx) in transactions {
+        for account in &tx.writable {
+            *per_account
+                .entry(*account)
+                .or_default()
+                .entry(tx.slot)
+                .or_default() += 1;
+        }
+    }
+
+    let mut hotspots: Vec<Hotspot> = per_account
+        .into_iter()
+        .map(|(account, slots)| Hotspot {
+            account: account.to_string(),
+            label: labels.get(&account).cloned(),
+            writes: slots.values().sum(),
+            max_writes_per_slot: slots.values().copied().max().unwrap_or(0),
+        })
+        // An account written once per slot never serializes anything
+        .filter(|h| h.max_writes_per_slot > 1)
+        .collect();
+    hotspots.sort_by_key(|h| Reverse((h.max_writes_per_slot, h.writes)));
+    hotspots.truncate(TOP_N);
+    hotspots
+}
+
+// Helper function to read a percentile from sorted latencies, in milliseconds
+fn percentile(sorted: &[Duration], pct: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let index = (sorted.len() * pct / 100).min(sorted.len() - 1);
+    sorted[index].as_millis() as u64
+}
+
+// Helper function to divide counts, treating 0/0 as 0
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
@@ -0,0 +1,244 @@
+//! Prometheus metrics shared by the AgentMarket off-chain services.
+//!
+//! Every service reports into the same metric families, labelled with its
+//! own name, so one dashboard covers the indexer, the relay, agent
+//! runtimes, the crank, and the auditor alike:
+//!
+//! - `agentmarket_events_processed_total{service,event}`: decoded program events handled
+//! - `agentmarket_failed_transactions_total{service}`: transactions that failed on-chain
+//!   or could not be sent
+//! - `agentmarket_processed_slot{service}`: slot of the newest notification handled
+//! - `agentmarket_tip_slot`: the cluster's confirmed slot, polled by [`Metrics::track_tip`]
+//! - `agentmarket_slot_lag{service}`: tip minus processed slot
+//! - `agentmarket_pending_disputes`: requests whose result is disputed and unresolved
+//! - `agentmarket_jobs_total{service,job,outcome}`: crank jobs executed, skipped, or failed
+//! - `agentmarket_pending_jobs{service}`: crank jobs found due on the last pass
+//! - `agentmarket_payer_balance_lamports{service}`: balance of the key paying fees
+//! - `agentmarket_invariant_violations{invariant}`: accounts breaking a protocol invariant
+//!   on the auditor's last pass
+//!
+//! Services are notified only about transactions that touch the programs, so
+//! the lag also grows while the programs are idle; alert on it together with
+//! the event rate.
+
+use std::net::SocketAddr;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use agentmarket_sdk::RpcClient;
+use axum::http::header::CONTENT_TYPE;
+use axum::routing::get;
+use axum::Router;
+use prometheus::{
+    register_int_counter_vec, register_int_gauge, register_int_gauge_vec, Encoder, IntCounterVec,
+    IntGauge, IntGaugeVec, TextEncoder,
+};
+use serde_json::json;
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+static EVENTS_PROCESSED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "agentmarket_events_processed_total",
+        "Decoded program events handled",
+        &["service", "event"]
+    )
+    .expect("metric registers once")
+});
+
+static FAILED_TRANSACTIONS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "agentmarket_failed_transactions_total",
+        "Transactions that failed on-chain or could not be sent",
+        &["service"]
+    )
+    .expect("metric registers once")
+});
+
+static PROCESSED_SLOT: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "agentmarket_processed_slot",
+        "Slot of the newest notification handled",
+        &["service"]
+    )
+    .expect("metric registers once")
+});
+
+static TIP_SLOT: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!("agentmarket_tip_slot", "The cluster's confirmed slot")
+        .expect("metric registers once")
+});
+
+static SLOT_LAG: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "agentmarket_slot_lag",
+        "Slots between the cluster tip and the newest notification handled",
+        &["service"]
+    )
+    .expect("metric registers once")
+});
+
+static PENDING_DISPUTES: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "agentmarket_pending_disputes",
+        "Service requests whose result is disputed and unresolved"
+    )
+    .expect("metric registers once")
+});
+
+static JOBS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "agentmarket_jobs_total",
+        "Crank jobs by outcome",
+        &["service", "job", "outcome"]
+    )
+    .expect("metric registers once")
+});
+
+static PENDING_JOBS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "agentmarket_pending_jobs",
+        "Crank jobs found due on the last pass",
+        &["service"]
+    )
+    .expect("metric registers once")
+});
+
+static PAYER_BALANCE: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "agentmarket_payer_balance_lamports",
+        "Balance of the key paying transaction fees",
+        &["service"]
+    )
+    .expect("metric registers once")
+});
+
+static INVARIANT_VIOLATIONS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "agentmarket_invariant_violations",
+        "Accounts breaking a protocol invariant on the auditor's last pass",
+        &["invariant"]
+    )
+    .expect("metric registers once")
+});
+
+/// One service's handle on the shared metrics
+#[derive(Clone, Copy, Debug)]
+pub struct Metrics {
+    service: &'static str,
+}
+
+impl Metrics {
+    /// Register the metric families and label them with `service`
+    pub fn new(service: &'static str) -> Self {
+        // Touch every series up front so scrapes show zeros rather than gaps
+        FAILED_TRANSACTIONS.with_label_values(&[service]);
+        PROCESSED_SLOT.with_label_values(&[service]);
+        SLOT_LAG.with_label_values(&[service]);
+        LazyLock::force(&EVENTS_PROCESSED);
+        LazyLock::force(&JOBS);
+        LazyLock::force(&PENDING_JOBS);
+        LazyLock::force(&PAYER_BALANCE);
+        LazyLock::force(&INVARIANT_VIOLATIONS);
+        LazyLock::force(&TIP_SLOT);
+        LazyLock::force(&PENDING_DISPUTES);
+
+        Self { service }
+    }
+
+    pub fn event_processed(&self, event: &str) {
+        EVENTS_PROCESSED
+            .with_label_values(&[self.service, event])
+            .inc();
+    }
+
+    pub fn transaction_failed(&self) {
+        FAILED_TRANSACTIONS.with_label_values(&[self.service]).inc();
+    }
+
+    /// Record a handled notification's slot; older slots from replays are ignored
+    pub fn slot_processed(&self, slot: u64) {
+        let processed = PROCESSED_SLOT.with_label_values(&[self.service]);
+        if slot as i64 > processed.get() {
+            processed.set(slot as i64);
+            self.update_lag();
+        }
+    }
+
+    pub fn set_pending_disputes(&self, count: u64) {
+        PENDING_DISPUTES.set(count as i64);
+    }
+
+    /// Count a crank job by kind and outcome (`executed`, `skipped`, `failed`)
+    pub fn job_finished(&self, job: &str, outcome: &str) {
+        JOBS.with_label_values(&[self.service, job, outcome]).inc();
+    }
+
+    pub fn set_pending_jobs(&self, count: usize) {
+        PENDING_JOBS
+            .with_label_values(&[self.service])
+            .set(count as i64);
+    }
+
+    pub fn set_payer_balance(&self, lamports: u64) {
+        PAYER_BALANCE
+            .with_label_values(&[self.service])
+            .set(lamports as i64);
+    }
+
+    pub fn set_invariant_violations(&self, invariant: &str, count: usize) {
+        INVARIANT_VIOLATIONS
+            .with_label_values(&[invariant])
+            .set(count as i64);
+    }
+
+    /// Poll the cluster's confirmed slot every `interval` to keep the lag current
+    pub async fn track_tip(self, rpc: RpcClient, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match rpc
+                .call("getSlot", json!([{ "commitment": "confirmed" }]))
+                .await
+            {
+                Ok(slot) => {
+                    if let Some(slot) = slot.as_u64() {
+                        TIP_SLOT.set(slot as i64);
+                        self.update_lag();
+                    }
+                }
+                Err(e) => warn!("failed to read the cluster tip: {e}"),
+            }
+        }
+    }
+
+    // Helper function to recompute the lag; zero until both slots are known
+    fn update_lag(&self) {
+        let processed = PROCESSED_SLOT.with_label_values(&[self.service]).get();
+        let tip = TIP_SLOT.get();
+        let lag = if processed == 0 || tip == 0 {
+            0
+        } else {
+            (tip - processed).max(0)
+        };
+        SLOT_LAG.with_label_values(&[self.service]).set(lag);
+    }
+}
+
+/// Serve `/metrics` in the Prometheus text format on `addr` until the task is dropped
+pub async fn serve(addr: SocketAddr) -> std::io::Result<()> {
+    let app = Router::new().route("/metrics", get(render));
+    let listener = TcpListener::bind(addr).await?;
+    info!("metrics listening on {addr}");
+    axum::serve(listener, app).await
+}
+
+// Helper function to encode every registered metric family
+async fn render() -> ([(axum::http::HeaderName, String); 1], Vec<u8>) {
+    let encoder = TextEncoder::new();
+    let mut body = Vec::new();
+    if let Err(e) = encoder.encode(&prometheus::gather(), &mut body) {
+        warn!("failed to encode metrics: {e}");
+    }
+    ([(CONTENT_TYPE, encoder.format_type().to_string())], body)
+}
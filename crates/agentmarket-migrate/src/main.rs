@@ -0,0 +1,146 @@
+//! AgentMarket migrator: upgrade program accounts to a new layout.
+//!
+//! Lists every AgentProfile, Rating, or ServiceRequest account, picks out the
+//! ones still in an older layout, and sends the program's migrate instruction
+//! for each. The program does the conversion itself: it decodes the account
+//! with the current layout, reading the fields the old one lacked as zero,
+//! fills those in, and writes it back at the new size, so every existing
+//! field is kept and nothing but the account address goes over the wire.
+//!
+//! The migrate instructions only run while the guardian has paused the
+//! protocol and only for the guardian authority, so the upgrade procedure is:
+//! pause, deploy, migrate each account type, unpause. `--dry-run` lists the
+//! accounts that would be migrated without sending anything.
+//!
+//! Progress is checkpointed to `--state` after every account, so an
+//! interrupted run resumes where it stopped.
+
+mod migrator;
+mod state;
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use agentmarket_sdk::RpcClient;
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use solana_keypair::{read_keypair_file, Keypair};
+use solana_signer::Signer;
+use tracing::info;
+use tracing_subscriber::EnvFilter;
+
+use crate::migrator::{Migrator, Report, Target};
+use crate::state::State;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "agentmarket-migrate",
+    about = "Upgrade AgentMarket program accounts to a new account layout"
+)]
+struct Args {
+    /// JSON-RPC endpoint of the cluster to migrate
+    #[arg(
+        long,
+        env = "AGENTMARKET_RPC_URL",
+        default_value = "http://127.0.0.1:8899"
+    )]
+    url: String,
+
+    /// Guardian authority keypair; pays any rent increase
+    #[arg(long, env = "AGENTMARKET_KEYPAIR")]
+    keypair: Option<PathBuf>,
+
+    /// Account type to migrate
+    #[arg(long, value_enum)]
+    account: Target,
+
+    /// Resume file; defaults to `migration-<account>.json`
+    #[arg(long)]
+    state: Option<PathBuf>,
+
+    /// Accounts read per `getMultipleAccounts` call (max 100)
+    #[arg(long, default_value_t = 50)]
+    batch_size: usize,
+
+    /// Stop after this many accounts not yet migrated
+    #[arg(long)]
+    limit: Option<usize>,
+
+    /// Seconds to wait for each transaction to confirm
+    #[arg(long, default_value_t = 60)]
+    confirm_timeout: u64,
+
+    /// List the accounts that would be migrated without sending anything
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
+        .init();
+
+    let args = Args::parse();
+    let account = args.account.name();
+
+    let state_path = args
+        .state
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("migration-{account}.json")));
+    let mut state = State::open(&state_path, account)?;
+
+    let authority = load_keypair(args.keypair.as_deref())?;
+    info!(authority = %authority.pubkey(), url = %args.url, account, dry_run = args.dry_run, "starting");
+
+    let migrator = Migrator {
+        rpc: RpcClient::new(&args.url),
+        authority,
+        target: args.account,
+        batch_size: args.batch_size,
+        confirm_timeout: Duration::from_secs(args.confirm_timeout),
+        dry_run: args.dry_run,
+    };
+    migrator.preflight().await?;
+    let report = migrator.run(&mut state, args.limit).await?;
+
+    print_report(&report, args.dry_run);
+    if !report.failed.is_empty() {
+        bail!(
+            "{} accounts failed; rerun to retry them from {}",
+            report.failed.len(),
+            state_path.display()
+        );
+    }
+    Ok(())
+}
+
+fn print_report(report: &Report, dry_run: bool) {
+    let verb = if dry_run {
+        "would migrate:"
+    } else {
+        "migrated:"
+    };
+    println!("{:<18}{}", "found:", report.found);
+    println!("{:<18}{}", "already migrated:", report.already_migrated);
+    println!("{:<18}{}", "up to date:", report.up_to_date);
+    println!("{:<18}{}", verb, report.migrated);
+    println!("{:<18}{}", "failed:", report.failed.len());
+    for (address, error) in &report.failed {
+        println!("  {address}  {error}");
+    }
+}
+
+// Helper function to read the authority keypair, defaulting to the Solana CLI's
+fn load_keypair(path: Option<&Path>) -> Result<Keypair> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => {
+            let home = std::env::var_os("HOME").context("HOME is not set; pass --keypair")?;
+            PathBuf::from(home).join(".config/solana/id.json")
+        }
+    };
+
+    read_keypair_file(&path)
+        .map_err(|e| anyhow::anyhow!("failed to read keypair {}: {e}", path.display()))
+}
@@ -0,0 +1,251 @@
+//! Batch loop: list accounts, pick out the ones in an older layout, and migrate each.
+
+use std::time::Duration;
+
+use agentmarket_sdk::accounts::{
+    self, AgentProfile, GuardianConfig, Rating, ServiceRequest, PROFILE_VERSION,
+};
+use agentmarket_sdk::instructions::{escrow, registry, reputation};
+use agentmarket_sdk::{
+    pda, Instruction, Pubkey, RpcClient, ESCROW_PROGRAM_ID, REGISTRY_PROGRAM_ID,
+    REPUTATION_PROGRAM_ID,
+};
+use anchor_lang::Discriminator;
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use solana_keypair::Keypair;
+use solana_signature::Signature;
+use solana_signer::Signer;
+use tracing::{info, warn};
+
+use crate::state::State;
+
+/// How often signature statuses are polled while confirming
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// `getMultipleAccounts` accepts at most this many addresses
+const MAX_BATCH: usize = 100;
+
+/// Account types with a migrate instruction
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Target {
+    AgentProfile,
+    Rating,
+    ServiceRequest,
+}
+
+impl Target {
+    /// Type name in the IDL
+    pub fn name(self) -> &'static str {
+        match self {
+            Target::AgentProfile => "AgentProfile",
+            Target::Rating => "Rating",
+            Target::ServiceRequest => "ServiceRequest",
+        }
+    }
+
+    fn program_id(self) -> Pubkey {
+        match self {
+            Target::AgentProfile => REGISTRY_PROGRAM_ID,
+            Target::Rating => REPUTATION_PROGRAM_ID,
+            Target::ServiceRequest => ESCROW_PROGRAM_ID,
+        }
+    }
+
+    /// Anchor derives discriminators from the type name, so old and new layouts share it
+    fn discriminator(self) -> &'static [u8] {
+        match self {
+            Target::AgentProfile => AgentProfile::DISCRIMINATOR,
+            Target::Rating => Rating::DISCRIMINATOR,
+            Target::ServiceRequest => ServiceRequest::DISCRIMINATOR,
+        }
+    }
+
+    /// Size of an account written under the current layout
+    fn space(self) -> usize {
+        8 + match self {
+            Target::AgentProfile => AgentProfile::INIT_SPACE,
+            Target::Rating => Rating::INIT_SPACE,
+            Target::ServiceRequest => ServiceRequest::INIT_SPACE,
+        }
+    }
+
+    /// Whether the account is still in an older layout; fields are only ever
+    /// appended, so one is either shorter than the current layout or, for
+    /// profiles, also carries an older version
+    fn needs_migration(self, data: &[u8]) -> Result<bool> {
+        if data.len() < self.space() {
+            return Ok(true);
+        }
+        match self {
+            Target::AgentProfile => {
+                let profile: AgentProfile = accounts::decode(data)?;
+                Ok(profile.version < PROFILE_VERSION)
+            }
+            Target::Rating | Target::ServiceRequest => Ok(false),
+        }
+    }
+
+    fn instruction(self, authority: Pubkey, address: Pubkey) -> Instruction {
+        match self {
+            Target::AgentProfile => registry::migrate_agent_profile(authority, address),
+            Target::Rating => reputation::migrate_rating(authority, address),
+            Target::ServiceRequest => escrow::migrate_service_request(authority, address),
+        }
+    }
+}
+
+pub struct Migrator {
+    pub rpc: RpcClient,
+    pub authority: Keypair,
+    pub target: Target,
+    pub batch_size: usize,
+    pub confirm_timeout: Duration,
+    pub dry_run: bool,
+}
+
+/// Outcome of a run
+#[derive(Default)]
+pub struct Report {
+    pub found: usize,
+    pub already_migrated: usize,
+    pub up_to_date: usize,
+    pub migrated: usize,
+    pub failed: Vec<(Pubkey, String)>,
+}
+
+impl Migrator {
+    /// Refuse to write unless the protocol is paused and our keypair is the guardian authority
+    pub async fn preflight(&self) -> Result<()> {
+        let guardian: GuardianConfig = self.rpc.fetch(&pda::guardian_config().0).await?;
+        let problem = if guardian.authority != self.authority.pubkey() {
+            Some(format!(
+                "keypair {} is not the guardian authority {}",
+                self.authority.pubkey(),
+                guardian.authority
+            ))
+        } else if !guardian.is_paused {
+            Some("the protocol is not paused".to_string())
+        } else {
+            None
+        };
+
+        match problem {
+            Some(problem) if self.dry_run => {
+                warn!("{problem}; a real run would refuse to start");
+                Ok(())
+            }
+            Some(problem) => bail!("{problem}"),
+            None => Ok(()),
+        }
+    }
+
+    pub async fn run(&self, state: &mut State, limit: Option<usize>) -> Result<Report> {
+        let account = self.target.name();
+        let addresses = self
+            .rpc
+            .program_account_addresses(&self.target.program_id(), self.target.discriminator())
+            .await?;
+
+        let mut report = Report {
+            found: addresses.len(),
+            ..Report::default()
+        };
+        let mut pending: Vec<Pubkey> = addresses
+            .into_iter()
+            .filter(|address| !state.migrated.contains(&address.to_string()))
+            .collect();
+        report.already_migrated = report.found - pending.len();
+        if let Some(limit) = limit {
+            pending.truncate(limit);
+        }
+        info!(
+            account,
+            found = report.found,
+            pending = pending.len(),
+            "migrating"
+        );
+
+        for batch in pending.chunks(self.batch_size.clamp(1, MAX_BATCH)) {
+            let data = self.rpc.get_multiple_account_data(batch).await?;
+            for (address, data) in batch.iter().zip(data) {
+                let Some(data) = data else { continue };
+
+                match self.migrate_one(state, address, &data).await {
+                    Ok(Outcome::UpToDate) => report.up_to_date += 1,
+                    Ok(Outcome::Migrated) => report.migrated += 1,
+                    Err(e) => {
+                        warn!(%address, error = %format!("{e:#}"), "migration failed");
+                        report.failed.push((*address, format!("{e:#}")));
+                    }
+                }
+            }
+            info!(
+                done = report.migrated + report.up_to_date,
+                failed = report.failed.len(),
+                "batch complete"
+            );
+        }
+
+        Ok(report)
+    }
+
+    async fn migrate_one(
+        &self,
+        state: &mut State,
+        address: &Pubkey,
+        data: &[u8],
+    ) -> Result<Outcome> {
+        if !self.target.needs_migration(data)? {
+            return Ok(Outcome::UpToDate);
+        }
+        if self.dry_run {
+            return Ok(Outcome::Migrated);
+        }
+
+        let instruction = self.target.instruction(self.authority.pubkey(), *address);
+        let signature = self
+            .rpc
+            .send_instructions(&[instruction], &self.authority.pubkey(), &[&self.authority])
+            .await?;
+        self.confirm(&signature).await?;
+        state.finish(&address.to_string())?;
+
+        info!(%address, from_bytes = data.len(), "migrated");
+        Ok(Outcome::Migrated)
+    }
+
+    // Helper function to poll until `signature` is confirmed or fails
+    async fn confirm(&self, signature: &Signature) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + self.confirm_timeout;
+
+        loop {
+            let result = self
+                .rpc
+                .call("getSignatureStatuses", json!([[signature.to_string()]]))
+                .await?;
+            let status = result.pointer("/value/0").unwrap_or(&Value::Null);
+
+            if !status.is_null() {
+                if let Some(err) = status.get("err").filter(|e| !e.is_null()) {
+                    bail!("transaction {signature} failed: {err}");
+                }
+                let level = status.get("confirmationStatus").and_then(Value::as_str);
+                if matches!(level, Some("confirmed" | "finalized")) {
+                    return Ok(());
+                }
+            }
+
+            if tokio::time::Instant::now() >= deadline {
+                bail!("transaction {signature} not confirmed in time");
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+enum Outcome {
+    UpToDate,
+    Migrated,
+}
@@ -0,0 +1,59 @@
+//! Resume file: which accounts are done.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct State {
+    /// Account type this file tracks; a file is never reused for another
+    pub account: String,
+    /// Accounts whose migrate instruction has been confirmed
+    pub migrated: BTreeSet<String>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl State {
+    /// Load the resume file at `path`, or start a fresh one for `account`
+    pub fn open(path: &Path, account: &str) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self {
+                account: account.to_string(),
+                migrated: BTreeSet::new(),
+                path: path.to_path_buf(),
+            });
+        }
+
+        let raw =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let mut state: State =
+            serde_json::from_str(&raw).with_context(|| format!("parsing {}", path.display()))?;
+        if state.account != account {
+            bail!(
+                "{} tracks a {} migration, not {account}",
+                path.display(),
+                state.account
+            );
+        }
+        state.path = path.to_path_buf();
+        Ok(state)
+    }
+
+    /// Mark an account done once its migrate instruction is confirmed
+    pub fn finish(&mut self, address: &str) -> Result<()> {
+        self.migrated.insert(address.to_string());
+        self.save()
+    }
+
+    // Helper function to write through a temp file so a crash never leaves a torn state file
+    fn save(&self) -> Result<()> {
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("writing {}", tmp.display()))?;
+        fs::rename(&tmp, &self.path).with_context(|| format!("writing {}", self.path.display()))
+    }
+}
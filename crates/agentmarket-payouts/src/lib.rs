@@ -0,0 +1,166 @@
+//! Off-chain payout simulation for the AgentMarket programs.
+//!
+//! Reproduces the programs' split, rounding, and fee arithmetic bit for bit
+//! so wallets and dashboards can show exactly what an instruction will pay
+//! before it is sent. The library has no dependencies and never touches the
+//! chain.
+//!
+//! The rounding rules worth knowing:
+//! - Percentage and basis-point shares round down, and whatever rounding
+//!   leaves over goes to the treasury, so a split always sums to its input.
+//!   Co-creator shares of an escrow release are the exception: the creator
+//!   keeps what they leave over.
+//! - The programs are built without overflow checks, so the escrow and
+//!   royalty splits use the same wrapping arithmetic they do; for amounts
+//!   that only matters above roughly 184 million SOL.
+//!
+//! `test-vectors.json` next to this crate pins the expected outputs. This
+//! crate's tests check the library against it, and the integration tests
+//! replay the same vectors through the compiled programs, so the two cannot
+//! drift apart unnoticed.
+
+use std::fmt;
+
+/// Basis points in 100%
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Insurance premiums are capped at 10% of the payout
+pub const MAX_PREMIUM_BPS: u16 = 1_000;
+
+/// The fixed split `marketplace_escrow::approve_result` releases a request with
+pub const ESCROW_SHARES: Shares = Shares {
+    creator: 85,
+    platform: 10,
+    treasury: 5,
+};
+
+/// Percentage split between creator, platform, and treasury
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shares {
+    pub creator: u8,
+    pub platform: u8,
+    pub treasury: u8,
+}
+
+/// Lamports each party receives from one payment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Split {
+    pub creator: u64,
+    pub platform: u64,
+    pub treasury: u64,
+}
+
+impl Split {
+    /// Platform and treasury together: what the protocol keeps
+    pub fn fees(&self) -> u64 {
+        self.platform.wrapping_add(self.treasury)
+    }
+}
+
+/// Why the program would reject the payment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayoutError {
+    /// The amount is zero
+    InvalidAmount,
+    /// The shares do not add up to 100
+    InvalidShareTotal,
+    /// The premium rate exceeds [`MAX_PREMIUM_BPS`]
+    InvalidPremium,
+    /// The rank has no leader, or every filled rank has zero weight
+    InvalidRank,
+}
+
+impl fmt::Display for PayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PayoutError::InvalidAmount => "amount must be greater than zero",
+            PayoutError::InvalidShareTotal => "shares must total 100",
+            PayoutError::InvalidPremium => "premium rate exceeds the maximum",
+            PayoutError::InvalidRank => "rank has no prize",
+        })
+    }
+}
+
+impl std::error::Error for PayoutError {}
+
+/// What approving a completed service request of `amount` releases from escrow
+///
+/// Opening the request already rejects a zero amount, so this cannot fail.
+pub fn escrow_release(amount: u64) -> Split {
+    split(amount, ESCROW_SHARES)
+}
+
+/// What each co-creator takes of an escrow release's `creator_amount`, given
+/// their shares in basis points; the creator keeps the rest
+///
+/// The registry holds the shares to at most 10,000 bps in total.
+pub fn co_creator_shares(creator_amount: u64, shares_bps: &[u16]) -> Vec<u64> {
+    shares_bps
+        .iter()
+        .map(|bps| ((creator_amount as u128) * (*bps as u128) / BPS_DENOMINATOR as u128) as u64)
+        .collect()
+}
+
+/// What `royalty_splitter::distribute_payment` pays out under `shares`
+pub fn royalty_distribution(amount: u64, shares: Shares) -> Result<Split, PayoutError> {
+    if amount == 0 {
+        return Err(PayoutError::InvalidAmount);
+    }
+    let total = shares
+        .creator
+        .wrapping_add(shares.platform)
+        .wrapping_add(shares.treasury);
+    if total != 100 {
+        return Err(PayoutError::InvalidShareTotal);
+    }
+    Ok(split(amount, shares))
+}
+
+/// Premium `insurance_fund::deposit_premium` charges on a payout
+///
+/// The program refuses deposits whose premium rounds down to zero.
+pub fn insurance_premium(payout_amount: u64, premium_bps: u16) -> Result<u64, PayoutError> {
+    if premium_bps > MAX_PREMIUM_BPS {
+        return Err(PayoutError::InvalidPremium);
+    }
+    let premium =
+        ((payout_amount as u128) * (premium_bps as u128) / BPS_DENOMINATOR as u128) as u64;
+    if premium == 0 {
+        return Err(PayoutError::InvalidAmount);
+    }
+    Ok(premium)
+}
+
+/// Prize `leaderboard::claim_prize` pays `rank` when `filled` ranks have leaders
+///
+/// Weights of ranks left unfilled are shared among the filled ones in
+/// proportion to their own weights.
+pub fn leaderboard_prize(
+    prize_pool: u64,
+    prize_weights_bps: &[u16],
+    filled: usize,
+    rank: usize,
+) -> Result<u64, PayoutError> {
+    if rank >= filled || filled > prize_weights_bps.len() {
+        return Err(PayoutError::InvalidRank);
+    }
+    let filled_weight: u64 = prize_weights_bps[..filled]
+        .iter()
+        .map(|weight| *weight as u64)
+        .sum();
+    if filled_weight == 0 {
+        return Err(PayoutError::InvalidRank);
+    }
+    Ok(((prize_pool as u128) * (prize_weights_bps[rank] as u128) / (filled_weight as u128)) as u64)
+}
+
+// Helper function to split by percentage the way the programs do: round down, remainder to treasury
+fn split(amount: u64, shares: Shares) -> Split {
+    let creator = amount.wrapping_mul(shares.creator as u64) / 100;
+    let platform = amount.wrapping_mul(shares.platform as u64) / 100;
+    Split {
+        creator,
+        platform,
+        treasury: amount.wrapping_sub(creator).wrapping_sub(platform),
+    }
+}
@@ -0,0 +1,114 @@
+//! The library against the shared vectors the integration tests replay through the programs.
+
+use agentmarket_payouts::{
+    escrow_release, insurance_premium, leaderboard_prize, royalty_distribution, PayoutError,
+    Shares, Split,
+};
+use serde_json::Value;
+
+fn vectors(kind: &str) -> Vec<Value> {
+    let all: Value = serde_json::from_str(include_str!("../test-vectors.json")).unwrap();
+    all[kind].as_array().unwrap().clone()
+}
+
+fn u64_at(vector: &Value, key: &str) -> u64 {
+    vector[key].as_u64().unwrap()
+}
+
+fn expected_split(vector: &Value) -> Split {
+    Split {
+        creator: u64_at(vector, "creator"),
+        platform: u64_at(vector, "platform"),
+        treasury: u64_at(vector, "treasury"),
+    }
+}
+
+#[test]
+fn escrow_release_matches_vectors() {
+    for vector in vectors("escrow_release") {
+        let split = escrow_release(u64_at(&vector, "amount"));
+        assert_eq!(split, expected_split(&vector), "{vector}");
+        assert_eq!(
+            split.creator + split.fees(),
+            u64_at(&vector, "amount"),
+            "{vector}"
+        );
+    }
+}
+
+#[test]
+fn royalty_distribution_matches_vectors() {
+    for vector in vectors("royalty_distribution") {
+        let shares: Vec<u8> = vector["shares"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|share| share.as_u64().unwrap() as u8)
+            .collect();
+        let shares = Shares {
+            creator: shares[0],
+            platform: shares[1],
+            treasury: shares[2],
+        };
+        let split = royalty_distribution(u64_at(&vector, "amount"), shares).unwrap();
+        assert_eq!(split, expected_split(&vector), "{vector}");
+    }
+}
+
+#[test]
+fn royalty_distribution_rejects_what_the_program_rejects() {
+    let shares = Shares {
+        creator: 85,
+        platform: 10,
+        treasury: 5,
+    };
+    assert_eq!(
+        royalty_distribution(0, shares),
+        Err(PayoutError::InvalidAmount)
+    );
+    assert_eq!(
+        royalty_distribution(
+            100,
+            Shares {
+                creator: 85,
+                platform: 10,
+                treasury: 10,
+            }
+        ),
+        Err(PayoutError::InvalidShareTotal)
+    );
+}
+
+#[test]
+fn insurance_premium_matches_vectors() {
+    for vector in vectors("insurance_premium") {
+        let premium = insurance_premium(
+            u64_at(&vector, "payout_amount"),
+            u64_at(&vector, "premium_bps") as u16,
+        );
+        // A premium that rounds to zero is refused by deposit_premium
+        match u64_at(&vector, "premium") {
+            0 => assert_eq!(premium, Err(PayoutError::InvalidAmount), "{vector}"),
+            expected => assert_eq!(premium, Ok(expected), "{vector}"),
+        }
+    }
+}
+
+#[test]
+fn leaderboard_prize_matches_vectors() {
+    for vector in vectors("leaderboard_prize") {
+        let weights: Vec<u16> = vector["prize_weights_bps"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|weight| weight.as_u64().unwrap() as u16)
+            .collect();
+        let prize = leaderboard_prize(
+            u64_at(&vector, "prize_pool"),
+            &weights,
+            u64_at(&vector, "filled") as usize,
+            u64_at(&vector, "rank") as usize,
+        );
+        assert_eq!(prize, Ok(u64_at(&vector, "prize")), "{vector}");
+    }
+}
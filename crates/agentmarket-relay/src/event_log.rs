@@ -0,0 +1,267 @@
+//! Sequenced event log: an in-memory replay window, optionally journaled to disk.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+/// Live records buffered per client before it counts as lagging
+const BROADCAST_CAPACITY: usize = 4096;
+
+/// One decoded event with its relay-assigned sequence number
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Record {
+    pub seq: u64,
+    pub slot: u64,
+    pub signature: String,
+    pub program: String,
+    pub event: String,
+    pub data: Value,
+}
+
+/// Result of asking for records starting at a sequence number
+pub enum Replay {
+    /// Every record from the requested sequence through the latest
+    Complete(Vec<Arc<Record>>),
+    /// The requested start has been evicted; records from `oldest` onwards
+    Gap {
+        oldest: u64,
+        records: Vec<Arc<Record>>,
+    },
+}
+
+/// Shared event log; sequence numbers start at 1 and never repeat within a journal
+pub struct EventLog {
+    inner: Mutex<Inner>,
+    live: broadcast::Sender<Arc<Record>>,
+}
+
+struct Inner {
+    window: VecDeque<Arc<Record>>,
+    capacity: usize,
+    next_seq: u64,
+    journal: Option<File>,
+    cursors: HashMap<String, u64>,
+    cursors_path: Option<PathBuf>,
+    cursors_dirty: bool,
+}
+
+impl EventLog {
+    /// Open the log, reloading the replay window and client cursors from `state_dir`
+    pub fn open(capacity: usize, state_dir: Option<&Path>) -> Result<Self> {
+        let mut inner = Inner {
+            window: VecDeque::with_capacity(capacity),
+            capacity,
+            next_seq: 1,
+            journal: None,
+            cursors: HashMap::new(),
+            cursors_path: None,
+            cursors_dirty: false,
+        };
+
+        if let Some(dir) = state_dir {
+            fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+            let journal_path = dir.join("events.jsonl");
+            let replayed = inner.load_journal(&journal_path)?;
+            // Rewrite once the journal holds far more than the window, keeping it bounded
+            if replayed > capacity * 2 {
+                inner.compact_journal(&journal_path)?;
+            }
+            let mut journal = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&journal_path)
+                .with_context(|| format!("opening {}", journal_path.display()))?;
+            // Terminate a torn final line so the next record starts on its own line
+            if !ends_with_newline(&journal_path)? {
+                journal.write_all(b"\n")?;
+            }
+            inner.journal = Some(journal);
+
+            let cursors_path = dir.join("cursors.json");
+            if cursors_path.exists() {
+                let raw = fs::read_to_string(&cursors_path)?;
+                inner.cursors = serde_json::from_str(&raw)
+                    .with_context(|| format!("parsing {}", cursors_path.display()))?;
+            }
+            inner.cursors_path = Some(cursors_path);
+
+            info!(
+                records = inner.window.len(),
+                next_seq = inner.next_seq,
+                clients = inner.cursors.len(),
+                "restored relay state"
+            );
+        }
+
+        let (live, _) = broadcast::channel(BROADCAST_CAPACITY);
+        Ok(Self {
+            inner: Mutex::new(inner),
+            live,
+        })
+    }
+
+    /// Assign the next sequence number, persist, and fan out to live subscribers
+    pub fn append(
+        &self,
+        slot: u64,
+        signature: &str,
+        program: String,
+        event: String,
+        data: Value,
+    ) -> Result<u64> {
+        let mut inner = self.inner.lock().unwrap();
+        let record = Arc::new(Record {
+            seq: inner.next_seq,
+            slot,
+            signature: signature.to_owned(),
+            program,
+            event,
+            data,
+        });
+
+        if let Some(journal) = inner.journal.as_mut() {
+            let mut line = serde_json::to_vec(record.as_ref())?;
+            line.push(b'\n');
+            journal.write_all(&line)?;
+        }
+
+        inner.next_seq += 1;
+        inner.push(record.clone());
+        // No receivers is fine; clients replay from the window when they connect
+        let _ = self.live.send(record.clone());
+        Ok(record.seq)
+    }
+
+    /// Subscribe to records appended from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<Record>> {
+        self.live.subscribe()
+    }
+
+    /// Oldest retained and latest assigned sequence numbers; latest is 0 before the first event
+    pub fn bounds(&self) -> (u64, u64) {
+        let inner = self.inner.lock().unwrap();
+        (
+            inner.window.front().map_or(inner.next_seq, |r| r.seq),
+            inner.next_seq - 1,
+        )
+    }
+
+    /// Records with sequence `from` and later, still in the window
+    pub fn replay(&self, from: u64) -> Replay {
+        let inner = self.inner.lock().unwrap();
+        let oldest = inner.window.front().map_or(inner.next_seq, |r| r.seq);
+        let start = from.max(oldest);
+        let records = inner
+            .window
+            .iter()
+            .skip((start - oldest) as usize)
+            .cloned()
+            .collect();
+
+        if from < oldest {
+            Replay::Gap { oldest, records }
+        } else {
+            Replay::Complete(records)
+        }
+    }
+
+    /// Last sequence number acknowledged by `client_id`
+    pub fn cursor(&self, client_id: &str) -> Option<u64> {
+        self.inner.lock().unwrap().cursors.get(client_id).copied()
+    }
+
+    /// Record that `client_id` has processed everything through `seq`
+    pub fn acknowledge(&self, client_id: &str, seq: u64) {
+        let mut inner = self.inner.lock().unwrap();
+        let cursor = inner.cursors.entry(client_id.to_owned()).or_default();
+        if seq > *cursor {
+            *cursor = seq;
+            inner.cursors_dirty = true;
+        }
+    }
+
+    /// Write client cursors to disk if any changed since the last flush
+    pub fn flush_cursors(&self) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        let Some(path) = inner.cursors_path.clone() else {
+            return Ok(());
+        };
+        if !inner.cursors_dirty {
+            return Ok(());
+        }
+
+        // Write-then-rename so a crash never leaves a truncated cursor file
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec(&inner.cursors)?)?;
+        fs::rename(&tmp, &path)?;
+        inner.cursors_dirty = false;
+        Ok(())
+    }
+}
+
+impl Inner {
+    // Helper function to append to the window, evicting the oldest record when full
+    fn push(&mut self, record: Arc<Record>) {
+        if self.window.len() == self.capacity {
+            self.window.pop_front();
+        }
+        self.window.push_back(record);
+    }
+
+    // Helper function to rebuild the window from a journal; returns the lines read
+    fn load_journal(&mut self, path: &Path) -> Result<usize> {
+        if !path.exists() {
+            return Ok(0);
+        }
+
+        let mut lines = 0;
+        for (number, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            let line = line?;
+            lines += 1;
+            match serde_json::from_str::<Record>(&line) {
+                Ok(record) => {
+                    self.next_seq = record.seq + 1;
+                    self.push(Arc::new(record));
+                }
+                // A torn final line from a crash is expected; anything else is worth a warning
+                Err(e) => warn!("skipping journal line {}: {e}", number + 1),
+            }
+        }
+        Ok(lines)
+    }
+
+    // Helper function to rewrite the journal with only the current window
+    fn compact_journal(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("jsonl.tmp");
+        let mut file = File::create(&tmp)?;
+        for record in &self.window {
+            let mut line = serde_json::to_vec(record.as_ref())?;
+            line.push(b'\n');
+            file.write_all(&line)?;
+        }
+        file.sync_all()?;
+        fs::rename(&tmp, path)?;
+        info!(records = self.window.len(), "compacted event journal");
+        Ok(())
+    }
+}
+
+// Helper function to check whether a file is empty or ends in a newline
+fn ends_with_newline(path: &Path) -> Result<bool> {
+    let mut file = File::open(path)?;
+    if file.metadata()?.len() == 0 {
+        return Ok(true);
+    }
+    let mut last = [0u8];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
@@ -0,0 +1,128 @@
+//! AgentMarket relay: fans out decoded program events over WebSocket with replay.
+//!
+//! The relay follows the registry, escrow, reputation, and royalty programs
+//! over `logsSubscribe`, numbers every decoded event with a monotonically
+//! increasing sequence, and keeps the most recent ones in a replay window.
+//! Clients subscribe from a sequence number (or resume from their acked
+//! cursor) so a reconnecting agent backend or UI never misses a status
+//! transition that is still in the window. With `--state-dir` the window and
+//! cursors survive restarts; see `server.rs` for the client protocol.
+//! With `--metrics-addr` it serves Prometheus metrics.
+
+mod event_log;
+mod server;
+mod source;
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use agentmarket_metrics::Metrics;
+use agentmarket_sdk::events::INDEXED_PROGRAMS;
+use agentmarket_sdk::RpcClient;
+use anyhow::Result;
+use clap::Parser;
+use tokio::net::TcpListener;
+use tracing::{error, info, warn};
+use tracing_subscriber::EnvFilter;
+
+use crate::event_log::EventLog;
+
+/// How often acknowledged cursors are written to the state directory
+const CURSOR_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the cluster tip is polled for the lag metric
+const TIP_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "agentmarket-relay",
+    about = "Relay AgentMarket program events to WebSocket clients"
+)]
+struct Args {
+    /// Upstream Solana websocket endpoint
+    #[arg(
+        long,
+        env = "AGENTMARKET_WS_URL",
+        default_value = "ws://127.0.0.1:8900"
+    )]
+    ws_url: String,
+
+    /// Address clients connect to
+    #[arg(long, env = "AGENTMARKET_RELAY_ADDR", default_value = "0.0.0.0:8910")]
+    listen: SocketAddr,
+
+    /// Events retained for replay
+    #[arg(long, default_value_t = 100_000)]
+    window: usize,
+
+    /// Directory for the event journal and client cursors; in-memory only when omitted
+    #[arg(long, env = "AGENTMARKET_RELAY_STATE_DIR")]
+    state_dir: Option<PathBuf>,
+
+    /// Serve Prometheus metrics on this address
+    #[arg(long, env = "AGENTMARKET_METRICS_ADDR")]
+    metrics_addr: Option<SocketAddr>,
+
+    /// JSON-RPC endpoint polled for the cluster tip when serving metrics
+    #[arg(
+        long,
+        env = "AGENTMARKET_RPC_URL",
+        default_value = "http://127.0.0.1:8899"
+    )]
+    rpc_url: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
+        .init();
+
+    let args = Args::parse();
+    anyhow::ensure!(args.window > 0, "--window must be greater than zero");
+    let log = Arc::new(EventLog::open(args.window, args.state_dir.as_deref())?);
+
+    let metrics = Metrics::new("relay");
+    if let Some(addr) = args.metrics_addr {
+        tokio::spawn(async move {
+            if let Err(e) = agentmarket_metrics::serve(addr).await {
+                error!("metrics server failed: {e}");
+            }
+        });
+        tokio::spawn(metrics.track_tip(RpcClient::new(&args.rpc_url), TIP_POLL_INTERVAL));
+    }
+
+    let source = {
+        let log = log.clone();
+        let ws_url = args.ws_url.clone();
+        tokio::spawn(async move { source::run(&ws_url, &log, &INDEXED_PROGRAMS, metrics).await })
+    };
+
+    let flusher = {
+        let log = log.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CURSOR_FLUSH_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(e) = log.flush_cursors() {
+                    warn!("failed to persist client cursors: {e:#}");
+                }
+            }
+        })
+    };
+
+    let listener = TcpListener::bind(args.listen).await?;
+    info!("relay listening on {}", args.listen);
+
+    tokio::select! {
+        result = server::serve(listener, log.clone()) => result?,
+        _ = tokio::signal::ctrl_c() => info!("shutting down"),
+    }
+
+    source.abort();
+    flusher.abort();
+    log.flush_cursors()?;
+    Ok(())
+}
@@ -0,0 +1,239 @@
+//! Client-facing WebSocket server: subscribe, replay from a sequence, then follow live.
+//!
+//! Protocol (JSON text frames):
+//!
+//! - client → `{"type":"subscribe","client_id":"ui-1","from":42,"events":[...],"mentions":[...]}`,
+//!   all fields optional. `from` is the first sequence to deliver; without it a
+//!   known `client_id` resumes after its last ack, and anyone else starts live.
+//!   `events` filters by event name and `mentions` by any pubkey in the event.
+//! - client → `{"type":"ack","seq":57}` advances the client's stored cursor.
+//! - server → `hello` with the retained range, optional `gap` when the requested
+//!   start was evicted, then `event` frames in sequence order.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{accept_async, WebSocketStream};
+use tracing::{debug, info, warn};
+
+use crate::event_log::{EventLog, Record, Replay};
+
+/// How long a new connection may take to send its subscribe message
+const SUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Keepalive ping interval for idle connections
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Subscribe {
+        client_id: Option<String>,
+        from: Option<u64>,
+        #[serde(default)]
+        events: Vec<String>,
+        #[serde(default)]
+        mentions: Vec<String>,
+    },
+    Ack {
+        seq: u64,
+    },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage<'a> {
+    Hello { oldest: u64, latest: u64, from: u64 },
+    Gap { requested: u64, oldest: u64 },
+    Event(&'a Record),
+    Error { message: String },
+}
+
+/// Which records a client wants
+struct Filter {
+    events: Vec<String>,
+    mentions: Vec<String>,
+}
+
+impl Filter {
+    fn matches(&self, record: &Record) -> bool {
+        let event_ok = self.events.is_empty() || self.events.contains(&record.event);
+        let mentions_ok = self.mentions.is_empty()
+            || record.data.as_object().is_some_and(|fields| {
+                fields
+                    .values()
+                    .filter_map(Value::as_str)
+                    .any(|value| self.mentions.iter().any(|m| m == value))
+            });
+        event_ok && mentions_ok
+    }
+}
+
+type Socket = WebSocketStream<TcpStream>;
+
+/// Accept clients forever
+pub async fn serve(listener: TcpListener, log: Arc<EventLog>) -> Result<()> {
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let log = log.clone();
+        tokio::spawn(async move {
+            match handle(stream, &log).await {
+                Ok(()) => debug!(%peer, "client disconnected"),
+                Err(e) => debug!(%peer, "client session ended: {e:#}"),
+            }
+        });
+    }
+}
+
+// Helper function to run one client session
+async fn handle(stream: TcpStream, log: &EventLog) -> Result<()> {
+    let mut socket = accept_async(stream).await?;
+
+    let (client_id, from, filter) =
+        match tokio::time::timeout(SUBSCRIBE_TIMEOUT, next_message(&mut socket)).await {
+            Ok(Ok(Some(ClientMessage::Subscribe {
+                client_id,
+                from,
+                events,
+                mentions,
+            }))) => (client_id, from, Filter { events, mentions }),
+            Ok(Ok(_)) | Err(_) => {
+                send(
+                    &mut socket,
+                    &ServerMessage::Error {
+                        message: "expected a subscribe message".into(),
+                    },
+                )
+                .await?;
+                return Ok(());
+            }
+            Ok(Err(e)) => return Err(e),
+        };
+
+    // Subscribe before reading the window so nothing lands between replay and live
+    let mut live = log.subscribe();
+    let (oldest, latest) = log.bounds();
+    let mut next = from
+        .or_else(|| {
+            client_id
+                .as_deref()
+                .and_then(|id| log.cursor(id))
+                .map(|acked| acked + 1)
+        })
+        .unwrap_or(latest + 1);
+
+    info!(
+        client = client_id.as_deref().unwrap_or("-"),
+        from = next,
+        "client subscribed"
+    );
+    send(
+        &mut socket,
+        &ServerMessage::Hello {
+            oldest,
+            latest,
+            from: next,
+        },
+    )
+    .await?;
+    next = catch_up(&mut socket, log, next, &filter).await?;
+
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    ping.tick().await;
+
+    loop {
+        tokio::select! {
+            received = live.recv() => match received {
+                Ok(record) if record.seq < next => {}
+                Ok(record) if record.seq == next => {
+                    if filter.matches(&record) {
+                        send(&mut socket, &ServerMessage::Event(&record)).await?;
+                    }
+                    next = record.seq + 1;
+                }
+                // Out of step with the live feed (after a lag): refill from the window
+                Ok(_) | Err(RecvError::Lagged(_)) => {
+                    next = catch_up(&mut socket, log, next, &filter).await?;
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            message = next_message(&mut socket) => match message? {
+                Some(ClientMessage::Ack { seq }) => {
+                    if let Some(id) = client_id.as_deref() {
+                        log.acknowledge(id, seq.min(next.saturating_sub(1)));
+                    }
+                }
+                Some(ClientMessage::Subscribe { .. }) => {
+                    send(
+                        &mut socket,
+                        &ServerMessage::Error { message: "already subscribed".into() },
+                    )
+                    .await?;
+                }
+                None => return Ok(()),
+            },
+            _ = ping.tick() => socket.send(Message::Ping(Vec::new().into())).await?,
+        }
+    }
+}
+
+// Helper function to send retained records from `next` onwards; returns the new `next`
+async fn catch_up(socket: &mut Socket, log: &EventLog, next: u64, filter: &Filter) -> Result<u64> {
+    let records = match log.replay(next) {
+        Replay::Complete(records) => records,
+        Replay::Gap { oldest, records } => {
+            warn!(
+                requested = next,
+                oldest, "client cursor older than the replay window"
+            );
+            send(
+                socket,
+                &ServerMessage::Gap {
+                    requested: next,
+                    oldest,
+                },
+            )
+            .await?;
+            records
+        }
+    };
+
+    let mut next = next;
+    for record in records {
+        if filter.matches(&record) {
+            send(socket, &ServerMessage::Event(&record)).await?;
+        }
+        next = record.seq + 1;
+    }
+    Ok(next)
+}
+
+// Helper function to read the next client message, answering pings; None on close
+async fn next_message(socket: &mut Socket) -> Result<Option<ClientMessage>> {
+    while let Some(message) = socket.next().await {
+        match message? {
+            Message::Text(text) => {
+                return serde_json::from_str(&text)
+                    .map(Some)
+                    .map_err(|e| anyhow!("invalid client message: {e}"));
+            }
+            Message::Ping(payload) => socket.send(Message::Pong(payload)).await?,
+            Message::Close(_) => return Ok(None),
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+// Helper function to serialize and send one server message
+async fn send(socket: &mut Socket, message: &ServerMessage<'_>) -> Result<()> {
+    let text = serde_json::to_string(message)?;
+    socket.send(Message::Text(text.into())).await?;
+    Ok(())
+}
@@ -0,0 +1,163 @@
+//! Upstream feed: decoded program events from the `logsSubscribe` websocket.
+
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+
+use agentmarket_metrics::Metrics;
+use agentmarket_sdk::events::parse_logs;
+use agentmarket_sdk::Pubkey;
+use anyhow::{anyhow, Result};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, info, warn};
+
+use crate::event_log::EventLog;
+
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Recent signatures remembered to drop transactions reported by several subscriptions
+const SEEN_SIGNATURES: usize = 10_000;
+
+/// Stream program logs into `log` forever, reconnecting with backoff on failure
+pub async fn run(ws_url: &str, log: &EventLog, programs: &[Pubkey], metrics: Metrics) {
+    let mut backoff = Duration::from_secs(1);
+    let mut seen = SeenSignatures::default();
+
+    loop {
+        match stream_once(ws_url, log, programs, &mut seen, &metrics).await {
+            Ok(()) => warn!("upstream websocket closed by server"),
+            Err(e) => warn!("upstream websocket error: {e:#}"),
+        }
+
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+// Helper function to hold one upstream session until it drops
+async fn stream_once(
+    ws_url: &str,
+    log: &EventLog,
+    programs: &[Pubkey],
+    seen: &mut SeenSignatures,
+    metrics: &Metrics,
+) -> Result<()> {
+    let (mut socket, _) = connect_async(ws_url).await?;
+
+    for (id, program_id) in programs.iter().enumerate() {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "logsSubscribe",
+            "params": [{"mentions": [program_id.to_string()]}, {"commitment": "confirmed"}],
+        });
+        socket
+            .send(Message::Text(request.to_string().into()))
+            .await?;
+    }
+
+    info!(
+        url = ws_url,
+        programs = programs.len(),
+        "subscribed to program logs"
+    );
+
+    while let Some(message) = socket.next().await {
+        let text = match message? {
+            Message::Text(text) => text,
+            Message::Ping(payload) => {
+                socket.send(Message::Pong(payload)).await?;
+                continue;
+            }
+            Message::Close(_) => return Ok(()),
+            _ => continue,
+        };
+
+        let value: Value = serde_json::from_str(&text)?;
+        if value.get("method").and_then(Value::as_str) != Some("logsNotification") {
+            if let Some(error) = value.get("error") {
+                return Err(anyhow!("subscription rejected: {error}"));
+            }
+            continue;
+        }
+
+        relay_notification(&value, log, seen, metrics)?;
+    }
+
+    Ok(())
+}
+
+// Helper function to append a successful transaction's events, once per signature
+fn relay_notification(
+    value: &Value,
+    log: &EventLog,
+    seen: &mut SeenSignatures,
+    metrics: &Metrics,
+) -> Result<()> {
+    let Some(result) = value.pointer("/params/result") else {
+        return Ok(());
+    };
+    let Some(notification) = result.get("value") else {
+        return Ok(());
+    };
+    let (Some(signature), Some(slot)) = (
+        notification.get("signature").and_then(Value::as_str),
+        result.pointer("/context/slot").and_then(Value::as_u64),
+    ) else {
+        return Ok(());
+    };
+    if !seen.insert(signature) {
+        return Ok(());
+    }
+    if !notification.get("err").is_none_or(Value::is_null) {
+        metrics.transaction_failed();
+        return Ok(());
+    }
+
+    let logs: Vec<&str> = notification
+        .get("logs")
+        .and_then(Value::as_array)
+        .map(|logs| logs.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    for (program_id, event) in parse_logs(&logs) {
+        let seq = log.append(
+            slot,
+            signature,
+            program_id.to_string(),
+            event.name().to_owned(),
+            event.to_json(),
+        )?;
+        debug!(seq, event = event.name(), signature, "relayed event");
+        metrics.event_processed(event.name());
+    }
+    metrics.slot_processed(slot);
+
+    Ok(())
+}
+
+/// Bounded set of recently relayed signatures
+#[derive(Default)]
+struct SeenSignatures {
+    set: HashSet<String>,
+    order: VecDeque<String>,
+}
+
+impl SeenSignatures {
+    // Helper function to remember `signature`; false if it was already seen
+    fn insert(&mut self, signature: &str) -> bool {
+        if self.set.contains(signature) {
+            return false;
+        }
+        if self.order.len() == SEEN_SIGNATURES {
+            if let Some(oldest) = self.order.pop_front() {
+                self.set.remove(&oldest);
+            }
+        }
+        self.set.insert(signature.to_owned());
+        self.order.push_back(signature.to_owned());
+        true
+    }
+}
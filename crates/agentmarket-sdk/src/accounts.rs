@@ -0,0 +1,93 @@
+//! Program account types and decoding helpers.
+
+pub use agent_registry::{
+    AcceptedMint, AccessGrant, AgentAttestation, AgentCapabilities, AgentEndpoint, AgentIndex,
+    AgentLineage, AgentProfile, AgentSale, AgentVersion, AllowlistEntry, BlockEntry, Bundle,
+    Capability, CapabilityIndex, CapabilityPrice, CapabilitySchema, CoCreator, CoCreatorSplit,
+    EndpointChallenge, FeaturedEntry, FeaturedIndex, HealthCheck, HealthChecker, HealthLog,
+    LanguageIndex, ListingStake, NameClaim, OperatorKey, PricingModel, ReferralCode,
+    RegistryConfig, ServiceOffering, StakeSlash, SubscriptionTier, Visibility, PROFILE_VERSION,
+};
+pub use marketplace_escrow::{
+    AgentRequestCount, ArbitrationConfig, DisputePanel, JurorPool, JurorSeat, RequestStatus,
+    ServiceRequest, TrialCounter,
+};
+pub use protocol_guardian::GuardianConfig;
+pub use reputation_system::{AgentReputationProfile, Rating, ReputationConfig};
+pub use royalty_splitter::{DistributionRecord, RoyaltyConfig};
+
+use std::collections::HashMap;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, Discriminator};
+
+use crate::error::SdkError;
+
+/// Decode raw account data, checking the Anchor discriminator
+pub fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T, SdkError> {
+    let mut slice = data;
+    T::try_deserialize(&mut slice).map_err(|e| SdkError::Decode(e.to_string()))
+}
+
+/// Whether raw account data carries the discriminator of `T`
+pub fn is_account<T: Discriminator>(data: &[u8]) -> bool {
+    data.starts_with(T::DISCRIMINATOR)
+}
+
+/// Declared formats of one of the agent's capabilities
+pub fn capability_schema<'a>(
+    profile: &'a AgentProfile,
+    capability_id: &str,
+) -> Option<&'a CapabilitySchema> {
+    profile
+        .capability_schemas
+        .iter()
+        .find(|schema| schema.capability_id == capability_id)
+}
+
+/// Whether `upstream`'s capability produces the format `downstream`'s
+/// capability takes; false unless both agents declared their schemas
+pub fn feeds_into(upstream: (&AgentProfile, &str), downstream: (&AgentProfile, &str)) -> bool {
+    match (
+        capability_schema(upstream.0, upstream.1),
+        capability_schema(downstream.0, downstream.1),
+    ) {
+        (Some(output), Some(input)) => output.output_schema == input.input_schema,
+        _ => false,
+    }
+}
+
+/// Every agent `root` transitively depends on, ordered so each comes after
+/// its own dependencies and `root` comes last. `None` if the graph has a
+/// cycle or a dependency's profile is missing from `profiles`
+pub fn dependency_order(
+    root: &Pubkey,
+    profiles: &HashMap<Pubkey, AgentProfile>,
+) -> Option<Vec<Pubkey>> {
+    let mut order = Vec::new();
+    visit_dependencies(root, profiles, &mut Vec::new(), &mut order)?;
+    Some(order)
+}
+
+// Helper function to append `agent` to `order` after its dependencies,
+// tracking the agents on the current path to spot cycles
+fn visit_dependencies(
+    agent: &Pubkey,
+    profiles: &HashMap<Pubkey, AgentProfile>,
+    path: &mut Vec<Pubkey>,
+    order: &mut Vec<Pubkey>,
+) -> Option<()> {
+    if order.contains(agent) {
+        return Some(());
+    }
+    if path.contains(agent) {
+        return None;
+    }
+    path.push(*agent);
+    for dependency in &profiles.get(agent)?.dependencies {
+        visit_dependencies(dependency, profiles, path, order)?;
+    }
+    path.pop();
+    order.push(*agent);
+    Some(())
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::Pubkey;
+use thiserror::Error;
+
+/// Errors returned by the SDK's RPC and decoding helpers
+#[derive(Debug, Error)]
+pub enum SdkError {
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("RPC error {code}: {message}")]
+    Rpc { code: i64, message: String },
+    #[error("unexpected RPC response: {0}")]
+    InvalidResponse(String),
+    #[error("account {0} not found")]
+    AccountNotFound(Pubkey),
+    #[error("failed to decode account: {0}")]
+    Decode(String),
+    #[error("failed to serialize transaction: {0}")]
+    Serialize(String),
+    #[error("signing failed: {0}")]
+    Signing(String),
+    #[error("no signer for required key {0}")]
+    MissingSigner(Pubkey),
+}
@@ -0,0 +1,831 @@
+//! Decoding of Anchor events emitted through `Program data:` log lines.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Value};
+
+// All three migrate instructions emit the same layout under the same name,
+// so the registry's type decodes each of them
+pub use agent_registry::{
+    AcceptedMintAdded, AcceptedMintRemoved, AccessConsumed, AccessGrantIssued, AccessGrantRevoked,
+    AccountMigrated, AgentAttestationIssued, AgentAttestationRevoked, AgentAuthoritySet,
+    AgentCapabilitiesAdded, AgentCapabilitiesRemoved, AgentCapacityUpdated, AgentDeregistered,
+    AgentForked, AgentFrozen, AgentHeartbeat, AgentListedForSale, AgentListingsPaused,
+    AgentListingsResumed, AgentManifestRegistered, AgentMarkedStale, AgentMetadataUpdated,
+    AgentOwnershipTransferred, AgentPromoted, AgentReferred, AgentRegistered, AgentRetired,
+    AgentSaleCancelled, AgentSold, AgentSoulbound, AgentTreeInitialized, AgentUnfrozen,
+    AgentUpdated, AgentVerificationRevoked, AgentVerified, AgentVersionPublished,
+    AgentVisibilitySet, AllowlistEntryAdded, AllowlistEntryRemoved, BundleCreated, BundleUpdated,
+    CapabilityAdded, CapabilityDeprecated, CapabilityPriceRemoved, CapabilityPriceSet,
+    CapabilitySchemaCleared, CapabilitySchemaSet, CoCreatorsUpdated, CollectionInitialized,
+    DependenciesSet, EndpointChallengeIssued, EndpointVerified, EndpointsRotated,
+    HealthCheckSubmitted, HealthCheckerAdded, HealthCheckerRemoved, LanguagesSet, ListingStaked,
+    MetadataAuthorityMigrated, OperatorRegistered, OperatorRevoked, PayoutWalletSet,
+    ProfileMigrated, ProfileResized, ReferralCodeCreated, ReferralFeesSwept, RegistrationFeesSwept,
+    RegistryConfigUpdated, RentToppedUp, ServiceOfferingAdded, ServiceOfferingRemoved,
+    ServiceOfferingUpdated, StakeSlashed, SubscriptionTierCreated, SubscriptionTierRetired,
+    SurgePriceResolved, TrialRequestsSet, UsdPriceResolved, UserBlocked, UserUnblocked,
+};
+pub use marketplace_escrow::{
+    AgentVersionPinned, ArbiterSet, BundleSelected, CapabilitySelected, CoCreatorPaid,
+    DisputeResolved, JurorLeft, JurorRegistered, JurorVoted, PanelDrawn, PanelSettled,
+    PaymentReleased, RequestCancelled, RequestClosed, RequestExpired, ResultDisputed,
+    ResultSubmitted, ServiceOfferingSelected, ServiceRequestCreated,
+};
+pub use reputation_system::{
+    AgentReputationInitialized, RatingModerated, RatingReported, RatingSubmitted,
+    ReputationAdminChanged,
+};
+pub use royalty_splitter::{
+    LineageRoyaltyPaid, PauseStateChanged, PaymentDistributed, PlatformFeesWithdrawn,
+    RoyaltyAdminChanged, RoyaltyConfigInitialized, RoyaltyConfigUpdated,
+};
+
+use crate::json::ToJson;
+use crate::{ESCROW_PROGRAM_ID, REGISTRY_PROGRAM_ID, REPUTATION_PROGRAM_ID, ROYALTY_PROGRAM_ID};
+
+/// An event emitted by one of the AgentMarket programs
+pub enum ProgramEvent {
+    AgentRegistered(AgentRegistered),
+    AgentUpdated(AgentUpdated),
+    AgentOwnershipTransferred(AgentOwnershipTransferred),
+    AgentListedForSale(AgentListedForSale),
+    AgentSaleCancelled(AgentSaleCancelled),
+    AgentSold(AgentSold),
+    AgentVersionPublished(AgentVersionPublished),
+    ServiceOfferingAdded(ServiceOfferingAdded),
+    ServiceOfferingUpdated(ServiceOfferingUpdated),
+    ServiceOfferingRemoved(ServiceOfferingRemoved),
+    CapabilityPriceSet(CapabilityPriceSet),
+    CapabilityPriceRemoved(CapabilityPriceRemoved),
+    BundleCreated(BundleCreated),
+    BundleUpdated(BundleUpdated),
+    SubscriptionTierCreated(SubscriptionTierCreated),
+    SubscriptionTierRetired(SubscriptionTierRetired),
+    AccessGrantIssued(AccessGrantIssued),
+    AccessConsumed(AccessConsumed),
+    AccessGrantRevoked(AccessGrantRevoked),
+    AgentDeregistered(AgentDeregistered),
+    AgentRetired(AgentRetired),
+    AgentAuthoritySet(AgentAuthoritySet),
+    CollectionInitialized(CollectionInitialized),
+    AgentTreeInitialized(AgentTreeInitialized),
+    CapabilityAdded(CapabilityAdded),
+    CapabilityDeprecated(CapabilityDeprecated),
+    AcceptedMintAdded(AcceptedMintAdded),
+    AcceptedMintRemoved(AcceptedMintRemoved),
+    HealthCheckerAdded(HealthCheckerAdded),
+    HealthCheckerRemoved(HealthCheckerRemoved),
+    HealthCheckSubmitted(HealthCheckSubmitted),
+    UsdPriceResolved(UsdPriceResolved),
+    SurgePriceResolved(SurgePriceResolved),
+    EndpointChallengeIssued(EndpointChallengeIssued),
+    EndpointVerified(EndpointVerified),
+    EndpointsRotated(EndpointsRotated),
+    PayoutWalletSet(PayoutWalletSet),
+    ProfileResized(ProfileResized),
+    AgentCapabilitiesAdded(AgentCapabilitiesAdded),
+    AgentCapabilitiesRemoved(AgentCapabilitiesRemoved),
+    RentToppedUp(RentToppedUp),
+    ProfileMigrated(ProfileMigrated),
+    AgentVerified(AgentVerified),
+    AgentVerificationRevoked(AgentVerificationRevoked),
+    AgentFrozen(AgentFrozen),
+    AgentUnfrozen(AgentUnfrozen),
+    AgentAttestationIssued(AgentAttestationIssued),
+    AgentAttestationRevoked(AgentAttestationRevoked),
+    CoCreatorsUpdated(CoCreatorsUpdated),
+    AgentHeartbeat(AgentHeartbeat),
+    AgentMarkedStale(AgentMarkedStale),
+    AgentCapacityUpdated(AgentCapacityUpdated),
+    AgentListingsPaused(AgentListingsPaused),
+    AgentListingsResumed(AgentListingsResumed),
+    AgentVisibilitySet(AgentVisibilitySet),
+    TrialRequestsSet(TrialRequestsSet),
+    LanguagesSet(LanguagesSet),
+    DependenciesSet(DependenciesSet),
+    MetadataAuthorityMigrated(MetadataAuthorityMigrated),
+    AgentMetadataUpdated(AgentMetadataUpdated),
+    AllowlistEntryAdded(AllowlistEntryAdded),
+    AllowlistEntryRemoved(AllowlistEntryRemoved),
+    UserBlocked(UserBlocked),
+    UserUnblocked(UserUnblocked),
+    CapabilitySchemaSet(CapabilitySchemaSet),
+    CapabilitySchemaCleared(CapabilitySchemaCleared),
+    AgentForked(AgentForked),
+    AgentManifestRegistered(AgentManifestRegistered),
+    OperatorRegistered(OperatorRegistered),
+    OperatorRevoked(OperatorRevoked),
+    AgentSoulbound(AgentSoulbound),
+    RegistryConfigUpdated(RegistryConfigUpdated),
+    RegistrationFeesSwept(RegistrationFeesSwept),
+    ReferralCodeCreated(ReferralCodeCreated),
+    AgentReferred(AgentReferred),
+    ReferralFeesSwept(ReferralFeesSwept),
+    ListingStaked(ListingStaked),
+    StakeSlashed(StakeSlashed),
+    AgentPromoted(AgentPromoted),
+    ServiceRequestCreated(ServiceRequestCreated),
+    ResultSubmitted(ResultSubmitted),
+    PaymentReleased(PaymentReleased),
+    CoCreatorPaid(CoCreatorPaid),
+    ResultDisputed(ResultDisputed),
+    RequestCancelled(RequestCancelled),
+    RequestExpired(RequestExpired),
+    RequestClosed(RequestClosed),
+    ArbiterSet(ArbiterSet),
+    DisputeResolved(DisputeResolved),
+    JurorRegistered(JurorRegistered),
+    JurorLeft(JurorLeft),
+    PanelDrawn(PanelDrawn),
+    JurorVoted(JurorVoted),
+    PanelSettled(PanelSettled),
+    AgentVersionPinned(AgentVersionPinned),
+    ServiceOfferingSelected(ServiceOfferingSelected),
+    BundleSelected(BundleSelected),
+    CapabilitySelected(CapabilitySelected),
+    AgentReputationInitialized(AgentReputationInitialized),
+    RatingSubmitted(RatingSubmitted),
+    RatingReported(RatingReported),
+    RatingModerated(RatingModerated),
+    ReputationAdminChanged(ReputationAdminChanged),
+    RoyaltyConfigInitialized(RoyaltyConfigInitialized),
+    RoyaltyConfigUpdated(RoyaltyConfigUpdated),
+    PaymentDistributed(PaymentDistributed),
+    RoyaltyAdminChanged(RoyaltyAdminChanged),
+    PlatformFeesWithdrawn(PlatformFeesWithdrawn),
+    PauseStateChanged(PauseStateChanged),
+    LineageRoyaltyPaid(LineageRoyaltyPaid),
+    AccountMigrated(AccountMigrated),
+}
+
+// Helper macro to render the named fields of an event as a JSON object
+macro_rules! fields {
+    ($event:expr; $($field:ident),+ $(,)?) => {
+        json!({ $(stringify!($field): ToJson::to_json(&$event.$field)),+ })
+    };
+}
+
+impl ProgramEvent {
+    /// The event's type name, as declared in the program
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AgentRegistered(_) => "AgentRegistered",
+            Self::AgentUpdated(_) => "AgentUpdated",
+            Self::AgentOwnershipTransferred(_) => "AgentOwnershipTransferred",
+            Self::AgentListedForSale(_) => "AgentListedForSale",
+            Self::AgentSaleCancelled(_) => "AgentSaleCancelled",
+            Self::AgentSold(_) => "AgentSold",
+            Self::AgentVersionPublished(_) => "AgentVersionPublished",
+            Self::ServiceOfferingAdded(_) => "ServiceOfferingAdded",
+            Self::ServiceOfferingUpdated(_) => "ServiceOfferingUpdated",
+            Self::ServiceOfferingRemoved(_) => "ServiceOfferingRemoved",
+            Self::CapabilityPriceSet(_) => "CapabilityPriceSet",
+            Self::CapabilityPriceRemoved(_) => "CapabilityPriceRemoved",
+            Self::BundleCreated(_) => "BundleCreated",
+            Self::BundleUpdated(_) => "BundleUpdated",
+            Self::SubscriptionTierCreated(_) => "SubscriptionTierCreated",
+            Self::SubscriptionTierRetired(_) => "SubscriptionTierRetired",
+            Self::AccessGrantIssued(_) => "AccessGrantIssued",
+            Self::AccessConsumed(_) => "AccessConsumed",
+            Self::AccessGrantRevoked(_) => "AccessGrantRevoked",
+            Self::AgentDeregistered(_) => "AgentDeregistered",
+            Self::AgentRetired(_) => "AgentRetired",
+            Self::AgentAuthoritySet(_) => "AgentAuthoritySet",
+            Self::CollectionInitialized(_) => "CollectionInitialized",
+            Self::AgentTreeInitialized(_) => "AgentTreeInitialized",
+            Self::CapabilityAdded(_) => "CapabilityAdded",
+            Self::CapabilityDeprecated(_) => "CapabilityDeprecated",
+            Self::AcceptedMintAdded(_) => "AcceptedMintAdded",
+            Self::AcceptedMintRemoved(_) => "AcceptedMintRemoved",
+            Self::HealthCheckerAdded(_) => "HealthCheckerAdded",
+            Self::HealthCheckerRemoved(_) => "HealthCheckerRemoved",
+            Self::HealthCheckSubmitted(_) => "HealthCheckSubmitted",
+            Self::UsdPriceResolved(_) => "UsdPriceResolved",
+            Self::SurgePriceResolved(_) => "SurgePriceResolved",
+            Self::EndpointChallengeIssued(_) => "EndpointChallengeIssued",
+            Self::EndpointVerified(_) => "EndpointVerified",
+            Self::EndpointsRotated(_) => "EndpointsRotated",
+            Self::PayoutWalletSet(_) => "PayoutWalletSet",
+            Self::ProfileResized(_) => "ProfileResized",
+            Self::AgentCapabilitiesAdded(_) => "AgentCapabilitiesAdded",
+            Self::AgentCapabilitiesRemoved(_) => "AgentCapabilitiesRemoved",
+            Self::RentToppedUp(_) => "RentToppedUp",
+            Self::ProfileMigrated(_) => "ProfileMigrated",
+            Self::AgentVerified(_) => "AgentVerified",
+            Self::AgentVerificationRevoked(_) => "AgentVerificationRevoked",
+            Self::AgentFrozen(_) => "AgentFrozen",
+            Self::AgentUnfrozen(_) => "AgentUnfrozen",
+            Self::AgentAttestationIssued(_) => "AgentAttestationIssued",
+            Self::AgentAttestationRevoked(_) => "AgentAttestationRevoked",
+            Self::CoCreatorsUpdated(_) => "CoCreatorsUpdated",
+            Self::AgentHeartbeat(_) => "AgentHeartbeat",
+            Self::AgentMarkedStale(_) => "AgentMarkedStale",
+            Self::AgentCapacityUpdated(_) => "AgentCapacityUpdated",
+            Self::AgentListingsPaused(_) => "AgentListingsPaused",
+            Self::AgentListingsResumed(_) => "AgentListingsResumed",
+            Self::AgentVisibilitySet(_) => "AgentVisibilitySet",
+            Self::TrialRequestsSet(_) => "TrialRequestsSet",
+            Self::LanguagesSet(_) => "LanguagesSet",
+            Self::DependenciesSet(_) => "DependenciesSet",
+            Self::MetadataAuthorityMigrated(_) => "MetadataAuthorityMigrated",
+            Self::AgentMetadataUpdated(_) => "AgentMetadataUpdated",
+            Self::AllowlistEntryAdded(_) => "AllowlistEntryAdded",
+            Self::AllowlistEntryRemoved(_) => "AllowlistEntryRemoved",
+            Self::UserBlocked(_) => "UserBlocked",
+            Self::UserUnblocked(_) => "UserUnblocked",
+            Self::CapabilitySchemaSet(_) => "CapabilitySchemaSet",
+            Self::CapabilitySchemaCleared(_) => "CapabilitySchemaCleared",
+            Self::AgentForked(_) => "AgentForked",
+            Self::AgentManifestRegistered(_) => "AgentManifestRegistered",
+            Self::OperatorRegistered(_) => "OperatorRegistered",
+            Self::OperatorRevoked(_) => "OperatorRevoked",
+            Self::AgentSoulbound(_) => "AgentSoulbound",
+            Self::RegistryConfigUpdated(_) => "RegistryConfigUpdated",
+            Self::RegistrationFeesSwept(_) => "RegistrationFeesSwept",
+            Self::ReferralCodeCreated(_) => "ReferralCodeCreated",
+            Self::AgentReferred(_) => "AgentReferred",
+            Self::ReferralFeesSwept(_) => "ReferralFeesSwept",
+            Self::ListingStaked(_) => "ListingStaked",
+            Self::StakeSlashed(_) => "StakeSlashed",
+            Self::AgentPromoted(_) => "AgentPromoted",
+            Self::ServiceRequestCreated(_) => "ServiceRequestCreated",
+            Self::ResultSubmitted(_) => "ResultSubmitted",
+            Self::PaymentReleased(_) => "PaymentReleased",
+            Self::CoCreatorPaid(_) => "CoCreatorPaid",
+            Self::ResultDisputed(_) => "ResultDisputed",
+            Self::RequestCancelled(_) => "RequestCancelled",
+            Self::RequestExpired(_) => "RequestExpired",
+            Self::RequestClosed(_) => "RequestClosed",
+            Self::ArbiterSet(_) => "ArbiterSet",
+            Self::DisputeResolved(_) => "DisputeResolved",
+            Self::JurorRegistered(_) => "JurorRegistered",
+            Self::JurorLeft(_) => "JurorLeft",
+            Self::PanelDrawn(_) => "PanelDrawn",
+            Self::JurorVoted(_) => "JurorVoted",
+            Self::PanelSettled(_) => "PanelSettled",
+            Self::AgentVersionPinned(_) => "AgentVersionPinned",
+            Self::ServiceOfferingSelected(_) => "ServiceOfferingSelected",
+            Self::BundleSelected(_) => "BundleSelected",
+            Self::CapabilitySelected(_) => "CapabilitySelected",
+            Self::AgentReputationInitialized(_) => "AgentReputationInitialized",
+            Self::RatingSubmitted(_) => "RatingSubmitted",
+            Self::RatingReported(_) => "RatingReported",
+            Self::RatingModerated(_) => "RatingModerated",
+            Self::ReputationAdminChanged(_) => "ReputationAdminChanged",
+            Self::RoyaltyConfigInitialized(_) => "RoyaltyConfigInitialized",
+            Self::RoyaltyConfigUpdated(_) => "RoyaltyConfigUpdated",
+            Self::PaymentDistributed(_) => "PaymentDistributed",
+            Self::RoyaltyAdminChanged(_) => "RoyaltyAdminChanged",
+            Self::PlatformFeesWithdrawn(_) => "PlatformFeesWithdrawn",
+            Self::PauseStateChanged(_) => "PauseStateChanged",
+            Self::LineageRoyaltyPaid(_) => "LineageRoyaltyPaid",
+            Self::AccountMigrated(_) => "AccountMigrated",
+        }
+    }
+
+    /// The event's fields as a JSON object, with pubkeys as base58 strings
+    pub fn to_json(&self) -> Value {
+        match self {
+            Self::AgentRegistered(e) => {
+                fields!(e; agent_id, creator, name, capabilities, nft_mint, timestamp)
+            }
+            Self::AgentUpdated(e) => fields!(e; agent_id, creator, name, is_active, timestamp),
+            Self::AgentOwnershipTransferred(e) => {
+                fields!(e; agent_id, previous_owner, new_owner, nft_mint, timestamp)
+            }
+            Self::AgentListedForSale(e) => {
+                fields!(e; agent_id, seller, nft_mint, price, timestamp)
+            }
+            Self::AgentSaleCancelled(e) => fields!(e; agent_id, seller, timestamp),
+            Self::AgentSold(e) => fields!(e; agent_id, seller, buyer, price, timestamp),
+            Self::AgentVersionPublished(e) => fields!(
+                e;
+                agent_id,
+                agent_version,
+                major,
+                minor,
+                patch,
+                ipfs_hash,
+                changelog_hash,
+                timestamp
+            ),
+            Self::ServiceOfferingAdded(e) => fields!(
+                e;
+                agent_id,
+                service_offering,
+                offering_id,
+                name,
+                price,
+                turnaround_secs,
+                timestamp
+            ),
+            Self::ServiceOfferingUpdated(e) => fields!(
+                e;
+                agent_id,
+                service_offering,
+                price,
+                turnaround_secs,
+                is_active,
+                timestamp
+            ),
+            Self::ServiceOfferingRemoved(e) => {
+                fields!(e; agent_id, service_offering, timestamp)
+            }
+            Self::CapabilityPriceSet(e) => {
+                fields!(e; agent_id, capability_price, capability_id, price, timestamp)
+            }
+            Self::CapabilityPriceRemoved(e) => {
+                fields!(e; agent_id, capability_price, capability_id, timestamp)
+            }
+            Self::BundleCreated(e) => fields!(
+                e;
+                bundle,
+                curator,
+                bundle_id,
+                name,
+                agents,
+                price,
+                discount_bps,
+                timestamp
+            ),
+            Self::BundleUpdated(e) => {
+                fields!(e; bundle, curator, price, discount_bps, is_active, timestamp)
+            }
+            Self::SubscriptionTierCreated(e) => fields!(
+                e;
+                agent_id,
+                subscription_tier,
+                tier_id,
+                name,
+                monthly_price,
+                requests_per_month,
+                timestamp
+            ),
+            Self::SubscriptionTierRetired(e) => {
+                fields!(e; agent_id, subscription_tier, tier_id, timestamp)
+            }
+            Self::AccessGrantIssued(e) => fields!(
+                e;
+                agent_id,
+                user,
+                subscription_tier,
+                months,
+                amount,
+                expires_at,
+                remaining_quota,
+                timestamp
+            ),
+            Self::AccessConsumed(e) => {
+                fields!(e; agent_id, user, units, remaining_quota, timestamp)
+            }
+            Self::AccessGrantRevoked(e) => fields!(e; agent_id, user, timestamp),
+            Self::AgentDeregistered(e) => fields!(e; agent_id, creator, nft_mint, timestamp),
+            Self::AgentRetired(e) => fields!(e; agent_id, successor, timestamp),
+            Self::AgentAuthoritySet(e) => fields!(e; agent_id, authority, timestamp),
+            Self::CollectionInitialized(e) => fields!(e; collection_mint, authority, timestamp),
+            Self::AgentTreeInitialized(e) => {
+                fields!(e; merkle_tree, max_depth, max_buffer_size, timestamp)
+            }
+            Self::CapabilityAdded(e) => fields!(e; capability, id, name, timestamp),
+            Self::CapabilityDeprecated(e) => fields!(e; capability, id, timestamp),
+            Self::AcceptedMintAdded(e) => fields!(e; mint, decimals, timestamp),
+            Self::AcceptedMintRemoved(e) => fields!(e; mint, timestamp),
+            Self::HealthCheckerAdded(e) => fields!(e; checker, timestamp),
+            Self::HealthCheckerRemoved(e) => fields!(e; checker, timestamp),
+            Self::HealthCheckSubmitted(e) => fields!(
+                e;
+                agent_id,
+                checker,
+                healthy,
+                latency_ms,
+                uptime_bps,
+                timestamp
+            ),
+            Self::UsdPriceResolved(e) => {
+                fields!(e; agent_id, usd_cents, price, expo, lamports, timestamp)
+            }
+            Self::SurgePriceResolved(e) => {
+                fields!(e; agent_id, open_requests, lamports, timestamp)
+            }
+            Self::EndpointChallengeIssued(e) => {
+                fields!(e; agent_id, endpoints, nonce, timestamp)
+            }
+            Self::EndpointVerified(e) => {
+                fields!(e; agent_id, endpoints, endpoint_key, timestamp)
+            }
+            Self::EndpointsRotated(e) => fields!(e; agent_id, endpoints, timestamp),
+            Self::PayoutWalletSet(e) => fields!(e; agent_id, payout_wallet, timestamp),
+            Self::ProfileResized(e) => {
+                fields!(e; agent_id, capability_limit, description_limit, size, timestamp)
+            }
+            Self::AgentCapabilitiesAdded(e) => {
+                fields!(e; agent_id, capabilities, count, timestamp)
+            }
+            Self::AgentCapabilitiesRemoved(e) => {
+                fields!(e; agent_id, capabilities, count, timestamp)
+            }
+            Self::RentToppedUp(e) => {
+                fields!(e; agent_id, account, target, funder, amount, timestamp)
+            }
+            Self::ProfileMigrated(e) => {
+                fields!(e; agent_id, from_version, version, size, timestamp)
+            }
+            Self::AgentVerified(e) => fields!(e; agent_id, verifier, timestamp),
+            Self::AgentVerificationRevoked(e) => fields!(e; agent_id, verifier, timestamp),
+            Self::AgentFrozen(e) => fields!(e; agent_id, authority, timestamp),
+            Self::AgentUnfrozen(e) => fields!(e; agent_id, authority, timestamp),
+            Self::AgentAttestationIssued(e) => fields!(
+                e;
+                attestation,
+                agent_id,
+                issuer,
+                schema,
+                data_hash,
+                expires_at,
+                timestamp
+            ),
+            Self::AgentAttestationRevoked(e) => {
+                fields!(e; attestation, agent_id, issuer, schema, timestamp)
+            }
+            Self::CoCreatorsUpdated(e) => fields!(e; agent_id, co_creators, timestamp),
+            Self::AgentHeartbeat(e) => fields!(e; agent_id, timestamp),
+            Self::AgentMarkedStale(e) => fields!(e; agent_id, last_seen_at, timestamp),
+            Self::AgentCapacityUpdated(e) => {
+                fields!(e; agent_id, max_concurrent_requests, timestamp)
+            }
+            Self::AgentListingsPaused(e) => fields!(e; agent_id, paused_until, timestamp),
+            Self::AgentListingsResumed(e) => fields!(e; agent_id, timestamp),
+            Self::AgentVisibilitySet(e) => fields!(e; agent_id, visibility, timestamp),
+            Self::TrialRequestsSet(e) => fields!(e; agent_id, trial_requests, timestamp),
+            Self::LanguagesSet(e) => fields!(e; agent_id, languages, timestamp),
+            Self::DependenciesSet(e) => fields!(e; agent_id, dependencies, timestamp),
+            Self::MetadataAuthorityMigrated(e) => fields!(
+                e;
+                agent_id,
+                previous_authority,
+                metadata_authority,
+                timestamp
+            ),
+            Self::AgentMetadataUpdated(e) => {
+                fields!(e; agent_id, name, symbol, uri, timestamp)
+            }
+            Self::AllowlistEntryAdded(e) => fields!(e; agent_id, user, timestamp),
+            Self::AllowlistEntryRemoved(e) => fields!(e; agent_id, user, timestamp),
+            Self::UserBlocked(e) => fields!(e; agent_id, user, timestamp),
+            Self::UserUnblocked(e) => fields!(e; agent_id, user, timestamp),
+            Self::CapabilitySchemaSet(e) => fields!(
+                e;
+                agent_id,
+                capability_id,
+                input_schema,
+                output_schema,
+                timestamp
+            ),
+            Self::CapabilitySchemaCleared(e) => fields!(e; agent_id, capability_id, timestamp),
+            Self::AgentForked(e) => {
+                fields!(e; agent_id, parent_agent, lineage_royalty_bps, timestamp)
+            }
+            Self::AgentManifestRegistered(e) => {
+                fields!(e; agent_id, operator, manifest_hash, timestamp)
+            }
+            Self::OperatorRegistered(e) => fields!(e; creator, operator, timestamp),
+            Self::OperatorRevoked(e) => fields!(e; creator, operator, timestamp),
+            Self::AgentSoulbound(e) => fields!(e; agent_id, token_account, timestamp),
+            Self::RegistryConfigUpdated(e) => fields!(
+                e;
+                listing_stake,
+                slash_authority,
+                featured_price_per_day,
+                verifier,
+                heartbeat_window,
+                registration_fee,
+                timestamp
+            ),
+            Self::RegistrationFeesSwept(e) => fields!(e; amount, timestamp),
+            Self::ReferralCodeCreated(e) => {
+                fields!(e; referral_code, referrer, code, timestamp)
+            }
+            Self::AgentReferred(e) => {
+                fields!(e; agent_id, referral_code, referrer, share, timestamp)
+            }
+            Self::ReferralFeesSwept(e) => {
+                fields!(e; referral_code, referrer, amount, timestamp)
+            }
+            Self::ListingStaked(e) => fields!(e; agent_id, amount, timestamp),
+            Self::StakeSlashed(e) => {
+                fields!(e; agent_id, request_id, user, amount, remaining, timestamp)
+            }
+            Self::AgentPromoted(e) => {
+                fields!(e; agent_id, holder, days, amount, featured_until, timestamp)
+            }
+            Self::ServiceRequestCreated(e) => {
+                fields!(e; request_id, agent_id, user, amount, payment_mint, timestamp)
+            }
+            Self::ResultSubmitted(e) => fields!(e; request_id, agent_id, timestamp),
+            Self::PaymentReleased(e) => fields!(
+                e;
+                request_id,
+                creator,
+                creator_amount,
+                platform_amount,
+                treasury_amount,
+                timestamp,
+            ),
+            Self::CoCreatorPaid(e) => fields!(e; request_id, co_creator, amount),
+            Self::ResultDisputed(e) => fields!(e; request_id, user, reason, timestamp),
+            Self::RequestCancelled(e) => fields!(e; request_id, user, refund_amount, timestamp),
+            Self::RequestExpired(e) => {
+                fields!(e; request_id, agent_id, user, refund_amount, timestamp)
+            }
+            Self::RequestClosed(e) => fields!(e; request_id, agent_id, user, timestamp),
+            Self::ArbiterSet(e) => fields!(e; arbiter, timestamp),
+            Self::DisputeResolved(e) => fields!(
+                e;
+                request_id,
+                agent_id,
+                arbiter,
+                outcome,
+                agent_amount,
+                refund_amount,
+                timestamp,
+            ),
+            Self::JurorRegistered(e) => fields!(e; juror, stake, timestamp),
+            Self::JurorLeft(e) => fields!(e; juror, stake, timestamp),
+            Self::PanelDrawn(e) => fields!(e; request_id, agent_id, jurors, voting_ends_at),
+            Self::JurorVoted(e) => fields!(e; request_id, juror, verdict, timestamp),
+            Self::PanelSettled(e) => fields!(
+                e;
+                request_id,
+                agent_id,
+                votes_for_agent,
+                votes_for_user,
+                agent_amount,
+                refund_amount,
+                juror_rewards,
+                forfeited,
+                timestamp,
+            ),
+            Self::AgentVersionPinned(e) => {
+                fields!(e; request_id, agent_id, agent_version, timestamp)
+            }
+            Self::ServiceOfferingSelected(e) => {
+                fields!(e; request_id, agent_id, service_offering, price, timestamp)
+            }
+            Self::BundleSelected(e) => {
+                fields!(e; request_id, agent_id, bundle, share_price, timestamp)
+            }
+            Self::CapabilitySelected(e) => {
+                fields!(e; request_id, agent_id, capability_price, price, timestamp)
+            }
+            Self::AgentReputationInitialized(e) => fields!(e; agent_id),
+            Self::RatingSubmitted(e) => fields!(e; rating_id, agent_id, user, stars, new_average),
+            Self::RatingReported(e) => fields!(e; rating_id, reporter, reason),
+            Self::RatingModerated(e) => fields!(e; rating_id, is_valid, moderator),
+            Self::ReputationAdminChanged(e) => fields!(e; previous_admin, new_admin),
+            Self::RoyaltyConfigInitialized(e) => fields!(
+                e;
+                creator_share,
+                platform_share,
+                treasury_share,
+                platform_wallet,
+                treasury_wallet,
+            ),
+            Self::RoyaltyConfigUpdated(e) => fields!(
+                e;
+                creator_share,
+                platform_share,
+                treasury_share,
+                platform_wallet,
+                treasury_wallet,
+            ),
+            Self::PaymentDistributed(e) => fields!(
+                e;
+                distribution_id,
+                creator,
+                total_amount,
+                creator_amount,
+                platform_amount,
+                treasury_amount,
+            ),
+            Self::RoyaltyAdminChanged(e) => fields!(e; previous_admin, new_admin),
+            Self::PlatformFeesWithdrawn(e) => fields!(e; amount, destination, withdrawn_by),
+            Self::PauseStateChanged(e) => fields!(e; is_paused, changed_by),
+            Self::LineageRoyaltyPaid(e) => {
+                fields!(e; agent_id, parent_agent, parent_creator, amount)
+            }
+            Self::AccountMigrated(e) => fields!(e; account, size, timestamp),
+        }
+    }
+}
+
+/// Program ids whose events this module understands
+pub const INDEXED_PROGRAMS: [Pubkey; 4] = [
+    REGISTRY_PROGRAM_ID,
+    ESCROW_PROGRAM_ID,
+    REPUTATION_PROGRAM_ID,
+    ROYALTY_PROGRAM_ID,
+];
+
+// Helper macro to try each event type's discriminator in turn
+macro_rules! decode_as {
+    ($data:expr, $($event:ident),+ $(,)?) => {{
+        $(
+            if $data.starts_with($event::DISCRIMINATOR) {
+                let mut body = &$data[$event::DISCRIMINATOR.len()..];
+                return $event::deserialize(&mut body).ok().map(ProgramEvent::$event);
+            }
+        )+
+        None
+    }};
+}
+
+/// Decode one event payload emitted by `program_id`
+pub fn decode_event(program_id: &Pubkey, data: &[u8]) -> Option<ProgramEvent> {
+    if *program_id == REGISTRY_PROGRAM_ID {
+        decode_as!(
+            data,
+            AgentRegistered,
+            AgentUpdated,
+            AgentOwnershipTransferred,
+            AgentListedForSale,
+            AgentSaleCancelled,
+            AgentSold,
+            AgentVersionPublished,
+            ServiceOfferingAdded,
+            ServiceOfferingUpdated,
+            ServiceOfferingRemoved,
+            CapabilityPriceSet,
+            CapabilityPriceRemoved,
+            BundleCreated,
+            BundleUpdated,
+            SubscriptionTierCreated,
+            SubscriptionTierRetired,
+            AccessGrantIssued,
+            AccessConsumed,
+            AccessGrantRevoked,
+            AgentDeregistered,
+            AgentRetired,
+            AgentAuthoritySet,
+            CollectionInitialized,
+            AgentTreeInitialized,
+            CapabilityAdded,
+            CapabilityDeprecated,
+            AcceptedMintAdded,
+            AcceptedMintRemoved,
+            HealthCheckerAdded,
+            HealthCheckerRemoved,
+            HealthCheckSubmitted,
+            UsdPriceResolved,
+            SurgePriceResolved,
+            EndpointChallengeIssued,
+            EndpointVerified,
+            EndpointsRotated,
+            PayoutWalletSet,
+            ProfileResized,
+            AgentCapabilitiesAdded,
+            AgentCapabilitiesRemoved,
+            RentToppedUp,
+            ProfileMigrated,
+            AgentVerified,
+            AgentVerificationRevoked,
+            AgentFrozen,
+            AgentUnfrozen,
+            AgentAttestationIssued,
+            AgentAttestationRevoked,
+            CoCreatorsUpdated,
+            AgentHeartbeat,
+            AgentMarkedStale,
+            AgentCapacityUpdated,
+            AgentListingsPaused,
+            AgentListingsResumed,
+            AgentVisibilitySet,
+            TrialRequestsSet,
+            LanguagesSet,
+            DependenciesSet,
+            MetadataAuthorityMigrated,
+            AgentMetadataUpdated,
+            AllowlistEntryAdded,
+            AllowlistEntryRemoved,
+            UserBlocked,
+            UserUnblocked,
+            CapabilitySchemaSet,
+            CapabilitySchemaCleared,
+            AgentForked,
+            AgentManifestRegistered,
+            OperatorRegistered,
+            OperatorRevoked,
+            AgentSoulbound,
+            RegistryConfigUpdated,
+            RegistrationFeesSwept,
+            ReferralCodeCreated,
+            AgentReferred,
+            ReferralFeesSwept,
+            ListingStaked,
+            StakeSlashed,
+            AgentPromoted,
+            AccountMigrated,
+        )
+    } else if *program_id == ESCROW_PROGRAM_ID {
+        decode_as!(
+            data,
+            ServiceRequestCreated,
+            ResultSubmitted,
+            PaymentReleased,
+            CoCreatorPaid,
+            ResultDisputed,
+            RequestCancelled,
+            RequestExpired,
+            RequestClosed,
+            ArbiterSet,
+            DisputeResolved,
+            JurorRegistered,
+            JurorLeft,
+            PanelDrawn,
+            JurorVoted,
+            PanelSettled,
+            AgentVersionPinned,
+            ServiceOfferingSelected,
+            BundleSelected,
+            CapabilitySelected,
+            AccountMigrated,
+        )
+    } else if *program_id == REPUTATION_PROGRAM_ID {
+        decode_as!(
+            data,
+            AgentReputationInitialized,
+            RatingSubmitted,
+            RatingReported,
+            RatingModerated,
+            ReputationAdminChanged,
+            AccountMigrated,
+        )
+    } else if *program_id == ROYALTY_PROGRAM_ID {
+        decode_as!(
+            data,
+            RoyaltyConfigInitialized,
+            RoyaltyConfigUpdated,
+            PaymentDistributed,
+            RoyaltyAdminChanged,
+            PlatformFeesWithdrawn,
+            PauseStateChanged,
+            LineageRoyaltyPaid,
+        )
+    } else {
+        None
+    }
+}
+
+/// Decode every known event in a transaction's log messages, in emission order
+///
+/// Tracks the invoke stack so each `Program data:` line is attributed to the
+/// program that emitted it, including events emitted from CPIs.
+pub fn parse_logs<S: AsRef<str>>(logs: &[S]) -> Vec<(Pubkey, ProgramEvent)> {
+    parse_logs_with(logs, decode_event)
+}
+
+/// [`parse_logs`] with a caller-supplied decoder for each `Program data:` payload
+pub fn parse_logs_with<S, T>(
+    logs: &[S],
+    decode: impl Fn(&Pubkey, &[u8]) -> Option<T>,
+) -> Vec<(Pubkey, T)>
+where
+    S: AsRef<str>,
+{
+    let mut stack: Vec<Pubkey> = Vec::new();
+    let mut events = Vec::new();
+
+    for line in logs {
+        let line = line.as_ref();
+        if let Some(rest) = line.strip_prefix("Program data: ") {
+            let Some(program_id) = stack.last() else {
+                continue;
+            };
+            if let Ok(data) = BASE64.decode(rest.trim()) {
+                if let Some(event) = decode(program_id, &data) {
+                    events.push((*program_id, event));
+                }
+            }
+        } else if let Some(rest) = line.strip_prefix("Program ") {
+            let mut parts = rest.split_whitespace();
+            let (Some(id), Some(action)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            match action {
+                "invoke" => {
+                    if let Ok(program_id) = id.parse() {
+                        stack.push(program_id);
+                    }
+                }
+                "success" | "failed:" => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    events
+}
@@ -0,0 +1,858 @@
+//! Builders for the marketplace escrow program.
+//!
+//! The optional raffle and analytics hook accounts are left empty; programs
+//! that need them can push the extra metas onto the returned instruction.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_spl::associated_token::get_associated_token_address;
+use marketplace_escrow::{accounts, instruction};
+pub use marketplace_escrow::{DisputeOutcome, Verdict};
+
+use super::build;
+use crate::accounts::Bundle;
+use crate::{pda, ESCROW_PROGRAM_ID, REGISTRY_PROGRAM_ID};
+
+// Helper function to build an analytics hook with every account omitted
+fn no_analytics() -> accounts::AnalyticsHook {
+    accounts::AnalyticsHook {
+        analytics_config: None,
+        epoch_metrics: None,
+        agent_activity: None,
+        agent_analytics: None,
+        analytics_hook: None,
+        analytics_program: None,
+        system_program: None,
+    }
+}
+
+/// Open a service request and escrow `amount` lamports from the user, or
+/// nothing while the user has free trial requests of the agent left. The
+/// request is due within the agent's SLA oracle timeout, if it has one
+pub fn create_service_request(
+    user: Pubkey,
+    agent_id: Pubkey,
+    amount: u64,
+    request_data: String,
+) -> Instruction {
+    open_request(user, agent_id, None, amount, request_data, None)
+}
+
+/// Open a service request like [`create_service_request`] that anyone can
+/// expire for a refund once `deadline` passes without a result
+pub fn create_service_request_with_deadline(
+    user: Pubkey,
+    agent_id: Pubkey,
+    amount: u64,
+    request_data: String,
+    deadline: i64,
+) -> Instruction {
+    open_request(user, agent_id, None, amount, request_data, Some(deadline))
+}
+
+/// Open a service request paid in `mint`, escrowing `amount` base units
+/// from the user's associated token account in one the escrow PDA owns.
+/// The registry must accept `mint`
+pub fn create_token_service_request(
+    user: Pubkey,
+    agent_id: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+    request_data: String,
+) -> Instruction {
+    open_request(user, agent_id, Some(mint), amount, request_data, None)
+}
+
+// Helper function to build a request paid in lamports, or in `payment_mint` when given
+fn open_request(
+    user: Pubkey,
+    agent_id: Pubkey,
+    payment_mint: Option<Pubkey>,
+    amount: u64,
+    request_data: String,
+    deadline: Option<i64>,
+) -> Instruction {
+    let service_request = pda::service_request(&user, &agent_id).0;
+    let escrow_account = pda::escrow_account(&service_request).0;
+
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::CreateServiceRequest {
+            service_request,
+            escrow_account,
+            agent_requests: pda::agent_requests(&agent_id).0,
+            user,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+            analytics: no_analytics(),
+            block_entry: pda::block_entry(&agent_id, &user).0,
+            allowlist_entry: Some(pda::allowlist_entry(&agent_id, &user).0),
+            trial_counter: Some(pda::trial_counter(&agent_id, &user).0),
+            payment_mint,
+            user_token_account: token_account(&user, payment_mint),
+            escrow_token_account: token_account(&escrow_account, payment_mint),
+            token_program: payment_mint.map(|_| anchor_spl::token::ID),
+            associated_token_program: payment_mint.map(|_| anchor_spl::associated_token::ID),
+            accepted_mint: payment_mint.map(|mint| pda::accepted_mint(&mint).0),
+            agent_sla: Some(pda::agent_sla(&agent_id).0),
+        },
+        instruction::CreateServiceRequest {
+            agent_id,
+            amount,
+            request_data,
+            deadline,
+        },
+    )
+}
+
+// Helper function to find `owner`'s associated token account of the payment mint, if the request has one
+fn token_account(owner: &Pubkey, payment_mint: Option<Pubkey>) -> Option<Pubkey> {
+    payment_mint.map(|mint| get_associated_token_address(owner, &mint))
+}
+
+/// Submit the result for a request against `agent_id`, signed by the agent's
+/// manager: the authority on its profile, or the holder of `nft_mint`
+pub fn submit_result(
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    agent_authority: Pubkey,
+    result_data: String,
+) -> Instruction {
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::SubmitResult {
+            service_request,
+            agent_authority,
+            agent_profile: agent_id,
+            holder_token_account: Some(get_associated_token_address(&agent_authority, &nft_mint)),
+            operator_key: None,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SubmitResult { result_data },
+    )
+}
+
+/// Submit the result for a request against `agent_id` as `operator`, a key
+/// `manager` registered with `register_operator`
+pub fn submit_result_as_operator(
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    manager: Pubkey,
+    operator: Pubkey,
+    result_data: String,
+) -> Instruction {
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::SubmitResult {
+            service_request,
+            agent_authority: operator,
+            agent_profile: agent_id,
+            holder_token_account: Some(get_associated_token_address(&manager, &nft_mint)),
+            operator_key: Some(pda::operator_key(&manager, &operator).0),
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SubmitResult { result_data },
+    )
+}
+
+/// Approve a completed request against `agent_id` and release the payment
+/// splits; `payout_wallet` must be the one on the agent's profile and the fee
+/// wallets the royalty config's. Agents with co-creators also need
+/// [`with_co_creators`]
+pub fn approve_result(
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    user: Pubkey,
+    payout_wallet: Pubkey,
+    platform_wallet: Pubkey,
+    treasury_wallet: Pubkey,
+) -> Instruction {
+    release_splits(
+        service_request,
+        agent_id,
+        user,
+        None,
+        [payout_wallet, platform_wallet, treasury_wallet],
+    )
+}
+
+/// Approve a completed request paid in `mint`, releasing the splits to each
+/// wallet's associated token account of it. Co-creators go through
+/// [`with_co_creators`] as their associated token accounts of `mint`
+pub fn approve_token_result(
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    user: Pubkey,
+    mint: Pubkey,
+    payout_wallet: Pubkey,
+    platform_wallet: Pubkey,
+    treasury_wallet: Pubkey,
+) -> Instruction {
+    release_splits(
+        service_request,
+        agent_id,
+        user,
+        Some(mint),
+        [payout_wallet, platform_wallet, treasury_wallet],
+    )
+}
+
+// Helper function to build an approval paying the payout, platform, and treasury wallets, in `payment_mint` when given
+fn release_splits(
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    user: Pubkey,
+    payment_mint: Option<Pubkey>,
+    [payout_wallet, platform_wallet, treasury_wallet]: [Pubkey; 3],
+) -> Instruction {
+    let escrow_account = pda::escrow_account(&service_request).0;
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::ApproveResult {
+            service_request,
+            escrow_account,
+            agent_requests: pda::agent_requests(&agent_id).0,
+            user,
+            agent_profile: agent_id,
+            payout_hook: pda::payout_hook().0,
+            registry_program: REGISTRY_PROGRAM_ID,
+            payout_wallet,
+            co_creator_split: pda::co_creator_split(&agent_id).0,
+            royalty_config: pda::royalty_config().0,
+            platform_wallet,
+            treasury_wallet,
+            guardian_config: pda::guardian_config().0,
+            raffle_config: None,
+            raffle_round: None,
+            ticket_batch: None,
+            raffle_hook: None,
+            raffle_program: None,
+            system_program: system_program::ID,
+            analytics: no_analytics(),
+            escrow_token_account: token_account(&escrow_account, payment_mint),
+            payout_token_account: token_account(&payout_wallet, payment_mint),
+            platform_token_account: token_account(&platform_wallet, payment_mint),
+            treasury_token_account: token_account(&treasury_wallet, payment_mint),
+            token_program: payment_mint.map(|_| anchor_spl::token::ID),
+        },
+        instruction::ApproveResult {},
+    )
+}
+
+/// Release a completed request's payment once the user has let
+/// `REVIEW_WINDOW` pass without approving or disputing it; anyone can send
+/// this. The fee wallets must be the royalty config's. Agents with
+/// co-creators also need [`with_co_creators`]
+pub fn auto_release(
+    caller: Pubkey,
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    user: Pubkey,
+    payout_wallet: Pubkey,
+    platform_wallet: Pubkey,
+    treasury_wallet: Pubkey,
+) -> Instruction {
+    release_after_review(
+        caller,
+        service_request,
+        agent_id,
+        user,
+        None,
+        [payout_wallet, platform_wallet, treasury_wallet],
+    )
+}
+
+/// [`auto_release`] for a request paid in `mint`, releasing the splits to
+/// each wallet's associated token account of it
+#[allow(clippy::too_many_arguments)]
+pub fn auto_release_token(
+    caller: Pubkey,
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    user: Pubkey,
+    mint: Pubkey,
+    payout_wallet: Pubkey,
+    platform_wallet: Pubkey,
+    treasury_wallet: Pubkey,
+) -> Instruction {
+    release_after_review(
+        caller,
+        service_request,
+        agent_id,
+        user,
+        Some(mint),
+        [payout_wallet, platform_wallet, treasury_wallet],
+    )
+}
+
+// Helper function to build an auto-release paying out in `payment_mint` when given
+fn release_after_review(
+    caller: Pubkey,
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    user: Pubkey,
+    payment_mint: Option<Pubkey>,
+    [payout_wallet, platform_wallet, treasury_wallet]: [Pubkey; 3],
+) -> Instruction {
+    let escrow_account = pda::escrow_account(&service_request).0;
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::AutoRelease {
+            service_request,
+            escrow_account,
+            agent_requests: pda::agent_requests(&agent_id).0,
+            user,
+            caller,
+            agent_profile: agent_id,
+            payout_hook: pda::payout_hook().0,
+            registry_program: REGISTRY_PROGRAM_ID,
+            payout_wallet,
+            co_creator_split: pda::co_creator_split(&agent_id).0,
+            royalty_config: pda::royalty_config().0,
+            platform_wallet,
+            treasury_wallet,
+            guardian_config: pda::guardian_config().0,
+            system_program: system_program::ID,
+            analytics: no_analytics(),
+            escrow_token_account: token_account(&escrow_account, payment_mint),
+            payout_token_account: token_account(&payout_wallet, payment_mint),
+            platform_token_account: token_account(&platform_wallet, payment_mint),
+            treasury_token_account: token_account(&treasury_wallet, payment_mint),
+            token_program: payment_mint.map(|_| anchor_spl::token::ID),
+        },
+        instruction::AutoRelease {},
+    )
+}
+
+/// Append the agent's co-creator wallets to an [`approve_result`], in the
+/// order its `CoCreatorSplit` lists them
+pub fn with_co_creators(mut approve_result: Instruction, co_creators: &[Pubkey]) -> Instruction {
+    approve_result.accounts.extend(
+        co_creators
+            .iter()
+            .map(|co_creator| AccountMeta::new(*co_creator, false)),
+    );
+    approve_result
+}
+
+/// Pin the agent release that serves a pending request
+pub fn pin_agent_version(
+    service_request: Pubkey,
+    agent_version: Pubkey,
+    user: Pubkey,
+) -> Instruction {
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::PinAgentVersion {
+            service_request,
+            agent_version,
+            user,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::PinAgentVersion {},
+    )
+}
+
+/// Order a specific offering from the agent's catalog for a pending request
+pub fn select_service_offering(
+    service_request: Pubkey,
+    service_offering: Pubkey,
+    user: Pubkey,
+) -> Instruction {
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::SelectServiceOffering {
+            service_request,
+            service_offering,
+            user,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SelectServiceOffering {},
+    )
+}
+
+/// Order one of the agent's capabilities at its price override for a pending
+/// request
+pub fn select_capability(
+    service_request: Pubkey,
+    capability_price: Pubkey,
+    user: Pubkey,
+) -> Instruction {
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::SelectCapability {
+            service_request,
+            capability_price,
+            user,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SelectCapability {},
+    )
+}
+
+/// Place a pending request as one agent's part of a bundle purchase
+pub fn select_bundle(service_request: Pubkey, bundle: Pubkey, user: Pubkey) -> Instruction {
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::SelectBundle {
+            service_request,
+            bundle,
+            user,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SelectBundle {},
+    )
+}
+
+/// Buy a bundle: open a request per agent, each escrowing its share of the
+/// discounted price, and place it in the bundle. Send the instructions in
+/// one transaction so the purchase lands whole; larger bundles may need an
+/// address lookup table to fit
+pub fn purchase_bundle(
+    user: Pubkey,
+    bundle: Pubkey,
+    terms: &Bundle,
+    request_data: String,
+) -> Vec<Instruction> {
+    terms
+        .agents
+        .iter()
+        .flat_map(|agent_id| {
+            let service_request = pda::service_request(&user, agent_id).0;
+            [
+                create_service_request(user, *agent_id, terms.share_price(), request_data.clone()),
+                select_bundle(service_request, bundle, user),
+            ]
+        })
+        .collect()
+}
+
+/// Dispute a completed request
+pub fn dispute_result(service_request: Pubkey, user: Pubkey, reason: String) -> Instruction {
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::DisputeResult {
+            service_request,
+            user,
+            guardian_config: pda::guardian_config().0,
+            analytics: no_analytics(),
+        },
+        instruction::DisputeResult { reason },
+    )
+}
+
+/// Name the arbiter who rules on disputed requests (guardian authority)
+pub fn set_arbiter(authority: Pubkey, arbiter: Pubkey) -> Instruction {
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::SetArbiter {
+            arbitration_config: pda::arbitration_config().0,
+            authority,
+            guardian_config: pda::guardian_config().0,
+            system_program: system_program::ID,
+        },
+        instruction::SetArbiter { arbiter },
+    )
+}
+
+/// Rule on a disputed request as the arbiter, paying the agent's share as
+/// [`approve_result`] would and refunding the user the rest. The fee wallets
+/// must be the royalty config's. Agents with co-creators also need
+/// [`with_co_creators`]
+#[allow(clippy::too_many_arguments)]
+pub fn resolve_dispute(
+    arbiter: Pubkey,
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    user: Pubkey,
+    payout_wallet: Pubkey,
+    platform_wallet: Pubkey,
+    treasury_wallet: Pubkey,
+    outcome: DisputeOutcome,
+) -> Instruction {
+    rule_on_dispute(
+        arbiter,
+        service_request,
+        agent_id,
+        user,
+        None,
+        [payout_wallet, platform_wallet, treasury_wallet],
+        outcome,
+    )
+}
+
+/// [`resolve_dispute`] for a request paid in `mint`, paying each wallet's
+/// associated token account of it
+#[allow(clippy::too_many_arguments)]
+pub fn resolve_token_dispute(
+    arbiter: Pubkey,
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    user: Pubkey,
+    mint: Pubkey,
+    payout_wallet: Pubkey,
+    platform_wallet: Pubkey,
+    treasury_wallet: Pubkey,
+    outcome: DisputeOutcome,
+) -> Instruction {
+    rule_on_dispute(
+        arbiter,
+        service_request,
+        agent_id,
+        user,
+        Some(mint),
+        [payout_wallet, platform_wallet, treasury_wallet],
+        outcome,
+    )
+}
+
+// Helper function to build a dispute ruling paying out in `payment_mint` when given
+fn rule_on_dispute(
+    arbiter: Pubkey,
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    user: Pubkey,
+    payment_mint: Option<Pubkey>,
+    [payout_wallet, platform_wallet, treasury_wallet]: [Pubkey; 3],
+    outcome: DisputeOutcome,
+) -> Instruction {
+    let escrow_account = pda::escrow_account(&service_request).0;
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::ResolveDispute {
+            service_request,
+            escrow_account,
+            agent_requests: pda::agent_requests(&agent_id).0,
+            arbitration_config: pda::arbitration_config().0,
+            dispute_panel: pda::dispute_panel(&service_request).0,
+            arbiter,
+            user,
+            agent_profile: agent_id,
+            payout_hook: pda::payout_hook().0,
+            registry_program: REGISTRY_PROGRAM_ID,
+            payout_wallet,
+            co_creator_split: pda::co_creator_split(&agent_id).0,
+            royalty_config: pda::royalty_config().0,
+            platform_wallet,
+            treasury_wallet,
+            guardian_config: pda::guardian_config().0,
+            system_program: system_program::ID,
+            analytics: no_analytics(),
+            escrow_token_account: token_account(&escrow_account, payment_mint),
+            user_token_account: token_account(&user, payment_mint),
+            payout_token_account: token_account(&payout_wallet, payment_mint),
+            platform_token_account: token_account(&platform_wallet, payment_mint),
+            treasury_token_account: token_account(&treasury_wallet, payment_mint),
+            token_program: payment_mint.map(|_| anchor_spl::token::ID),
+        },
+        instruction::ResolveDispute { outcome },
+    )
+}
+
+/// Join the jury pool, staking `JUROR_STAKE` lamports
+pub fn register_juror(juror: Pubkey) -> Instruction {
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::RegisterJuror {
+            juror_pool: pda::juror_pool().0,
+            juror,
+            system_program: system_program::ID,
+        },
+        instruction::RegisterJuror {},
+    )
+}
+
+/// Leave the jury pool and withdraw the stake
+pub fn leave_jury(juror: Pubkey) -> Instruction {
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::LeaveJury {
+            juror_pool: pda::juror_pool().0,
+            juror,
+        },
+        instruction::LeaveJury {},
+    )
+}
+
+/// Draw a juror panel for a disputed request once the request's
+/// `panel_slot` has passed; anyone can send this and gets the panel's rent
+/// back when it settles
+pub fn draw_panel(caller: Pubkey, service_request: Pubkey, agent_id: Pubkey) -> Instruction {
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::DrawPanel {
+            service_request,
+            dispute_panel: pda::dispute_panel(&service_request).0,
+            juror_pool: pda::juror_pool().0,
+            agent_profile: agent_id,
+            slot_hashes: sysvar::slot_hashes::ID,
+            caller,
+            system_program: system_program::ID,
+        },
+        instruction::DrawPanel {},
+    )
+}
+
+/// Commit a disputed request's panel draw to a new slot after its
+/// `panel_slot` aged out of the `SlotHashes` sysvar undrawn; anyone can send this
+pub fn renew_panel_slot(service_request: Pubkey) -> Instruction {
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::RenewPanelSlot {
+            service_request,
+            slot_hashes: sysvar::slot_hashes::ID,
+        },
+        instruction::RenewPanelSlot {},
+    )
+}
+
+/// Vote on a disputed request as one of its panel's jurors
+pub fn cast_vote(juror: Pubkey, service_request: Pubkey, verdict: Verdict) -> Instruction {
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::CastVote {
+            dispute_panel: pda::dispute_panel(&service_request).0,
+            juror,
+        },
+        instruction::CastVote { verdict },
+    )
+}
+
+/// Settle a disputed request by its panel's vote once everyone has voted or
+/// the voting window has ended; anyone can send this. `panel_payer` is
+/// whoever drew the panel. The fee wallets must be the royalty config's.
+/// Agents with co-creators also need [`with_co_creators`]
+#[allow(clippy::too_many_arguments)]
+pub fn settle_panel(
+    caller: Pubkey,
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    user: Pubkey,
+    panel_payer: Pubkey,
+    payout_wallet: Pubkey,
+    platform_wallet: Pubkey,
+    treasury_wallet: Pubkey,
+) -> Instruction {
+    settle_by_vote(
+        caller,
+        service_request,
+        agent_id,
+        user,
+        panel_payer,
+        None,
+        [payout_wallet, platform_wallet, treasury_wallet],
+    )
+}
+
+/// [`settle_panel`] for a request paid in `mint`, paying each wallet's
+/// associated token account of it
+#[allow(clippy::too_many_arguments)]
+pub fn settle_token_panel(
+    caller: Pubkey,
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    user: Pubkey,
+    panel_payer: Pubkey,
+    mint: Pubkey,
+    payout_wallet: Pubkey,
+    platform_wallet: Pubkey,
+    treasury_wallet: Pubkey,
+) -> Instruction {
+    settle_by_vote(
+        caller,
+        service_request,
+        agent_id,
+        user,
+        panel_payer,
+        Some(mint),
+        [payout_wallet, platform_wallet, treasury_wallet],
+    )
+}
+
+// Helper function to build a panel settlement paying out in `payment_mint` when given
+fn settle_by_vote(
+    caller: Pubkey,
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    user: Pubkey,
+    panel_payer: Pubkey,
+    payment_mint: Option<Pubkey>,
+    [payout_wallet, platform_wallet, treasury_wallet]: [Pubkey; 3],
+) -> Instruction {
+    let escrow_account = pda::escrow_account(&service_request).0;
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::SettlePanel {
+            service_request,
+            escrow_account,
+            agent_requests: pda::agent_requests(&agent_id).0,
+            dispute_panel: pda::dispute_panel(&service_request).0,
+            payer: panel_payer,
+            juror_pool: pda::juror_pool().0,
+            caller,
+            user,
+            agent_profile: agent_id,
+            payout_hook: pda::payout_hook().0,
+            registry_program: REGISTRY_PROGRAM_ID,
+            payout_wallet,
+            co_creator_split: pda::co_creator_split(&agent_id).0,
+            royalty_config: pda::royalty_config().0,
+            platform_wallet,
+            treasury_wallet,
+            guardian_config: pda::guardian_config().0,
+            system_program: system_program::ID,
+            analytics: no_analytics(),
+            escrow_token_account: token_account(&escrow_account, payment_mint),
+            user_token_account: token_account(&user, payment_mint),
+            payout_token_account: token_account(&payout_wallet, payment_mint),
+            platform_token_account: token_account(&platform_wallet, payment_mint),
+            treasury_token_account: token_account(&treasury_wallet, payment_mint),
+            token_program: payment_mint.map(|_| anchor_spl::token::ID),
+        },
+        instruction::SettlePanel {},
+    )
+}
+
+/// Cancel a pending request against `agent_id` and refund the user
+pub fn cancel_request(service_request: Pubkey, agent_id: Pubkey, user: Pubkey) -> Instruction {
+    refund_request(service_request, agent_id, user, None)
+}
+
+/// Cancel a pending request paid in `mint`, refunding the user's associated
+/// token account of it
+pub fn cancel_token_request(
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    user: Pubkey,
+    mint: Pubkey,
+) -> Instruction {
+    refund_request(service_request, agent_id, user, Some(mint))
+}
+
+// Helper function to build a cancellation refunding the user, in `payment_mint` when given
+fn refund_request(
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    user: Pubkey,
+    payment_mint: Option<Pubkey>,
+) -> Instruction {
+    let escrow_account = pda::escrow_account(&service_request).0;
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::CancelRequest {
+            service_request,
+            escrow_account,
+            agent_requests: pda::agent_requests(&agent_id).0,
+            user,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+            escrow_token_account: token_account(&escrow_account, payment_mint),
+            user_token_account: token_account(&user, payment_mint),
+            token_program: payment_mint.map(|_| anchor_spl::token::ID),
+        },
+        instruction::CancelRequest {},
+    )
+}
+
+/// Refund the user of a request paid in lamports whose deadline passed
+/// without a result; anyone can send this
+pub fn expire_request(
+    caller: Pubkey,
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    user: Pubkey,
+) -> Instruction {
+    refund_expired(caller, service_request, agent_id, user, None)
+}
+
+/// [`expire_request`] for a request paid in `mint`, refunding the user's
+/// associated token account
+pub fn expire_token_request(
+    caller: Pubkey,
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    user: Pubkey,
+    mint: Pubkey,
+) -> Instruction {
+    refund_expired(caller, service_request, agent_id, user, Some(mint))
+}
+
+// Helper function to build an expiry refunding the user, in `payment_mint` when given
+fn refund_expired(
+    caller: Pubkey,
+    service_request: Pubkey,
+    agent_id: Pubkey,
+    user: Pubkey,
+    payment_mint: Option<Pubkey>,
+) -> Instruction {
+    let escrow_account = pda::escrow_account(&service_request).0;
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::ExpireRequest {
+            service_request,
+            escrow_account,
+            agent_requests: pda::agent_requests(&agent_id).0,
+            user,
+            caller,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+            escrow_token_account: token_account(&escrow_account, payment_mint),
+            user_token_account: token_account(&user, payment_mint),
+            token_program: payment_mint.map(|_| anchor_spl::token::ID),
+        },
+        instruction::ExpireRequest {},
+    )
+}
+
+/// Close an approved, cancelled, or resolved request, returning its rent to
+/// the user
+pub fn close_request(service_request: Pubkey, user: Pubkey) -> Instruction {
+    close_settled_request(service_request, user, None)
+}
+
+/// Close a settled request paid in `mint`, sweeping any tokens left in its
+/// escrow to the user's associated token account of it
+pub fn close_token_request(service_request: Pubkey, user: Pubkey, mint: Pubkey) -> Instruction {
+    close_settled_request(service_request, user, Some(mint))
+}
+
+// Helper function to build a close of a settled request, with its escrow token account when paid in `payment_mint`
+fn close_settled_request(
+    service_request: Pubkey,
+    user: Pubkey,
+    payment_mint: Option<Pubkey>,
+) -> Instruction {
+    let escrow_account = pda::escrow_account(&service_request).0;
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::CloseRequest {
+            service_request,
+            escrow_account,
+            user,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+            escrow_token_account: token_account(&escrow_account, payment_mint),
+            user_token_account: token_account(&user, payment_mint),
+            token_program: payment_mint.map(|_| anchor_spl::token::ID),
+        },
+        instruction::CloseRequest {},
+    )
+}
+
+/// Upgrade a service request to the current layout during a schema migration
+/// (guardian authority, protocol paused); `authority` covers the extra rent
+pub fn migrate_service_request(authority: Pubkey, service_request: Pubkey) -> Instruction {
+    build(
+        ESCROW_PROGRAM_ID,
+        accounts::MigrateServiceRequest {
+            service_request,
+            authority,
+            guardian_config: pda::guardian_config().0,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateServiceRequest {},
+    )
+}
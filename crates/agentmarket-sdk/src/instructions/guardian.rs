@@ -0,0 +1,70 @@
+//! Builders for the protocol guardian (global pause) program.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_program;
+use protocol_guardian::{accounts, instruction};
+
+use super::build;
+use crate::{pda, GUARDIAN_PROGRAM_ID};
+
+/// Create the guardian config with the signer as authority
+pub fn initialize_guardian(authority: Pubkey, guardians: Vec<Pubkey>) -> Instruction {
+    build(
+        GUARDIAN_PROGRAM_ID,
+        accounts::InitializeGuardian {
+            guardian_config: pda::guardian_config().0,
+            authority,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeGuardian { guardians },
+    )
+}
+
+/// Halt every guarded instruction (any guardian or the authority)
+pub fn pause_protocol(caller: Pubkey) -> Instruction {
+    build(
+        GUARDIAN_PROGRAM_ID,
+        accounts::PauseProtocol {
+            guardian_config: pda::guardian_config().0,
+            caller,
+        },
+        instruction::PauseProtocol {},
+    )
+}
+
+/// Lift the pause (authority only)
+pub fn unpause_protocol(authority: Pubkey) -> Instruction {
+    build(
+        GUARDIAN_PROGRAM_ID,
+        accounts::UpdateGuardian {
+            guardian_config: pda::guardian_config().0,
+            authority,
+        },
+        instruction::UnpauseProtocol {},
+    )
+}
+
+/// Replace the guardian set (authority only)
+pub fn set_guardians(authority: Pubkey, guardians: Vec<Pubkey>) -> Instruction {
+    build(
+        GUARDIAN_PROGRAM_ID,
+        accounts::UpdateGuardian {
+            guardian_config: pda::guardian_config().0,
+            authority,
+        },
+        instruction::SetGuardians { guardians },
+    )
+}
+
+/// Hand guardian authority to a new key
+pub fn transfer_authority(authority: Pubkey, new_authority: Pubkey) -> Instruction {
+    build(
+        GUARDIAN_PROGRAM_ID,
+        accounts::UpdateGuardian {
+            guardian_config: pda::guardian_config().0,
+            authority,
+        },
+        instruction::TransferAuthority { new_authority },
+    )
+}
@@ -0,0 +1,27 @@
+//! Typed instruction builders, one module per program.
+//!
+//! Each builder derives the PDAs it needs and returns a ready-to-sign
+//! [`Instruction`](anchor_lang::solana_program::instruction::Instruction).
+
+pub mod escrow;
+pub mod guardian;
+pub mod registry;
+pub mod reputation;
+pub mod royalty;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+
+// Helper function to assemble an Anchor instruction from its accounts and args
+pub(crate) fn build(
+    program_id: Pubkey,
+    accounts: impl ToAccountMetas,
+    data: impl InstructionData,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
@@ -0,0 +1,2274 @@
+//! Builders for the agent registry program.
+
+use agent_registry::{
+    accounts, instruction, AgentEndpoint, AgentProfile, CoCreator, PricingModel, RegistryConfig,
+    Visibility, MAX_CAPABILITY_ID_LEN,
+};
+pub use agent_registry::{
+    endpoint_challenge_message, manifest_message, normalize_agent_name, AgentManifest,
+    AgentProfileSummary, AgentProfileView, RentTarget, ED25519_PROGRAM_ID,
+};
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_spl::associated_token::get_associated_token_address;
+use marketplace_escrow::ServiceRequest;
+use royalty_splitter::RoyaltyConfig;
+
+use super::build;
+use crate::{
+    pda, ACCOUNT_COMPRESSION_PROGRAM_ID, BUBBLEGUM_PROGRAM_ID, ESCROW_PROGRAM_ID, NOOP_PROGRAM_ID,
+    REGISTRY_PROGRAM_ID, REPUTATION_PROGRAM_ID, ROYALTY_PROGRAM_ID, ROYALTY_RULE_SET,
+    TOKEN_AUTH_RULES_PROGRAM_ID, TOKEN_METADATA_PROGRAM_ID,
+};
+
+/// Arguments for registering an agent and minting its NFT
+#[derive(Clone)]
+pub struct RegisterAgentArgs {
+    pub name: String,
+    pub description: String,
+    pub capabilities: Vec<String>,
+    pub pricing: PricingModel,
+    pub endpoints: Vec<AgentEndpoint>,
+    pub ipfs_hash: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+/// Register an agent; `mint` is a fresh keypair that must also sign. The NFT
+/// is verified into the platform collection, which must already exist, every
+/// capability must be in the taxonomy, and the creator locks the configured
+/// listing stake. Pricing in an SPL token needs the token to be accepted.
+/// The agent takes the next entry of the agent index, so `config` must be
+/// current
+pub fn register_agent(
+    creator: Pubkey,
+    mint: Pubkey,
+    config: &RegistryConfig,
+    args: RegisterAgentArgs,
+) -> Instruction {
+    let capabilities = capability_accounts(&pda::agent_profile(&creator).0, &args.capabilities);
+    let mut ix = build(
+        REGISTRY_PROGRAM_ID,
+        register_agent_accounts(creator, mint, config, &args.name, &args.pricing),
+        instruction::RegisterAgent {
+            name: args.name,
+            description: args.description,
+            capabilities: args.capabilities,
+            pricing: args.pricing,
+            endpoints: args.endpoints,
+            ipfs_hash: args.ipfs_hash,
+            symbol: args.symbol,
+            uri: args.uri,
+        },
+    );
+    ix.accounts.extend(capabilities);
+    ix
+}
+
+/// Register an agent as `register_agent` does, but leave its NFT frozen in
+/// the creator's token account so it can never be transferred or sold
+pub fn register_agent_soulbound(
+    creator: Pubkey,
+    mint: Pubkey,
+    config: &RegistryConfig,
+    args: RegisterAgentArgs,
+) -> Instruction {
+    let capabilities = capability_accounts(&pda::agent_profile(&creator).0, &args.capabilities);
+    let mut ix = build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RegisterAgentSoulbound {
+            register: register_agent_accounts(creator, mint, config, &args.name, &args.pricing),
+        },
+        instruction::RegisterAgentSoulbound {
+            name: args.name,
+            description: args.description,
+            capabilities: args.capabilities,
+            pricing: args.pricing,
+            endpoints: args.endpoints,
+            ipfs_hash: args.ipfs_hash,
+            symbol: args.symbol,
+            uri: args.uri,
+        },
+    );
+    ix.accounts.extend(capabilities);
+    ix
+}
+
+/// Register an agent forked from `parent_agent`, as `register_agent` does,
+/// routing `lineage_royalty_bps` of lineage payments to the parent's creator
+pub fn fork_agent(
+    creator: Pubkey,
+    mint: Pubkey,
+    parent_agent: Pubkey,
+    config: &RegistryConfig,
+    args: RegisterAgentArgs,
+    lineage_royalty_bps: u16,
+) -> Instruction {
+    let agent_id = pda::agent_profile(&creator).0;
+    let capabilities = capability_accounts(&agent_id, &args.capabilities);
+    let mut ix = build(
+        REGISTRY_PROGRAM_ID,
+        accounts::ForkAgent {
+            register: register_agent_accounts(creator, mint, config, &args.name, &args.pricing),
+            parent_profile: parent_agent,
+            agent_lineage: pda::agent_lineage(&agent_id).0,
+            system_program: system_program::ID,
+        },
+        instruction::ForkAgent {
+            name: args.name,
+            description: args.description,
+            capabilities: args.capabilities,
+            pricing: args.pricing,
+            endpoints: args.endpoints,
+            ipfs_hash: args.ipfs_hash,
+            symbol: args.symbol,
+            uri: args.uri,
+            lineage_royalty_bps,
+        },
+    );
+    ix.accounts.extend(capabilities);
+    ix
+}
+
+/// Let `operator` sign manifests registering agents for `creator`, and submit
+/// results for the agents `creator` manages (creator)
+pub fn register_operator(creator: Pubkey, operator: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RegisterOperator {
+            operator_key: pda::operator_key(&creator, &operator).0,
+            creator,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::RegisterOperator { operator },
+    )
+}
+
+/// Stop accepting manifests signed by `operator` (creator)
+pub fn revoke_operator(creator: Pubkey, operator: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RevokeOperator {
+            operator_key: pda::operator_key(&creator, &operator).0,
+            creator,
+        },
+        instruction::RevokeOperator {},
+    )
+}
+
+/// Manifest an operator signs, as [`manifest_message`], to register the agent
+/// `args` describes for `creator`. `nonce` must be the operator's
+/// `next_nonce`, and the manifest can't be submitted after `expires_at`
+pub fn agent_manifest(
+    creator: Pubkey,
+    nonce: u64,
+    expires_at: i64,
+    args: RegisterAgentArgs,
+) -> AgentManifest {
+    AgentManifest {
+        creator,
+        nonce,
+        expires_at,
+        name: args.name,
+        description: args.description,
+        capabilities: args.capabilities,
+        pricing: args.pricing,
+        endpoints: args.endpoints,
+        ipfs_hash: args.ipfs_hash,
+        symbol: args.symbol,
+        uri: args.uri,
+    }
+}
+
+/// Register the agent `manifest` describes for its creator, who does not
+/// sign; `payer` funds the registration and `mint` is a fresh keypair that
+/// must also sign. Must come right after [`ed25519_verify`] of the
+/// registered `operator`'s signature over [`manifest_message`]
+pub fn register_agent_with_manifest(
+    payer: Pubkey,
+    mint: Pubkey,
+    config: &RegistryConfig,
+    operator: Pubkey,
+    manifest: &AgentManifest,
+) -> Instruction {
+    let creator = manifest.creator;
+    let agent_id = pda::agent_profile(&creator).0;
+    let collection_mint = pda::collection_mint().0;
+    let capabilities = capability_accounts(&agent_id, &manifest.capabilities);
+    let mut ix = build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RegisterAgentWithManifest {
+            agent_profile: agent_id,
+            mint,
+            token_account: get_associated_token_address(&creator, &mint),
+            metadata: pda::token_metadata(&mint).0,
+            creator,
+            operator_key: pda::operator_key(&creator, &operator).0,
+            payer,
+            registry_config: pda::registry_config().0,
+            accepted_mint: accepted_mint(&manifest.pricing),
+            listing_stake: pda::listing_stake(&agent_id).0,
+            agent_index: pda::agent_index(config.agent_count).0,
+            name_claim: pda::name_claim(&manifest.name).0,
+            fee_vault: pda::fee_vault().0,
+            agent_reputation: pda::agent_reputation(&agent_id).0,
+            reputation_program: REPUTATION_PROGRAM_ID,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_metadata_program: TOKEN_METADATA_PROGRAM_ID,
+            rent: sysvar::rent::ID,
+            metadata_authority: pda::metadata_authority().0,
+            collection_authority: pda::collection_authority().0,
+            collection_mint,
+            collection_metadata: pda::token_metadata(&collection_mint).0,
+            collection_master_edition: pda::master_edition(&collection_mint).0,
+            instructions: sysvar::instructions::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::RegisterAgentWithManifest {
+            manifest: manifest_message(manifest),
+        },
+    );
+    ix.accounts.extend(capabilities);
+    ix
+}
+
+// Helper function to list the accounts a standard registration touches
+fn register_agent_accounts(
+    creator: Pubkey,
+    mint: Pubkey,
+    config: &RegistryConfig,
+    name: &str,
+    pricing: &PricingModel,
+) -> accounts::RegisterAgent {
+    let collection_mint = pda::collection_mint().0;
+    accounts::RegisterAgent {
+        agent_profile: pda::agent_profile(&creator).0,
+        mint,
+        token_account: get_associated_token_address(&creator, &mint),
+        metadata: pda::token_metadata(&mint).0,
+        creator,
+        registry_config: pda::registry_config().0,
+        accepted_mint: accepted_mint(pricing),
+        listing_stake: pda::listing_stake(&pda::agent_profile(&creator).0).0,
+        agent_index: pda::agent_index(config.agent_count).0,
+        name_claim: pda::name_claim(name).0,
+        fee_vault: pda::fee_vault().0,
+        agent_reputation: pda::agent_reputation(&pda::agent_profile(&creator).0).0,
+        reputation_program: REPUTATION_PROGRAM_ID,
+        system_program: system_program::ID,
+        token_program: anchor_spl::token::ID,
+        associated_token_program: anchor_spl::associated_token::ID,
+        token_metadata_program: TOKEN_METADATA_PROGRAM_ID,
+        rent: sysvar::rent::ID,
+        collection_authority: pda::collection_authority().0,
+        collection_mint,
+        collection_metadata: pda::token_metadata(&collection_mint).0,
+        collection_master_edition: pda::master_edition(&collection_mint).0,
+        guardian_config: pda::guardian_config().0,
+    }
+}
+
+/// Register an agent as `register_agent` does, but mint its NFT as a pNFT
+/// bound to the royalty rule set; `mint` is a fresh keypair that must sign
+pub fn register_agent_programmable(
+    creator: Pubkey,
+    mint: Pubkey,
+    config: &RegistryConfig,
+    args: RegisterAgentArgs,
+) -> Instruction {
+    let agent_id = pda::agent_profile(&creator).0;
+    let collection_mint = pda::collection_mint().0;
+    let token_account = get_associated_token_address(&creator, &mint);
+    let capabilities = capability_accounts(&agent_id, &args.capabilities);
+    let mut ix = build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RegisterAgentProgrammable {
+            agent_profile: agent_id,
+            mint,
+            token_account,
+            token_record: pda::token_record(&mint, &token_account).0,
+            metadata: pda::token_metadata(&mint).0,
+            master_edition: pda::master_edition(&mint).0,
+            creator,
+            registry_config: pda::registry_config().0,
+            accepted_mint: accepted_mint(&args.pricing),
+            listing_stake: pda::listing_stake(&agent_id).0,
+            agent_index: pda::agent_index(config.agent_count).0,
+            name_claim: pda::name_claim(&args.name).0,
+            fee_vault: pda::fee_vault().0,
+            agent_reputation: pda::agent_reputation(&agent_id).0,
+            reputation_program: REPUTATION_PROGRAM_ID,
+            collection_authority: pda::collection_authority().0,
+            collection_mint,
+            collection_metadata: pda::token_metadata(&collection_mint).0,
+            collection_master_edition: pda::master_edition(&collection_mint).0,
+            authorization_rules: ROYALTY_RULE_SET,
+            authorization_rules_program: TOKEN_AUTH_RULES_PROGRAM_ID,
+            sysvar_instructions: sysvar::instructions::ID,
+            token_metadata_program: TOKEN_METADATA_PROGRAM_ID,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::RegisterAgentProgrammable {
+            name: args.name,
+            description: args.description,
+            capabilities: args.capabilities,
+            pricing: args.pricing,
+            endpoints: args.endpoints,
+            ipfs_hash: args.ipfs_hash,
+            symbol: args.symbol,
+            uri: args.uri,
+        },
+    );
+    ix.accounts.extend(capabilities);
+    ix
+}
+
+/// Register an agent with a compressed NFT minted into the platform's
+/// Bubblegum tree at `merkle_tree`
+pub fn register_agent_compressed(
+    creator: Pubkey,
+    merkle_tree: Pubkey,
+    config: &RegistryConfig,
+    args: RegisterAgentArgs,
+) -> Instruction {
+    let collection_mint = pda::collection_mint().0;
+    let capabilities = capability_accounts(&pda::agent_profile(&creator).0, &args.capabilities);
+    let mut ix = build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RegisterAgentCompressed {
+            agent_profile: pda::agent_profile(&creator).0,
+            creator,
+            registry_config: pda::registry_config().0,
+            accepted_mint: accepted_mint(&args.pricing),
+            listing_stake: pda::listing_stake(&pda::agent_profile(&creator).0).0,
+            agent_index: pda::agent_index(config.agent_count).0,
+            name_claim: pda::name_claim(&args.name).0,
+            fee_vault: pda::fee_vault().0,
+            agent_reputation: pda::agent_reputation(&pda::agent_profile(&creator).0).0,
+            reputation_program: REPUTATION_PROGRAM_ID,
+            tree_config: pda::tree_config(&merkle_tree).0,
+            merkle_tree,
+            collection_authority: pda::collection_authority().0,
+            collection_mint,
+            collection_metadata: pda::token_metadata(&collection_mint).0,
+            collection_master_edition: pda::master_edition(&collection_mint).0,
+            bubblegum_signer: pda::bubblegum_signer().0,
+            log_wrapper: NOOP_PROGRAM_ID,
+            compression_program: ACCOUNT_COMPRESSION_PROGRAM_ID,
+            token_metadata_program: TOKEN_METADATA_PROGRAM_ID,
+            bubblegum_program: BUBBLEGUM_PROGRAM_ID,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::RegisterAgentCompressed {
+            name: args.name,
+            description: args.description,
+            capabilities: args.capabilities,
+            pricing: args.pricing,
+            endpoints: args.endpoints,
+            ipfs_hash: args.ipfs_hash,
+            symbol: args.symbol,
+            uri: args.uri,
+        },
+    );
+    ix.accounts.extend(capabilities);
+    ix
+}
+
+// Helper function to find the `AcceptedMint` account that SPL pricing must come with
+fn accepted_mint(pricing: &PricingModel) -> Option<Pubkey> {
+    pricing
+        .payment_mint()
+        .map(|mint| pda::accepted_mint(&mint).0)
+}
+
+// Helper function to list the taxonomy and index account of each capability an agent lists
+fn capability_accounts(agent_id: &Pubkey, capabilities: &[String]) -> Vec<AccountMeta> {
+    capabilities
+        .iter()
+        .flat_map(|id| {
+            [
+                AccountMeta::new_readonly(pda::capability(id).0, false),
+                AccountMeta::new(pda::capability_index(id, agent_id).0, false),
+            ]
+        })
+        .collect()
+}
+
+// Helper function to list the index accounts the registry closes when an agent stops listing `capabilities`
+fn dropped_index_accounts<'a>(
+    agent_id: &Pubkey,
+    capabilities: impl IntoIterator<Item = &'a String>,
+) -> Vec<AccountMeta> {
+    capabilities
+        .into_iter()
+        // Longer ids predate the taxonomy and never had an index
+        .filter(|id| id.len() <= MAX_CAPABILITY_ID_LEN)
+        .map(|id| AccountMeta::new(pda::capability_index(id, agent_id).0, false))
+        .collect()
+}
+
+// Helper function to list the index account of each language
+fn language_accounts<'a>(
+    agent_id: &Pubkey,
+    languages: impl IntoIterator<Item = &'a String>,
+) -> Vec<AccountMeta> {
+    languages
+        .into_iter()
+        .map(|language| AccountMeta::new(pda::language_index(language, agent_id).0, false))
+        .collect()
+}
+
+/// Bytes to allocate for a concurrent Merkle tree with no canopy
+pub fn merkle_tree_space(max_depth: u32, max_buffer_size: u32) -> u64 {
+    let (depth, buffer) = (max_depth as u64, max_buffer_size as u64);
+    // Account header, then sequence number, active index, and buffer size
+    let header = 56 + 24;
+    // Each change log holds a root, a path, and a padded index; so does the rightmost proof
+    let change_log = 32 + 32 * depth + 8;
+    header + buffer * change_log + 32 * depth + 40
+}
+
+/// Create the platform's compressed agent tree (guardian authority).
+/// `merkle_tree` must first be allocated with [`merkle_tree_space`] bytes,
+/// owned by the account compression program, in the same transaction
+pub fn initialize_agent_tree(
+    authority: Pubkey,
+    merkle_tree: Pubkey,
+    max_depth: u32,
+    max_buffer_size: u32,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::InitializeAgentTree {
+            tree_config: pda::tree_config(&merkle_tree).0,
+            merkle_tree,
+            collection_authority: pda::collection_authority().0,
+            authority,
+            guardian_config: pda::guardian_config().0,
+            log_wrapper: NOOP_PROGRAM_ID,
+            compression_program: ACCOUNT_COMPRESSION_PROGRAM_ID,
+            bubblegum_program: BUBBLEGUM_PROGRAM_ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeAgentTree {
+            max_depth,
+            max_buffer_size,
+        },
+    )
+}
+
+/// Create the platform agent collection NFT (guardian authority)
+pub fn initialize_collection(
+    authority: Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> Instruction {
+    let collection_authority = pda::collection_authority().0;
+    let collection_mint = pda::collection_mint().0;
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::InitializeCollection {
+            collection_authority,
+            collection_mint,
+            collection_token_account: get_associated_token_address(
+                &collection_authority,
+                &collection_mint,
+            ),
+            collection_metadata: pda::token_metadata(&collection_mint).0,
+            collection_master_edition: pda::master_edition(&collection_mint).0,
+            authority,
+            guardian_config: pda::guardian_config().0,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_metadata_program: TOKEN_METADATA_PROGRAM_ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::InitializeCollection { name, symbol, uri },
+    )
+}
+
+/// Create the registry config: the listing stake new agents lock, in
+/// lamports, the key that may slash it, the lamports a featured listing
+/// costs per day, the key that verifies agents, the seconds an agent may go
+/// without a heartbeat, zero for no limit, and the lamports each new agent
+/// pays to register (guardian authority)
+pub fn initialize_registry_config(
+    authority: Pubkey,
+    listing_stake: u64,
+    slash_authority: Pubkey,
+    featured_price_per_day: u64,
+    verifier: Pubkey,
+    heartbeat_window: i64,
+    registration_fee: u64,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::InitializeRegistryConfig {
+            registry_config: pda::registry_config().0,
+            authority,
+            guardian_config: pda::guardian_config().0,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeRegistryConfig {
+            listing_stake,
+            slash_authority,
+            featured_price_per_day,
+            verifier,
+            heartbeat_window,
+            registration_fee,
+        },
+    )
+}
+
+/// Change the listing stake, slash authority, featured listing price,
+/// verifier, heartbeat window, and/or registration fee (guardian authority)
+pub fn update_registry_config(
+    authority: Pubkey,
+    listing_stake: Option<u64>,
+    slash_authority: Option<Pubkey>,
+    featured_price_per_day: Option<u64>,
+    verifier: Option<Pubkey>,
+    heartbeat_window: Option<i64>,
+    registration_fee: Option<u64>,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::UpdateRegistryConfig {
+            registry_config: pda::registry_config().0,
+            authority,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::UpdateRegistryConfig {
+            listing_stake,
+            slash_authority,
+            featured_price_per_day,
+            verifier,
+            heartbeat_window,
+            registration_fee,
+        },
+    )
+}
+
+/// Route the collected registration fees through the royalty splitter under
+/// its current `config`; `payer` covers the distribution record's rent
+pub fn sweep_registration_fees(payer: Pubkey, config: &RoyaltyConfig) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SweepRegistrationFees {
+            fee_vault: pda::fee_vault().0,
+            royalty_config: pda::royalty_config().0,
+            distribution_record: pda::distribution_record(config.total_transactions).0,
+            platform_account: config.platform_wallet,
+            treasury_account: config.treasury_wallet,
+            payer,
+            royalty_program: ROYALTY_PROGRAM_ID,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SweepRegistrationFees {},
+    )
+}
+
+/// Create referral code `code` as `referrer`, the creator of a registered agent
+pub fn create_referral_code(referrer: Pubkey, code: &str) -> Instruction {
+    let referral_code = pda::referral_code(code).0;
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::CreateReferralCode {
+            referrer_profile: pda::agent_profile(&referrer).0,
+            referral_code,
+            referral_vault: pda::referral_vault(&referral_code).0,
+            referrer,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::CreateReferralCode { code: code.into() },
+    )
+}
+
+/// Register an agent as `register_agent` does, crediting referral code `code`
+/// with its share of the registration fee
+pub fn register_agent_with_referral(
+    creator: Pubkey,
+    mint: Pubkey,
+    config: &RegistryConfig,
+    code: &str,
+    args: RegisterAgentArgs,
+) -> Instruction {
+    let referral_code = pda::referral_code(code).0;
+    let capabilities = capability_accounts(&pda::agent_profile(&creator).0, &args.capabilities);
+    let mut ix = build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RegisterAgentWithReferral {
+            register: register_agent_accounts(creator, mint, config, &args.name, &args.pricing),
+            referral_code,
+            referral_vault: pda::referral_vault(&referral_code).0,
+        },
+        instruction::RegisterAgentWithReferral {
+            name: args.name,
+            description: args.description,
+            capabilities: args.capabilities,
+            pricing: args.pricing,
+            endpoints: args.endpoints,
+            ipfs_hash: args.ipfs_hash,
+            symbol: args.symbol,
+            uri: args.uri,
+        },
+    );
+    ix.accounts.extend(capabilities);
+    ix
+}
+
+/// Route referral code `code`'s accrued fees through the royalty splitter
+/// under its current `config`, paying the creator share to `referrer`
+pub fn sweep_referral_fees(
+    payer: Pubkey,
+    code: &str,
+    referrer: Pubkey,
+    config: &RoyaltyConfig,
+) -> Instruction {
+    let referral_code = pda::referral_code(code).0;
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SweepReferralFees {
+            referral_code,
+            referral_vault: pda::referral_vault(&referral_code).0,
+            referrer,
+            royalty_config: pda::royalty_config().0,
+            distribution_record: pda::distribution_record(config.total_transactions).0,
+            platform_account: config.platform_wallet,
+            treasury_account: config.treasury_wallet,
+            payer,
+            royalty_program: ROYALTY_PROGRAM_ID,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SweepReferralFees {},
+    )
+}
+
+/// Slash `amount` lamports of an agent's stake over the disputed request
+/// `request` at `service_request`, paying its requester (slash authority)
+pub fn slash_stake(
+    slash_authority: Pubkey,
+    service_request: Pubkey,
+    request: &ServiceRequest,
+    amount: u64,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SlashStake {
+            registry_config: pda::registry_config().0,
+            service_request,
+            listing_stake: pda::listing_stake(&request.agent_id).0,
+            stake_slash: pda::stake_slash(&service_request, request.created_at).0,
+            user: request.user,
+            slash_authority,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SlashStake { amount },
+    )
+}
+
+/// Grant an agent the verified badge (verifier)
+pub fn verify_agent(verifier: Pubkey, agent_id: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SetAgentVerification {
+            registry_config: pda::registry_config().0,
+            agent_profile: agent_id,
+            verifier,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::VerifyAgent {},
+    )
+}
+
+/// Take an agent's verified badge away (verifier)
+pub fn revoke_verification(verifier: Pubkey, agent_id: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SetAgentVerification {
+            registry_config: pda::registry_config().0,
+            agent_profile: agent_id,
+            verifier,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::RevokeVerification {},
+    )
+}
+
+/// Freeze a malicious agent, blocking profile updates and new requests
+/// (slash authority)
+pub fn freeze_agent(slash_authority: Pubkey, agent_id: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SetAgentFreeze {
+            registry_config: pda::registry_config().0,
+            agent_profile: agent_id,
+            agent_requests: pda::agent_requests(&agent_id).0,
+            capacity_hook: pda::capacity_hook().0,
+            slash_authority,
+            escrow_program: ESCROW_PROGRAM_ID,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::FreezeAgent {},
+    )
+}
+
+/// Lift an agent's freeze (slash authority)
+pub fn unfreeze_agent(slash_authority: Pubkey, agent_id: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SetAgentFreeze {
+            registry_config: pda::registry_config().0,
+            agent_profile: agent_id,
+            agent_requests: pda::agent_requests(&agent_id).0,
+            capacity_hook: pda::capacity_hook().0,
+            slash_authority,
+            escrow_program: ESCROW_PROGRAM_ID,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::UnfreezeAgent {},
+    )
+}
+
+/// An attestation to publish with [`issue_agent_attestation`]
+#[derive(Clone)]
+pub struct AgentAttestationArgs {
+    /// Identifies what is attested, e.g. the hash of a schema URI
+    pub schema: [u8; 32],
+    /// Hash of the off-chain attestation data
+    pub data_hash: [u8; 32],
+    pub expires_at: Option<i64>,
+}
+
+/// Attest to an agent as `issuer`, or renew an earlier attestation under the same schema
+pub fn issue_agent_attestation(
+    issuer: Pubkey,
+    agent_id: Pubkey,
+    args: AgentAttestationArgs,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::IssueAgentAttestation {
+            agent_profile: agent_id,
+            attestation: pda::agent_attestation(&agent_id, &issuer, &args.schema).0,
+            issuer,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::IssueAgentAttestation {
+            schema: args.schema,
+            data_hash: args.data_hash,
+            expires_at: args.expires_at,
+        },
+    )
+}
+
+/// Revoke one of `issuer`'s attestations about an agent
+pub fn revoke_agent_attestation(issuer: Pubkey, agent_id: Pubkey, schema: [u8; 32]) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RevokeAgentAttestation {
+            attestation: pda::agent_attestation(&agent_id, &issuer, &schema).0,
+            issuer,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::RevokeAgentAttestation {},
+    )
+}
+
+/// Add a capability to the taxonomy (guardian authority)
+pub fn add_capability(
+    authority: Pubkey,
+    id: String,
+    name: String,
+    description: String,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::AddCapability {
+            capability: pda::capability(&id).0,
+            authority,
+            guardian_config: pda::guardian_config().0,
+            system_program: system_program::ID,
+        },
+        instruction::AddCapability {
+            id,
+            name,
+            description,
+        },
+    )
+}
+
+/// Deprecate a capability so new agents can no longer list it (guardian authority)
+pub fn deprecate_capability(authority: Pubkey, id: &str) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::DeprecateCapability {
+            capability: pda::capability(id).0,
+            authority,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::DeprecateCapability {},
+    )
+}
+
+/// Accept `mint` as a token agents may price in (guardian authority)
+pub fn add_accepted_mint(authority: Pubkey, mint: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::AddAcceptedMint {
+            accepted_mint: pda::accepted_mint(&mint).0,
+            mint,
+            authority,
+            guardian_config: pda::guardian_config().0,
+            system_program: system_program::ID,
+        },
+        instruction::AddAcceptedMint {},
+    )
+}
+
+/// Stop accepting `mint` for new pricing (guardian authority)
+pub fn remove_accepted_mint(authority: Pubkey, mint: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RemoveAcceptedMint {
+            accepted_mint: pda::accepted_mint(&mint).0,
+            authority,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::RemoveAcceptedMint {},
+    )
+}
+
+/// Let `checker` post endpoint health checks (guardian authority)
+pub fn add_health_checker(authority: Pubkey, checker: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::AddHealthChecker {
+            health_checker: pda::health_checker(&checker).0,
+            authority,
+            guardian_config: pda::guardian_config().0,
+            system_program: system_program::ID,
+        },
+        instruction::AddHealthChecker { checker },
+    )
+}
+
+/// Stop accepting health checks from `checker` (guardian authority)
+pub fn remove_health_checker(authority: Pubkey, checker: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RemoveHealthChecker {
+            health_checker: pda::health_checker(&checker).0,
+            authority,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::RemoveHealthChecker {},
+    )
+}
+
+/// Post whether the agent's endpoints answered, as a registered `checker`
+/// that also pays for the agent's health log on its first check
+pub fn submit_health_check(
+    checker: Pubkey,
+    agent_id: Pubkey,
+    healthy: bool,
+    latency_ms: u32,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SubmitHealthCheck {
+            agent_profile: agent_id,
+            health_log: pda::health_log(&agent_id).0,
+            health_checker: pda::health_checker(&checker).0,
+            checker,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SubmitHealthCheck {
+            healthy,
+            latency_ms,
+        },
+    )
+}
+
+/// Optional profile changes; `None` fields are left untouched
+#[derive(Clone, Default)]
+pub struct UpdateAgentArgs {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub pricing: Option<PricingModel>,
+    pub is_active: Option<bool>,
+}
+
+/// Hand management of an agent to `authority`, e.g. a multisig vault, or back
+/// to the NFT holder with `None`; signed by the current manager
+pub fn set_agent_authority(
+    manager: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    authority: Option<Pubkey>,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SetAgentAuthority {
+            agent_profile: agent_id,
+            holder_token_account: get_associated_token_address(&manager, &nft_mint),
+            manager,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SetAgentAuthority { authority },
+    )
+}
+
+/// Update an agent profile as the holder of its NFT, or as its authority
+/// when it has one. A new name goes through [`rename_agent`]
+pub fn update_agent(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    args: UpdateAgentArgs,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::UpdateAgent {
+            agent_profile: agent_id,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            guardian_config: pda::guardian_config().0,
+            system_program: system_program::ID,
+            accepted_mint: args.pricing.as_ref().and_then(accepted_mint),
+            name_claim: None,
+            new_name_claim: None,
+        },
+        instruction::UpdateAgent {
+            name: args.name,
+            description: args.description,
+            pricing: args.pricing,
+            is_active: args.is_active,
+            capabilities: None,
+        },
+    )
+}
+
+/// Rename an agent as the holder of its NFT, or as its authority when it has
+/// one, moving its name claim to `name`. Fails if another agent holds a name
+/// that reads the same
+pub fn rename_agent(holder: Pubkey, profile: &AgentProfile, name: String) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::UpdateAgent {
+            agent_profile: profile.agent_id,
+            holder_token_account: get_associated_token_address(&holder, &profile.nft_mint),
+            holder,
+            guardian_config: pda::guardian_config().0,
+            system_program: system_program::ID,
+            accepted_mint: None,
+            name_claim: Some(pda::name_claim(&profile.name).0),
+            new_name_claim: Some(pda::name_claim(&name).0),
+        },
+        instruction::UpdateAgent {
+            name: Some(name),
+            description: None,
+            pricing: None,
+            is_active: None,
+            capabilities: None,
+        },
+    )
+}
+
+/// Replace an agent's capabilities as the holder of its NFT, indexing the
+/// new ones and closing the indexes of those it no longer lists
+pub fn update_agent_capabilities(
+    holder: Pubkey,
+    profile: &AgentProfile,
+    capabilities: Vec<String>,
+) -> Instruction {
+    let mut ix = build(
+        REGISTRY_PROGRAM_ID,
+        accounts::UpdateAgent {
+            agent_profile: profile.agent_id,
+            holder_token_account: get_associated_token_address(&holder, &profile.nft_mint),
+            holder,
+            guardian_config: pda::guardian_config().0,
+            system_program: system_program::ID,
+            accepted_mint: None,
+            name_claim: None,
+            new_name_claim: None,
+        },
+        instruction::UpdateAgent {
+            name: None,
+            description: None,
+            pricing: None,
+            is_active: None,
+            capabilities: Some(capabilities.clone()),
+        },
+    );
+    ix.accounts
+        .extend(capability_accounts(&profile.agent_id, &capabilities));
+    ix.accounts.extend(dropped_index_accounts(
+        &profile.agent_id,
+        profile
+            .capabilities
+            .iter()
+            .filter(|id| !capabilities.contains(id)),
+    ));
+    ix
+}
+
+/// List capabilities past the profile's limit in the agent's
+/// `AgentCapabilities` account as the holder of its NFT, indexing each
+pub fn add_agent_capabilities(
+    holder: Pubkey,
+    profile: &AgentProfile,
+    capabilities: Vec<String>,
+) -> Instruction {
+    let mut ix = build(
+        REGISTRY_PROGRAM_ID,
+        accounts::AddAgentCapabilities {
+            agent_profile: profile.agent_id,
+            agent_capabilities: pda::agent_capabilities(&profile.agent_id).0,
+            holder_token_account: get_associated_token_address(&holder, &profile.nft_mint),
+            holder,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::AddAgentCapabilities {
+            capabilities: capabilities.clone(),
+        },
+    );
+    ix.accounts
+        .extend(capability_accounts(&profile.agent_id, &capabilities));
+    ix
+}
+
+/// Drop capabilities from the agent's `AgentCapabilities` account as the
+/// holder of its NFT, closing their indexes
+pub fn remove_agent_capabilities(
+    holder: Pubkey,
+    profile: &AgentProfile,
+    capabilities: Vec<String>,
+) -> Instruction {
+    let mut ix = build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RemoveAgentCapabilities {
+            agent_profile: profile.agent_id,
+            agent_capabilities: pda::agent_capabilities(&profile.agent_id).0,
+            holder_token_account: get_associated_token_address(&holder, &profile.nft_mint),
+            holder,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::RemoveAgentCapabilities {
+            capabilities: capabilities.clone(),
+        },
+    );
+    ix.accounts
+        .extend(dropped_index_accounts(&profile.agent_id, &capabilities));
+    ix
+}
+
+/// Pay the rent shortfall of one of an agent's accounts, named by `target`
+pub fn top_up_rent(funder: Pubkey, agent_id: Pubkey, target: RentTarget) -> Instruction {
+    let account = match target {
+        RentTarget::Profile => agent_id,
+        RentTarget::Capabilities => pda::agent_capabilities(&agent_id).0,
+        RentTarget::HealthLog => pda::health_log(&agent_id).0,
+        RentTarget::Analytics => pda::agent_analytics(&agent_id).0,
+    };
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::TopUpRent {
+            agent_profile: agent_id,
+            account,
+            funder,
+            system_program: system_program::ID,
+        },
+        instruction::TopUpRent { target },
+    )
+}
+
+/// Replace the languages an agent serves as the holder of its NFT, indexing
+/// the new ones and closing the indexes of those it no longer lists
+pub fn set_languages(
+    holder: Pubkey,
+    profile: &AgentProfile,
+    languages: Vec<String>,
+) -> Instruction {
+    let mut ix = build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SetLanguages {
+            agent_profile: profile.agent_id,
+            holder_token_account: get_associated_token_address(&holder, &profile.nft_mint),
+            holder,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SetLanguages {
+            languages: languages.clone(),
+        },
+    );
+    ix.accounts
+        .extend(language_accounts(&profile.agent_id, &languages));
+    ix.accounts.extend(language_accounts(
+        &profile.agent_id,
+        profile
+            .languages
+            .iter()
+            .filter(|language| !languages.contains(language)),
+    ));
+    ix
+}
+
+/// Replace the agents this one calls into as the holder of its NFT; each
+/// dependency's profile is passed so the registry can check it is active
+pub fn set_dependencies(
+    holder: Pubkey,
+    profile: &AgentProfile,
+    dependencies: Vec<Pubkey>,
+) -> Instruction {
+    let mut ix = build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SetDependencies {
+            agent_profile: profile.agent_id,
+            holder_token_account: get_associated_token_address(&holder, &profile.nft_mint),
+            holder,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SetDependencies {
+            dependencies: dependencies.clone(),
+        },
+    );
+    ix.accounts.extend(
+        dependencies
+            .iter()
+            .map(|dependency| AccountMeta::new_readonly(*dependency, false)),
+    );
+    ix
+}
+
+/// A release to record with [`publish_agent_version`]
+#[derive(Clone)]
+pub struct AgentVersionArgs {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+    pub ipfs_hash: String,
+    /// SHA-256 of the release notes
+    pub changelog_hash: [u8; 32],
+}
+
+/// Publish a new release of an agent as the holder of its NFT
+pub fn publish_agent_version(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    args: AgentVersionArgs,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::PublishAgentVersion {
+            agent_profile: agent_id,
+            agent_version: pda::agent_version(&agent_id, args.major, args.minor, args.patch).0,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::PublishAgentVersion {
+            major: args.major,
+            minor: args.minor,
+            patch: args.patch,
+            ipfs_hash: args.ipfs_hash,
+            changelog_hash: args.changelog_hash,
+        },
+    )
+}
+
+/// A service to list with [`add_service_offering`]
+#[derive(Clone)]
+pub struct ServiceOfferingArgs {
+    /// Distinguishes the agent's offerings; any unused number
+    pub offering_id: u16,
+    pub name: String,
+    pub description: String,
+    /// Lamports a request for the service escrows at least
+    pub price: u64,
+    pub turnaround_secs: u32,
+}
+
+/// Add a service to the agent's catalog as the holder of its NFT
+pub fn add_service_offering(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    args: ServiceOfferingArgs,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::AddServiceOffering {
+            agent_profile: agent_id,
+            service_offering: pda::service_offering(&agent_id, args.offering_id).0,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::AddServiceOffering {
+            offering_id: args.offering_id,
+            name: args.name,
+            description: args.description,
+            price: args.price,
+            turnaround_secs: args.turnaround_secs,
+        },
+    )
+}
+
+/// Optional offering changes; `None` fields are left untouched
+#[derive(Clone, Default)]
+pub struct UpdateServiceOfferingArgs {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub price: Option<u64>,
+    pub turnaround_secs: Option<u32>,
+    pub is_active: Option<bool>,
+}
+
+/// Change one of the agent's offerings as the holder of its NFT
+pub fn update_service_offering(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    offering_id: u16,
+    args: UpdateServiceOfferingArgs,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::UpdateServiceOffering {
+            agent_profile: agent_id,
+            service_offering: pda::service_offering(&agent_id, offering_id).0,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::UpdateServiceOffering {
+            name: args.name,
+            description: args.description,
+            price: args.price,
+            turnaround_secs: args.turnaround_secs,
+            is_active: args.is_active,
+        },
+    )
+}
+
+/// Remove one of the agent's offerings as the holder of its NFT, refunding its rent
+pub fn remove_service_offering(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    offering_id: u16,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RemoveServiceOffering {
+            agent_profile: agent_id,
+            service_offering: pda::service_offering(&agent_id, offering_id).0,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::RemoveServiceOffering {},
+    )
+}
+
+/// Price one of the agent's capabilities on its own, as the holder of its
+/// NFT; setting it again changes the price
+pub fn set_capability_price(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    capability_id: String,
+    price: u64,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SetCapabilityPrice {
+            agent_profile: agent_id,
+            capability_price: pda::capability_price(&agent_id, &capability_id).0,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SetCapabilityPrice {
+            capability_id,
+            price,
+        },
+    )
+}
+
+/// Drop a capability's price override, as the holder of its NFT, who gets
+/// the rent back
+pub fn remove_capability_price(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    capability_id: String,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RemoveCapabilityPrice {
+            agent_profile: agent_id,
+            capability_price: pda::capability_price(&agent_id, &capability_id).0,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::RemoveCapabilityPrice { capability_id },
+    )
+}
+
+/// A bundle to list with [`create_bundle`]
+#[derive(Clone)]
+pub struct BundleArgs {
+    /// Distinguishes the curator's bundles; any unused number
+    pub bundle_id: u16,
+    pub name: String,
+    /// 2-5 active agents
+    pub agents: Vec<Pubkey>,
+    /// Combined list price, in lamports
+    pub price: u64,
+    pub discount_bps: u16,
+}
+
+/// List several agents together at a discount. `creators` are the agents'
+/// creators, in the same order; each must sign the transaction
+pub fn create_bundle(curator: Pubkey, args: BundleArgs, creators: &[Pubkey]) -> Instruction {
+    let mut ix = build(
+        REGISTRY_PROGRAM_ID,
+        accounts::CreateBundle {
+            bundle: pda::bundle(&curator, args.bundle_id).0,
+            curator,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::CreateBundle {
+            bundle_id: args.bundle_id,
+            name: args.name,
+            agents: args.agents.clone(),
+            price: args.price,
+            discount_bps: args.discount_bps,
+        },
+    );
+    ix.accounts.extend(
+        args.agents
+            .iter()
+            .map(|agent_id| AccountMeta::new_readonly(*agent_id, false)),
+    );
+    ix.accounts.extend(
+        creators
+            .iter()
+            .map(|creator| AccountMeta::new_readonly(*creator, true)),
+    );
+    ix
+}
+
+/// Optional bundle changes; `None` fields are left untouched
+#[derive(Clone, Default)]
+pub struct UpdateBundleArgs {
+    pub name: Option<String>,
+    pub price: Option<u64>,
+    pub discount_bps: Option<u16>,
+    pub is_active: Option<bool>,
+}
+
+/// Change one of the curator's bundles
+pub fn update_bundle(curator: Pubkey, bundle_id: u16, args: UpdateBundleArgs) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::UpdateBundle {
+            bundle: pda::bundle(&curator, bundle_id).0,
+            curator,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::UpdateBundle {
+            name: args.name,
+            price: args.price,
+            discount_bps: args.discount_bps,
+            is_active: args.is_active,
+        },
+    )
+}
+
+/// A plan to offer with [`create_subscription_tier`]
+#[derive(Clone)]
+pub struct SubscriptionTierArgs {
+    /// Distinguishes the agent's tiers; any unused number
+    pub tier_id: u16,
+    pub name: String,
+    /// Lamports per month
+    pub monthly_price: u64,
+    pub requests_per_month: u32,
+    /// Hash of the off-chain feature list
+    pub features_hash: [u8; 32],
+}
+
+/// Add a tier to a subscription-priced agent as the holder of its NFT
+pub fn create_subscription_tier(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    args: SubscriptionTierArgs,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::CreateSubscriptionTier {
+            agent_profile: agent_id,
+            subscription_tier: pda::subscription_tier(&agent_id, args.tier_id).0,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::CreateSubscriptionTier {
+            tier_id: args.tier_id,
+            name: args.name,
+            monthly_price: args.monthly_price,
+            requests_per_month: args.requests_per_month,
+            features_hash: args.features_hash,
+        },
+    )
+}
+
+/// Stop new subscriptions on one of the agent's tiers as the holder of its NFT
+pub fn retire_subscription_tier(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    tier_id: u16,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RetireSubscriptionTier {
+            agent_profile: agent_id,
+            subscription_tier: pda::subscription_tier(&agent_id, tier_id).0,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::RetireSubscriptionTier {},
+    )
+}
+
+/// Buy `months` of access on one of the agent's tiers, paying through the
+/// royalty splitter under its current `config`
+pub fn issue_access_grant(
+    user: Pubkey,
+    profile: &AgentProfile,
+    config: &RoyaltyConfig,
+    tier_id: u16,
+    months: u32,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::IssueAccessGrant {
+            agent_profile: profile.agent_id,
+            subscription_tier: pda::subscription_tier(&profile.agent_id, tier_id).0,
+            access_grant: pda::access_grant(&profile.agent_id, &user).0,
+            user,
+            creator_account: profile.payout_wallet,
+            royalty_config: pda::royalty_config().0,
+            distribution_record: pda::distribution_record(config.total_transactions).0,
+            platform_account: config.platform_wallet,
+            treasury_account: config.treasury_wallet,
+            royalty_program: ROYALTY_PROGRAM_ID,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::IssueAccessGrant { months },
+    )
+}
+
+/// Draw `units` requests from a user's access grant as the holder of the agent's NFT
+pub fn consume_access(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    user: Pubkey,
+    units: u32,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::ConsumeAccess {
+            agent_profile: agent_id,
+            access_grant: pda::access_grant(&agent_id, &user).0,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::ConsumeAccess { units },
+    )
+}
+
+/// Cut off a user's access as the holder of the agent's NFT
+pub fn revoke_access_grant(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    user: Pubkey,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RevokeAccessGrant {
+            agent_profile: agent_id,
+            access_grant: pda::access_grant(&agent_id, &user).0,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::RevokeAccessGrant {},
+    )
+}
+
+/// Replace the co-creators sharing the agent's creator payouts as the holder
+/// of its NFT; an empty list ends the split
+pub fn set_co_creators(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    co_creators: Vec<CoCreator>,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SetCoCreators {
+            agent_profile: agent_id,
+            co_creator_split: pda::co_creator_split(&agent_id).0,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SetCoCreators { co_creators },
+    )
+}
+
+/// Resolve a USD-pegged agent's price to lamports against its Pyth `feed`.
+/// Put it ahead of `create_service_request` so the quote lands in the same
+/// transaction; simulate it to read the amount from the return data
+pub fn resolve_usd_price(agent_id: Pubkey, feed: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::ResolveUsdPrice {
+            agent_profile: agent_id,
+            price_feed: feed,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::ResolveUsdPrice {},
+    )
+}
+
+/// Resolve a surge-priced agent's price to lamports at its current open
+/// request count. Put it ahead of `create_service_request` so the quote
+/// lands in the same transaction
+pub fn resolve_surge_price(agent_id: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::ResolveSurgePrice {
+            agent_profile: agent_id,
+            agent_requests: pda::agent_requests(&agent_id).0,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::ResolveSurgePrice {},
+    )
+}
+
+/// Read an agent's fixed-size profile fields and derived fields as an
+/// [`AgentProfileView`] in the transaction's return data. Fetch the profile
+/// account for its description, capabilities, endpoints, and schemas
+pub fn get_agent_profile(agent_id: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::GetAgentProfile {
+            agent_profile: agent_id,
+            agent_requests: pda::agent_requests(&agent_id).0,
+        },
+        instruction::GetAgentProfile {},
+    )
+}
+
+/// Feature an agent for `days` more days, as the holder of its NFT, paying
+/// the fee through the royalty splitter under its current `config`
+pub fn promote_agent(
+    holder: Pubkey,
+    profile: &AgentProfile,
+    config: &RoyaltyConfig,
+    days: u32,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::PromoteAgent {
+            agent_profile: profile.agent_id,
+            holder_token_account: get_associated_token_address(&holder, &profile.nft_mint),
+            holder,
+            registry_config: pda::registry_config().0,
+            featured_index: pda::featured_index().0,
+            royalty_config: pda::royalty_config().0,
+            distribution_record: pda::distribution_record(config.total_transactions).0,
+            platform_account: config.platform_wallet,
+            treasury_account: config.treasury_wallet,
+            royalty_program: ROYALTY_PROGRAM_ID,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::PromoteAgent { days },
+    )
+}
+
+/// Issue a challenge nonce for the agent's endpoint to sign, as the holder of its NFT
+pub fn request_endpoint_challenge(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RequestEndpointChallenge {
+            agent_profile: agent_id,
+            endpoint_challenge: pda::endpoint_challenge(&agent_id).0,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::RequestEndpointChallenge {},
+    )
+}
+
+/// Mark the agent's endpoint verified, as the holder of its NFT. Must come
+/// right after [`ed25519_verify`] of `endpoint_key`'s signature over
+/// [`endpoint_challenge_message`] for the outstanding nonce
+pub fn attest_endpoint(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    endpoint_key: Pubkey,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::AttestEndpoint {
+            agent_profile: agent_id,
+            endpoint_challenge: pda::endpoint_challenge(&agent_id).0,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            instructions: sysvar::instructions::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::AttestEndpoint { endpoint_key },
+    )
+}
+
+/// Native ed25519 program instruction verifying one signature, with every
+/// input carried in the instruction itself
+pub fn ed25519_verify(pubkey: &Pubkey, signature: &[u8; 64], message: &[u8]) -> Instruction {
+    // Header and offsets, then the key, signature, and message
+    const PUBKEY_OFFSET: u16 = 16;
+    const SIGNATURE_OFFSET: u16 = PUBKEY_OFFSET + 32;
+    const MESSAGE_OFFSET: u16 = SIGNATURE_OFFSET + 64;
+    // The inputs live in this same instruction
+    const THIS_INSTRUCTION: u16 = u16::MAX;
+
+    let mut data = vec![1, 0];
+    for field in [
+        SIGNATURE_OFFSET,
+        THIS_INSTRUCTION,
+        PUBKEY_OFFSET,
+        THIS_INSTRUCTION,
+        MESSAGE_OFFSET,
+        message.len() as u16,
+        THIS_INSTRUCTION,
+    ] {
+        data.extend_from_slice(&field.to_le_bytes());
+    }
+    data.extend_from_slice(pubkey.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+
+    Instruction {
+        program_id: ED25519_PROGRAM_ID,
+        accounts: Vec::new(),
+        data,
+    }
+}
+
+/// Record a heartbeat for the agent as the holder of its NFT
+pub fn heartbeat(holder: Pubkey, agent_id: Pubkey, nft_mint: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::Heartbeat {
+            agent_profile: agent_id,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::Heartbeat {},
+    )
+}
+
+/// Replace an agent's endpoints as the holder of its NFT; the program orders
+/// them by priority and clears the endpoint verification if they changed
+pub fn rotate_endpoints(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    endpoints: Vec<AgentEndpoint>,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RotateEndpoints {
+            agent_profile: agent_id,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::RotateEndpoints { endpoints },
+    )
+}
+
+/// Send an agent's escrow and royalty payouts to `payout_wallet`, as the
+/// holder of its NFT
+pub fn set_payout_wallet(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    payout_wallet: Pubkey,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SetPayoutWallet {
+            agent_profile: agent_id,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SetPayoutWallet { payout_wallet },
+    )
+}
+
+/// Hand the update authority of an agent's NFT metadata to the registry, as
+/// its current update authority
+pub fn migrate_metadata_authority(update_authority: Pubkey, profile: &AgentProfile) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::MigrateMetadataAuthority {
+            agent_profile: profile.agent_id,
+            metadata: pda::token_metadata(&profile.nft_mint).0,
+            nft_mint: profile.nft_mint,
+            update_authority,
+            metadata_authority: pda::metadata_authority().0,
+            sysvar_instructions: sysvar::instructions::ID,
+            token_metadata_program: TOKEN_METADATA_PROGRAM_ID,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::MigrateMetadataAuthority {},
+    )
+}
+
+/// Update the NFT metadata of a migrated agent as the holder of its NFT; the
+/// name follows the profile, `symbol` and `uri` change when given
+pub fn update_agent_metadata(
+    holder: Pubkey,
+    profile: &AgentProfile,
+    symbol: Option<String>,
+    uri: Option<String>,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::UpdateAgentMetadata {
+            agent_profile: profile.agent_id,
+            holder_token_account: get_associated_token_address(&holder, &profile.nft_mint),
+            holder,
+            metadata: pda::token_metadata(&profile.nft_mint).0,
+            nft_mint: profile.nft_mint,
+            metadata_authority: pda::metadata_authority().0,
+            sysvar_instructions: sysvar::instructions::ID,
+            token_metadata_program: TOKEN_METADATA_PROGRAM_ID,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::UpdateAgentMetadata { symbol, uri },
+    )
+}
+
+/// Grow an agent's profile to hold up to `capability_limit` capabilities
+/// and a `description_limit`-character description, as the holder of its
+/// NFT, who pays the extra rent
+pub fn resize_profile(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    capability_limit: u8,
+    description_limit: u16,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::ResizeProfile {
+            agent_profile: agent_id,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::ResizeProfile {
+            capability_limit,
+            description_limit,
+        },
+    )
+}
+
+/// Deactivate an agent that missed its heartbeat window (permissionless)
+pub fn mark_stale(agent_id: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::MarkStale {
+            registry_config: pda::registry_config().0,
+            agent_profile: agent_id,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::MarkStale {},
+    )
+}
+
+/// Cap how many requests the agent serves at once, zero for no limit, as
+/// the holder of its NFT
+pub fn set_max_concurrent_requests(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    max_concurrent_requests: u32,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SetMaxConcurrentRequests {
+            agent_profile: agent_id,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            agent_requests: pda::agent_requests(&agent_id).0,
+            capacity_hook: pda::capacity_hook().0,
+            escrow_program: ESCROW_PROGRAM_ID,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SetMaxConcurrentRequests {
+            max_concurrent_requests,
+        },
+    )
+}
+
+/// Declare the input and output schema CIDs of one of the agent's
+/// capabilities, as the holder of its NFT
+pub fn set_capability_schema(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    capability_id: String,
+    input_schema: String,
+    output_schema: String,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SetCapabilitySchema {
+            agent_profile: agent_id,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SetCapabilitySchema {
+            capability_id,
+            input_schema,
+            output_schema,
+        },
+    )
+}
+
+/// Withdraw the declared schemas of one of the agent's capabilities, as the
+/// holder of its NFT
+pub fn clear_capability_schema(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    capability_id: String,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SetCapabilitySchema {
+            agent_profile: agent_id,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::ClearCapabilitySchema { capability_id },
+    )
+}
+
+/// Take the agent's listings offline until `paused_until`, as the holder of its NFT
+pub fn pause_listings(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    paused_until: i64,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SetListingPause {
+            agent_profile: agent_id,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            agent_requests: pda::agent_requests(&agent_id).0,
+            capacity_hook: pda::capacity_hook().0,
+            escrow_program: ESCROW_PROGRAM_ID,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::PauseListings { paused_until },
+    )
+}
+
+/// Open the agent to every user or only its allowlist, as the holder of its
+/// NFT; the escrow enforces it on new requests
+pub fn set_visibility(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    visibility: Visibility,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SetVisibility {
+            agent_profile: agent_id,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            agent_requests: pda::agent_requests(&agent_id).0,
+            capacity_hook: pda::capacity_hook().0,
+            escrow_program: ESCROW_PROGRAM_ID,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SetVisibility { visibility },
+    )
+}
+
+/// Let each wallet make its first `trial_requests` requests of the agent for
+/// free, as the holder of its NFT; the escrow enforces it on new requests
+pub fn set_trial_requests(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    trial_requests: u8,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SetTrialRequests {
+            agent_profile: agent_id,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            agent_requests: pda::agent_requests(&agent_id).0,
+            capacity_hook: pda::capacity_hook().0,
+            escrow_program: ESCROW_PROGRAM_ID,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SetTrialRequests { trial_requests },
+    )
+}
+
+/// Retire an agent for good as the holder of its NFT, pointing new requests
+/// at `successor` when given; the profile and its history stay on-chain
+pub fn retire_agent(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    successor: Option<Pubkey>,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RetireAgent {
+            agent_profile: agent_id,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            successor_profile: successor,
+            agent_requests: pda::agent_requests(&agent_id).0,
+            capacity_hook: pda::capacity_hook().0,
+            escrow_program: ESCROW_PROGRAM_ID,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::RetireAgent { successor },
+    )
+}
+
+/// Put `user` on the agent's allowlist, as the holder of its NFT
+pub fn add_allowlist_entry(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    user: Pubkey,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::AddAllowlistEntry {
+            agent_profile: agent_id,
+            allowlist_entry: pda::allowlist_entry(&agent_id, &user).0,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::AddAllowlistEntry { user },
+    )
+}
+
+/// Take `user` off the agent's allowlist, as the holder of its NFT
+pub fn remove_allowlist_entry(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    user: Pubkey,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::RemoveAllowlistEntry {
+            agent_profile: agent_id,
+            allowlist_entry: pda::allowlist_entry(&agent_id, &user).0,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::RemoveAllowlistEntry {},
+    )
+}
+
+/// Refuse service to `user`, as the holder of the agent's NFT
+pub fn block_user(holder: Pubkey, agent_id: Pubkey, nft_mint: Pubkey, user: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::BlockUser {
+            agent_profile: agent_id,
+            block_entry: pda::block_entry(&agent_id, &user).0,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::BlockUser { user },
+    )
+}
+
+/// Serve a blocked `user` again, as the holder of the agent's NFT
+pub fn unblock_user(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    user: Pubkey,
+) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::UnblockUser {
+            agent_profile: agent_id,
+            block_entry: pda::block_entry(&agent_id, &user).0,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::UnblockUser {},
+    )
+}
+
+/// End the agent's scheduled pause early, as the holder of its NFT
+pub fn resume_listings(holder: Pubkey, agent_id: Pubkey, nft_mint: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::SetListingPause {
+            agent_profile: agent_id,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            agent_requests: pda::agent_requests(&agent_id).0,
+            capacity_hook: pda::capacity_hook().0,
+            escrow_program: ESCROW_PROGRAM_ID,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::ResumeListings {},
+    )
+}
+
+/// Make the holder of the agent NFT the profile's creator
+pub fn transfer_ownership(holder: Pubkey, agent_id: Pubkey, nft_mint: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::TransferOwnership {
+            agent_profile: agent_id,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            holder,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::TransferOwnership {},
+    )
+}
+
+/// List an agent for sale at `price` lamports as the holder of its NFT, which
+/// moves into the sale's escrow; `authority` co-signs when the agent has one
+pub fn list_agent_for_sale(
+    seller: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    price: u64,
+    authority: Option<Pubkey>,
+) -> Instruction {
+    let agent_sale = pda::agent_sale(&agent_id).0;
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::ListAgentForSale {
+            agent_profile: agent_id,
+            agent_sale,
+            nft_mint,
+            seller_token_account: get_associated_token_address(&seller, &nft_mint),
+            sale_token_account: get_associated_token_address(&agent_sale, &nft_mint),
+            seller,
+            authority,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::ListAgentForSale { price },
+    )
+}
+
+/// Take an agent off sale, returning its NFT to the seller
+pub fn cancel_agent_sale(seller: Pubkey, agent_id: Pubkey, nft_mint: Pubkey) -> Instruction {
+    let agent_sale = pda::agent_sale(&agent_id).0;
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::CancelAgentSale {
+            agent_sale,
+            sale_token_account: get_associated_token_address(&agent_sale, &nft_mint),
+            seller_token_account: get_associated_token_address(&seller, &nft_mint),
+            seller,
+            token_program: anchor_spl::token::ID,
+        },
+        instruction::CancelAgentSale {},
+    )
+}
+
+/// Buy a listed agent at its asking `price`, taking its NFT and profile
+pub fn buy_agent(
+    buyer: Pubkey,
+    seller: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    price: u64,
+) -> Instruction {
+    let agent_sale = pda::agent_sale(&agent_id).0;
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::BuyAgent {
+            agent_profile: agent_id,
+            agent_sale,
+            nft_mint,
+            sale_token_account: get_associated_token_address(&agent_sale, &nft_mint),
+            buyer_token_account: get_associated_token_address(&buyer, &nft_mint),
+            seller,
+            buyer,
+            system_program: system_program::ID,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::BuyAgent { price },
+    )
+}
+
+/// Deregister an agent as the holder of its NFT, burning it and refunding the
+/// rent of its profile, its name claim, and its capability and language
+/// indexes; fails while the agent has open escrow requests. `name`,
+/// `capabilities` and `languages` are the profile's; `authority` co-signs
+/// when the agent has one
+pub fn deregister_agent(
+    holder: Pubkey,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    name: &str,
+    capabilities: &[String],
+    languages: &[String],
+    authority: Option<Pubkey>,
+) -> Instruction {
+    let mut ix = build(
+        REGISTRY_PROGRAM_ID,
+        accounts::DeregisterAgent {
+            agent_profile: agent_id,
+            nft_mint,
+            holder_token_account: get_associated_token_address(&holder, &nft_mint),
+            agent_requests: pda::agent_requests(&agent_id).0,
+            listing_stake: pda::listing_stake(&agent_id).0,
+            agent_capabilities: pda::agent_capabilities(&agent_id).0,
+            name_claim: pda::name_claim(name).0,
+            holder,
+            authority,
+            collection_authority: pda::collection_authority().0,
+            token_program: anchor_spl::token::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::DeregisterAgent {},
+    );
+    ix.accounts
+        .extend(dropped_index_accounts(&agent_id, capabilities));
+    ix.accounts.extend(language_accounts(&agent_id, languages));
+    ix
+}
+
+/// Upgrade an agent profile from an older layout to the current one, growing
+/// it as needed; `payer` covers the extra rent
+pub fn migrate_profile(payer: Pubkey, agent_id: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::MigrateProfile {
+            agent_profile: agent_id,
+            payer,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::MigrateProfile {},
+    )
+}
+
+/// Upgrade an agent profile to the current layout during a schema migration
+/// (guardian authority, protocol paused); `authority` covers the extra rent
+pub fn migrate_agent_profile(authority: Pubkey, agent_profile: Pubkey) -> Instruction {
+    build(
+        REGISTRY_PROGRAM_ID,
+        accounts::MigrateAgentProfile {
+            agent_profile,
+            authority,
+            guardian_config: pda::guardian_config().0,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateAgentProfile {},
+    )
+}
@@ -0,0 +1,145 @@
+//! Builders for the reputation program.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_program;
+use reputation_system::{accounts, instruction};
+
+use super::build;
+use crate::{pda, REGISTRY_PROGRAM_ID, REPUTATION_PROGRAM_ID};
+
+/// Star and detail scores for a rating, each 1-5
+#[derive(Clone, Copy)]
+pub struct RatingScores {
+    pub stars: u8,
+    pub quality: u8,
+    pub speed: u8,
+    pub value: u8,
+}
+
+/// Create the reputation aggregate for an agent registered before
+/// registration created it
+pub fn initialize_agent_reputation(creator: Pubkey, agent_id: Pubkey) -> Instruction {
+    build(
+        REPUTATION_PROGRAM_ID,
+        accounts::InitializeAgentReputation {
+            agent_profile: pda::agent_reputation(&agent_id).0,
+            creator,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::InitializeAgentReputation { agent_id },
+    )
+}
+
+/// Rate an agent for a completed request
+pub fn submit_rating(
+    user: Pubkey,
+    agent_id: Pubkey,
+    request_id: Pubkey,
+    scores: RatingScores,
+    review_text: String,
+) -> Instruction {
+    build(
+        REPUTATION_PROGRAM_ID,
+        accounts::SubmitRating {
+            rating: pda::rating(&user, &request_id).0,
+            agent_profile: pda::agent_reputation(&agent_id).0,
+            registry_profile: agent_id,
+            reputation_hook: pda::reputation_hook().0,
+            registry_program: REGISTRY_PROGRAM_ID,
+            user,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::SubmitRating {
+            request_id,
+            stars: scores.stars,
+            quality: scores.quality,
+            speed: scores.speed,
+            value: scores.value,
+            review_text,
+        },
+    )
+}
+
+/// Flag a rating for moderation
+pub fn report_rating(rating: Pubkey, reporter: Pubkey, reason: String) -> Instruction {
+    build(
+        REPUTATION_PROGRAM_ID,
+        accounts::ReportRating {
+            rating,
+            reporter,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::ReportRating { reason },
+    )
+}
+
+/// Create the moderation config with the guardian authority as admin
+pub fn initialize_config(authority: Pubkey) -> Instruction {
+    build(
+        REPUTATION_PROGRAM_ID,
+        accounts::InitializeConfig {
+            reputation_config: pda::reputation_config().0,
+            authority,
+            guardian_config: pda::guardian_config().0,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeConfig {},
+    )
+}
+
+/// Hand the moderation admin role to a new key
+pub fn transfer_admin(admin: Pubkey, new_admin: Pubkey) -> Instruction {
+    build(
+        REPUTATION_PROGRAM_ID,
+        accounts::TransferAdmin {
+            reputation_config: pda::reputation_config().0,
+            admin,
+        },
+        instruction::TransferAdmin { new_admin },
+    )
+}
+
+/// Moderate a reported rating (admin only)
+pub fn moderate_rating(
+    admin: Pubkey,
+    rating: Pubkey,
+    agent_id: Pubkey,
+    is_valid: bool,
+    admin_note: String,
+) -> Instruction {
+    build(
+        REPUTATION_PROGRAM_ID,
+        accounts::ModerateRating {
+            rating,
+            agent_profile: pda::agent_reputation(&agent_id).0,
+            registry_profile: agent_id,
+            reputation_hook: pda::reputation_hook().0,
+            registry_program: REGISTRY_PROGRAM_ID,
+            reputation_config: pda::reputation_config().0,
+            admin,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::ModerateRating {
+            is_valid,
+            admin_note,
+        },
+    )
+}
+
+/// Upgrade a rating to the current layout during a schema migration
+/// (guardian authority, protocol paused); `authority` covers the extra rent
+pub fn migrate_rating(authority: Pubkey, rating: Pubkey) -> Instruction {
+    build(
+        REPUTATION_PROGRAM_ID,
+        accounts::MigrateRating {
+            rating,
+            authority,
+            guardian_config: pda::guardian_config().0,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateRating {},
+    )
+}
@@ -0,0 +1,155 @@
+//! Builders for the royalty splitter program.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_program;
+use royalty_splitter::{accounts, instruction, RoyaltyConfig};
+
+use super::build;
+use crate::{pda, ROYALTY_PROGRAM_ID};
+
+/// Percentage split between creator, platform, and treasury (must total 100)
+#[derive(Clone, Copy)]
+pub struct Shares {
+    pub creator: u8,
+    pub platform: u8,
+    pub treasury: u8,
+}
+
+/// Create the royalty config with the signer as admin
+pub fn initialize_config(
+    admin: Pubkey,
+    shares: Shares,
+    platform_wallet: Pubkey,
+    treasury_wallet: Pubkey,
+) -> Instruction {
+    build(
+        ROYALTY_PROGRAM_ID,
+        accounts::InitializeConfig {
+            royalty_config: pda::royalty_config().0,
+            admin,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeConfig {
+            creator_share: shares.creator,
+            platform_share: shares.platform,
+            treasury_share: shares.treasury,
+            platform_wallet,
+            treasury_wallet,
+        },
+    )
+}
+
+/// Distribute `amount` from `source_account`, which must sign, using the current split
+///
+/// `transaction_index` is the config's current `total_transactions`, which
+/// seeds the new distribution record.
+#[allow(clippy::too_many_arguments)]
+pub fn distribute_payment(
+    payer: Pubkey,
+    source_account: Pubkey,
+    creator: Pubkey,
+    platform_wallet: Pubkey,
+    treasury_wallet: Pubkey,
+    transaction_index: u64,
+    amount: u64,
+) -> Instruction {
+    build(
+        ROYALTY_PROGRAM_ID,
+        accounts::DistributePayment {
+            royalty_config: pda::royalty_config().0,
+            distribution_record: pda::distribution_record(transaction_index).0,
+            source_account,
+            creator_account: creator,
+            platform_account: platform_wallet,
+            treasury_account: treasury_wallet,
+            payer,
+            system_program: system_program::ID,
+            guardian_config: pda::guardian_config().0,
+        },
+        instruction::DistributePayment { amount, creator },
+    )
+}
+
+/// Distribute `amount` to a forked agent as `distribute_payment` does, after
+/// sending its lineage royalty to `parent_payout_wallet`, the payout wallet of
+/// `parent_agent`
+#[allow(clippy::too_many_arguments)]
+pub fn distribute_lineage_payment(
+    payer: Pubkey,
+    source_account: Pubkey,
+    creator: Pubkey,
+    agent_id: Pubkey,
+    parent_agent: Pubkey,
+    parent_payout_wallet: Pubkey,
+    config: &RoyaltyConfig,
+    amount: u64,
+) -> Instruction {
+    build(
+        ROYALTY_PROGRAM_ID,
+        accounts::DistributeLineagePayment {
+            distribution: accounts::DistributePayment {
+                royalty_config: pda::royalty_config().0,
+                distribution_record: pda::distribution_record(config.total_transactions).0,
+                source_account,
+                creator_account: creator,
+                platform_account: config.platform_wallet,
+                treasury_account: config.treasury_wallet,
+                payer,
+                system_program: system_program::ID,
+                guardian_config: pda::guardian_config().0,
+            },
+            agent_lineage: pda::agent_lineage(&agent_id).0,
+            parent_profile: parent_agent,
+            parent_payout_wallet,
+        },
+        instruction::DistributeLineagePayment { amount, creator },
+    )
+}
+
+/// Change the split and/or wallets (admin only)
+pub fn update_config(
+    admin: Pubkey,
+    shares: Option<Shares>,
+    platform_wallet: Option<Pubkey>,
+    treasury_wallet: Option<Pubkey>,
+) -> Instruction {
+    build(
+        ROYALTY_PROGRAM_ID,
+        accounts::UpdateConfig {
+            royalty_config: pda::royalty_config().0,
+            admin,
+        },
+        instruction::UpdateConfig {
+            creator_share: shares.map(|s| s.creator),
+            platform_share: shares.map(|s| s.platform),
+            treasury_share: shares.map(|s| s.treasury),
+            platform_wallet,
+            treasury_wallet,
+        },
+    )
+}
+
+/// Hand royalty administration to a new key
+pub fn transfer_admin(admin: Pubkey, new_admin: Pubkey) -> Instruction {
+    build(
+        ROYALTY_PROGRAM_ID,
+        accounts::UpdateConfig {
+            royalty_config: pda::royalty_config().0,
+            admin,
+        },
+        instruction::TransferAdmin { new_admin },
+    )
+}
+
+/// Pause or resume distributions (admin only)
+pub fn set_pause_state(admin: Pubkey, is_paused: bool) -> Instruction {
+    build(
+        ROYALTY_PROGRAM_ID,
+        accounts::SetPauseState {
+            royalty_config: pda::royalty_config().0,
+            admin,
+        },
+        instruction::SetPauseState { is_paused },
+    )
+}
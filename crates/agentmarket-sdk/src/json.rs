@@ -0,0 +1,587 @@
+//! JSON rendering of program accounts and event fields, with pubkeys as base58 strings.
+//!
+//! Anchor account and event types only derive Borsh, so services that export
+//! or relay them go through [`ToJson`] instead of serde.
+
+use anchor_lang::prelude::Pubkey;
+use serde_json::{json, Value};
+
+use crate::instructions::escrow::{DisputeOutcome, Verdict};
+use crate::instructions::registry::RentTarget;
+
+use crate::accounts::{
+    AcceptedMint, AccessGrant, AgentAttestation, AgentCapabilities, AgentEndpoint, AgentIndex,
+    AgentLineage, AgentProfile, AgentReputationProfile, AgentRequestCount, AgentSale, AgentVersion,
+    AllowlistEntry, ArbitrationConfig, BlockEntry, Bundle, Capability, CapabilityIndex,
+    CapabilityPrice, CapabilitySchema, CoCreator, CoCreatorSplit, DisputePanel, DistributionRecord,
+    EndpointChallenge, FeaturedEntry, FeaturedIndex, GuardianConfig, HealthCheck, HealthChecker,
+    HealthLog, JurorPool, JurorSeat, LanguageIndex, ListingStake, NameClaim, OperatorKey,
+    PricingModel, Rating, ReferralCode, RegistryConfig, ReputationConfig, RequestStatus,
+    RoyaltyConfig, ServiceOffering, ServiceRequest, StakeSlash, SubscriptionTier, TrialCounter,
+    Visibility,
+};
+
+/// Render a value as JSON
+pub trait ToJson {
+    fn to_json(&self) -> Value;
+}
+
+impl ToJson for Pubkey {
+    fn to_json(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+// Helper macro to implement `ToJson` for types serde already renders
+macro_rules! serde_value {
+    ($($ty:ty),+) => {
+        $(impl ToJson for $ty {
+            fn to_json(&self) -> Value {
+                json!(self)
+            }
+        })+
+    };
+}
+
+serde_value!(u8, u16, u32, u64, i32, i64, bool, String);
+
+/// Hashes render as lowercase hex
+impl ToJson for [u8; 32] {
+    fn to_json(&self) -> Value {
+        Value::String(self.iter().map(|byte| format!("{byte:02x}")).collect())
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> Value {
+        Value::Array(self.iter().map(ToJson::to_json).collect())
+    }
+}
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> Value {
+        self.as_ref().map_or(Value::Null, ToJson::to_json)
+    }
+}
+
+impl ToJson for PricingModel {
+    fn to_json(&self) -> Value {
+        match self {
+            PricingModel::PerQuery {
+                price,
+                payment_mint,
+            } => json!({
+                "kind": "per_query",
+                "price": price,
+                "payment_mint": payment_mint.to_json(),
+            }),
+            PricingModel::Subscription {
+                monthly,
+                payment_mint,
+            } => json!({
+                "kind": "subscription",
+                "monthly": monthly,
+                "payment_mint": payment_mint.to_json(),
+            }),
+            PricingModel::Custom {
+                base,
+                variable,
+                payment_mint,
+            } => json!({
+                "kind": "custom",
+                "base": base,
+                "variable": variable,
+                "payment_mint": payment_mint.to_json(),
+            }),
+            PricingModel::UsdPegged { usd_cents, feed } => json!({
+                "kind": "usd_pegged",
+                "usd_cents": usd_cents,
+                "feed": feed.to_json(),
+            }),
+            PricingModel::Surge {
+                base,
+                min,
+                max,
+                target_open,
+                step_bps,
+            } => json!({
+                "kind": "surge",
+                "base": base,
+                "min": min,
+                "max": max,
+                "target_open": target_open,
+                "step_bps": step_bps,
+            }),
+        }
+    }
+}
+
+impl ToJson for RequestStatus {
+    fn to_json(&self) -> Value {
+        Value::String(
+            match self {
+                RequestStatus::Pending => "pending",
+                RequestStatus::InProgress => "in_progress",
+                RequestStatus::Completed => "completed",
+                RequestStatus::Approved => "approved",
+                RequestStatus::Disputed => "disputed",
+                RequestStatus::Cancelled => "cancelled",
+                RequestStatus::Resolved => "resolved",
+            }
+            .into(),
+        )
+    }
+}
+
+impl ToJson for Visibility {
+    fn to_json(&self) -> Value {
+        Value::String(
+            match self {
+                Visibility::Public => "public",
+                Visibility::Allowlist => "allowlist",
+            }
+            .into(),
+        )
+    }
+}
+
+impl ToJson for DisputeOutcome {
+    fn to_json(&self) -> Value {
+        match self {
+            DisputeOutcome::AwardAgent => json!({ "kind": "award_agent" }),
+            DisputeOutcome::RefundUser => json!({ "kind": "refund_user" }),
+            DisputeOutcome::Split { agent_bps } => json!({
+                "kind": "split",
+                "agent_bps": agent_bps,
+            }),
+        }
+    }
+}
+
+impl ToJson for Verdict {
+    fn to_json(&self) -> Value {
+        Value::String(
+            match self {
+                Verdict::Agent => "agent",
+                Verdict::User => "user",
+            }
+            .into(),
+        )
+    }
+}
+
+impl ToJson for RentTarget {
+    fn to_json(&self) -> Value {
+        Value::String(
+            match self {
+                RentTarget::Profile => "profile",
+                RentTarget::Capabilities => "capabilities",
+                RentTarget::HealthLog => "health_log",
+                RentTarget::Analytics => "analytics",
+            }
+            .into(),
+        )
+    }
+}
+
+// Helper macro to implement `ToJson` for an account as an object of its named fields
+macro_rules! account {
+    ($ty:ty { $($field:ident),+ $(,)? }) => {
+        impl ToJson for $ty {
+            fn to_json(&self) -> Value {
+                // Built field by field; `json!` hits the recursion limit on large accounts
+                let mut object = serde_json::Map::new();
+                $(object.insert(stringify!($field).into(), ToJson::to_json(&self.$field));)+
+                Value::Object(object)
+            }
+        }
+    };
+}
+
+account!(AgentProfile {
+    agent_id,
+    creator,
+    payout_wallet,
+    name,
+    description,
+    capabilities,
+    pricing_model,
+    endpoints,
+    ipfs_hash,
+    reputation_score,
+    total_services,
+    total_earnings,
+    created_at,
+    is_active,
+    nft_mint,
+    endpoint_verified,
+    featured_until,
+    is_verified,
+    last_seen_at,
+    max_concurrent_requests,
+    paused_until,
+    capability_schemas,
+    parent_agent,
+    visibility,
+    capability_limit,
+    description_limit,
+    trial_requests,
+    languages,
+    dependencies,
+    soulbound,
+    retired,
+    successor,
+    authority,
+    frozen,
+    version,
+    uptime_bps,
+});
+
+account!(AllowlistEntry {
+    agent_id,
+    user,
+    added_at,
+});
+
+account!(BlockEntry {
+    agent_id,
+    user,
+    blocked_at,
+});
+
+account!(AgentEndpoint {
+    url,
+    region,
+    priority,
+});
+
+account!(CapabilitySchema {
+    capability_id,
+    input_schema,
+    output_schema,
+});
+
+account!(AgentVersion {
+    agent_id,
+    major,
+    minor,
+    patch,
+    ipfs_hash,
+    changelog_hash,
+    publisher,
+    published_at,
+});
+
+account!(ServiceOffering {
+    agent_id,
+    offering_id,
+    price,
+    turnaround_secs,
+    is_active,
+    created_at,
+    updated_at,
+    name,
+    description,
+});
+
+account!(CapabilityPrice {
+    agent_id,
+    price,
+    updated_at,
+    capability_id,
+});
+
+account!(ReferralCode {
+    referrer,
+    referrals,
+    earned,
+    created_at,
+    code,
+});
+
+account!(Bundle {
+    curator,
+    bundle_id,
+    price,
+    discount_bps,
+    is_active,
+    created_at,
+    updated_at,
+    agents,
+    name,
+});
+
+account!(SubscriptionTier {
+    agent_id,
+    tier_id,
+    monthly_price,
+    requests_per_month,
+    is_active,
+    features_hash,
+    created_at,
+    retired_at,
+    name,
+});
+
+account!(AccessGrant {
+    agent_id,
+    user,
+    subscription_tier,
+    issued_at,
+    expires_at,
+    remaining_quota,
+    is_revoked,
+});
+
+account!(AgentAttestation {
+    agent_id,
+    issuer,
+    schema,
+    data_hash,
+    issued_at,
+    is_revoked,
+    expires_at,
+    revoked_at,
+});
+
+account!(CoCreatorSplit {
+    agent_id,
+    co_creators,
+});
+
+account!(CoCreator { key, bps });
+
+account!(AgentLineage {
+    agent_id,
+    parent_agent,
+    royalty_bps,
+});
+
+account!(AcceptedMint {
+    mint,
+    decimals,
+    added_at,
+});
+
+account!(Capability {
+    id,
+    name,
+    description,
+    is_deprecated,
+    created_at,
+    deprecated_at,
+});
+
+account!(CapabilityIndex {
+    capability,
+    agent_id,
+    created_at,
+});
+
+account!(LanguageIndex {
+    language,
+    agent_id,
+    created_at,
+});
+
+account!(NameClaim {
+    agent_id,
+    claimed_at,
+});
+
+account!(EndpointChallenge {
+    agent_id,
+    nonce,
+    endpoints,
+    issued_at,
+});
+
+account!(RegistryConfig {
+    listing_stake,
+    slash_authority,
+    featured_price_per_day,
+    verifier,
+    heartbeat_window,
+    registration_fee,
+    agent_count,
+});
+
+account!(AgentIndex { index, agent_id });
+
+account!(FeaturedIndex { entries });
+
+account!(FeaturedEntry {
+    agent_id,
+    featured_until,
+});
+
+account!(AgentCapabilities {
+    agent_id,
+    capabilities,
+});
+
+account!(AgentSale {
+    agent_id,
+    seller,
+    nft_mint,
+    price,
+    listed_at,
+});
+
+account!(OperatorKey {
+    creator,
+    operator,
+    next_nonce,
+    registered_at,
+});
+
+account!(HealthChecker { checker, added_at });
+
+account!(HealthLog {
+    agent_id,
+    cursor,
+    checks,
+});
+
+account!(HealthCheck {
+    checker,
+    healthy,
+    latency_ms,
+    checked_at,
+});
+
+account!(ListingStake {
+    agent_id,
+    amount,
+    staked_at,
+    total_slashed,
+});
+
+account!(StakeSlash {
+    service_request,
+    agent_id,
+    amount,
+    slashed_at,
+});
+
+account!(ServiceRequest {
+    request_id,
+    agent_id,
+    user,
+    amount,
+    status,
+    request_data,
+    result_data,
+    created_at,
+    completed_at,
+    escrow_account,
+    agent_version,
+    service_offering,
+    bundle,
+    capability_price,
+    payment_mint,
+    deadline,
+    panel_slot,
+});
+
+account!(AgentRequestCount {
+    agent_id,
+    open_requests,
+    max_open_requests,
+    paused_until,
+    allowlist_only,
+    trial_requests,
+    retired,
+    successor,
+    frozen,
+});
+
+account!(ArbitrationConfig {
+    arbiter,
+    disputes_resolved,
+});
+
+account!(JurorSeat {
+    juror,
+    stake,
+    active_panels,
+});
+
+account!(JurorPool { jurors });
+
+account!(DisputePanel {
+    service_request,
+    payer,
+    jurors,
+    votes,
+    voting_ends_at,
+});
+
+account!(TrialCounter {
+    agent_id,
+    user,
+    used,
+});
+
+account!(Rating {
+    rating_id,
+    agent_id,
+    user,
+    request_id,
+    stars,
+    quality,
+    speed,
+    value,
+    review_text,
+    created_at,
+    is_reported,
+    report_reason,
+    is_moderated,
+    is_valid,
+    admin_note,
+});
+
+account!(AgentReputationProfile {
+    agent_id,
+    total_ratings,
+    average_rating,
+    quality_score,
+    speed_score,
+    value_score,
+    created_at,
+    last_rating_at,
+});
+
+account!(ReputationConfig { admin });
+
+account!(RoyaltyConfig {
+    creator_share,
+    platform_share,
+    treasury_share,
+    platform_wallet,
+    treasury_wallet,
+    admin,
+    total_distributed,
+    total_transactions,
+    created_at,
+    updated_at,
+    is_paused,
+});
+
+account!(DistributionRecord {
+    distribution_id,
+    creator,
+    total_amount,
+    creator_amount,
+    platform_amount,
+    treasury_amount,
+    timestamp,
+});
+
+account!(GuardianConfig {
+    authority,
+    guardians,
+    is_paused,
+    last_changed_at,
+    last_changed_by,
+});
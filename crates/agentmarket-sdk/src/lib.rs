@@ -0,0 +1,51 @@
+//! Rust client SDK for the AgentMarket programs.
+//!
+//! Provides typed instruction builders ([`instructions`]), PDA derivation
+//! ([`pda`]), account and event decoding ([`accounts`], [`events`]), JSON
+//! rendering of both ([`json`]), a small async JSON-RPC client ([`rpc`]),
+//! and pluggable local, hardware, and remote signers ([`signer`]) so
+//! services can talk to the programs without Anchor's TypeScript client or
+//! hand-rolled instruction data.
+
+pub mod accounts;
+pub mod error;
+pub mod events;
+pub mod instructions;
+pub mod json;
+pub mod pda;
+pub mod rpc;
+pub mod signer;
+
+pub use anchor_lang::prelude::Pubkey;
+pub use anchor_lang::solana_program::instruction::Instruction;
+pub use error::SdkError;
+pub use rpc::RpcClient;
+pub use signer::TransactionSigner;
+
+/// Agent registry program
+pub const REGISTRY_PROGRAM_ID: Pubkey = agent_registry::ID;
+/// Marketplace escrow program
+pub const ESCROW_PROGRAM_ID: Pubkey = marketplace_escrow::ID;
+/// Reputation program
+pub const REPUTATION_PROGRAM_ID: Pubkey = reputation_system::ID;
+/// Royalty splitter program
+pub const ROYALTY_PROGRAM_ID: Pubkey = royalty_splitter::ID;
+/// Protocol guardian (global pause) program
+pub const GUARDIAN_PROGRAM_ID: Pubkey = protocol_guardian::ID;
+/// Protocol analytics program
+pub const ANALYTICS_PROGRAM_ID: Pubkey = protocol_analytics::ID;
+/// SLA oracle program publishing each agent's recommended request timeout
+pub const SLA_ORACLE_PROGRAM_ID: Pubkey = marketplace_escrow::SLA_ORACLE_ID;
+/// Metaplex token metadata program used for agent NFTs
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey =
+    anchor_lang::pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+/// Metaplex Bubblegum program used for compressed agent NFTs
+pub const BUBBLEGUM_PROGRAM_ID: Pubkey = agent_registry::BUBBLEGUM_PROGRAM_ID;
+/// SPL account compression program owning the compressed agent tree
+pub const ACCOUNT_COMPRESSION_PROGRAM_ID: Pubkey = agent_registry::ACCOUNT_COMPRESSION_PROGRAM_ID;
+/// SPL noop program Bubblegum logs through
+pub const NOOP_PROGRAM_ID: Pubkey = agent_registry::NOOP_PROGRAM_ID;
+/// Metaplex token auth rules program evaluating pNFT transfers
+pub const TOKEN_AUTH_RULES_PROGRAM_ID: Pubkey = agent_registry::TOKEN_AUTH_RULES_PROGRAM_ID;
+/// Rule set programmable agent NFTs are bound to
+pub const ROYALTY_RULE_SET: Pubkey = agent_registry::ROYALTY_RULE_SET;
@@ -0,0 +1,434 @@
+//! PDA derivation helpers mirroring the seeds used by the programs.
+
+use anchor_lang::prelude::Pubkey;
+
+use crate::{
+    ANALYTICS_PROGRAM_ID, BUBBLEGUM_PROGRAM_ID, ESCROW_PROGRAM_ID, GUARDIAN_PROGRAM_ID,
+    REGISTRY_PROGRAM_ID, REPUTATION_PROGRAM_ID, ROYALTY_PROGRAM_ID, SLA_ORACLE_PROGRAM_ID,
+    TOKEN_METADATA_PROGRAM_ID,
+};
+
+/// Agent profile owned by a creator: `["agent", creator]`
+pub fn agent_profile(creator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"agent", creator.as_ref()], &REGISTRY_PROGRAM_ID)
+}
+
+/// Signer and update authority of the platform agent collection: `["collection_authority"]`
+pub fn collection_authority() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"collection_authority"], &REGISTRY_PROGRAM_ID)
+}
+
+/// Update authority of agent NFT metadata once migrated: `["metadata_authority"]`
+pub fn metadata_authority() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"metadata_authority"], &REGISTRY_PROGRAM_ID)
+}
+
+/// Mint of the platform agent collection NFT: `["collection_mint"]`
+pub fn collection_mint() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"collection_mint"], &REGISTRY_PROGRAM_ID)
+}
+
+/// Registry-wide settings, including the listing stake: `["registry_config"]`
+pub fn registry_config() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"registry_config"], &REGISTRY_PROGRAM_ID)
+}
+
+/// System-owned vault collecting registration fees until they are swept: `["fee_vault"]`
+pub fn fee_vault() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"fee_vault"], &REGISTRY_PROGRAM_ID)
+}
+
+/// Referral code new agents can register with: `["referral_code", code]`
+pub fn referral_code(code: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"referral_code", code.as_bytes()], &REGISTRY_PROGRAM_ID)
+}
+
+/// System-owned vault accruing a referral code's share of registration fees:
+/// `["referral_vault", referral_code]`
+pub fn referral_vault(referral_code: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"referral_vault", referral_code.as_ref()],
+        &REGISTRY_PROGRAM_ID,
+    )
+}
+
+/// Agents with a paid promotion: `["featured_index"]`
+pub fn featured_index() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"featured_index"], &REGISTRY_PROGRAM_ID)
+}
+
+/// Capabilities an agent lists past its profile's limit:
+/// `["agent_capabilities", agent_id]`
+pub fn agent_capabilities(agent_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"agent_capabilities", agent_id.as_ref()],
+        &REGISTRY_PROGRAM_ID,
+    )
+}
+
+/// Rolling daily analytics of an agent: `["agent_analytics", agent_id]`
+pub fn agent_analytics(agent_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"agent_analytics", agent_id.as_ref()],
+        &ANALYTICS_PROGRAM_ID,
+    )
+}
+
+/// SLA oracle record of an agent, setting request deadlines: `["agent_sla", agent_id]`
+pub fn agent_sla(agent_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"agent_sla", agent_id.as_ref()], &SLA_ORACLE_PROGRAM_ID)
+}
+
+/// An agent's sale listing, which escrows its NFT: `["agent_sale", agent_id]`
+pub fn agent_sale(agent_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"agent_sale", agent_id.as_ref()], &REGISTRY_PROGRAM_ID)
+}
+
+/// Operational key allowed to sign `creator`'s agent manifests:
+/// `["operator", creator, operator]`
+pub fn operator_key(creator: &Pubkey, operator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"operator", creator.as_ref(), operator.as_ref()],
+        &REGISTRY_PROGRAM_ID,
+    )
+}
+
+/// Key allowed to post endpoint health checks: `["health_checker", checker]`
+pub fn health_checker(checker: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"health_checker", checker.as_ref()], &REGISTRY_PROGRAM_ID)
+}
+
+/// Ring buffer of an agent's latest health checks: `["health_log", agent_id]`
+pub fn health_log(agent_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"health_log", agent_id.as_ref()], &REGISTRY_PROGRAM_ID)
+}
+
+/// SPL token agents may price in: `["accepted_mint", mint]`
+pub fn accepted_mint(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"accepted_mint", mint.as_ref()], &REGISTRY_PROGRAM_ID)
+}
+
+/// Stake an agent locked at registration: `["listing_stake", agent_id]`
+pub fn listing_stake(agent_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"listing_stake", agent_id.as_ref()], &REGISTRY_PROGRAM_ID)
+}
+
+/// Entry `index` of the sequential agent list: `["agent_index", index]`
+pub fn agent_index(index: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"agent_index", &index.to_le_bytes()],
+        &REGISTRY_PROGRAM_ID,
+    )
+}
+
+/// Record of a stake slash over a disputed request:
+/// `["stake_slash", service_request, created_at]`, with the request's
+/// creation time in little-endian bytes
+pub fn stake_slash(service_request: &Pubkey, created_at: i64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"stake_slash",
+            service_request.as_ref(),
+            &created_at.to_le_bytes(),
+        ],
+        &REGISTRY_PROGRAM_ID,
+    )
+}
+
+/// Entry in the capability taxonomy: `["capability", id]`
+pub fn capability(id: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"capability", id.as_bytes()], &REGISTRY_PROGRAM_ID)
+}
+
+/// Discovery index entry for an agent's capability: `["capability_index", id, agent_id]`
+pub fn capability_index(id: &str, agent_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"capability_index", id.as_bytes(), agent_id.as_ref()],
+        &REGISTRY_PROGRAM_ID,
+    )
+}
+
+/// Claim on an agent's normalized name: `["name", agent_name_seed(name)]`
+pub fn name_claim(name: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"name", &agent_registry::agent_name_seed(name)],
+        &REGISTRY_PROGRAM_ID,
+    )
+}
+
+/// Discovery index entry for a language an agent serves: `["language_index", language, agent_id]`
+pub fn language_index(language: &str, agent_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"language_index", language.as_bytes(), agent_id.as_ref()],
+        &REGISTRY_PROGRAM_ID,
+    )
+}
+
+/// Outstanding endpoint challenge of an agent: `["endpoint_challenge", agent_id]`
+pub fn endpoint_challenge(agent_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"endpoint_challenge", agent_id.as_ref()],
+        &REGISTRY_PROGRAM_ID,
+    )
+}
+
+/// An issuer's attestation about an agent: `["agent_attestation", agent_id, issuer, schema]`
+pub fn agent_attestation(agent_id: &Pubkey, issuer: &Pubkey, schema: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"agent_attestation",
+            agent_id.as_ref(),
+            issuer.as_ref(),
+            schema.as_ref(),
+        ],
+        &REGISTRY_PROGRAM_ID,
+    )
+}
+
+/// Co-creators an agent's creator share is split with: `["co_creators", agent_id]`
+pub fn co_creator_split(agent_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"co_creators", agent_id.as_ref()], &REGISTRY_PROGRAM_ID)
+}
+
+/// Parent and lineage royalty of a forked agent: `["lineage", agent_id]`
+pub fn agent_lineage(agent_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"lineage", agent_id.as_ref()], &REGISTRY_PROGRAM_ID)
+}
+
+/// Price override of one of an agent's capabilities:
+/// `["capability_price", agent_id, capability_id]`
+pub fn capability_price(agent_id: &Pubkey, capability_id: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"capability_price",
+            agent_id.as_ref(),
+            capability_id.as_bytes(),
+        ],
+        &REGISTRY_PROGRAM_ID,
+    )
+}
+
+/// One service in an agent's catalog: `["service_offering", agent_id, offering_id]`
+pub fn service_offering(agent_id: &Pubkey, offering_id: u16) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"service_offering",
+            agent_id.as_ref(),
+            &offering_id.to_le_bytes(),
+        ],
+        &REGISTRY_PROGRAM_ID,
+    )
+}
+
+/// A user's paid access to an agent: `["access_grant", agent_id, user]`
+pub fn access_grant(agent_id: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"access_grant", agent_id.as_ref(), user.as_ref()],
+        &REGISTRY_PROGRAM_ID,
+    )
+}
+
+/// A user allowed to hire an allowlist-only agent: `["allowlist", agent_id, user]`
+pub fn allowlist_entry(agent_id: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"allowlist", agent_id.as_ref(), user.as_ref()],
+        &REGISTRY_PROGRAM_ID,
+    )
+}
+
+/// A user the agent refuses to serve: `["blocklist", agent_id, user]`
+pub fn block_entry(agent_id: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"blocklist", agent_id.as_ref(), user.as_ref()],
+        &REGISTRY_PROGRAM_ID,
+    )
+}
+
+/// Several of a curator's agents sold together: `["bundle", curator, bundle_id]`
+pub fn bundle(curator: &Pubkey, bundle_id: u16) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"bundle", curator.as_ref(), &bundle_id.to_le_bytes()],
+        &REGISTRY_PROGRAM_ID,
+    )
+}
+
+/// One plan of a subscription-priced agent: `["subscription_tier", agent_id, tier_id]`
+pub fn subscription_tier(agent_id: &Pubkey, tier_id: u16) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"subscription_tier",
+            agent_id.as_ref(),
+            &tier_id.to_le_bytes(),
+        ],
+        &REGISTRY_PROGRAM_ID,
+    )
+}
+
+/// A published release of an agent: `["agent_version", agent_id, major, minor, patch]`
+pub fn agent_version(agent_id: &Pubkey, major: u16, minor: u16, patch: u16) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"agent_version",
+            agent_id.as_ref(),
+            &major.to_le_bytes(),
+            &minor.to_le_bytes(),
+            &patch.to_le_bytes(),
+        ],
+        &REGISTRY_PROGRAM_ID,
+    )
+}
+
+/// Service request opened by a user against an agent: `["request", user, agent_id]`
+pub fn service_request(user: &Pubkey, agent_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"request", user.as_ref(), agent_id.as_ref()],
+        &ESCROW_PROGRAM_ID,
+    )
+}
+
+/// Escrow vault holding a request's payment: `["escrow", service_request]`
+pub fn escrow_account(service_request: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"escrow", service_request.as_ref()], &ESCROW_PROGRAM_ID)
+}
+
+/// Count of an agent's requests still holding escrowed funds: `["agent_requests", agent_id]`
+pub fn agent_requests(agent_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"agent_requests", agent_id.as_ref()], &ESCROW_PROGRAM_ID)
+}
+
+/// Free requests `user` has made of an agent: `["trial", agent_id, user]`
+pub fn trial_counter(agent_id: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"trial", agent_id.as_ref(), user.as_ref()],
+        &ESCROW_PROGRAM_ID,
+    )
+}
+
+/// Registry signer that pushes agent capacity and pauses into the escrow: `["capacity_hook"]`
+pub fn capacity_hook() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"capacity_hook"], &REGISTRY_PROGRAM_ID)
+}
+
+/// Who rules on disputed requests: `["arbitration_config"]`
+pub fn arbitration_config() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"arbitration_config"], &ESCROW_PROGRAM_ID)
+}
+
+/// Stakers dispute panels are drawn from: `["juror_pool"]`
+pub fn juror_pool() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"juror_pool"], &ESCROW_PROGRAM_ID)
+}
+
+/// Jurors drawn for a disputed request and their votes: `["dispute_panel", service_request]`
+pub fn dispute_panel(service_request: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"dispute_panel", service_request.as_ref()],
+        &ESCROW_PROGRAM_ID,
+    )
+}
+
+/// Escrow signer that credits payouts to registry profiles: `["payout_hook"]`
+pub fn payout_hook() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"payout_hook"], &ESCROW_PROGRAM_ID)
+}
+
+/// Reputation signer that pushes scores into the registry: `["reputation_hook"]`
+pub fn reputation_hook() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"reputation_hook"], &REPUTATION_PROGRAM_ID)
+}
+
+/// Reputation aggregate for an agent: `["agent_reputation", agent_id]`
+pub fn agent_reputation(agent_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"agent_reputation", agent_id.as_ref()],
+        &REPUTATION_PROGRAM_ID,
+    )
+}
+
+/// Rating left by a user for a request: `["rating", user, request_id]`
+pub fn rating(user: &Pubkey, request_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"rating", user.as_ref(), request_id.as_ref()],
+        &REPUTATION_PROGRAM_ID,
+    )
+}
+
+/// Reputation moderation config: `["reputation_config"]`
+pub fn reputation_config() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"reputation_config"], &REPUTATION_PROGRAM_ID)
+}
+
+/// Royalty split config: `["royalty_config"]`
+pub fn royalty_config() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"royalty_config"], &ROYALTY_PROGRAM_ID)
+}
+
+/// Distribution record for the n-th royalty distribution: `["distribution", n]`
+pub fn distribution_record(transaction_index: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"distribution", transaction_index.to_le_bytes().as_ref()],
+        &ROYALTY_PROGRAM_ID,
+    )
+}
+
+/// Protocol-wide pause switch read by every program: `["guardian_config"]`
+pub fn guardian_config() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"guardian_config"], &GUARDIAN_PROGRAM_ID)
+}
+
+/// Metaplex metadata account for an NFT mint
+pub fn token_metadata(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            mint.as_ref(),
+        ],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )
+}
+
+/// Metaplex master edition account for an NFT mint
+pub fn master_edition(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            mint.as_ref(),
+            b"edition",
+        ],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )
+}
+
+/// Metaplex token record of a pNFT token account
+pub fn token_record(mint: &Pubkey, token_account: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            mint.as_ref(),
+            b"token_record",
+            token_account.as_ref(),
+        ],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )
+}
+
+/// Bubblegum config of a compressed NFT tree: `[merkle_tree]`
+pub fn tree_config(merkle_tree: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[merkle_tree.as_ref()], &BUBBLEGUM_PROGRAM_ID)
+}
+
+/// Bubblegum's signer for collection CPIs: `["collection_cpi"]`
+pub fn bubblegum_signer() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"collection_cpi"], &BUBBLEGUM_PROGRAM_ID)
+}
+
+/// Asset id of the compressed NFT at `leaf_index`: `["asset", merkle_tree, leaf_index]`
+pub fn compressed_asset(merkle_tree: &Pubkey, leaf_index: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"asset", merkle_tree.as_ref(), &leaf_index.to_le_bytes()],
+        &BUBBLEGUM_PROGRAM_ID,
+    )
+}
@@ -0,0 +1,485 @@
+//! Minimal async JSON-RPC client for fetching program accounts and sending transactions.
+
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_commitment_config::CommitmentLevel;
+use solana_hash::Hash;
+use solana_signature::Signature;
+use solana_signer::signers::Signers;
+use solana_transaction::Transaction;
+
+use crate::accounts::decode;
+use crate::error::SdkError;
+use crate::signer::{sign_transaction, TransactionSigner};
+
+/// A confirmed signature touching an address, newest first
+#[derive(Debug, Clone)]
+pub struct SignatureInfo {
+    pub signature: Signature,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub failed: bool,
+}
+
+/// Log messages emitted by a confirmed transaction
+#[derive(Debug, Clone)]
+pub struct TransactionLogs {
+    pub signature: Signature,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub failed: bool,
+    pub logs: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<Value>,
+    error: Option<RpcErrorBody>,
+}
+
+#[derive(Deserialize)]
+struct RpcErrorBody {
+    code: i64,
+    message: String,
+}
+
+/// JSON-RPC client bound to one cluster endpoint
+#[derive(Clone)]
+pub struct RpcClient {
+    http: reqwest::Client,
+    url: String,
+    commitment: CommitmentLevel,
+}
+
+impl RpcClient {
+    /// Client using `confirmed` commitment
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::new_with_commitment(url, CommitmentLevel::Confirmed)
+    }
+
+    pub fn new_with_commitment(url: impl Into<String>, commitment: CommitmentLevel) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url: url.into(),
+            commitment,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn commitment(&self) -> &'static str {
+        match self.commitment {
+            CommitmentLevel::Processed => "processed",
+            CommitmentLevel::Confirmed => "confirmed",
+            CommitmentLevel::Finalized => "finalized",
+        }
+    }
+
+    /// Issue a raw JSON-RPC call and return its `result`
+    pub async fn call(&self, method: &str, params: Value) -> Result<Value, SdkError> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+
+        let response: RpcResponse = self
+            .http
+            .post(&self.url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if let Some(error) = response.error {
+            return Err(SdkError::Rpc {
+                code: error.code,
+                message: error.message,
+            });
+        }
+
+        Ok(response.result.unwrap_or(Value::Null))
+    }
+
+    /// Raw data of an account, or `None` if it does not exist
+    pub async fn get_account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>, SdkError> {
+        let result = self
+            .call(
+                "getAccountInfo",
+                json!([address.to_string(), {"encoding": "base64", "commitment": self.commitment()}]),
+            )
+            .await?;
+
+        match result.get("value") {
+            Some(Value::Null) | None => Ok(None),
+            Some(value) => decode_data(value).map(Some),
+        }
+    }
+
+    /// Lamport balance of an account
+    pub async fn get_balance(&self, address: &Pubkey) -> Result<u64, SdkError> {
+        let result = self
+            .call(
+                "getBalance",
+                json!([address.to_string(), {"commitment": self.commitment()}]),
+            )
+            .await?;
+
+        result
+            .get("value")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| SdkError::InvalidResponse("getBalance".into()))
+    }
+
+    /// Fetch and decode an Anchor account
+    pub async fn fetch<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<T, SdkError> {
+        self.fetch_optional(address)
+            .await?
+            .ok_or(SdkError::AccountNotFound(*address))
+    }
+
+    /// Fetch and decode an Anchor account that may not exist yet
+    pub async fn fetch_optional<T: AccountDeserialize>(
+        &self,
+        address: &Pubkey,
+    ) -> Result<Option<T>, SdkError> {
+        match self.get_account_data(address).await? {
+            Some(data) => decode(&data).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// All accounts of type `T` owned by `program_id`, filtered by discriminator
+    pub async fn fetch_all<T: AccountDeserialize + Discriminator>(
+        &self,
+        program_id: &Pubkey,
+    ) -> Result<Vec<(Pubkey, T)>, SdkError> {
+        self.fetch_all_matching(program_id, &[]).await
+    }
+
+    /// Accounts of type `T` whose data also matches each `(offset, bytes)` filter
+    ///
+    /// Offsets count from the start of the account data, discriminator included.
+    pub async fn fetch_all_matching<T: AccountDeserialize + Discriminator>(
+        &self,
+        program_id: &Pubkey,
+        filters: &[(usize, &[u8])],
+    ) -> Result<Vec<(Pubkey, T)>, SdkError> {
+        let filters: Vec<Value> = std::iter::once((0, T::DISCRIMINATOR))
+            .chain(filters.iter().copied())
+            .map(|(offset, bytes)| {
+                json!({
+                    "memcmp": {
+                        "offset": offset,
+                        "bytes": BASE64.encode(bytes),
+                        "encoding": "base64",
+                    }
+                })
+            })
+            .collect();
+
+        let result = self
+            .call(
+                "getProgramAccounts",
+                json!([
+                    program_id.to_string(),
+                    {
+                        "encoding": "base64",
+                        "commitment": self.commitment(),
+                        "filters": filters,
+                    }
+                ]),
+            )
+            .await?;
+
+        let entries = result
+            .as_array()
+            .ok_or_else(|| SdkError::InvalidResponse("getProgramAccounts".into()))?;
+
+        entries
+            .iter()
+            .map(|entry| {
+                let address = entry
+                    .get("pubkey")
+                    .and_then(Value::as_str)
+                    .and_then(|s| Pubkey::from_str(s).ok())
+                    .ok_or_else(|| SdkError::InvalidResponse("pubkey".into()))?;
+                let account = entry
+                    .get("account")
+                    .ok_or_else(|| SdkError::InvalidResponse("account".into()))?;
+                let data = decode_data(account)?;
+                Ok((address, decode(&data)?))
+            })
+            .collect()
+    }
+
+    /// Addresses of every account owned by `program_id` whose data starts with `discriminator`
+    ///
+    /// Requests an empty data slice, so listing large account sets stays cheap.
+    pub async fn program_account_addresses(
+        &self,
+        program_id: &Pubkey,
+        discriminator: &[u8],
+    ) -> Result<Vec<Pubkey>, SdkError> {
+        let result = self
+            .call(
+                "getProgramAccounts",
+                json!([
+                    program_id.to_string(),
+                    {
+                        "encoding": "base64",
+                        "commitment": self.commitment(),
+                        "dataSlice": {"offset": 0, "length": 0},
+                        "filters": [{
+                            "memcmp": {
+                                "offset": 0,
+                                "bytes": BASE64.encode(discriminator),
+                                "encoding": "base64",
+                            }
+                        }],
+                    }
+                ]),
+            )
+            .await?;
+
+        result
+            .as_array()
+            .ok_or_else(|| SdkError::InvalidResponse("getProgramAccounts".into()))?
+            .iter()
+            .map(|entry| {
+                entry
+                    .get("pubkey")
+                    .and_then(Value::as_str)
+                    .and_then(|s| Pubkey::from_str(s).ok())
+                    .ok_or_else(|| SdkError::InvalidResponse("pubkey".into()))
+            })
+            .collect()
+    }
+
+    /// Raw data of up to 100 accounts in one request, `None` where an account does not exist
+    pub async fn get_multiple_account_data(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<Option<Vec<u8>>>, SdkError> {
+        let keys: Vec<String> = addresses.iter().map(Pubkey::to_string).collect();
+        let result = self
+            .call(
+                "getMultipleAccounts",
+                json!([keys, {"encoding": "base64", "commitment": self.commitment()}]),
+            )
+            .await?;
+
+        result
+            .get("value")
+            .and_then(Value::as_array)
+            .ok_or_else(|| SdkError::InvalidResponse("getMultipleAccounts".into()))?
+            .iter()
+            .map(|account| match account {
+                Value::Null => Ok(None),
+                account => decode_data(account).map(Some),
+            })
+            .collect()
+    }
+
+    /// Lamport balances of up to 100 accounts in one request, zero where an account does not exist
+    pub async fn get_multiple_balances(&self, addresses: &[Pubkey]) -> Result<Vec<u64>, SdkError> {
+        let keys: Vec<String> = addresses.iter().map(Pubkey::to_string).collect();
+        let result = self
+            .call(
+                "getMultipleAccounts",
+                json!([keys, {
+                    "encoding": "base64",
+                    "commitment": self.commitment(),
+                    "dataSlice": {"offset": 0, "length": 0},
+                }]),
+            )
+            .await?;
+
+        result
+            .get("value")
+            .and_then(Value::as_array)
+            .ok_or_else(|| SdkError::InvalidResponse("getMultipleAccounts".into()))?
+            .iter()
+            .map(|account| match account {
+                Value::Null => Ok(0),
+                account => account
+                    .get("lamports")
+                    .and_then(Value::as_u64)
+                    .ok_or_else(|| SdkError::InvalidResponse("lamports".into())),
+            })
+            .collect()
+    }
+
+    pub async fn get_latest_blockhash(&self) -> Result<Hash, SdkError> {
+        let result = self
+            .call(
+                "getLatestBlockhash",
+                json!([{"commitment": self.commitment()}]),
+            )
+            .await?;
+
+        result
+            .pointer("/value/blockhash")
+            .and_then(Value::as_str)
+            .and_then(|s| Hash::from_str(s).ok())
+            .ok_or_else(|| SdkError::InvalidResponse("getLatestBlockhash".into()))
+    }
+
+    /// Submit a signed transaction and return its signature
+    pub async fn send_transaction(&self, transaction: &Transaction) -> Result<Signature, SdkError> {
+        let wire =
+            bincode::serialize(transaction).map_err(|e| SdkError::Serialize(e.to_string()))?;
+        let result = self
+            .call(
+                "sendTransaction",
+                json!([BASE64.encode(wire), {"encoding": "base64", "preflightCommitment": self.commitment()}]),
+            )
+            .await?;
+
+        result
+            .as_str()
+            .and_then(|s| Signature::from_str(s).ok())
+            .ok_or_else(|| SdkError::InvalidResponse("sendTransaction".into()))
+    }
+
+    /// Sign `instructions` with a fresh blockhash and submit them, `payer` paying fees
+    pub async fn send_instructions<S: Signers + ?Sized>(
+        &self,
+        instructions: &[Instruction],
+        payer: &Pubkey,
+        signers: &S,
+    ) -> Result<Signature, SdkError> {
+        let blockhash = self.get_latest_blockhash().await?;
+        let transaction =
+            Transaction::new_signed_with_payer(instructions, Some(payer), signers, blockhash);
+        self.send_transaction(&transaction).await
+    }
+
+    /// Like [`send_instructions`](Self::send_instructions), with async signers and a separate fee payer
+    ///
+    /// `fee_payer` may be a hot key that only holds SOL for fees while the
+    /// authorities the instructions need sign remotely or on a hardware wallet.
+    pub async fn send_instructions_with(
+        &self,
+        instructions: &[Instruction],
+        fee_payer: &dyn TransactionSigner,
+        signers: &[&dyn TransactionSigner],
+    ) -> Result<Signature, SdkError> {
+        let blockhash = self.get_latest_blockhash().await?;
+        let transaction = sign_transaction(instructions, fee_payer, signers, blockhash).await?;
+        self.send_transaction(&transaction).await
+    }
+
+    /// Signatures for an address, newest first, paging backwards from `before`
+    pub async fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before: Option<&Signature>,
+        limit: usize,
+    ) -> Result<Vec<SignatureInfo>, SdkError> {
+        let mut config = json!({"limit": limit, "commitment": self.commitment()});
+        if let Some(before) = before {
+            config["before"] = json!(before.to_string());
+        }
+
+        let result = self
+            .call(
+                "getSignaturesForAddress",
+                json!([address.to_string(), config]),
+            )
+            .await?;
+
+        let entries = result
+            .as_array()
+            .ok_or_else(|| SdkError::InvalidResponse("getSignaturesForAddress".into()))?;
+
+        entries
+            .iter()
+            .map(|entry| {
+                let signature = entry
+                    .get("signature")
+                    .and_then(Value::as_str)
+                    .and_then(|s| Signature::from_str(s).ok())
+                    .ok_or_else(|| SdkError::InvalidResponse("signature".into()))?;
+                Ok(SignatureInfo {
+                    signature,
+                    slot: entry
+                        .get("slot")
+                        .and_then(Value::as_u64)
+                        .unwrap_or_default(),
+                    block_time: entry.get("blockTime").and_then(Value::as_i64),
+                    failed: !entry.get("err").is_none_or(Value::is_null),
+                })
+            })
+            .collect()
+    }
+
+    /// Log messages of a confirmed transaction, or `None` if it is unknown
+    pub async fn get_transaction_logs(
+        &self,
+        signature: &Signature,
+    ) -> Result<Option<TransactionLogs>, SdkError> {
+        let result = self
+            .call(
+                "getTransaction",
+                json!([
+                    signature.to_string(),
+                    {"encoding": "json", "commitment": self.commitment(), "maxSupportedTransactionVersion": 0}
+                ]),
+            )
+            .await?;
+
+        if result.is_null() {
+            return Ok(None);
+        }
+
+        let meta = result
+            .get("meta")
+            .ok_or_else(|| SdkError::InvalidResponse("getTransaction meta".into()))?;
+        let logs = meta
+            .get("logMessages")
+            .and_then(Value::as_array)
+            .map(|logs| {
+                logs.iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_owned)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Some(TransactionLogs {
+            signature: *signature,
+            slot: result
+                .get("slot")
+                .and_then(Value::as_u64)
+                .unwrap_or_default(),
+            block_time: result.get("blockTime").and_then(Value::as_i64),
+            failed: !meta.get("err").is_none_or(Value::is_null),
+            logs,
+        }))
+    }
+}
+
+// Helper function to pull base64 account data out of an RPC account object
+fn decode_data(account: &Value) -> Result<Vec<u8>, SdkError> {
+    let encoded = account
+        .pointer("/data/0")
+        .and_then(Value::as_str)
+        .ok_or_else(|| SdkError::InvalidResponse("account data".into()))?;
+
+    BASE64
+        .decode(encoded)
+        .map_err(|e| SdkError::InvalidResponse(e.to_string()))
+}
@@ -0,0 +1,174 @@
+//! Pluggable transaction signers: local keypairs, hardware wallets, and remote signing services.
+//!
+//! [`TransactionSigner`] signs a serialized transaction message and may do so
+//! asynchronously, so the key can live outside the process:
+//!
+//! - Anything implementing `solana_signer::Signer` is a `TransactionSigner`:
+//!   keypairs, presigners, and `solana-remote-wallet`'s Ledger keypairs, which
+//!   applications link themselves so the SDK carries no USB dependencies.
+//! - [`RemoteSigner`] asks an HTTP signing service, such as a sidecar in
+//!   front of a KMS or HSM, to sign and checks the signature it returns.
+//!
+//! [`RpcClient::send_instructions_with`](crate::RpcClient::send_instructions_with)
+//! takes the fee payer separately from the other signers, so a service can
+//! act with an operator key while a different, funded key pays the fees.
+
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::json;
+use solana_hash::Hash;
+use solana_signature::Signature;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use crate::error::SdkError;
+
+/// Future returned by [`TransactionSigner::sign_message`]
+pub type SignFuture<'a> = Pin<Box<dyn Future<Output = Result<Signature, SdkError>> + Send + 'a>>;
+
+/// A key that can sign transaction messages, possibly out of process
+pub trait TransactionSigner: Send + Sync {
+    fn pubkey(&self) -> Pubkey;
+
+    /// Sign the serialized transaction message
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> SignFuture<'a>;
+}
+
+impl<T: Signer + Send + Sync> TransactionSigner for T {
+    fn pubkey(&self) -> Pubkey {
+        Signer::pubkey(self)
+    }
+
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> SignFuture<'a> {
+        let signed = self
+            .try_sign_message(message)
+            .map_err(|e| SdkError::Signing(e.to_string()));
+        Box::pin(std::future::ready(signed))
+    }
+}
+
+/// Signer backed by an HTTP signing service
+///
+/// Each message is POSTed to `url` as
+/// `{"pubkey": "<base58>", "message": "<base64>"}` and the service answers
+/// `{"signature": "<base58>"}`. With a token set, requests carry it as a
+/// bearer `Authorization` header.
+pub struct RemoteSigner {
+    http: reqwest::Client,
+    url: String,
+    pubkey: Pubkey,
+    token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RemoteSignature {
+    signature: String,
+}
+
+impl RemoteSigner {
+    pub fn new(url: impl Into<String>, pubkey: Pubkey) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url: url.into(),
+            pubkey,
+            token: None,
+        }
+    }
+
+    /// Authenticate requests with a bearer token
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+}
+
+impl TransactionSigner for RemoteSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> SignFuture<'a> {
+        Box::pin(async move {
+            let mut request = self.http.post(&self.url).json(&json!({
+                "pubkey": self.pubkey.to_string(),
+                "message": BASE64.encode(message),
+            }));
+            if let Some(token) = &self.token {
+                request = request.bearer_auth(token);
+            }
+            let response: RemoteSignature =
+                request.send().await?.error_for_status()?.json().await?;
+
+            let signature: Signature = response.signature.parse().map_err(|_| {
+                SdkError::Signing(format!("{} returned a malformed signature", self.url))
+            })?;
+            // Never send a transaction the service signed with the wrong key or message
+            if !signature.verify(self.pubkey.as_ref(), message) {
+                return Err(SdkError::Signing(format!(
+                    "{} returned a signature that does not verify for {}",
+                    self.url, self.pubkey
+                )));
+            }
+            Ok(signature)
+        })
+    }
+}
+
+/// Load a signer from a command-line style spec
+///
+/// `remote:<pubkey>@<url>` selects a [`RemoteSigner`], authenticated with
+/// `AGENTMARKET_SIGNER_TOKEN` when that is set; anything else is read as a
+/// keypair file path.
+pub fn from_spec(spec: &str) -> Result<Box<dyn TransactionSigner>, SdkError> {
+    if let Some(remote) = spec.strip_prefix("remote:") {
+        let (pubkey, url) = remote.split_once('@').ok_or_else(|| {
+            SdkError::Signing(format!("expected remote:<pubkey>@<url>, got {spec}"))
+        })?;
+        let pubkey: Pubkey = pubkey
+            .parse()
+            .map_err(|_| SdkError::Signing(format!("invalid signer pubkey {pubkey}")))?;
+        let mut signer = RemoteSigner::new(url, pubkey);
+        if let Ok(token) = std::env::var("AGENTMARKET_SIGNER_TOKEN") {
+            signer = signer.with_token(token);
+        }
+        return Ok(Box::new(signer));
+    }
+
+    let keypair = solana_keypair::read_keypair_file(Path::new(spec))
+        .map_err(|e| SdkError::Signing(format!("failed to read keypair {spec}: {e}")))?;
+    Ok(Box::new(keypair))
+}
+
+/// Build and sign a transaction, `fee_payer` paying its fees
+///
+/// Every signer the instructions require must be `fee_payer` or among
+/// `signers`; extra signers are ignored.
+pub async fn sign_transaction(
+    instructions: &[Instruction],
+    fee_payer: &dyn TransactionSigner,
+    signers: &[&dyn TransactionSigner],
+    blockhash: Hash,
+) -> Result<Transaction, SdkError> {
+    let mut transaction = Transaction::new_with_payer(instructions, Some(&fee_payer.pubkey()));
+    transaction.message.recent_blockhash = blockhash;
+    let message = transaction.message_data();
+
+    let required = transaction.message.header.num_required_signatures as usize;
+    let mut signatures = Vec::with_capacity(required);
+    for key in &transaction.message.account_keys[..required] {
+        let signer = std::iter::once(fee_payer)
+            .chain(signers.iter().copied())
+            .find(|signer| signer.pubkey() == *key)
+            .ok_or(SdkError::MissingSigner(*key))?;
+        signatures.push(signer.sign_message(&message).await?);
+    }
+    transaction.signatures = signatures;
+    Ok(transaction)
+}
@@ -0,0 +1,183 @@
+//! Demo agent catalog: categories, names, descriptions, and pricing.
+
+use agentmarket_sdk::accounts::{AgentEndpoint, PricingModel};
+use agentmarket_sdk::instructions::registry::RegisterAgentArgs;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Lamports in one SOL
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+/// Metadata names are capped at 32 bytes and the registry prefixes "AgentMarket: "
+const MAX_NAME_LEN: usize = 19;
+
+/// One marketplace category and the vocabulary its agents are built from
+pub struct Category {
+    pub capability: &'static str,
+    extra_capabilities: &'static [&'static str],
+    nouns: &'static [&'static str],
+    pitch: &'static str,
+}
+
+pub const CATEGORIES: &[Category] = &[
+    Category {
+        capability: "code-review",
+        extra_capabilities: &["rust", "typescript", "security-audit"],
+        nouns: &["Linter", "Reviewer", "Auditor"],
+        pitch: "Reviews pull requests and flags bugs, style issues, and unsafe patterns",
+    },
+    Category {
+        capability: "translation",
+        extra_capabilities: &["spanish", "japanese", "localization"],
+        nouns: &["Lingo", "Polyglot", "Babel"],
+        pitch: "Translates documents and UI strings while keeping tone and formatting",
+    },
+    Category {
+        capability: "data-analysis",
+        extra_capabilities: &["sql", "charts", "forecasting"],
+        nouns: &["Analyst", "Quant", "Insight"],
+        pitch: "Turns CSVs and SQL queries into summaries, charts, and forecasts",
+    },
+    Category {
+        capability: "image-gen",
+        extra_capabilities: &["logos", "illustration", "upscaling"],
+        nouns: &["Canvas", "Pixel", "Studio"],
+        pitch: "Generates and upscales images, logos, and illustrations from prompts",
+    },
+    Category {
+        capability: "summarization",
+        extra_capabilities: &["research", "meeting-notes", "legal"],
+        nouns: &["Digest", "Brief", "Scribe"],
+        pitch: "Condenses long documents, papers, and transcripts into key points",
+    },
+    Category {
+        capability: "defi-analytics",
+        extra_capabilities: &["solana", "risk-scoring", "yield"],
+        nouns: &["Oracle", "Ledger", "Vault"],
+        pitch: "Tracks on-chain positions and scores protocol and wallet risk",
+    },
+    Category {
+        capability: "customer-support",
+        extra_capabilities: &["triage", "faq", "sentiment"],
+        nouns: &["Helper", "Concierge", "Desk"],
+        pitch: "Answers support tickets and routes escalations with sentiment tags",
+    },
+    Category {
+        capability: "content-writing",
+        extra_capabilities: &["seo", "copywriting", "social"],
+        nouns: &["Quill", "Writer", "Echo"],
+        pitch: "Drafts blog posts, product copy, and social threads on brief",
+    },
+];
+
+const ADJECTIVES: &[&str] = &[
+    "Swift", "Atlas", "Nova", "Prism", "Sage", "Bolt", "Lumen", "Orbit", "Zen", "Apex", "Cedar",
+    "Iris",
+];
+
+/// Every capability id the demo agents list, for seeding the taxonomy
+pub fn capabilities() -> impl Iterator<Item = &'static str> {
+    CATEGORIES.iter().flat_map(|category| {
+        std::iter::once(category.capability).chain(category.extra_capabilities.iter().copied())
+    })
+}
+
+/// A demo agent: the registration arguments plus how good its service is
+pub struct AgentSpec {
+    pub args: RegisterAgentArgs,
+    /// Typical star rating its users give, 1-5
+    pub quality: u8,
+}
+
+/// Build the `index`-th demo agent, cycling through categories so each gets a share
+pub fn agent(index: usize, rng: &mut impl Rng) -> AgentSpec {
+    let category = &CATEGORIES[index % CATEGORIES.len()];
+    let adjective = ADJECTIVES[(index / CATEGORIES.len()) % ADJECTIVES.len()];
+    let noun = category.nouns.choose(rng).expect("nouns are non-empty");
+    let mut name = format!("{adjective} {noun}");
+    name.truncate(MAX_NAME_LEN);
+
+    let mut capabilities = vec![category.capability.to_owned()];
+    let extra = rng.gen_range(1..=category.extra_capabilities.len());
+    capabilities.extend(
+        category
+            .extra_capabilities
+            .choose_multiple(rng, extra)
+            .map(|c| (*c).to_owned()),
+    );
+
+    let slug = name.to_lowercase().replace(' ', "-");
+    AgentSpec {
+        args: RegisterAgentArgs {
+            description: format!("{}. Demo agent seeded for development.", category.pitch),
+            capabilities,
+            pricing: pricing(rng),
+            endpoints: ["us-east", "eu-west"]
+                .into_iter()
+                .zip(0..)
+                .map(|(region, priority)| AgentEndpoint {
+                    url: format!("https://{region}.demo.agentmarket.dev/agents/{slug}-{index}"),
+                    region: region.into(),
+                    priority,
+                })
+                .collect(),
+            ipfs_hash: String::new(),
+            symbol: "AGENT".into(),
+            uri: format!("https://demo.agentmarket.dev/metadata/{slug}-{index}.json"),
+            name,
+        },
+        quality: *[3, 4, 4, 5, 5].choose(rng).expect("non-empty"),
+    }
+}
+
+/// Lamports a user escrows for one request to an agent with this pricing
+pub fn request_amount(pricing: &PricingModel) -> u64 {
+    match pricing {
+        PricingModel::PerQuery { price, .. } => *price,
+        // A month of subscription is far more than a demo wallet holds; charge a day
+        PricingModel::Subscription { monthly, .. } => (monthly / 30).max(1),
+        PricingModel::Custom { base, .. } => *base,
+        // The seeder never lists USD-pegged agents; assume SOL at $100
+        PricingModel::UsdPegged { usd_cents, .. } => usd_cents * (LAMPORTS_PER_SOL / 10_000),
+        PricingModel::Surge { base, .. } => *base,
+    }
+}
+
+/// A short made-up request payload for an agent's first capability
+pub fn request_data(capability: &str, rng: &mut impl Rng) -> String {
+    format!(
+        r#"{{"task":"{capability}","input":"demo-input-{}"}}"#,
+        rng.gen::<u32>()
+    )
+}
+
+/// Review text matching a star rating
+pub fn review(stars: u8) -> &'static str {
+    match stars {
+        5 => "Excellent result, fast turnaround. Would use again.",
+        4 => "Good output with minor issues; solid value.",
+        3 => "Usable but needed some manual fixes.",
+        2 => "Slow and only partly what I asked for.",
+        _ => "Did not deliver what was requested.",
+    }
+}
+
+// Helper function to pick a pricing model with demo-sized prices
+fn pricing(rng: &mut impl Rng) -> PricingModel {
+    let milli_sol = LAMPORTS_PER_SOL / 1000;
+    match rng.gen_range(0..10) {
+        0..=5 => PricingModel::PerQuery {
+            price: rng.gen_range(1..=10) * milli_sol,
+            payment_mint: None,
+        },
+        6..=7 => PricingModel::Subscription {
+            monthly: rng.gen_range(30..=150) * milli_sol,
+            payment_mint: None,
+        },
+        _ => PricingModel::Custom {
+            base: rng.gen_range(2..=8) * milli_sol,
+            variable: rng.gen_range(5..=25),
+            payment_mint: None,
+        },
+    }
+}
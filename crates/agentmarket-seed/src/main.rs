@@ -0,0 +1,154 @@
+//! AgentMarket seeder: populate a local or devnet deployment with demo data.
+//!
+//! Registers dozens of agents across categories, opens service requests and
+//! walks them to every status (pending, completed, approved, disputed,
+//! cancelled), rates most approved ones, and runs direct royalty
+//! distributions, so frontend and SDK work has realistic accounts and events
+//! to build against. Missing singleton configs are created with the funding
+//! keypair as admin.
+//!
+//! All generated wallets derive from `--seed`; rerunning with the same seed
+//! tops them up and skips what already exists. `--keys-dir` writes them out as
+//! Solana keypair files so developers can act as any demo creator or user.
+
+mod catalog;
+mod seeder;
+
+use std::path::{Path, PathBuf};
+
+use agentmarket_sdk::RpcClient;
+use anyhow::{Context, Result};
+use clap::Parser;
+use solana_keypair::{read_keypair_file, write_keypair_file, Keypair};
+use solana_signer::Signer;
+use tracing::info;
+use tracing_subscriber::EnvFilter;
+
+use crate::seeder::{Plan, Seeder};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "agentmarket-seed",
+    about = "Populate an AgentMarket deployment with demo data"
+)]
+struct Args {
+    /// JSON-RPC endpoint of the cluster to seed
+    #[arg(
+        long,
+        env = "AGENTMARKET_RPC_URL",
+        default_value = "http://127.0.0.1:8899"
+    )]
+    url: String,
+
+    /// Keypair that pays for everything and administers any config it creates
+    #[arg(long, env = "AGENTMARKET_KEYPAIR")]
+    keypair: Option<PathBuf>,
+
+    /// Derivation seed for every generated wallet and random choice
+    #[arg(long, default_value = "agentmarket-demo")]
+    seed: String,
+
+    /// Agents to register
+    #[arg(long, default_value_t = 24)]
+    agents: usize,
+
+    /// Users opening requests
+    #[arg(long, default_value_t = 8)]
+    users: usize,
+
+    /// Requests each user opens, each to a different agent
+    #[arg(long, default_value_t = 6)]
+    requests_per_user: usize,
+
+    /// Direct royalty distributions to run
+    #[arg(long, default_value_t = 10)]
+    distributions: usize,
+
+    /// Balance each generated wallet is topped up to
+    #[arg(long, default_value_t = 100_000_000)]
+    fund_lamports: u64,
+
+    /// Airdrop this many lamports to the funding keypair first (localnet/devnet)
+    #[arg(long)]
+    airdrop: Option<u64>,
+
+    /// Write every generated keypair here as `<role>-<index>.json`
+    #[arg(long)]
+    keys_dir: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()))
+        .init();
+
+    let args = Args::parse();
+    let funder = load_keypair(args.keypair.as_deref())?;
+    info!(funder = %funder.pubkey(), url = %args.url, seed = %args.seed, "seeding");
+
+    let mut seeder = Seeder::new(RpcClient::new(&args.url), funder, &args.seed);
+    if let Some(lamports) = args.airdrop {
+        seeder.airdrop(lamports).await?;
+    }
+
+    if let Some(dir) = &args.keys_dir {
+        write_keys(&seeder, dir, &args)?;
+    }
+
+    let summary = seeder
+        .run(&Plan {
+            agents: args.agents,
+            users: args.users,
+            requests_per_user: args.requests_per_user,
+            distributions: args.distributions,
+            fund_lamports: args.fund_lamports,
+        })
+        .await?;
+
+    println!(
+        "agents:        {} created, {} already present",
+        summary.agents_created, summary.agents_existing
+    );
+    println!(
+        "requests:      {} created, {} already present",
+        summary.requests.values().sum::<usize>(),
+        summary.requests_existing
+    );
+    for (status, count) in &summary.requests {
+        println!("  {status:<12} {count}");
+    }
+    println!("ratings:       {}", summary.ratings);
+    println!("distributions: {}", summary.distributions);
+    Ok(())
+}
+
+// Helper function to save the generated creator and user keypairs
+fn write_keys(seeder: &Seeder, dir: &Path, args: &Args) -> Result<()> {
+    std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    let roles = [("creator", args.agents), ("user", args.users)];
+
+    for (role, count) in roles {
+        for index in 0..count {
+            let path = dir.join(format!("{role}-{index:02}.json"));
+            write_keypair_file(&seeder.keypair(role, index), &path)
+                .map_err(|e| anyhow::anyhow!("failed to write {}: {e}", path.display()))?;
+        }
+    }
+    info!(dir = %dir.display(), "wrote demo keypairs");
+    Ok(())
+}
+
+// Helper function to read the funding keypair, defaulting to the Solana CLI's
+fn load_keypair(path: Option<&Path>) -> Result<Keypair> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => {
+            let home = std::env::var_os("HOME").context("HOME is not set; pass --keypair")?;
+            PathBuf::from(home).join(".config/solana/id.json")
+        }
+    };
+
+    read_keypair_file(&path)
+        .map_err(|e| anyhow::anyhow!("failed to read keypair {}: {e}", path.display()))
+}
@@ -0,0 +1,658 @@
+//! Drives the programs through registration, request lifecycles, ratings, and distributions.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use agentmarket_sdk::accounts::{
+    AgentProfile, AgentReputationProfile, GuardianConfig, PricingModel, Rating, RegistryConfig,
+    ReputationConfig, RoyaltyConfig, ServiceRequest,
+};
+use agentmarket_sdk::instructions::reputation::RatingScores;
+use agentmarket_sdk::instructions::royalty::Shares;
+use agentmarket_sdk::instructions::{escrow, guardian, registry, reputation, royalty};
+use agentmarket_sdk::{pda, Instruction, Pubkey, RpcClient};
+use anchor_lang::solana_program::system_instruction;
+use anyhow::{bail, Context, Result};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde_json::{json, Value};
+use solana_keypair::Keypair;
+use solana_sha256_hasher::hashv;
+use solana_signature::Signature;
+use solana_signer::Signer;
+use tracing::{debug, info};
+
+use crate::catalog;
+
+/// Balance given to the platform and treasury wallets so small payouts stay rent-exempt
+const FEE_WALLET_FLOOR: u64 = 10_000_000;
+/// Listing stake set when the seeder creates the registry config
+const DEMO_LISTING_STAKE: u64 = 10_000_000;
+/// Daily featured listing price set when the seeder creates the registry config
+const DEMO_FEATURED_PRICE_PER_DAY: u64 = 5_000_000;
+/// Heartbeat window set when the seeder creates the registry config; demo
+/// agents never send heartbeats, so it is left off
+const DEMO_HEARTBEAT_WINDOW: i64 = 0;
+/// Registration fee set when the seeder creates the registry config
+const DEMO_REGISTRATION_FEE: u64 = 1_000_000;
+/// Capability accounts created per transaction
+const CAPABILITIES_PER_TX: usize = 8;
+/// Transfers packed into one funding transaction
+const TRANSFERS_PER_TX: usize = 8;
+/// How long to wait for each transaction to reach `confirmed`
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How much demo data to create
+pub struct Plan {
+    pub agents: usize,
+    pub users: usize,
+    pub requests_per_user: usize,
+    pub distributions: usize,
+    /// Target balance for each generated creator and user wallet
+    pub fund_lamports: u64,
+}
+
+/// What a run created, and what it found already in place
+#[derive(Default)]
+pub struct Summary {
+    pub agents_created: usize,
+    pub agents_existing: usize,
+    /// New requests by the status they were left in
+    pub requests: BTreeMap<&'static str, usize>,
+    pub requests_existing: usize,
+    pub ratings: usize,
+    pub distributions: usize,
+}
+
+/// Where a seeded request ends up
+#[derive(Clone, Copy)]
+enum Outcome {
+    Pending,
+    Completed,
+    Approved { rating: Option<RatingScores> },
+    Disputed,
+    Cancelled,
+}
+
+impl Outcome {
+    fn label(self) -> &'static str {
+        match self {
+            Outcome::Pending => "pending",
+            Outcome::Completed => "completed",
+            Outcome::Approved { .. } => "approved",
+            Outcome::Disputed => "disputed",
+            Outcome::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// A registered demo agent
+struct Agent {
+    creator: Keypair,
+    agent_id: Pubkey,
+    nft_mint: Pubkey,
+    pricing: PricingModel,
+    capability: String,
+    quality: u8,
+    is_active: bool,
+}
+
+/// Seeds a deployment from one funding wallet
+///
+/// Every generated keypair and random choice derives from `seed`, so a rerun
+/// with the same seed reuses the same wallets and skips agents, requests, and
+/// ratings that already exist. Distributions are not idempotent and are added
+/// on every run.
+pub struct Seeder {
+    rpc: RpcClient,
+    funder: Keypair,
+    seed: String,
+    rng: StdRng,
+}
+
+impl Seeder {
+    pub fn new(rpc: RpcClient, funder: Keypair, seed: &str) -> Self {
+        let rng = StdRng::from_seed(hashv(&[seed.as_bytes(), b"rng"]).to_bytes());
+        Self {
+            rpc,
+            funder,
+            seed: seed.to_owned(),
+            rng,
+        }
+    }
+
+    /// Deterministic keypair for the `index`-th wallet playing `role`
+    pub fn keypair(&self, role: &str, index: usize) -> Keypair {
+        let digest = hashv(&[
+            self.seed.as_bytes(),
+            role.as_bytes(),
+            &(index as u64).to_le_bytes(),
+        ]);
+        Keypair::new_from_array(digest.to_bytes())
+    }
+
+    /// Ask the cluster faucet for lamports; localnet and devnet only
+    pub async fn airdrop(&self, lamports: u64) -> Result<()> {
+        let result = self
+            .rpc
+            .call(
+                "requestAirdrop",
+                json!([self.funder.pubkey().to_string(), lamports]),
+            )
+            .await
+            .context("airdrop failed")?;
+        let signature: Signature = result
+            .as_str()
+            .and_then(|s| s.parse().ok())
+            .context("requestAirdrop returned no signature")?;
+        self.confirm(&signature).await
+    }
+
+    pub async fn run(&mut self, plan: &Plan) -> Result<Summary> {
+        let mut summary = Summary::default();
+        let config = self.ensure_configs().await?;
+
+        let creators: Vec<Keypair> = (0..plan.agents)
+            .map(|i| self.keypair("creator", i))
+            .collect();
+        let users: Vec<Keypair> = (0..plan.users).map(|i| self.keypair("user", i)).collect();
+        let wallets: Vec<(Pubkey, u64)> = creators
+            .iter()
+            .chain(&users)
+            .map(|k| (k.pubkey(), plan.fund_lamports))
+            .chain([
+                (config.platform_wallet, FEE_WALLET_FLOOR),
+                (config.treasury_wallet, FEE_WALLET_FLOOR),
+            ])
+            .collect();
+        self.fund(&wallets).await?;
+
+        let agents = self.seed_agents(creators, &mut summary).await?;
+        self.seed_requests(&agents, &users, plan, &config, &mut summary)
+            .await?;
+        self.seed_distributions(&agents, plan.distributions, &mut summary)
+            .await?;
+        Ok(summary)
+    }
+
+    // Helper function to create any missing singleton config with the funder as admin
+    async fn ensure_configs(&self) -> Result<RoyaltyConfig> {
+        let admin = self.funder.pubkey();
+
+        match self
+            .rpc
+            .fetch_optional::<GuardianConfig>(&pda::guardian_config().0)
+            .await?
+        {
+            Some(config) if config.is_paused => bail!("the protocol is paused; unpause it first"),
+            Some(_) => {}
+            None => {
+                info!("initializing protocol guardian");
+                self.send(&[guardian::initialize_guardian(admin, vec![admin])], &[])
+                    .await?;
+            }
+        }
+
+        if self
+            .rpc
+            .fetch_optional::<ReputationConfig>(&pda::reputation_config().0)
+            .await?
+            .is_none()
+        {
+            info!("initializing reputation config");
+            self.send(&[reputation::initialize_config(admin)], &[])
+                .await?;
+        }
+
+        if self
+            .rpc
+            .get_account_data(&pda::registry_config().0)
+            .await?
+            .is_none()
+        {
+            info!("initializing registry config");
+            self.send(
+                &[registry::initialize_registry_config(
+                    admin,
+                    DEMO_LISTING_STAKE,
+                    admin,
+                    DEMO_FEATURED_PRICE_PER_DAY,
+                    admin,
+                    DEMO_HEARTBEAT_WINDOW,
+                    DEMO_REGISTRATION_FEE,
+                )],
+                &[],
+            )
+            .await?;
+        }
+
+        if self
+            .rpc
+            .get_account_data(&pda::collection_mint().0)
+            .await?
+            .is_none()
+        {
+            info!("initializing agent collection");
+            self.send(
+                &[registry::initialize_collection(
+                    admin,
+                    "AgentMarket Agents".into(),
+                    "AGENT".into(),
+                    "https://demo.agentmarket.dev/metadata/collection.json".into(),
+                )],
+                &[],
+            )
+            .await?;
+        }
+
+        let mut missing = Vec::new();
+        for id in catalog::capabilities() {
+            if self
+                .rpc
+                .get_account_data(&pda::capability(id).0)
+                .await?
+                .is_none()
+            {
+                missing.push(registry::add_capability(
+                    admin,
+                    id.into(),
+                    id.replace('-', " "),
+                    String::new(),
+                ));
+            }
+        }
+        if !missing.is_empty() {
+            info!(capabilities = missing.len(), "adding capabilities");
+            for chunk in missing.chunks(CAPABILITIES_PER_TX) {
+                self.send(chunk, &[]).await?;
+            }
+        }
+
+        if let Some(config) = self
+            .rpc
+            .fetch_optional::<RoyaltyConfig>(&pda::royalty_config().0)
+            .await?
+        {
+            return Ok(config);
+        }
+        info!("initializing royalty config");
+        let shares = Shares {
+            creator: 85,
+            platform: 10,
+            treasury: 5,
+        };
+        let platform = self.keypair("platform", 0).pubkey();
+        let treasury = self.keypair("treasury", 0).pubkey();
+        self.send(
+            &[royalty::initialize_config(
+                admin, shares, platform, treasury,
+            )],
+            &[],
+        )
+        .await?;
+        Ok(self.rpc.fetch(&pda::royalty_config().0).await?)
+    }
+
+    // Helper function to top up each wallet to its target balance from the funder
+    async fn fund(&self, wallets: &[(Pubkey, u64)]) -> Result<()> {
+        let mut transfers = Vec::new();
+        for (wallet, target) in wallets {
+            let balance = self.rpc.get_balance(wallet).await?;
+            if balance < target / 2 {
+                transfers.push(system_instruction::transfer(
+                    &self.funder.pubkey(),
+                    wallet,
+                    target - balance,
+                ));
+            }
+        }
+
+        info!(
+            wallets = wallets.len(),
+            topped_up = transfers.len(),
+            "funding wallets"
+        );
+        for chunk in transfers.chunks(TRANSFERS_PER_TX) {
+            self.send(chunk, &[]).await?;
+        }
+        Ok(())
+    }
+
+    // Helper function to register each creator's agent and its reputation profile
+    async fn seed_agents(
+        &mut self,
+        creators: Vec<Keypair>,
+        summary: &mut Summary,
+    ) -> Result<Vec<Agent>> {
+        let mut agents = Vec::with_capacity(creators.len());
+
+        for (index, creator) in creators.into_iter().enumerate() {
+            // Draw the spec even for existing agents so later choices stay reproducible
+            let spec = catalog::agent(index, &mut self.rng);
+            let deactivate = self.rng.gen_ratio(1, 10);
+            let agent_id = pda::agent_profile(&creator.pubkey()).0;
+
+            let (nft_mint, pricing, is_active) =
+                match self.rpc.fetch_optional::<AgentProfile>(&agent_id).await? {
+                    Some(profile) => {
+                        summary.agents_existing += 1;
+                        (profile.nft_mint, profile.pricing_model, profile.is_active)
+                    }
+                    None => {
+                        let mint = self.keypair("mint", index);
+                        let config = self
+                            .rpc
+                            .fetch::<RegistryConfig>(&pda::registry_config().0)
+                            .await?;
+                        let mut instructions = vec![registry::register_agent(
+                            creator.pubkey(),
+                            mint.pubkey(),
+                            &config,
+                            spec.args.clone(),
+                        )];
+                        if deactivate {
+                            instructions.push(registry::update_agent(
+                                creator.pubkey(),
+                                agent_id,
+                                mint.pubkey(),
+                                registry::UpdateAgentArgs {
+                                    is_active: Some(false),
+                                    ..Default::default()
+                                },
+                            ));
+                        }
+                        self.send(&instructions, &[&creator, &mint]).await?;
+                        debug!(%agent_id, name = %spec.args.name, "registered agent");
+                        summary.agents_created += 1;
+                        (mint.pubkey(), spec.args.pricing.clone(), !deactivate)
+                    }
+                };
+
+            if self
+                .rpc
+                .fetch_optional::<AgentReputationProfile>(&pda::agent_reputation(&agent_id).0)
+                .await?
+                .is_none()
+            {
+                self.send(
+                    &[reputation::initialize_agent_reputation(
+                        creator.pubkey(),
+                        agent_id,
+                    )],
+                    &[&creator],
+                )
+                .await?;
+            }
+
+            agents.push(Agent {
+                creator,
+                agent_id,
+                nft_mint,
+                pricing,
+                capability: spec.args.capabilities[0].clone(),
+                quality: spec.quality,
+                is_active,
+            });
+        }
+
+        info!(
+            created = summary.agents_created,
+            existing = summary.agents_existing,
+            "agents seeded"
+        );
+        Ok(agents)
+    }
+
+    // Helper function to open requests from each user and walk them to their outcome
+    async fn seed_requests(
+        &mut self,
+        agents: &[Agent],
+        users: &[Keypair],
+        plan: &Plan,
+        config: &RoyaltyConfig,
+        summary: &mut Summary,
+    ) -> Result<()> {
+        let active: Vec<&Agent> = agents.iter().filter(|a| a.is_active).collect();
+
+        for user in users {
+            let picks: Vec<&Agent> = active
+                .choose_multiple(&mut self.rng, plan.requests_per_user)
+                .copied()
+                .collect();
+
+            for agent in picks {
+                let outcome = self.outcome(agent.quality);
+                let data = catalog::request_data(&agent.capability, &mut self.rng);
+                let request = pda::service_request(&user.pubkey(), &agent.agent_id).0;
+
+                if self
+                    .rpc
+                    .fetch_optional::<ServiceRequest>(&request)
+                    .await?
+                    .is_some()
+                {
+                    summary.requests_existing += 1;
+                    continue;
+                }
+
+                self.send(
+                    &[escrow::create_service_request(
+                        user.pubkey(),
+                        agent.agent_id,
+                        catalog::request_amount(&agent.pricing),
+                        data,
+                    )],
+                    &[user],
+                )
+                .await?;
+
+                match outcome {
+                    Outcome::Pending => {}
+                    Outcome::Cancelled => {
+                        self.send(
+                            &[escrow::cancel_request(
+                                request,
+                                agent.agent_id,
+                                user.pubkey(),
+                            )],
+                            &[user],
+                        )
+                        .await?;
+                    }
+                    Outcome::Completed | Outcome::Approved { .. } | Outcome::Disputed => {
+                        let result = json!({"status": "ok", "output": "demo result"});
+                        self.send(
+                            &[escrow::submit_result(
+                                request,
+                                agent.agent_id,
+                                agent.nft_mint,
+                                agent.creator.pubkey(),
+                                result.to_string(),
+                            )],
+                            &[&agent.creator],
+                        )
+                        .await?;
+                    }
+                }
+
+                match outcome {
+                    Outcome::Approved { rating } => {
+                        self.send(
+                            &[escrow::approve_result(
+                                request,
+                                agent.agent_id,
+                                user.pubkey(),
+                                agent.creator.pubkey(),
+                                config.platform_wallet,
+                                config.treasury_wallet,
+                            )],
+                            &[user],
+                        )
+                        .await?;
+                        if let Some(scores) = rating {
+                            self.rate(user, agent, request, scores, summary).await?;
+                        }
+                    }
+                    Outcome::Disputed => {
+                        self.send(
+                            &[escrow::dispute_result(
+                                request,
+                                user.pubkey(),
+                                "Result did not match the request".into(),
+                            )],
+                            &[user],
+                        )
+                        .await?;
+                    }
+                    _ => {}
+                }
+
+                *summary.requests.entry(outcome.label()).or_default() += 1;
+            }
+        }
+
+        info!(
+            created = summary.requests.values().sum::<usize>(),
+            existing = summary.requests_existing,
+            ratings = summary.ratings,
+            "requests seeded"
+        );
+        Ok(())
+    }
+
+    // Helper function to rate an approved request unless this user already has
+    async fn rate(
+        &self,
+        user: &Keypair,
+        agent: &Agent,
+        request: Pubkey,
+        scores: RatingScores,
+        summary: &mut Summary,
+    ) -> Result<()> {
+        let rating = pda::rating(&user.pubkey(), &request).0;
+        if self.rpc.fetch_optional::<Rating>(&rating).await?.is_some() {
+            return Ok(());
+        }
+
+        self.send(
+            &[reputation::submit_rating(
+                user.pubkey(),
+                agent.agent_id,
+                request,
+                scores,
+                catalog::review(scores.stars).into(),
+            )],
+            &[user],
+        )
+        .await?;
+        summary.ratings += 1;
+        Ok(())
+    }
+
+    // Helper function to pay creators directly through the royalty splitter
+    async fn seed_distributions(
+        &mut self,
+        agents: &[Agent],
+        count: usize,
+        summary: &mut Summary,
+    ) -> Result<()> {
+        if agents.is_empty() {
+            return Ok(());
+        }
+
+        for _ in 0..count {
+            let agent = agents.choose(&mut self.rng).expect("agents are non-empty");
+            let amount = catalog::request_amount(&agent.pricing) * self.rng.gen_range(1..=5);
+            let config: RoyaltyConfig = self.rpc.fetch(&pda::royalty_config().0).await?;
+            if config.is_paused {
+                bail!("royalty distributions are paused");
+            }
+
+            let funder = self.funder.pubkey();
+            self.send(
+                &[royalty::distribute_payment(
+                    funder,
+                    funder,
+                    agent.creator.pubkey(),
+                    config.platform_wallet,
+                    config.treasury_wallet,
+                    config.total_transactions,
+                    amount,
+                )],
+                &[],
+            )
+            .await?;
+            summary.distributions += 1;
+        }
+
+        info!(count = summary.distributions, "distributions seeded");
+        Ok(())
+    }
+
+    // Helper function to draw a request outcome, with ratings scattered around the agent's quality
+    fn outcome(&mut self, quality: u8) -> Outcome {
+        match self.rng.gen_range(0..100) {
+            0..=19 => Outcome::Pending,
+            20..=34 => Outcome::Completed,
+            35..=79 => {
+                let rated = self.rng.gen_ratio(3, 4);
+                let mut score = || {
+                    quality
+                        .saturating_add_signed(self.rng.gen_range(-1..=1))
+                        .clamp(1, 5)
+                };
+                let scores = RatingScores {
+                    stars: score(),
+                    quality: score(),
+                    speed: score(),
+                    value: score(),
+                };
+                Outcome::Approved {
+                    rating: rated.then_some(scores),
+                }
+            }
+            80..=89 => Outcome::Disputed,
+            _ => Outcome::Cancelled,
+        }
+    }
+
+    // Helper function to send instructions paid for by the funder and wait for confirmation
+    async fn send(&self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<()> {
+        let mut all: Vec<&Keypair> = vec![&self.funder];
+        all.extend(signers);
+        let signature = self
+            .rpc
+            .send_instructions(instructions, &self.funder.pubkey(), &all)
+            .await?;
+        self.confirm(&signature).await
+    }
+
+    // Helper function to poll until a signature is confirmed, failing on a transaction error
+    async fn confirm(&self, signature: &Signature) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + CONFIRM_TIMEOUT;
+
+        loop {
+            let result = self
+                .rpc
+                .call("getSignatureStatuses", json!([[signature.to_string()]]))
+                .await?;
+            let status = result.pointer("/value/0").cloned().unwrap_or(Value::Null);
+
+            if !status.is_null() {
+                if let Some(err) = status.get("err").filter(|e| !e.is_null()) {
+                    bail!("transaction {signature} failed: {err}");
+                }
+                let level = status.get("confirmationStatus").and_then(Value::as_str);
+                if matches!(level, Some("confirmed" | "finalized")) {
+                    return Ok(());
+                }
+            }
+
+            if tokio::time::Instant::now() >= deadline {
+                bail!("transaction {signature} was not confirmed in time");
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+    }
+}
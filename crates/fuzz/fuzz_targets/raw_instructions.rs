@@ -0,0 +1,27 @@
+//! Arbitrary instruction data and account permutations, interleaved with well-formed setup.
+
+use agentmarket_fuzz::actions::{Action, RawInstruction};
+use agentmarket_fuzz::env::FuzzEnv;
+use agentmarket_fuzz::invariants;
+
+/// Longest setup and raw sequences executed per input
+const MAX_ACTIONS: usize = 16;
+const MAX_RAW_INSTRUCTIONS: usize = 16;
+
+fn main() {
+    loop {
+        honggfuzz::fuzz!(|input: (Vec<Action>, Vec<RawInstruction>)| {
+            let (actions, raw_instructions) = input;
+            let mut env = FuzzEnv::new();
+            // Well-formed actions first, so raw instructions can reach real agents and requests
+            for action in actions.into_iter().take(MAX_ACTIONS) {
+                action.apply(&mut env);
+                invariants::check(&env);
+            }
+            for instruction in raw_instructions.into_iter().take(MAX_RAW_INSTRUCTIONS) {
+                instruction.apply(&mut env);
+                invariants::check(&env);
+            }
+        });
+    }
+}
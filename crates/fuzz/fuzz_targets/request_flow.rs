@@ -0,0 +1,20 @@
+//! Sequences of well-formed marketplace instructions with arbitrary arguments and signers.
+
+use agentmarket_fuzz::actions::Action;
+use agentmarket_fuzz::env::FuzzEnv;
+use agentmarket_fuzz::invariants;
+
+/// Longest action sequence executed per input
+const MAX_ACTIONS: usize = 32;
+
+fn main() {
+    loop {
+        honggfuzz::fuzz!(|actions: Vec<Action>| {
+            let mut env = FuzzEnv::new();
+            for action in actions.into_iter().take(MAX_ACTIONS) {
+                action.apply(&mut env);
+                invariants::check(&env);
+            }
+        });
+    }
+}
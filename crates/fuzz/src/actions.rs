@@ -0,0 +1,421 @@
+//! Fuzzer-generated operations and how they map onto program instructions.
+
+use agent_registry::{AgentProfile, PricingModel, RegistryConfig};
+use agentmarket_sdk::accounts::ServiceRequest;
+use agentmarket_sdk::instructions::registry::{self, RegisterAgentArgs};
+use agentmarket_sdk::instructions::reputation::{self, RatingScores};
+use agentmarket_sdk::instructions::royalty::{self, Shares};
+use agentmarket_sdk::instructions::{escrow, guardian};
+use agentmarket_sdk::{
+    pda, Instruction, ESCROW_PROGRAM_ID, REGISTRY_PROGRAM_ID, REPUTATION_PROGRAM_ID,
+    ROYALTY_PROGRAM_ID,
+};
+use anchor_lang::solana_program::instruction::AccountMeta;
+use anchor_lang::solana_program::native_token::LAMPORTS_PER_SOL;
+use anchor_lang::Discriminator;
+use arbitrary::Arbitrary;
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+use crate::env::FuzzEnv;
+
+/// A well-formed instruction with fuzzer-chosen arguments and signers
+#[derive(Arbitrary, Debug)]
+pub enum Action {
+    RegisterAgent {
+        creator: u8,
+        price: u64,
+    },
+    CreateRequest {
+        user: u8,
+        agent: u8,
+        amount: u64,
+        request_data: String,
+    },
+    SubmitResult {
+        request: u8,
+        signer: u8,
+        result_data: String,
+    },
+    Approve {
+        request: u8,
+        signer: u8,
+        creator: u8,
+    },
+    Dispute {
+        request: u8,
+        signer: u8,
+        reason: String,
+    },
+    Cancel {
+        request: u8,
+        signer: u8,
+    },
+    Distribute {
+        payer: u8,
+        creator: u8,
+        amount: u64,
+    },
+    UpdateShares {
+        signer: u8,
+        creator: u8,
+        platform: u8,
+        treasury: u8,
+    },
+    SetRoyaltyPause {
+        signer: u8,
+        paused: bool,
+    },
+    SetGuardianPause {
+        signer: u8,
+        paused: bool,
+    },
+    Rate {
+        user: u8,
+        request: u8,
+        stars: u8,
+        quality: u8,
+        speed: u8,
+        value: u8,
+    },
+}
+
+impl Action {
+    /// Execute against `env`, recording anything the action created
+    pub fn apply(self, env: &mut FuzzEnv) {
+        match self {
+            Action::RegisterAgent { creator, price } => {
+                let creator = env.signer(creator);
+                let mint = Keypair::new();
+                let agent_id = pda::agent_profile(&creator.pubkey()).0;
+                let config = env
+                    .fetch::<RegistryConfig>(&pda::registry_config().0)
+                    .expect("registry config is initialized");
+                let registered = env.send(
+                    &[registry::register_agent(
+                        creator.pubkey(),
+                        mint.pubkey(),
+                        &config,
+                        RegisterAgentArgs {
+                            // Agent names are claimed, so each creator needs its own
+                            name: format!("Fuzz agent {}", creator.pubkey()),
+                            description: String::new(),
+                            capabilities: Vec::new(),
+                            pricing: PricingModel::PerQuery {
+                                price,
+                                payment_mint: None,
+                            },
+                            endpoints: Vec::new(),
+                            ipfs_hash: String::new(),
+                            symbol: "FUZZ".into(),
+                            uri: String::new(),
+                        },
+                    )],
+                    &[&creator, &mint],
+                );
+                if registered {
+                    env.agents.push(agent_id);
+                }
+            }
+            Action::CreateRequest {
+                user,
+                agent,
+                amount,
+                request_data,
+            } => {
+                let Some(agent_id) = env.agent(agent) else {
+                    return;
+                };
+                let user = env.signer(user);
+                // Keep amounts within what the funded actors can pay
+                let amount = amount % (10 * LAMPORTS_PER_SOL);
+                let created = env.send(
+                    &[escrow::create_service_request(
+                        user.pubkey(),
+                        agent_id,
+                        amount,
+                        request_data,
+                    )],
+                    &[&user],
+                );
+                let request = pda::service_request(&user.pubkey(), &agent_id).0;
+                if created && !env.requests.contains(&request) {
+                    env.requests.push(request);
+                }
+            }
+            Action::SubmitResult {
+                request,
+                signer,
+                result_data,
+            } => {
+                let Some(request) = env.request(request) else {
+                    return;
+                };
+                let Some(agent_id) = env.request_agent(&request) else {
+                    return;
+                };
+                let Some(profile) = env.fetch::<AgentProfile>(&agent_id) else {
+                    return;
+                };
+                let signer = env.signer(signer);
+                env.send(
+                    &[escrow::submit_result(
+                        request,
+                        agent_id,
+                        profile.nft_mint,
+                        signer.pubkey(),
+                        result_data,
+                    )],
+                    &[&signer],
+                );
+            }
+            Action::Approve {
+                request,
+                signer,
+                creator,
+            } => {
+                let Some(request) = env.request(request) else {
+                    return;
+                };
+                let Some(agent_id) = env.request_agent(&request) else {
+                    return;
+                };
+                let signer = env.signer(signer);
+                let creator = env.signer(creator).pubkey();
+                let (platform_wallet, treasury_wallet) = (env.platform_wallet, env.treasury_wallet);
+                env.send(
+                    &[escrow::approve_result(
+                        request,
+                        agent_id,
+                        signer.pubkey(),
+                        creator,
+                        platform_wallet,
+                        treasury_wallet,
+                    )],
+                    &[&signer],
+                );
+            }
+            Action::Dispute {
+                request,
+                signer,
+                reason,
+            } => {
+                let Some(request) = env.request(request) else {
+                    return;
+                };
+                let signer = env.signer(signer);
+                env.send(
+                    &[escrow::dispute_result(request, signer.pubkey(), reason)],
+                    &[&signer],
+                );
+            }
+            Action::Cancel { request, signer } => {
+                let Some(request) = env.request(request) else {
+                    return;
+                };
+                let Some(agent_id) = env.request_agent(&request) else {
+                    return;
+                };
+                let signer = env.signer(signer);
+                env.send(
+                    &[escrow::cancel_request(request, agent_id, signer.pubkey())],
+                    &[&signer],
+                );
+            }
+            Action::Distribute {
+                payer,
+                creator,
+                amount,
+            } => {
+                let payer = env.signer(payer);
+                let creator = env.signer(creator).pubkey();
+                let amount = amount % (10 * LAMPORTS_PER_SOL);
+                let index = env.distribution_count();
+                let (platform_wallet, treasury_wallet) = (env.platform_wallet, env.treasury_wallet);
+                env.send(
+                    &[royalty::distribute_payment(
+                        payer.pubkey(),
+                        payer.pubkey(),
+                        creator,
+                        platform_wallet,
+                        treasury_wallet,
+                        index,
+                        amount,
+                    )],
+                    &[&payer],
+                );
+            }
+            Action::UpdateShares {
+                signer,
+                creator,
+                platform,
+                treasury,
+            } => {
+                let signer = env.signer(signer);
+                env.send(
+                    &[royalty::update_config(
+                        signer.pubkey(),
+                        Some(Shares {
+                            creator,
+                            platform,
+                            treasury,
+                        }),
+                        None,
+                        None,
+                    )],
+                    &[&signer],
+                );
+            }
+            Action::SetRoyaltyPause { signer, paused } => {
+                let signer = env.signer(signer);
+                env.send(
+                    &[royalty::set_pause_state(signer.pubkey(), paused)],
+                    &[&signer],
+                );
+            }
+            Action::SetGuardianPause { signer, paused } => {
+                let signer = env.signer(signer);
+                let instruction = if paused {
+                    guardian::pause_protocol(signer.pubkey())
+                } else {
+                    guardian::unpause_protocol(signer.pubkey())
+                };
+                env.send(&[instruction], &[&signer]);
+            }
+            Action::Rate {
+                user,
+                request,
+                stars,
+                quality,
+                speed,
+                value,
+            } => {
+                let Some(request) = env.request(request) else {
+                    return;
+                };
+                let Some(state) = env.fetch::<ServiceRequest>(&request) else {
+                    return;
+                };
+                let user = env.signer(user);
+                let rated = env.send(
+                    &[reputation::submit_rating(
+                        user.pubkey(),
+                        state.agent_id,
+                        request,
+                        RatingScores {
+                            stars,
+                            quality,
+                            speed,
+                            value,
+                        },
+                        String::new(),
+                    )],
+                    &[&user],
+                );
+                if rated {
+                    *env.ratings.entry(state.agent_id).or_default() += 1;
+                }
+            }
+        }
+    }
+}
+
+/// One entry of a raw instruction's account list
+#[derive(Arbitrary, Debug)]
+pub struct AccountChoice {
+    pub address: u8,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// A real instruction discriminator followed by arbitrary data and accounts
+#[derive(Arbitrary, Debug)]
+pub struct RawInstruction {
+    pub program: u8,
+    pub instruction: u8,
+    pub data: Vec<u8>,
+    pub accounts: Vec<AccountChoice>,
+    pub payer: u8,
+}
+
+// Instruction discriminators per fuzzed program, so raw data reaches the handlers
+const REGISTRY_INSTRUCTIONS: &[&[u8]] = &[
+    agent_registry::instruction::RegisterAgent::DISCRIMINATOR,
+    agent_registry::instruction::UpdateAgent::DISCRIMINATOR,
+    agent_registry::instruction::UpdateReputation::DISCRIMINATOR,
+];
+const ESCROW_INSTRUCTIONS: &[&[u8]] = &[
+    marketplace_escrow::instruction::CreateServiceRequest::DISCRIMINATOR,
+    marketplace_escrow::instruction::SubmitResult::DISCRIMINATOR,
+    marketplace_escrow::instruction::ApproveResult::DISCRIMINATOR,
+    marketplace_escrow::instruction::DisputeResult::DISCRIMINATOR,
+    marketplace_escrow::instruction::CancelRequest::DISCRIMINATOR,
+];
+const REPUTATION_INSTRUCTIONS: &[&[u8]] = &[
+    reputation_system::instruction::SubmitRating::DISCRIMINATOR,
+    reputation_system::instruction::InitializeAgentReputation::DISCRIMINATOR,
+    reputation_system::instruction::GetAgentStats::DISCRIMINATOR,
+    reputation_system::instruction::ReportRating::DISCRIMINATOR,
+    reputation_system::instruction::InitializeConfig::DISCRIMINATOR,
+    reputation_system::instruction::TransferAdmin::DISCRIMINATOR,
+    reputation_system::instruction::ModerateRating::DISCRIMINATOR,
+];
+const ROYALTY_INSTRUCTIONS: &[&[u8]] = &[
+    royalty_splitter::instruction::InitializeConfig::DISCRIMINATOR,
+    royalty_splitter::instruction::DistributePayment::DISCRIMINATOR,
+    royalty_splitter::instruction::UpdateConfig::DISCRIMINATOR,
+    royalty_splitter::instruction::TransferAdmin::DISCRIMINATOR,
+    royalty_splitter::instruction::WithdrawPlatformFees::DISCRIMINATOR,
+    royalty_splitter::instruction::GetStats::DISCRIMINATOR,
+    royalty_splitter::instruction::SetPauseState::DISCRIMINATOR,
+];
+
+impl RawInstruction {
+    /// Execute against `env`; failures are expected, only invariant violations matter
+    pub fn apply(self, env: &mut FuzzEnv) {
+        let (program_id, instructions) = match self.program % 4 {
+            0 => (REGISTRY_PROGRAM_ID, REGISTRY_INSTRUCTIONS),
+            1 => (ESCROW_PROGRAM_ID, ESCROW_INSTRUCTIONS),
+            2 => (REPUTATION_PROGRAM_ID, REPUTATION_INSTRUCTIONS),
+            _ => (ROYALTY_PROGRAM_ID, ROYALTY_INSTRUCTIONS),
+        };
+        let mut data = instructions[self.instruction as usize % instructions.len()].to_vec();
+        data.extend_from_slice(&self.data);
+
+        let addresses = env.addresses();
+        let payer = env.signer(self.payer);
+        let mut signers = vec![payer.insecure_clone()];
+        let mut accounts = Vec::with_capacity(self.accounts.len());
+
+        for choice in self.accounts {
+            let pubkey = addresses[choice.address as usize % addresses.len()];
+            // Only keys the harness holds can sign
+            let keypair = env
+                .actors
+                .iter()
+                .chain(std::iter::once(&env.admin))
+                .find(|k| k.pubkey() == pubkey);
+            let is_signer = choice.is_signer && keypair.is_some();
+            if let (true, Some(keypair)) = (is_signer, keypair) {
+                if !signers.iter().any(|s| s.pubkey() == pubkey) {
+                    signers.push(keypair.insecure_clone());
+                }
+            }
+            accounts.push(AccountMeta {
+                pubkey,
+                is_signer,
+                is_writable: choice.is_writable,
+            });
+        }
+
+        let signer_refs: Vec<&Keypair> = signers.iter().collect();
+        env.send(
+            &[Instruction {
+                program_id,
+                accounts,
+                data,
+            }],
+            &signer_refs,
+        );
+    }
+}
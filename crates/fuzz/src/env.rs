@@ -0,0 +1,278 @@
+//! LiteSVM state shared by the fuzz targets, with lamport accounting on every transaction.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use agentmarket_sdk::accounts::ServiceRequest;
+use agentmarket_sdk::instructions::royalty::{self, Shares};
+use agentmarket_sdk::instructions::{guardian, registry, reputation};
+use agentmarket_sdk::{
+    pda, Instruction, Pubkey, ESCROW_PROGRAM_ID, GUARDIAN_PROGRAM_ID, REGISTRY_PROGRAM_ID,
+    REPUTATION_PROGRAM_ID, ROYALTY_PROGRAM_ID, TOKEN_METADATA_PROGRAM_ID,
+};
+use anchor_lang::solana_program::native_token::LAMPORTS_PER_SOL;
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_lang::AccountDeserialize;
+use litesvm::LiteSVM;
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+/// Number of funded user/creator keypairs besides the admin
+pub const ACTORS: usize = 4;
+
+/// Fee charged per transaction signature by LiteSVM's default fee structure
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+/// Stake each registered agent locks
+const LISTING_STAKE: u64 = LAMPORTS_PER_SOL / 100;
+
+/// A deployed marketplace plus everything the fuzzer has created in it
+pub struct FuzzEnv {
+    pub svm: LiteSVM,
+    /// Guardian authority, royalty admin, and reputation admin
+    pub admin: Keypair,
+    pub actors: Vec<Keypair>,
+    pub platform_wallet: Pubkey,
+    pub treasury_wallet: Pubkey,
+    /// Registered agent ids
+    pub agents: Vec<Pubkey>,
+    /// Service request addresses
+    pub requests: Vec<Pubkey>,
+    /// Successful ratings per agent id
+    pub ratings: HashMap<Pubkey, u64>,
+}
+
+impl FuzzEnv {
+    pub fn new() -> Self {
+        let mut svm = LiteSVM::new();
+        for (program_id, name) in [
+            (REGISTRY_PROGRAM_ID, "agent_registry"),
+            (ESCROW_PROGRAM_ID, "marketplace_escrow"),
+            (REPUTATION_PROGRAM_ID, "reputation_system"),
+            (ROYALTY_PROGRAM_ID, "royalty_splitter"),
+            (GUARDIAN_PROGRAM_ID, "protocol_guardian"),
+        ] {
+            load_program(
+                &mut svm,
+                program_id,
+                deploy_dir().join(format!("{name}.so")),
+            );
+        }
+        load_program(
+            &mut svm,
+            TOKEN_METADATA_PROGRAM_ID,
+            programs_dir().join("metadata_program.so"),
+        );
+
+        let admin = Keypair::new();
+        let actors: Vec<Keypair> = (0..ACTORS).map(|_| Keypair::new()).collect();
+        let platform_wallet = Pubkey::new_unique();
+        let treasury_wallet = Pubkey::new_unique();
+        for account in actors.iter().map(Keypair::pubkey).chain([
+            admin.pubkey(),
+            platform_wallet,
+            treasury_wallet,
+        ]) {
+            svm.airdrop(&account, 100 * LAMPORTS_PER_SOL).unwrap();
+        }
+
+        let mut env = Self {
+            svm,
+            admin,
+            actors,
+            platform_wallet,
+            treasury_wallet,
+            agents: Vec::new(),
+            requests: Vec::new(),
+            ratings: HashMap::new(),
+        };
+
+        let admin_key = env.admin.pubkey();
+        let admin = env.admin.insecure_clone();
+        let shares = Shares {
+            creator: 85,
+            platform: 10,
+            treasury: 5,
+        };
+        assert!(
+            env.send(
+                &[
+                    guardian::initialize_guardian(admin_key, vec![admin_key]),
+                    royalty::initialize_config(admin_key, shares, platform_wallet, treasury_wallet),
+                    reputation::initialize_config(admin_key),
+                    registry::initialize_registry_config(
+                        admin_key,
+                        LISTING_STAKE,
+                        admin_key,
+                        0,
+                        admin_key,
+                        0,
+                        0,
+                    ),
+                ],
+                &[&admin],
+            ),
+            "config initialization failed"
+        );
+        assert!(
+            env.send(
+                &[registry::initialize_collection(
+                    admin_key,
+                    "AgentMarket Agents".into(),
+                    "AGENT".into(),
+                    String::new(),
+                )],
+                &[&admin],
+            ),
+            "collection initialization failed"
+        );
+
+        env
+    }
+
+    /// Actor by fuzzer-chosen index; the index one past the actors selects the admin
+    pub fn signer(&self, index: u8) -> Keypair {
+        match index as usize % (ACTORS + 1) {
+            ACTORS => self.admin.insecure_clone(),
+            i => self.actors[i].insecure_clone(),
+        }
+    }
+
+    pub fn agent(&self, index: u8) -> Option<Pubkey> {
+        pick(&self.agents, index)
+    }
+
+    pub fn request(&self, index: u8) -> Option<Pubkey> {
+        pick(&self.requests, index)
+    }
+
+    /// Agent a created request was opened against
+    pub fn request_agent(&self, request: &Pubkey) -> Option<Pubkey> {
+        self.fetch::<ServiceRequest>(request)
+            .map(|state| state.agent_id)
+    }
+
+    /// Every address the fuzzer may place in a raw instruction's account list
+    pub fn addresses(&self) -> Vec<Pubkey> {
+        let mut addresses: BTreeSet<Pubkey> = BTreeSet::new();
+        addresses.extend(self.actors.iter().map(Keypair::pubkey));
+        addresses.extend([
+            self.admin.pubkey(),
+            self.platform_wallet,
+            self.treasury_wallet,
+            pda::royalty_config().0,
+            pda::reputation_config().0,
+            pda::guardian_config().0,
+            system_program::ID,
+            sysvar::rent::ID,
+            REGISTRY_PROGRAM_ID,
+            ESCROW_PROGRAM_ID,
+            REPUTATION_PROGRAM_ID,
+            ROYALTY_PROGRAM_ID,
+        ]);
+        for agent_id in &self.agents {
+            addresses.insert(*agent_id);
+            addresses.insert(pda::agent_reputation(agent_id).0);
+            addresses.insert(pda::agent_requests(agent_id).0);
+        }
+        for request in &self.requests {
+            addresses.insert(*request);
+            addresses.insert(pda::escrow_account(request).0);
+        }
+        for index in 0..self.distribution_count() {
+            addresses.insert(pda::distribution_record(index).0);
+        }
+        addresses.into_iter().collect()
+    }
+
+    /// Sign and process `instructions`, asserting that no lamports are created or destroyed
+    ///
+    /// The first signer pays the fee. Returns whether the transaction succeeded.
+    pub fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> bool {
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&signers[0].pubkey()),
+            signers,
+            self.svm.latest_blockhash(),
+        );
+        let keys = transaction.message.account_keys.clone();
+        let fee = LAMPORTS_PER_SIGNATURE * transaction.signatures.len() as u64;
+
+        let before = self.total_lamports(&keys);
+        let result = self.svm.send_transaction(transaction);
+        self.svm.expire_blockhash();
+        let after = self.total_lamports(&keys);
+
+        match &result {
+            Ok(_) => assert_eq!(
+                after + fee,
+                before,
+                "lamports not conserved by successful transaction"
+            ),
+            // A failed transaction may or may not have been charged its fee, but nothing else moves
+            Err(failed) => assert!(
+                after == before || after + fee == before,
+                "failed transaction moved lamports: {:?}",
+                failed.err
+            ),
+        }
+
+        result.is_ok()
+    }
+
+    pub fn fetch<T: AccountDeserialize>(&self, address: &Pubkey) -> Option<T> {
+        let account = self.svm.get_account(address)?;
+        T::try_deserialize(&mut account.data.as_slice()).ok()
+    }
+
+    pub fn balance(&self, address: &Pubkey) -> u64 {
+        self.svm.get_balance(address).unwrap_or_default()
+    }
+
+    /// Distribution records created so far, per the royalty config
+    pub fn distribution_count(&self) -> u64 {
+        self.fetch::<agentmarket_sdk::accounts::RoyaltyConfig>(&pda::royalty_config().0)
+            .map(|config| config.total_transactions)
+            .unwrap_or_default()
+    }
+
+    // Helper function to sum the balances of a transaction's accounts
+    fn total_lamports(&self, keys: &[Pubkey]) -> u64 {
+        keys.iter().map(|key| self.balance(key)).sum()
+    }
+}
+
+impl Default for FuzzEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Helper function to pick an element by fuzzer-chosen index
+fn pick(items: &[Pubkey], index: u8) -> Option<Pubkey> {
+    (!items.is_empty()).then(|| items[index as usize % items.len()])
+}
+
+// Helper function to locate the Anchor workspace
+fn programs_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../programs")
+}
+
+// Helper function to locate the SBF builds, honouring the usual SBF_OUT_DIR override
+fn deploy_dir() -> PathBuf {
+    std::env::var_os("SBF_OUT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| programs_dir().join("target/deploy"))
+}
+
+// Helper function to deploy one program, failing with a hint when it has not been built
+fn load_program(svm: &mut LiteSVM, program_id: Pubkey, path: PathBuf) {
+    svm.add_program_from_file(program_id, &path)
+        .unwrap_or_else(|e| {
+            panic!(
+                "failed to load {}: {e} (run `anchor build` first)",
+                path.display()
+            )
+        });
+}
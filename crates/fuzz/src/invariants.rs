@@ -0,0 +1,134 @@
+//! Cross-program properties that must hold after every executed step.
+
+use agentmarket_sdk::accounts::{
+    AgentReputationProfile, AgentRequestCount, DistributionRecord, RequestStatus, RoyaltyConfig,
+    ServiceRequest,
+};
+use agentmarket_sdk::pda;
+
+use crate::env::FuzzEnv;
+
+/// Panic if any invariant is violated
+pub fn check(env: &FuzzEnv) {
+    check_royalty(env);
+    check_escrow(env);
+    check_request_counts(env);
+    check_reputation(env);
+}
+
+// Helper function: shares total 100 and the distribution ledger adds up
+fn check_royalty(env: &FuzzEnv) {
+    let Some(config) = env.fetch::<RoyaltyConfig>(&pda::royalty_config().0) else {
+        return;
+    };
+    assert_eq!(
+        config.creator_share as u16 + config.platform_share as u16 + config.treasury_share as u16,
+        100,
+        "royalty shares must total 100"
+    );
+
+    let mut distributed = 0u64;
+    for index in 0..config.total_transactions {
+        let record: DistributionRecord = env
+            .fetch(&pda::distribution_record(index).0)
+            .unwrap_or_else(|| panic!("distribution record {index} missing"));
+        assert_eq!(
+            record.creator_amount + record.platform_amount + record.treasury_amount,
+            record.total_amount,
+            "distribution {index} splits do not add up"
+        );
+        distributed += record.total_amount;
+    }
+    assert_eq!(
+        distributed, config.total_distributed,
+        "total_distributed disagrees with the distribution records"
+    );
+}
+
+// Helper function: each escrow PDA holds exactly what its request still owes
+fn check_escrow(env: &FuzzEnv) {
+    for request in &env.requests {
+        let state: ServiceRequest = env
+            .fetch(request)
+            .unwrap_or_else(|| panic!("service request {request} disappeared"));
+        let escrowed = env.balance(&pda::escrow_account(request).0);
+
+        match state.status {
+            RequestStatus::Approved | RequestStatus::Cancelled | RequestStatus::Resolved => {
+                assert_eq!(
+                    escrowed, 0,
+                    "settled request {request} left funds in escrow"
+                )
+            }
+            RequestStatus::Pending
+            | RequestStatus::InProgress
+            | RequestStatus::Completed
+            | RequestStatus::Disputed => assert_eq!(
+                escrowed, state.amount,
+                "open request {request} escrow does not match its amount"
+            ),
+        }
+    }
+}
+
+// Helper function: each agent's open request count matches its unsettled requests
+fn check_request_counts(env: &FuzzEnv) {
+    for agent_id in &env.agents {
+        let open = env
+            .requests
+            .iter()
+            .filter_map(|request| env.fetch::<ServiceRequest>(request))
+            .filter(|state| state.agent_id == *agent_id)
+            .filter(|state| {
+                !matches!(
+                    state.status,
+                    RequestStatus::Approved | RequestStatus::Cancelled | RequestStatus::Resolved
+                )
+            })
+            .count() as u32;
+        let counted = env
+            .fetch::<AgentRequestCount>(&pda::agent_requests(agent_id).0)
+            .map(|count| count.open_requests)
+            .unwrap_or_default();
+        assert_eq!(
+            counted, open,
+            "agent {agent_id} counts {counted} open requests but has {open}"
+        );
+    }
+}
+
+// Helper function: aggregates never count more ratings than were accepted and stay on the 1-5 scale
+//
+// Moderation may remove ratings and recomputes the average from its rounded
+// value, so only the upper bounds hold for the count and the average.
+fn check_reputation(env: &FuzzEnv) {
+    for agent_id in &env.agents {
+        let Some(profile) = env.fetch::<AgentReputationProfile>(&pda::agent_reputation(agent_id).0)
+        else {
+            continue;
+        };
+        let expected = env.ratings.get(agent_id).copied().unwrap_or_default();
+        assert!(
+            profile.total_ratings <= expected,
+            "agent {agent_id} counts {} ratings but only {expected} were accepted",
+            profile.total_ratings
+        );
+        assert!(
+            profile.average_rating <= 5,
+            "agent {agent_id} average {} out of range",
+            profile.average_rating
+        );
+        if profile.total_ratings > 0 {
+            for score in [
+                profile.quality_score,
+                profile.speed_score,
+                profile.value_score,
+            ] {
+                assert!(
+                    (1..=5).contains(&score),
+                    "agent {agent_id} score {score} out of range"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,14 @@
+//! Fuzzing harness for the AgentMarket programs.
+//!
+//! Each honggfuzz target under `fuzz_targets/` decodes the fuzzer's bytes into
+//! a sequence of [`actions::Action`]s (well-formed instructions with arbitrary
+//! arguments and signers) or [`actions::RawInstruction`]s (arbitrary data and
+//! account permutations), executes them in LiteSVM against the registry,
+//! escrow, reputation, and royalty programs, and checks the invariants in
+//! [`invariants`] after every step. Programs are loaded from the same SBF
+//! builds as the integration tests, so run `anchor build` first, then e.g.
+//! `cargo hfuzz run request_flow`.
+
+pub mod actions;
+pub mod env;
+pub mod invariants;
@@ -0,0 +1,6 @@
+//! End-to-end tests for the AgentMarket programs live under `tests/`.
+//!
+//! They load the SBF builds from `programs/target/deploy` (or `$SBF_OUT_DIR`),
+//! so run `anchor build` in `programs/` first. The Metaplex token metadata
+//! program is read from `programs/metadata_program.so`, the same file the
+//! Anchor test validator loads as genesis.
@@ -0,0 +1,565 @@
+//! Shared LiteSVM harness: loads the programs and initializes every config.
+
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use agent_registry::{AgentEndpoint, AgentProfile, PricingModel, RegistryConfig};
+use agentmarket_sdk::instructions::registry::{self, RegisterAgentArgs};
+use agentmarket_sdk::instructions::royalty::{self, Shares};
+use agentmarket_sdk::instructions::{escrow, guardian, reputation};
+use agentmarket_sdk::{
+    pda, Instruction, Pubkey, ACCOUNT_COMPRESSION_PROGRAM_ID, BUBBLEGUM_PROGRAM_ID,
+    ESCROW_PROGRAM_ID, GUARDIAN_PROGRAM_ID, NOOP_PROGRAM_ID, REGISTRY_PROGRAM_ID,
+    REPUTATION_PROGRAM_ID, ROYALTY_PROGRAM_ID, SLA_ORACLE_PROGRAM_ID, TOKEN_AUTH_RULES_PROGRAM_ID,
+    TOKEN_METADATA_PROGRAM_ID,
+};
+use anchor_lang::prelude::{Clock, Rent};
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::solana_program::instruction::InstructionError;
+use anchor_lang::solana_program::native_token::LAMPORTS_PER_SOL;
+use anchor_lang::solana_program::slot_hashes::SlotHashes;
+use anchor_lang::solana_program::system_instruction;
+use anchor_lang::{AccountDeserialize, AccountSerialize};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::associated_token::spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use anchor_spl::token::spl_token;
+use litesvm::types::TransactionResult;
+use litesvm::LiteSVM;
+use marketplace_escrow::{DisputePanel, ServiceRequest};
+use solana_account::Account;
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+use solana_transaction_error::TransactionError;
+
+/// Royalty split configured by [`TestEnv::new`]
+pub const SHARES: Shares = Shares {
+    creator: 80,
+    platform: 15,
+    treasury: 5,
+};
+
+/// Stake each agent locks at registration; the admin is the slash authority
+pub const LISTING_STAKE: u64 = LAMPORTS_PER_SOL / 10;
+/// Daily featured listing price set in the registry config
+pub const FEATURED_PRICE_PER_DAY: u64 = LAMPORTS_PER_SOL / 100;
+/// Seconds an agent may go without a heartbeat before it can be marked stale
+pub const HEARTBEAT_WINDOW: i64 = 60 * 60;
+
+/// A fresh chain with all programs deployed and their configs initialized
+pub struct TestEnv {
+    pub svm: LiteSVM,
+    /// Guardian authority, royalty admin, reputation admin, slash authority, and verifier
+    pub admin: Keypair,
+    pub platform_wallet: Pubkey,
+    pub treasury_wallet: Pubkey,
+}
+
+impl TestEnv {
+    pub fn new() -> Self {
+        let mut svm = LiteSVM::new();
+        for (program_id, name) in [
+            (REGISTRY_PROGRAM_ID, "agent_registry"),
+            (ESCROW_PROGRAM_ID, "marketplace_escrow"),
+            (REPUTATION_PROGRAM_ID, "reputation_system"),
+            (ROYALTY_PROGRAM_ID, "royalty_splitter"),
+            (GUARDIAN_PROGRAM_ID, "protocol_guardian"),
+        ] {
+            load_program(
+                &mut svm,
+                program_id,
+                deploy_dir().join(format!("{name}.so")),
+            );
+        }
+        for (program_id, name) in [
+            (TOKEN_METADATA_PROGRAM_ID, "metadata_program"),
+            (BUBBLEGUM_PROGRAM_ID, "bubblegum_program"),
+            (
+                ACCOUNT_COMPRESSION_PROGRAM_ID,
+                "account_compression_program",
+            ),
+            (NOOP_PROGRAM_ID, "noop_program"),
+            (TOKEN_AUTH_RULES_PROGRAM_ID, "token_auth_rules_program"),
+        ] {
+            load_program(
+                &mut svm,
+                program_id,
+                programs_dir().join(format!("{name}.so")),
+            );
+        }
+
+        let admin = Keypair::new();
+        let platform_wallet = Pubkey::new_unique();
+        let treasury_wallet = Pubkey::new_unique();
+        // Payout wallets start funded so small splits never leave them below rent exemption
+        for account in [admin.pubkey(), platform_wallet, treasury_wallet] {
+            svm.airdrop(&account, 10 * LAMPORTS_PER_SOL).unwrap();
+        }
+
+        let mut env = Self {
+            svm,
+            admin,
+            platform_wallet,
+            treasury_wallet,
+        };
+
+        let admin_key = env.admin.pubkey();
+        env.send_as_admin(&[
+            guardian::initialize_guardian(admin_key, vec![admin_key]),
+            royalty::initialize_config(admin_key, SHARES, platform_wallet, treasury_wallet),
+            reputation::initialize_config(admin_key),
+            registry::initialize_registry_config(
+                admin_key,
+                LISTING_STAKE,
+                admin_key,
+                FEATURED_PRICE_PER_DAY,
+                admin_key,
+                HEARTBEAT_WINDOW,
+                0,
+            ),
+        ])
+        .expect("config initialization failed");
+        env.send_as_admin(&[registry::initialize_collection(
+            admin_key,
+            "AgentMarket Agents".into(),
+            "AGENT".into(),
+            String::new(),
+        )])
+        .expect("collection initialization failed");
+        env.send_as_admin(&[registry::add_capability(
+            admin_key,
+            "summarization".into(),
+            "Summarization".into(),
+            String::new(),
+        )])
+        .expect("capability setup failed");
+
+        env
+    }
+
+    /// A new keypair holding `sol` SOL
+    pub fn funded_keypair(&mut self, sol: u64) -> Keypair {
+        let keypair = Keypair::new();
+        self.svm
+            .airdrop(&keypair.pubkey(), sol * LAMPORTS_PER_SOL)
+            .unwrap();
+        keypair
+    }
+
+    /// Sign and process `instructions`; the first signer pays the fee
+    pub fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> TransactionResult {
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&signers[0].pubkey()),
+            signers,
+            self.svm.latest_blockhash(),
+        );
+        let result = self.svm.send_transaction(transaction);
+        // Identical follow-up transactions would otherwise be rejected as duplicates
+        self.svm.expire_blockhash();
+        result
+    }
+
+    pub fn send_as_admin(&mut self, instructions: &[Instruction]) -> TransactionResult {
+        let admin = self.admin.insecure_clone();
+        self.send(instructions, &[&admin])
+    }
+
+    pub fn fetch<T: AccountDeserialize>(&self, address: &Pubkey) -> T {
+        let account = self
+            .svm
+            .get_account(address)
+            .unwrap_or_else(|| panic!("account {address} does not exist"));
+        T::try_deserialize(&mut account.data.as_slice()).expect("account failed to decode")
+    }
+
+    pub fn balance(&self, address: &Pubkey) -> u64 {
+        self.svm.get_balance(address).unwrap_or_default()
+    }
+
+    /// Register an agent for `creator` and create its reputation profile; returns the agent id
+    pub fn register_agent(&mut self, creator: &Keypair) -> Pubkey {
+        let mint = Keypair::new();
+        let agent_id = pda::agent_profile(&creator.pubkey()).0;
+        let config: RegistryConfig = self.fetch(&pda::registry_config().0);
+
+        self.send(
+            &[registry::register_agent(
+                creator.pubkey(),
+                mint.pubkey(),
+                &config,
+                RegisterAgentArgs {
+                    name: agent_name(&creator.pubkey()),
+                    ..agent_args()
+                },
+            )],
+            &[creator, &mint],
+        )
+        .expect("agent registration failed");
+
+        agent_id
+    }
+
+    /// Allocate a small Merkle tree and make it the compressed agent tree; returns its address
+    pub fn init_agent_tree(&mut self) -> Pubkey {
+        let tree = Keypair::new();
+        let (max_depth, max_buffer_size) = (5, 8);
+        let space = registry::merkle_tree_space(max_depth, max_buffer_size);
+        let admin = self.admin.insecure_clone();
+        self.send(
+            &[
+                system_instruction::create_account(
+                    &admin.pubkey(),
+                    &tree.pubkey(),
+                    Rent::default().minimum_balance(space as usize),
+                    space,
+                    &ACCOUNT_COMPRESSION_PROGRAM_ID,
+                ),
+                registry::initialize_agent_tree(
+                    admin.pubkey(),
+                    tree.pubkey(),
+                    max_depth,
+                    max_buffer_size,
+                ),
+            ],
+            &[&admin, &tree],
+        )
+        .expect("agent tree initialization failed");
+        tree.pubkey()
+    }
+
+    /// Create an SPL mint with the admin as mint authority; returns its address
+    pub fn create_mint(&mut self, decimals: u8) -> Pubkey {
+        let mint = Keypair::new();
+        let space = anchor_spl::token::Mint::LEN;
+        let admin = self.admin.insecure_clone();
+        self.send(
+            &[
+                system_instruction::create_account(
+                    &admin.pubkey(),
+                    &mint.pubkey(),
+                    Rent::default().minimum_balance(space),
+                    space as u64,
+                    &spl_token::ID,
+                ),
+                spl_token::instruction::initialize_mint2(
+                    &spl_token::ID,
+                    &mint.pubkey(),
+                    &admin.pubkey(),
+                    None,
+                    decimals,
+                )
+                .unwrap(),
+            ],
+            &[&admin, &mint],
+        )
+        .expect("mint creation failed");
+        mint.pubkey()
+    }
+
+    /// Create an SPL mint as [`TestEnv::create_mint`] does and accept it as a
+    /// payment mint in the registry; returns its address
+    pub fn create_accepted_mint(&mut self, decimals: u8) -> Pubkey {
+        let mint = self.create_mint(decimals);
+        let admin = self.admin.pubkey();
+        self.send_as_admin(&[registry::add_accepted_mint(admin, mint)])
+            .expect("accepting the mint failed");
+        mint
+    }
+
+    /// Create `owner`'s associated token account for `mint` and mint `amount`
+    /// into it; returns its address
+    pub fn token_account(&mut self, owner: &Pubkey, mint: &Pubkey, amount: u64) -> Pubkey {
+        let admin = self.admin.insecure_clone();
+        let address = get_associated_token_address(owner, mint);
+        self.send(
+            &[
+                create_associated_token_account_idempotent(
+                    &admin.pubkey(),
+                    owner,
+                    mint,
+                    &spl_token::ID,
+                ),
+                spl_token::instruction::mint_to(
+                    &spl_token::ID,
+                    mint,
+                    &address,
+                    &admin.pubkey(),
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+            ],
+            &[&admin],
+        )
+        .expect("token account creation failed");
+        address
+    }
+
+    /// Token balance of the SPL token account at `address`
+    pub fn token_balance(&self, address: &Pubkey) -> u64 {
+        self.fetch::<anchor_spl::token::TokenAccount>(address)
+            .amount
+    }
+
+    /// Current cluster time
+    pub fn now(&self) -> i64 {
+        self.svm.get_sysvar::<Clock>().unix_timestamp
+    }
+
+    /// Move the cluster clock `secs` seconds forward
+    pub fn advance_clock(&mut self, secs: i64) {
+        let mut clock = self.svm.get_sysvar::<Clock>();
+        clock.unix_timestamp += secs;
+        self.svm.set_sysvar(&clock);
+    }
+
+    /// Publish a trading Pyth v2 price account quoting `price * 10^expo` USD
+    /// per SOL as of `age` seconds ago; returns its address
+    pub fn pyth_price_feed(&mut self, price: i64, expo: i32, age: i64) -> Pubkey {
+        let feed = Pubkey::new_unique();
+        let now = self.svm.get_sysvar::<Clock>().unix_timestamp;
+        let mut data = vec![0u8; 3_312];
+        data[0..4].copy_from_slice(&0xa1b2_c3d4u32.to_le_bytes());
+        data[4..8].copy_from_slice(&2u32.to_le_bytes());
+        data[8..12].copy_from_slice(&3u32.to_le_bytes());
+        data[20..24].copy_from_slice(&expo.to_le_bytes());
+        data[96..104].copy_from_slice(&(now - age).to_le_bytes());
+        data[208..216].copy_from_slice(&price.to_le_bytes());
+        data[224..228].copy_from_slice(&1u32.to_le_bytes());
+        self.svm
+            .set_account(
+                feed,
+                Account {
+                    lamports: Rent::default().minimum_balance(data.len()),
+                    data,
+                    owner: agent_registry::PYTH_ORACLE_IDS[0],
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            )
+            .unwrap();
+        feed
+    }
+
+    /// Publish the SLA oracle's record of `agent_id`, recommending requests
+    /// time out after `recommended_timeout` seconds
+    pub fn agent_sla(&mut self, agent_id: Pubkey, recommended_timeout: i64) {
+        let mut data = Vec::new();
+        sla_oracle::AgentSla {
+            agent_id,
+            last_round: 0,
+            uptime_bps: 10_000,
+            latency_ms: 250,
+            rounds_observed: 1,
+            breach_count: 0,
+            consecutive_breaches: 0,
+            recommended_timeout,
+            updated_at: self.now(),
+        }
+        .try_serialize(&mut data)
+        .unwrap();
+        self.svm
+            .set_account(
+                pda::agent_sla(&agent_id).0,
+                Account {
+                    lamports: Rent::default().minimum_balance(data.len()),
+                    data,
+                    owner: SLA_ORACLE_PROGRAM_ID,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            )
+            .unwrap();
+    }
+
+    /// Open a request from `user` against `agent_id`, escrowing `amount`; returns the request address
+    pub fn open_request(&mut self, user: &Keypair, agent_id: Pubkey, amount: u64) -> Pubkey {
+        self.send(
+            &[escrow::create_service_request(
+                user.pubkey(),
+                agent_id,
+                amount,
+                "Summarize the attached paper".into(),
+            )],
+            &[user],
+        )
+        .expect("request creation failed");
+
+        pda::service_request(&user.pubkey(), &agent_id).0
+    }
+
+    /// Open a request and have the agent's creator submit a result for it
+    pub fn completed_request(
+        &mut self,
+        user: &Keypair,
+        creator: &Keypair,
+        agent_id: Pubkey,
+        amount: u64,
+    ) -> Pubkey {
+        let request = self.open_request(user, agent_id, amount);
+        let nft_mint = self.fetch::<AgentProfile>(&agent_id).nft_mint;
+        self.send(
+            &[escrow::submit_result(
+                request,
+                agent_id,
+                nft_mint,
+                creator.pubkey(),
+                "A three-paragraph summary".into(),
+            )],
+            &[creator],
+        )
+        .expect("result submission failed");
+        request
+    }
+
+    /// Open a request, submit a result for it, and have the user dispute it
+    pub fn disputed_request(
+        &mut self,
+        user: &Keypair,
+        creator: &Keypair,
+        agent_id: Pubkey,
+        amount: u64,
+    ) -> Pubkey {
+        let request = self.completed_request(user, creator, agent_id, amount);
+        self.send(
+            &[escrow::dispute_result(
+                request,
+                user.pubkey(),
+                "Summary misses the conclusions".into(),
+            )],
+            &[user],
+        )
+        .expect("dispute failed");
+        request
+    }
+
+    /// Fund `count` keypairs and register each in the jury pool
+    pub fn jurors(&mut self, count: usize) -> Vec<Keypair> {
+        (0..count)
+            .map(|_| {
+                let juror = self.funded_keypair(5);
+                self.send(&[escrow::register_juror(juror.pubkey())], &[&juror])
+                    .expect("juror registration failed");
+                juror
+            })
+            .collect()
+    }
+
+    /// Move one slot past the panel slot a disputed request committed to and
+    /// publish a fresh hash for it to the `SlotHashes` sysvar, which panel
+    /// draws are seeded from
+    pub fn reach_panel_slot(&mut self, request: &Pubkey) {
+        let panel_slot = self
+            .fetch::<ServiceRequest>(request)
+            .panel_slot
+            .expect("request is not disputed");
+        self.svm.warp_to_slot(panel_slot + 1);
+        self.svm.set_sysvar(&SlotHashes::new(&[
+            (panel_slot + 1, Hash::new_unique()),
+            (panel_slot, Hash::new_unique()),
+        ]));
+    }
+
+    /// Reach the request's panel slot and have `caller` draw a panel for the
+    /// disputed request; returns the drawn jurors out of `jurors`, in panel order
+    pub fn draw_panel(
+        &mut self,
+        caller: &Keypair,
+        request: Pubkey,
+        agent_id: Pubkey,
+        jurors: &[Keypair],
+    ) -> Vec<Keypair> {
+        self.reach_panel_slot(&request);
+        self.send(
+            &[escrow::draw_panel(caller.pubkey(), request, agent_id)],
+            &[caller],
+        )
+        .expect("panel draw failed");
+
+        let panel: DisputePanel = self.fetch(&pda::dispute_panel(&request).0);
+        panel
+            .jurors
+            .iter()
+            .map(|drawn| {
+                jurors
+                    .iter()
+                    .find(|juror| juror.pubkey() == *drawn)
+                    .expect("panel drew an unknown juror")
+                    .insecure_clone()
+            })
+            .collect()
+    }
+}
+
+/// Name `TestEnv::register_agent` gives `creator`'s agent; names are claimed,
+/// so agents in one test need different ones
+pub fn agent_name(creator: &Pubkey) -> String {
+    format!("Summarizer {}", &creator.to_string()[..8])
+}
+
+/// Registration arguments for a test agent
+pub fn agent_args() -> RegisterAgentArgs {
+    RegisterAgentArgs {
+        name: "Summarizer".into(),
+        description: "Summarizes long documents".into(),
+        capabilities: vec!["summarization".into()],
+        pricing: PricingModel::PerQuery {
+            price: LAMPORTS_PER_SOL,
+            payment_mint: None,
+        },
+        endpoints: vec![AgentEndpoint {
+            url: "https://agents.example/summarizer".into(),
+            region: "us-east".into(),
+            priority: 0,
+        }],
+        ipfs_hash: "QmTestHash".into(),
+        symbol: "AGENT".into(),
+        uri: "https://agents.example/summarizer.json".into(),
+    }
+}
+
+/// Assert that a transaction failed with the given Anchor error code
+pub fn assert_anchor_error(result: TransactionResult, code: impl Into<u32>) {
+    let code = code.into();
+    match result {
+        Ok(_) => panic!("expected error {code}, but the transaction succeeded"),
+        Err(failed) => match failed.err {
+            TransactionError::InstructionError(_, InstructionError::Custom(actual)) => {
+                assert_eq!(actual, code, "logs: {:#?}", failed.meta.logs)
+            }
+            other => panic!(
+                "expected error {code}, got {other:?}; logs: {:#?}",
+                failed.meta.logs
+            ),
+        },
+    }
+}
+
+// Helper function to locate the Anchor workspace
+fn programs_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../programs")
+}
+
+// Helper function to locate the SBF builds, honouring the usual SBF_OUT_DIR override
+fn deploy_dir() -> PathBuf {
+    std::env::var_os("SBF_OUT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| programs_dir().join("target/deploy"))
+}
+
+// Helper function to deploy one program, failing with a hint when it has not been built
+fn load_program(svm: &mut LiteSVM, program_id: Pubkey, path: PathBuf) {
+    svm.add_program_from_file(program_id, &path)
+        .unwrap_or_else(|e| {
+            panic!(
+                "failed to load {}: {e} (run `anchor build` first)",
+                path.display()
+            )
+        });
+}
@@ -0,0 +1,3544 @@
+//! Disputes, cancellations, pauses, and unauthorized callers across program boundaries.
+
+mod common;
+
+use agent_registry::{
+    AgentEndpoint, CoCreator, ErrorCode as RegistryError, PricingModel, RentTarget, Visibility,
+};
+use agentmarket_sdk::accounts::{
+    AcceptedMint, AgentProfile, AgentReputationProfile, AgentRequestCount, EndpointChallenge,
+    ListingStake, RegistryConfig, ReputationConfig, RequestStatus, RoyaltyConfig, ServiceRequest,
+};
+use agentmarket_sdk::instructions::reputation::{self, RatingScores};
+use agentmarket_sdk::instructions::{escrow, guardian, registry, royalty};
+use agentmarket_sdk::{pda, Instruction, Pubkey, ROYALTY_RULE_SET};
+use anchor_lang::prelude::Clock;
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::solana_program::native_token::LAMPORTS_PER_SOL;
+use anchor_lang::solana_program::slot_hashes::SlotHashes;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::associated_token::spl_associated_token_account::instruction::create_associated_token_account;
+use anchor_spl::token::spl_token;
+use marketplace_escrow::{
+    ErrorCode as EscrowError, PANEL_SIZE, PANEL_SLOT_DELAY, REVIEW_WINDOW, VOTING_WINDOW,
+};
+use reputation_system::ReputationError;
+use royalty_splitter::RoyaltyError;
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+use common::{agent_args, assert_anchor_error, TestEnv, HEARTBEAT_WINDOW, LISTING_STAKE};
+
+#[test]
+fn disputed_request_keeps_funds_escrowed() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let request = env.completed_request(&user, &creator, agent_id, LAMPORTS_PER_SOL);
+
+    env.send(
+        &[escrow::dispute_result(
+            request,
+            user.pubkey(),
+            "Summary misses the conclusions".into(),
+        )],
+        &[&user],
+    )
+    .unwrap();
+
+    let state: ServiceRequest = env.fetch(&request);
+    assert!(state.status == RequestStatus::Disputed);
+    assert_eq!(
+        env.balance(&pda::escrow_account(&request).0),
+        LAMPORTS_PER_SOL
+    );
+
+    // A disputed request can no longer be approved
+    let result = env.send(
+        &[escrow::approve_result(
+            request,
+            agent_id,
+            user.pubkey(),
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::InvalidRequestStatus);
+}
+
+#[test]
+fn cancelled_request_refunds_user() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let request = env.open_request(&user, agent_id, LAMPORTS_PER_SOL);
+
+    let before = env.balance(&user.pubkey());
+    env.send(
+        &[escrow::cancel_request(request, agent_id, user.pubkey())],
+        &[&user],
+    )
+    .unwrap();
+
+    let state: ServiceRequest = env.fetch(&request);
+    assert!(state.status == RequestStatus::Cancelled);
+    assert_eq!(env.balance(&pda::escrow_account(&request).0), 0);
+    // Refund minus the transaction fee
+    assert!(env.balance(&user.pubkey()) > before + LAMPORTS_PER_SOL - LAMPORTS_PER_SOL / 1000);
+
+    // Only pending requests can be cancelled
+    let result = env.send(
+        &[escrow::cancel_request(request, agent_id, user.pubkey())],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::CannotCancelRequest);
+}
+
+#[test]
+fn cancelled_token_requests_refund_only_the_users_token_account() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let usdc = env.create_accepted_mint(6);
+    let user_tokens = env.token_account(&user.pubkey(), &usdc, 5_000_000);
+    env.send(
+        &[escrow::create_token_service_request(
+            user.pubkey(),
+            agent_id,
+            usdc,
+            5_000_000,
+            "Summarize the attached paper".into(),
+        )],
+        &[&user],
+    )
+    .unwrap();
+    let request = pda::service_request(&user.pubkey(), &agent_id).0;
+
+    // A token account of another mint can't take the refund
+    let other_mint = env.create_mint(6);
+    let other_tokens = env.token_account(&user.pubkey(), &other_mint, 0);
+    let mut ix = escrow::cancel_token_request(request, agent_id, user.pubkey(), usdc);
+    for meta in ix
+        .accounts
+        .iter_mut()
+        .filter(|meta| meta.pubkey == user_tokens)
+    {
+        meta.pubkey = other_tokens;
+    }
+    let result = env.send(&[ix], &[&user]);
+    assert_anchor_error(result, EscrowError::InvalidTokenAccount);
+
+    env.send(
+        &[escrow::cancel_token_request(
+            request,
+            agent_id,
+            user.pubkey(),
+            usdc,
+        )],
+        &[&user],
+    )
+    .unwrap();
+    assert!(env.fetch::<ServiceRequest>(&request).status == RequestStatus::Cancelled);
+    assert_eq!(env.token_balance(&user_tokens), 5_000_000);
+}
+
+#[test]
+fn stray_tokens_in_the_escrow_token_account_do_not_block_a_refund() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let usdc = env.create_accepted_mint(6);
+    let user_tokens = env.token_account(&user.pubkey(), &usdc, 5_000_000);
+    env.send(
+        &[escrow::create_token_service_request(
+            user.pubkey(),
+            agent_id,
+            usdc,
+            5_000_000,
+            "Summarize the attached paper".into(),
+        )],
+        &[&user],
+    )
+    .unwrap();
+    let request = pda::service_request(&user.pubkey(), &agent_id).0;
+
+    // Anyone can send tokens to the escrow's associated token account
+    let escrow_tokens = env.token_account(&pda::escrow_account(&request).0, &usdc, 1);
+
+    env.send(
+        &[escrow::cancel_token_request(
+            request,
+            agent_id,
+            user.pubkey(),
+            usdc,
+        )],
+        &[&user],
+    )
+    .unwrap();
+    assert!(env.fetch::<ServiceRequest>(&request).status == RequestStatus::Cancelled);
+    assert_eq!(env.token_balance(&user_tokens), 5_000_000);
+    // The account holding the stray unit is left open instead of failing the refund
+    assert_eq!(env.token_balance(&escrow_tokens), 1);
+}
+
+#[test]
+fn token_requests_need_an_accepted_mint_and_cannot_buy_lamport_prices() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    let junk = env.create_mint(9);
+    let amount = 10 * LAMPORTS_PER_SOL;
+    let user_tokens = env.token_account(&user.pubkey(), &junk, amount);
+
+    let result = env.send(
+        &[escrow::create_token_service_request(
+            user.pubkey(),
+            agent_id,
+            junk,
+            amount,
+            "Summarize the attached paper".into(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::PaymentMintNotAccepted);
+    assert_eq!(env.token_balance(&user_tokens), amount);
+
+    // Even an accepted token's base units don't cover a price quoted in lamports
+    env.send_as_admin(&[registry::add_accepted_mint(env.admin.pubkey(), junk)])
+        .unwrap();
+    env.send(
+        &[escrow::create_token_service_request(
+            user.pubkey(),
+            agent_id,
+            junk,
+            amount,
+            "Summarize the attached paper".into(),
+        )],
+        &[&user],
+    )
+    .unwrap();
+    let request = pda::service_request(&user.pubkey(), &agent_id).0;
+    env.send(
+        &[registry::add_service_offering(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            registry::ServiceOfferingArgs {
+                offering_id: 7,
+                name: "Deep research".into(),
+                description: "A cited literature review".into(),
+                price: LAMPORTS_PER_SOL,
+                turnaround_secs: 86_400,
+            },
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let result = env.send(
+        &[escrow::select_service_offering(
+            request,
+            pda::service_offering(&agent_id, 7).0,
+            user.pubkey(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::PaymentMintMismatch);
+}
+
+#[test]
+fn requests_expire_only_once_their_deadline_passes() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let now = env.now();
+
+    let result = env.send(
+        &[escrow::create_service_request_with_deadline(
+            user.pubkey(),
+            agent_id,
+            LAMPORTS_PER_SOL,
+            "Summarize the attached paper".into(),
+            now,
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::InvalidDeadline);
+
+    env.send(
+        &[escrow::create_service_request_with_deadline(
+            user.pubkey(),
+            agent_id,
+            LAMPORTS_PER_SOL,
+            "Summarize the attached paper".into(),
+            now + 60,
+        )],
+        &[&user],
+    )
+    .unwrap();
+    let request = pda::service_request(&user.pubkey(), &agent_id).0;
+    let result = env.send(
+        &[escrow::expire_request(
+            user.pubkey(),
+            request,
+            agent_id,
+            user.pubkey(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::RequestNotExpired);
+
+    // Past the deadline the agent can no longer deliver
+    env.advance_clock(61);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    let result = env.send(
+        &[escrow::submit_result(
+            request,
+            agent_id,
+            nft_mint,
+            creator.pubkey(),
+            "A three-paragraph summary".into(),
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, EscrowError::DeadlinePassed);
+}
+
+#[test]
+fn only_the_agent_manager_or_its_operators_submit_results() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let stranger = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    let request = env.open_request(&user, agent_id, LAMPORTS_PER_SOL);
+
+    // A wallet that doesn't hold the agent's NFT
+    let result = env.send(
+        &[escrow::submit_result(
+            request,
+            agent_id,
+            nft_mint,
+            stranger.pubkey(),
+            "Not my agent".into(),
+        )],
+        &[&stranger],
+    );
+    assert_anchor_error(result, EscrowError::NotAgentManager);
+
+    // Nor can it pass itself off as an operator the creator never registered
+    let result = env.send(
+        &[escrow::submit_result_as_operator(
+            request,
+            agent_id,
+            nft_mint,
+            creator.pubkey(),
+            stranger.pubkey(),
+            "Not my agent".into(),
+        )],
+        &[&stranger],
+    );
+    assert_anchor_error(result, EscrowError::NotAgentManager);
+
+    // Once the agent has a management authority, the holder no longer answers for it
+    let vault = env.funded_keypair(1);
+    env.send(
+        &[registry::set_agent_authority(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            Some(vault.pubkey()),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let result = env.send(
+        &[escrow::submit_result(
+            request,
+            agent_id,
+            nft_mint,
+            creator.pubkey(),
+            "A three-paragraph summary".into(),
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, EscrowError::NotAgentManager);
+    assert!(env.fetch::<ServiceRequest>(&request).status == RequestStatus::Pending);
+}
+
+#[test]
+fn auto_release_waits_for_the_review_window_and_pays_the_configured_fee_wallets() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let cranker = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let request = env.completed_request(&user, &creator, agent_id, LAMPORTS_PER_SOL);
+
+    // The user still has time to approve or dispute
+    env.advance_clock(REVIEW_WINDOW - 1);
+    let result = env.send(
+        &[escrow::auto_release(
+            cranker.pubkey(),
+            request,
+            agent_id,
+            user.pubkey(),
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+        )],
+        &[&cranker],
+    );
+    assert_anchor_error(result, EscrowError::ReviewWindowOpen);
+
+    // Whoever releases the payment can't send the fees to themselves
+    env.advance_clock(1);
+    let result = env.send(
+        &[escrow::auto_release(
+            cranker.pubkey(),
+            request,
+            agent_id,
+            user.pubkey(),
+            creator.pubkey(),
+            cranker.pubkey(),
+            cranker.pubkey(),
+        )],
+        &[&cranker],
+    );
+    assert_anchor_error(result, EscrowError::FeeWalletMismatch);
+}
+
+#[test]
+fn approving_users_pay_fees_only_to_the_configured_wallets() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let request = env.completed_request(&user, &creator, agent_id, LAMPORTS_PER_SOL);
+
+    // The user can't keep the platform and treasury fees for themselves
+    let result = env.send(
+        &[escrow::approve_result(
+            request,
+            agent_id,
+            user.pubkey(),
+            creator.pubkey(),
+            user.pubkey(),
+            user.pubkey(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::FeeWalletMismatch);
+    assert!(env.fetch::<ServiceRequest>(&request).status == RequestStatus::Completed);
+}
+
+#[test]
+fn only_the_arbiter_resolves_disputed_requests() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let arbiter = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let request = env.completed_request(&user, &creator, agent_id, LAMPORTS_PER_SOL);
+
+    let result = env.send(
+        &[escrow::set_arbiter(creator.pubkey(), creator.pubkey())],
+        &[&creator],
+    );
+    assert_anchor_error(result, EscrowError::UnauthorizedArbiterUpdate);
+    let admin = env.admin.pubkey();
+    env.send_as_admin(&[escrow::set_arbiter(admin, arbiter.pubkey())])
+        .unwrap();
+
+    let resolve = |arbiter| {
+        escrow::resolve_dispute(
+            arbiter,
+            request,
+            agent_id,
+            user.pubkey(),
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+            escrow::DisputeOutcome::AwardAgent,
+        )
+    };
+    let (by_arbiter, by_creator) = (resolve(arbiter.pubkey()), resolve(creator.pubkey()));
+
+    // Nothing to rule on until the user disputes the result
+    let result = env.send(&[by_arbiter.clone()], &[&arbiter]);
+    assert_anchor_error(result, EscrowError::RequestNotDisputed);
+
+    env.send(
+        &[escrow::dispute_result(
+            request,
+            user.pubkey(),
+            "Summary misses the conclusions".into(),
+        )],
+        &[&user],
+    )
+    .unwrap();
+    let result = env.send(&[by_creator], &[&creator]);
+    assert_anchor_error(result, EscrowError::UnauthorizedArbiter);
+
+    // A split can't release more than the whole payment
+    let result = env.send(
+        &[escrow::resolve_dispute(
+            arbiter.pubkey(),
+            request,
+            agent_id,
+            user.pubkey(),
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+            escrow::DisputeOutcome::Split { agent_bps: 10_001 },
+        )],
+        &[&arbiter],
+    );
+    assert_anchor_error(result, EscrowError::InvalidDisputeSplit);
+    assert!(env.fetch::<ServiceRequest>(&request).status == RequestStatus::Disputed);
+}
+
+#[test]
+fn juror_panels_are_drawn_from_the_slot_committed_to_at_dispute() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let cranker = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let jurors = env.jurors(3);
+    let disputed_at = env.svm.get_sysvar::<Clock>().slot;
+    let request = env.disputed_request(&user, &creator, agent_id, LAMPORTS_PER_SOL);
+    let panel_slot = env.fetch::<ServiceRequest>(&request).panel_slot.unwrap();
+    assert_eq!(panel_slot, disputed_at + PANEL_SLOT_DELAY);
+    let draw = escrow::draw_panel(cranker.pubkey(), request, agent_id);
+
+    // No one can draw before the committed slot's hash exists
+    let result = env.send(&[draw.clone()], &[&cranker]);
+    assert_anchor_error(result, EscrowError::PanelSlotNotReached);
+    let result = env.send(&[escrow::renew_panel_slot(request)], &[&cranker]);
+    assert_anchor_error(result, EscrowError::PanelSlotNotExpired);
+
+    // Nor from a later slot's hash once the committed one has aged out
+    let later = panel_slot + 600;
+    env.svm.warp_to_slot(later);
+    env.svm
+        .set_sysvar(&SlotHashes::new(&[(later, Hash::new_unique())]));
+    let result = env.send(&[draw.clone()], &[&cranker]);
+    assert_anchor_error(result, EscrowError::PanelSlotExpired);
+
+    // Instead the draw is committed to a slot still to come
+    env.send(&[escrow::renew_panel_slot(request)], &[&cranker])
+        .unwrap();
+    assert_eq!(
+        env.fetch::<ServiceRequest>(&request).panel_slot,
+        Some(later + PANEL_SLOT_DELAY)
+    );
+    let panel = env.draw_panel(&cranker, request, agent_id, &jurors);
+    assert_eq!(panel.len(), PANEL_SIZE);
+}
+
+#[test]
+fn juror_panels_need_enough_unconflicted_jurors_and_only_count_votes_in_time() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let cranker = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let request = env.disputed_request(&user, &creator, agent_id, LAMPORTS_PER_SOL);
+    let mut jurors = env.jurors(2);
+
+    // The agent's creator joins the pool but can't sit on its own dispute
+    env.send(&[escrow::register_juror(creator.pubkey())], &[&creator])
+        .unwrap();
+    env.reach_panel_slot(&request);
+    let result = env.send(
+        &[escrow::draw_panel(cranker.pubkey(), request, agent_id)],
+        &[&cranker],
+    );
+    assert_anchor_error(result, EscrowError::NotEnoughJurors);
+
+    jurors.extend(env.jurors(1));
+    let panel = env.draw_panel(&cranker, request, agent_id, &jurors);
+    let vote = |juror: &Keypair| escrow::cast_vote(juror.pubkey(), request, escrow::Verdict::Agent);
+    let result = env.send(&[vote(&creator)], &[&creator]);
+    assert_anchor_error(result, EscrowError::NotOnPanel);
+    env.send(&[vote(&panel[0])], &[&panel[0]]).unwrap();
+    let result = env.send(&[vote(&panel[0])], &[&panel[0]]);
+    assert_anchor_error(result, EscrowError::AlreadyVoted);
+
+    // Panelists are locked in until the vote settles, which waits for the window
+    let result = env.send(&[escrow::leave_jury(panel[1].pubkey())], &[&panel[1]]);
+    assert_anchor_error(result, EscrowError::JurorOnPanel);
+    let settle = escrow::settle_panel(
+        cranker.pubkey(),
+        request,
+        agent_id,
+        user.pubkey(),
+        cranker.pubkey(),
+        creator.pubkey(),
+        env.platform_wallet,
+        env.treasury_wallet,
+    );
+    let result = env.send(&[settle], &[&cranker]);
+    assert_anchor_error(result, EscrowError::VotingOpen);
+
+    env.advance_clock(VOTING_WINDOW);
+    let result = env.send(&[vote(&panel[1])], &[&panel[1]]);
+    assert_anchor_error(result, EscrowError::VotingClosed);
+}
+
+#[test]
+fn only_the_user_closes_and_only_settled_requests() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let request = env.disputed_request(&user, &creator, agent_id, LAMPORTS_PER_SOL);
+
+    // A dispute still holds the payment until it is resolved
+    let result = env.send(&[escrow::close_request(request, user.pubkey())], &[&user]);
+    assert_anchor_error(result, EscrowError::RequestNotSettled);
+
+    let result = env.send(
+        &[escrow::close_request(request, creator.pubkey())],
+        &[&creator],
+    );
+    assert_anchor_error(result, EscrowError::UnauthorizedUser);
+    assert_eq!(
+        env.balance(&pda::escrow_account(&request).0),
+        LAMPORTS_PER_SOL
+    );
+}
+
+#[test]
+fn settled_requests_stay_open_while_the_protocol_is_paused() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let request = env.open_request(&user, agent_id, LAMPORTS_PER_SOL);
+    env.send(
+        &[escrow::cancel_request(request, agent_id, user.pubkey())],
+        &[&user],
+    )
+    .unwrap();
+
+    let admin = env.admin.pubkey();
+    env.send_as_admin(&[guardian::pause_protocol(admin)])
+        .unwrap();
+    let result = env.send(&[escrow::close_request(request, user.pubkey())], &[&user]);
+    assert_anchor_error(result, EscrowError::ProtocolPaused);
+    assert!(env.fetch::<ServiceRequest>(&request).status == RequestStatus::Cancelled);
+
+    env.send_as_admin(&[guardian::unpause_protocol(admin)])
+        .unwrap();
+    env.send(&[escrow::close_request(request, user.pubkey())], &[&user])
+        .unwrap();
+    assert_eq!(env.balance(&request), 0);
+}
+
+#[test]
+fn disputes_can_slash_the_listing_stake() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let stake = pda::listing_stake(&agent_id).0;
+    assert_eq!(env.fetch::<ListingStake>(&stake).amount, LISTING_STAKE);
+    let request = env.completed_request(&user, &creator, agent_id, LAMPORTS_PER_SOL);
+    let opened: ServiceRequest = env.fetch(&request);
+
+    let slash = |authority, amount| registry::slash_stake(authority, request, &opened, amount);
+    let admin = env.admin.pubkey();
+    // Only disputed requests are ruled on
+    let result = env.send_as_admin(&[slash(admin, LISTING_STAKE / 3)]);
+    assert_anchor_error(result, RegistryError::RequestNotDisputed);
+
+    env.send(
+        &[escrow::dispute_result(
+            request,
+            user.pubkey(),
+            "Summary misses the conclusions".into(),
+        )],
+        &[&user],
+    )
+    .unwrap();
+    let result = env.send(&[slash(creator.pubkey(), LISTING_STAKE / 2)], &[&creator]);
+    assert_anchor_error(result, RegistryError::UnauthorizedSlash);
+
+    let before = env.balance(&user.pubkey());
+    env.send_as_admin(&[slash(admin, LISTING_STAKE / 2)])
+        .unwrap();
+    assert_eq!(env.balance(&user.pubkey()), before + LISTING_STAKE / 2);
+    let listing_stake: ListingStake = env.fetch(&stake);
+    assert_eq!(listing_stake.amount, LISTING_STAKE / 2);
+    assert_eq!(listing_stake.total_slashed, LISTING_STAKE / 2);
+
+    // Each dispute is ruled on once
+    assert!(env
+        .send_as_admin(&[slash(admin, LISTING_STAKE / 4)])
+        .is_err());
+}
+
+#[test]
+fn requests_reopened_at_a_closed_address_can_be_slashed_again() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let arbiter = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let admin = env.admin.pubkey();
+    env.send_as_admin(&[escrow::set_arbiter(admin, arbiter.pubkey())])
+        .unwrap();
+
+    let request = env.disputed_request(&user, &creator, agent_id, LAMPORTS_PER_SOL);
+    let first: ServiceRequest = env.fetch(&request);
+    env.send_as_admin(&[registry::slash_stake(
+        admin,
+        request,
+        &first,
+        LISTING_STAKE / 4,
+    )])
+    .unwrap();
+    env.send(
+        &[escrow::resolve_dispute(
+            arbiter.pubkey(),
+            request,
+            agent_id,
+            user.pubkey(),
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+            escrow::DisputeOutcome::RefundUser,
+        )],
+        &[&arbiter],
+    )
+    .unwrap();
+    env.send(&[escrow::close_request(request, user.pubkey())], &[&user])
+        .unwrap();
+
+    // The next request reuses the address, but not the first one's slash record
+    env.advance_clock(60);
+    assert_eq!(
+        env.disputed_request(&user, &creator, agent_id, LAMPORTS_PER_SOL),
+        request
+    );
+    let second: ServiceRequest = env.fetch(&request);
+    let before = env.balance(&user.pubkey());
+    env.send_as_admin(&[registry::slash_stake(
+        admin,
+        request,
+        &second,
+        LISTING_STAKE / 4,
+    )])
+    .unwrap();
+    assert_eq!(env.balance(&user.pubkey()), before + LISTING_STAKE / 4);
+    let listing_stake: ListingStake = env.fetch(&pda::listing_stake(&agent_id).0);
+    assert_eq!(listing_stake.total_slashed, LISTING_STAKE / 2);
+    assert_ne!(
+        pda::stake_slash(&request, first.created_at).0,
+        pda::stake_slash(&request, second.created_at).0
+    );
+}
+
+#[test]
+fn deregistration_waits_for_open_requests() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let profile = env.fetch::<AgentProfile>(&agent_id);
+    let request = env.open_request(&user, agent_id, LAMPORTS_PER_SOL);
+
+    let counter: AgentRequestCount = env.fetch(&pda::agent_requests(&agent_id).0);
+    assert_eq!(counter.open_requests, 1);
+    let result = env.send(
+        &[registry::deregister_agent(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            &profile.name,
+            &profile.capabilities,
+            &profile.languages,
+            None,
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::AgentHasOpenRequests);
+
+    // Settling the request frees the agent to leave
+    env.send(
+        &[escrow::cancel_request(request, agent_id, user.pubkey())],
+        &[&user],
+    )
+    .unwrap();
+    let counter: AgentRequestCount = env.fetch(&pda::agent_requests(&agent_id).0);
+    assert_eq!(counter.open_requests, 0);
+
+    let profile_rent = env.balance(&agent_id);
+    let stake = env.balance(&pda::listing_stake(&agent_id).0);
+    let before = env.balance(&creator.pubkey());
+    env.send(
+        &[registry::deregister_agent(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            &profile.name,
+            &profile.capabilities,
+            &profile.languages,
+            None,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    assert_eq!(env.balance(&agent_id), 0);
+    let index = pda::capability_index("summarization", &agent_id).0;
+    assert_eq!(env.balance(&index), 0);
+    assert_eq!(env.balance(&pda::listing_stake(&agent_id).0), 0);
+    // Profile, index, and token account rent and the listing stake come back, less the transaction fee
+    assert!(
+        env.balance(&creator.pubkey()) > before + profile_rent + stake - LAMPORTS_PER_SOL / 1000
+    );
+}
+
+#[test]
+fn nft_holder_controls_the_agent() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let buyer = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    // Sell the NFT
+    let buyer_nft_account = get_associated_token_address(&buyer.pubkey(), &nft_mint);
+    env.send(
+        &[
+            create_associated_token_account(
+                &buyer.pubkey(),
+                &buyer.pubkey(),
+                &nft_mint,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::transfer(
+                &spl_token::ID,
+                &get_associated_token_address(&creator.pubkey(), &nft_mint),
+                &buyer_nft_account,
+                &creator.pubkey(),
+                &[],
+                1,
+            )
+            .unwrap(),
+        ],
+        &[&buyer, &creator],
+    )
+    .unwrap();
+
+    let deactivate = registry::UpdateAgentArgs {
+        is_active: Some(false),
+        ..Default::default()
+    };
+    let result = env.send(
+        &[registry::update_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            deactivate.clone(),
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::AgentNftNotHeld);
+
+    // The buyer controls the profile before and after taking over as creator
+    env.send(
+        &[registry::update_agent(
+            buyer.pubkey(),
+            agent_id,
+            nft_mint,
+            deactivate,
+        )],
+        &[&buyer],
+    )
+    .unwrap();
+    assert!(!env.fetch::<AgentProfile>(&agent_id).is_active);
+
+    env.send(
+        &[registry::transfer_ownership(
+            buyer.pubkey(),
+            agent_id,
+            nft_mint,
+        )],
+        &[&buyer],
+    )
+    .unwrap();
+    assert_eq!(env.fetch::<AgentProfile>(&agent_id).creator, buyer.pubkey());
+
+    let result = env.send(
+        &[registry::transfer_ownership(
+            buyer.pubkey(),
+            agent_id,
+            nft_mint,
+        )],
+        &[&buyer],
+    );
+    assert_anchor_error(result, RegistryError::AlreadyOwner);
+}
+
+#[test]
+fn requests_only_pin_their_own_agents_versions() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let other_creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let other_agent = env.register_agent(&other_creator);
+    let other_mint = env.fetch::<AgentProfile>(&other_agent).nft_mint;
+    let request = env.open_request(&user, agent_id, LAMPORTS_PER_SOL);
+
+    let release = registry::AgentVersionArgs {
+        major: 2,
+        minor: 0,
+        patch: 0,
+        ipfs_hash: "QmOtherRelease".into(),
+        changelog_hash: [0; 32],
+    };
+    // Only the NFT holder publishes
+    let result = env.send(
+        &[registry::publish_agent_version(
+            creator.pubkey(),
+            other_agent,
+            other_mint,
+            release.clone(),
+        )],
+        &[&creator],
+    );
+    assert!(result.is_err());
+    env.send(
+        &[registry::publish_agent_version(
+            other_creator.pubkey(),
+            other_agent,
+            other_mint,
+            release,
+        )],
+        &[&other_creator],
+    )
+    .unwrap();
+
+    let other_version = pda::agent_version(&other_agent, 2, 0, 0).0;
+    let result = env.send(
+        &[escrow::pin_agent_version(
+            request,
+            other_version,
+            user.pubkey(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::InvalidAgentVersion);
+
+    // Nor does an agent profile pass for a version
+    let result = env.send(
+        &[escrow::pin_agent_version(request, agent_id, user.pubkey())],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::InvalidAgentVersion);
+}
+
+#[test]
+fn requests_only_order_affordable_active_offerings() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let other_creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    let other_agent = env.register_agent(&other_creator);
+    let other_mint = env.fetch::<AgentProfile>(&other_agent).nft_mint;
+    let request = env.open_request(&user, agent_id, LAMPORTS_PER_SOL);
+
+    let offering = registry::ServiceOfferingArgs {
+        offering_id: 7,
+        name: "Deep research".into(),
+        description: "A cited literature review".into(),
+        price: 2 * LAMPORTS_PER_SOL,
+        turnaround_secs: 86_400,
+    };
+    for (holder, agent, mint) in [
+        (&creator, agent_id, nft_mint),
+        (&other_creator, other_agent, other_mint),
+    ] {
+        env.send(
+            &[registry::add_service_offering(
+                holder.pubkey(),
+                agent,
+                mint,
+                offering.clone(),
+            )],
+            &[holder],
+        )
+        .unwrap();
+    }
+
+    // The request escrowed less than the offering costs
+    let offering_address = pda::service_offering(&agent_id, 7).0;
+    let result = env.send(
+        &[escrow::select_service_offering(
+            request,
+            offering_address,
+            user.pubkey(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::OfferingPriceNotMet);
+
+    // Another agent's offering doesn't apply
+    let result = env.send(
+        &[escrow::select_service_offering(
+            request,
+            pda::service_offering(&other_agent, 7).0,
+            user.pubkey(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::InvalidServiceOffering);
+
+    // A withdrawn offering can't be ordered even once affordable
+    env.send(
+        &[registry::update_service_offering(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            7,
+            registry::UpdateServiceOfferingArgs {
+                price: Some(LAMPORTS_PER_SOL),
+                is_active: Some(false),
+                ..Default::default()
+            },
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let result = env.send(
+        &[escrow::select_service_offering(
+            request,
+            offering_address,
+            user.pubkey(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::ServiceOfferingInactive);
+}
+
+#[test]
+fn bundles_need_every_creator_and_their_share_escrowed() {
+    let mut env = TestEnv::new();
+    let curator = env.funded_keypair(10);
+    let partner = env.funded_keypair(10);
+    let outsider = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agents = vec![env.register_agent(&curator), env.register_agent(&partner)];
+    let outside_agent = env.register_agent(&outsider);
+    let creators = [curator.pubkey(), partner.pubkey()];
+    let args = registry::BundleArgs {
+        bundle_id: 3,
+        name: "Research desk".into(),
+        agents: agents.clone(),
+        price: 2 * LAMPORTS_PER_SOL,
+        discount_bps: 2_000,
+    };
+
+    // A bundle needs at least two agents
+    let result = env.send(
+        &[registry::create_bundle(
+            curator.pubkey(),
+            registry::BundleArgs {
+                agents: agents[..1].to_vec(),
+                ..args.clone()
+            },
+            &creators[..1],
+        )],
+        &[&curator],
+    );
+    assert_anchor_error(result, RegistryError::InvalidBundleSize);
+
+    // The partner never agreed to discount their agent
+    let mut unapproved = registry::create_bundle(curator.pubkey(), args.clone(), &creators);
+    unapproved.accounts.last_mut().unwrap().is_signer = false;
+    let result = env.send(&[unapproved], &[&curator]);
+    assert_anchor_error(result, RegistryError::BundleAgentNotApproved);
+
+    env.send(
+        &[registry::create_bundle(curator.pubkey(), args, &creators)],
+        &[&curator, &partner],
+    )
+    .unwrap();
+    let bundle = pda::bundle(&curator.pubkey(), 3).0;
+
+    // Each request must escrow its agent's share of the discounted price
+    let request = env.open_request(&user, agents[0], LAMPORTS_PER_SOL / 2);
+    let result = env.send(
+        &[escrow::select_bundle(request, bundle, user.pubkey())],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::BundlePriceNotMet);
+
+    // Agents outside the bundle can't be bought through it
+    let request = env.open_request(&user, outside_agent, LAMPORTS_PER_SOL);
+    let result = env.send(
+        &[escrow::select_bundle(request, bundle, user.pubkey())],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::AgentNotInBundle);
+}
+
+#[test]
+fn agents_only_price_in_accepted_tokens() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    let usdc = env.create_mint(6);
+    let priced_in_usdc = registry::UpdateAgentArgs {
+        pricing: Some(PricingModel::PerQuery {
+            price: 2_500_000,
+            payment_mint: Some(usdc),
+        }),
+        ..Default::default()
+    };
+
+    // The token isn't accepted yet, so there is no account to vouch for it
+    let result = env.send(
+        &[registry::update_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            priced_in_usdc.clone(),
+        )],
+        &[&creator],
+    );
+    assert!(result.is_err());
+
+    // Only the guardian authority accepts tokens
+    let result = env.send(
+        &[registry::add_accepted_mint(creator.pubkey(), usdc)],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::UnauthorizedConfigAdmin);
+    env.send_as_admin(&[registry::add_accepted_mint(env.admin.pubkey(), usdc)])
+        .unwrap();
+    assert_eq!(
+        env.fetch::<AcceptedMint>(&pda::accepted_mint(&usdc).0)
+            .decimals,
+        6
+    );
+
+    env.send(
+        &[registry::update_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            priced_in_usdc,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let pricing = env.fetch::<AgentProfile>(&agent_id).pricing_model;
+    assert_eq!(pricing.payment_mint(), Some(usdc));
+
+    // Another token's account doesn't vouch for this one
+    let other_mint = env.create_mint(9);
+    env.send_as_admin(&[registry::add_accepted_mint(env.admin.pubkey(), other_mint)])
+        .unwrap();
+    let mut ix = registry::update_agent(
+        creator.pubkey(),
+        agent_id,
+        nft_mint,
+        registry::UpdateAgentArgs {
+            pricing: Some(PricingModel::Subscription {
+                monthly: 50_000_000,
+                payment_mint: Some(usdc),
+            }),
+            ..Default::default()
+        },
+    );
+    let accepted = pda::accepted_mint(&usdc).0;
+    for meta in ix
+        .accounts
+        .iter_mut()
+        .filter(|meta| meta.pubkey == accepted)
+    {
+        meta.pubkey = pda::accepted_mint(&other_mint).0;
+    }
+    let result = env.send(&[ix], &[&creator]);
+    assert_anchor_error(result, RegistryError::PaymentMintNotAccepted);
+}
+
+#[test]
+fn usd_prices_resolve_only_against_the_agents_live_feed() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    let feed = env.pyth_price_feed(15_000_000_000, -8, 0);
+
+    // Lamport-priced agents have nothing to resolve
+    let result = env.send(&[registry::resolve_usd_price(agent_id, feed)], &[&creator]);
+    assert_anchor_error(result, RegistryError::NotUsdPegged);
+
+    env.send(
+        &[registry::update_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            registry::UpdateAgentArgs {
+                pricing: Some(PricingModel::UsdPegged {
+                    usd_cents: 1_500,
+                    feed,
+                }),
+                ..Default::default()
+            },
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    // A requester can't swap in a cheaper feed
+    let other_feed = env.pyth_price_feed(30_000_000_000, -8, 0);
+    let result = env.send(
+        &[registry::resolve_usd_price(agent_id, other_feed)],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::InvalidPriceFeed);
+
+    // Nor resolve against a price that has stopped updating
+    let stale = env.pyth_price_feed(15_000_000_000, -8, 120);
+    env.send(
+        &[registry::update_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            registry::UpdateAgentArgs {
+                pricing: Some(PricingModel::UsdPegged {
+                    usd_cents: 1_500,
+                    feed: stale,
+                }),
+                ..Default::default()
+            },
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let result = env.send(&[registry::resolve_usd_price(agent_id, stale)], &[&creator]);
+    assert_anchor_error(result, RegistryError::StalePrice);
+}
+
+#[test]
+fn only_the_verifier_verifies_agents() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+
+    // Not even the agent's own creator
+    let result = env.send(
+        &[registry::verify_agent(creator.pubkey(), agent_id)],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::UnauthorizedVerifier);
+
+    let result = env.send_as_admin(&[registry::revoke_verification(env.admin.pubkey(), agent_id)]);
+    assert_anchor_error(result, RegistryError::AgentNotVerified);
+
+    // Handing the role to a new key takes it from the old one
+    let verifier = env.funded_keypair(1);
+    env.send_as_admin(&[registry::update_registry_config(
+        env.admin.pubkey(),
+        None,
+        None,
+        None,
+        Some(verifier.pubkey()),
+        None,
+        None,
+    )])
+    .unwrap();
+    let result = env.send_as_admin(&[registry::verify_agent(env.admin.pubkey(), agent_id)]);
+    assert_anchor_error(result, RegistryError::UnauthorizedVerifier);
+    env.send(
+        &[registry::verify_agent(verifier.pubkey(), agent_id)],
+        &[&verifier],
+    )
+    .unwrap();
+    let result = env.send(
+        &[registry::verify_agent(verifier.pubkey(), agent_id)],
+        &[&verifier],
+    );
+    assert_anchor_error(result, RegistryError::AgentAlreadyVerified);
+}
+
+#[test]
+fn only_the_issuer_revokes_an_attestation() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let issuer = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let schema = [7; 32];
+
+    env.send(
+        &[registry::issue_agent_attestation(
+            issuer.pubkey(),
+            agent_id,
+            registry::AgentAttestationArgs {
+                schema,
+                data_hash: [1; 32],
+                expires_at: None,
+            },
+        )],
+        &[&issuer],
+    )
+    .unwrap();
+
+    // The agent's creator can't clear an attestation it dislikes
+    let mut ix = registry::revoke_agent_attestation(issuer.pubkey(), agent_id, schema);
+    for meta in ix
+        .accounts
+        .iter_mut()
+        .filter(|meta| meta.pubkey == issuer.pubkey())
+    {
+        meta.pubkey = creator.pubkey();
+    }
+    let result = env.send(&[ix], &[&creator]);
+    assert_anchor_error(result, RegistryError::UnauthorizedIssuer);
+
+    let revoke = registry::revoke_agent_attestation(issuer.pubkey(), agent_id, schema);
+    env.send(&[revoke.clone()], &[&issuer]).unwrap();
+    let result = env.send(&[revoke], &[&issuer]);
+    assert_anchor_error(result, RegistryError::AttestationAlreadyRevoked);
+}
+
+#[test]
+fn co_creators_must_fit_within_the_creator_share_and_be_paid() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let co_creator = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    let share = |key: Pubkey, bps: u16| CoCreator { key, bps };
+
+    for (co_creators, error) in [
+        (
+            vec![
+                share(co_creator.pubkey(), 6_000),
+                share(Pubkey::new_unique(), 4_001),
+            ],
+            RegistryError::InvalidCoCreatorShares,
+        ),
+        (
+            vec![share(co_creator.pubkey(), 0)],
+            RegistryError::InvalidCoCreatorShares,
+        ),
+        (
+            vec![
+                share(co_creator.pubkey(), 100),
+                share(co_creator.pubkey(), 100),
+            ],
+            RegistryError::DuplicateCoCreator,
+        ),
+        (
+            (0..6).map(|_| share(Pubkey::new_unique(), 100)).collect(),
+            RegistryError::TooManyCoCreators,
+        ),
+    ] {
+        let result = env.send(
+            &[registry::set_co_creators(
+                creator.pubkey(),
+                agent_id,
+                nft_mint,
+                co_creators,
+            )],
+            &[&creator],
+        );
+        assert_anchor_error(result, error);
+    }
+
+    env.send(
+        &[registry::set_co_creators(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            vec![share(co_creator.pubkey(), 1_000)],
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    // Approving without the co-creator's wallet, or with another in its place, is refused
+    let request = env.completed_request(&user, &creator, agent_id, LAMPORTS_PER_SOL);
+    let approve = escrow::approve_result(
+        request,
+        agent_id,
+        user.pubkey(),
+        creator.pubkey(),
+        env.platform_wallet,
+        env.treasury_wallet,
+    );
+    let result = env.send(&[approve.clone()], &[&user]);
+    assert_anchor_error(result, EscrowError::CoCreatorMismatch);
+    let result = env.send(
+        &[escrow::with_co_creators(approve, &[creator.pubkey()])],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::CoCreatorMismatch);
+}
+
+#[test]
+fn agents_are_only_marked_stale_after_missing_the_window() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let stranger = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    let result = env.send(&[registry::mark_stale(agent_id)], &[&stranger]);
+    assert_anchor_error(result, RegistryError::AgentNotStale);
+
+    // Only the NFT holder speaks for the agent
+    let result = env.send(
+        &[registry::heartbeat(stranger.pubkey(), agent_id, nft_mint)],
+        &[&stranger],
+    );
+    assert_anchor_error(result, RegistryError::AgentNftNotHeld);
+
+    // A heartbeat late in the window restarts it
+    env.advance_clock(HEARTBEAT_WINDOW - 10);
+    env.send(
+        &[registry::heartbeat(creator.pubkey(), agent_id, nft_mint)],
+        &[&creator],
+    )
+    .unwrap();
+    env.advance_clock(20);
+    let result = env.send(&[registry::mark_stale(agent_id)], &[&stranger]);
+    assert_anchor_error(result, RegistryError::AgentNotStale);
+
+    // With the window switched off nothing goes stale
+    env.send_as_admin(&[registry::update_registry_config(
+        env.admin.pubkey(),
+        None,
+        None,
+        None,
+        None,
+        Some(0),
+        None,
+    )])
+    .unwrap();
+    env.advance_clock(10 * HEARTBEAT_WINDOW);
+    let result = env.send(&[registry::mark_stale(agent_id)], &[&stranger]);
+    assert_anchor_error(result, RegistryError::HeartbeatsDisabled);
+}
+
+#[test]
+fn only_subscription_agents_offer_tiers_once() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    let tier = |requests_per_month| registry::SubscriptionTierArgs {
+        tier_id: 1,
+        name: "Starter".into(),
+        monthly_price: LAMPORTS_PER_SOL,
+        requests_per_month,
+        features_hash: [0; 32],
+    };
+
+    // The test agent charges per query
+    let result = env.send(
+        &[registry::create_subscription_tier(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            tier(100),
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::NotSubscriptionPriced);
+
+    env.send(
+        &[registry::update_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            registry::UpdateAgentArgs {
+                pricing: Some(PricingModel::Subscription {
+                    monthly: LAMPORTS_PER_SOL,
+                    payment_mint: None,
+                }),
+                ..Default::default()
+            },
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let result = env.send(
+        &[registry::create_subscription_tier(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            tier(0),
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::InvalidTierQuota);
+
+    env.send(
+        &[registry::create_subscription_tier(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            tier(100),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let retire = registry::retire_subscription_tier(creator.pubkey(), agent_id, nft_mint, 1);
+    env.send(&[retire.clone()], &[&creator]).unwrap();
+    let result = env.send(&[retire], &[&creator]);
+    assert_anchor_error(result, RegistryError::TierRetired);
+}
+
+#[test]
+fn access_grants_stop_at_their_quota_and_expiry() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    env.send(
+        &[
+            registry::update_agent(
+                creator.pubkey(),
+                agent_id,
+                nft_mint,
+                registry::UpdateAgentArgs {
+                    pricing: Some(PricingModel::Subscription {
+                        monthly: LAMPORTS_PER_SOL,
+                        payment_mint: None,
+                    }),
+                    ..Default::default()
+                },
+            ),
+            registry::create_subscription_tier(
+                creator.pubkey(),
+                agent_id,
+                nft_mint,
+                registry::SubscriptionTierArgs {
+                    tier_id: 1,
+                    name: "Trial".into(),
+                    monthly_price: LAMPORTS_PER_SOL / 10,
+                    requests_per_month: 3,
+                    features_hash: [0; 32],
+                },
+            ),
+        ],
+        &[&creator],
+    )
+    .unwrap();
+    let profile: AgentProfile = env.fetch(&agent_id);
+    let config: RoyaltyConfig = env.fetch(&pda::royalty_config().0);
+
+    let result = env.send(
+        &[registry::issue_access_grant(
+            user.pubkey(),
+            &profile,
+            &config,
+            1,
+            13,
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, RegistryError::InvalidAccessTerm);
+
+    env.send(
+        &[registry::issue_access_grant(
+            user.pubkey(),
+            &profile,
+            &config,
+            1,
+            1,
+        )],
+        &[&user],
+    )
+    .unwrap();
+    let consume = |units| {
+        registry::consume_access(creator.pubkey(), agent_id, nft_mint, user.pubkey(), units)
+    };
+    let result = env.send(&[consume(4)], &[&creator]);
+    assert_anchor_error(result, RegistryError::AccessQuotaExceeded);
+
+    // Only the agent's NFT holder draws on the grant
+    let result = env.send(
+        &[registry::consume_access(
+            user.pubkey(),
+            agent_id,
+            nft_mint,
+            user.pubkey(),
+            1,
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, RegistryError::AgentNftNotHeld);
+
+    env.send(&[consume(1)], &[&creator]).unwrap();
+    env.advance_clock(31 * 86_400);
+    let result = env.send(&[consume(1)], &[&creator]);
+    assert_anchor_error(result, RegistryError::AccessGrantInactive);
+}
+
+#[test]
+fn surge_pricing_keeps_its_base_within_bounds() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    let result = env.send(&[registry::resolve_surge_price(agent_id)], &[&creator]);
+    assert_anchor_error(result, RegistryError::NotSurgePriced);
+
+    let result = env.send(
+        &[registry::update_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            registry::UpdateAgentArgs {
+                pricing: Some(PricingModel::Surge {
+                    base: LAMPORTS_PER_SOL,
+                    min: LAMPORTS_PER_SOL / 2,
+                    max: LAMPORTS_PER_SOL / 2,
+                    target_open: 2,
+                    step_bps: 1_000,
+                }),
+                ..Default::default()
+            },
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::InvalidSurgeBounds);
+}
+
+#[test]
+fn agents_only_list_curated_capabilities() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let mint = Keypair::new();
+
+    let registry_config: RegistryConfig = env.fetch(&pda::registry_config().0);
+    let mut args = common::agent_args();
+    args.capabilities.push("telepathy".into());
+    let result = env.send(
+        &[registry::register_agent(
+            creator.pubkey(),
+            mint.pubkey(),
+            &registry_config,
+            args,
+        )],
+        &[&creator, &mint],
+    );
+    assert_anchor_error(result, RegistryError::UnknownCapability);
+
+    // Only the guardian authority curates the taxonomy
+    let result = env.send(
+        &[registry::deprecate_capability(
+            creator.pubkey(),
+            "summarization",
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::UnauthorizedCapabilityAdmin);
+
+    let admin = env.admin.pubkey();
+    env.send_as_admin(&[registry::deprecate_capability(admin, "summarization")])
+        .unwrap();
+    let result = env.send(
+        &[registry::register_agent(
+            creator.pubkey(),
+            mint.pubkey(),
+            &registry_config,
+            common::agent_args(),
+        )],
+        &[&creator, &mint],
+    );
+    assert_anchor_error(result, RegistryError::CapabilityDeprecated);
+}
+
+#[test]
+fn endpoint_attestation_needs_the_claimed_keys_signature() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    env.send(
+        &[registry::request_endpoint_challenge(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let challenge: EndpointChallenge = env.fetch(&pda::endpoint_challenge(&agent_id).0);
+    let message = registry::endpoint_challenge_message(&agent_id, &challenge.nonce);
+    let endpoint_key = Keypair::new();
+    let impostor = Keypair::new();
+
+    // No signature at all
+    let result = env.send(
+        &[registry::attest_endpoint(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            endpoint_key.pubkey(),
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::MissingEndpointSignature);
+
+    // A valid signature, but from a different key than the one claimed
+    let signature = impostor.sign_message(&message);
+    let result = env.send(
+        &[
+            registry::ed25519_verify(&impostor.pubkey(), signature.as_array(), &message),
+            registry::attest_endpoint(creator.pubkey(), agent_id, nft_mint, endpoint_key.pubkey()),
+        ],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::InvalidEndpointSignature);
+    assert!(!env.fetch::<AgentProfile>(&agent_id).endpoint_verified);
+}
+
+#[test]
+fn manifests_need_the_operator_signature_and_their_own_creator() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let other_creator = env.funded_keypair(10);
+    let payer = env.funded_keypair(10);
+    let mint = Keypair::new();
+    let registry_config: RegistryConfig = env.fetch(&pda::registry_config().0);
+    let operator = Keypair::new();
+    for owner in [&creator, &other_creator] {
+        env.send(
+            &[registry::register_operator(
+                owner.pubkey(),
+                operator.pubkey(),
+            )],
+            &[owner],
+        )
+        .unwrap();
+    }
+    let expires_at = env.now() + 600;
+    let manifest = registry::agent_manifest(creator.pubkey(), 0, expires_at, agent_args());
+
+    // No signature at all
+    let result = env.send(
+        &[registry::register_agent_with_manifest(
+            payer.pubkey(),
+            mint.pubkey(),
+            &registry_config,
+            operator.pubkey(),
+            &manifest,
+        )],
+        &[&payer, &mint],
+    );
+    assert_anchor_error(result, RegistryError::MissingManifestSignature);
+
+    // The operator signed a different manifest than the one submitted
+    let mut signed_args = agent_args();
+    signed_args.name = "Someone else's agent".into();
+    let signed = registry::manifest_message(&registry::agent_manifest(
+        creator.pubkey(),
+        0,
+        expires_at,
+        signed_args,
+    ));
+    let signature = operator.sign_message(&signed);
+    let result = env.send(
+        &[
+            registry::ed25519_verify(&operator.pubkey(), signature.as_array(), &signed),
+            registry::register_agent_with_manifest(
+                payer.pubkey(),
+                mint.pubkey(),
+                &registry_config,
+                operator.pubkey(),
+                &manifest,
+            ),
+        ],
+        &[&payer, &mint],
+    );
+    assert_anchor_error(result, RegistryError::InvalidManifestSignature);
+
+    // A manifest signed for one creator cannot register an agent for another,
+    // even one that registered the same operator
+    let message = registry::manifest_message(&manifest);
+    let signature = operator.sign_message(&message);
+    let mut ix = registry::register_agent_with_manifest(
+        payer.pubkey(),
+        mint.pubkey(),
+        &registry_config,
+        operator.pubkey(),
+        &registry::agent_manifest(other_creator.pubkey(), 0, expires_at, agent_args()),
+    );
+    ix.data = agent_registry::instruction::RegisterAgentWithManifest {
+        manifest: message.clone(),
+    }
+    .data();
+    let result = env.send(
+        &[
+            registry::ed25519_verify(&operator.pubkey(), signature.as_array(), &message),
+            ix,
+        ],
+        &[&payer, &mint],
+    );
+    assert_anchor_error(result, RegistryError::ManifestCreatorMismatch);
+    assert_eq!(
+        env.balance(&pda::agent_profile(&other_creator.pubkey()).0),
+        0
+    );
+}
+
+#[test]
+fn manifests_need_a_registered_operator_a_fresh_nonce_and_time_left() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let payer = env.funded_keypair(10);
+    let mint = Keypair::new();
+    let registry_config: RegistryConfig = env.fetch(&pda::registry_config().0);
+    let operator = Keypair::new();
+    let submit = |env: &mut TestEnv, manifest: &agent_registry::AgentManifest| {
+        let message = registry::manifest_message(manifest);
+        let signature = operator.sign_message(&message);
+        env.send(
+            &[
+                registry::ed25519_verify(&operator.pubkey(), signature.as_array(), &message),
+                registry::register_agent_with_manifest(
+                    payer.pubkey(),
+                    mint.pubkey(),
+                    &registry_config,
+                    operator.pubkey(),
+                    manifest,
+                ),
+            ],
+            &[&payer, &mint],
+        )
+    };
+    let expires_at = env.now() + 600;
+    let manifest = registry::agent_manifest(creator.pubkey(), 0, expires_at, agent_args());
+
+    // A key the creator never registered signs nothing on their behalf
+    let result = submit(&mut env, &manifest);
+    assert_anchor_error(result, anchor_lang::error::ErrorCode::AccountNotInitialized);
+
+    env.send(
+        &[registry::register_operator(
+            creator.pubkey(),
+            operator.pubkey(),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    // Manifests carry the operator's next nonce, so old ones can't be replayed
+    let skipped = registry::agent_manifest(creator.pubkey(), 1, expires_at, agent_args());
+    let result = submit(&mut env, &skipped);
+    assert_anchor_error(result, RegistryError::StaleManifestNonce);
+
+    let expired = registry::agent_manifest(creator.pubkey(), 0, env.now() - 1, agent_args());
+    let result = submit(&mut env, &expired);
+    assert_anchor_error(result, RegistryError::ManifestExpired);
+
+    // A revoked operator is as good as an unregistered one
+    env.send(
+        &[registry::revoke_operator(
+            creator.pubkey(),
+            operator.pubkey(),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let result = submit(&mut env, &manifest);
+    assert_anchor_error(result, anchor_lang::error::ErrorCode::AccountNotInitialized);
+    assert_eq!(env.balance(&pda::agent_profile(&creator.pubkey()).0), 0);
+}
+
+#[test]
+fn referral_codes_cannot_be_self_referred_or_swept_to_strangers() {
+    let mut env = TestEnv::new();
+    let referrer = env.funded_keypair(10);
+    let stranger = env.funded_keypair(10);
+    let payer = env.funded_keypair(1);
+
+    // Only creators of a registered agent can hand out codes
+    let result = env.send(
+        &[registry::create_referral_code(
+            stranger.pubkey(),
+            "stranger",
+        )],
+        &[&stranger],
+    );
+    assert_anchor_error(result, anchor_lang::error::ErrorCode::AccountNotInitialized);
+
+    // Re-registering after deregistering cannot use the referrer's own code
+    let agent_id = env.register_agent(&referrer);
+    env.send(
+        &[registry::create_referral_code(referrer.pubkey(), "summer")],
+        &[&referrer],
+    )
+    .unwrap();
+    let profile: AgentProfile = env.fetch(&agent_id);
+    env.send(
+        &[registry::deregister_agent(
+            referrer.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            &profile.name,
+            &profile.capabilities,
+            &profile.languages,
+            None,
+        )],
+        &[&referrer],
+    )
+    .unwrap();
+    let mint = Keypair::new();
+    let registry_config: RegistryConfig = env.fetch(&pda::registry_config().0);
+    let result = env.send(
+        &[registry::register_agent_with_referral(
+            referrer.pubkey(),
+            mint.pubkey(),
+            &registry_config,
+            "summer",
+            agent_args(),
+        )],
+        &[&referrer, &mint],
+    );
+    assert_anchor_error(result, RegistryError::SelfReferral);
+
+    // Nothing has accrued yet, and the fees only ever go to the referrer
+    let config: RoyaltyConfig = env.fetch(&pda::royalty_config().0);
+    let result = env.send(
+        &[registry::sweep_referral_fees(
+            payer.pubkey(),
+            "summer",
+            referrer.pubkey(),
+            &config,
+        )],
+        &[&payer],
+    );
+    assert_anchor_error(result, RegistryError::NoReferralFees);
+    let result = env.send(
+        &[registry::sweep_referral_fees(
+            payer.pubkey(),
+            "summer",
+            stranger.pubkey(),
+            &config,
+        )],
+        &[&payer],
+    );
+    assert_anchor_error(result, RegistryError::InvalidReferrer);
+}
+
+#[test]
+fn only_requester_can_settle() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let stranger = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let request = env.completed_request(&user, &creator, agent_id, LAMPORTS_PER_SOL);
+
+    let result = env.send(
+        &[escrow::approve_result(
+            request,
+            agent_id,
+            stranger.pubkey(),
+            stranger.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+        )],
+        &[&stranger],
+    );
+    assert_anchor_error(result, EscrowError::UnauthorizedUser);
+
+    let result = env.send(
+        &[escrow::dispute_result(
+            request,
+            stranger.pubkey(),
+            "Not my request".into(),
+        )],
+        &[&stranger],
+    );
+    assert_anchor_error(result, EscrowError::UnauthorizedUser);
+}
+
+#[test]
+fn guardian_pause_halts_every_program() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let request = env.completed_request(&user, &creator, agent_id, LAMPORTS_PER_SOL);
+
+    let admin = env.admin.pubkey();
+    env.send_as_admin(&[guardian::pause_protocol(admin)])
+        .unwrap();
+
+    let other_user = env.funded_keypair(10);
+    let result = env.send(
+        &[escrow::create_service_request(
+            other_user.pubkey(),
+            agent_id,
+            LAMPORTS_PER_SOL,
+            "Paused request".into(),
+        )],
+        &[&other_user],
+    );
+    assert_anchor_error(result, EscrowError::ProtocolPaused);
+
+    let result = env.send(
+        &[escrow::approve_result(
+            request,
+            agent_id,
+            user.pubkey(),
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::ProtocolPaused);
+
+    let result = env.send(
+        &[royalty::distribute_payment(
+            user.pubkey(),
+            user.pubkey(),
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+            0,
+            LAMPORTS_PER_SOL,
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, RoyaltyError::ProtocolPaused);
+
+    let result = env.send(
+        &[reputation::submit_rating(
+            user.pubkey(),
+            agent_id,
+            request,
+            RatingScores {
+                stars: 5,
+                quality: 5,
+                speed: 5,
+                value: 5,
+            },
+            String::new(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, ReputationError::ProtocolPaused);
+
+    // Unpausing restores the flow where it stopped
+    env.send_as_admin(&[guardian::unpause_protocol(admin)])
+        .unwrap();
+    env.send(
+        &[escrow::approve_result(
+            request,
+            agent_id,
+            user.pubkey(),
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+        )],
+        &[&user],
+    )
+    .unwrap();
+    let state: ServiceRequest = env.fetch(&request);
+    assert!(state.status == RequestStatus::Approved);
+}
+
+#[test]
+fn royalty_pause_blocks_only_distributions() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+
+    let admin = env.admin.pubkey();
+    env.send_as_admin(&[royalty::set_pause_state(admin, true)])
+        .unwrap();
+
+    let result = env.send(
+        &[royalty::distribute_payment(
+            user.pubkey(),
+            user.pubkey(),
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+            0,
+            LAMPORTS_PER_SOL,
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, RoyaltyError::ContractPaused);
+
+    // Escrow is unaffected by the royalty-only pause
+    env.open_request(&user, agent_id, LAMPORTS_PER_SOL);
+
+    // And only the royalty admin can pause
+    let result = env.send(&[royalty::set_pause_state(user.pubkey(), false)], &[&user]);
+    assert_anchor_error(result, RoyaltyError::UnauthorizedAdmin);
+}
+
+#[test]
+fn only_the_guardian_authority_initializes_the_reputation_config() {
+    let mut env = TestEnv::new();
+    let squatter = env.funded_keypair(10);
+    let admin = env.admin.pubkey();
+
+    // Start from a deployment whose reputation config doesn't exist yet
+    let config = pda::reputation_config().0;
+    let mut account = env.svm.get_account(&config).unwrap();
+    account.lamports = 0;
+    account.data.clear();
+    account.owner = Pubkey::default();
+    env.svm.set_account(config, account).unwrap();
+
+    let result = env.send(
+        &[reputation::initialize_config(squatter.pubkey())],
+        &[&squatter],
+    );
+    assert_anchor_error(result, ReputationError::UnauthorizedAdmin);
+
+    env.send_as_admin(&[reputation::initialize_config(admin)])
+        .unwrap();
+    assert_eq!(env.fetch::<ReputationConfig>(&config).admin, admin);
+}
+
+#[test]
+fn agents_at_capacity_reject_new_requests() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let first = env.funded_keypair(10);
+    let second = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    // Only the holder of the agent NFT declares its capacity
+    let result = env.send(
+        &[registry::set_max_concurrent_requests(
+            first.pubkey(),
+            agent_id,
+            nft_mint,
+            1,
+        )],
+        &[&first],
+    );
+    assert_anchor_error(result, RegistryError::AgentNftNotHeld);
+
+    env.send(
+        &[registry::set_max_concurrent_requests(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            1,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let request = env.open_request(&first, agent_id, LAMPORTS_PER_SOL);
+    let result = env.send(
+        &[escrow::create_service_request(
+            second.pubkey(),
+            agent_id,
+            LAMPORTS_PER_SOL,
+            "Summarize the attached paper".into(),
+        )],
+        &[&second],
+    );
+    assert_anchor_error(result, EscrowError::AgentAtCapacity);
+
+    // Cancelling the open request frees the slot
+    env.send(
+        &[escrow::cancel_request(request, agent_id, first.pubkey())],
+        &[&first],
+    )
+    .unwrap();
+    env.open_request(&second, agent_id, LAMPORTS_PER_SOL);
+}
+
+#[test]
+fn paused_agents_reject_new_requests() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let profile: AgentProfile = env.fetch(&agent_id);
+
+    let result = env.send(
+        &[registry::resume_listings(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::ListingsNotPaused);
+
+    // A pause has to end in the future
+    let result = env.send(
+        &[registry::pause_listings(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            profile.created_at,
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::InvalidPauseWindow);
+
+    // Only the holder of the agent NFT pauses its listings
+    let result = env.send(
+        &[registry::pause_listings(
+            user.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            profile.created_at + 3600,
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, RegistryError::AgentNftNotHeld);
+
+    env.send(
+        &[registry::pause_listings(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            profile.created_at + 3600,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let result = env.send(
+        &[escrow::create_service_request(
+            user.pubkey(),
+            agent_id,
+            LAMPORTS_PER_SOL,
+            "Summarize the attached paper".into(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::AgentPaused);
+}
+
+#[test]
+fn capability_schemas_must_describe_listed_capabilities() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    let result = env.send(
+        &[registry::set_capability_schema(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            "translation".into(),
+            "bafkreitext".into(),
+            "bafkreitext".into(),
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::CapabilityNotListed);
+
+    let result = env.send(
+        &[registry::set_capability_schema(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            "summarization".into(),
+            String::new(),
+            "bafkreisummary".into(),
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::InvalidSchemaCid);
+
+    let result = env.send(
+        &[registry::clear_capability_schema(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            "summarization".into(),
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::CapabilitySchemaNotSet);
+}
+
+#[test]
+fn only_the_reputation_system_sets_registry_scores() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let attacker = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+
+    // Signing as anything but the reputation hook PDA is refused
+    let forged = Instruction {
+        program_id: agent_registry::ID,
+        accounts: agent_registry::accounts::UpdateReputation {
+            agent_profile: agent_id,
+            reputation_hook: attacker.pubkey(),
+            guardian_config: pda::guardian_config().0,
+        }
+        .to_account_metas(None),
+        data: agent_registry::instruction::UpdateReputation { new_rating: 5 }.data(),
+    };
+    let result = env.send(&[forged], &[&attacker]);
+    assert_anchor_error(result, anchor_lang::error::ErrorCode::ConstraintSeeds);
+
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert_eq!(profile.reputation_score, 0);
+    assert_eq!(profile.total_services, 0);
+}
+
+#[test]
+fn lineage_royalties_are_capped_and_paid_to_the_parent_creator() {
+    let mut env = TestEnv::new();
+    let parent_creator = env.funded_keypair(10);
+    let fork_creator = env.funded_keypair(10);
+    let payer = env.funded_keypair(10);
+    let parent = env.register_agent(&parent_creator);
+
+    let registry_config: RegistryConfig = env.fetch(&pda::registry_config().0);
+    let mint = Keypair::new();
+    let result = env.send(
+        &[registry::fork_agent(
+            fork_creator.pubkey(),
+            mint.pubkey(),
+            parent,
+            &registry_config,
+            common::agent_args(),
+            agent_registry::MAX_LINEAGE_ROYALTY_BPS + 1,
+        )],
+        &[&fork_creator, &mint],
+    );
+    assert_anchor_error(result, RegistryError::LineageRoyaltyTooHigh);
+
+    env.send(
+        &[registry::fork_agent(
+            fork_creator.pubkey(),
+            mint.pubkey(),
+            parent,
+            &registry_config,
+            common::agent_args(),
+            200,
+        )],
+        &[&fork_creator, &mint],
+    )
+    .unwrap();
+    let fork = pda::agent_profile(&fork_creator.pubkey()).0;
+
+    // The royalty can't be redirected away from the parent's creator
+    let config: RoyaltyConfig = env.fetch(&pda::royalty_config().0);
+    let result = env.send(
+        &[royalty::distribute_lineage_payment(
+            payer.pubkey(),
+            payer.pubkey(),
+            fork_creator.pubkey(),
+            fork,
+            parent,
+            payer.pubkey(),
+            &config,
+            LAMPORTS_PER_SOL,
+        )],
+        &[&payer],
+    );
+    assert_anchor_error(result, RoyaltyError::InvalidLineage);
+}
+
+#[test]
+fn out_of_range_rating_is_rejected() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let request = env.completed_request(&user, &creator, agent_id, LAMPORTS_PER_SOL);
+
+    let result = env.send(
+        &[reputation::submit_rating(
+            user.pubkey(),
+            agent_id,
+            request,
+            RatingScores {
+                stars: 6,
+                quality: 5,
+                speed: 5,
+                value: 5,
+            },
+            String::new(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, ReputationError::InvalidRating);
+}
+
+#[test]
+fn registration_fees_must_keep_the_vault_rent_exempt() {
+    let mut env = TestEnv::new();
+    let payer = env.funded_keypair(1);
+
+    let result = env.send_as_admin(&[registry::update_registry_config(
+        env.admin.pubkey(),
+        None,
+        None,
+        None,
+        None,
+        None,
+        Some(1_000),
+    )]);
+    assert_anchor_error(result, RegistryError::RegistrationFeeBelowRent);
+
+    // Nothing has been collected yet
+    let config: RoyaltyConfig = env.fetch(&pda::royalty_config().0);
+    let result = env.send(
+        &[registry::sweep_registration_fees(payer.pubkey(), &config)],
+        &[&payer],
+    );
+    assert_anchor_error(result, RegistryError::NoRegistrationFees);
+}
+
+#[test]
+fn registration_must_take_the_next_agent_index() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let late_creator = env.funded_keypair(10);
+    let stale: RegistryConfig = env.fetch(&pda::registry_config().0);
+    env.register_agent(&creator);
+
+    // A config read before the last registration points at a taken entry
+    let mint = Keypair::new();
+    let result = env.send(
+        &[registry::register_agent(
+            late_creator.pubkey(),
+            mint.pubkey(),
+            &stale,
+            common::agent_args(),
+        )],
+        &[&late_creator, &mint],
+    );
+    assert_anchor_error(result, anchor_lang::error::ErrorCode::ConstraintSeeds);
+}
+
+#[test]
+fn endpoint_lists_are_capped_and_need_distinct_priorities() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    let endpoint = |priority| AgentEndpoint {
+        url: format!("https://agents.example/summarizer-{priority}"),
+        region: "us-east".into(),
+        priority,
+    };
+    let result = env.send(
+        &[registry::rotate_endpoints(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            vec![endpoint(0), endpoint(1), endpoint(1)],
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::DuplicateEndpointPriority);
+
+    let result = env.send(
+        &[registry::rotate_endpoints(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            (0..=agent_registry::MAX_ENDPOINTS as u8)
+                .map(endpoint)
+                .collect(),
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::TooManyEndpoints);
+}
+
+#[test]
+fn private_agents_refuse_users_off_their_allowlist() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    let request = || {
+        escrow::create_service_request(
+            user.pubkey(),
+            agent_id,
+            LAMPORTS_PER_SOL,
+            "Summarize the attached paper".into(),
+        )
+    };
+
+    // Only the NFT holder manages the allowlist
+    let result = env.send(
+        &[registry::add_allowlist_entry(
+            user.pubkey(),
+            agent_id,
+            nft_mint,
+            user.pubkey(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, RegistryError::AgentNftNotHeld);
+
+    env.send(
+        &[registry::set_visibility(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            Visibility::Allowlist,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let result = env.send(&[request()], &[&user]);
+    assert_anchor_error(result, EscrowError::NotAllowlisted);
+
+    // A removed entry no longer lets the user in
+    env.send(
+        &[
+            registry::add_allowlist_entry(creator.pubkey(), agent_id, nft_mint, user.pubkey()),
+            registry::remove_allowlist_entry(creator.pubkey(), agent_id, nft_mint, user.pubkey()),
+        ],
+        &[&creator],
+    )
+    .unwrap();
+    let result = env.send(&[request()], &[&user]);
+    assert_anchor_error(result, EscrowError::NotAllowlisted);
+
+    env.send(
+        &[registry::set_visibility(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            Visibility::Public,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    env.send(&[request()], &[&user]).unwrap();
+}
+
+#[test]
+fn blocked_users_cannot_open_requests() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    // Only the NFT holder manages the blocklist
+    let result = env.send(
+        &[registry::block_user(
+            user.pubkey(),
+            agent_id,
+            nft_mint,
+            user.pubkey(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, RegistryError::AgentNftNotHeld);
+
+    env.send(
+        &[registry::block_user(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            user.pubkey(),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let result = env.send(
+        &[escrow::create_service_request(
+            user.pubkey(),
+            agent_id,
+            LAMPORTS_PER_SOL,
+            "Summarize the attached paper".into(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::UserBlocked);
+}
+
+#[test]
+fn profile_limits_only_grow_up_to_the_cap() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    let long_description = registry::UpdateAgentArgs {
+        description: Some("a".repeat(800)),
+        ..Default::default()
+    };
+
+    let result = env.send(
+        &[registry::update_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            long_description.clone(),
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::DescriptionTooLong);
+
+    let result = env.send(
+        &[registry::resize_profile(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            agent_registry::MAX_CAPABILITY_LIMIT + 1,
+            1_000,
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::ProfileLimitTooLarge);
+
+    env.send(
+        &[registry::resize_profile(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            12,
+            1_000,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let result = env.send(
+        &[registry::resize_profile(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            12,
+            900,
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::ProfileLimitShrunk);
+
+    env.send(
+        &[registry::update_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            long_description,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+}
+
+#[test]
+fn payments_cannot_bypass_the_payout_wallet() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    let payout_wallet = Keypair::new().pubkey();
+
+    // Only the NFT holder picks the payout wallet
+    let result = env.send(
+        &[registry::set_payout_wallet(
+            user.pubkey(),
+            agent_id,
+            nft_mint,
+            user.pubkey(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, RegistryError::AgentNftNotHeld);
+
+    env.send(
+        &[registry::set_payout_wallet(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            payout_wallet,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let request = env.completed_request(&user, &creator, agent_id, LAMPORTS_PER_SOL);
+    let result = env.send(
+        &[escrow::approve_result(
+            request,
+            agent_id,
+            user.pubkey(),
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::PayoutWalletMismatch);
+}
+
+#[test]
+fn a_precreated_reputation_profile_does_not_block_registration() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let griefer = env.funded_keypair(10);
+    let agent_id = pda::agent_profile(&creator.pubkey()).0;
+
+    env.send(
+        &[reputation::initialize_agent_reputation(
+            griefer.pubkey(),
+            agent_id,
+        )],
+        &[&griefer],
+    )
+    .unwrap();
+    assert_eq!(env.register_agent(&creator), agent_id);
+    let aggregate: AgentReputationProfile = env.fetch(&pda::agent_reputation(&agent_id).0);
+    assert_eq!(aggregate.agent_id, agent_id);
+}
+
+#[test]
+fn only_the_escrow_records_agent_earnings() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let attacker = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+
+    // Signing as anything but the escrow payout hook PDA is refused
+    let forged = Instruction {
+        program_id: agent_registry::ID,
+        accounts: agent_registry::accounts::RecordEarnings {
+            agent_profile: agent_id,
+            payout_hook: attacker.pubkey(),
+            guardian_config: pda::guardian_config().0,
+        }
+        .to_account_metas(None),
+        data: agent_registry::instruction::RecordEarnings {
+            amount: LAMPORTS_PER_SOL,
+        }
+        .data(),
+    };
+    let result = env.send(&[forged], &[&attacker]);
+    assert_anchor_error(result, anchor_lang::error::ErrorCode::ConstraintSeeds);
+
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert_eq!(profile.total_earnings, 0);
+    assert_eq!(profile.total_services, 0);
+}
+
+#[test]
+fn programmable_agents_cannot_pick_their_own_rule_set() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let mint = Keypair::new();
+    let registry_config: RegistryConfig = env.fetch(&pda::registry_config().0);
+
+    // A permissive rule set would let sales skip the royalty
+    let mut register = registry::register_agent_programmable(
+        creator.pubkey(),
+        mint.pubkey(),
+        &registry_config,
+        agent_args(),
+    );
+    for meta in &mut register.accounts {
+        if meta.pubkey == ROYALTY_RULE_SET {
+            meta.pubkey = Pubkey::new_unique();
+        }
+    }
+    let result = env.send(&[register], &[&creator, &mint]);
+    assert_anchor_error(result, anchor_lang::error::ErrorCode::ConstraintAddress);
+    assert!(env
+        .svm
+        .get_account(&pda::agent_profile(&creator.pubkey()).0)
+        .is_none());
+}
+
+#[test]
+fn requests_outside_a_trial_must_escrow_a_payment() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+
+    // Without a trial, nothing marks a zero-amount request as free
+    let result = env.send(
+        &[escrow::create_service_request(
+            user.pubkey(),
+            agent_id,
+            0,
+            "Summarize the attached paper".into(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::InvalidAmount);
+    assert!(env
+        .svm
+        .get_account(&pda::trial_counter(&agent_id, &user.pubkey()).0)
+        .is_none());
+}
+
+#[test]
+fn languages_must_be_iso_codes() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let profile: AgentProfile = env.fetch(&agent_id);
+
+    for (languages, error) in [
+        (vec!["english".into()], RegistryError::InvalidLanguageCode),
+        (vec!["pt_br".into()], RegistryError::InvalidLanguageCode),
+        (
+            vec!["en".into(), "en".into()],
+            RegistryError::DuplicateLanguage,
+        ),
+    ] {
+        let result = env.send(
+            &[registry::set_languages(
+                creator.pubkey(),
+                &profile,
+                languages,
+            )],
+            &[&creator],
+        );
+        assert_anchor_error(result, error);
+    }
+    assert!(env.fetch::<AgentProfile>(&agent_id).languages.is_empty());
+}
+
+#[test]
+fn dependencies_must_be_other_active_agents() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let dependency_creator = env.funded_keypair(10);
+    let dependency = env.register_agent(&dependency_creator);
+    let retired_creator = env.funded_keypair(10);
+    let retired = env.register_agent(&retired_creator);
+    let retired_mint = env.fetch::<AgentProfile>(&retired).nft_mint;
+    env.send(
+        &[registry::update_agent(
+            retired_creator.pubkey(),
+            retired,
+            retired_mint,
+            registry::UpdateAgentArgs {
+                is_active: Some(false),
+                ..Default::default()
+            },
+        )],
+        &[&retired_creator],
+    )
+    .unwrap();
+    let profile: AgentProfile = env.fetch(&agent_id);
+
+    for (dependencies, error) in [
+        (vec![agent_id], RegistryError::SelfDependency),
+        (
+            vec![dependency, dependency],
+            RegistryError::DuplicateDependency,
+        ),
+        (vec![retired], RegistryError::DependencyInactive),
+    ] {
+        let result = env.send(
+            &[registry::set_dependencies(
+                creator.pubkey(),
+                &profile,
+                dependencies,
+            )],
+            &[&creator],
+        );
+        assert_anchor_error(result, error);
+    }
+    assert!(env.fetch::<AgentProfile>(&agent_id).dependencies.is_empty());
+}
+
+#[test]
+fn soulbound_agent_nfts_cannot_be_sold() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let buyer = env.funded_keypair(10);
+    let mint = Keypair::new();
+    let registry_config: RegistryConfig = env.fetch(&pda::registry_config().0);
+    env.send(
+        &[registry::register_agent_soulbound(
+            creator.pubkey(),
+            mint.pubkey(),
+            &registry_config,
+            agent_args(),
+        )],
+        &[&creator, &mint],
+    )
+    .unwrap();
+
+    let result = env.send(
+        &[
+            create_associated_token_account(
+                &buyer.pubkey(),
+                &buyer.pubkey(),
+                &mint.pubkey(),
+                &spl_token::ID,
+            ),
+            spl_token::instruction::transfer(
+                &spl_token::ID,
+                &get_associated_token_address(&creator.pubkey(), &mint.pubkey()),
+                &get_associated_token_address(&buyer.pubkey(), &mint.pubkey()),
+                &creator.pubkey(),
+                &[],
+                1,
+            )
+            .unwrap(),
+        ],
+        &[&buyer, &creator],
+    );
+    assert_anchor_error(result, spl_token::error::TokenError::AccountFrozen as u32);
+}
+
+#[test]
+fn agent_metadata_updates_need_a_migrated_authority() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let profile: AgentProfile = env.fetch(&agent_id);
+
+    let result = env.send(
+        &[registry::update_agent_metadata(
+            creator.pubkey(),
+            &profile,
+            None,
+            Some("https://agents.example/impostor.json".into()),
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::MetadataAuthorityNotMigrated);
+}
+
+#[test]
+fn retired_agents_take_no_new_requests() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    // An agent cannot succeed itself
+    let result = env.send(
+        &[registry::retire_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            Some(agent_id),
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::InvalidSuccessor);
+
+    env.send(
+        &[registry::retire_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            None,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let result = env.send(
+        &[escrow::create_service_request(
+            user.pubkey(),
+            agent_id,
+            LAMPORTS_PER_SOL,
+            "Summarize the attached paper".into(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::AgentRetired);
+
+    // Retirement is final
+    let result = env.send(
+        &[registry::update_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            registry::UpdateAgentArgs {
+                is_active: Some(true),
+                ..Default::default()
+            },
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::AgentRetired);
+}
+
+#[test]
+fn managed_agents_ignore_the_holder_alone() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let vault = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let profile: AgentProfile = env.fetch(&agent_id);
+    env.send(
+        &[registry::set_agent_authority(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            Some(vault.pubkey()),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    let result = env.send(
+        &[registry::update_agent(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            registry::UpdateAgentArgs {
+                name: Some("Impostor".into()),
+                ..Default::default()
+            },
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::NotAgentAuthority);
+
+    // Only the authority can hand management back
+    let result = env.send(
+        &[registry::set_agent_authority(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            None,
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::NotAgentAuthority);
+
+    let result = env.send(
+        &[registry::deregister_agent(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            &profile.name,
+            &profile.capabilities,
+            &profile.languages,
+            None,
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::NotAgentAuthority);
+}
+
+#[test]
+fn frozen_agents_cannot_be_updated_hired_or_deregistered() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    // Only the slash authority freezes agents
+    let result = env.send(
+        &[registry::freeze_agent(creator.pubkey(), agent_id)],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::UnauthorizedSlash);
+    let result = env.send_as_admin(&[registry::unfreeze_agent(env.admin.pubkey(), agent_id)]);
+    assert_anchor_error(result, RegistryError::AgentNotFrozen);
+
+    env.send_as_admin(&[registry::freeze_agent(env.admin.pubkey(), agent_id)])
+        .unwrap();
+    let result = env.send(
+        &[registry::update_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            registry::UpdateAgentArgs {
+                name: Some("Totally Legit Summarizer".into()),
+                ..Default::default()
+            },
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::AgentFrozen);
+
+    let result = env.send(
+        &[escrow::create_service_request(
+            user.pubkey(),
+            agent_id,
+            LAMPORTS_PER_SOL,
+            "Summarize the attached paper".into(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::AgentFrozen);
+
+    // Nor can it be deregistered to take its listing stake out of reach of a slash
+    let profile: AgentProfile = env.fetch(&agent_id);
+    let result = env.send(
+        &[registry::deregister_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            &profile.name,
+            &profile.capabilities,
+            &profile.languages,
+            None,
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::AgentFrozen);
+    assert_eq!(
+        env.fetch::<ListingStake>(&pda::listing_stake(&agent_id).0)
+            .amount,
+        LISTING_STAKE
+    );
+}
+
+#[test]
+fn current_profiles_need_no_migration() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+
+    let result = env.send(
+        &[registry::migrate_profile(creator.pubkey(), agent_id)],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::ProfileUpToDate);
+}
+
+#[test]
+fn capability_prices_cover_listed_capabilities_and_their_price() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    let result = env.send(
+        &[registry::set_capability_price(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            "fine-tuning".into(),
+            LAMPORTS_PER_SOL,
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::CapabilityNotListed);
+
+    env.send(
+        &[registry::set_capability_price(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            "summarization".into(),
+            2 * LAMPORTS_PER_SOL,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let request = env.open_request(&user, agent_id, LAMPORTS_PER_SOL);
+    let result = env.send(
+        &[escrow::select_capability(
+            request,
+            pda::capability_price(&agent_id, "summarization").0,
+            user.pubkey(),
+        )],
+        &[&user],
+    );
+    assert_anchor_error(result, EscrowError::CapabilityPriceNotMet);
+}
+
+#[test]
+fn health_checks_need_a_registered_checker_and_respect_the_interval() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let checker = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+
+    let result = env.send(
+        &[registry::add_health_checker(
+            checker.pubkey(),
+            checker.pubkey(),
+        )],
+        &[&checker],
+    );
+    assert_anchor_error(result, RegistryError::UnauthorizedConfigAdmin);
+
+    let result = env.send(
+        &[registry::submit_health_check(
+            checker.pubkey(),
+            agent_id,
+            true,
+            80,
+        )],
+        &[&checker],
+    );
+    assert_anchor_error(result, anchor_lang::error::ErrorCode::AccountNotInitialized);
+
+    env.send_as_admin(&[registry::add_health_checker(
+        env.admin.pubkey(),
+        checker.pubkey(),
+    )])
+    .unwrap();
+    env.send(
+        &[registry::submit_health_check(
+            checker.pubkey(),
+            agent_id,
+            true,
+            80,
+        )],
+        &[&checker],
+    )
+    .unwrap();
+    let result = env.send(
+        &[registry::submit_health_check(
+            checker.pubkey(),
+            agent_id,
+            true,
+            90,
+        )],
+        &[&checker],
+    );
+    assert_anchor_error(result, RegistryError::HealthCheckTooSoon);
+
+    env.send_as_admin(&[registry::remove_health_checker(
+        env.admin.pubkey(),
+        checker.pubkey(),
+    )])
+    .unwrap();
+    env.advance_clock(agent_registry::MIN_HEALTH_CHECK_INTERVAL);
+    let result = env.send(
+        &[registry::submit_health_check(
+            checker.pubkey(),
+            agent_id,
+            true,
+            80,
+        )],
+        &[&checker],
+    );
+    assert_anchor_error(result, anchor_lang::error::ErrorCode::AccountNotInitialized);
+}
+
+#[test]
+fn agent_sales_hold_their_price_and_skip_soulbound_agents() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let buyer = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    env.send(
+        &[registry::list_agent_for_sale(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            2 * LAMPORTS_PER_SOL,
+            None,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let result = env.send(
+        &[registry::buy_agent(
+            buyer.pubkey(),
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            LAMPORTS_PER_SOL,
+        )],
+        &[&buyer],
+    );
+    assert_anchor_error(result, RegistryError::SalePriceMismatch);
+
+    env.send(
+        &[registry::cancel_agent_sale(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(
+        env.fetch::<anchor_spl::token::TokenAccount>(&get_associated_token_address(
+            &creator.pubkey(),
+            &nft_mint
+        ))
+        .amount,
+        1
+    );
+    let result = env.send(
+        &[registry::buy_agent(
+            buyer.pubkey(),
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            2 * LAMPORTS_PER_SOL,
+        )],
+        &[&buyer],
+    );
+    assert_anchor_error(result, anchor_lang::error::ErrorCode::AccountNotInitialized);
+
+    let owner = env.funded_keypair(10);
+    let mint = Keypair::new();
+    let registry_config: RegistryConfig = env.fetch(&pda::registry_config().0);
+    env.send(
+        &[registry::register_agent_soulbound(
+            owner.pubkey(),
+            mint.pubkey(),
+            &registry_config,
+            agent_args(),
+        )],
+        &[&owner, &mint],
+    )
+    .unwrap();
+    let result = env.send(
+        &[registry::list_agent_for_sale(
+            owner.pubkey(),
+            pda::agent_profile(&owner.pubkey()).0,
+            mint.pubkey(),
+            LAMPORTS_PER_SOL,
+            None,
+        )],
+        &[&owner],
+    );
+    assert_anchor_error(result, RegistryError::AgentNotTransferable);
+}
+
+#[test]
+fn extra_capabilities_are_unique_and_removed_before_deregistering() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let profile: AgentProfile = env.fetch(&agent_id);
+
+    let result = env.send(
+        &[registry::add_agent_capabilities(
+            creator.pubkey(),
+            &profile,
+            vec!["summarization".into()],
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::DuplicateCapability);
+
+    env.send_as_admin(&[registry::add_capability(
+        env.admin.pubkey(),
+        "research".into(),
+        "Research".into(),
+        String::new(),
+    )])
+    .unwrap();
+    env.send(
+        &[registry::add_agent_capabilities(
+            creator.pubkey(),
+            &profile,
+            vec!["research".into()],
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let result = env.send(
+        &[registry::deregister_agent(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            &profile.name,
+            &profile.capabilities,
+            &profile.languages,
+            None,
+        )],
+        &[&creator],
+    );
+    assert_anchor_error(result, RegistryError::AgentCapabilitiesNotEmpty);
+
+    env.send(
+        &[registry::remove_agent_capabilities(
+            creator.pubkey(),
+            &profile,
+            vec!["research".into()],
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    env.send(
+        &[registry::deregister_agent(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            &profile.name,
+            &profile.capabilities,
+            &profile.languages,
+            None,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(env.balance(&pda::agent_capabilities(&agent_id).0), 0);
+}
+
+#[test]
+fn rent_top_ups_need_a_matching_account_that_is_short() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let funder = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+
+    // Registration already funds the profile to the rent-exempt minimum
+    let result = env.send(
+        &[registry::top_up_rent(
+            funder.pubkey(),
+            agent_id,
+            RentTarget::Profile,
+        )],
+        &[&funder],
+    );
+    assert_anchor_error(result, RegistryError::RentAlreadyExempt);
+
+    // The agent has never been health checked, so there is no log to fund
+    let result = env.send(
+        &[registry::top_up_rent(
+            funder.pubkey(),
+            agent_id,
+            RentTarget::HealthLog,
+        )],
+        &[&funder],
+    );
+    assert_anchor_error(result, RegistryError::InvalidRentTarget);
+}
+
+#[test]
+fn lookalike_names_cannot_be_registered_or_renamed_into() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let impostor = env.funded_keypair(10);
+    let mint = Keypair::new();
+    let config: RegistryConfig = env.fetch(&pda::registry_config().0);
+    env.send(
+        &[registry::register_agent(
+            creator.pubkey(),
+            mint.pubkey(),
+            &config,
+            agent_args(),
+        )],
+        &[&creator, &mint],
+    )
+    .unwrap();
+
+    // Case, spacing, punctuation, and digits posing as letters don't count
+    let mint = Keypair::new();
+    let config: RegistryConfig = env.fetch(&pda::registry_config().0);
+    let result = env.send(
+        &[registry::register_agent(
+            impostor.pubkey(),
+            mint.pubkey(),
+            &config,
+            registry::RegisterAgentArgs {
+                name: "SUMMAR1ZER!".into(),
+                ..agent_args()
+            },
+        )],
+        &[&impostor, &mint],
+    );
+    assert_anchor_error(result, RegistryError::NameTaken);
+
+    // Letters from other scripts that pass for Latin ones aren't allowed at all
+    for name in ["\u{405}ummarizer", "Summarizer\u{200b}", "summarizér"] {
+        let mint = Keypair::new();
+        let result = env.send(
+            &[registry::register_agent(
+                impostor.pubkey(),
+                mint.pubkey(),
+                &config,
+                registry::RegisterAgentArgs {
+                    name: name.into(),
+                    ..agent_args()
+                },
+            )],
+            &[&impostor, &mint],
+        );
+        assert_anchor_error(result, RegistryError::AgentNameNotAscii);
+    }
+
+    // Nor are names with nothing to claim them by
+    let mint = Keypair::new();
+    let result = env.send(
+        &[registry::register_agent(
+            impostor.pubkey(),
+            mint.pubkey(),
+            &config,
+            registry::RegisterAgentArgs {
+                name: "- !!! -".into(),
+                ..agent_args()
+            },
+        )],
+        &[&impostor, &mint],
+    );
+    assert_anchor_error(result, RegistryError::InvalidAgentName);
+
+    let agent_id = env.register_agent(&impostor);
+    let profile: AgentProfile = env.fetch(&agent_id);
+    let result = env.send(
+        &[registry::rename_agent(
+            impostor.pubkey(),
+            &profile,
+            "Summ arizer".into(),
+        )],
+        &[&impostor],
+    );
+    assert_anchor_error(result, RegistryError::NameTaken);
+
+    // A rename that normalizes to the agent's own name still can't add lookalikes
+    let result = env.send(
+        &[registry::rename_agent(
+            impostor.pubkey(),
+            &profile,
+            format!("{}\u{200b}", profile.name),
+        )],
+        &[&impostor],
+    );
+    assert_anchor_error(result, RegistryError::AgentNameNotAscii);
+
+    // A rename without the name claims is refused
+    let result = env.send(
+        &[registry::update_agent(
+            impostor.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            registry::UpdateAgentArgs {
+                name: Some("Summarizer Pro".into()),
+                ..Default::default()
+            },
+        )],
+        &[&impostor],
+    );
+    assert_anchor_error(result, RegistryError::InvalidNameClaim);
+}
@@ -0,0 +1,3215 @@
+//! Happy path across all programs: register → request → submit → approve → distribute → rate.
+
+mod common;
+
+use std::collections::HashMap;
+
+use agent_registry::{
+    AgentEndpoint, CoCreator, PricingModel, RentTarget, Visibility, ACCESS_MONTH_SECS,
+    DEFAULT_CAPABILITY_LIMIT, DEFAULT_DESCRIPTION_LIMIT, HEALTH_LOG_LEN, MIN_HEALTH_CHECK_INTERVAL,
+    PROFILE_VERSION, REFERRAL_FEE_BPS,
+};
+use agentmarket_sdk::accounts::{
+    self, AccessGrant, AgentAttestation, AgentCapabilities, AgentIndex, AgentLineage, AgentProfile,
+    AgentReputationProfile, AgentRequestCount, AgentSale, AgentVersion, AllowlistEntry, BlockEntry,
+    Bundle, CapabilityIndex, CapabilityPrice, CoCreatorSplit, DistributionRecord, FeaturedIndex,
+    HealthLog, JurorPool, LanguageIndex, NameClaim, Rating, ReferralCode, RegistryConfig,
+    RequestStatus, RoyaltyConfig, ServiceOffering, ServiceRequest, SubscriptionTier, TrialCounter,
+};
+use agentmarket_sdk::instructions::reputation::{self, RatingScores};
+use agentmarket_sdk::instructions::royalty;
+use agentmarket_sdk::instructions::{escrow, guardian, registry};
+use agentmarket_sdk::{pda, Pubkey, ROYALTY_RULE_SET};
+use anchor_lang::solana_program::native_token::LAMPORTS_PER_SOL;
+use anchor_lang::solana_program::program::MAX_RETURN_DATA;
+use anchor_lang::{AccountSerialize, AnchorDeserialize};
+use anchor_spl::associated_token::get_associated_token_address;
+use marketplace_escrow::{
+    JUROR_FEE_BPS, JUROR_STAKE, NON_VOTER_PENALTY, PANEL_SIZE, REVIEW_WINDOW, VOTING_WINDOW,
+};
+use mpl_token_metadata::accounts::Metadata;
+use mpl_token_metadata::types::{ProgrammableConfig, TokenStandard};
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+
+use common::{agent_args, TestEnv, FEATURED_PRICE_PER_DAY, HEARTBEAT_WINDOW, SHARES};
+
+#[test]
+fn request_lifecycle_pays_out_and_records_reputation() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+
+    let agent_id = env.register_agent(&creator);
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert_eq!(profile.creator, creator.pubkey());
+    assert!(profile.is_active);
+
+    // The agent NFT is a verified member of the platform collection
+    let metadata = env
+        .svm
+        .get_account(&pda::token_metadata(&profile.nft_mint).0)
+        .unwrap();
+    let collection = Metadata::safe_deserialize(&metadata.data)
+        .unwrap()
+        .collection
+        .unwrap();
+    assert_eq!(collection.key, pda::collection_mint().0);
+    assert!(collection.verified);
+
+    // Create: the payment moves into the escrow PDA
+    let amount = LAMPORTS_PER_SOL;
+    let request = env.open_request(&user, agent_id, amount);
+    let escrow_account = pda::escrow_account(&request).0;
+    assert_eq!(env.balance(&escrow_account), amount);
+    let state: ServiceRequest = env.fetch(&request);
+    assert!(state.status == RequestStatus::Pending);
+    assert_eq!(state.agent_id, agent_id);
+
+    // Submit
+    env.send(
+        &[escrow::submit_result(
+            request,
+            agent_id,
+            profile.nft_mint,
+            creator.pubkey(),
+            "A three-paragraph summary".into(),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let state: ServiceRequest = env.fetch(&request);
+    assert!(state.status == RequestStatus::Completed);
+    assert!(state.completed_at.is_some());
+
+    // Approve: escrow splits 85/10/5 between creator, platform, and treasury
+    let creator_before = env.balance(&creator.pubkey());
+    let platform_before = env.balance(&env.platform_wallet);
+    let treasury_before = env.balance(&env.treasury_wallet);
+    env.send(
+        &[escrow::approve_result(
+            request,
+            agent_id,
+            user.pubkey(),
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+        )],
+        &[&user],
+    )
+    .unwrap();
+
+    let state: ServiceRequest = env.fetch(&request);
+    assert!(state.status == RequestStatus::Approved);
+    assert_eq!(env.balance(&escrow_account), 0);
+    assert_eq!(
+        env.balance(&creator.pubkey()) - creator_before,
+        amount * 85 / 100
+    );
+    assert_eq!(
+        env.balance(&env.platform_wallet) - platform_before,
+        amount * 10 / 100
+    );
+    assert_eq!(
+        env.balance(&env.treasury_wallet) - treasury_before,
+        amount - amount * 85 / 100 - amount * 10 / 100
+    );
+
+    // Distribute a tip through the royalty splitter using the configured shares
+    let tip = LAMPORTS_PER_SOL / 2;
+    let creator_before = env.balance(&creator.pubkey());
+    let platform_before = env.balance(&env.platform_wallet);
+    env.send(
+        &[royalty::distribute_payment(
+            user.pubkey(),
+            user.pubkey(),
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+            0,
+            tip,
+        )],
+        &[&user],
+    )
+    .unwrap();
+
+    let record: DistributionRecord = env.fetch(&pda::distribution_record(0).0);
+    assert_eq!(record.creator, creator.pubkey());
+    assert_eq!(record.total_amount, tip);
+    assert_eq!(record.creator_amount, tip * SHARES.creator as u64 / 100);
+    assert_eq!(
+        env.balance(&creator.pubkey()) - creator_before,
+        record.creator_amount
+    );
+    assert_eq!(
+        env.balance(&env.platform_wallet) - platform_before,
+        record.platform_amount
+    );
+    let config: RoyaltyConfig = env.fetch(&pda::royalty_config().0);
+    assert_eq!(config.total_transactions, 1);
+    assert_eq!(config.total_distributed, tip);
+
+    // Rate
+    env.send(
+        &[reputation::submit_rating(
+            user.pubkey(),
+            agent_id,
+            request,
+            RatingScores {
+                stars: 5,
+                quality: 4,
+                speed: 5,
+                value: 4,
+            },
+            "Fast and accurate".into(),
+        )],
+        &[&user],
+    )
+    .unwrap();
+
+    let rating: Rating = env.fetch(&pda::rating(&user.pubkey(), &request).0);
+    assert_eq!(rating.stars, 5);
+    assert_eq!(rating.request_id, request);
+    let aggregate: AgentReputationProfile = env.fetch(&pda::agent_reputation(&agent_id).0);
+    assert_eq!(aggregate.agent_id, agent_id);
+    assert_eq!(aggregate.total_ratings, 1);
+    assert_eq!(aggregate.average_rating, 5);
+
+    // The aggregate is mirrored onto the registry profile
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert_eq!(profile.reputation_score, 5);
+    assert_eq!(profile.total_services, 1);
+}
+
+#[test]
+fn requests_pin_a_published_agent_version() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    for (minor, ipfs_hash) in [(0, "QmRelease100"), (1, "QmRelease110")] {
+        env.send(
+            &[registry::publish_agent_version(
+                creator.pubkey(),
+                agent_id,
+                nft_mint,
+                registry::AgentVersionArgs {
+                    major: 1,
+                    minor,
+                    patch: 0,
+                    ipfs_hash: ipfs_hash.into(),
+                    changelog_hash: [minor as u8; 32],
+                },
+            )],
+            &[&creator],
+        )
+        .unwrap();
+    }
+
+    let version_address = pda::agent_version(&agent_id, 1, 1, 0).0;
+    let version: AgentVersion = env.fetch(&version_address);
+    assert_eq!(version.agent_id, agent_id);
+    assert_eq!(version.ipfs_hash, "QmRelease110");
+    assert_eq!(version.publisher, creator.pubkey());
+    // The profile follows the newest release
+    assert_eq!(
+        env.fetch::<AgentProfile>(&agent_id).ipfs_hash,
+        "QmRelease110"
+    );
+
+    let request = env.open_request(&user, agent_id, LAMPORTS_PER_SOL);
+    assert_eq!(env.fetch::<ServiceRequest>(&request).agent_version, None);
+    env.send(
+        &[escrow::pin_agent_version(
+            request,
+            version_address,
+            user.pubkey(),
+        )],
+        &[&user],
+    )
+    .unwrap();
+    assert_eq!(
+        env.fetch::<ServiceRequest>(&request).agent_version,
+        Some(version_address)
+    );
+}
+
+#[test]
+fn compressed_agents_are_leaves_in_the_agent_tree() {
+    let mut env = TestEnv::new();
+    let merkle_tree = env.init_agent_tree();
+    let user = env.funded_keypair(10);
+
+    for leaf_index in 0..2 {
+        let creator = env.funded_keypair(10);
+        let registry_config: RegistryConfig = env.fetch(&pda::registry_config().0);
+        env.send(
+            &[registry::register_agent_compressed(
+                creator.pubkey(),
+                merkle_tree,
+                &registry_config,
+                agent_args(),
+            )],
+            &[&creator],
+        )
+        .unwrap();
+
+        let agent_id = pda::agent_profile(&creator.pubkey()).0;
+        let profile: AgentProfile = env.fetch(&agent_id);
+        assert_eq!(
+            profile.nft_mint,
+            pda::compressed_asset(&merkle_tree, leaf_index).0
+        );
+        assert!(profile.is_active);
+        // No mint or token account is created for the agent
+        assert!(env.svm.get_account(&profile.nft_mint).is_none());
+
+        // Compressed agents take requests like any other
+        env.open_request(&user, agent_id, LAMPORTS_PER_SOL);
+    }
+}
+
+#[test]
+fn agents_are_indexed_by_capability() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+
+    let summarization = pda::capability_index("summarization", &agent_id).0;
+    let index: CapabilityIndex = env.fetch(&summarization);
+    assert_eq!(index.capability, pda::capability("summarization").0);
+    assert_eq!(index.agent_id, agent_id);
+
+    let admin = env.admin.pubkey();
+    env.send_as_admin(&[registry::add_capability(
+        admin,
+        "research".into(),
+        "Research".into(),
+        String::new(),
+    )])
+    .unwrap();
+    let profile: AgentProfile = env.fetch(&agent_id);
+    env.send(
+        &[registry::update_agent_capabilities(
+            creator.pubkey(),
+            &profile,
+            vec!["research".into()],
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    // Dropping a capability closes its index
+    assert_eq!(env.balance(&summarization), 0);
+    let research = pda::capability_index("research", &agent_id).0;
+    assert_eq!(env.fetch::<CapabilityIndex>(&research).agent_id, agent_id);
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert_eq!(profile.capabilities, vec!["research".to_owned()]);
+}
+
+#[test]
+fn endpoints_prove_control_by_signing_a_challenge() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    assert!(!env.fetch::<AgentProfile>(&agent_id).endpoint_verified);
+
+    env.send(
+        &[registry::request_endpoint_challenge(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let challenge: EndpointChallenge = env.fetch(&pda::endpoint_challenge(&agent_id).0);
+
+    // The key the endpoint publishes signs the nonce
+    let endpoint_key = Keypair::new();
+    let message = registry::endpoint_challenge_message(&agent_id, &challenge.nonce);
+    let signature = endpoint_key.sign_message(&message);
+    env.send(
+        &[
+            registry::ed25519_verify(&endpoint_key.pubkey(), signature.as_array(), &message),
+            registry::attest_endpoint(creator.pubkey(), agent_id, nft_mint, endpoint_key.pubkey()),
+        ],
+        &[&creator],
+    )
+    .unwrap();
+    assert!(env.fetch::<AgentProfile>(&agent_id).endpoint_verified);
+    assert_eq!(env.balance(&pda::endpoint_challenge(&agent_id).0), 0);
+
+    // Rotating the endpoints clears the verification
+    env.send(
+        &[registry::rotate_endpoints(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            vec![AgentEndpoint {
+                url: "https://agents.example/summarizer-v2".into(),
+                region: "us-east".into(),
+                priority: 0,
+            }],
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert!(!env.fetch::<AgentProfile>(&agent_id).endpoint_verified);
+}
+
+#[test]
+fn endpoints_are_kept_in_failover_order() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    let endpoint = |region: &str, priority| AgentEndpoint {
+        url: format!("https://{region}.agents.example/summarizer"),
+        region: region.into(),
+        priority,
+    };
+    env.send(
+        &[registry::rotate_endpoints(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            vec![
+                endpoint("eu-west", 2),
+                endpoint("us-east", 0),
+                endpoint("ap-south", 1),
+            ],
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    let profile: AgentProfile = env.fetch(&agent_id);
+    let regions: Vec<&str> = profile
+        .endpoints
+        .iter()
+        .map(|e| e.region.as_str())
+        .collect();
+    assert_eq!(regions, ["us-east", "ap-south", "eu-west"]);
+}
+
+#[test]
+fn requests_order_an_offering_from_the_agents_catalog() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    env.send(
+        &[registry::add_service_offering(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            registry::ServiceOfferingArgs {
+                offering_id: 1,
+                name: "Paper summary".into(),
+                description: "A one-page summary of a research paper".into(),
+                price: LAMPORTS_PER_SOL / 2,
+                turnaround_secs: 3_600,
+            },
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let offering_address = pda::service_offering(&agent_id, 1).0;
+    let offering: ServiceOffering = env.fetch(&offering_address);
+    assert_eq!(offering.agent_id, agent_id);
+    assert_eq!(offering.price, LAMPORTS_PER_SOL / 2);
+    assert!(offering.is_active);
+
+    let request = env.open_request(&user, agent_id, LAMPORTS_PER_SOL / 2);
+    env.send(
+        &[escrow::select_service_offering(
+            request,
+            offering_address,
+            user.pubkey(),
+        )],
+        &[&user],
+    )
+    .unwrap();
+    assert_eq!(
+        env.fetch::<ServiceRequest>(&request).service_offering,
+        Some(offering_address)
+    );
+
+    env.send(
+        &[registry::update_service_offering(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            1,
+            registry::UpdateServiceOfferingArgs {
+                price: Some(LAMPORTS_PER_SOL),
+                turnaround_secs: Some(1_800),
+                ..Default::default()
+            },
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let offering: ServiceOffering = env.fetch(&offering_address);
+    assert_eq!(offering.price, LAMPORTS_PER_SOL);
+    assert_eq!(offering.turnaround_secs, 1_800);
+
+    env.send(
+        &[registry::remove_service_offering(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            1,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(env.balance(&offering_address), 0);
+}
+
+#[test]
+fn verifier_grants_and_revokes_the_verified_badge() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    assert!(!env.fetch::<AgentProfile>(&agent_id).is_verified);
+
+    env.send_as_admin(&[registry::verify_agent(env.admin.pubkey(), agent_id)])
+        .unwrap();
+    assert!(env.fetch::<AgentProfile>(&agent_id).is_verified);
+
+    env.send_as_admin(&[registry::revoke_verification(env.admin.pubkey(), agent_id)])
+        .unwrap();
+    assert!(!env.fetch::<AgentProfile>(&agent_id).is_verified);
+}
+
+#[test]
+fn issuers_attest_to_agents_under_a_schema() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let issuer = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let schema = [7; 32];
+
+    env.send(
+        &[registry::issue_agent_attestation(
+            issuer.pubkey(),
+            agent_id,
+            registry::AgentAttestationArgs {
+                schema,
+                data_hash: [1; 32],
+                expires_at: None,
+            },
+        )],
+        &[&issuer],
+    )
+    .unwrap();
+    let address = pda::agent_attestation(&agent_id, &issuer.pubkey(), &schema).0;
+    let attestation: AgentAttestation = env.fetch(&address);
+    assert_eq!(attestation.agent_id, agent_id);
+    assert_eq!(attestation.issuer, issuer.pubkey());
+    assert!(attestation.is_valid(attestation.issued_at));
+
+    env.send(
+        &[registry::revoke_agent_attestation(
+            issuer.pubkey(),
+            agent_id,
+            schema,
+        )],
+        &[&issuer],
+    )
+    .unwrap();
+    let attestation: AgentAttestation = env.fetch(&address);
+    assert!(!attestation.is_valid(attestation.issued_at));
+
+    // Issuing again renews the revoked attestation with the new evidence
+    env.send(
+        &[registry::issue_agent_attestation(
+            issuer.pubkey(),
+            agent_id,
+            registry::AgentAttestationArgs {
+                schema,
+                data_hash: [2; 32],
+                expires_at: None,
+            },
+        )],
+        &[&issuer],
+    )
+    .unwrap();
+    let attestation: AgentAttestation = env.fetch(&address);
+    assert!(attestation.is_valid(attestation.issued_at));
+    assert_eq!(attestation.data_hash, [2; 32]);
+}
+
+#[test]
+fn co_creators_split_the_creator_share() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let designer = env.funded_keypair(1);
+    let trainer = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    env.send(
+        &[registry::set_co_creators(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            vec![
+                CoCreator {
+                    key: designer.pubkey(),
+                    bps: 2_000,
+                },
+                CoCreator {
+                    key: trainer.pubkey(),
+                    bps: 500,
+                },
+            ],
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let split: CoCreatorSplit = env.fetch(&pda::co_creator_split(&agent_id).0);
+    assert_eq!(split.agent_id, agent_id);
+    assert_eq!(split.co_creators.len(), 2);
+
+    let amount = LAMPORTS_PER_SOL;
+    let request = env.completed_request(&user, &creator, agent_id, amount);
+    let creator_before = env.balance(&creator.pubkey());
+    let designer_before = env.balance(&designer.pubkey());
+    let trainer_before = env.balance(&trainer.pubkey());
+    env.send(
+        &[escrow::with_co_creators(
+            escrow::approve_result(
+                request,
+                agent_id,
+                user.pubkey(),
+                creator.pubkey(),
+                env.platform_wallet,
+                env.treasury_wallet,
+            ),
+            &[designer.pubkey(), trainer.pubkey()],
+        )],
+        &[&user],
+    )
+    .unwrap();
+
+    // The co-creators' 20% and 5% come out of the creator's 85%
+    let creator_amount = amount * 85 / 100;
+    assert_eq!(
+        env.balance(&designer.pubkey()) - designer_before,
+        creator_amount * 2_000 / 10_000
+    );
+    assert_eq!(
+        env.balance(&trainer.pubkey()) - trainer_before,
+        creator_amount * 500 / 10_000
+    );
+    assert_eq!(
+        env.balance(&creator.pubkey()) - creator_before,
+        creator_amount - creator_amount * 2_500 / 10_000
+    );
+}
+
+#[test]
+fn silent_agents_are_marked_stale_until_reactivated() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let cranker = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    env.advance_clock(HEARTBEAT_WINDOW / 2);
+    env.send(
+        &[registry::heartbeat(creator.pubkey(), agent_id, nft_mint)],
+        &[&creator],
+    )
+    .unwrap();
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert!(profile.last_seen_at > profile.created_at);
+
+    // Anyone can deactivate the agent once a full window passes without a heartbeat
+    env.advance_clock(HEARTBEAT_WINDOW + 1);
+    env.send(&[registry::mark_stale(agent_id)], &[&cranker])
+        .unwrap();
+    assert!(!env.fetch::<AgentProfile>(&agent_id).is_active);
+
+    // Reactivating counts as a heartbeat, so the agent is not stale again at once
+    env.send(
+        &[registry::update_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            registry::UpdateAgentArgs {
+                is_active: Some(true),
+                ..Default::default()
+            },
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert!(profile.is_active);
+    assert!(env
+        .send(&[registry::mark_stale(agent_id)], &[&cranker])
+        .is_err());
+}
+
+#[test]
+fn agent_capacity_is_mirrored_to_the_escrow() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let first = env.funded_keypair(10);
+    let second = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    env.send(
+        &[registry::set_max_concurrent_requests(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            1,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(
+        env.fetch::<AgentProfile>(&agent_id).max_concurrent_requests,
+        1
+    );
+    let counter: AgentRequestCount = env.fetch(&pda::agent_requests(&agent_id).0);
+    assert_eq!(counter.agent_id, agent_id);
+    assert_eq!(counter.max_open_requests, 1);
+
+    // Settling the only open request makes room for the next one
+    let request = env.completed_request(&first, &creator, agent_id, LAMPORTS_PER_SOL);
+    env.send(
+        &[escrow::approve_result(
+            request,
+            agent_id,
+            first.pubkey(),
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+        )],
+        &[&first],
+    )
+    .unwrap();
+    env.open_request(&second, agent_id, LAMPORTS_PER_SOL);
+    let counter: AgentRequestCount = env.fetch(&pda::agent_requests(&agent_id).0);
+    assert_eq!(counter.open_requests, 1);
+}
+
+#[test]
+fn paused_agents_come_back_when_the_pause_ends() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let first = env.funded_keypair(10);
+    let second = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let profile: AgentProfile = env.fetch(&agent_id);
+    let paused_until = profile.created_at + 86_400;
+
+    env.send(
+        &[registry::pause_listings(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            paused_until,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(
+        env.fetch::<AgentProfile>(&agent_id).paused_until,
+        Some(paused_until)
+    );
+    let counter: AgentRequestCount = env.fetch(&pda::agent_requests(&agent_id).0);
+    assert_eq!(counter.paused_until, paused_until);
+    assert!(env
+        .send(
+            &[escrow::create_service_request(
+                first.pubkey(),
+                agent_id,
+                LAMPORTS_PER_SOL,
+                "Summarize the attached paper".into(),
+            )],
+            &[&first],
+        )
+        .is_err());
+
+    // Requests are accepted again once the pause lapses, without a resume
+    env.advance_clock(86_400);
+    env.open_request(&first, agent_id, LAMPORTS_PER_SOL);
+
+    // Resuming ends a later pause early
+    env.send(
+        &[registry::pause_listings(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            paused_until + 86_400,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    env.send(
+        &[registry::resume_listings(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(env.fetch::<AgentProfile>(&agent_id).paused_until, None);
+    env.open_request(&second, agent_id, LAMPORTS_PER_SOL);
+}
+
+#[test]
+fn capability_schemas_tell_which_agents_chain() {
+    let mut env = TestEnv::new();
+    let upstream_creator = env.funded_keypair(10);
+    let downstream_creator = env.funded_keypair(10);
+    let upstream = env.register_agent(&upstream_creator);
+    let downstream = env.register_agent(&downstream_creator);
+    let upstream_mint = env.fetch::<AgentProfile>(&upstream).nft_mint;
+    let downstream_mint = env.fetch::<AgentProfile>(&downstream).nft_mint;
+
+    env.send(
+        &[registry::set_capability_schema(
+            upstream_creator.pubkey(),
+            upstream,
+            upstream_mint,
+            "summarization".into(),
+            "bafkreipdfinput".into(),
+            "bafkreisummary".into(),
+        )],
+        &[&upstream_creator],
+    )
+    .unwrap();
+    env.send(
+        &[registry::set_capability_schema(
+            downstream_creator.pubkey(),
+            downstream,
+            downstream_mint,
+            "summarization".into(),
+            "bafkreisummary".into(),
+            "bafkreibrief".into(),
+        )],
+        &[&downstream_creator],
+    )
+    .unwrap();
+
+    let upstream_profile: AgentProfile = env.fetch(&upstream);
+    let downstream_profile: AgentProfile = env.fetch(&downstream);
+    assert_eq!(upstream_profile.capability_schemas.len(), 1);
+    assert!(accounts::feeds_into(
+        (&upstream_profile, "summarization"),
+        (&downstream_profile, "summarization"),
+    ));
+    assert!(!accounts::feeds_into(
+        (&downstream_profile, "summarization"),
+        (&upstream_profile, "summarization"),
+    ));
+
+    // Declaring again replaces the entry, and clearing removes it
+    env.send(
+        &[registry::set_capability_schema(
+            upstream_creator.pubkey(),
+            upstream,
+            upstream_mint,
+            "summarization".into(),
+            "bafkreipdfinput".into(),
+            "bafkreimarkdown".into(),
+        )],
+        &[&upstream_creator],
+    )
+    .unwrap();
+    let upstream_profile: AgentProfile = env.fetch(&upstream);
+    assert_eq!(upstream_profile.capability_schemas.len(), 1);
+    assert!(!accounts::feeds_into(
+        (&upstream_profile, "summarization"),
+        (&downstream_profile, "summarization"),
+    ));
+
+    env.send(
+        &[registry::clear_capability_schema(
+            upstream_creator.pubkey(),
+            upstream,
+            upstream_mint,
+            "summarization".into(),
+        )],
+        &[&upstream_creator],
+    )
+    .unwrap();
+    let upstream_profile: AgentProfile = env.fetch(&upstream);
+    assert!(accounts::capability_schema(&upstream_profile, "summarization").is_none());
+}
+
+#[test]
+fn forks_record_lineage_and_pay_the_parent() {
+    let mut env = TestEnv::new();
+    let parent_creator = env.funded_keypair(10);
+    let fork_creator = env.funded_keypair(10);
+    let payer = env.funded_keypair(10);
+    let parent = env.register_agent(&parent_creator);
+
+    let registry_config: RegistryConfig = env.fetch(&pda::registry_config().0);
+    let mint = Keypair::new();
+    env.send(
+        &[registry::fork_agent(
+            fork_creator.pubkey(),
+            mint.pubkey(),
+            parent,
+            &registry_config,
+            agent_args(),
+            200,
+        )],
+        &[&fork_creator, &mint],
+    )
+    .unwrap();
+    let fork = pda::agent_profile(&fork_creator.pubkey()).0;
+    let profile: AgentProfile = env.fetch(&fork);
+    assert_eq!(profile.parent_agent, Some(parent));
+    assert_eq!(profile.creator, fork_creator.pubkey());
+    let lineage: AgentLineage = env.fetch(&pda::agent_lineage(&fork).0);
+    assert_eq!(lineage.parent_agent, parent);
+    assert_eq!(lineage.royalty_bps, 200);
+
+    // 2% of a lineage payment goes to the parent's creator, the rest is split as usual
+    let config: RoyaltyConfig = env.fetch(&pda::royalty_config().0);
+    let parent_before = env.balance(&parent_creator.pubkey());
+    env.send(
+        &[royalty::distribute_lineage_payment(
+            payer.pubkey(),
+            payer.pubkey(),
+            fork_creator.pubkey(),
+            fork,
+            parent,
+            parent_creator.pubkey(),
+            &config,
+            LAMPORTS_PER_SOL,
+        )],
+        &[&payer],
+    )
+    .unwrap();
+    assert_eq!(
+        env.balance(&parent_creator.pubkey()) - parent_before,
+        LAMPORTS_PER_SOL / 50
+    );
+    let record: DistributionRecord =
+        env.fetch(&pda::distribution_record(config.total_transactions).0);
+    assert_eq!(record.creator, fork_creator.pubkey());
+    assert_eq!(
+        record.total_amount,
+        LAMPORTS_PER_SOL - LAMPORTS_PER_SOL / 50
+    );
+}
+
+#[test]
+fn subscription_agents_offer_named_tiers() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    env.send(
+        &[registry::update_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            registry::UpdateAgentArgs {
+                pricing: Some(PricingModel::Subscription {
+                    monthly: LAMPORTS_PER_SOL,
+                    payment_mint: None,
+                }),
+                ..Default::default()
+            },
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    for (tier_id, name, monthly_price, requests_per_month) in [
+        (1, "Starter", LAMPORTS_PER_SOL, 100),
+        (2, "Pro", 5 * LAMPORTS_PER_SOL, 1_000),
+    ] {
+        env.send(
+            &[registry::create_subscription_tier(
+                creator.pubkey(),
+                agent_id,
+                nft_mint,
+                registry::SubscriptionTierArgs {
+                    tier_id,
+                    name: name.into(),
+                    monthly_price,
+                    requests_per_month,
+                    features_hash: [tier_id as u8; 32],
+                },
+            )],
+            &[&creator],
+        )
+        .unwrap();
+    }
+    let pro: SubscriptionTier = env.fetch(&pda::subscription_tier(&agent_id, 2).0);
+    assert_eq!(pro.agent_id, agent_id);
+    assert_eq!(pro.name, "Pro");
+    assert_eq!(pro.monthly_price, 5 * LAMPORTS_PER_SOL);
+    assert_eq!(pro.requests_per_month, 1_000);
+    assert!(pro.is_active);
+
+    // Retired tiers stay readable for the subscriptions still running on them
+    env.send(
+        &[registry::retire_subscription_tier(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            1,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let starter: SubscriptionTier = env.fetch(&pda::subscription_tier(&agent_id, 1).0);
+    assert!(!starter.is_active);
+    assert_eq!(starter.requests_per_month, 100);
+}
+
+#[test]
+fn access_grants_track_paid_entitlements() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    env.send(
+        &[
+            registry::update_agent(
+                creator.pubkey(),
+                agent_id,
+                nft_mint,
+                registry::UpdateAgentArgs {
+                    pricing: Some(PricingModel::Subscription {
+                        monthly: LAMPORTS_PER_SOL,
+                        payment_mint: None,
+                    }),
+                    ..Default::default()
+                },
+            ),
+            registry::create_subscription_tier(
+                creator.pubkey(),
+                agent_id,
+                nft_mint,
+                registry::SubscriptionTierArgs {
+                    tier_id: 1,
+                    name: "Starter".into(),
+                    monthly_price: LAMPORTS_PER_SOL,
+                    requests_per_month: 100,
+                    features_hash: [1; 32],
+                },
+            ),
+        ],
+        &[&creator],
+    )
+    .unwrap();
+
+    let creator_before = env.balance(&creator.pubkey());
+    let profile: AgentProfile = env.fetch(&agent_id);
+    let config: RoyaltyConfig = env.fetch(&pda::royalty_config().0);
+    env.send(
+        &[registry::issue_access_grant(
+            user.pubkey(),
+            &profile,
+            &config,
+            1,
+            2,
+        )],
+        &[&user],
+    )
+    .unwrap();
+    let grant_address = pda::access_grant(&agent_id, &user.pubkey()).0;
+    let grant: AccessGrant = env.fetch(&grant_address);
+    assert_eq!(grant.user, user.pubkey());
+    assert_eq!(grant.remaining_quota, 200);
+    assert_eq!(grant.expires_at - grant.issued_at, 2 * ACCESS_MONTH_SECS);
+    assert!(env.balance(&creator.pubkey()) > creator_before);
+
+    env.send(
+        &[registry::consume_access(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            user.pubkey(),
+            5,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(
+        env.fetch::<AccessGrant>(&grant_address).remaining_quota,
+        195
+    );
+
+    // Buying again while the grant runs extends it
+    let config: RoyaltyConfig = env.fetch(&pda::royalty_config().0);
+    env.send(
+        &[registry::issue_access_grant(
+            user.pubkey(),
+            &profile,
+            &config,
+            1,
+            1,
+        )],
+        &[&user],
+    )
+    .unwrap();
+    let extended: AccessGrant = env.fetch(&grant_address);
+    assert_eq!(extended.remaining_quota, 295);
+    assert_eq!(extended.expires_at, grant.expires_at + ACCESS_MONTH_SECS);
+
+    env.send(
+        &[registry::revoke_access_grant(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            user.pubkey(),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert!(!env.fetch::<AccessGrant>(&grant_address).is_valid(0));
+}
+
+#[test]
+fn usd_pegged_agents_are_paid_at_the_current_sol_price() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    // $15 at $150 per SOL
+    let feed = env.pyth_price_feed(15_000_000_000, -8, 0);
+    env.send(
+        &[registry::update_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            registry::UpdateAgentArgs {
+                pricing: Some(PricingModel::UsdPegged {
+                    usd_cents: 1_500,
+                    feed,
+                }),
+                ..Default::default()
+            },
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    let quote = env
+        .send(&[registry::resolve_usd_price(agent_id, feed)], &[&user])
+        .unwrap();
+    let lamports = u64::from_le_bytes(quote.return_data.data.try_into().unwrap());
+    assert_eq!(lamports, LAMPORTS_PER_SOL / 10);
+
+    env.send(
+        &[
+            registry::resolve_usd_price(agent_id, feed),
+            escrow::create_service_request(
+                user.pubkey(),
+                agent_id,
+                lamports,
+                "Summarize the attached paper".into(),
+            ),
+        ],
+        &[&user],
+    )
+    .unwrap();
+    let request: ServiceRequest = env.fetch(&pda::service_request(&user.pubkey(), &agent_id).0);
+    assert_eq!(request.amount, LAMPORTS_PER_SOL / 10);
+}
+
+#[test]
+fn surge_priced_agents_charge_more_as_requests_queue_up() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    let base = LAMPORTS_PER_SOL / 10;
+    env.send(
+        &[registry::update_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            registry::UpdateAgentArgs {
+                pricing: Some(PricingModel::Surge {
+                    base,
+                    min: base / 2,
+                    max: base * 2,
+                    target_open: 1,
+                    step_bps: 5_000,
+                }),
+                ..Default::default()
+            },
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    // Idle below the target, then half the base more per queued request up to the cap
+    for expected in [base / 2, base, base * 3 / 2, base * 2, base * 2] {
+        let user = env.funded_keypair(10);
+        let quote = env
+            .send(&[registry::resolve_surge_price(agent_id)], &[&user])
+            .unwrap();
+        let lamports = u64::from_le_bytes(quote.return_data.data.try_into().unwrap());
+        assert_eq!(lamports, expected);
+        env.send(
+            &[
+                registry::resolve_surge_price(agent_id),
+                escrow::create_service_request(
+                    user.pubkey(),
+                    agent_id,
+                    lamports,
+                    "Summarize the attached paper".into(),
+                ),
+            ],
+            &[&user],
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn promoted_agents_are_featured_until_their_promotion_lapses() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert_eq!(profile.featured_until, 0);
+    let platform_before = env.balance(&env.platform_wallet);
+    let treasury_before = env.balance(&env.treasury_wallet);
+
+    let config: RoyaltyConfig = env.fetch(&pda::royalty_config().0);
+    env.send(
+        &[registry::promote_agent(
+            creator.pubkey(),
+            &profile,
+            &config,
+            3,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    // The fee goes through the splitter, the creator share to the platform
+    let fee = 3 * FEATURED_PRICE_PER_DAY;
+    let treasury_share = fee * SHARES.treasury as u64 / 100;
+    assert_eq!(
+        env.balance(&env.platform_wallet) - platform_before,
+        fee - treasury_share
+    );
+    assert_eq!(
+        env.balance(&env.treasury_wallet) - treasury_before,
+        treasury_share
+    );
+    let record: DistributionRecord =
+        env.fetch(&pda::distribution_record(config.total_transactions).0);
+    assert_eq!(record.total_amount, fee);
+
+    let featured_until = env.fetch::<AgentProfile>(&agent_id).featured_until;
+    let index: FeaturedIndex = env.fetch(&pda::featured_index().0);
+    assert_eq!(index.entries.len(), 1);
+    assert_eq!(index.entries[0].agent_id, agent_id);
+    assert_eq!(index.entries[0].featured_until, featured_until);
+
+    // Promoting again extends the running promotion in place
+    let profile: AgentProfile = env.fetch(&agent_id);
+    let config: RoyaltyConfig = env.fetch(&pda::royalty_config().0);
+    env.send(
+        &[registry::promote_agent(
+            creator.pubkey(),
+            &profile,
+            &config,
+            2,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let extended = env.fetch::<AgentProfile>(&agent_id).featured_until;
+    assert_eq!(extended, featured_until + 2 * 86_400);
+    let index: FeaturedIndex = env.fetch(&pda::featured_index().0);
+    assert_eq!(index.entries.len(), 1);
+    assert_eq!(index.entries[0].featured_until, extended);
+}
+
+#[test]
+fn bundle_purchases_open_a_request_per_agent() {
+    let mut env = TestEnv::new();
+    let curator = env.funded_keypair(10);
+    let partner = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agents = [env.register_agent(&curator), env.register_agent(&partner)];
+
+    env.send(
+        &[registry::create_bundle(
+            curator.pubkey(),
+            registry::BundleArgs {
+                bundle_id: 1,
+                name: "Research desk".into(),
+                agents: agents.to_vec(),
+                price: 2 * LAMPORTS_PER_SOL,
+                discount_bps: 2_000,
+            },
+            &[curator.pubkey(), partner.pubkey()],
+        )],
+        &[&curator, &partner],
+    )
+    .unwrap();
+    let bundle_address = pda::bundle(&curator.pubkey(), 1).0;
+    let bundle: Bundle = env.fetch(&bundle_address);
+    assert_eq!(bundle.agents, agents);
+    assert_eq!(bundle.share_price(), 4 * LAMPORTS_PER_SOL / 5);
+
+    env.send(
+        &escrow::purchase_bundle(
+            user.pubkey(),
+            bundle_address,
+            &bundle,
+            "Review and summarize the attached paper".into(),
+        ),
+        &[&user],
+    )
+    .unwrap();
+    for agent_id in agents {
+        let request: ServiceRequest = env.fetch(&pda::service_request(&user.pubkey(), &agent_id).0);
+        assert_eq!(request.amount, bundle.share_price());
+        assert_eq!(request.bundle, Some(bundle_address));
+    }
+
+    env.send(
+        &[registry::update_bundle(
+            curator.pubkey(),
+            1,
+            registry::UpdateBundleArgs {
+                is_active: Some(false),
+                ..Default::default()
+            },
+        )],
+        &[&curator],
+    )
+    .unwrap();
+    assert!(!env.fetch::<Bundle>(&bundle_address).is_active);
+}
+
+#[test]
+fn consecutive_distributions_use_sequential_records() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(1);
+    let payer = env.funded_keypair(10);
+
+    for index in 0..3 {
+        env.send(
+            &[royalty::distribute_payment(
+                payer.pubkey(),
+                payer.pubkey(),
+                creator.pubkey(),
+                env.platform_wallet,
+                env.treasury_wallet,
+                index,
+                LAMPORTS_PER_SOL,
+            )],
+            &[&payer],
+        )
+        .unwrap();
+    }
+
+    let config: RoyaltyConfig = env.fetch(&pda::royalty_config().0);
+    assert_eq!(config.total_transactions, 3);
+    assert_eq!(config.total_distributed, 3 * LAMPORTS_PER_SOL);
+    for index in 0..3 {
+        let record: DistributionRecord = env.fetch(&pda::distribution_record(index).0);
+        assert_eq!(record.total_amount, LAMPORTS_PER_SOL);
+    }
+}
+
+#[test]
+fn registration_fees_are_swept_through_the_royalty_splitter() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let payer = env.funded_keypair(1);
+    let fee = LAMPORTS_PER_SOL / 100;
+    env.send_as_admin(&[registry::update_registry_config(
+        env.admin.pubkey(),
+        None,
+        None,
+        None,
+        None,
+        None,
+        Some(fee),
+    )])
+    .unwrap();
+
+    env.register_agent(&creator);
+    let fee_vault = pda::fee_vault().0;
+    assert_eq!(env.balance(&fee_vault), fee);
+
+    let platform_before = env.balance(&env.platform_wallet);
+    let config: RoyaltyConfig = env.fetch(&pda::royalty_config().0);
+    env.send(
+        &[registry::sweep_registration_fees(payer.pubkey(), &config)],
+        &[&payer],
+    )
+    .unwrap();
+    assert_eq!(env.balance(&fee_vault), 0);
+    assert!(env.balance(&env.platform_wallet) > platform_before);
+    let record: DistributionRecord =
+        env.fetch(&pda::distribution_record(config.total_transactions).0);
+    assert_eq!(record.total_amount, fee);
+}
+
+#[test]
+fn referred_registrations_pay_the_referrer_through_the_royalty_splitter() {
+    let mut env = TestEnv::new();
+    let referrer = env.funded_keypair(10);
+    let creator = env.funded_keypair(10);
+    let payer = env.funded_keypair(1);
+    let fee = LAMPORTS_PER_SOL / 100;
+    env.send_as_admin(&[registry::update_registry_config(
+        env.admin.pubkey(),
+        None,
+        None,
+        None,
+        None,
+        None,
+        Some(fee),
+    )])
+    .unwrap();
+
+    env.register_agent(&referrer);
+    env.send(
+        &[registry::create_referral_code(referrer.pubkey(), "summer")],
+        &[&referrer],
+    )
+    .unwrap();
+    let referral_code = pda::referral_code("summer").0;
+    let referral_vault = pda::referral_vault(&referral_code).0;
+    let vault_rent = env.balance(&referral_vault);
+
+    let mint = Keypair::new();
+    let registry_config: RegistryConfig = env.fetch(&pda::registry_config().0);
+    let fee_vault_before = env.balance(&pda::fee_vault().0);
+    env.send(
+        &[registry::register_agent_with_referral(
+            creator.pubkey(),
+            mint.pubkey(),
+            &registry_config,
+            "summer",
+            agent_args(),
+        )],
+        &[&creator, &mint],
+    )
+    .unwrap();
+    let share = fee * REFERRAL_FEE_BPS / 10_000;
+    assert_eq!(env.balance(&referral_vault), vault_rent + share);
+    assert_eq!(
+        env.balance(&pda::fee_vault().0),
+        fee_vault_before + fee - share
+    );
+    let code: ReferralCode = env.fetch(&referral_code);
+    assert_eq!(code.referrer, referrer.pubkey());
+    assert_eq!(code.referrals, 1);
+    assert_eq!(code.earned, share);
+
+    let referrer_before = env.balance(&referrer.pubkey());
+    let config: RoyaltyConfig = env.fetch(&pda::royalty_config().0);
+    env.send(
+        &[registry::sweep_referral_fees(
+            payer.pubkey(),
+            "summer",
+            referrer.pubkey(),
+            &config,
+        )],
+        &[&payer],
+    )
+    .unwrap();
+    assert_eq!(env.balance(&referral_vault), vault_rent);
+    assert!(env.balance(&referrer.pubkey()) > referrer_before);
+    let record: DistributionRecord =
+        env.fetch(&pda::distribution_record(config.total_transactions).0);
+    assert_eq!(record.creator, referrer.pubkey());
+    assert_eq!(record.total_amount, share);
+}
+
+#[test]
+fn agents_are_enumerable_through_the_agent_index() {
+    let mut env = TestEnv::new();
+    let first_creator = env.funded_keypair(10);
+    let second_creator = env.funded_keypair(10);
+    let first = env.register_agent(&first_creator);
+    let second = env.register_agent(&second_creator);
+
+    let config: RegistryConfig = env.fetch(&pda::registry_config().0);
+    assert_eq!(config.agent_count, 2);
+    for (index, agent_id) in [first, second].into_iter().enumerate() {
+        let entry: AgentIndex = env.fetch(&pda::agent_index(index as u64).0);
+        assert_eq!(entry.index, index as u64);
+        assert_eq!(entry.agent_id, agent_id);
+    }
+
+    // Entries outlive the agent; only its profile goes away
+    let profile: AgentProfile = env.fetch(&first);
+    env.send(
+        &[registry::deregister_agent(
+            first_creator.pubkey(),
+            first,
+            profile.nft_mint,
+            &profile.name,
+            &profile.capabilities,
+            &profile.languages,
+            None,
+        )],
+        &[&first_creator],
+    )
+    .unwrap();
+    assert_eq!(env.balance(&first), 0);
+    let entry: AgentIndex = env.fetch(&pda::agent_index(0).0);
+    assert_eq!(entry.agent_id, first);
+}
+
+#[test]
+fn allowlisted_users_can_hire_private_agents() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    env.send(
+        &[
+            registry::set_visibility(creator.pubkey(), agent_id, nft_mint, Visibility::Allowlist),
+            registry::add_allowlist_entry(creator.pubkey(), agent_id, nft_mint, user.pubkey()),
+        ],
+        &[&creator],
+    )
+    .unwrap();
+    assert!(env.fetch::<AgentProfile>(&agent_id).visibility == Visibility::Allowlist);
+    let counter: AgentRequestCount = env.fetch(&pda::agent_requests(&agent_id).0);
+    assert!(counter.allowlist_only);
+    let entry: AllowlistEntry = env.fetch(&pda::allowlist_entry(&agent_id, &user.pubkey()).0);
+    assert_eq!(entry.user, user.pubkey());
+
+    let request = env.open_request(&user, agent_id, LAMPORTS_PER_SOL);
+    let request: ServiceRequest = env.fetch(&request);
+    assert_eq!(request.user, user.pubkey());
+}
+
+#[test]
+fn unblocked_users_can_hire_the_agent_again() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    let block_entry = pda::block_entry(&agent_id, &user.pubkey()).0;
+
+    env.send(
+        &[registry::block_user(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            user.pubkey(),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let entry: BlockEntry = env.fetch(&block_entry);
+    assert_eq!(entry.agent_id, agent_id);
+    assert_eq!(entry.user, user.pubkey());
+
+    env.send(
+        &[registry::unblock_user(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            user.pubkey(),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(env.balance(&block_entry), 0);
+
+    let request = env.open_request(&user, agent_id, LAMPORTS_PER_SOL);
+    let request: ServiceRequest = env.fetch(&request);
+    assert_eq!(request.user, user.pubkey());
+}
+
+#[test]
+fn resized_profiles_take_longer_descriptions() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    let rent_before = env.balance(&agent_id);
+
+    env.send(
+        &[
+            registry::resize_profile(creator.pubkey(), agent_id, nft_mint, 16, 1_200),
+            registry::update_agent(
+                creator.pubkey(),
+                agent_id,
+                nft_mint,
+                registry::UpdateAgentArgs {
+                    description: Some("a".repeat(1_200)),
+                    ..Default::default()
+                },
+            ),
+        ],
+        &[&creator],
+    )
+    .unwrap();
+
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert_eq!(profile.capability_limit, 16);
+    assert_eq!(profile.description_limit, 1_200);
+    assert_eq!(profile.description.len(), 1_200);
+    assert_eq!(profile.nft_mint, nft_mint);
+    assert!(env.balance(&agent_id) > rent_before);
+}
+
+#[test]
+fn agent_profiles_are_readable_through_return_data() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    env.open_request(&user, agent_id, LAMPORTS_PER_SOL);
+
+    let result = env
+        .send(&[registry::get_agent_profile(agent_id)], &[&user])
+        .unwrap();
+    let view = registry::AgentProfileView::try_from_slice(&result.return_data.data).unwrap();
+    assert_eq!(view.profile.agent_id, agent_id);
+    assert_eq!(view.profile.name, common::agent_name(&creator.pubkey()));
+    assert_eq!(view.effective_price, Some(LAMPORTS_PER_SOL));
+    assert_eq!(view.payment_mint, None);
+    assert_eq!(view.open_requests, 1);
+    assert!(view.accepting_requests);
+    assert!(!view.verified);
+
+    // A paused agent still reads back, but takes no requests
+    env.send(
+        &[registry::pause_listings(
+            creator.pubkey(),
+            agent_id,
+            view.profile.nft_mint,
+            view.profile.created_at + 86_400,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let result = env
+        .send(&[registry::get_agent_profile(agent_id)], &[&user])
+        .unwrap();
+    let view = registry::AgentProfileView::try_from_slice(&result.return_data.data).unwrap();
+    assert!(!view.accepting_requests);
+}
+
+#[test]
+fn profiles_filled_to_their_default_limits_still_fit_in_return_data() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    env.send(
+        &[registry::update_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            registry::UpdateAgentArgs {
+                description: Some("d".repeat(DEFAULT_DESCRIPTION_LIMIT)),
+                ..Default::default()
+            },
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let capabilities: Vec<String> = (0..DEFAULT_CAPABILITY_LIMIT)
+        .map(|i| format!("capability-{i:09}"))
+        .collect();
+    let profile: AgentProfile = env.fetch(&agent_id);
+    env.send(
+        &[registry::update_agent_capabilities(
+            creator.pubkey(),
+            &profile,
+            capabilities.clone(),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    for capability in capabilities {
+        env.send(
+            &[registry::set_capability_schema(
+                creator.pubkey(),
+                agent_id,
+                nft_mint,
+                capability,
+                "i".repeat(64),
+                "o".repeat(64),
+            )],
+            &[&creator],
+        )
+        .unwrap();
+    }
+    let endpoints = (0..4)
+        .map(|priority| AgentEndpoint {
+            url: format!("https://{}.example/{priority}", "e".repeat(130)),
+            region: "us-east".into(),
+            priority,
+        })
+        .collect();
+    env.send(
+        &[registry::rotate_endpoints(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            endpoints,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    // The lists are counted rather than returned, so the view stays bounded
+    let result = env
+        .send(&[registry::get_agent_profile(agent_id)], &[&user])
+        .unwrap();
+    assert!(result.return_data.data.len() <= MAX_RETURN_DATA);
+    let view = registry::AgentProfileView::try_from_slice(&result.return_data.data).unwrap();
+    assert_eq!(view.profile.agent_id, agent_id);
+    assert_eq!(
+        view.profile.description_len as usize,
+        DEFAULT_DESCRIPTION_LIMIT
+    );
+    assert_eq!(
+        view.profile.capability_count as usize,
+        DEFAULT_CAPABILITY_LIMIT
+    );
+    assert_eq!(
+        view.profile.capability_schema_count as usize,
+        DEFAULT_CAPABILITY_LIMIT
+    );
+    assert_eq!(view.profile.endpoint_count, 4);
+}
+
+#[test]
+fn approved_payments_go_to_the_payout_wallet() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    let payout_wallet = Keypair::new().pubkey();
+    assert_eq!(
+        env.fetch::<AgentProfile>(&agent_id).payout_wallet,
+        creator.pubkey()
+    );
+
+    env.send(
+        &[registry::set_payout_wallet(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            payout_wallet,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(
+        env.fetch::<AgentProfile>(&agent_id).payout_wallet,
+        payout_wallet
+    );
+
+    let amount = LAMPORTS_PER_SOL;
+    let request = env.completed_request(&user, &creator, agent_id, amount);
+    let creator_before = env.balance(&creator.pubkey());
+    env.send(
+        &[escrow::approve_result(
+            request,
+            agent_id,
+            user.pubkey(),
+            payout_wallet,
+            env.platform_wallet,
+            env.treasury_wallet,
+        )],
+        &[&user],
+    )
+    .unwrap();
+    assert_eq!(env.balance(&payout_wallet), amount * 85 / 100);
+    assert_eq!(env.balance(&creator.pubkey()), creator_before);
+}
+
+#[test]
+fn registration_creates_the_reputation_profile() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+
+    let aggregate: AgentReputationProfile = env.fetch(&pda::agent_reputation(&agent_id).0);
+    assert_eq!(aggregate.agent_id, agent_id);
+    assert_eq!(aggregate.total_ratings, 0);
+}
+
+#[test]
+fn approved_payments_are_credited_to_the_profile() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+
+    let amount = LAMPORTS_PER_SOL;
+    for _ in 0..2 {
+        let user = env.funded_keypair(10);
+        let request = env.completed_request(&user, &creator, agent_id, amount);
+        env.send(
+            &[escrow::approve_result(
+                request,
+                agent_id,
+                user.pubkey(),
+                creator.pubkey(),
+                env.platform_wallet,
+                env.treasury_wallet,
+            )],
+            &[&user],
+        )
+        .unwrap();
+    }
+
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert_eq!(profile.total_earnings, 2 * (amount * 85 / 100));
+    assert_eq!(profile.total_services, 2);
+}
+
+#[test]
+fn programmable_agents_are_bound_to_the_royalty_rule_set() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let mint = Keypair::new();
+    let agent_id = pda::agent_profile(&creator.pubkey()).0;
+    let registry_config: RegistryConfig = env.fetch(&pda::registry_config().0);
+
+    env.send(
+        &[registry::register_agent_programmable(
+            creator.pubkey(),
+            mint.pubkey(),
+            &registry_config,
+            agent_args(),
+        )],
+        &[&creator, &mint],
+    )
+    .unwrap();
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert_eq!(profile.nft_mint, mint.pubkey());
+
+    let metadata = env
+        .svm
+        .get_account(&pda::token_metadata(&mint.pubkey()).0)
+        .unwrap();
+    let metadata = Metadata::safe_deserialize(&metadata.data).unwrap();
+    assert_eq!(
+        metadata.token_standard,
+        Some(TokenStandard::ProgrammableNonFungible)
+    );
+    assert_eq!(
+        metadata.programmable_config,
+        Some(ProgrammableConfig::V1 {
+            rule_set: Some(ROYALTY_RULE_SET)
+        })
+    );
+    assert_eq!(metadata.seller_fee_basis_points, 500);
+    assert!(metadata.collection.unwrap().verified);
+
+    // The creator holds the frozen token, which still passes the holder checks
+    let token: anchor_spl::token::TokenAccount = env.fetch(&get_associated_token_address(
+        &creator.pubkey(),
+        &mint.pubkey(),
+    ));
+    assert_eq!(token.amount, 1);
+    assert!(token.is_frozen());
+    let payout_wallet = Keypair::new().pubkey();
+    env.send(
+        &[registry::set_payout_wallet(
+            creator.pubkey(),
+            agent_id,
+            mint.pubkey(),
+            payout_wallet,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(
+        env.fetch::<AgentProfile>(&agent_id).payout_wallet,
+        payout_wallet
+    );
+}
+
+#[test]
+fn trial_requests_skip_the_escrow_deposit() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    env.send(
+        &[registry::set_trial_requests(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            1,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(env.fetch::<AgentProfile>(&agent_id).trial_requests, 1);
+    let counter: AgentRequestCount = env.fetch(&pda::agent_requests(&agent_id).0);
+    assert_eq!(counter.trial_requests, 1);
+
+    // The first request is free: the user pays rent only and nothing is escrowed
+    let user_before = env.balance(&user.pubkey());
+    let request = env.completed_request(&user, &creator, agent_id, LAMPORTS_PER_SOL);
+    assert!(user_before - env.balance(&user.pubkey()) < LAMPORTS_PER_SOL / 10);
+    assert_eq!(env.fetch::<ServiceRequest>(&request).amount, 0);
+    assert_eq!(env.balance(&pda::escrow_account(&request).0), 0);
+    let trial: TrialCounter = env.fetch(&pda::trial_counter(&agent_id, &user.pubkey()).0);
+    assert_eq!(trial.used, 1);
+
+    // Approving it releases nothing but still counts the service
+    env.send(
+        &[escrow::approve_result(
+            request,
+            agent_id,
+            user.pubkey(),
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+        )],
+        &[&user],
+    )
+    .unwrap();
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert_eq!(profile.total_services, 1);
+    assert_eq!(profile.total_earnings, 0);
+
+    // Another wallet's trial is its own
+    let other = env.funded_keypair(10);
+    let request = env.open_request(&other, agent_id, LAMPORTS_PER_SOL);
+    assert_eq!(env.fetch::<ServiceRequest>(&request).amount, 0);
+}
+
+#[test]
+fn agents_are_indexed_by_language() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    assert!(env.fetch::<AgentProfile>(&agent_id).languages.is_empty());
+
+    let profile: AgentProfile = env.fetch(&agent_id);
+    env.send(
+        &[registry::set_languages(
+            creator.pubkey(),
+            &profile,
+            vec!["en".into(), "pt-BR".into()],
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let portuguese = pda::language_index("pt-BR", &agent_id).0;
+    let index: LanguageIndex = env.fetch(&portuguese);
+    assert_eq!(index.language, "pt-BR");
+    assert_eq!(index.agent_id, agent_id);
+
+    // Dropping a language closes its index and keeps the rest
+    let profile: AgentProfile = env.fetch(&agent_id);
+    env.send(
+        &[registry::set_languages(
+            creator.pubkey(),
+            &profile,
+            vec!["en".into(), "es".into()],
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(env.balance(&portuguese), 0);
+    let spanish = pda::language_index("es", &agent_id).0;
+    assert_eq!(env.fetch::<LanguageIndex>(&spanish).agent_id, agent_id);
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert_eq!(profile.languages, vec!["en".to_owned(), "es".to_owned()]);
+
+    // Deregistering closes the language indexes too
+    env.send(
+        &[registry::deregister_agent(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            &profile.name,
+            &profile.capabilities,
+            &profile.languages,
+            None,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(env.balance(&pda::language_index("en", &agent_id).0), 0);
+    assert_eq!(env.balance(&spanish), 0);
+}
+
+#[test]
+fn agents_declare_the_agents_they_depend_on() {
+    let mut env = TestEnv::new();
+    let creators = [
+        env.funded_keypair(10),
+        env.funded_keypair(10),
+        env.funded_keypair(10),
+    ];
+    let [pipeline, summarizer, translator] = creators
+        .each_ref()
+        .map(|creator| env.register_agent(creator));
+    let [pipeline_creator, summarizer_creator, translator_creator] = &creators;
+
+    let profile: AgentProfile = env.fetch(&summarizer);
+    env.send(
+        &[registry::set_dependencies(
+            summarizer_creator.pubkey(),
+            &profile,
+            vec![translator],
+        )],
+        &[summarizer_creator],
+    )
+    .unwrap();
+    let profile: AgentProfile = env.fetch(&pipeline);
+    env.send(
+        &[registry::set_dependencies(
+            pipeline_creator.pubkey(),
+            &profile,
+            vec![summarizer, translator],
+        )],
+        &[pipeline_creator],
+    )
+    .unwrap();
+    assert_eq!(
+        env.fetch::<AgentProfile>(&pipeline).dependencies,
+        vec![summarizer, translator]
+    );
+
+    // Pipeline builders resolve the graph from the profiles alone
+    let profiles: HashMap<_, AgentProfile> = [pipeline, summarizer, translator]
+        .into_iter()
+        .map(|agent_id| (agent_id, env.fetch(&agent_id)))
+        .collect();
+    assert_eq!(
+        accounts::dependency_order(&pipeline, &profiles),
+        Some(vec![translator, summarizer, pipeline])
+    );
+
+    // Closing the loop makes the graph unresolvable
+    let profile: AgentProfile = env.fetch(&translator);
+    env.send(
+        &[registry::set_dependencies(
+            translator_creator.pubkey(),
+            &profile,
+            vec![pipeline],
+        )],
+        &[translator_creator],
+    )
+    .unwrap();
+    let profiles: HashMap<_, AgentProfile> = [pipeline, summarizer, translator]
+        .into_iter()
+        .map(|agent_id| (agent_id, env.fetch(&agent_id)))
+        .collect();
+    assert_eq!(accounts::dependency_order(&pipeline, &profiles), None);
+}
+
+#[test]
+fn soulbound_agents_keep_their_nft_frozen_until_deregistered() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let mint = Keypair::new();
+    let agent_id = pda::agent_profile(&creator.pubkey()).0;
+    let registry_config: RegistryConfig = env.fetch(&pda::registry_config().0);
+
+    env.send(
+        &[registry::register_agent_soulbound(
+            creator.pubkey(),
+            mint.pubkey(),
+            &registry_config,
+            agent_args(),
+        )],
+        &[&creator, &mint],
+    )
+    .unwrap();
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert!(profile.soulbound);
+    let token_account = get_associated_token_address(&creator.pubkey(), &mint.pubkey());
+    let token: anchor_spl::token::TokenAccount = env.fetch(&token_account);
+    assert_eq!(token.amount, 1);
+    assert!(token.is_frozen());
+
+    // The registry thaws the NFT to burn it on the way out
+    env.send(
+        &[registry::deregister_agent(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            &profile.name,
+            &profile.capabilities,
+            &profile.languages,
+            None,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(env.balance(&token_account), 0);
+    assert_eq!(env.balance(&agent_id), 0);
+}
+
+#[test]
+fn signed_manifests_register_agents_for_their_creator() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let mint = Keypair::new();
+    let agent_id = pda::agent_profile(&creator.pubkey()).0;
+    let registry_config: RegistryConfig = env.fetch(&pda::registry_config().0);
+
+    // The creator registers the operational key once
+    let operator = Keypair::new();
+    env.send(
+        &[registry::register_operator(
+            creator.pubkey(),
+            operator.pubkey(),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    // The build pipeline only ever holds the operational key and its own fee payer
+    let pipeline = env.funded_keypair(10);
+    let manifest = registry::agent_manifest(creator.pubkey(), 0, env.now() + 600, agent_args());
+    let message = registry::manifest_message(&manifest);
+    let signature = operator.sign_message(&message);
+    let creator_balance = env.balance(&creator.pubkey());
+    env.send(
+        &[
+            registry::ed25519_verify(&operator.pubkey(), signature.as_array(), &message),
+            registry::register_agent_with_manifest(
+                pipeline.pubkey(),
+                mint.pubkey(),
+                &registry_config,
+                operator.pubkey(),
+                &manifest,
+            ),
+        ],
+        &[&pipeline, &mint],
+    )
+    .unwrap();
+
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert_eq!(profile.creator, creator.pubkey());
+    assert_eq!(profile.name, agent_args().name);
+    assert_eq!(profile.capabilities, agent_args().capabilities);
+    assert_eq!(profile.nft_mint, mint.pubkey());
+    assert!(profile.is_active);
+    assert_eq!(
+        env.token_balance(&get_associated_token_address(
+            &creator.pubkey(),
+            &mint.pubkey()
+        )),
+        1
+    );
+    // The pipeline paid for everything
+    assert_eq!(env.balance(&creator.pubkey()), creator_balance);
+
+    let operator_key: accounts::OperatorKey =
+        env.fetch(&pda::operator_key(&creator.pubkey(), &operator.pubkey()).0);
+    assert_eq!(operator_key.next_nonce, 1);
+}
+
+#[test]
+fn migrated_metadata_only_changes_through_the_registry() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let profile: AgentProfile = env.fetch(&agent_id);
+
+    env.send(
+        &[registry::migrate_metadata_authority(
+            creator.pubkey(),
+            &profile,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    env.send(
+        &[registry::update_agent_metadata(
+            creator.pubkey(),
+            &profile,
+            None,
+            Some("https://agents.example/summarizer-v2.json".into()),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    let metadata = env
+        .svm
+        .get_account(&pda::token_metadata(&profile.nft_mint).0)
+        .unwrap();
+    let metadata = Metadata::safe_deserialize(&metadata.data).unwrap();
+    assert_eq!(metadata.update_authority, pda::metadata_authority().0);
+    assert_eq!(
+        metadata.name.trim_end_matches('\0'),
+        format!("AgentMarket: {}", profile.name)
+    );
+    assert_eq!(
+        metadata.uri.trim_end_matches('\0'),
+        "https://agents.example/summarizer-v2.json"
+    );
+    assert_eq!(metadata.symbol.trim_end_matches('\0'), agent_args().symbol);
+}
+
+#[test]
+fn retired_agents_point_users_to_their_successor() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let successor_creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let successor = env.register_agent(&successor_creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    env.send(
+        &[registry::retire_agent(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            Some(successor),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    // The profile stays for its history, deactivated
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert!(profile.retired);
+    assert!(!profile.is_active);
+    assert_eq!(profile.successor, Some(successor));
+    let agent_requests: AgentRequestCount = env.fetch(&pda::agent_requests(&agent_id).0);
+    assert!(agent_requests.retired);
+    assert_eq!(agent_requests.successor, Some(successor));
+
+    // New work goes to the successor instead
+    let request = env.open_request(&user, successor, LAMPORTS_PER_SOL);
+    assert_eq!(env.fetch::<ServiceRequest>(&request).agent_id, successor);
+}
+
+#[test]
+fn a_management_authority_runs_the_agent_for_the_holder() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    // Stands in for a multisig vault
+    let vault = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let profile: AgentProfile = env.fetch(&agent_id);
+
+    env.send(
+        &[registry::set_agent_authority(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            Some(vault.pubkey()),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(
+        env.fetch::<AgentProfile>(&agent_id).authority,
+        Some(vault.pubkey())
+    );
+
+    // The vault updates the agent without holding its NFT
+    env.send(
+        &[registry::rename_agent(
+            vault.pubkey(),
+            &profile,
+            "Vault Summarizer".into(),
+        )],
+        &[&vault],
+    )
+    .unwrap();
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert_eq!(profile.name, "Vault Summarizer");
+
+    // It answers the agent's requests too
+    let user = env.funded_keypair(10);
+    let request = env.open_request(&user, agent_id, LAMPORTS_PER_SOL);
+    env.send(
+        &[escrow::submit_result(
+            request,
+            agent_id,
+            profile.nft_mint,
+            vault.pubkey(),
+            "A three-paragraph summary".into(),
+        )],
+        &[&vault],
+    )
+    .unwrap();
+    assert!(env.fetch::<ServiceRequest>(&request).status == RequestStatus::Completed);
+
+    // Deregistering takes the holder, who burns the NFT, and the vault
+    env.send(
+        &[registry::deregister_agent(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            &profile.name,
+            &profile.capabilities,
+            &profile.languages,
+            Some(vault.pubkey()),
+        )],
+        &[&creator, &vault],
+    )
+    .unwrap();
+    assert_eq!(env.balance(&agent_id), 0);
+}
+
+#[test]
+fn registered_operators_submit_results_for_the_agent() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let operator = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    env.send(
+        &[registry::register_operator(
+            creator.pubkey(),
+            operator.pubkey(),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    let request = env.open_request(&user, agent_id, LAMPORTS_PER_SOL);
+    env.send(
+        &[escrow::submit_result_as_operator(
+            request,
+            agent_id,
+            nft_mint,
+            creator.pubkey(),
+            operator.pubkey(),
+            "A three-paragraph summary".into(),
+        )],
+        &[&operator],
+    )
+    .unwrap();
+    let state: ServiceRequest = env.fetch(&request);
+    assert!(state.status == RequestStatus::Completed);
+    assert_eq!(state.result_data, "A three-paragraph summary");
+}
+
+#[test]
+fn frozen_agents_take_requests_again_once_unfrozen() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+
+    env.send_as_admin(&[registry::freeze_agent(env.admin.pubkey(), agent_id)])
+        .unwrap();
+    assert!(env.fetch::<AgentProfile>(&agent_id).frozen);
+    assert!(
+        env.fetch::<AgentRequestCount>(&pda::agent_requests(&agent_id).0)
+            .frozen
+    );
+
+    env.send_as_admin(&[registry::unfreeze_agent(env.admin.pubkey(), agent_id)])
+        .unwrap();
+    assert!(!env.fetch::<AgentProfile>(&agent_id).frozen);
+    let request = env.open_request(&user, agent_id, LAMPORTS_PER_SOL);
+    assert_eq!(env.fetch::<ServiceRequest>(&request).agent_id, agent_id);
+}
+
+#[test]
+fn profiles_from_before_versioning_are_upgraded_in_place() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let payer = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let current_size = env.svm.get_account(&agent_id).unwrap().data.len();
+
+    // Rewrite the profile as the pre-versioning layout left it: no payout
+    // wallet, no version byte, and no room to spare
+    let mut profile: AgentProfile = env.fetch(&agent_id);
+    profile.payout_wallet = Pubkey::default();
+    profile.version = 0;
+    let mut data = Vec::new();
+    profile.try_serialize(&mut data).unwrap();
+    data.pop();
+    let mut account = env.svm.get_account(&agent_id).unwrap();
+    account.data = data;
+    env.svm.set_account(agent_id, account).unwrap();
+
+    // Anyone can pay for the upgrade
+    env.send(
+        &[registry::migrate_profile(payer.pubkey(), agent_id)],
+        &[&payer],
+    )
+    .unwrap();
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert_eq!(profile.version, PROFILE_VERSION);
+    assert_eq!(profile.payout_wallet, creator.pubkey());
+    assert_eq!(
+        env.svm.get_account(&agent_id).unwrap().data.len(),
+        current_size
+    );
+}
+
+#[test]
+fn service_requests_from_before_panel_slots_are_upgraded_in_place() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let admin = env.admin.pubkey();
+    let agent_id = env.register_agent(&creator);
+    let request = env.disputed_request(&user, &creator, agent_id, LAMPORTS_PER_SOL);
+    let current_size = env.svm.get_account(&request).unwrap().data.len();
+
+    // Rewrite the request as the layout before committed panel slots left it:
+    // disputed, with no `panel_slot` and no room for one
+    let original: ServiceRequest = env.fetch(&request);
+    let mut old = original.clone();
+    old.panel_slot = None;
+    let mut data = Vec::new();
+    old.try_serialize(&mut data).unwrap();
+    data.pop();
+    data.resize(current_size - 9, 0);
+    let mut account = env.svm.get_account(&request).unwrap();
+    account.data = data;
+    env.svm.set_account(request, account).unwrap();
+
+    env.send_as_admin(&[guardian::pause_protocol(admin)])
+        .unwrap();
+    env.send_as_admin(&[escrow::migrate_service_request(admin, request)])
+        .unwrap();
+
+    // Every field the old layout held survives, and the dispute gets a panel slot
+    let migrated: ServiceRequest = env.fetch(&request);
+    assert_eq!(migrated.request_id, original.request_id);
+    assert_eq!(migrated.user, original.user);
+    assert_eq!(migrated.amount, original.amount);
+    assert!(migrated.status == RequestStatus::Disputed);
+    assert_eq!(migrated.request_data, original.request_data);
+    assert_eq!(migrated.result_data, original.result_data);
+    assert_eq!(migrated.deadline, original.deadline);
+    assert!(migrated.panel_slot.is_some());
+    assert_eq!(
+        env.svm.get_account(&request).unwrap().data.len(),
+        current_size
+    );
+}
+
+#[test]
+fn requests_order_a_capability_at_its_own_price() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+
+    env.send(
+        &[registry::set_capability_price(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            "summarization".into(),
+            LAMPORTS_PER_SOL / 2,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let capability_price = pda::capability_price(&agent_id, "summarization").0;
+    let priced = env.fetch::<CapabilityPrice>(&capability_price);
+    assert_eq!(priced.price, LAMPORTS_PER_SOL / 2);
+    assert_eq!(priced.capability_id, "summarization");
+
+    let request = env.open_request(&user, agent_id, LAMPORTS_PER_SOL / 2);
+    env.send(
+        &[escrow::select_capability(
+            request,
+            capability_price,
+            user.pubkey(),
+        )],
+        &[&user],
+    )
+    .unwrap();
+    assert_eq!(
+        env.fetch::<ServiceRequest>(&request).capability_price,
+        Some(capability_price)
+    );
+
+    env.send(
+        &[registry::remove_capability_price(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            "summarization".into(),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(env.balance(&capability_price), 0);
+}
+
+#[test]
+fn health_checks_set_the_agent_uptime() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let checker = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    assert_eq!(env.fetch::<AgentProfile>(&agent_id).uptime_bps, None);
+
+    env.send_as_admin(&[registry::add_health_checker(
+        env.admin.pubkey(),
+        checker.pubkey(),
+    )])
+    .unwrap();
+    env.send(
+        &[registry::submit_health_check(
+            checker.pubkey(),
+            agent_id,
+            true,
+            80,
+        )],
+        &[&checker],
+    )
+    .unwrap();
+    assert_eq!(
+        env.fetch::<AgentProfile>(&agent_id).uptime_bps,
+        Some(10_000)
+    );
+
+    env.advance_clock(MIN_HEALTH_CHECK_INTERVAL);
+    env.send(
+        &[registry::submit_health_check(
+            checker.pubkey(),
+            agent_id,
+            false,
+            0,
+        )],
+        &[&checker],
+    )
+    .unwrap();
+    assert_eq!(env.fetch::<AgentProfile>(&agent_id).uptime_bps, Some(5_000));
+
+    // A full window of failures pushes the healthy check out of the log
+    for _ in 0..HEALTH_LOG_LEN {
+        env.advance_clock(MIN_HEALTH_CHECK_INTERVAL);
+        env.send(
+            &[registry::submit_health_check(
+                checker.pubkey(),
+                agent_id,
+                false,
+                0,
+            )],
+            &[&checker],
+        )
+        .unwrap();
+    }
+    let log = env.fetch::<HealthLog>(&pda::health_log(&agent_id).0);
+    assert_eq!(log.checks.len(), HEALTH_LOG_LEN);
+    assert_eq!(log.cursor as usize, 2);
+    assert!(log.checks.iter().all(|check| !check.healthy));
+    assert_eq!(env.fetch::<AgentProfile>(&agent_id).uptime_bps, Some(0));
+}
+
+#[test]
+fn agents_sell_with_their_profile_and_reputation() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let buyer = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    let reputation_score = env.fetch::<AgentProfile>(&agent_id).reputation_score;
+
+    env.send(
+        &[registry::list_agent_for_sale(
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            2 * LAMPORTS_PER_SOL,
+            None,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let agent_sale = pda::agent_sale(&agent_id).0;
+    let sale = env.fetch::<AgentSale>(&agent_sale);
+    assert_eq!(sale.seller, creator.pubkey());
+    assert_eq!(sale.price, 2 * LAMPORTS_PER_SOL);
+    let sale_token_account = get_associated_token_address(&agent_sale, &nft_mint);
+    let escrowed: anchor_spl::token::TokenAccount = env.fetch(&sale_token_account);
+    assert_eq!(escrowed.amount, 1);
+
+    let rent = env.balance(&agent_sale) + env.balance(&sale_token_account);
+    let seller_before = env.balance(&creator.pubkey());
+    env.send(
+        &[registry::buy_agent(
+            buyer.pubkey(),
+            creator.pubkey(),
+            agent_id,
+            nft_mint,
+            2 * LAMPORTS_PER_SOL,
+        )],
+        &[&buyer],
+    )
+    .unwrap();
+    assert_eq!(
+        env.balance(&creator.pubkey()),
+        seller_before + 2 * LAMPORTS_PER_SOL + rent
+    );
+    assert_eq!(env.balance(&agent_sale), 0);
+    assert_eq!(env.balance(&sale_token_account), 0);
+    let held: anchor_spl::token::TokenAccount =
+        env.fetch(&get_associated_token_address(&buyer.pubkey(), &nft_mint));
+    assert_eq!(held.amount, 1);
+
+    let profile: AgentProfile = env.fetch(&agent_id);
+    assert_eq!(profile.creator, buyer.pubkey());
+    assert_eq!(profile.payout_wallet, buyer.pubkey());
+    assert_eq!(profile.reputation_score, reputation_score);
+}
+
+#[test]
+fn agents_list_capabilities_past_the_profile_limit() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let profile: AgentProfile = env.fetch(&agent_id);
+
+    let ids: Vec<String> = (0..12).map(|i| format!("skill-{i:02}")).collect();
+    for id in &ids {
+        env.send_as_admin(&[registry::add_capability(
+            env.admin.pubkey(),
+            id.clone(),
+            id.clone(),
+            String::new(),
+        )])
+        .unwrap();
+    }
+    for batch in ids.chunks(4) {
+        env.send(
+            &[registry::add_agent_capabilities(
+                creator.pubkey(),
+                &profile,
+                batch.to_vec(),
+            )],
+            &[&creator],
+        )
+        .unwrap();
+    }
+    let agent_capabilities = pda::agent_capabilities(&agent_id).0;
+    let listed = env.fetch::<AgentCapabilities>(&agent_capabilities);
+    assert_eq!(listed.agent_id, agent_id);
+    assert_eq!(listed.capabilities, ids);
+    assert_eq!(
+        env.fetch::<CapabilityIndex>(&pda::capability_index("skill-11", &agent_id).0)
+            .agent_id,
+        agent_id
+    );
+    let size = env.svm.get_account(&agent_capabilities).unwrap().data.len();
+
+    // Removing ids shrinks the account and closes their indexes
+    env.send(
+        &[registry::remove_agent_capabilities(
+            creator.pubkey(),
+            &profile,
+            vec!["skill-00".into(), "skill-05".into()],
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    let listed = env.fetch::<AgentCapabilities>(&agent_capabilities);
+    assert_eq!(listed.capabilities.len(), 10);
+    assert!(!listed.capabilities.contains(&"skill-05".to_string()));
+    assert_eq!(
+        env.svm.get_account(&agent_capabilities).unwrap().data.len(),
+        size - 2 * (4 + "skill-00".len())
+    );
+    assert_eq!(
+        env.balance(&pda::capability_index("skill-05", &agent_id).0),
+        0
+    );
+}
+
+#[test]
+fn anyone_tops_up_an_agent_accounts_rent() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let funder = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+
+    // Leave the profile short of its rent-exempt minimum
+    let mut account = env.svm.get_account(&agent_id).unwrap();
+    let minimum = env
+        .svm
+        .minimum_balance_for_rent_exemption(account.data.len());
+    account.lamports = minimum - 10_000;
+    env.svm.set_account(agent_id, account).unwrap();
+
+    let before = env.balance(&funder.pubkey());
+    env.send(
+        &[registry::top_up_rent(
+            funder.pubkey(),
+            agent_id,
+            RentTarget::Profile,
+        )],
+        &[&funder],
+    )
+    .unwrap();
+    assert_eq!(env.balance(&agent_id), minimum);
+    assert!(before - env.balance(&funder.pubkey()) >= 10_000);
+}
+
+#[test]
+fn agent_names_are_claimed_until_deregistered() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let next_creator = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let profile: AgentProfile = env.fetch(&agent_id);
+    let claim = pda::name_claim(&profile.name).0;
+    assert_eq!(env.fetch::<NameClaim>(&claim).agent_id, agent_id);
+
+    // Renaming moves the claim to the new name
+    env.send(
+        &[registry::rename_agent(
+            creator.pubkey(),
+            &profile,
+            "Research Desk".into(),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(env.balance(&claim), 0);
+    let claim = pda::name_claim("Research Desk").0;
+    assert_eq!(env.fetch::<NameClaim>(&claim).agent_id, agent_id);
+
+    // Deregistering releases it for anyone else
+    let profile: AgentProfile = env.fetch(&agent_id);
+    env.send(
+        &[registry::deregister_agent(
+            creator.pubkey(),
+            agent_id,
+            profile.nft_mint,
+            &profile.name,
+            &profile.capabilities,
+            &profile.languages,
+            None,
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    assert_eq!(env.balance(&claim), 0);
+
+    let mint = Keypair::new();
+    let config: RegistryConfig = env.fetch(&pda::registry_config().0);
+    env.send(
+        &[registry::register_agent(
+            next_creator.pubkey(),
+            mint.pubkey(),
+            &config,
+            registry::RegisterAgentArgs {
+                name: "research-desk".into(),
+                ..agent_args()
+            },
+        )],
+        &[&next_creator, &mint],
+    )
+    .unwrap();
+    assert_eq!(
+        env.fetch::<NameClaim>(&claim).agent_id,
+        pda::agent_profile(&next_creator.pubkey()).0
+    );
+}
+
+#[test]
+fn requests_paid_in_tokens_split_the_escrowed_tokens() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let usdc = env.create_accepted_mint(6);
+    let amount = 10_000_000;
+    let user_tokens = env.token_account(&user.pubkey(), &usdc, amount);
+    let creator_tokens = env.token_account(&creator.pubkey(), &usdc, 0);
+    let platform_tokens = env.token_account(&env.platform_wallet.clone(), &usdc, 0);
+    let treasury_tokens = env.token_account(&env.treasury_wallet.clone(), &usdc, 0);
+
+    // Create: the tokens move into the escrow PDA's token account
+    env.send(
+        &[escrow::create_token_service_request(
+            user.pubkey(),
+            agent_id,
+            usdc,
+            amount,
+            "Summarize the attached paper".into(),
+        )],
+        &[&user],
+    )
+    .unwrap();
+    let request = pda::service_request(&user.pubkey(), &agent_id).0;
+    let escrow_tokens = get_associated_token_address(&pda::escrow_account(&request).0, &usdc);
+    assert_eq!(env.token_balance(&user_tokens), 0);
+    assert_eq!(env.token_balance(&escrow_tokens), amount);
+    assert_eq!(
+        env.fetch::<ServiceRequest>(&request).payment_mint,
+        Some(usdc)
+    );
+
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    env.send(
+        &[escrow::submit_result(
+            request,
+            agent_id,
+            nft_mint,
+            creator.pubkey(),
+            "A three-paragraph summary".into(),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+
+    // Approve: the tokens split 85/10/5 and the emptied escrow token account closes
+    env.send(
+        &[escrow::approve_token_result(
+            request,
+            agent_id,
+            user.pubkey(),
+            usdc,
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+        )],
+        &[&user],
+    )
+    .unwrap();
+    assert!(env.fetch::<ServiceRequest>(&request).status == RequestStatus::Approved);
+    assert_eq!(env.token_balance(&creator_tokens), amount * 85 / 100);
+    assert_eq!(env.token_balance(&platform_tokens), amount * 10 / 100);
+    assert_eq!(
+        env.token_balance(&treasury_tokens),
+        amount - amount * 85 / 100 - amount * 10 / 100
+    );
+    assert_eq!(env.balance(&escrow_tokens), 0);
+}
+
+#[test]
+fn closing_a_token_request_sweeps_stray_escrow_tokens_to_the_user() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let agent_id = env.register_agent(&creator);
+    let usdc = env.create_accepted_mint(6);
+    let amount = 10_000_000;
+    let user_tokens = env.token_account(&user.pubkey(), &usdc, amount);
+    let create = escrow::create_token_service_request(
+        user.pubkey(),
+        agent_id,
+        usdc,
+        amount,
+        "Summarize the attached paper".into(),
+    );
+    env.send(&[create.clone()], &[&user]).unwrap();
+    let request = pda::service_request(&user.pubkey(), &agent_id).0;
+    env.send(
+        &[escrow::cancel_token_request(
+            request,
+            agent_id,
+            user.pubkey(),
+            usdc,
+        )],
+        &[&user],
+    )
+    .unwrap();
+
+    // Anyone can recreate the escrow's token account and send tokens to it after settlement
+    let stray = 500;
+    let escrow_tokens = env.token_account(&pda::escrow_account(&request).0, &usdc, stray);
+
+    // Closing hands them to the user and closes the account
+    env.send(
+        &[escrow::close_token_request(request, user.pubkey(), usdc)],
+        &[&user],
+    )
+    .unwrap();
+    assert_eq!(env.token_balance(&user_tokens), amount + stray);
+    assert_eq!(env.balance(&escrow_tokens), 0);
+
+    // So the next request at the same address escrows only its own deposit
+    env.send(&[create], &[&user]).unwrap();
+    assert_eq!(env.token_balance(&escrow_tokens), amount);
+}
+
+#[test]
+fn requests_past_the_agents_sla_timeout_refund_the_user() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let cranker = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+
+    // The SLA oracle's recommended timeout sets the deadline
+    env.agent_sla(agent_id, 600);
+    let request = env.open_request(&user, agent_id, LAMPORTS_PER_SOL);
+    let state: ServiceRequest = env.fetch(&request);
+    assert_eq!(state.deadline, Some(state.created_at + 600));
+
+    // Once it passes without a result, anyone refunds the user
+    env.advance_clock(601);
+    let before = env.balance(&user.pubkey());
+    env.send(
+        &[escrow::expire_request(
+            cranker.pubkey(),
+            request,
+            agent_id,
+            user.pubkey(),
+        )],
+        &[&cranker],
+    )
+    .unwrap();
+    assert!(env.fetch::<ServiceRequest>(&request).status == RequestStatus::Cancelled);
+    assert_eq!(env.balance(&user.pubkey()) - before, LAMPORTS_PER_SOL);
+    assert_eq!(env.balance(&pda::escrow_account(&request).0), 0);
+    assert_eq!(
+        env.fetch::<AgentRequestCount>(&pda::agent_requests(&agent_id).0)
+            .open_requests,
+        0
+    );
+}
+
+#[test]
+fn unreviewed_results_are_released_after_the_review_window() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let cranker = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let amount = LAMPORTS_PER_SOL;
+    let request = env.completed_request(&user, &creator, agent_id, amount);
+
+    // The user never reviews the result, so anyone pays the agent once the window closes
+    env.advance_clock(REVIEW_WINDOW);
+    let creator_before = env.balance(&creator.pubkey());
+    let platform_before = env.balance(&env.platform_wallet);
+    let treasury_before = env.balance(&env.treasury_wallet);
+    env.send(
+        &[escrow::auto_release(
+            cranker.pubkey(),
+            request,
+            agent_id,
+            user.pubkey(),
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+        )],
+        &[&cranker],
+    )
+    .unwrap();
+
+    assert!(env.fetch::<ServiceRequest>(&request).status == RequestStatus::Approved);
+    assert_eq!(env.balance(&pda::escrow_account(&request).0), 0);
+    assert_eq!(
+        env.balance(&creator.pubkey()) - creator_before,
+        amount * 85 / 100
+    );
+    assert_eq!(
+        env.balance(&env.platform_wallet) - platform_before,
+        amount * 10 / 100
+    );
+    assert_eq!(
+        env.balance(&env.treasury_wallet) - treasury_before,
+        amount - amount * 85 / 100 - amount * 10 / 100
+    );
+    assert_eq!(
+        env.fetch::<AgentProfile>(&agent_id).total_earnings,
+        amount * 85 / 100
+    );
+}
+
+#[test]
+fn the_arbiter_splits_a_disputed_payment_between_agent_and_user() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let arbiter = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let amount = LAMPORTS_PER_SOL;
+    let request = env.completed_request(&user, &creator, agent_id, amount);
+    env.send(
+        &[escrow::dispute_result(
+            request,
+            user.pubkey(),
+            "Only half the pages were translated".into(),
+        )],
+        &[&user],
+    )
+    .unwrap();
+
+    let admin = env.admin.pubkey();
+    env.send_as_admin(&[escrow::set_arbiter(admin, arbiter.pubkey())])
+        .unwrap();
+    let user_before = env.balance(&user.pubkey());
+    let creator_before = env.balance(&creator.pubkey());
+    let platform_before = env.balance(&env.platform_wallet);
+    env.send(
+        &[escrow::resolve_dispute(
+            arbiter.pubkey(),
+            request,
+            agent_id,
+            user.pubkey(),
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+            escrow::DisputeOutcome::Split { agent_bps: 6_000 },
+        )],
+        &[&arbiter],
+    )
+    .unwrap();
+
+    // The agent's share is split as on approval and the rest goes back to the user
+    let agent_amount = amount * 6 / 10;
+    assert!(env.fetch::<ServiceRequest>(&request).status == RequestStatus::Resolved);
+    assert_eq!(env.balance(&pda::escrow_account(&request).0), 0);
+    assert_eq!(
+        env.balance(&user.pubkey()) - user_before,
+        amount - agent_amount
+    );
+    assert_eq!(
+        env.balance(&creator.pubkey()) - creator_before,
+        agent_amount * 85 / 100
+    );
+    assert_eq!(
+        env.balance(&env.platform_wallet) - platform_before,
+        agent_amount * 10 / 100
+    );
+    let counter: AgentRequestCount = env.fetch(&pda::agent_requests(&agent_id).0);
+    assert_eq!(counter.open_requests, 0);
+    let config: accounts::ArbitrationConfig = env.fetch(&pda::arbitration_config().0);
+    assert_eq!(config.arbiter, arbiter.pubkey());
+    assert_eq!(config.disputes_resolved, 1);
+}
+
+#[test]
+fn a_juror_panel_settles_a_dispute_by_majority_vote() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let cranker = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let amount = LAMPORTS_PER_SOL;
+    let request = env.disputed_request(&user, &creator, agent_id, amount);
+    let jurors = env.jurors(4);
+    let panel = env.draw_panel(&cranker, request, agent_id, &jurors);
+    assert_eq!(panel.len(), PANEL_SIZE);
+
+    // Two jurors side with the user and the third never votes
+    for juror in &panel[..2] {
+        env.send(
+            &[escrow::cast_vote(
+                juror.pubkey(),
+                request,
+                escrow::Verdict::User,
+            )],
+            &[juror],
+        )
+        .unwrap();
+    }
+    env.advance_clock(VOTING_WINDOW);
+    let user_before = env.balance(&user.pubkey());
+    env.send(
+        &[escrow::settle_panel(
+            cranker.pubkey(),
+            request,
+            agent_id,
+            user.pubkey(),
+            cranker.pubkey(),
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+        )],
+        &[&cranker],
+    )
+    .unwrap();
+
+    // The user is refunded all but the jurors' fee
+    let juror_fee = amount * JUROR_FEE_BPS / 10_000;
+    assert!(env.fetch::<ServiceRequest>(&request).status == RequestStatus::Resolved);
+    assert_eq!(
+        env.balance(&user.pubkey()) - user_before,
+        amount - juror_fee
+    );
+    assert_eq!(env.balance(&pda::escrow_account(&request).0), 0);
+    assert_eq!(env.balance(&pda::dispute_panel(&request).0), 0);
+
+    // The majority shares the fee and the non-voter's forfeit
+    let reward = (juror_fee + NON_VOTER_PENALTY) / 2;
+    let pool: JurorPool = env.fetch(&pda::juror_pool().0);
+    let stake = |juror: &Keypair| {
+        pool.jurors
+            .iter()
+            .find(|seat| seat.juror == juror.pubkey())
+            .unwrap()
+            .stake
+    };
+    assert_eq!(stake(&panel[0]), JUROR_STAKE + reward);
+    assert_eq!(stake(&panel[1]), JUROR_STAKE + reward);
+    assert_eq!(stake(&panel[2]), JUROR_STAKE - NON_VOTER_PENALTY);
+    assert!(pool.jurors.iter().all(|seat| seat.active_panels == 0));
+
+    // Free of the panel, jurors can leave with their stake and rewards
+    let before = env.balance(&panel[0].pubkey());
+    env.send(
+        &[escrow::leave_jury(panel[0].pubkey())],
+        &[&cranker, &panel[0]],
+    )
+    .unwrap();
+    assert_eq!(
+        env.balance(&panel[0].pubkey()) - before,
+        JUROR_STAKE + reward
+    );
+}
+
+#[test]
+fn partial_refunds_of_token_payments_take_fees_on_the_released_portion_only() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let arbiter = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let usdc = env.create_accepted_mint(6);
+    let amount = 10_000_000;
+    let user_tokens = env.token_account(&user.pubkey(), &usdc, amount);
+    let creator_tokens = env.token_account(&creator.pubkey(), &usdc, 0);
+    let platform_tokens = env.token_account(&env.platform_wallet.clone(), &usdc, 0);
+    let treasury_tokens = env.token_account(&env.treasury_wallet.clone(), &usdc, 0);
+    env.send(
+        &[escrow::create_token_service_request(
+            user.pubkey(),
+            agent_id,
+            usdc,
+            amount,
+            "Summarize the attached paper".into(),
+        )],
+        &[&user],
+    )
+    .unwrap();
+    let request = pda::service_request(&user.pubkey(), &agent_id).0;
+    let nft_mint = env.fetch::<AgentProfile>(&agent_id).nft_mint;
+    env.send(
+        &[escrow::submit_result(
+            request,
+            agent_id,
+            nft_mint,
+            creator.pubkey(),
+            "A three-paragraph summary".into(),
+        )],
+        &[&creator],
+    )
+    .unwrap();
+    env.send(
+        &[escrow::dispute_result(
+            request,
+            user.pubkey(),
+            "Summary misses the conclusions".into(),
+        )],
+        &[&user],
+    )
+    .unwrap();
+
+    // Refund 60% and release 40%, which alone is split 85/10/5
+    let admin = env.admin.pubkey();
+    env.send_as_admin(&[escrow::set_arbiter(admin, arbiter.pubkey())])
+        .unwrap();
+    env.send(
+        &[escrow::resolve_token_dispute(
+            arbiter.pubkey(),
+            request,
+            agent_id,
+            user.pubkey(),
+            usdc,
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+            escrow::DisputeOutcome::Split { agent_bps: 4_000 },
+        )],
+        &[&arbiter],
+    )
+    .unwrap();
+
+    let released = amount * 40 / 100;
+    assert!(env.fetch::<ServiceRequest>(&request).status == RequestStatus::Resolved);
+    assert_eq!(env.token_balance(&user_tokens), amount - released);
+    assert_eq!(env.token_balance(&creator_tokens), released * 85 / 100);
+    assert_eq!(env.token_balance(&platform_tokens), released * 10 / 100);
+    assert_eq!(
+        env.token_balance(&treasury_tokens),
+        released - released * 85 / 100 - released * 10 / 100
+    );
+    let escrow_tokens = get_associated_token_address(&pda::escrow_account(&request).0, &usdc);
+    assert_eq!(env.balance(&escrow_tokens), 0);
+}
+
+#[test]
+fn settled_requests_close_and_return_their_rent_to_the_user() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(10);
+    let user = env.funded_keypair(10);
+    let payer = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+    let request = env.completed_request(&user, &creator, agent_id, LAMPORTS_PER_SOL);
+    env.send(
+        &[escrow::approve_result(
+            request,
+            agent_id,
+            user.pubkey(),
+            creator.pubkey(),
+            env.platform_wallet,
+            env.treasury_wallet,
+        )],
+        &[&user],
+    )
+    .unwrap();
+
+    // Closing pays back the request account's rent
+    let rent = env.balance(&request);
+    let before = env.balance(&user.pubkey());
+    env.send(
+        &[escrow::close_request(request, user.pubkey())],
+        &[&payer, &user],
+    )
+    .unwrap();
+    assert_eq!(env.balance(&request), 0);
+    assert_eq!(env.balance(&user.pubkey()), before + rent);
+
+    // Which frees the address for the user's next request of the agent
+    assert_eq!(env.open_request(&user, agent_id, LAMPORTS_PER_SOL), request);
+    assert!(env.fetch::<ServiceRequest>(&request).status == RequestStatus::Pending);
+}
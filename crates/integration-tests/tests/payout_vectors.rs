@@ -0,0 +1,160 @@
+//! Replays the shared payout vectors through the programs so `agentmarket-payouts` cannot drift from them.
+
+mod common;
+
+use agentmarket_payouts::{Shares as PayoutShares, Split};
+use agentmarket_sdk::accounts::DistributionRecord;
+use agentmarket_sdk::instructions::escrow;
+use agentmarket_sdk::instructions::royalty::{self, Shares};
+use agentmarket_sdk::pda;
+use anchor_lang::solana_program::native_token::LAMPORTS_PER_SOL;
+use serde_json::Value;
+use solana_signer::Signer;
+
+use common::TestEnv;
+
+fn vectors(kind: &str) -> Vec<Value> {
+    let all: Value =
+        serde_json::from_str(include_str!("../../agentmarket-payouts/test-vectors.json")).unwrap();
+    all[kind].as_array().unwrap().clone()
+}
+
+fn u64_at(vector: &Value, key: &str) -> u64 {
+    vector[key].as_u64().unwrap()
+}
+
+#[test]
+fn escrow_release_matches_library() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(1);
+    let agent_id = env.register_agent(&creator);
+
+    for vector in vectors("escrow_release") {
+        let amount = u64_at(&vector, "amount");
+        let user = env.funded_keypair(amount / LAMPORTS_PER_SOL + 1);
+        let request = env.completed_request(&user, &creator, agent_id, amount);
+
+        let creator_before = env.balance(&creator.pubkey());
+        let platform_before = env.balance(&env.platform_wallet);
+        let treasury_before = env.balance(&env.treasury_wallet);
+        env.send(
+            &[escrow::approve_result(
+                request,
+                agent_id,
+                user.pubkey(),
+                creator.pubkey(),
+                env.platform_wallet,
+                env.treasury_wallet,
+            )],
+            &[&user],
+        )
+        .unwrap();
+
+        let paid = Split {
+            creator: env.balance(&creator.pubkey()) - creator_before,
+            platform: env.balance(&env.platform_wallet) - platform_before,
+            treasury: env.balance(&env.treasury_wallet) - treasury_before,
+        };
+        assert_eq!(
+            paid,
+            agentmarket_payouts::escrow_release(amount),
+            "{vector}"
+        );
+        assert_eq!(paid.creator, u64_at(&vector, "creator"), "{vector}");
+    }
+}
+
+#[test]
+fn royalty_distribution_matches_library() {
+    let mut env = TestEnv::new();
+    let creator = env.funded_keypair(1);
+    let payer = env.funded_keypair(10);
+
+    for (index, vector) in vectors("royalty_distribution").into_iter().enumerate() {
+        let shares = vector["shares"].as_array().unwrap();
+        let shares = Shares {
+            creator: shares[0].as_u64().unwrap() as u8,
+            platform: shares[1].as_u64().unwrap() as u8,
+            treasury: shares[2].as_u64().unwrap() as u8,
+        };
+        env.send_as_admin(&[royalty::update_config(
+            env.admin.pubkey(),
+            Some(shares),
+            None,
+            None,
+        )])
+        .unwrap();
+
+        let amount = u64_at(&vector, "amount");
+        env.send(
+            &[royalty::distribute_payment(
+                payer.pubkey(),
+                payer.pubkey(),
+                creator.pubkey(),
+                env.platform_wallet,
+                env.treasury_wallet,
+                index as u64,
+                amount,
+            )],
+            &[&payer],
+        )
+        .unwrap();
+
+        let record: DistributionRecord = env.fetch(&pda::distribution_record(index as u64).0);
+        let expected = agentmarket_payouts::royalty_distribution(
+            amount,
+            PayoutShares {
+                creator: shares.creator,
+                platform: shares.platform,
+                treasury: shares.treasury,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            Split {
+                creator: record.creator_amount,
+                platform: record.platform_amount,
+                treasury: record.treasury_amount,
+            },
+            expected,
+            "{vector}"
+        );
+    }
+}
+
+#[test]
+fn insurance_premium_matches_library() {
+    for vector in vectors("insurance_premium") {
+        let payout_amount = u64_at(&vector, "payout_amount");
+        let premium_bps = u64_at(&vector, "premium_bps") as u16;
+        let on_chain = insurance_fund::calculate_premium(payout_amount, premium_bps);
+        assert_eq!(on_chain, u64_at(&vector, "premium"), "{vector}");
+
+        // The library reports the zero premium deposit_premium refuses as an error
+        let simulated = agentmarket_payouts::insurance_premium(payout_amount, premium_bps);
+        assert_eq!(simulated.unwrap_or(0), on_chain, "{vector}");
+    }
+}
+
+#[test]
+fn leaderboard_prize_matches_library() {
+    for vector in vectors("leaderboard_prize") {
+        let prize_pool = u64_at(&vector, "prize_pool");
+        let weights: Vec<u16> = vector["prize_weights_bps"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|weight| weight.as_u64().unwrap() as u16)
+            .collect();
+        let filled = u64_at(&vector, "filled") as usize;
+        let rank = u64_at(&vector, "rank") as usize;
+
+        let on_chain = leaderboard::calculate_prize(prize_pool, &weights, filled, rank);
+        assert_eq!(on_chain, u64_at(&vector, "prize"), "{vector}");
+        assert_eq!(
+            agentmarket_payouts::leaderboard_prize(prize_pool, &weights, filled, rank),
+            Ok(on_chain),
+            "{vector}"
+        );
+    }
+}
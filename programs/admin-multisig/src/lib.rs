@@ -0,0 +1,439 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+
+declare_id!("2RmUbdt9AnnTp73VCtXZctUT7taj65okdGoXBz5efJVc");
+
+/// Maximum number of multisig owners
+pub const MAX_OWNERS: usize = 10;
+/// Maximum number of accounts a proposed instruction may reference
+pub const MAX_TRANSACTION_ACCOUNTS: usize = 24;
+/// Maximum size of a proposed instruction's data
+pub const MAX_TRANSACTION_DATA: usize = 1024;
+
+#[program]
+pub mod admin_multisig {
+    use super::*;
+
+    /// Create a multisig whose signer PDA can hold admin roles across programs
+    pub fn create_multisig(
+        ctx: Context<CreateMultisig>,
+        create_key: Pubkey,
+        owners: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        validate_owners(&owners, threshold)?;
+
+        let multisig_key = ctx.accounts.multisig.key();
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.create_key = create_key;
+        multisig.owners = owners;
+        multisig.threshold = threshold;
+        multisig.owner_set_seqno = 0;
+        multisig.transaction_count = 0;
+
+        let (signer, _) = Pubkey::find_program_address(
+            &[b"multisig_signer", multisig_key.as_ref()],
+            ctx.program_id,
+        );
+
+        emit!(MultisigCreated {
+            multisig: multisig_key,
+            signer,
+            owners: multisig.owners.clone(),
+            threshold,
+        });
+
+        Ok(())
+    }
+
+    /// Propose an instruction to be executed by the multisig signer (owners only)
+    pub fn propose_transaction(
+        ctx: Context<ProposeTransaction>,
+        program_id: Pubkey,
+        accounts: Vec<TransactionAccount>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        require!(
+            accounts.len() <= MAX_TRANSACTION_ACCOUNTS,
+            MultisigError::TooManyAccounts
+        );
+        require!(data.len() <= MAX_TRANSACTION_DATA, MultisigError::DataTooLong);
+
+        let multisig = &mut ctx.accounts.multisig;
+        let proposer_index = multisig
+            .owner_index(&ctx.accounts.proposer.key())
+            .ok_or(MultisigError::InvalidOwner)?;
+
+        let transaction_key = ctx.accounts.transaction.key();
+        let transaction = &mut ctx.accounts.transaction;
+        let mut approvals = vec![false; multisig.owners.len()];
+        approvals[proposer_index] = true;
+
+        transaction.multisig = multisig.key();
+        transaction.index = multisig.transaction_count;
+        transaction.proposer = ctx.accounts.proposer.key();
+        transaction.program_id = program_id;
+        transaction.accounts = accounts;
+        transaction.data = data;
+        transaction.approvals = approvals;
+        transaction.owner_set_seqno = multisig.owner_set_seqno;
+        transaction.did_execute = false;
+        transaction.created_at = Clock::get()?.unix_timestamp;
+
+        multisig.transaction_count += 1;
+
+        emit!(TransactionProposed {
+            multisig: multisig.key(),
+            transaction: transaction_key,
+            index: transaction.index,
+            proposer: transaction.proposer,
+            program_id,
+        });
+
+        Ok(())
+    }
+
+    /// Approve a pending transaction (owners only)
+    pub fn approve(
+        ctx: Context<Approve>,
+    ) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        let transaction = &mut ctx.accounts.transaction;
+
+        require!(!transaction.did_execute, MultisigError::AlreadyExecuted);
+        require!(
+            transaction.owner_set_seqno == multisig.owner_set_seqno,
+            MultisigError::StaleOwnerSet
+        );
+
+        let owner_index = multisig
+            .owner_index(&ctx.accounts.owner.key())
+            .ok_or(MultisigError::InvalidOwner)?;
+        transaction.approvals[owner_index] = true;
+
+        emit!(TransactionApproved {
+            transaction: transaction.key(),
+            owner: ctx.accounts.owner.key(),
+            approvals: transaction.approval_count() as u8,
+        });
+
+        Ok(())
+    }
+
+    /// Execute a transaction once it has reached the approval threshold
+    ///
+    /// `remaining_accounts` must contain every account referenced by the
+    /// transaction followed by the target program.
+    pub fn execute_transaction<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteTransaction<'info>>,
+    ) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        let transaction = &mut ctx.accounts.transaction;
+
+        require!(!transaction.did_execute, MultisigError::AlreadyExecuted);
+        require!(
+            transaction.owner_set_seqno == multisig.owner_set_seqno,
+            MultisigError::StaleOwnerSet
+        );
+        require!(
+            transaction.approval_count() >= multisig.threshold as usize,
+            MultisigError::NotEnoughApprovals
+        );
+
+        let multisig_key = multisig.key();
+        let signer_key = ctx.accounts.multisig_signer.key();
+        let instruction = Instruction {
+            program_id: transaction.program_id,
+            accounts: transaction
+                .accounts
+                .iter()
+                .map(|account| AccountMeta {
+                    pubkey: account.pubkey,
+                    is_signer: account.is_signer || account.pubkey == signer_key,
+                    is_writable: account.is_writable,
+                })
+                .collect(),
+            data: transaction.data.clone(),
+        };
+
+        transaction.did_execute = true;
+
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"multisig_signer",
+            multisig_key.as_ref(),
+            &[ctx.bumps.multisig_signer],
+        ]];
+        anchor_lang::solana_program::program::invoke_signed(
+            &instruction,
+            ctx.remaining_accounts,
+            signer_seeds,
+        )?;
+
+        emit!(TransactionExecuted {
+            multisig: multisig_key,
+            transaction: transaction.key(),
+            index: transaction.index,
+            executor: ctx.accounts.executor.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Replace the owner set and threshold (only callable by the multisig itself)
+    pub fn set_owners_and_threshold(
+        ctx: Context<Auth>,
+        owners: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        validate_owners(&owners, threshold)?;
+
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.owners = owners;
+        multisig.threshold = threshold;
+        // Invalidate every transaction approved under the previous owner set
+        multisig.owner_set_seqno += 1;
+
+        emit!(OwnersChanged {
+            multisig: multisig.key(),
+            owners: multisig.owners.clone(),
+            threshold,
+            owner_set_seqno: multisig.owner_set_seqno,
+        });
+
+        Ok(())
+    }
+}
+
+// Helper function to validate an owner set and threshold
+fn validate_owners(owners: &[Pubkey], threshold: u8) -> Result<()> {
+    require!(
+        !owners.is_empty() && owners.len() <= MAX_OWNERS,
+        MultisigError::InvalidOwnerCount
+    );
+    require!(
+        threshold > 0 && threshold as usize <= owners.len(),
+        MultisigError::InvalidThreshold
+    );
+    for (i, owner) in owners.iter().enumerate() {
+        require!(!owners[..i].contains(owner), MultisigError::DuplicateOwner);
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(create_key: Pubkey)]
+pub struct CreateMultisig<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Multisig::INIT_SPACE,
+        seeds = [b"multisig", create_key.as_ref()],
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(program_id: Pubkey, accounts: Vec<TransactionAccount>, data: Vec<u8>)]
+pub struct ProposeTransaction<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig", multisig.create_key.as_ref()],
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Transaction::space(multisig.owners.len(), accounts.len(), data.len()),
+        seeds = [b"transaction", multisig.key().as_ref(), multisig.transaction_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub transaction: Account<'info, Transaction>,
+
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Approve<'info> {
+    #[account(
+        seeds = [b"multisig", multisig.create_key.as_ref()],
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        mut,
+        has_one = multisig,
+        seeds = [b"transaction", multisig.key().as_ref(), transaction.index.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub transaction: Account<'info, Transaction>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteTransaction<'info> {
+    #[account(
+        seeds = [b"multisig", multisig.create_key.as_ref()],
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    /// CHECK: PDA that signs on behalf of the multisig
+    #[account(
+        seeds = [b"multisig_signer", multisig.key().as_ref()],
+        bump
+    )]
+    pub multisig_signer: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = multisig,
+        seeds = [b"transaction", multisig.key().as_ref(), transaction.index.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub transaction: Account<'info, Transaction>,
+
+    pub executor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Auth<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig", multisig.create_key.as_ref()],
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+
+    #[account(
+        seeds = [b"multisig_signer", multisig.key().as_ref()],
+        bump
+    )]
+    pub multisig_signer: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Multisig {
+    pub create_key: Pubkey,
+    #[max_len(MAX_OWNERS)]
+    pub owners: Vec<Pubkey>,
+    pub threshold: u8,
+    pub owner_set_seqno: u32,
+    pub transaction_count: u64,
+}
+
+impl Multisig {
+    pub fn owner_index(&self, key: &Pubkey) -> Option<usize> {
+        self.owners.iter().position(|owner| owner == key)
+    }
+}
+
+#[account]
+pub struct Transaction {
+    pub multisig: Pubkey,
+    pub index: u64,
+    pub proposer: Pubkey,
+    pub program_id: Pubkey,
+    pub accounts: Vec<TransactionAccount>,
+    pub data: Vec<u8>,
+    pub approvals: Vec<bool>,
+    pub owner_set_seqno: u32,
+    pub did_execute: bool,
+    pub created_at: i64,
+}
+
+impl Transaction {
+    pub fn space(owners: usize, accounts: usize, data: usize) -> usize {
+        32 + 8 + 32 + 32
+            + 4 + accounts * TransactionAccount::INIT_SPACE
+            + 4 + data
+            + 4 + owners
+            + 4 + 1 + 8
+    }
+
+    pub fn approval_count(&self) -> usize {
+        self.approvals.iter().filter(|approved| **approved).count()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct TransactionAccount {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+#[event]
+pub struct MultisigCreated {
+    pub multisig: Pubkey,
+    pub signer: Pubkey,
+    pub owners: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+#[event]
+pub struct TransactionProposed {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub index: u64,
+    pub proposer: Pubkey,
+    pub program_id: Pubkey,
+}
+
+#[event]
+pub struct TransactionApproved {
+    pub transaction: Pubkey,
+    pub owner: Pubkey,
+    pub approvals: u8,
+}
+
+#[event]
+pub struct TransactionExecuted {
+    pub multisig: Pubkey,
+    pub transaction: Pubkey,
+    pub index: u64,
+    pub executor: Pubkey,
+}
+
+#[event]
+pub struct OwnersChanged {
+    pub multisig: Pubkey,
+    pub owners: Vec<Pubkey>,
+    pub threshold: u8,
+    pub owner_set_seqno: u32,
+}
+
+#[error_code]
+pub enum MultisigError {
+    #[msg("Multisig must have between 1 and 10 owners")]
+    InvalidOwnerCount,
+    #[msg("Threshold must be between 1 and the number of owners")]
+    InvalidThreshold,
+    #[msg("Owners must be unique")]
+    DuplicateOwner,
+    #[msg("Signer is not a multisig owner")]
+    InvalidOwner,
+    #[msg("Too many accounts in proposed transaction (max 24)")]
+    TooManyAccounts,
+    #[msg("Proposed transaction data is too long (max 1024 bytes)")]
+    DataTooLong,
+    #[msg("Transaction has already been executed")]
+    AlreadyExecuted,
+    #[msg("Owner set changed since this transaction was proposed")]
+    StaleOwnerSet,
+    #[msg("Not enough owners have approved this transaction")]
+    NotEnoughApprovals,
+}
@@ -1,31 +1,207 @@
 use anchor_lang::prelude::*;
+use anchor_spl::associated_token::get_associated_token_address;
 use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use marketplace_escrow::program::MarketplaceEscrow;
+use marketplace_escrow::{AgentRequestCount, RequestStatus, ServiceRequest};
 use mpl_token_metadata::instructions::{
+    CreateMasterEditionV3Cpi, CreateMasterEditionV3CpiAccounts, CreateMasterEditionV3InstructionArgs,
     CreateMetadataAccountV3Cpi, CreateMetadataAccountV3CpiAccounts, CreateMetadataAccountV3InstructionArgs,
+    CreateV1Cpi, CreateV1CpiAccounts, CreateV1InstructionArgs, MintV1Cpi, MintV1CpiAccounts,
+    MintV1InstructionArgs, SetAndVerifyCollectionCpi, SetAndVerifyCollectionCpiAccounts,
+    UpdateV1Cpi, UpdateV1CpiAccounts, UpdateV1InstructionArgs, VerifyCollectionV1Cpi,
+    VerifyCollectionV1CpiAccounts,
 };
-use mpl_token_metadata::types::{Creator, DataV2};
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program_option::COption;
+use mpl_token_metadata::accounts::Metadata;
+use mpl_token_metadata::types::{
+    Collection, CollectionDetailsToggle, CollectionToggle, Creator, Data, DataV2, PrintSupply,
+    RuleSetToggle, TokenStandard, UsesToggle,
+};
+use program_utils::ed25519::verify_signature;
+use program_utils::migration::{grow_account, read_upgraded, write_upgraded};
+use protocol_guardian::GuardianConfig;
+use reputation_system::program::ReputationSystem;
+use royalty_splitter::program::RoyaltySplitter;
+use solana_sha256_hasher::hashv;
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
+/// Metaplex Bubblegum program that mints compressed agent NFTs
+pub const BUBBLEGUM_PROGRAM_ID: Pubkey = pubkey!("BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY");
+/// SPL account compression program that owns the agent Merkle tree
+pub const ACCOUNT_COMPRESSION_PROGRAM_ID: Pubkey = pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+/// SPL noop program Bubblegum logs leaf changes through
+pub const NOOP_PROGRAM_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+/// Metaplex token auth rules program that evaluates pNFT rule sets
+pub const TOKEN_AUTH_RULES_PROGRAM_ID: Pubkey = pubkey!("auth9SigNpDKz4sJJ1DfCTuZrZNSAgh9sFD3rboVmgg");
+/// Metaplex Foundation rule set, which only lets pNFTs move through
+/// royalty-honouring programs; programmable agents are bound to it
+pub const ROYALTY_RULE_SET: Pubkey = pubkey!("eBJLFYPxJmMGKuFwpDWkzxZeUrad92kZRC5BJLpzyT9");
+
+/// Native ed25519 signature verification program
+pub use program_utils::ed25519::ED25519_PROGRAM_ID;
+/// Seconds an endpoint challenge can be answered after it is issued
+pub const ENDPOINT_CHALLENGE_TTL: i64 = 60 * 60;
+/// Prefix of every endpoint challenge message, so the signature can't be replayed elsewhere
+pub const ENDPOINT_CHALLENGE_DOMAIN: &[u8] = b"agentmarket-endpoint-challenge";
+/// Prefix of every signed agent manifest, so the signature can't be replayed elsewhere
+pub const MANIFEST_DOMAIN: &[u8] = b"agentmarket-agent-manifest";
+
+/// Endpoints one agent can list for failover
+pub const MAX_ENDPOINTS: usize = 4;
+/// Longest endpoint region tag, e.g. `us-east-1`
+pub const MAX_REGION_LEN: usize = 16;
+
+/// Longest capability id; ids are PDA seeds, so they stay well under 32 bytes
+pub const MAX_CAPABILITY_ID_LEN: usize = 20;
+/// Longest input or output schema reference, sized for a base32 CIDv1
+pub const MAX_SCHEMA_CID_LEN: usize = 64;
+
+/// Capabilities and description characters a new profile has room for
+pub const DEFAULT_CAPABILITY_LIMIT: usize = 10;
+pub const DEFAULT_DESCRIPTION_LIMIT: usize = 500;
+/// Most a profile can grow to through `resize_profile`, which keeps the
+/// reallocation under the runtime's 10 KiB per-instruction limit
+pub const MAX_CAPABILITY_LIMIT: u8 = 32;
+pub const MAX_DESCRIPTION_LIMIT: u16 = 2_000;
+/// Most capabilities an agent lists in its `AgentCapabilities` account, on
+/// top of those in its profile
+pub const MAX_AGENT_CAPABILITIES: usize = 128;
+
+/// Layout version of new agent profiles; `migrate_profile` upgrades older
+/// ones. Bump it whenever a new profile field needs more than its zero value
+/// to be valid, and teach `upgrade_profile` to fill it in
+pub const PROFILE_VERSION: u8 = 1;
+
+/// Languages one agent can list, each an ISO 639-1 code optionally followed
+/// by an ISO 3166 region, e.g. `en` or `pt-BR`
+pub const MAX_LANGUAGES: usize = 8;
+pub const MAX_LANGUAGE_CODE_LEN: usize = 5;
+
+/// Other agents one agent can declare it calls into
+pub const MAX_DEPENDENCIES: usize = 8;
+
+/// Co-creators one agent can split its creator share with
+pub const MAX_CO_CREATORS: usize = 5;
+
+/// Largest lineage royalty a fork can route to its parent's creator, in bps
+pub const MAX_LINEAGE_ROYALTY_BPS: u16 = 1_000;
+
+/// Fewest and most agents one bundle can combine
+pub const MIN_BUNDLE_AGENTS: usize = 2;
+pub const MAX_BUNDLE_AGENTS: usize = 5;
+
+/// Agents the featured index can hold at once
+pub const MAX_FEATURED_AGENTS: usize = 20;
+/// Longest single promotion, in days
+pub const MAX_PROMOTION_DAYS: u32 = 90;
+
+/// Health checks each agent's log keeps; older ones are overwritten
+pub const HEALTH_LOG_LEN: usize = 32;
+/// Shortest gap between two checks of one agent by the same checker
+pub const MIN_HEALTH_CHECK_INTERVAL: i64 = 5 * 60;
+
+/// Longest referral code
+pub const MAX_REFERRAL_CODE_LEN: usize = 32;
+/// Share of the registration fee a referred agent's referrer earns, in bps
+pub const REFERRAL_FEE_BPS: u64 = 2_000;
+
+/// Longest stretch of access one purchase buys, in months
+pub const MAX_ACCESS_MONTHS: u32 = 12;
+/// Length of a month of access
+pub const ACCESS_MONTH_SECS: i64 = 30 * 86_400;
+
+/// Reputation system program, whose `["reputation_hook"]` PDA alone may push
+/// scores into agent profiles
+pub const REPUTATION_SYSTEM_ID: Pubkey = reputation_system::ID;
+
+/// Pyth oracle programs (mainnet, devnet) that own the price accounts USD-pegged agents name
+pub const PYTH_ORACLE_IDS: [Pubkey; 2] = [
+    pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH"),
+    pubkey!("gSbePebfvPy7tRqimPoVecS2UsBvYv46ynrzWocc92s"),
+];
+/// Oldest Pyth price, in seconds, a USD-pegged price is resolved against
+pub const MAX_PRICE_AGE_SECS: i64 = 60;
+
+// Bubblegum instruction discriminators; its Rust SDK does not build against Solana 2.x
+const CREATE_TREE_CONFIG_IX: [u8; 8] = [165, 83, 136, 142, 89, 202, 47, 220];
+const MINT_TO_COLLECTION_V1_IX: [u8; 8] = [153, 18, 178, 47, 197, 158, 86, 15];
+// Offset of `num_minted` inside a Bubblegum TreeConfig account
+const TREE_NUM_MINTED_OFFSET: usize = 80;
+// Pyth v2 price account layout; pyth-sdk-solana does not build against Solana 2.x
+const PYTH_MAGIC: u32 = 0xa1b2_c3d4;
+const PYTH_PRICE_ACCOUNT_TYPE: u32 = 3;
+const PYTH_STATUS_TRADING: u32 = 1;
+const PYTH_EXPO_OFFSET: usize = 20;
+const PYTH_TIMESTAMP_OFFSET: usize = 96;
+const PYTH_AGG_PRICE_OFFSET: usize = 208;
+const PYTH_AGG_STATUS_OFFSET: usize = 224;
+const PYTH_PRICE_ACCOUNT_MIN_LEN: usize = 240;
+
 #[program]
 pub mod agent_registry {
     use super::*;
 
-    pub fn register_agent(
-        ctx: Context<RegisterAgent>,
+    #[allow(clippy::too_many_arguments)]
+    pub fn register_agent<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RegisterAgent<'info>>,
         name: String,
         description: String,
         capabilities: Vec<String>,
         pricing: PricingModel,
-        endpoint_url: String,
+        endpoints: Vec<AgentEndpoint>,
         ipfs_hash: String,
         symbol: String,
         uri: String,
     ) -> Result<()> {
         require!(name.len() <= 50, ErrorCode::NameTooLong);
-        require!(description.len() <= 500, ErrorCode::DescriptionTooLong);
-        require!(endpoint_url.len() <= 200, ErrorCode::EndpointTooLong);
-        require!(capabilities.len() <= 10, ErrorCode::TooManyCapabilities);
+        require!(description.len() <= DEFAULT_DESCRIPTION_LIMIT, ErrorCode::DescriptionTooLong);
+        let endpoints = validate_endpoints(endpoints)?;
+        require!(capabilities.len() <= DEFAULT_CAPABILITY_LIMIT, ErrorCode::TooManyCapabilities);
+        require_accepted_mint(&pricing, ctx.accounts.accepted_mint.as_deref())?;
+        require_valid_surge(&pricing)?;
+        claim_agent_name(
+            ctx.accounts.agent_profile.key(),
+            &name,
+            &ctx.accounts.name_claim.to_account_info(),
+            &ctx.accounts.creator.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        index_capabilities(
+            ctx.accounts.agent_profile.key(),
+            &capabilities,
+            &[],
+            ctx.remaining_accounts,
+            &ctx.accounts.creator.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        lock_listing_stake(
+            &mut ctx.accounts.listing_stake,
+            ctx.accounts.registry_config.listing_stake,
+            ctx.accounts.agent_profile.key(),
+            &ctx.accounts.creator,
+            &ctx.accounts.system_program,
+        )?;
+        collect_registration_fee(
+            &ctx.accounts.fee_vault,
+            ctx.accounts.registry_config.registration_fee,
+            &ctx.accounts.creator,
+            &ctx.accounts.system_program,
+        )?;
+        record_agent_index(
+            &mut ctx.accounts.agent_index,
+            &mut ctx.accounts.registry_config,
+            ctx.accounts.agent_profile.key(),
+        );
+        init_agent_reputation(
+            &ctx.accounts.agent_reputation,
+            ctx.accounts.agent_profile.key(),
+            &ctx.accounts.creator,
+            &ctx.accounts.system_program,
+            &ctx.accounts.guardian_config,
+            &ctx.accounts.reputation_program,
+        )?;
 
     let profile_key = ctx.accounts.agent_profile.key();
     let creator_key = ctx.accounts.creator.key();
@@ -35,11 +211,12 @@ pub mod agent_registry {
         // Initialize agent profile
     agent_profile.agent_id = profile_key;
     agent_profile.creator = creator_key;
+    agent_profile.payout_wallet = creator_key;
         agent_profile.name = name.clone();
         agent_profile.description = description.clone();
         agent_profile.capabilities = capabilities;
         agent_profile.pricing_model = pricing;
-        agent_profile.endpoint_url = endpoint_url;
+        agent_profile.endpoints = endpoints;
         agent_profile.ipfs_hash = ipfs_hash;
         agent_profile.reputation_score = 0;
         agent_profile.total_services = 0;
@@ -47,6 +224,27 @@ pub mod agent_registry {
         agent_profile.created_at = clock.unix_timestamp;
         agent_profile.is_active = true;
     agent_profile.nft_mint = ctx.accounts.mint.key();
+        agent_profile.endpoint_verified = false;
+        agent_profile.featured_until = 0;
+        agent_profile.is_verified = false;
+        agent_profile.last_seen_at = clock.unix_timestamp;
+        agent_profile.max_concurrent_requests = 0;
+        agent_profile.paused_until = None;
+        agent_profile.capability_schemas = Vec::new();
+        agent_profile.parent_agent = None;
+        agent_profile.visibility = Visibility::Public;
+        agent_profile.capability_limit = DEFAULT_CAPABILITY_LIMIT as u8;
+        agent_profile.description_limit = DEFAULT_DESCRIPTION_LIMIT as u16;
+        agent_profile.trial_requests = 0;
+        agent_profile.languages = Vec::new();
+        agent_profile.dependencies = Vec::new();
+        agent_profile.soulbound = false;
+        agent_profile.retired = false;
+        agent_profile.successor = None;
+        agent_profile.authority = None;
+        agent_profile.frozen = false;
+        agent_profile.version = PROFILE_VERSION;
+        agent_profile.uptime_bps = None;
 
         // Create NFT metadata
         let creator = Creator {
@@ -90,6 +288,23 @@ pub mod agent_registry {
         )
         .invoke()?;
 
+        // Verify the NFT into the platform collection so wallets and marketplaces can tell genuine agents apart
+        let collection_authority_seeds: &[&[&[u8]]] = &[&[b"collection_authority", &[ctx.bumps.collection_authority]]];
+        SetAndVerifyCollectionCpi::new(
+            &ctx.accounts.token_metadata_program.to_account_info(),
+            SetAndVerifyCollectionCpiAccounts {
+                metadata: &metadata_info,
+                collection_authority: &ctx.accounts.collection_authority.to_account_info(),
+                payer: &creator_info,
+                update_authority: &creator_info,
+                collection_mint: &ctx.accounts.collection_mint.to_account_info(),
+                collection: &ctx.accounts.collection_metadata.to_account_info(),
+                collection_master_edition_account: &ctx.accounts.collection_master_edition.to_account_info(),
+                collection_authority_record: None,
+            },
+        )
+        .invoke_signed(collection_authority_seeds)?;
+
         // Mint NFT to creator
         let cpi_accounts = token::MintTo {
             mint: ctx.accounts.mint.to_account_info(),
@@ -104,6 +319,7 @@ pub mod agent_registry {
             agent_id: agent_profile.agent_id,
             creator: agent_profile.creator,
             name: agent_profile.name.clone(),
+            capabilities: agent_profile.capabilities.clone(),
             nft_mint: agent_profile.nft_mint,
             timestamp: clock.unix_timestamp,
         });
@@ -111,163 +327,8473 @@ pub mod agent_registry {
         Ok(())
     }
 
-    pub fn update_agent(
-        ctx: Context<UpdateAgent>,
-        name: Option<String>,
-        description: Option<String>,
-        pricing: Option<PricingModel>,
-        endpoint_url: Option<String>,
-        is_active: Option<bool>,
+    /// Register a new agent derived from `parent_profile`, recording the
+    /// parent on the profile. Payments made through the royalty splitter's
+    /// `distribute_lineage_payment` send `lineage_royalty_bps` of the amount to
+    /// the parent's creator; zero forks without a royalty
+    #[allow(clippy::too_many_arguments)]
+    pub fn fork_agent<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ForkAgent<'info>>,
+        name: String,
+        description: String,
+        capabilities: Vec<String>,
+        pricing: PricingModel,
+        endpoints: Vec<AgentEndpoint>,
+        ipfs_hash: String,
+        symbol: String,
+        uri: String,
+        lineage_royalty_bps: u16,
     ) -> Result<()> {
-        let agent_profile = &mut ctx.accounts.agent_profile;
+        require!(
+            lineage_royalty_bps <= MAX_LINEAGE_ROYALTY_BPS,
+            ErrorCode::LineageRoyaltyTooHigh
+        );
 
-        if let Some(name) = name {
-            require!(name.len() <= 50, ErrorCode::NameTooLong);
-            agent_profile.name = name;
-        }
-        if let Some(description) = description {
-            require!(description.len() <= 500, ErrorCode::DescriptionTooLong);
-            agent_profile.description = description;
-        }
-        if let Some(pricing) = pricing {
-            agent_profile.pricing_model = pricing;
-        }
-        if let Some(endpoint_url) = endpoint_url {
-            require!(endpoint_url.len() <= 200, ErrorCode::EndpointTooLong);
-            agent_profile.endpoint_url = endpoint_url;
-        }
-        if let Some(is_active) = is_active {
-            agent_profile.is_active = is_active;
-        }
+        register_agent(
+            Context::new(
+                ctx.program_id,
+                &mut ctx.accounts.register,
+                ctx.remaining_accounts,
+                ctx.bumps.register,
+            ),
+            name,
+            description,
+            capabilities,
+            pricing,
+            endpoints,
+            ipfs_hash,
+            symbol,
+            uri,
+        )?;
 
-        emit!(AgentUpdated {
-            agent_id: agent_profile.agent_id,
-            creator: agent_profile.creator,
+        let agent_id = ctx.accounts.register.agent_profile.key();
+        let parent_agent = ctx.accounts.parent_profile.key();
+        ctx.accounts.register.agent_profile.parent_agent = Some(parent_agent);
+
+        let agent_lineage = &mut ctx.accounts.agent_lineage;
+        agent_lineage.agent_id = agent_id;
+        agent_lineage.parent_agent = parent_agent;
+        agent_lineage.royalty_bps = lineage_royalty_bps;
+
+        emit!(AgentForked {
+            agent_id,
+            parent_agent,
+            lineage_royalty_bps,
             timestamp: Clock::get()?.unix_timestamp,
         });
 
         Ok(())
     }
 
-    pub fn update_reputation(
-        ctx: Context<UpdateReputation>,
-        new_rating: u32,
-        service_count: u64,
+    /// Register an agent whose NFT stays frozen in the creator's token
+    /// account, so the identity and reputation behind it cannot be sold.
+    /// The collection authority, which every agent mint names as its freeze
+    /// authority, only thaws the NFT to burn it on deregistration
+    #[allow(clippy::too_many_arguments)]
+    pub fn register_agent_soulbound<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RegisterAgentSoulbound<'info>>,
+        name: String,
+        description: String,
+        capabilities: Vec<String>,
+        pricing: PricingModel,
+        endpoints: Vec<AgentEndpoint>,
+        ipfs_hash: String,
+        symbol: String,
+        uri: String,
     ) -> Result<()> {
-        let agent_profile = &mut ctx.accounts.agent_profile;
-        
-        agent_profile.reputation_score = new_rating;
-        agent_profile.total_services = service_count;
+        let collection_authority_bump = ctx.bumps.register.collection_authority;
+        register_agent(
+            Context::new(
+                ctx.program_id,
+                &mut ctx.accounts.register,
+                ctx.remaining_accounts,
+                ctx.bumps.register,
+            ),
+            name,
+            description,
+            capabilities,
+            pricing,
+            endpoints,
+            ipfs_hash,
+            symbol,
+            uri,
+        )?;
+
+        let register = &ctx.accounts.register;
+        let collection_authority_seeds: &[&[&[u8]]] =
+            &[&[b"collection_authority", &[collection_authority_bump]]];
+        token::freeze_account(CpiContext::new_with_signer(
+            register.token_program.to_account_info(),
+            token::FreezeAccount {
+                account: register.token_account.to_account_info(),
+                mint: register.mint.to_account_info(),
+                authority: register.collection_authority.to_account_info(),
+            },
+            collection_authority_seeds,
+        ))?;
+
+        let agent_id = register.agent_profile.key();
+        let token_account = register.token_account.key();
+        ctx.accounts.register.agent_profile.soulbound = true;
+
+        emit!(AgentSoulbound {
+            agent_id,
+            token_account,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-pub struct RegisterAgent<'info> {
-    #[account(
-        init,
-        payer = creator,
-        space = 8 + AgentProfile::INIT_SPACE,
-        seeds = [b"agent", creator.key().as_ref()],
-        bump
-    )]
-    pub agent_profile: Account<'info, AgentProfile>,
+    /// Let `operator`, an operational key such as a build pipeline's, sign
+    /// manifests that register agents on the creator's behalf, and submit
+    /// results to the escrow for agents the creator manages (creator)
+    pub fn register_operator(ctx: Context<RegisterOperator>, operator: Pubkey) -> Result<()> {
+        let clock = Clock::get()?;
+        let operator_key = &mut ctx.accounts.operator_key;
+        operator_key.creator = ctx.accounts.creator.key();
+        operator_key.operator = operator;
+        operator_key.next_nonce = 0;
+        operator_key.registered_at = clock.unix_timestamp;
 
-    #[account(
-        init,
-        payer = creator,
-        mint::decimals = 0,
-        mint::authority = creator,
-    )]
-    pub mint: Account<'info, Mint>,
+        emit!(OperatorRegistered {
+            creator: operator_key.creator,
+            operator,
+            timestamp: clock.unix_timestamp,
+        });
 
-    #[account(
-        init,
-        payer = creator,
-        associated_token::mint = mint,
-        associated_token::authority = creator,
-    )]
-    pub token_account: Account<'info, TokenAccount>,
+        Ok(())
+    }
 
-    /// CHECK: This is not dangerous because we don't read or write from this account
-    #[account(mut)]
-    pub metadata: UncheckedAccount<'info>,
+    /// Stop accepting manifests signed by an operator (creator)
+    pub fn revoke_operator(ctx: Context<RevokeOperator>) -> Result<()> {
+        emit!(OperatorRevoked {
+            creator: ctx.accounts.creator.key(),
+            operator: ctx.accounts.operator_key.operator,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
-    #[account(mut)]
-    pub creator: Signer<'info>,
+        Ok(())
+    }
 
-    pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, anchor_spl::associated_token::AssociatedToken>,
-    /// CHECK: This is not dangerous because we don't read or write from this account
-    pub token_metadata_program: UncheckedAccount<'info>,
-    pub rent: Sysvar<'info, Rent>,
-}
+    /// Register an agent from an [`AgentManifest`] signed off-chain by one of
+    /// the creator's registered operators. An ed25519 program instruction
+    /// placed immediately before this one must verify the operator's
+    /// signature over `manifest`, the [`manifest_message`] bytes. Anyone can
+    /// submit it and pays for the registration, so a build pipeline holding
+    /// only the operational key can publish agents without the creator key.
+    /// The creator does not sign, so the NFT is minted by the registry's
+    /// metadata authority, which stays its update authority, and lists the
+    /// creator unverified
+    pub fn register_agent_with_manifest<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RegisterAgentWithManifest<'info>>,
+        manifest: Vec<u8>,
+    ) -> Result<()> {
+        let operator = ctx.accounts.operator_key.operator;
+        verify_signature(
+            &ctx.accounts.instructions.to_account_info(),
+            &operator,
+            &manifest,
+            ErrorCode::MissingManifestSignature,
+            ErrorCode::InvalidManifestSignature,
+        )?;
 
-#[derive(Accounts)]
-pub struct UpdateAgent<'info> {
-    #[account(
-        mut,
-        seeds = [b"agent", creator.key().as_ref()],
-        bump,
-        has_one = creator
-    )]
-    pub agent_profile: Account<'info, AgentProfile>,
+        let parsed = manifest
+            .strip_prefix(MANIFEST_DOMAIN)
+            .and_then(|body| AgentManifest::try_from_slice(body).ok())
+            .ok_or(ErrorCode::InvalidManifest)?;
+        require_keys_eq!(
+            parsed.creator,
+            ctx.accounts.creator.key(),
+            ErrorCode::ManifestCreatorMismatch
+        );
+        let clock = Clock::get()?;
+        require!(clock.unix_timestamp <= parsed.expires_at, ErrorCode::ManifestExpired);
+        let operator_key = &mut ctx.accounts.operator_key;
+        require!(parsed.nonce == operator_key.next_nonce, ErrorCode::StaleManifestNonce);
+        operator_key.next_nonce += 1;
 
-    pub creator: Signer<'info>,
-}
+        let AgentManifest {
+            name,
+            description,
+            capabilities,
+            pricing,
+            endpoints,
+            ipfs_hash,
+            symbol,
+            uri,
+            ..
+        } = parsed;
+        require!(name.len() <= 50, ErrorCode::NameTooLong);
+        require!(description.len() <= DEFAULT_DESCRIPTION_LIMIT, ErrorCode::DescriptionTooLong);
+        let endpoints = validate_endpoints(endpoints)?;
+        require!(capabilities.len() <= DEFAULT_CAPABILITY_LIMIT, ErrorCode::TooManyCapabilities);
+        require_accepted_mint(&pricing, ctx.accounts.accepted_mint.as_deref())?;
+        require_valid_surge(&pricing)?;
+        claim_agent_name(
+            ctx.accounts.agent_profile.key(),
+            &name,
+            &ctx.accounts.name_claim.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        index_capabilities(
+            ctx.accounts.agent_profile.key(),
+            &capabilities,
+            &[],
+            ctx.remaining_accounts,
+            &ctx.accounts.payer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        lock_listing_stake(
+            &mut ctx.accounts.listing_stake,
+            ctx.accounts.registry_config.listing_stake,
+            ctx.accounts.agent_profile.key(),
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )?;
+        collect_registration_fee(
+            &ctx.accounts.fee_vault,
+            ctx.accounts.registry_config.registration_fee,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )?;
+        record_agent_index(
+            &mut ctx.accounts.agent_index,
+            &mut ctx.accounts.registry_config,
+            ctx.accounts.agent_profile.key(),
+        );
+        init_agent_reputation(
+            &ctx.accounts.agent_reputation,
+            ctx.accounts.agent_profile.key(),
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            &ctx.accounts.guardian_config,
+            &ctx.accounts.reputation_program,
+        )?;
 
-#[derive(Accounts)]
-pub struct UpdateReputation<'info> {
-    #[account(mut)]
-    pub agent_profile: Account<'info, AgentProfile>,
-}
+        let profile_key = ctx.accounts.agent_profile.key();
+        let creator_key = ctx.accounts.creator.key();
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.agent_id = profile_key;
+        agent_profile.creator = creator_key;
+        agent_profile.payout_wallet = creator_key;
+        agent_profile.name = name.clone();
+        agent_profile.description = description;
+        agent_profile.capabilities = capabilities;
+        agent_profile.pricing_model = pricing;
+        agent_profile.endpoints = endpoints;
+        agent_profile.ipfs_hash = ipfs_hash;
+        agent_profile.reputation_score = 0;
+        agent_profile.total_services = 0;
+        agent_profile.total_earnings = 0;
+        agent_profile.created_at = clock.unix_timestamp;
+        agent_profile.is_active = true;
+        agent_profile.nft_mint = ctx.accounts.mint.key();
+        agent_profile.endpoint_verified = false;
+        agent_profile.featured_until = 0;
+        agent_profile.is_verified = false;
+        agent_profile.last_seen_at = clock.unix_timestamp;
+        agent_profile.max_concurrent_requests = 0;
+        agent_profile.paused_until = None;
+        agent_profile.capability_schemas = Vec::new();
+        agent_profile.parent_agent = None;
+        agent_profile.visibility = Visibility::Public;
+        agent_profile.capability_limit = DEFAULT_CAPABILITY_LIMIT as u8;
+        agent_profile.description_limit = DEFAULT_DESCRIPTION_LIMIT as u16;
+        agent_profile.trial_requests = 0;
+        agent_profile.languages = Vec::new();
+        agent_profile.dependencies = Vec::new();
+        agent_profile.soulbound = false;
+        agent_profile.retired = false;
+        agent_profile.successor = None;
+        agent_profile.authority = None;
+        agent_profile.frozen = false;
+        agent_profile.version = PROFILE_VERSION;
+        agent_profile.uptime_bps = None;
 
-#[account]
-#[derive(InitSpace)]
-pub struct AgentProfile {
-    pub agent_id: Pubkey,
-    pub creator: Pubkey,
-    #[max_len(50)]
-    pub name: String,
-    #[max_len(500)]
-    pub description: String,
-    #[max_len(10, 20)]
-    pub capabilities: Vec<String>,
-    pub pricing_model: PricingModel,
-    #[max_len(200)]
-    pub endpoint_url: String,
-    #[max_len(100)]
-    pub ipfs_hash: String,
-    pub reputation_score: u32,
-    pub total_services: u64,
-    pub total_earnings: u64,
-    pub created_at: i64,
-    pub is_active: bool,
-    pub nft_mint: Pubkey,
-}
+        let accounts = &ctx.accounts;
+        let metadata_info = accounts.metadata.to_account_info();
+        let mint_info = accounts.mint.to_account_info();
+        let payer_info = accounts.payer.to_account_info();
+        let metadata_authority_info = accounts.metadata_authority.to_account_info();
+        let system_program_info = accounts.system_program.to_account_info();
+        let rent_info = accounts.rent.to_account_info();
+        let signer_seeds: &[&[&[u8]]] = &[
+            &[b"metadata_authority", &[ctx.bumps.metadata_authority]],
+            &[b"collection_authority", &[ctx.bumps.collection_authority]],
+        ];
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
-pub enum PricingModel {
-    PerQuery { price: u64 },
-    Subscription { monthly: u64 },
-    Custom { base: u64, variable: u8 },
-}
+        CreateMetadataAccountV3Cpi::new(
+            &accounts.token_metadata_program.to_account_info(),
+            CreateMetadataAccountV3CpiAccounts {
+                metadata: &metadata_info,
+                mint: &mint_info,
+                mint_authority: &metadata_authority_info,
+                payer: &payer_info,
+                update_authority: (&metadata_authority_info, true),
+                system_program: &system_program_info,
+                rent: Some(&rent_info),
+            },
+            CreateMetadataAccountV3InstructionArgs {
+                data: DataV2 {
+                    name: format!("AgentMarket: {}", name),
+                    symbol,
+                    uri,
+                    seller_fee_basis_points: 500, // 5% royalty
+                    creators: Some(vec![Creator {
+                        address: creator_key,
+                        verified: false,
+                        share: 100,
+                    }]),
+                    collection: None,
+                    uses: None,
+                },
+                is_mutable: true,
+                collection_details: None,
+            },
+        )
+        .invoke_signed(signer_seeds)?;
 
-#[event]
-pub struct AgentRegistered {
-    pub agent_id: Pubkey,
-    pub creator: Pubkey,
-    pub name: String,
-    pub nft_mint: Pubkey,
-    pub timestamp: i64,
-}
+        SetAndVerifyCollectionCpi::new(
+            &accounts.token_metadata_program.to_account_info(),
+            SetAndVerifyCollectionCpiAccounts {
+                metadata: &metadata_info,
+                collection_authority: &accounts.collection_authority.to_account_info(),
+                payer: &payer_info,
+                update_authority: &metadata_authority_info,
+                collection_mint: &accounts.collection_mint.to_account_info(),
+                collection: &accounts.collection_metadata.to_account_info(),
+                collection_master_edition_account: &accounts.collection_master_edition.to_account_info(),
+                collection_authority_record: None,
+            },
+        )
+        .invoke_signed(signer_seeds)?;
 
-#[event]
-pub struct AgentUpdated {
-    pub agent_id: Pubkey,
-    pub creator: Pubkey,
+        token::mint_to(
+            CpiContext::new_with_signer(
+                accounts.token_program.to_account_info(),
+                token::MintTo {
+                    mint: mint_info.clone(),
+                    to: accounts.token_account.to_account_info(),
+                    authority: metadata_authority_info.clone(),
+                },
+                signer_seeds,
+            ),
+            1,
+        )?;
+
+        emit!(AgentRegistered {
+            agent_id: profile_key,
+            creator: creator_key,
+            name: accounts.agent_profile.name.clone(),
+            capabilities: accounts.agent_profile.capabilities.clone(),
+            nft_mint: accounts.mint.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        emit!(AgentManifestRegistered {
+            agent_id: profile_key,
+            operator,
+            manifest_hash: hashv(&[&manifest]).to_bytes(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Register an agent whose NFT is a compressed leaf in the platform's
+    /// Bubblegum tree instead of a mint, verified into the same collection.
+    /// `nft_mint` records the leaf's asset id; the holder-gated instructions
+    /// take an SPL token account, so they apply to standard agents only
+    #[allow(clippy::too_many_arguments)]
+    pub fn register_agent_compressed<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RegisterAgentCompressed<'info>>,
+        name: String,
+        description: String,
+        capabilities: Vec<String>,
+        pricing: PricingModel,
+        endpoints: Vec<AgentEndpoint>,
+        ipfs_hash: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        require!(name.len() <= 50, ErrorCode::NameTooLong);
+        require!(description.len() <= DEFAULT_DESCRIPTION_LIMIT, ErrorCode::DescriptionTooLong);
+        let endpoints = validate_endpoints(endpoints)?;
+        require!(capabilities.len() <= DEFAULT_CAPABILITY_LIMIT, ErrorCode::TooManyCapabilities);
+        require_accepted_mint(&pricing, ctx.accounts.accepted_mint.as_deref())?;
+        require_valid_surge(&pricing)?;
+        claim_agent_name(
+            ctx.accounts.agent_profile.key(),
+            &name,
+            &ctx.accounts.name_claim.to_account_info(),
+            &ctx.accounts.creator.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        index_capabilities(
+            ctx.accounts.agent_profile.key(),
+            &capabilities,
+            &[],
+            ctx.remaining_accounts,
+            &ctx.accounts.creator.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        lock_listing_stake(
+            &mut ctx.accounts.listing_stake,
+            ctx.accounts.registry_config.listing_stake,
+            ctx.accounts.agent_profile.key(),
+            &ctx.accounts.creator,
+            &ctx.accounts.system_program,
+        )?;
+        collect_registration_fee(
+            &ctx.accounts.fee_vault,
+            ctx.accounts.registry_config.registration_fee,
+            &ctx.accounts.creator,
+            &ctx.accounts.system_program,
+        )?;
+        record_agent_index(
+            &mut ctx.accounts.agent_index,
+            &mut ctx.accounts.registry_config,
+            ctx.accounts.agent_profile.key(),
+        );
+        init_agent_reputation(
+            &ctx.accounts.agent_reputation,
+            ctx.accounts.agent_profile.key(),
+            &ctx.accounts.creator,
+            &ctx.accounts.system_program,
+            &ctx.accounts.guardian_config,
+            &ctx.accounts.reputation_program,
+        )?;
+
+        // The leaf about to be minted takes the next index in the tree
+        let merkle_tree = ctx.accounts.merkle_tree.key();
+        let leaf_index = {
+            let data = ctx.accounts.tree_config.try_borrow_data()?;
+            data.get(TREE_NUM_MINTED_OFFSET..TREE_NUM_MINTED_OFFSET + 8)
+                .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+                .ok_or(ErrorCode::InvalidAgentTree)?
+        };
+        let asset_id = Pubkey::find_program_address(
+            &[b"asset", merkle_tree.as_ref(), &leaf_index.to_le_bytes()],
+            &BUBBLEGUM_PROGRAM_ID,
+        )
+        .0;
+
+        let profile_key = ctx.accounts.agent_profile.key();
+        let creator_key = ctx.accounts.creator.key();
+        let clock = Clock::get()?;
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.agent_id = profile_key;
+        agent_profile.creator = creator_key;
+        agent_profile.payout_wallet = creator_key;
+        agent_profile.name = name.clone();
+        agent_profile.description = description;
+        agent_profile.capabilities = capabilities;
+        agent_profile.pricing_model = pricing;
+        agent_profile.endpoints = endpoints;
+        agent_profile.ipfs_hash = ipfs_hash;
+        agent_profile.reputation_score = 0;
+        agent_profile.total_services = 0;
+        agent_profile.total_earnings = 0;
+        agent_profile.created_at = clock.unix_timestamp;
+        agent_profile.is_active = true;
+        agent_profile.nft_mint = asset_id;
+        agent_profile.endpoint_verified = false;
+        agent_profile.featured_until = 0;
+        agent_profile.is_verified = false;
+        agent_profile.last_seen_at = clock.unix_timestamp;
+        agent_profile.max_concurrent_requests = 0;
+        agent_profile.paused_until = None;
+        agent_profile.capability_schemas = Vec::new();
+        agent_profile.parent_agent = None;
+        agent_profile.visibility = Visibility::Public;
+        agent_profile.capability_limit = DEFAULT_CAPABILITY_LIMIT as u8;
+        agent_profile.description_limit = DEFAULT_DESCRIPTION_LIMIT as u16;
+        agent_profile.trial_requests = 0;
+        agent_profile.languages = Vec::new();
+        agent_profile.dependencies = Vec::new();
+        agent_profile.soulbound = false;
+        agent_profile.retired = false;
+        agent_profile.successor = None;
+        agent_profile.authority = None;
+        agent_profile.frozen = false;
+        agent_profile.version = PROFILE_VERSION;
+        agent_profile.uptime_bps = None;
+
+        let metadata = CompressedMetadataArgs {
+            name: format!("AgentMarket: {}", name),
+            symbol,
+            uri,
+            seller_fee_basis_points: 500, // 5% royalty
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: Some(TOKEN_STANDARD_NON_FUNGIBLE),
+            collection: Some(CompressedCollection {
+                verified: false,
+                key: ctx.accounts.collection_mint.key(),
+            }),
+            uses: None,
+            token_program_version: TOKEN_PROGRAM_VERSION_ORIGINAL,
+            creators: vec![CompressedCreator {
+                address: creator_key,
+                verified: true,
+                share: 100,
+            }],
+        };
+        let mut data = MINT_TO_COLLECTION_V1_IX.to_vec();
+        metadata.serialize(&mut data)?;
+
+        let accounts = &ctx.accounts;
+        let instruction = Instruction {
+            program_id: BUBBLEGUM_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(accounts.tree_config.key(), false),
+                AccountMeta::new_readonly(creator_key, false),
+                AccountMeta::new_readonly(creator_key, false),
+                AccountMeta::new(merkle_tree, false),
+                AccountMeta::new_readonly(creator_key, true),
+                AccountMeta::new_readonly(accounts.collection_authority.key(), true),
+                AccountMeta::new_readonly(accounts.collection_authority.key(), true),
+                // No collection authority record: the authority is the collection's update authority
+                AccountMeta::new_readonly(BUBBLEGUM_PROGRAM_ID, false),
+                AccountMeta::new_readonly(accounts.collection_mint.key(), false),
+                AccountMeta::new(accounts.collection_metadata.key(), false),
+                AccountMeta::new_readonly(accounts.collection_master_edition.key(), false),
+                AccountMeta::new_readonly(accounts.bubblegum_signer.key(), false),
+                AccountMeta::new_readonly(accounts.log_wrapper.key(), false),
+                AccountMeta::new_readonly(accounts.compression_program.key(), false),
+                AccountMeta::new_readonly(accounts.token_metadata_program.key(), false),
+                AccountMeta::new_readonly(accounts.system_program.key(), false),
+            ],
+            data,
+        };
+        anchor_lang::solana_program::program::invoke_signed(
+            &instruction,
+            &[
+                accounts.tree_config.to_account_info(),
+                accounts.creator.to_account_info(),
+                accounts.merkle_tree.to_account_info(),
+                accounts.collection_authority.to_account_info(),
+                accounts.bubblegum_program.to_account_info(),
+                accounts.collection_mint.to_account_info(),
+                accounts.collection_metadata.to_account_info(),
+                accounts.collection_master_edition.to_account_info(),
+                accounts.bubblegum_signer.to_account_info(),
+                accounts.log_wrapper.to_account_info(),
+                accounts.compression_program.to_account_info(),
+                accounts.token_metadata_program.to_account_info(),
+                accounts.system_program.to_account_info(),
+            ],
+            &[&[b"collection_authority", &[ctx.bumps.collection_authority]]],
+        )?;
+
+        emit!(AgentRegistered {
+            agent_id: profile_key,
+            creator: creator_key,
+            name: accounts.agent_profile.name.clone(),
+            capabilities: accounts.agent_profile.capabilities.clone(),
+            nft_mint: asset_id,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Register an agent whose NFT is a Metaplex programmable NFT (pNFT)
+    /// bound to the royalty rule set, so secondary sales can't skip the 5%
+    /// royalty. The token stays frozen and moves only through the metadata
+    /// program's `transfer` instruction; the holder-gated instructions accept
+    /// it like a standard agent NFT, but the fractional vault can't lock it
+    #[allow(clippy::too_many_arguments)]
+    pub fn register_agent_programmable<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RegisterAgentProgrammable<'info>>,
+        name: String,
+        description: String,
+        capabilities: Vec<String>,
+        pricing: PricingModel,
+        endpoints: Vec<AgentEndpoint>,
+        ipfs_hash: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        require!(name.len() <= 50, ErrorCode::NameTooLong);
+        require!(description.len() <= DEFAULT_DESCRIPTION_LIMIT, ErrorCode::DescriptionTooLong);
+        let endpoints = validate_endpoints(endpoints)?;
+        require!(capabilities.len() <= DEFAULT_CAPABILITY_LIMIT, ErrorCode::TooManyCapabilities);
+        require_accepted_mint(&pricing, ctx.accounts.accepted_mint.as_deref())?;
+        require_valid_surge(&pricing)?;
+        claim_agent_name(
+            ctx.accounts.agent_profile.key(),
+            &name,
+            &ctx.accounts.name_claim.to_account_info(),
+            &ctx.accounts.creator.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        index_capabilities(
+            ctx.accounts.agent_profile.key(),
+            &capabilities,
+            &[],
+            ctx.remaining_accounts,
+            &ctx.accounts.creator.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        lock_listing_stake(
+            &mut ctx.accounts.listing_stake,
+            ctx.accounts.registry_config.listing_stake,
+            ctx.accounts.agent_profile.key(),
+            &ctx.accounts.creator,
+            &ctx.accounts.system_program,
+        )?;
+        collect_registration_fee(
+            &ctx.accounts.fee_vault,
+            ctx.accounts.registry_config.registration_fee,
+            &ctx.accounts.creator,
+            &ctx.accounts.system_program,
+        )?;
+        record_agent_index(
+            &mut ctx.accounts.agent_index,
+            &mut ctx.accounts.registry_config,
+            ctx.accounts.agent_profile.key(),
+        );
+        init_agent_reputation(
+            &ctx.accounts.agent_reputation,
+            ctx.accounts.agent_profile.key(),
+            &ctx.accounts.creator,
+            &ctx.accounts.system_program,
+            &ctx.accounts.guardian_config,
+            &ctx.accounts.reputation_program,
+        )?;
+
+        let profile_key = ctx.accounts.agent_profile.key();
+        let creator_key = ctx.accounts.creator.key();
+        let clock = Clock::get()?;
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.agent_id = profile_key;
+        agent_profile.creator = creator_key;
+        agent_profile.payout_wallet = creator_key;
+        agent_profile.name = name.clone();
+        agent_profile.description = description;
+        agent_profile.capabilities = capabilities;
+        agent_profile.pricing_model = pricing;
+        agent_profile.endpoints = endpoints;
+        agent_profile.ipfs_hash = ipfs_hash;
+        agent_profile.reputation_score = 0;
+        agent_profile.total_services = 0;
+        agent_profile.total_earnings = 0;
+        agent_profile.created_at = clock.unix_timestamp;
+        agent_profile.is_active = true;
+        agent_profile.nft_mint = ctx.accounts.mint.key();
+        agent_profile.endpoint_verified = false;
+        agent_profile.featured_until = 0;
+        agent_profile.is_verified = false;
+        agent_profile.last_seen_at = clock.unix_timestamp;
+        agent_profile.max_concurrent_requests = 0;
+        agent_profile.paused_until = None;
+        agent_profile.capability_schemas = Vec::new();
+        agent_profile.parent_agent = None;
+        agent_profile.visibility = Visibility::Public;
+        agent_profile.capability_limit = DEFAULT_CAPABILITY_LIMIT as u8;
+        agent_profile.description_limit = DEFAULT_DESCRIPTION_LIMIT as u16;
+        agent_profile.trial_requests = 0;
+        agent_profile.languages = Vec::new();
+        agent_profile.dependencies = Vec::new();
+        agent_profile.soulbound = false;
+        agent_profile.retired = false;
+        agent_profile.successor = None;
+        agent_profile.authority = None;
+        agent_profile.frozen = false;
+        agent_profile.version = PROFILE_VERSION;
+        agent_profile.uptime_bps = None;
+
+        let accounts = &ctx.accounts;
+        let token_metadata_program = accounts.token_metadata_program.to_account_info();
+        let metadata_info = accounts.metadata.to_account_info();
+        let master_edition_info = accounts.master_edition.to_account_info();
+        let mint_info = accounts.mint.to_account_info();
+        let creator_info = accounts.creator.to_account_info();
+        let system_program_info = accounts.system_program.to_account_info();
+        let sysvar_instructions_info = accounts.sysvar_instructions.to_account_info();
+        let token_program_info = accounts.token_program.to_account_info();
+
+        // Create the metadata and master edition; the metadata program initializes the mint
+        CreateV1Cpi::new(
+            &token_metadata_program,
+            CreateV1CpiAccounts {
+                metadata: &metadata_info,
+                master_edition: Some(&master_edition_info),
+                mint: (&mint_info, true),
+                authority: &creator_info,
+                payer: &creator_info,
+                update_authority: (&creator_info, true),
+                system_program: &system_program_info,
+                sysvar_instructions: &sysvar_instructions_info,
+                spl_token_program: Some(&token_program_info),
+            },
+            CreateV1InstructionArgs {
+                name: format!("AgentMarket: {}", name),
+                symbol,
+                uri,
+                seller_fee_basis_points: 500, // 5% royalty
+                creators: Some(vec![Creator {
+                    address: creator_key,
+                    verified: true,
+                    share: 100,
+                }]),
+                primary_sale_happened: false,
+                is_mutable: true,
+                token_standard: TokenStandard::ProgrammableNonFungible,
+                collection: Some(Collection {
+                    verified: false,
+                    key: accounts.collection_mint.key(),
+                }),
+                uses: None,
+                collection_details: None,
+                rule_set: Some(ROYALTY_RULE_SET),
+                decimals: Some(0),
+                print_supply: Some(PrintSupply::Zero),
+            },
+        )
+        .invoke()?;
+
+        // Mint the token to the creator, which also opens its token record
+        MintV1Cpi::new(
+            &token_metadata_program,
+            MintV1CpiAccounts {
+                token: &accounts.token_account.to_account_info(),
+                token_owner: Some(&creator_info),
+                metadata: &metadata_info,
+                master_edition: Some(&master_edition_info),
+                token_record: Some(&accounts.token_record.to_account_info()),
+                mint: &mint_info,
+                authority: &creator_info,
+                delegate_record: None,
+                payer: &creator_info,
+                system_program: &system_program_info,
+                sysvar_instructions: &sysvar_instructions_info,
+                spl_token_program: &token_program_info,
+                spl_ata_program: &accounts.associated_token_program.to_account_info(),
+                authorization_rules_program: Some(&accounts.authorization_rules_program.to_account_info()),
+                authorization_rules: Some(&accounts.authorization_rules.to_account_info()),
+            },
+            MintV1InstructionArgs {
+                amount: 1,
+                authorization_data: None,
+            },
+        )
+        .invoke()?;
+
+        // Verify the pNFT into the platform collection, as standard registration does
+        VerifyCollectionV1Cpi::new(
+            &token_metadata_program,
+            VerifyCollectionV1CpiAccounts {
+                authority: &accounts.collection_authority.to_account_info(),
+                delegate_record: None,
+                metadata: &metadata_info,
+                collection_mint: &accounts.collection_mint.to_account_info(),
+                collection_metadata: Some(&accounts.collection_metadata.to_account_info()),
+                collection_master_edition: Some(&accounts.collection_master_edition.to_account_info()),
+                system_program: &system_program_info,
+                sysvar_instructions: &sysvar_instructions_info,
+            },
+        )
+        .invoke_signed(&[&[b"collection_authority", &[ctx.bumps.collection_authority]]])?;
+
+        emit!(AgentRegistered {
+            agent_id: profile_key,
+            creator: creator_key,
+            name: accounts.agent_profile.name.clone(),
+            capabilities: accounts.agent_profile.capabilities.clone(),
+            nft_mint: accounts.mint.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Create the Bubblegum tree config for the platform's compressed agent
+    /// tree (guardian authority). The client allocates `merkle_tree` for the
+    /// compression program in the same transaction; the collection authority
+    /// PDA becomes the tree's creator, so only the registry mints into it
+    pub fn initialize_agent_tree(
+        ctx: Context<InitializeAgentTree>,
+        max_depth: u32,
+        max_buffer_size: u32,
+    ) -> Result<()> {
+        let mut data = CREATE_TREE_CONFIG_IX.to_vec();
+        data.extend_from_slice(&max_depth.to_le_bytes());
+        data.extend_from_slice(&max_buffer_size.to_le_bytes());
+        // `public: Some(false)`
+        data.extend_from_slice(&[1, 0]);
+
+        let accounts = &ctx.accounts;
+        let instruction = Instruction {
+            program_id: BUBBLEGUM_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(accounts.tree_config.key(), false),
+                AccountMeta::new(accounts.merkle_tree.key(), false),
+                AccountMeta::new(accounts.authority.key(), true),
+                AccountMeta::new_readonly(accounts.collection_authority.key(), true),
+                AccountMeta::new_readonly(accounts.log_wrapper.key(), false),
+                AccountMeta::new_readonly(accounts.compression_program.key(), false),
+                AccountMeta::new_readonly(accounts.system_program.key(), false),
+            ],
+            data,
+        };
+        anchor_lang::solana_program::program::invoke_signed(
+            &instruction,
+            &[
+                accounts.tree_config.to_account_info(),
+                accounts.merkle_tree.to_account_info(),
+                accounts.authority.to_account_info(),
+                accounts.collection_authority.to_account_info(),
+                accounts.log_wrapper.to_account_info(),
+                accounts.compression_program.to_account_info(),
+                accounts.system_program.to_account_info(),
+            ],
+            &[&[b"collection_authority", &[ctx.bumps.collection_authority]]],
+        )?;
+
+        emit!(AgentTreeInitialized {
+            merkle_tree: accounts.merkle_tree.key(),
+            max_depth,
+            max_buffer_size,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Create the platform collection NFT that every agent NFT is verified
+    /// into (guardian authority). The collection authority PDA holds the
+    /// collection token and is its update authority
+    pub fn initialize_collection(
+        ctx: Context<InitializeCollection>,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        let collection_authority_seeds: &[&[&[u8]]] = &[&[b"collection_authority", &[ctx.bumps.collection_authority]]];
+        let authority_info = ctx.accounts.collection_authority.to_account_info();
+        let mint_info = ctx.accounts.collection_mint.to_account_info();
+        let metadata_info = ctx.accounts.collection_metadata.to_account_info();
+        let payer_info = ctx.accounts.authority.to_account_info();
+        let token_metadata_program_info = ctx.accounts.token_metadata_program.to_account_info();
+        let token_program_info = ctx.accounts.token_program.to_account_info();
+        let system_program_info = ctx.accounts.system_program.to_account_info();
+        let rent_info = ctx.accounts.rent.to_account_info();
+
+        token::mint_to(
+            CpiContext::new_with_signer(
+                token_program_info.clone(),
+                token::MintTo {
+                    mint: mint_info.clone(),
+                    to: ctx.accounts.collection_token_account.to_account_info(),
+                    authority: authority_info.clone(),
+                },
+                collection_authority_seeds,
+            ),
+            1,
+        )?;
+
+        // Unsized, so agent NFTs can be verified with SetAndVerifyCollection
+        CreateMetadataAccountV3Cpi::new(
+            &token_metadata_program_info,
+            CreateMetadataAccountV3CpiAccounts {
+                metadata: &metadata_info,
+                mint: &mint_info,
+                mint_authority: &authority_info,
+                payer: &payer_info,
+                update_authority: (&authority_info, true),
+                system_program: &system_program_info,
+                rent: Some(&rent_info),
+            },
+            CreateMetadataAccountV3InstructionArgs {
+                data: DataV2 {
+                    name,
+                    symbol,
+                    uri,
+                    seller_fee_basis_points: 0,
+                    creators: None,
+                    collection: None,
+                    uses: None,
+                },
+                is_mutable: true,
+                collection_details: None,
+            },
+        )
+        .invoke_signed(collection_authority_seeds)?;
+
+        CreateMasterEditionV3Cpi::new(
+            &token_metadata_program_info,
+            CreateMasterEditionV3CpiAccounts {
+                edition: &ctx.accounts.collection_master_edition.to_account_info(),
+                mint: &mint_info,
+                update_authority: &authority_info,
+                mint_authority: &authority_info,
+                payer: &payer_info,
+                metadata: &metadata_info,
+                token_program: &token_program_info,
+                system_program: &system_program_info,
+                rent: Some(&rent_info),
+            },
+            CreateMasterEditionV3InstructionArgs { max_supply: Some(0) },
+        )
+        .invoke_signed(collection_authority_seeds)?;
+
+        emit!(CollectionInitialized {
+            collection_mint: ctx.accounts.collection_mint.key(),
+            authority: ctx.accounts.authority.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Create the registry config holding the listing stake every new agent
+    /// locks, the key allowed to slash it, the daily price of a featured
+    /// listing, the verifier, how long an agent may go without a heartbeat,
+    /// and the one-time registration fee (guardian authority)
+    pub fn initialize_registry_config(
+        ctx: Context<InitializeRegistryConfig>,
+        listing_stake: u64,
+        slash_authority: Pubkey,
+        featured_price_per_day: u64,
+        verifier: Pubkey,
+        heartbeat_window: i64,
+        registration_fee: u64,
+    ) -> Result<()> {
+        require!(heartbeat_window >= 0, ErrorCode::InvalidHeartbeatWindow);
+        require_valid_registration_fee(registration_fee)?;
+        let config = &mut ctx.accounts.registry_config;
+        config.listing_stake = listing_stake;
+        config.slash_authority = slash_authority;
+        config.featured_price_per_day = featured_price_per_day;
+        config.verifier = verifier;
+        config.heartbeat_window = heartbeat_window;
+        config.registration_fee = registration_fee;
+        config.agent_count = 0;
+
+        emit!(RegistryConfigUpdated {
+            listing_stake,
+            slash_authority,
+            featured_price_per_day,
+            verifier,
+            heartbeat_window,
+            registration_fee,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Change the listing stake, slash authority, featured listing price,
+    /// verifier, heartbeat window, or registration fee (guardian authority).
+    /// Agents keep the stake they locked at registration and the verification
+    /// they were granted
+    pub fn update_registry_config(
+        ctx: Context<UpdateRegistryConfig>,
+        listing_stake: Option<u64>,
+        slash_authority: Option<Pubkey>,
+        featured_price_per_day: Option<u64>,
+        verifier: Option<Pubkey>,
+        heartbeat_window: Option<i64>,
+        registration_fee: Option<u64>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.registry_config;
+        if let Some(listing_stake) = listing_stake {
+            config.listing_stake = listing_stake;
+        }
+        if let Some(slash_authority) = slash_authority {
+            config.slash_authority = slash_authority;
+        }
+        if let Some(featured_price_per_day) = featured_price_per_day {
+            config.featured_price_per_day = featured_price_per_day;
+        }
+        if let Some(verifier) = verifier {
+            config.verifier = verifier;
+        }
+        if let Some(heartbeat_window) = heartbeat_window {
+            require!(heartbeat_window >= 0, ErrorCode::InvalidHeartbeatWindow);
+            config.heartbeat_window = heartbeat_window;
+        }
+        if let Some(registration_fee) = registration_fee {
+            require_valid_registration_fee(registration_fee)?;
+            config.registration_fee = registration_fee;
+        }
+
+        emit!(RegistryConfigUpdated {
+            listing_stake: config.listing_stake,
+            slash_authority: config.slash_authority,
+            featured_price_per_day: config.featured_price_per_day,
+            verifier: config.verifier,
+            heartbeat_window: config.heartbeat_window,
+            registration_fee: config.registration_fee,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Route the registration fees collected in the fee vault through the
+    /// royalty splitter (permissionless). The creator share goes to the
+    /// platform wallet, as with promotion fees
+    pub fn sweep_registration_fees(ctx: Context<SweepRegistrationFees>) -> Result<()> {
+        let amount = ctx.accounts.fee_vault.lamports();
+        require!(amount > 0, ErrorCode::NoRegistrationFees);
+
+        let platform_wallet = ctx.accounts.platform_account.key();
+        royalty_splitter::cpi::distribute_payment(
+            CpiContext::new_with_signer(
+                ctx.accounts.royalty_program.to_account_info(),
+                royalty_splitter::cpi::accounts::DistributePayment {
+                    royalty_config: ctx.accounts.royalty_config.to_account_info(),
+                    distribution_record: ctx.accounts.distribution_record.to_account_info(),
+                    source_account: ctx.accounts.fee_vault.to_account_info(),
+                    creator_account: ctx.accounts.platform_account.to_account_info(),
+                    platform_account: ctx.accounts.platform_account.to_account_info(),
+                    treasury_account: ctx.accounts.treasury_account.to_account_info(),
+                    payer: ctx.accounts.payer.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    guardian_config: ctx.accounts.guardian_config.to_account_info(),
+                },
+                &[&[b"fee_vault", &[ctx.bumps.fee_vault]]],
+            ),
+            amount,
+            platform_wallet,
+        )?;
+
+        emit!(RegistrationFeesSwept {
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Create a referral code new agents can register with (creator of a
+    /// registered agent). The referrer funds the rent of the code's vault,
+    /// where its share of referred registration fees accrues
+    pub fn create_referral_code(ctx: Context<CreateReferralCode>, code: String) -> Result<()> {
+        require!(
+            !code.is_empty() && code.len() <= MAX_REFERRAL_CODE_LEN,
+            ErrorCode::InvalidReferralCode
+        );
+
+        let rent = Rent::get()?.minimum_balance(0);
+        let shortfall = rent.saturating_sub(ctx.accounts.referral_vault.lamports());
+        if shortfall > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.referrer.to_account_info(),
+                        to: ctx.accounts.referral_vault.to_account_info(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+
+        let clock = Clock::get()?;
+        let referral_code = &mut ctx.accounts.referral_code;
+        referral_code.referrer = ctx.accounts.referrer.key();
+        referral_code.referrals = 0;
+        referral_code.earned = 0;
+        referral_code.created_at = clock.unix_timestamp;
+        referral_code.code = code.clone();
+
+        emit!(ReferralCodeCreated {
+            referral_code: referral_code.key(),
+            referrer: referral_code.referrer,
+            code,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Register an agent as `register_agent` does, crediting `referral_code`.
+    /// `REFERRAL_FEE_BPS` of the registration fee moves from the fee vault to
+    /// the code's vault, to be swept to the referrer through the royalty
+    /// splitter. Creators cannot refer themselves
+    #[allow(clippy::too_many_arguments)]
+    pub fn register_agent_with_referral<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RegisterAgentWithReferral<'info>>,
+        name: String,
+        description: String,
+        capabilities: Vec<String>,
+        pricing: PricingModel,
+        endpoints: Vec<AgentEndpoint>,
+        ipfs_hash: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        let fee_vault_bump = ctx.bumps.register.fee_vault;
+        register_agent(
+            Context::new(
+                ctx.program_id,
+                &mut ctx.accounts.register,
+                ctx.remaining_accounts,
+                ctx.bumps.register,
+            ),
+            name,
+            description,
+            capabilities,
+            pricing,
+            endpoints,
+            ipfs_hash,
+            symbol,
+            uri,
+        )?;
+
+        // Never take the fee vault below its rent exemption
+        let register = &ctx.accounts.register;
+        let fee_vault_spare = register
+            .fee_vault
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0));
+        let share = (register.registry_config.registration_fee * REFERRAL_FEE_BPS / 10_000)
+            .min(fee_vault_spare);
+        if share > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new_with_signer(
+                    register.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: register.fee_vault.to_account_info(),
+                        to: ctx.accounts.referral_vault.to_account_info(),
+                    },
+                    &[&[b"fee_vault", &[fee_vault_bump]]],
+                ),
+                share,
+            )?;
+        }
+
+        let agent_id = register.agent_profile.key();
+        let referral_code = &mut ctx.accounts.referral_code;
+        referral_code.referrals += 1;
+        referral_code.earned += share;
+
+        emit!(AgentReferred {
+            agent_id,
+            referral_code: referral_code.key(),
+            referrer: referral_code.referrer,
+            share,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Route a referral code's accrued fees through the royalty splitter with
+    /// the referrer as creator (permissionless). The vault keeps its rent
+    pub fn sweep_referral_fees(ctx: Context<SweepReferralFees>) -> Result<()> {
+        let amount = ctx
+            .accounts
+            .referral_vault
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0));
+        require!(amount > 0, ErrorCode::NoReferralFees);
+
+        let referral_code = ctx.accounts.referral_code.key();
+        let referrer = ctx.accounts.referrer.key();
+        royalty_splitter::cpi::distribute_payment(
+            CpiContext::new_with_signer(
+                ctx.accounts.royalty_program.to_account_info(),
+                royalty_splitter::cpi::accounts::DistributePayment {
+                    royalty_config: ctx.accounts.royalty_config.to_account_info(),
+                    distribution_record: ctx.accounts.distribution_record.to_account_info(),
+                    source_account: ctx.accounts.referral_vault.to_account_info(),
+                    creator_account: ctx.accounts.referrer.to_account_info(),
+                    platform_account: ctx.accounts.platform_account.to_account_info(),
+                    treasury_account: ctx.accounts.treasury_account.to_account_info(),
+                    payer: ctx.accounts.payer.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    guardian_config: ctx.accounts.guardian_config.to_account_info(),
+                },
+                &[&[
+                    b"referral_vault",
+                    referral_code.as_ref(),
+                    &[ctx.bumps.referral_vault],
+                ]],
+            ),
+            amount,
+            referrer,
+        )?;
+
+        emit!(ReferralFeesSwept {
+            referral_code,
+            referrer,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Slash part of an agent's listing stake over one of its disputed
+    /// requests (slash authority), paying it to the user who disputed. Each
+    /// request can be ruled on once
+    pub fn slash_stake(ctx: Context<SlashStake>, amount: u64) -> Result<()> {
+        let service_request = &ctx.accounts.service_request;
+        require!(
+            service_request.status == RequestStatus::Disputed,
+            ErrorCode::RequestNotDisputed
+        );
+
+        let listing_stake = &mut ctx.accounts.listing_stake;
+        require!(
+            amount > 0 && amount <= listing_stake.amount,
+            ErrorCode::SlashExceedsStake
+        );
+        listing_stake.amount -= amount;
+        listing_stake.total_slashed = listing_stake.total_slashed.saturating_add(amount);
+        **listing_stake.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.user.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        let clock = Clock::get()?;
+        let slash = &mut ctx.accounts.stake_slash;
+        slash.service_request = service_request.key();
+        slash.agent_id = service_request.agent_id;
+        slash.amount = amount;
+        slash.slashed_at = clock.unix_timestamp;
+
+        emit!(StakeSlashed {
+            agent_id: service_request.agent_id,
+            request_id: service_request.request_id,
+            user: service_request.user,
+            amount,
+            remaining: listing_stake.amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Mark an audited agent as verified (verifier)
+    pub fn verify_agent(ctx: Context<SetAgentVerification>) -> Result<()> {
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        require!(!agent_profile.is_verified, ErrorCode::AgentAlreadyVerified);
+        agent_profile.is_verified = true;
+
+        emit!(AgentVerified {
+            agent_id: agent_profile.key(),
+            verifier: ctx.accounts.verifier.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Take an agent's verified badge away (verifier)
+    pub fn revoke_verification(ctx: Context<SetAgentVerification>) -> Result<()> {
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        require!(agent_profile.is_verified, ErrorCode::AgentNotVerified);
+        agent_profile.is_verified = false;
+
+        emit!(AgentVerificationRevoked {
+            agent_id: agent_profile.key(),
+            verifier: ctx.accounts.verifier.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Freeze a malicious agent (slash authority): its profile can't be
+    /// updated, and it can't be sold, transferred, retired, or deregistered
+    /// with its listing stake, until unfrozen. The escrow refuses new requests
+    /// for it meanwhile; open requests run their course
+    pub fn freeze_agent(ctx: Context<SetAgentFreeze>) -> Result<()> {
+        require!(!ctx.accounts.agent_profile.frozen, ErrorCode::AgentFrozen);
+
+        set_request_freeze(&ctx, true)?;
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.frozen = true;
+
+        emit!(AgentFrozen {
+            agent_id: agent_profile.key(),
+            authority: ctx.accounts.slash_authority.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Lift an agent's freeze once the incident is resolved (slash authority)
+    pub fn unfreeze_agent(ctx: Context<SetAgentFreeze>) -> Result<()> {
+        require!(ctx.accounts.agent_profile.frozen, ErrorCode::AgentNotFrozen);
+
+        set_request_freeze(&ctx, false)?;
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.frozen = false;
+
+        emit!(AgentUnfrozen {
+            agent_id: agent_profile.key(),
+            authority: ctx.accounts.slash_authority.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Attest to an agent under `schema`, e.g. a compliance certification,
+    /// committing to the off-chain evidence by `data_hash`. Any key can issue;
+    /// buyers decide which issuers they trust. Issuing again renews the attestation
+    pub fn issue_agent_attestation(
+        ctx: Context<IssueAgentAttestation>,
+        schema: [u8; 32],
+        data_hash: [u8; 32],
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        if let Some(expires_at) = expires_at {
+            require!(expires_at > clock.unix_timestamp, ErrorCode::InvalidAttestationExpiry);
+        }
+
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.agent_id = ctx.accounts.agent_profile.key();
+        attestation.issuer = ctx.accounts.issuer.key();
+        attestation.schema = schema;
+        attestation.data_hash = data_hash;
+        attestation.issued_at = clock.unix_timestamp;
+        attestation.is_revoked = false;
+        attestation.expires_at = expires_at;
+        attestation.revoked_at = None;
+
+        emit!(AgentAttestationIssued {
+            attestation: attestation.key(),
+            agent_id: attestation.agent_id,
+            issuer: attestation.issuer,
+            schema,
+            data_hash,
+            expires_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Revoke an attestation (issuer)
+    pub fn revoke_agent_attestation(ctx: Context<RevokeAgentAttestation>) -> Result<()> {
+        let clock = Clock::get()?;
+        let attestation = &mut ctx.accounts.attestation;
+        require!(!attestation.is_revoked, ErrorCode::AttestationAlreadyRevoked);
+        attestation.is_revoked = true;
+        attestation.revoked_at = Some(clock.unix_timestamp);
+
+        emit!(AgentAttestationRevoked {
+            attestation: attestation.key(),
+            agent_id: attestation.agent_id,
+            issuer: attestation.issuer,
+            schema: attestation.schema,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Add a capability to the curated taxonomy (guardian authority). Agents
+    /// list capability ids, each backed by one of these accounts
+    pub fn add_capability(
+        ctx: Context<AddCapability>,
+        id: String,
+        name: String,
+        description: String,
+    ) -> Result<()> {
+        require!(
+            !id.is_empty() && id.len() <= MAX_CAPABILITY_ID_LEN,
+            ErrorCode::CapabilityIdTooLong
+        );
+        require!(name.len() <= 50, ErrorCode::NameTooLong);
+        require!(description.len() <= 200, ErrorCode::DescriptionTooLong);
+
+        let clock = Clock::get()?;
+        let capability = &mut ctx.accounts.capability;
+        capability.id = id.clone();
+        capability.name = name.clone();
+        capability.description = description;
+        capability.is_deprecated = false;
+        capability.created_at = clock.unix_timestamp;
+        capability.deprecated_at = None;
+
+        emit!(CapabilityAdded {
+            capability: capability.key(),
+            id,
+            name,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Retire a capability (guardian authority). Agents already listing it
+    /// keep it; new registrations can no longer use it
+    pub fn deprecate_capability(ctx: Context<DeprecateCapability>) -> Result<()> {
+        let capability = &mut ctx.accounts.capability;
+        require!(!capability.is_deprecated, ErrorCode::CapabilityDeprecated);
+
+        let clock = Clock::get()?;
+        capability.is_deprecated = true;
+        capability.deprecated_at = Some(clock.unix_timestamp);
+
+        emit!(CapabilityDeprecated {
+            capability: capability.key(),
+            id: capability.id.clone(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Accept an SPL token for agent pricing (guardian authority)
+    pub fn add_accepted_mint(ctx: Context<AddAcceptedMint>) -> Result<()> {
+        let clock = Clock::get()?;
+        let accepted_mint = &mut ctx.accounts.accepted_mint;
+        accepted_mint.mint = ctx.accounts.mint.key();
+        accepted_mint.decimals = ctx.accounts.mint.decimals;
+        accepted_mint.added_at = clock.unix_timestamp;
+
+        emit!(AcceptedMintAdded {
+            mint: accepted_mint.mint,
+            decimals: accepted_mint.decimals,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Stop accepting an SPL token for new pricing (guardian authority).
+    /// Agents already priced in it keep their pricing until they change it
+    pub fn remove_accepted_mint(ctx: Context<RemoveAcceptedMint>) -> Result<()> {
+        emit!(AcceptedMintRemoved {
+            mint: ctx.accounts.accepted_mint.mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Let `checker` post endpoint health checks for any agent (guardian
+    /// authority)
+    pub fn add_health_checker(ctx: Context<AddHealthChecker>, checker: Pubkey) -> Result<()> {
+        let clock = Clock::get()?;
+        let health_checker = &mut ctx.accounts.health_checker;
+        health_checker.checker = checker;
+        health_checker.added_at = clock.unix_timestamp;
+
+        emit!(HealthCheckerAdded {
+            checker,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Stop accepting health checks from a checker (guardian authority).
+    /// Checks it already posted stay in the logs until overwritten
+    pub fn remove_health_checker(ctx: Context<RemoveHealthChecker>) -> Result<()> {
+        emit!(HealthCheckerRemoved {
+            checker: ctx.accounts.health_checker.checker,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Post whether the agent's endpoints answered (registered checker). The
+    /// check goes into the agent's ring buffer of the last `HEALTH_LOG_LEN`
+    /// checks, and the profile's `uptime_bps` becomes the share of them that
+    /// passed. Each checker may check an agent once per
+    /// `MIN_HEALTH_CHECK_INTERVAL`
+    pub fn submit_health_check(
+        ctx: Context<SubmitHealthCheck>,
+        healthy: bool,
+        latency_ms: u32,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let checker = ctx.accounts.checker.key();
+        let health_log = &mut ctx.accounts.health_log;
+        health_log.agent_id = ctx.accounts.agent_profile.key();
+        let last_check = health_log
+            .checks
+            .iter()
+            .filter(|check| check.checker == checker)
+            .map(|check| check.checked_at)
+            .max();
+        if let Some(last_check) = last_check {
+            require!(
+                clock.unix_timestamp >= last_check.saturating_add(MIN_HEALTH_CHECK_INTERVAL),
+                ErrorCode::HealthCheckTooSoon
+            );
+        }
+
+        let check = HealthCheck {
+            checker,
+            healthy,
+            latency_ms,
+            checked_at: clock.unix_timestamp,
+        };
+        if health_log.checks.len() < HEALTH_LOG_LEN {
+            health_log.checks.push(check);
+        } else {
+            health_log.checks[health_log.cursor as usize] = check;
+        }
+        health_log.cursor = ((health_log.cursor as usize + 1) % HEALTH_LOG_LEN) as u8;
+
+        let passed = health_log.checks.iter().filter(|check| check.healthy).count();
+        let uptime_bps = (passed * 10_000 / health_log.checks.len()) as u16;
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.uptime_bps = Some(uptime_bps);
+
+        emit!(HealthCheckSubmitted {
+            agent_id: agent_profile.key(),
+            checker,
+            healthy,
+            latency_ms,
+            uptime_bps,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Hand management of an agent to `authority`, e.g. a multisig vault, or
+    /// give it back to the NFT holder with `None`. Signed by the current
+    /// manager: the authority while one is set, otherwise the NFT holder.
+    /// While set, the authority signs `update_agent` in place of the holder
+    /// and co-signs `deregister_agent`; transferring ownership clears it
+    pub fn set_agent_authority(
+        ctx: Context<SetAgentAuthority>,
+        authority: Option<Pubkey>,
+    ) -> Result<()> {
+        require_agent_manager(
+            &ctx.accounts.agent_profile,
+            &ctx.accounts.manager,
+            &ctx.accounts.holder_token_account,
+        )?;
+
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.authority = authority;
+
+        emit!(AgentAuthoritySet {
+            agent_id: agent_profile.key(),
+            authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Update an agent profile as the holder of its NFT, or as its authority
+    /// when it has one. A new capability list takes a taxonomy and index
+    /// account per capability, then the index account of each capability
+    /// dropped from the old list. A new name that reads differently moves the
+    /// agent's `NameClaim`, taking the claims of the current and new name.
+    /// Endpoints change through `rotate_endpoints`
+    pub fn update_agent<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateAgent<'info>>,
+        name: Option<String>,
+        description: Option<String>,
+        pricing: Option<PricingModel>,
+        is_active: Option<bool>,
+        capabilities: Option<Vec<String>>,
+    ) -> Result<()> {
+        require_agent_manager(
+            &ctx.accounts.agent_profile,
+            &ctx.accounts.holder,
+            &ctx.accounts.holder_token_account,
+        )?;
+        require!(!ctx.accounts.agent_profile.frozen, ErrorCode::AgentFrozen);
+        if let Some(capabilities) = capabilities {
+            require!(
+                capabilities.len() <= ctx.accounts.agent_profile.capability_limit as usize,
+                ErrorCode::TooManyCapabilities
+            );
+            let agent_id = ctx.accounts.agent_profile.key();
+            let listed = ctx.accounts.agent_profile.capabilities.clone();
+            let holder = ctx.accounts.holder.to_account_info();
+            let (indexed, dropped) = ctx
+                .remaining_accounts
+                .split_at((2 * capabilities.len()).min(ctx.remaining_accounts.len()));
+            index_capabilities(
+                agent_id,
+                &capabilities,
+                &listed,
+                indexed,
+                &holder,
+                &ctx.accounts.system_program.to_account_info(),
+            )?;
+            let removed: Vec<String> = listed
+                .into_iter()
+                .filter(|id| !capabilities.contains(id))
+                .collect();
+            close_capability_indexes(agent_id, &removed, dropped, &holder)?;
+            // Schemas only describe capabilities the agent still lists
+            ctx.accounts
+                .agent_profile
+                .capability_schemas
+                .retain(|schema| capabilities.contains(&schema.capability_id));
+            ctx.accounts.agent_profile.capabilities = capabilities;
+        }
+
+        if let Some(name) = &name {
+            require!(name.len() <= 50, ErrorCode::NameTooLong);
+            require_valid_agent_name(name)?;
+            let current = &ctx.accounts.agent_profile.name;
+            if normalize_agent_name(name) != normalize_agent_name(current) {
+                let (Some(name_claim), Some(new_name_claim)) =
+                    (&ctx.accounts.name_claim, &ctx.accounts.new_name_claim)
+                else {
+                    return err!(ErrorCode::InvalidNameClaim);
+                };
+                let agent_id = ctx.accounts.agent_profile.key();
+                let holder = ctx.accounts.holder.to_account_info();
+                claim_agent_name(
+                    agent_id,
+                    name,
+                    &new_name_claim.to_account_info(),
+                    &holder,
+                    &ctx.accounts.system_program.to_account_info(),
+                )?;
+                release_agent_name(agent_id, &name_claim.to_account_info(), &holder)?;
+            }
+        }
+
+        let agent_profile = &mut ctx.accounts.agent_profile;
+
+        if let Some(name) = name {
+            agent_profile.name = name;
+        }
+        if let Some(description) = description {
+            require!(
+                description.len() <= agent_profile.description_limit as usize,
+                ErrorCode::DescriptionTooLong
+            );
+            agent_profile.description = description;
+        }
+        if let Some(pricing) = pricing {
+            require_accepted_mint(&pricing, ctx.accounts.accepted_mint.as_ref())?;
+            require_valid_surge(&pricing)?;
+            agent_profile.pricing_model = pricing;
+        }
+        if let Some(is_active) = is_active {
+            require!(!is_active || !agent_profile.retired, ErrorCode::AgentRetired);
+            // Reactivating counts as a heartbeat, so a stale agent isn't marked stale again at once
+            if is_active && !agent_profile.is_active {
+                agent_profile.last_seen_at = Clock::get()?.unix_timestamp;
+            }
+            agent_profile.is_active = is_active;
+        }
+
+        emit!(AgentUpdated {
+            agent_id: agent_profile.agent_id,
+            creator: agent_profile.creator,
+            name: agent_profile.name.clone(),
+            is_active: agent_profile.is_active,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Hand the update authority of an agent's NFT metadata to the registry's
+    /// metadata authority PDA, signed by the current update authority. From
+    /// then on the metadata only changes through `update_agent_metadata`, so
+    /// the NFT can no longer be renamed to pass for another agent. Can run in
+    /// the registration transaction to opt new agents in from the start
+    pub fn migrate_metadata_authority(ctx: Context<MigrateMetadataAuthority>) -> Result<()> {
+        let metadata_authority = ctx.accounts.metadata_authority.key();
+        UpdateV1Cpi::new(
+            &ctx.accounts.token_metadata_program.to_account_info(),
+            UpdateV1CpiAccounts {
+                authority: &ctx.accounts.update_authority.to_account_info(),
+                delegate_record: None,
+                token: None,
+                mint: &ctx.accounts.nft_mint.to_account_info(),
+                metadata: &ctx.accounts.metadata.to_account_info(),
+                edition: None,
+                payer: &ctx.accounts.update_authority.to_account_info(),
+                system_program: &ctx.accounts.system_program.to_account_info(),
+                sysvar_instructions: &ctx.accounts.sysvar_instructions.to_account_info(),
+                authorization_rules_program: None,
+                authorization_rules: None,
+            },
+            UpdateV1InstructionArgs {
+                new_update_authority: Some(metadata_authority),
+                data: None,
+                primary_sale_happened: None,
+                is_mutable: None,
+                collection: CollectionToggle::None,
+                collection_details: CollectionDetailsToggle::None,
+                uses: UsesToggle::None,
+                rule_set: RuleSetToggle::None,
+                authorization_data: None,
+            },
+        )
+        .invoke()?;
+
+        emit!(MetadataAuthorityMigrated {
+            agent_id: ctx.accounts.agent_profile.key(),
+            previous_authority: ctx.accounts.update_authority.key(),
+            metadata_authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Update the NFT metadata of an agent whose update authority was migrated
+    /// to the registry (NFT holder). The name is always reset to follow the
+    /// profile name; the symbol and URI change when given
+    pub fn update_agent_metadata(
+        ctx: Context<UpdateAgentMetadata>,
+        symbol: Option<String>,
+        uri: Option<String>,
+    ) -> Result<()> {
+        let metadata_info = ctx.accounts.metadata.to_account_info();
+        let current = Metadata::safe_deserialize(&metadata_info.try_borrow_data()?)
+            .map_err(|_| error!(ErrorCode::InvalidAgentMetadata))?;
+        require_keys_eq!(
+            current.update_authority,
+            ctx.accounts.metadata_authority.key(),
+            ErrorCode::MetadataAuthorityNotMigrated
+        );
+
+        let data = Data {
+            name: format!("AgentMarket: {}", ctx.accounts.agent_profile.name),
+            symbol: symbol.unwrap_or_else(|| current.symbol.trim_end_matches('\0').to_string()),
+            uri: uri.unwrap_or_else(|| current.uri.trim_end_matches('\0').to_string()),
+            seller_fee_basis_points: current.seller_fee_basis_points,
+            creators: current.creators,
+        };
+        let metadata_authority_seeds: &[&[&[u8]]] = &[&[b"metadata_authority", &[ctx.bumps.metadata_authority]]];
+        UpdateV1Cpi::new(
+            &ctx.accounts.token_metadata_program.to_account_info(),
+            UpdateV1CpiAccounts {
+                authority: &ctx.accounts.metadata_authority.to_account_info(),
+                delegate_record: None,
+                token: None,
+                mint: &ctx.accounts.nft_mint.to_account_info(),
+                metadata: &metadata_info,
+                edition: None,
+                payer: &ctx.accounts.holder.to_account_info(),
+                system_program: &ctx.accounts.system_program.to_account_info(),
+                sysvar_instructions: &ctx.accounts.sysvar_instructions.to_account_info(),
+                authorization_rules_program: None,
+                authorization_rules: None,
+            },
+            UpdateV1InstructionArgs {
+                new_update_authority: None,
+                data: Some(data.clone()),
+                primary_sale_happened: None,
+                is_mutable: None,
+                collection: CollectionToggle::None,
+                collection_details: CollectionDetailsToggle::None,
+                uses: UsesToggle::None,
+                rule_set: RuleSetToggle::None,
+                authorization_data: None,
+            },
+        )
+        .invoke_signed(metadata_authority_seeds)?;
+
+        emit!(AgentMetadataUpdated {
+            agent_id: ctx.accounts.agent_profile.key(),
+            name: data.name,
+            symbol: data.symbol,
+            uri: data.uri,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Replace the languages an agent serves as the holder of its NFT. Takes
+    /// the index account of each language in the new list, then the index
+    /// account of each language dropped from the old one
+    pub fn set_languages<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SetLanguages<'info>>,
+        languages: Vec<String>,
+    ) -> Result<()> {
+        require!(languages.len() <= MAX_LANGUAGES, ErrorCode::TooManyLanguages);
+        for (i, language) in languages.iter().enumerate() {
+            require!(is_language_code(language), ErrorCode::InvalidLanguageCode);
+            require!(!languages[..i].contains(language), ErrorCode::DuplicateLanguage);
+        }
+
+        let agent_id = ctx.accounts.agent_profile.key();
+        let holder = ctx.accounts.holder.to_account_info();
+        let (indexed, dropped) = ctx
+            .remaining_accounts
+            .split_at(languages.len().min(ctx.remaining_accounts.len()));
+        index_languages(
+            agent_id,
+            &languages,
+            indexed,
+            &holder,
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        let removed: Vec<String> = ctx
+            .accounts
+            .agent_profile
+            .languages
+            .iter()
+            .filter(|language| !languages.contains(language))
+            .cloned()
+            .collect();
+        close_language_indexes(agent_id, &removed, dropped, &holder)?;
+
+        ctx.accounts.agent_profile.languages = languages.clone();
+
+        emit!(LanguagesSet {
+            agent_id,
+            languages,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Replace the agents this one calls into as the holder of its NFT, so
+    /// pipeline builders can walk the dependency graph. Takes the profile of
+    /// each dependency, in order; every one must be active
+    pub fn set_dependencies<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SetDependencies<'info>>,
+        dependencies: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(dependencies.len() <= MAX_DEPENDENCIES, ErrorCode::TooManyDependencies);
+        require!(
+            ctx.remaining_accounts.len() == dependencies.len(),
+            ErrorCode::DependencyMismatch
+        );
+        let agent_id = ctx.accounts.agent_profile.key();
+        for (index, dependency) in dependencies.iter().enumerate() {
+            require_keys_neq!(*dependency, agent_id, ErrorCode::SelfDependency);
+            require!(!dependencies[..index].contains(dependency), ErrorCode::DuplicateDependency);
+            let profile_info = &ctx.remaining_accounts[index];
+            require_keys_eq!(profile_info.key(), *dependency, ErrorCode::DependencyMismatch);
+            let profile = Account::<AgentProfile>::try_from(profile_info)?;
+            require!(profile.is_active, ErrorCode::DependencyInactive);
+        }
+
+        ctx.accounts.agent_profile.dependencies = dependencies.clone();
+
+        emit!(DependenciesSet {
+            agent_id,
+            dependencies,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Record that the agent is alive (NFT holder). Operators call this more
+    /// often than the registry's heartbeat window to stay listed
+    pub fn heartbeat(ctx: Context<Heartbeat>) -> Result<()> {
+        let clock = Clock::get()?;
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.last_seen_at = clock.unix_timestamp;
+
+        emit!(AgentHeartbeat {
+            agent_id: agent_profile.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Deactivate an agent that has missed its heartbeat window, so buyers
+    /// stop finding it (permissionless). The holder reactivates it through
+    /// `update_agent` once it is back
+    pub fn mark_stale(ctx: Context<MarkStale>) -> Result<()> {
+        let heartbeat_window = ctx.accounts.registry_config.heartbeat_window;
+        require!(heartbeat_window > 0, ErrorCode::HeartbeatsDisabled);
+
+        let clock = Clock::get()?;
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        require!(agent_profile.is_active, ErrorCode::AgentNotStale);
+        // A scheduled pause excuses missed heartbeats until it ends
+        let quiet_since = agent_profile
+            .last_seen_at
+            .max(agent_profile.paused_until.unwrap_or(0));
+        require!(
+            clock.unix_timestamp > quiet_since.saturating_add(heartbeat_window),
+            ErrorCode::AgentNotStale
+        );
+        agent_profile.is_active = false;
+
+        emit!(AgentMarkedStale {
+            agent_id: agent_profile.key(),
+            last_seen_at: agent_profile.last_seen_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Declare how many requests the agent serves at once, zero for no limit
+    /// (NFT holder). The escrow refuses new requests while the agent is at
+    /// capacity; requests already open are unaffected
+    pub fn set_max_concurrent_requests(
+        ctx: Context<SetMaxConcurrentRequests>,
+        max_concurrent_requests: u32,
+    ) -> Result<()> {
+        let agent_id = ctx.accounts.agent_profile.key();
+        let hook_seeds: &[&[&[u8]]] = &[&[b"capacity_hook", &[ctx.bumps.capacity_hook]]];
+        marketplace_escrow::cpi::set_request_capacity(
+            CpiContext::new_with_signer(
+                ctx.accounts.escrow_program.to_account_info(),
+                marketplace_escrow::cpi::accounts::SetRequestCapacity {
+                    agent_requests: ctx.accounts.agent_requests.to_account_info(),
+                    capacity_hook: ctx.accounts.capacity_hook.to_account_info(),
+                    payer: ctx.accounts.holder.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    guardian_config: ctx.accounts.guardian_config.to_account_info(),
+                },
+                hook_seeds,
+            ),
+            agent_id,
+            max_concurrent_requests,
+        )?;
+
+        ctx.accounts.agent_profile.max_concurrent_requests = max_concurrent_requests;
+
+        emit!(AgentCapacityUpdated {
+            agent_id,
+            max_concurrent_requests,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Take the agent's listings offline until `paused_until` (NFT holder).
+    /// The escrow refuses new requests during the pause and accepts them
+    /// again once it ends, without a further instruction
+    pub fn pause_listings(ctx: Context<SetListingPause>, paused_until: i64) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            paused_until > clock.unix_timestamp,
+            ErrorCode::InvalidPauseWindow
+        );
+
+        set_request_pause(&ctx, paused_until)?;
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.paused_until = Some(paused_until);
+
+        emit!(AgentListingsPaused {
+            agent_id: agent_profile.key(),
+            paused_until,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// End a scheduled pause early (NFT holder)
+    pub fn resume_listings(ctx: Context<SetListingPause>) -> Result<()> {
+        require!(
+            ctx.accounts.agent_profile.paused_until.is_some(),
+            ErrorCode::ListingsNotPaused
+        );
+
+        set_request_pause(&ctx, 0)?;
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.paused_until = None;
+
+        emit!(AgentListingsResumed {
+            agent_id: agent_profile.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Open the agent to every user, or only to those on its allowlist (NFT
+    /// holder). The escrow enforces it when requests are created
+    pub fn set_visibility(ctx: Context<SetVisibility>, visibility: Visibility) -> Result<()> {
+        let hook_seeds: &[&[&[u8]]] = &[&[b"capacity_hook", &[ctx.bumps.capacity_hook]]];
+        marketplace_escrow::cpi::set_request_visibility(
+            CpiContext::new_with_signer(
+                ctx.accounts.escrow_program.to_account_info(),
+                marketplace_escrow::cpi::accounts::SetRequestVisibility {
+                    agent_requests: ctx.accounts.agent_requests.to_account_info(),
+                    capacity_hook: ctx.accounts.capacity_hook.to_account_info(),
+                    payer: ctx.accounts.holder.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    guardian_config: ctx.accounts.guardian_config.to_account_info(),
+                },
+                hook_seeds,
+            ),
+            ctx.accounts.agent_profile.key(),
+            visibility == Visibility::Allowlist,
+        )?;
+
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.visibility = visibility;
+
+        emit!(AgentVisibilitySet {
+            agent_id: agent_profile.key(),
+            visibility,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Let each wallet make its first `trial_requests` requests of the agent
+    /// for free (NFT holder); zero ends the trial. Mirrored to the escrow,
+    /// which skips the deposit while a wallet's trial counter is below it
+    pub fn set_trial_requests(ctx: Context<SetTrialRequests>, trial_requests: u8) -> Result<()> {
+        let hook_seeds: &[&[&[u8]]] = &[&[b"capacity_hook", &[ctx.bumps.capacity_hook]]];
+        marketplace_escrow::cpi::set_request_trials(
+            CpiContext::new_with_signer(
+                ctx.accounts.escrow_program.to_account_info(),
+                marketplace_escrow::cpi::accounts::SetRequestTrials {
+                    agent_requests: ctx.accounts.agent_requests.to_account_info(),
+                    capacity_hook: ctx.accounts.capacity_hook.to_account_info(),
+                    payer: ctx.accounts.holder.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    guardian_config: ctx.accounts.guardian_config.to_account_info(),
+                },
+                hook_seeds,
+            ),
+            ctx.accounts.agent_profile.key(),
+            trial_requests,
+        )?;
+
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.trial_requests = trial_requests;
+
+        emit!(TrialRequestsSet {
+            agent_id: agent_profile.key(),
+            trial_requests,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Let `user` hire the agent while it is allowlist-only (NFT holder)
+    pub fn add_allowlist_entry(ctx: Context<AddAllowlistEntry>, user: Pubkey) -> Result<()> {
+        let clock = Clock::get()?;
+        let allowlist_entry = &mut ctx.accounts.allowlist_entry;
+        allowlist_entry.agent_id = ctx.accounts.agent_profile.key();
+        allowlist_entry.user = user;
+        allowlist_entry.added_at = clock.unix_timestamp;
+
+        emit!(AllowlistEntryAdded {
+            agent_id: allowlist_entry.agent_id,
+            user,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Take a user off the agent's allowlist, refunding the entry's rent (NFT
+    /// holder). Requests the user already opened are unaffected
+    pub fn remove_allowlist_entry(ctx: Context<RemoveAllowlistEntry>) -> Result<()> {
+        emit!(AllowlistEntryRemoved {
+            agent_id: ctx.accounts.allowlist_entry.agent_id,
+            user: ctx.accounts.allowlist_entry.user,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Refuse service to `user` (NFT holder). The escrow rejects new requests
+    /// from a blocked user; requests already open are unaffected
+    pub fn block_user(ctx: Context<BlockUser>, user: Pubkey) -> Result<()> {
+        let clock = Clock::get()?;
+        let block_entry = &mut ctx.accounts.block_entry;
+        block_entry.agent_id = ctx.accounts.agent_profile.key();
+        block_entry.user = user;
+        block_entry.blocked_at = clock.unix_timestamp;
+
+        emit!(UserBlocked {
+            agent_id: block_entry.agent_id,
+            user,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Serve a blocked user again, refunding the entry's rent (NFT holder)
+    pub fn unblock_user(ctx: Context<UnblockUser>) -> Result<()> {
+        emit!(UserUnblocked {
+            agent_id: ctx.accounts.block_entry.agent_id,
+            user: ctx.accounts.block_entry.user,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Make the current holder of the agent NFT the profile's creator, so
+    /// escrow payouts and creator-gated integrations follow the NFT. Payouts
+    /// go to the new owner until they set another payout wallet
+    pub fn transfer_ownership(ctx: Context<TransferOwnership>) -> Result<()> {
+        let new_owner = ctx.accounts.holder.key();
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        require_keys_neq!(agent_profile.creator, new_owner, ErrorCode::AlreadyOwner);
+
+        let previous_owner = agent_profile.creator;
+        agent_profile.creator = new_owner;
+        agent_profile.payout_wallet = new_owner;
+        agent_profile.authority = None;
+
+        emit!(AgentOwnershipTransferred {
+            agent_id: agent_profile.agent_id,
+            previous_owner,
+            new_owner,
+            nft_mint: agent_profile.nft_mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Offer an agent for `price` lamports (NFT holder). The NFT moves into
+    /// an escrow account held by the sale until it is bought or the listing
+    /// is cancelled; the agent's authority co-signs while one is set.
+    /// Soulbound and programmable NFTs stay frozen and can't be listed
+    pub fn list_agent_for_sale(ctx: Context<ListAgentForSale>, price: u64) -> Result<()> {
+        require!(price > 0, ErrorCode::InvalidSalePrice);
+        if let Some(authority) = ctx.accounts.agent_profile.authority {
+            require!(
+                ctx.accounts
+                    .authority
+                    .as_ref()
+                    .is_some_and(|signer| signer.key() == authority),
+                ErrorCode::NotAgentAuthority
+            );
+        }
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.seller_token_account.to_account_info(),
+                    to: ctx.accounts.sale_token_account.to_account_info(),
+                    authority: ctx.accounts.seller.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+
+        let clock = Clock::get()?;
+        let agent_sale = &mut ctx.accounts.agent_sale;
+        agent_sale.agent_id = ctx.accounts.agent_profile.key();
+        agent_sale.seller = ctx.accounts.seller.key();
+        agent_sale.nft_mint = ctx.accounts.nft_mint.key();
+        agent_sale.price = price;
+        agent_sale.listed_at = clock.unix_timestamp;
+
+        emit!(AgentListedForSale {
+            agent_id: agent_sale.agent_id,
+            seller: agent_sale.seller,
+            nft_mint: agent_sale.nft_mint,
+            price,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Take an agent off sale, returning its NFT and the rent to the seller
+    pub fn cancel_agent_sale(ctx: Context<CancelAgentSale>) -> Result<()> {
+        release_sale_nft(
+            &ctx.accounts.agent_sale,
+            ctx.bumps.agent_sale,
+            &ctx.accounts.sale_token_account,
+            &ctx.accounts.seller_token_account,
+            ctx.accounts.seller.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
+
+        emit!(AgentSaleCancelled {
+            agent_id: ctx.accounts.agent_sale.agent_id,
+            seller: ctx.accounts.seller.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Buy a listed agent at its asking `price`, which guards against the
+    /// listing changing under the buyer. The seller is paid, the NFT moves to
+    /// the buyer, and the profile, with its reputation, follows it: the buyer
+    /// becomes the creator and payout wallet and any authority is cleared.
+    /// Requests still open on the agent pay out to the buyer
+    pub fn buy_agent(ctx: Context<BuyAgent>, price: u64) -> Result<()> {
+        require!(
+            ctx.accounts.agent_sale.price == price,
+            ErrorCode::SalePriceMismatch
+        );
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.buyer.to_account_info(),
+                    to: ctx.accounts.seller.to_account_info(),
+                },
+            ),
+            price,
+        )?;
+        release_sale_nft(
+            &ctx.accounts.agent_sale,
+            ctx.bumps.agent_sale,
+            &ctx.accounts.sale_token_account,
+            &ctx.accounts.buyer_token_account,
+            ctx.accounts.seller.to_account_info(),
+            &ctx.accounts.token_program,
+        )?;
+
+        let buyer = ctx.accounts.buyer.key();
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        let previous_owner = agent_profile.creator;
+        agent_profile.creator = buyer;
+        agent_profile.payout_wallet = buyer;
+        agent_profile.authority = None;
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        emit!(AgentSold {
+            agent_id: agent_profile.agent_id,
+            seller: ctx.accounts.seller.key(),
+            buyer,
+            price,
+            timestamp,
+        });
+        emit!(AgentOwnershipTransferred {
+            agent_id: agent_profile.agent_id,
+            previous_owner,
+            new_owner: buyer,
+            nft_mint: agent_profile.nft_mint,
+            timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Feature an agent for `days` more days (NFT holder). The fee is paid
+    /// through the royalty splitter, whose creator share goes to the platform
+    /// wallet, and the agent is listed in the featured index until it lapses
+    pub fn promote_agent(ctx: Context<PromoteAgent>, days: u32) -> Result<()> {
+        require!(
+            days > 0 && days <= MAX_PROMOTION_DAYS,
+            ErrorCode::InvalidPromotionDays
+        );
+        let price_per_day = ctx.accounts.registry_config.featured_price_per_day;
+        require!(price_per_day > 0, ErrorCode::PromotionsDisabled);
+        let amount = price_per_day
+            .checked_mul(days as u64)
+            .ok_or(ErrorCode::PromotionFeeOverflow)?;
+
+        let platform_wallet = ctx.accounts.platform_account.key();
+        royalty_splitter::cpi::distribute_payment(
+            CpiContext::new(
+                ctx.accounts.royalty_program.to_account_info(),
+                royalty_splitter::cpi::accounts::DistributePayment {
+                    royalty_config: ctx.accounts.royalty_config.to_account_info(),
+                    distribution_record: ctx.accounts.distribution_record.to_account_info(),
+                    source_account: ctx.accounts.holder.to_account_info(),
+                    creator_account: ctx.accounts.platform_account.to_account_info(),
+                    platform_account: ctx.accounts.platform_account.to_account_info(),
+                    treasury_account: ctx.accounts.treasury_account.to_account_info(),
+                    payer: ctx.accounts.holder.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    guardian_config: ctx.accounts.guardian_config.to_account_info(),
+                },
+            ),
+            amount,
+            platform_wallet,
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        // Extend a running promotion rather than restarting it
+        let featured_until = agent_profile
+            .featured_until
+            .max(now)
+            .saturating_add(days as i64 * 86_400);
+        agent_profile.featured_until = featured_until;
+
+        let featured_index = &mut ctx.accounts.featured_index;
+        featured_index.entries.retain(|entry| entry.featured_until > now);
+        match featured_index
+            .entries
+            .iter_mut()
+            .find(|entry| entry.agent_id == agent_profile.agent_id)
+        {
+            Some(entry) => entry.featured_until = featured_until,
+            None => {
+                require!(
+                    featured_index.entries.len() < MAX_FEATURED_AGENTS,
+                    ErrorCode::FeaturedIndexFull
+                );
+                featured_index.entries.push(FeaturedEntry {
+                    agent_id: agent_profile.agent_id,
+                    featured_until,
+                });
+            }
+        }
+
+        emit!(AgentPromoted {
+            agent_id: agent_profile.agent_id,
+            holder: ctx.accounts.holder.key(),
+            days,
+            amount,
+            featured_until,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Issue a fresh nonce the agent's endpoints must sign to prove control
+    /// of them (NFT holder). Replaces any challenge still outstanding
+    pub fn request_endpoint_challenge(ctx: Context<RequestEndpointChallenge>) -> Result<()> {
+        let agent_profile = &ctx.accounts.agent_profile;
+        require!(!agent_profile.endpoints.is_empty(), ErrorCode::NoEndpoint);
+
+        let clock = Clock::get()?;
+        let nonce = hashv(&[
+            agent_profile.key().as_ref(),
+            agent_profile.endpoints[0].url.as_bytes(),
+            &clock.slot.to_le_bytes(),
+            &clock.unix_timestamp.to_le_bytes(),
+        ])
+        .to_bytes();
+
+        let challenge = &mut ctx.accounts.endpoint_challenge;
+        challenge.agent_id = agent_profile.key();
+        challenge.nonce = nonce;
+        challenge.endpoints = agent_profile.endpoints.clone();
+        challenge.issued_at = clock.unix_timestamp;
+
+        emit!(EndpointChallengeIssued {
+            agent_id: agent_profile.key(),
+            endpoints: agent_profile.endpoints.clone(),
+            nonce,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Answer the outstanding endpoint challenge (NFT holder). An ed25519
+    /// program instruction placed immediately before this one must verify
+    /// `endpoint_key`'s signature over [`endpoint_challenge_message`];
+    /// `endpoint_key` is the key every listed endpoint publishes, which
+    /// clients check against the endpoints themselves
+    pub fn attest_endpoint(ctx: Context<AttestEndpoint>, endpoint_key: Pubkey) -> Result<()> {
+        let challenge = &ctx.accounts.endpoint_challenge;
+        let clock = Clock::get()?;
+        require!(
+            clock.unix_timestamp - challenge.issued_at <= ENDPOINT_CHALLENGE_TTL,
+            ErrorCode::EndpointChallengeExpired
+        );
+        require!(
+            challenge.endpoints == ctx.accounts.agent_profile.endpoints,
+            ErrorCode::EndpointChallengeExpired
+        );
+
+        verify_signature(
+            &ctx.accounts.instructions.to_account_info(),
+            &endpoint_key,
+            &endpoint_challenge_message(&challenge.agent_id, &challenge.nonce),
+            ErrorCode::MissingEndpointSignature,
+            ErrorCode::InvalidEndpointSignature,
+        )?;
+
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.endpoint_verified = true;
+
+        emit!(EndpointVerified {
+            agent_id: agent_profile.agent_id,
+            endpoints: agent_profile.endpoints.clone(),
+            endpoint_key,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Replace the agent's endpoints (NFT holder). Orchestrators try them in
+    /// ascending priority and fail over down the list; a changed list has to
+    /// prove control again
+    pub fn rotate_endpoints(ctx: Context<RotateEndpoints>, endpoints: Vec<AgentEndpoint>) -> Result<()> {
+        let endpoints = validate_endpoints(endpoints)?;
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        if endpoints != agent_profile.endpoints {
+            agent_profile.endpoint_verified = false;
+        }
+        agent_profile.endpoints = endpoints;
+
+        emit!(EndpointsRotated {
+            agent_id: agent_profile.agent_id,
+            endpoints: agent_profile.endpoints.clone(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Send the agent's escrow and royalty payouts to `payout_wallet` instead
+    /// of the creator's wallet (NFT holder)
+    pub fn set_payout_wallet(ctx: Context<SetPayoutWallet>, payout_wallet: Pubkey) -> Result<()> {
+        require_keys_neq!(payout_wallet, Pubkey::default(), ErrorCode::InvalidPayoutWallet);
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.payout_wallet = payout_wallet;
+
+        emit!(PayoutWalletSet {
+            agent_id: agent_profile.agent_id,
+            payout_wallet,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Grow the profile account so the agent can list more capabilities or a
+    /// longer description than a new profile allows (NFT holder, who pays the
+    /// extra rent). Limits only grow, so nothing stored is ever cut off
+    pub fn resize_profile(
+        ctx: Context<ResizeProfile>,
+        capability_limit: u8,
+        description_limit: u16,
+    ) -> Result<()> {
+        require!(
+            capability_limit <= MAX_CAPABILITY_LIMIT && description_limit <= MAX_DESCRIPTION_LIMIT,
+            ErrorCode::ProfileLimitTooLarge
+        );
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        require!(
+            capability_limit >= agent_profile.capability_limit
+                && description_limit >= agent_profile.description_limit,
+            ErrorCode::ProfileLimitShrunk
+        );
+        agent_profile.capability_limit = capability_limit;
+        agent_profile.description_limit = description_limit;
+
+        emit!(ProfileResized {
+            agent_id: agent_profile.agent_id,
+            capability_limit,
+            description_limit,
+            size: profile_space(capability_limit, description_limit) as u32,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// List capabilities past the profile's limit in the agent's
+    /// `AgentCapabilities` account, created on first use (NFT holder, or the
+    /// agent's authority when it has one, who pays the rent). The account
+    /// grows with each id, up to `MAX_AGENT_CAPABILITIES`. Ids must be in the
+    /// taxonomy and are indexed for discovery like the profile's own;
+    /// remaining accounts are the taxonomy and index account of each, in order
+    pub fn add_agent_capabilities<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AddAgentCapabilities<'info>>,
+        capabilities: Vec<String>,
+    ) -> Result<()> {
+        require_agent_manager(
+            &ctx.accounts.agent_profile,
+            &ctx.accounts.holder,
+            &ctx.accounts.holder_token_account,
+        )?;
+        require!(!ctx.accounts.agent_profile.frozen, ErrorCode::AgentFrozen);
+
+        let agent_id = ctx.accounts.agent_profile.key();
+        let agent_capabilities = &mut ctx.accounts.agent_capabilities;
+        agent_capabilities.agent_id = agent_id;
+        for id in &capabilities {
+            require!(
+                !id.is_empty() && id.len() <= MAX_CAPABILITY_ID_LEN,
+                ErrorCode::CapabilityIdTooLong
+            );
+            // Both lists share one discovery index per id
+            require!(
+                !agent_capabilities.capabilities.contains(id)
+                    && !ctx.accounts.agent_profile.capabilities.contains(id),
+                ErrorCode::DuplicateCapability
+            );
+            agent_capabilities.capabilities.push(id.clone());
+        }
+        require!(
+            agent_capabilities.capabilities.len() <= MAX_AGENT_CAPABILITIES,
+            ErrorCode::TooManyCapabilities
+        );
+        index_capabilities(
+            agent_id,
+            &capabilities,
+            &[],
+            ctx.remaining_accounts,
+            &ctx.accounts.holder.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+        fit_agent_capabilities(
+            &ctx.accounts.agent_capabilities,
+            &ctx.accounts.holder,
+            &ctx.accounts.system_program,
+        )?;
+
+        emit!(AgentCapabilitiesAdded {
+            agent_id,
+            count: ctx.accounts.agent_capabilities.capabilities.len() as u16,
+            capabilities,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Drop capabilities from the agent's `AgentCapabilities` account (NFT
+    /// holder, or the agent's authority when it has one). Their discovery
+    /// indexes close and the account shrinks, refunding the freed rent to the
+    /// signer; remaining accounts are the index account of each, in order
+    pub fn remove_agent_capabilities<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RemoveAgentCapabilities<'info>>,
+        capabilities: Vec<String>,
+    ) -> Result<()> {
+        require_agent_manager(
+            &ctx.accounts.agent_profile,
+            &ctx.accounts.holder,
+            &ctx.accounts.holder_token_account,
+        )?;
+        require!(!ctx.accounts.agent_profile.frozen, ErrorCode::AgentFrozen);
+
+        let agent_capabilities = &mut ctx.accounts.agent_capabilities;
+        for id in &capabilities {
+            let position = agent_capabilities
+                .capabilities
+                .iter()
+                .position(|listed| listed == id)
+                .ok_or(ErrorCode::CapabilityNotListed)?;
+            agent_capabilities.capabilities.remove(position);
+        }
+        let agent_id = agent_capabilities.agent_id;
+        close_capability_indexes(
+            agent_id,
+            &capabilities,
+            ctx.remaining_accounts,
+            &ctx.accounts.holder.to_account_info(),
+        )?;
+        fit_agent_capabilities(
+            &ctx.accounts.agent_capabilities,
+            &ctx.accounts.holder,
+            &ctx.accounts.system_program,
+        )?;
+
+        emit!(AgentCapabilitiesRemoved {
+            agent_id,
+            count: ctx.accounts.agent_capabilities.capabilities.len() as u16,
+            capabilities,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Bring one of an agent's accounts back to rent exemption, e.g. after a
+    /// reallocation or a rent change left it short (permissionless). The
+    /// funder pays exactly the shortfall; the event credits them so the
+    /// creator knows who kept the account alive
+    pub fn top_up_rent(ctx: Context<TopUpRent>, target: RentTarget) -> Result<()> {
+        let agent_id = ctx.accounts.agent_profile.key();
+        let (expected, owner) = match target {
+            RentTarget::Profile => (agent_id, crate::ID),
+            RentTarget::Capabilities => (
+                Pubkey::find_program_address(
+                    &[b"agent_capabilities", agent_id.as_ref()],
+                    &crate::ID,
+                )
+                .0,
+                crate::ID,
+            ),
+            RentTarget::HealthLog => (
+                Pubkey::find_program_address(&[b"health_log", agent_id.as_ref()], &crate::ID).0,
+                crate::ID,
+            ),
+            RentTarget::Analytics => (
+                Pubkey::find_program_address(
+                    &[b"agent_analytics", agent_id.as_ref()],
+                    &protocol_analytics::ID,
+                )
+                .0,
+                protocol_analytics::ID,
+            ),
+        };
+        let account = &ctx.accounts.account;
+        require_keys_eq!(account.key(), expected, ErrorCode::InvalidRentTarget);
+        require_keys_eq!(*account.owner, owner, ErrorCode::InvalidRentTarget);
+
+        let amount = Rent::get()?
+            .minimum_balance(account.data_len())
+            .saturating_sub(account.lamports());
+        require!(amount > 0, ErrorCode::RentAlreadyExempt);
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.funder.to_account_info(),
+                    to: account.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        emit!(RentToppedUp {
+            agent_id,
+            account: account.key(),
+            target,
+            funder: ctx.accounts.funder.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Record a new release of the agent (NFT holder). Each semver gets its
+    /// own `AgentVersion` PDA, so a version can never be rewritten, and the
+    /// profile's `ipfs_hash` moves to the new release
+    pub fn publish_agent_version(
+        ctx: Context<PublishAgentVersion>,
+        major: u16,
+        minor: u16,
+        patch: u16,
+        ipfs_hash: String,
+        changelog_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(ipfs_hash.len() <= 100, ErrorCode::IpfsHashTooLong);
+
+        let clock = Clock::get()?;
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.ipfs_hash = ipfs_hash.clone();
+
+        let agent_version = &mut ctx.accounts.agent_version;
+        agent_version.agent_id = agent_profile.agent_id;
+        agent_version.major = major;
+        agent_version.minor = minor;
+        agent_version.patch = patch;
+        agent_version.ipfs_hash = ipfs_hash.clone();
+        agent_version.changelog_hash = changelog_hash;
+        agent_version.publisher = ctx.accounts.holder.key();
+        agent_version.published_at = clock.unix_timestamp;
+
+        emit!(AgentVersionPublished {
+            agent_id: agent_profile.agent_id,
+            agent_version: agent_version.key(),
+            major,
+            minor,
+            patch,
+            ipfs_hash,
+            changelog_hash,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Add a service to the agent's catalog, priced on its own (NFT holder).
+    /// Requests select an offering after they are created
+    pub fn add_service_offering(
+        ctx: Context<AddServiceOffering>,
+        offering_id: u16,
+        name: String,
+        description: String,
+        price: u64,
+        turnaround_secs: u32,
+    ) -> Result<()> {
+        require!(name.len() <= 50, ErrorCode::NameTooLong);
+        require!(description.len() <= 200, ErrorCode::OfferingDescriptionTooLong);
+        require!(price > 0, ErrorCode::InvalidOfferingPrice);
+
+        let clock = Clock::get()?;
+        let offering = &mut ctx.accounts.service_offering;
+        offering.agent_id = ctx.accounts.agent_profile.key();
+        offering.offering_id = offering_id;
+        offering.price = price;
+        offering.turnaround_secs = turnaround_secs;
+        offering.is_active = true;
+        offering.created_at = clock.unix_timestamp;
+        offering.updated_at = clock.unix_timestamp;
+        offering.name = name.clone();
+        offering.description = description;
+
+        emit!(ServiceOfferingAdded {
+            agent_id: offering.agent_id,
+            service_offering: offering.key(),
+            offering_id,
+            name,
+            price,
+            turnaround_secs,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Change an offering's details, price, or availability (NFT holder).
+    /// Requests that already selected it keep the amount they escrowed
+    pub fn update_service_offering(
+        ctx: Context<UpdateServiceOffering>,
+        name: Option<String>,
+        description: Option<String>,
+        price: Option<u64>,
+        turnaround_secs: Option<u32>,
+        is_active: Option<bool>,
+    ) -> Result<()> {
+        let offering = &mut ctx.accounts.service_offering;
+        if let Some(name) = name {
+            require!(name.len() <= 50, ErrorCode::NameTooLong);
+            offering.name = name;
+        }
+        if let Some(description) = description {
+            require!(description.len() <= 200, ErrorCode::OfferingDescriptionTooLong);
+            offering.description = description;
+        }
+        if let Some(price) = price {
+            require!(price > 0, ErrorCode::InvalidOfferingPrice);
+            offering.price = price;
+        }
+        if let Some(turnaround_secs) = turnaround_secs {
+            offering.turnaround_secs = turnaround_secs;
+        }
+        if let Some(is_active) = is_active {
+            offering.is_active = is_active;
+        }
+        let clock = Clock::get()?;
+        offering.updated_at = clock.unix_timestamp;
+
+        emit!(ServiceOfferingUpdated {
+            agent_id: offering.agent_id,
+            service_offering: offering.key(),
+            price: offering.price,
+            turnaround_secs: offering.turnaround_secs,
+            is_active: offering.is_active,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Remove an offering from the catalog, returning its rent (NFT holder).
+    /// Offerings should be removed before the agent is deregistered
+    pub fn remove_service_offering(ctx: Context<RemoveServiceOffering>) -> Result<()> {
+        emit!(ServiceOfferingRemoved {
+            agent_id: ctx.accounts.agent_profile.key(),
+            service_offering: ctx.accounts.service_offering.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// List several agents together at a combined price with a discount.
+    /// `remaining_accounts` holds each agent's profile, in order, then each
+    /// agent's creator, who must sign to agree to the discount
+    pub fn create_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateBundle<'info>>,
+        bundle_id: u16,
+        name: String,
+        agents: Vec<Pubkey>,
+        price: u64,
+        discount_bps: u16,
+    ) -> Result<()> {
+        require!(name.len() <= 50, ErrorCode::NameTooLong);
+        require!(
+            (MIN_BUNDLE_AGENTS..=MAX_BUNDLE_AGENTS).contains(&agents.len()),
+            ErrorCode::InvalidBundleSize
+        );
+        require!(price > 0, ErrorCode::InvalidOfferingPrice);
+        require!(discount_bps < 10_000, ErrorCode::InvalidBundleDiscount);
+        require!(
+            ctx.remaining_accounts.len() == 2 * agents.len(),
+            ErrorCode::BundleAgentMismatch
+        );
+        let (profiles, creators) = ctx.remaining_accounts.split_at(agents.len());
+        for (index, agent_id) in agents.iter().enumerate() {
+            require!(!agents[..index].contains(agent_id), ErrorCode::DuplicateBundleAgent);
+            require_keys_eq!(profiles[index].key(), *agent_id, ErrorCode::BundleAgentMismatch);
+            let profile = Account::<AgentProfile>::try_from(&profiles[index])?;
+            require!(profile.is_active, ErrorCode::BundleAgentInactive);
+            require!(
+                creators[index].key() == profile.creator && creators[index].is_signer,
+                ErrorCode::BundleAgentNotApproved
+            );
+        }
+        let curator = ctx.accounts.curator.key();
+
+        let clock = Clock::get()?;
+        let bundle = &mut ctx.accounts.bundle;
+        bundle.curator = curator;
+        bundle.bundle_id = bundle_id;
+        bundle.price = price;
+        bundle.discount_bps = discount_bps;
+        bundle.is_active = true;
+        bundle.created_at = clock.unix_timestamp;
+        bundle.updated_at = clock.unix_timestamp;
+        bundle.agents = agents.clone();
+        bundle.name = name.clone();
+
+        emit!(BundleCreated {
+            bundle: bundle.key(),
+            curator,
+            bundle_id,
+            name,
+            agents,
+            price,
+            discount_bps,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Change a bundle's name, price, discount, or availability (curator).
+    /// Requests already placed through it keep the amount they escrowed
+    pub fn update_bundle(
+        ctx: Context<UpdateBundle>,
+        name: Option<String>,
+        price: Option<u64>,
+        discount_bps: Option<u16>,
+        is_active: Option<bool>,
+    ) -> Result<()> {
+        let bundle = &mut ctx.accounts.bundle;
+        if let Some(name) = name {
+            require!(name.len() <= 50, ErrorCode::NameTooLong);
+            bundle.name = name;
+        }
+        if let Some(price) = price {
+            require!(price > 0, ErrorCode::InvalidOfferingPrice);
+            bundle.price = price;
+        }
+        if let Some(discount_bps) = discount_bps {
+            require!(discount_bps < 10_000, ErrorCode::InvalidBundleDiscount);
+            bundle.discount_bps = discount_bps;
+        }
+        if let Some(is_active) = is_active {
+            bundle.is_active = is_active;
+        }
+        let clock = Clock::get()?;
+        bundle.updated_at = clock.unix_timestamp;
+
+        emit!(BundleUpdated {
+            bundle: bundle.key(),
+            curator: bundle.curator,
+            price: bundle.price,
+            discount_bps: bundle.discount_bps,
+            is_active: bundle.is_active,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Add a subscription tier to a subscription-priced agent (NFT holder).
+    /// `features_hash` commits to the off-chain list of what the tier includes
+    pub fn create_subscription_tier(
+        ctx: Context<CreateSubscriptionTier>,
+        tier_id: u16,
+        name: String,
+        monthly_price: u64,
+        requests_per_month: u32,
+        features_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(
+            matches!(ctx.accounts.agent_profile.pricing_model, PricingModel::Subscription { .. }),
+            ErrorCode::NotSubscriptionPriced
+        );
+        require!(name.len() <= 32, ErrorCode::NameTooLong);
+        require!(monthly_price > 0, ErrorCode::InvalidOfferingPrice);
+        require!(requests_per_month > 0, ErrorCode::InvalidTierQuota);
+
+        let clock = Clock::get()?;
+        let tier = &mut ctx.accounts.subscription_tier;
+        tier.agent_id = ctx.accounts.agent_profile.key();
+        tier.tier_id = tier_id;
+        tier.monthly_price = monthly_price;
+        tier.requests_per_month = requests_per_month;
+        tier.is_active = true;
+        tier.features_hash = features_hash;
+        tier.created_at = clock.unix_timestamp;
+        tier.retired_at = 0;
+        tier.name = name.clone();
+
+        emit!(SubscriptionTierCreated {
+            agent_id: tier.agent_id,
+            subscription_tier: tier.key(),
+            tier_id,
+            name,
+            monthly_price,
+            requests_per_month,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Retire a tier so no new subscriptions start on it (NFT holder). The
+    /// account stays so running subscriptions keep their quota
+    pub fn retire_subscription_tier(ctx: Context<RetireSubscriptionTier>) -> Result<()> {
+        let clock = Clock::get()?;
+        let tier = &mut ctx.accounts.subscription_tier;
+        require!(tier.is_active, ErrorCode::TierRetired);
+        tier.is_active = false;
+        tier.retired_at = clock.unix_timestamp;
+
+        emit!(SubscriptionTierRetired {
+            agent_id: tier.agent_id,
+            subscription_tier: tier.key(),
+            tier_id: tier.tier_id,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Buy `months` of access on one of the agent's tiers (user). The price is
+    /// paid through the royalty splitter to the agent's payout wallet, and the
+    /// user's `AccessGrant` records the expiry and the requests left, so the
+    /// agent can check a caller's entitlement from chain state alone. Buying
+    /// again while the grant is valid extends it on the same tier
+    pub fn issue_access_grant(ctx: Context<IssueAccessGrant>, months: u32) -> Result<()> {
+        require!(
+            months > 0 && months <= MAX_ACCESS_MONTHS,
+            ErrorCode::InvalidAccessTerm
+        );
+        let tier = &ctx.accounts.subscription_tier;
+        let amount = tier
+            .monthly_price
+            .checked_mul(months as u64)
+            .ok_or(ErrorCode::AccessPriceOverflow)?;
+        let quota = tier
+            .requests_per_month
+            .checked_mul(months)
+            .ok_or(ErrorCode::AccessPriceOverflow)?;
+
+        royalty_splitter::cpi::distribute_payment(
+            CpiContext::new(
+                ctx.accounts.royalty_program.to_account_info(),
+                royalty_splitter::cpi::accounts::DistributePayment {
+                    royalty_config: ctx.accounts.royalty_config.to_account_info(),
+                    distribution_record: ctx.accounts.distribution_record.to_account_info(),
+                    source_account: ctx.accounts.user.to_account_info(),
+                    creator_account: ctx.accounts.creator_account.to_account_info(),
+                    platform_account: ctx.accounts.platform_account.to_account_info(),
+                    treasury_account: ctx.accounts.treasury_account.to_account_info(),
+                    payer: ctx.accounts.user.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    guardian_config: ctx.accounts.guardian_config.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.creator_account.key(),
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let term = months as i64 * ACCESS_MONTH_SECS;
+        let tier_key = ctx.accounts.subscription_tier.key();
+        let grant = &mut ctx.accounts.access_grant;
+        if grant.is_valid(now) {
+            require_keys_eq!(grant.subscription_tier, tier_key, ErrorCode::AccessTierMismatch);
+            grant.expires_at = grant.expires_at.saturating_add(term);
+            grant.remaining_quota = grant.remaining_quota.saturating_add(quota);
+        } else {
+            grant.agent_id = ctx.accounts.agent_profile.key();
+            grant.user = ctx.accounts.user.key();
+            grant.subscription_tier = tier_key;
+            grant.issued_at = now;
+            grant.expires_at = now.saturating_add(term);
+            grant.remaining_quota = quota;
+            grant.is_revoked = false;
+        }
+
+        emit!(AccessGrantIssued {
+            agent_id: grant.agent_id,
+            user: grant.user,
+            subscription_tier: tier_key,
+            months,
+            amount,
+            expires_at: grant.expires_at,
+            remaining_quota: grant.remaining_quota,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Draw `units` requests from a user's access grant as the agent serves
+    /// them (NFT holder). Fails once the grant is expired, revoked, or spent
+    pub fn consume_access(ctx: Context<ConsumeAccess>, units: u32) -> Result<()> {
+        require!(units > 0, ErrorCode::InvalidAccessUnits);
+        let now = Clock::get()?.unix_timestamp;
+        let grant = &mut ctx.accounts.access_grant;
+        require!(grant.is_valid(now), ErrorCode::AccessGrantInactive);
+        require!(
+            grant.remaining_quota >= units,
+            ErrorCode::AccessQuotaExceeded
+        );
+        grant.remaining_quota -= units;
+
+        emit!(AccessConsumed {
+            agent_id: grant.agent_id,
+            user: grant.user,
+            units,
+            remaining_quota: grant.remaining_quota,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Cut off a user's access before it expires (NFT holder), e.g. for abuse.
+    /// The purchase is not refunded
+    pub fn revoke_access_grant(ctx: Context<RevokeAccessGrant>) -> Result<()> {
+        let grant = &mut ctx.accounts.access_grant;
+        require!(!grant.is_revoked, ErrorCode::AccessGrantInactive);
+        grant.is_revoked = true;
+
+        emit!(AccessGrantRevoked {
+            agent_id: grant.agent_id,
+            user: grant.user,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Declare the input and output formats of one of the agent's listed
+    /// capabilities as IPFS CIDs of their schemas (NFT holder), so clients can
+    /// check that one agent's output feeds another's input
+    pub fn set_capability_schema(
+        ctx: Context<SetCapabilitySchema>,
+        capability_id: String,
+        input_schema: String,
+        output_schema: String,
+    ) -> Result<()> {
+        require!(
+            !input_schema.is_empty() && input_schema.len() <= MAX_SCHEMA_CID_LEN,
+            ErrorCode::InvalidSchemaCid
+        );
+        require!(
+            !output_schema.is_empty() && output_schema.len() <= MAX_SCHEMA_CID_LEN,
+            ErrorCode::InvalidSchemaCid
+        );
+
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        require!(
+            agent_profile.capabilities.contains(&capability_id),
+            ErrorCode::CapabilityNotListed
+        );
+        let schema = CapabilitySchema {
+            capability_id: capability_id.clone(),
+            input_schema: input_schema.clone(),
+            output_schema: output_schema.clone(),
+        };
+        match agent_profile
+            .capability_schemas
+            .iter_mut()
+            .find(|existing| existing.capability_id == capability_id)
+        {
+            Some(existing) => *existing = schema,
+            None => agent_profile.capability_schemas.push(schema),
+        }
+
+        emit!(CapabilitySchemaSet {
+            agent_id: agent_profile.key(),
+            capability_id,
+            input_schema,
+            output_schema,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw the declared formats of one of the agent's capabilities (NFT holder)
+    pub fn clear_capability_schema(
+        ctx: Context<SetCapabilitySchema>,
+        capability_id: String,
+    ) -> Result<()> {
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        let before = agent_profile.capability_schemas.len();
+        agent_profile
+            .capability_schemas
+            .retain(|schema| schema.capability_id != capability_id);
+        require!(
+            agent_profile.capability_schemas.len() < before,
+            ErrorCode::CapabilitySchemaNotSet
+        );
+
+        emit!(CapabilitySchemaCleared {
+            agent_id: agent_profile.key(),
+            capability_id,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Price one of the agent's capabilities on its own, overriding the
+    /// agent's pricing for requests that select it (NFT holder). Setting it
+    /// again changes the price; requests that already selected it keep the
+    /// amount they escrowed
+    pub fn set_capability_price(
+        ctx: Context<SetCapabilityPrice>,
+        capability_id: String,
+        price: u64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.agent_profile.capabilities.contains(&capability_id),
+            ErrorCode::CapabilityNotListed
+        );
+        require!(price > 0, ErrorCode::InvalidOfferingPrice);
+
+        let clock = Clock::get()?;
+        let capability_price = &mut ctx.accounts.capability_price;
+        capability_price.agent_id = ctx.accounts.agent_profile.key();
+        capability_price.price = price;
+        capability_price.updated_at = clock.unix_timestamp;
+        capability_price.capability_id = capability_id.clone();
+
+        emit!(CapabilityPriceSet {
+            agent_id: capability_price.agent_id,
+            capability_price: capability_price.key(),
+            capability_id,
+            price,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Drop a capability's price override, returning its rent (NFT holder).
+    /// Overrides should be removed before their capability is delisted
+    pub fn remove_capability_price(
+        ctx: Context<RemoveCapabilityPrice>,
+        capability_id: String,
+    ) -> Result<()> {
+        emit!(CapabilityPriceRemoved {
+            agent_id: ctx.accounts.agent_profile.key(),
+            capability_price: ctx.accounts.capability_price.key(),
+            capability_id,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Replace the co-creators who share the agent's creator payouts (NFT
+    /// holder). Each takes `bps` of the creator share of every approved request;
+    /// the holder's wallet keeps the rest. An empty list ends the split
+    pub fn set_co_creators(ctx: Context<SetCoCreators>, co_creators: Vec<CoCreator>) -> Result<()> {
+        require!(co_creators.len() <= MAX_CO_CREATORS, ErrorCode::TooManyCoCreators);
+        let mut total_bps: u32 = 0;
+        for (index, co_creator) in co_creators.iter().enumerate() {
+            require!(co_creator.bps > 0, ErrorCode::InvalidCoCreatorShares);
+            require!(
+                co_creators[..index].iter().all(|other| other.key != co_creator.key),
+                ErrorCode::DuplicateCoCreator
+            );
+            total_bps += co_creator.bps as u32;
+        }
+        require!(total_bps <= 10_000, ErrorCode::InvalidCoCreatorShares);
+
+        let split = &mut ctx.accounts.co_creator_split;
+        split.agent_id = ctx.accounts.agent_profile.key();
+        split.co_creators = co_creators.clone();
+
+        emit!(CoCreatorsUpdated {
+            agent_id: split.agent_id,
+            co_creators,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Resolve a USD-pegged agent's price to lamports against its Pyth SOL/USD
+    /// feed. Requesters run this ahead of `create_service_request` in the same
+    /// transaction and escrow the returned amount
+    pub fn resolve_usd_price(ctx: Context<ResolveUsdPrice>) -> Result<u64> {
+        let agent_profile = &ctx.accounts.agent_profile;
+        let PricingModel::UsdPegged { usd_cents, feed } = agent_profile.pricing_model else {
+            return err!(ErrorCode::NotUsdPegged);
+        };
+        require_keys_eq!(ctx.accounts.price_feed.key(), feed, ErrorCode::InvalidPriceFeed);
+
+        let clock = Clock::get()?;
+        let (price, expo) = read_pyth_price(&ctx.accounts.price_feed, clock.unix_timestamp)?;
+        let lamports = usd_cents_to_lamports(usd_cents, price, expo)?;
+
+        emit!(UsdPriceResolved {
+            agent_id: agent_profile.key(),
+            usd_cents,
+            price,
+            expo,
+            lamports,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(lamports)
+    }
+
+    /// Resolve a surge-priced agent's price to lamports at its current open
+    /// request count. Requesters run this ahead of `create_service_request`
+    /// in the same transaction and escrow the returned amount
+    pub fn resolve_surge_price(ctx: Context<ResolveSurgePrice>) -> Result<u64> {
+        let agent_profile = &ctx.accounts.agent_profile;
+        let PricingModel::Surge { base, min, max, target_open, step_bps } = agent_profile.pricing_model else {
+            return err!(ErrorCode::NotSurgePriced);
+        };
+
+        let open_requests = open_request_count(&ctx.accounts.agent_requests)?;
+        let lamports = surge_lamports(base, min, max, target_open, step_bps, open_requests);
+
+        emit!(SurgePriceResolved {
+            agent_id: agent_profile.key(),
+            open_requests,
+            lamports,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(lamports)
+    }
+
+    /// Return the agent's fixed-size profile fields plus what clients would
+    /// otherwise derive from them (view function), so CPI callers and light
+    /// clients need not decode the account layout. The description,
+    /// capabilities, endpoints, and schemas are only counted, since with
+    /// them the profile outgrows the transaction's return data; read those
+    /// from the account. USD-pegged agents have no effective price here; it
+    /// takes `resolve_usd_price` and the agent's feed
+    pub fn get_agent_profile(ctx: Context<GetAgentProfile>) -> Result<AgentProfileView> {
+        let profile = &ctx.accounts.agent_profile;
+        let open_requests = open_request_count(&ctx.accounts.agent_requests)?;
+        let effective_price = match profile.pricing_model {
+            PricingModel::PerQuery { price, .. } => Some(price),
+            PricingModel::Subscription { monthly, .. } => Some(monthly),
+            PricingModel::Custom { base, .. } => Some(base),
+            PricingModel::UsdPegged { .. } => None,
+            PricingModel::Surge { base, min, max, target_open, step_bps } => {
+                Some(surge_lamports(base, min, max, target_open, step_bps, open_requests))
+            }
+        };
+        let now = Clock::get()?.unix_timestamp;
+        let accepting_requests = profile.is_active
+            && !profile.retired
+            && !profile.frozen
+            && profile.paused_until.unwrap_or(0) <= now;
+
+        let view = AgentProfileView {
+            effective_price,
+            payment_mint: profile.pricing_model.payment_mint(),
+            open_requests,
+            accepting_requests,
+            verified: profile.is_verified && profile.endpoint_verified,
+            profile: AgentProfileSummary {
+                agent_id: profile.agent_id,
+                creator: profile.creator,
+                payout_wallet: profile.payout_wallet,
+                name: profile.name.clone(),
+                pricing_model: profile.pricing_model.clone(),
+                ipfs_hash: profile.ipfs_hash.clone(),
+                reputation_score: profile.reputation_score,
+                total_services: profile.total_services,
+                total_earnings: profile.total_earnings,
+                created_at: profile.created_at,
+                is_active: profile.is_active,
+                nft_mint: profile.nft_mint,
+                endpoint_verified: profile.endpoint_verified,
+                featured_until: profile.featured_until,
+                is_verified: profile.is_verified,
+                last_seen_at: profile.last_seen_at,
+                max_concurrent_requests: profile.max_concurrent_requests,
+                paused_until: profile.paused_until,
+                parent_agent: profile.parent_agent,
+                visibility: profile.visibility,
+                trial_requests: profile.trial_requests,
+                soulbound: profile.soulbound,
+                retired: profile.retired,
+                successor: profile.successor,
+                authority: profile.authority,
+                frozen: profile.frozen,
+                version: profile.version,
+                uptime_bps: profile.uptime_bps,
+                description_len: profile.description.len() as u16,
+                capability_count: profile.capabilities.len() as u8,
+                endpoint_count: profile.endpoints.len() as u8,
+                capability_schema_count: profile.capability_schemas.len() as u8,
+            },
+        };
+
+        Ok(view)
+    }
+
+    /// Mirror an agent's average rating onto its profile. Only the
+    /// reputation system can call this, signing with its reputation hook
+    /// whenever a rating is submitted or moderated. `total_services` counts
+    /// escrow payouts, not ratings, so it is left alone
+    pub fn update_reputation(ctx: Context<UpdateReputation>, new_rating: u32) -> Result<()> {
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.reputation_score = new_rating;
+
+        Ok(())
+    }
+
+    /// Credit an agent with the creator share of an approved request. Only
+    /// the escrow program can call this, signing with its payout hook when
+    /// it releases a payment
+    pub fn record_earnings(ctx: Context<RecordEarnings>, amount: u64) -> Result<()> {
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.total_earnings = agent_profile.total_earnings.saturating_add(amount);
+        agent_profile.total_services = agent_profile.total_services.saturating_add(1);
+
+        Ok(())
+    }
+
+    /// Take an agent off the market for good while keeping its profile and
+    /// history (NFT holder). The agent is deactivated and can't be
+    /// reactivated; new requests are refused by the escrow, which records
+    /// `successor` so frontends can send users to the replacement. A successor
+    /// must be another active, unretired agent, passed as `successor_profile`
+    pub fn retire_agent(ctx: Context<RetireAgent>, successor: Option<Pubkey>) -> Result<()> {
+        let agent_id = ctx.accounts.agent_profile.key();
+        require!(!ctx.accounts.agent_profile.retired, ErrorCode::AgentRetired);
+        if let Some(successor) = successor {
+            require_keys_neq!(successor, agent_id, ErrorCode::InvalidSuccessor);
+            let successor_profile = ctx
+                .accounts
+                .successor_profile
+                .as_ref()
+                .ok_or(ErrorCode::InvalidSuccessor)?;
+            require_keys_eq!(successor_profile.key(), successor, ErrorCode::InvalidSuccessor);
+            require!(
+                successor_profile.is_active && !successor_profile.retired,
+                ErrorCode::InvalidSuccessor
+            );
+        }
+
+        let hook_seeds: &[&[&[u8]]] = &[&[b"capacity_hook", &[ctx.bumps.capacity_hook]]];
+        marketplace_escrow::cpi::retire_requests(
+            CpiContext::new_with_signer(
+                ctx.accounts.escrow_program.to_account_info(),
+                marketplace_escrow::cpi::accounts::RetireRequests {
+                    agent_requests: ctx.accounts.agent_requests.to_account_info(),
+                    capacity_hook: ctx.accounts.capacity_hook.to_account_info(),
+                    payer: ctx.accounts.holder.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    guardian_config: ctx.accounts.guardian_config.to_account_info(),
+                },
+                hook_seeds,
+            ),
+            agent_id,
+            successor,
+        )?;
+
+        let agent_profile = &mut ctx.accounts.agent_profile;
+        agent_profile.is_active = false;
+        agent_profile.retired = true;
+        agent_profile.successor = successor;
+
+        emit!(AgentRetired {
+            agent_id,
+            successor,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Retire an agent: burn its NFT, close the holder's token account and the
+    /// profile, and refund the rent to the holder. Refused while the escrow
+    /// program still holds requests against the agent
+    pub fn deregister_agent<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DeregisterAgent<'info>>,
+    ) -> Result<()> {
+        require!(
+            open_request_count(&ctx.accounts.agent_requests)? == 0,
+            ErrorCode::AgentHasOpenRequests
+        );
+        if let Some(authority) = ctx.accounts.agent_profile.authority {
+            require!(
+                ctx.accounts
+                    .authority
+                    .as_ref()
+                    .is_some_and(|signer| signer.key() == authority),
+                ErrorCode::NotAgentAuthority
+            );
+        }
+
+        let token_program = ctx.accounts.token_program.to_account_info();
+        // Soulbound NFTs are thawed so they can be burned
+        let collection_authority = ctx.accounts.collection_authority.key();
+        if ctx.accounts.holder_token_account.is_frozen()
+            && ctx.accounts.nft_mint.freeze_authority == COption::Some(collection_authority)
+        {
+            let collection_authority_seeds: &[&[&[u8]]] =
+                &[&[b"collection_authority", &[ctx.bumps.collection_authority]]];
+            token::thaw_account(CpiContext::new_with_signer(
+                token_program.clone(),
+                token::ThawAccount {
+                    account: ctx.accounts.holder_token_account.to_account_info(),
+                    mint: ctx.accounts.nft_mint.to_account_info(),
+                    authority: ctx.accounts.collection_authority.to_account_info(),
+                },
+                collection_authority_seeds,
+            ))?;
+        }
+        token::burn(
+            CpiContext::new(
+                token_program.clone(),
+                token::Burn {
+                    mint: ctx.accounts.nft_mint.to_account_info(),
+                    from: ctx.accounts.holder_token_account.to_account_info(),
+                    authority: ctx.accounts.holder.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+        token::close_account(CpiContext::new(
+            token_program,
+            token::CloseAccount {
+                account: ctx.accounts.holder_token_account.to_account_info(),
+                destination: ctx.accounts.holder.to_account_info(),
+                authority: ctx.accounts.holder.to_account_info(),
+            },
+        ))?;
+
+        // Agents registered before listing stakes have none to return
+        let listing_stake = ctx.accounts.listing_stake.to_account_info();
+        if *listing_stake.owner == crate::ID {
+            close_program_account(&listing_stake, &ctx.accounts.holder.to_account_info())?;
+        }
+
+        // Extra capabilities are removed beforehand, closing their indexes
+        let agent_capabilities = ctx.accounts.agent_capabilities.to_account_info();
+        if *agent_capabilities.owner == crate::ID {
+            let listed = AgentCapabilities::try_deserialize(
+                &mut &agent_capabilities.try_borrow_data()?[..],
+            )?;
+            require!(
+                listed.capabilities.is_empty(),
+                ErrorCode::AgentCapabilitiesNotEmpty
+            );
+            close_program_account(&agent_capabilities, &ctx.accounts.holder.to_account_info())?;
+        }
+        release_agent_name(
+            ctx.accounts.agent_profile.key(),
+            &ctx.accounts.name_claim.to_account_info(),
+            &ctx.accounts.holder.to_account_info(),
+        )?;
+
+        // Capability indexes come first, then language indexes
+        let agent_profile = &ctx.accounts.agent_profile;
+        let capability_indexes = agent_profile
+            .capabilities
+            .iter()
+            .filter(|id| id.len() <= MAX_CAPABILITY_ID_LEN)
+            .count();
+        let (capability_accounts, language_accounts) = ctx
+            .remaining_accounts
+            .split_at(capability_indexes.min(ctx.remaining_accounts.len()));
+        close_capability_indexes(
+            agent_profile.key(),
+            &agent_profile.capabilities,
+            capability_accounts,
+            &ctx.accounts.holder.to_account_info(),
+        )?;
+        close_language_indexes(
+            agent_profile.key(),
+            &agent_profile.languages,
+            language_accounts,
+            &ctx.accounts.holder.to_account_info(),
+        )?;
+
+        emit!(AgentDeregistered {
+            agent_id: agent_profile.agent_id,
+            creator: agent_profile.creator,
+            nft_mint: agent_profile.nft_mint,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Upgrade a profile written under an older layout to `PROFILE_VERSION`,
+    /// growing the account to the current size and filling in fields the old
+    /// layout lacked. Fields are only ever appended, so an old profile reads
+    /// as the current one with zeroed trailing fields. Anyone can run it,
+    /// paying for the extra rent
+    pub fn migrate_profile(ctx: Context<MigrateProfile>) -> Result<()> {
+        let target = &ctx.accounts.agent_profile;
+        let profile: AgentProfile =
+            read_upgraded(target, AgentProfile::INIT_SPACE, ErrorCode::MigrationTargetMismatch)?;
+        require!(profile.version < PROFILE_VERSION, ErrorCode::ProfileUpToDate);
+
+        let (from_version, space) = rewrite_profile(
+            target,
+            profile,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )?;
+
+        emit!(ProfileMigrated {
+            agent_id: target.key(),
+            from_version,
+            version: PROFILE_VERSION,
+            size: space as u32,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Upgrade an agent profile to the current layout during a schema
+    /// migration (guardian authority, protocol paused), the same way
+    /// `migrate_profile` does; every field the old layout held is kept
+    pub fn migrate_agent_profile(ctx: Context<MigrateAgentProfile>) -> Result<()> {
+        let target = &ctx.accounts.agent_profile;
+        let profile: AgentProfile =
+            read_upgraded(target, AgentProfile::INIT_SPACE, ErrorCode::MigrationTargetMismatch)?;
+        require!(
+            profile.version < PROFILE_VERSION
+                || target.data_len()
+                    < profile_space(profile.capability_limit, profile.description_limit),
+            ErrorCode::ProfileUpToDate
+        );
+
+        let (_, space) = rewrite_profile(
+            target,
+            profile,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+        )?;
+
+        emit!(AccountMigrated {
+            account: target.key(),
+            size: space as u32,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// Helper function to push the agent's pause window to the escrow program
+fn set_request_pause(ctx: &Context<SetListingPause>, paused_until: i64) -> Result<()> {
+    let hook_seeds: &[&[&[u8]]] = &[&[b"capacity_hook", &[ctx.bumps.capacity_hook]]];
+    marketplace_escrow::cpi::set_request_pause(
+        CpiContext::new_with_signer(
+            ctx.accounts.escrow_program.to_account_info(),
+            marketplace_escrow::cpi::accounts::SetRequestPause {
+                agent_requests: ctx.accounts.agent_requests.to_account_info(),
+                capacity_hook: ctx.accounts.capacity_hook.to_account_info(),
+                payer: ctx.accounts.holder.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                guardian_config: ctx.accounts.guardian_config.to_account_info(),
+            },
+            hook_seeds,
+        ),
+        ctx.accounts.agent_profile.key(),
+        paused_until,
+    )
+}
+
+// Helper function to move a listed agent's NFT out of its sale escrow and
+// close the escrow token account, refunding its rent to the seller
+fn release_sale_nft<'info>(
+    agent_sale: &Account<'info, AgentSale>,
+    bump: u8,
+    sale_token_account: &Account<'info, TokenAccount>,
+    recipient_token_account: &Account<'info, TokenAccount>,
+    seller: AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+) -> Result<()> {
+    let sale_seeds: &[&[&[u8]]] = &[&[b"agent_sale", agent_sale.agent_id.as_ref(), &[bump]]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            token::Transfer {
+                from: sale_token_account.to_account_info(),
+                to: recipient_token_account.to_account_info(),
+                authority: agent_sale.to_account_info(),
+            },
+            sale_seeds,
+        ),
+        1,
+    )?;
+    token::close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        token::CloseAccount {
+            account: sale_token_account.to_account_info(),
+            destination: seller,
+            authority: agent_sale.to_account_info(),
+        },
+        sale_seeds,
+    ))
+}
+
+// Helper function to push the agent's freeze to the escrow program
+fn set_request_freeze(ctx: &Context<SetAgentFreeze>, frozen: bool) -> Result<()> {
+    let hook_seeds: &[&[&[u8]]] = &[&[b"capacity_hook", &[ctx.bumps.capacity_hook]]];
+    marketplace_escrow::cpi::set_request_freeze(
+        CpiContext::new_with_signer(
+            ctx.accounts.escrow_program.to_account_info(),
+            marketplace_escrow::cpi::accounts::SetRequestFreeze {
+                agent_requests: ctx.accounts.agent_requests.to_account_info(),
+                capacity_hook: ctx.accounts.capacity_hook.to_account_info(),
+                payer: ctx.accounts.slash_authority.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                guardian_config: ctx.accounts.guardian_config.to_account_info(),
+            },
+            hook_seeds,
+        ),
+        ctx.accounts.agent_profile.key(),
+        frozen,
+    )
+}
+
+// Helper function to require that `signer` manages the agent: its authority
+// while one is set, otherwise the holder of its NFT in `holder_token_account`
+fn require_agent_manager(
+    profile: &AgentProfile,
+    signer: &Signer,
+    holder_token_account: &UncheckedAccount,
+) -> Result<()> {
+    if let Some(authority) = profile.authority {
+        require_keys_eq!(signer.key(), authority, ErrorCode::NotAgentAuthority);
+        return Ok(());
+    }
+    require_keys_eq!(
+        *holder_token_account.owner,
+        token::ID,
+        ErrorCode::AgentNftNotHeld
+    );
+    let token_account =
+        TokenAccount::try_deserialize(&mut &holder_token_account.try_borrow_data()?[..])
+            .map_err(|_| error!(ErrorCode::AgentNftNotHeld))?;
+    require!(
+        token_account.mint == profile.nft_mint
+            && token_account.owner == signer.key()
+            && token_account.amount == 1,
+        ErrorCode::AgentNftNotHeld
+    );
+    Ok(())
+}
+
+// Helper function to read the escrow program's open request count for an agent, which is absent until its first request
+fn open_request_count(agent_requests: &UncheckedAccount) -> Result<u32> {
+    if agent_requests.data_is_empty() {
+        return Ok(0);
+    }
+    require_keys_eq!(
+        *agent_requests.owner,
+        marketplace_escrow::ID,
+        anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram
+    );
+    let data = agent_requests.try_borrow_data()?;
+    Ok(AgentRequestCount::try_deserialize(&mut &data[..])?.open_requests)
+}
+
+// Helper function to price a surge-priced agent at `open_requests`: each
+// request above the target adds `step_bps` of the base; each one below takes it off
+fn surge_lamports(base: u64, min: u64, max: u64, target_open: u32, step_bps: u16, open_requests: u32) -> u64 {
+    let steps = i128::from(open_requests) - i128::from(target_open);
+    let adjustment = i128::from(base) * steps * i128::from(step_bps) / 10_000;
+    (i128::from(base) + adjustment).clamp(i128::from(min), i128::from(max)) as u64
+}
+
+// Helper function to require that pricing in an SPL token comes with that token's `AcceptedMint` account
+fn require_accepted_mint(pricing: &PricingModel, accepted_mint: Option<&Account<AcceptedMint>>) -> Result<()> {
+    if let Some(payment_mint) = pricing.payment_mint() {
+        let accepted_mint = accepted_mint.ok_or(ErrorCode::PaymentMintNotAccepted)?;
+        require_keys_eq!(accepted_mint.mint, payment_mint, ErrorCode::PaymentMintNotAccepted);
+    }
+    Ok(())
+}
+
+// Helper function to read a live aggregate price and exponent from a Pyth v2 price account
+fn read_pyth_price(price_feed: &UncheckedAccount, now: i64) -> Result<(i64, i32)> {
+    require!(PYTH_ORACLE_IDS.contains(price_feed.owner), ErrorCode::InvalidPriceFeed);
+    let data = price_feed.try_borrow_data()?;
+    require!(data.len() >= PYTH_PRICE_ACCOUNT_MIN_LEN, ErrorCode::InvalidPriceFeed);
+    let read_u32 = |at: usize| u32::from_le_bytes(data[at..at + 4].try_into().unwrap());
+    let read_i64 = |at: usize| i64::from_le_bytes(data[at..at + 8].try_into().unwrap());
+    require!(
+        read_u32(0) == PYTH_MAGIC && read_u32(8) == PYTH_PRICE_ACCOUNT_TYPE,
+        ErrorCode::InvalidPriceFeed
+    );
+
+    let expo = read_u32(PYTH_EXPO_OFFSET) as i32;
+    let price = read_i64(PYTH_AGG_PRICE_OFFSET);
+    let published_at = read_i64(PYTH_TIMESTAMP_OFFSET);
+    require!(
+        read_u32(PYTH_AGG_STATUS_OFFSET) == PYTH_STATUS_TRADING && price > 0,
+        ErrorCode::PriceUnavailable
+    );
+    require!(now.saturating_sub(published_at) <= MAX_PRICE_AGE_SECS, ErrorCode::StalePrice);
+    Ok((price, expo))
+}
+
+// Helper function to convert a USD amount to lamports at `price * 10^expo` USD per SOL, rounding up
+fn usd_cents_to_lamports(usd_cents: u64, price: i64, expo: i32) -> Result<u64> {
+    // lamports = usd_cents / 100 * 10^9 / (price * 10^expo)
+    let mut numerator = (usd_cents as u128).checked_mul(10u128.pow(7)).ok_or(ErrorCode::UsdPriceOverflow)?;
+    let mut denominator = price as u128;
+    let scale = 10u128.checked_pow(expo.unsigned_abs()).ok_or(ErrorCode::UsdPriceOverflow)?;
+    if expo < 0 {
+        numerator = numerator.checked_mul(scale).ok_or(ErrorCode::UsdPriceOverflow)?;
+    } else {
+        denominator = denominator.checked_mul(scale).ok_or(ErrorCode::UsdPriceOverflow)?;
+    }
+    u64::try_from(numerator.div_ceil(denominator)).map_err(|_| error!(ErrorCode::UsdPriceOverflow))
+}
+
+// Helper function to require that surge pricing keeps its base inside its bounds
+fn require_valid_surge(pricing: &PricingModel) -> Result<()> {
+    if let PricingModel::Surge { base, min, max, .. } = *pricing {
+        require!(min > 0 && min <= base && base <= max, ErrorCode::InvalidSurgeBounds);
+    }
+    Ok(())
+}
+
+/// Check that `accepted_mint` is the registry's `AcceptedMint` account for
+/// `mint`, i.e. that agents may be paid in it. For programs settling SPL
+/// payments against an agent's pricing
+pub fn check_accepted_mint(accepted_mint: &AccountInfo, mint: &Pubkey) -> Result<()> {
+    let (expected, _) = Pubkey::find_program_address(&[b"accepted_mint", mint.as_ref()], &crate::ID);
+    require_keys_eq!(accepted_mint.key(), expected, ErrorCode::PaymentMintNotAccepted);
+    require_keys_eq!(*accepted_mint.owner, crate::ID, ErrorCode::PaymentMintNotAccepted);
+    let data = accepted_mint.try_borrow_data()?;
+    require_keys_eq!(
+        AcceptedMint::try_deserialize(&mut &data[..])?.mint,
+        *mint,
+        ErrorCode::PaymentMintNotAccepted
+    );
+    Ok(())
+}
+
+// Helper function to check a registration fee can fund the fee vault; the
+// vault holds no data, so its first deposit must cover its rent exemption
+fn require_valid_registration_fee(registration_fee: u64) -> Result<()> {
+    require!(
+        registration_fee == 0 || registration_fee >= Rent::get()?.minimum_balance(0),
+        ErrorCode::RegistrationFeeBelowRent
+    );
+    Ok(())
+}
+
+// Helper function to collect the configured registration fee into the fee vault
+fn collect_registration_fee<'info>(
+    fee_vault: &UncheckedAccount<'info>,
+    amount: u64,
+    creator: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    if amount > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: creator.to_account_info(),
+                    to: fee_vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+    }
+    Ok(())
+}
+
+// Helper function to size an agent profile for the given limits. Each
+// capability past the default also reserves room for its schema
+fn profile_space(capability_limit: u8, description_limit: u16) -> usize {
+    let extra_capabilities = (capability_limit as usize).saturating_sub(DEFAULT_CAPABILITY_LIMIT);
+    let extra_description = (description_limit as usize).saturating_sub(DEFAULT_DESCRIPTION_LIMIT);
+    8 + AgentProfile::INIT_SPACE
+        + extra_capabilities * (4 + MAX_CAPABILITY_ID_LEN + CapabilitySchema::INIT_SPACE)
+        + extra_description
+}
+
+// Helper function to size an agent's capabilities account for its list
+fn agent_capabilities_space(capabilities: &[String]) -> usize {
+    8 + 32 + 4 + capabilities.iter().map(|id| 4 + id.len()).sum::<usize>()
+}
+
+// Helper function to fit an agent's capabilities account to its list, with
+// `payer` topping up the rent as it grows and getting it back as it shrinks
+fn fit_agent_capabilities<'info>(
+    agent_capabilities: &Account<'info, AgentCapabilities>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let space = agent_capabilities_space(&agent_capabilities.capabilities);
+    let target = agent_capabilities.to_account_info();
+    if space >= target.data_len() {
+        return grow_account(&target, payer, system_program, space);
+    }
+    target.resize(space)?;
+    let excess = target
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(space));
+    **target.try_borrow_mut_lamports()? -= excess;
+    **payer.to_account_info().try_borrow_mut_lamports()? += excess;
+    Ok(())
+}
+
+// Helper function to check an endpoint list and order it by priority, the
+// order orchestrators fail over in
+fn validate_endpoints(mut endpoints: Vec<AgentEndpoint>) -> Result<Vec<AgentEndpoint>> {
+    require!(endpoints.len() <= MAX_ENDPOINTS, ErrorCode::TooManyEndpoints);
+    for endpoint in &endpoints {
+        require!(!endpoint.url.is_empty(), ErrorCode::EmptyEndpointUrl);
+        require!(endpoint.url.len() <= 200, ErrorCode::EndpointTooLong);
+        require!(endpoint.region.len() <= MAX_REGION_LEN, ErrorCode::RegionTooLong);
+    }
+    endpoints.sort_by_key(|endpoint| endpoint.priority);
+    require!(
+        endpoints.windows(2).all(|pair| pair[0].priority < pair[1].priority),
+        ErrorCode::DuplicateEndpointPriority
+    );
+    Ok(endpoints)
+}
+
+// Helper function to create a new agent's `AgentReputationProfile` through
+// the reputation system, so it can be rated as soon as it is listed. Anyone
+// can create one for any agent id, so one that already exists is kept
+fn init_agent_reputation<'info>(
+    agent_reputation: &UncheckedAccount<'info>,
+    agent_id: Pubkey,
+    creator: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    guardian_config: &Account<'info, GuardianConfig>,
+    reputation_program: &Program<'info, ReputationSystem>,
+) -> Result<()> {
+    if !agent_reputation.data_is_empty() {
+        return Ok(());
+    }
+    reputation_system::cpi::initialize_agent_reputation(
+        CpiContext::new(
+            reputation_program.to_account_info(),
+            reputation_system::cpi::accounts::InitializeAgentReputation {
+                agent_profile: agent_reputation.to_account_info(),
+                creator: creator.to_account_info(),
+                system_program: system_program.to_account_info(),
+                guardian_config: guardian_config.to_account_info(),
+            },
+        ),
+        agent_id,
+    )
+}
+
+// Helper function to give a new agent the next sequential index entry
+fn record_agent_index(
+    agent_index: &mut Account<AgentIndex>,
+    registry_config: &mut Account<RegistryConfig>,
+    agent_id: Pubkey,
+) {
+    agent_index.index = registry_config.agent_count;
+    agent_index.agent_id = agent_id;
+    registry_config.agent_count += 1;
+}
+
+// Helper function to lock the configured listing stake in a new agent's stake account
+fn lock_listing_stake<'info>(
+    listing_stake: &mut Account<'info, ListingStake>,
+    amount: u64,
+    agent_id: Pubkey,
+    creator: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    if amount > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: creator.to_account_info(),
+                    to: listing_stake.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+    }
+
+    let clock = Clock::get()?;
+    listing_stake.agent_id = agent_id;
+    listing_stake.amount = amount;
+    listing_stake.staked_at = clock.unix_timestamp;
+    listing_stake.total_slashed = 0;
+
+    emit!(ListingStaked {
+        agent_id,
+        amount,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+// Helper function to check each capability against its taxonomy account and create its discovery index. `accounts` holds the taxonomy and index account of each capability in order; capabilities in `listed` stay valid once deprecated
+fn index_capabilities<'info>(
+    agent_id: Pubkey,
+    capabilities: &[String],
+    listed: &[String],
+    accounts: &[AccountInfo<'info>],
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    require!(
+        accounts.len() >= 2 * capabilities.len(),
+        ErrorCode::UnknownCapability
+    );
+    for (id, pair) in capabilities.iter().zip(accounts.chunks(2)) {
+        let (taxonomy, index) = (&pair[0], &pair[1]);
+        let (expected, _) = Pubkey::find_program_address(&[b"capability", id.as_bytes()], &crate::ID);
+        require_keys_eq!(taxonomy.key(), expected, ErrorCode::UnknownCapability);
+        require_keys_eq!(*taxonomy.owner, crate::ID, ErrorCode::UnknownCapability);
+        let capability = Capability::try_deserialize(&mut &taxonomy.try_borrow_data()?[..])?;
+        require!(
+            !capability.is_deprecated || listed.contains(id),
+            ErrorCode::CapabilityDeprecated
+        );
+
+        let (expected, bump) = Pubkey::find_program_address(
+            &[b"capability_index", id.as_bytes(), agent_id.as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(index.key(), expected, ErrorCode::InvalidCapabilityIndex);
+        if !index.data_is_empty() {
+            continue;
+        }
+
+        create_index_account(
+            index,
+            &[b"capability_index", id.as_bytes(), agent_id.as_ref(), &[bump]],
+            8 + CapabilityIndex::INIT_SPACE,
+            payer,
+            system_program,
+        )?;
+
+        CapabilityIndex {
+            capability: taxonomy.key(),
+            agent_id,
+            created_at: Clock::get()?.unix_timestamp,
+        }
+        .try_serialize(&mut &mut index.try_borrow_mut_data()?[..])?;
+    }
+    Ok(())
+}
+
+// Helper function to close the discovery index of each capability an agent stops listing; agents registered before indexes existed have none to close
+fn close_capability_indexes<'info>(
+    agent_id: Pubkey,
+    capabilities: &[String],
+    accounts: &[AccountInfo<'info>],
+    destination: &AccountInfo<'info>,
+) -> Result<()> {
+    // Ids too long for the taxonomy predate it and never had an index
+    let indexed = capabilities.iter().filter(|id| id.len() <= MAX_CAPABILITY_ID_LEN);
+    require!(
+        accounts.len() >= indexed.clone().count(),
+        ErrorCode::InvalidCapabilityIndex
+    );
+    for (id, index) in indexed.zip(accounts) {
+        let (expected, _) = Pubkey::find_program_address(
+            &[b"capability_index", id.as_bytes(), agent_id.as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(index.key(), expected, ErrorCode::InvalidCapabilityIndex);
+        if *index.owner == crate::ID {
+            close_program_account(index, destination)?;
+        }
+    }
+    Ok(())
+}
+
+// Helper function to create a discovery index PDA owned by this program. Same
+// steps as Anchor's `init`, so lamports sent to the address beforehand can't block it
+fn create_index_account<'info>(
+    index: &AccountInfo<'info>,
+    seeds: &[&[u8]],
+    space: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let shortfall = Rent::get()?.minimum_balance(space).saturating_sub(index.lamports());
+    if shortfall > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.clone(),
+                    to: index.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    anchor_lang::system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            anchor_lang::system_program::Allocate {
+                account_to_allocate: index.clone(),
+            },
+            &[seeds],
+        ),
+        space as u64,
+    )?;
+    anchor_lang::system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            anchor_lang::system_program::Assign {
+                account_to_assign: index.clone(),
+            },
+            &[seeds],
+        ),
+        &crate::ID,
+    )?;
+    Ok(())
+}
+
+// Helper function to check a language code is `ll` or `ll-RR`
+fn is_language_code(code: &str) -> bool {
+    let bytes = code.as_bytes();
+    match bytes.len() {
+        2 => bytes.iter().all(u8::is_ascii_lowercase),
+        5 => {
+            bytes[..2].iter().all(u8::is_ascii_lowercase)
+                && bytes[2] == b'-'
+                && bytes[3..].iter().all(u8::is_ascii_uppercase)
+        }
+        _ => false,
+    }
+}
+
+// Helper function to create the discovery index of each language an agent serves, keeping any that already exist
+fn index_languages<'info>(
+    agent_id: Pubkey,
+    languages: &[String],
+    accounts: &[AccountInfo<'info>],
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    require!(accounts.len() >= languages.len(), ErrorCode::InvalidLanguageIndex);
+    for (language, index) in languages.iter().zip(accounts) {
+        let (expected, bump) = Pubkey::find_program_address(
+            &[b"language_index", language.as_bytes(), agent_id.as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(index.key(), expected, ErrorCode::InvalidLanguageIndex);
+        if !index.data_is_empty() {
+            continue;
+        }
+
+        create_index_account(
+            index,
+            &[b"language_index", language.as_bytes(), agent_id.as_ref(), &[bump]],
+            8 + LanguageIndex::INIT_SPACE,
+            payer,
+            system_program,
+        )?;
+
+        LanguageIndex {
+            language: language.clone(),
+            agent_id,
+            created_at: Clock::get()?.unix_timestamp,
+        }
+        .try_serialize(&mut &mut index.try_borrow_mut_data()?[..])?;
+    }
+    Ok(())
+}
+
+// Helper function to close the discovery index of each language an agent stops serving
+fn close_language_indexes<'info>(
+    agent_id: Pubkey,
+    languages: &[String],
+    accounts: &[AccountInfo<'info>],
+    destination: &AccountInfo<'info>,
+) -> Result<()> {
+    require!(accounts.len() >= languages.len(), ErrorCode::InvalidLanguageIndex);
+    for (language, index) in languages.iter().zip(accounts) {
+        let (expected, _) = Pubkey::find_program_address(
+            &[b"language_index", language.as_bytes(), agent_id.as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(index.key(), expected, ErrorCode::InvalidLanguageIndex);
+        if *index.owner == crate::ID {
+            close_program_account(index, destination)?;
+        }
+    }
+    Ok(())
+}
+
+// Helper function to close an account this program owns, sending its lamports to `destination`
+fn close_program_account<'info>(account: &AccountInfo<'info>, destination: &AccountInfo<'info>) -> Result<()> {
+    let lamports = account.lamports();
+    **account.try_borrow_mut_lamports()? = 0;
+    **destination.try_borrow_mut_lamports()? += lamports;
+    account.assign(&anchor_lang::system_program::ID);
+    account.resize(0)?;
+    Ok(())
+}
+
+// Helper function to claim an agent's name by creating the `NameClaim` of its normalized form; a name that normalizes the same as one already claimed is refused
+fn claim_agent_name<'info>(
+    agent_id: Pubkey,
+    name: &str,
+    claim: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    require_valid_agent_name(name)?;
+    let seed = agent_name_seed(name);
+    let (expected, bump) = Pubkey::find_program_address(&[b"name", &seed], &crate::ID);
+    require_keys_eq!(claim.key(), expected, ErrorCode::InvalidNameClaim);
+    require!(claim.data_is_empty(), ErrorCode::NameTaken);
+
+    create_index_account(
+        claim,
+        &[b"name", &seed, &[bump]],
+        8 + NameClaim::INIT_SPACE,
+        payer,
+        system_program,
+    )?;
+
+    NameClaim {
+        agent_id,
+        claimed_at: Clock::get()?.unix_timestamp,
+    }
+    .try_serialize(&mut &mut claim.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+// Helper function to check a name can be claimed: printable ASCII only, since letters from other scripts can pass for
+// Latin ones and `normalize_agent_name` would not fold them, with at least one letter or digit to claim it by
+fn require_valid_agent_name(name: &str) -> Result<()> {
+    require!(
+        name.bytes().all(|b| b.is_ascii_graphic() || b == b' '),
+        ErrorCode::AgentNameNotAscii
+    );
+    require!(!normalize_agent_name(name).is_empty(), ErrorCode::InvalidAgentName);
+    Ok(())
+}
+
+// Helper function to release the `NameClaim` an agent holds; agents registered before name claims hold none, and their name may since have gone to another agent
+fn release_agent_name<'info>(
+    agent_id: Pubkey,
+    claim: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+) -> Result<()> {
+    if *claim.owner != crate::ID {
+        return Ok(());
+    }
+    let held = NameClaim::try_deserialize(&mut &claim.try_borrow_data()?[..])?;
+    if held.agent_id == agent_id {
+        close_program_account(claim, destination)?;
+    }
+    Ok(())
+}
+
+/// Form of an agent name that `NameClaim`s are keyed by: its ASCII letters
+/// and digits, lowercased, with the digits and letters easily mistaken for
+/// each other folded together, so "Summar1zer" and "summarizer" are one name.
+/// Registered names are printable ASCII, so only spacing and punctuation
+/// are dropped
+pub fn normalize_agent_name(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .map(|c| match c {
+            '0' => 'o',
+            '1' | 'i' => 'l',
+            _ => c,
+        })
+        .collect()
+}
+
+/// Seed of the `NameClaim` for `name`: the hash of its normalized form,
+/// which can be longer than a seed may be
+pub fn agent_name_seed(name: &str) -> [u8; 32] {
+    hashv(&[normalize_agent_name(name).as_bytes()]).to_bytes()
+}
+
+/// Bytes an endpoint signs to answer a challenge: a domain tag, the agent, and the nonce
+pub fn endpoint_challenge_message(agent_id: &Pubkey, nonce: &[u8; 32]) -> Vec<u8> {
+    [ENDPOINT_CHALLENGE_DOMAIN, agent_id.as_ref(), nonce].concat()
+}
+
+/// Message an operator signs to publish `manifest`, and the bytes
+/// `register_agent_with_manifest` takes
+pub fn manifest_message(manifest: &AgentManifest) -> Vec<u8> {
+    let mut message = MANIFEST_DOMAIN.to_vec();
+    manifest
+        .serialize(&mut message)
+        .expect("serializing to a Vec cannot fail");
+    message
+}
+
+// Helper function to fill in the fields a profile's layout version lacked
+// with what registration would have set. Each step runs once, from the
+// profile's version up to `PROFILE_VERSION`
+fn upgrade_profile(profile: &mut AgentProfile) {
+    if profile.version < 1 {
+        if profile.payout_wallet == Pubkey::default() {
+            profile.payout_wallet = profile.creator;
+        }
+        if profile.capability_limit == 0 {
+            profile.capability_limit = DEFAULT_CAPABILITY_LIMIT as u8;
+        }
+        if profile.description_limit == 0 {
+            profile.description_limit = DEFAULT_DESCRIPTION_LIMIT as u16;
+        }
+        if profile.last_seen_at == 0 {
+            profile.last_seen_at = profile.created_at;
+        }
+    }
+    profile.version = PROFILE_VERSION;
+}
+
+// Helper function to bring a profile read in an older layout up to
+// `PROFILE_VERSION` and write it back, growing the account to fit. Returns the
+// version it was read at and the account's new size
+fn rewrite_profile<'info>(
+    target: &AccountInfo<'info>,
+    mut profile: AgentProfile,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<(u8, usize)> {
+    require_keys_eq!(
+        profile.agent_id,
+        target.key(),
+        ErrorCode::MigrationIdentityMismatch
+    );
+    let from_version = profile.version;
+    upgrade_profile(&mut profile);
+
+    let space = target
+        .data_len()
+        .max(profile_space(profile.capability_limit, profile.description_limit));
+    write_upgraded(target, payer, system_program, space, &profile)?;
+
+    Ok((from_version, space))
+}
+
+#[derive(Accounts)]
+pub struct RegisterAgent<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + AgentProfile::INIT_SPACE,
+        seeds = [b"agent", creator.key().as_ref()],
+        bump
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    /// Frozen only for soulbound agents; see `register_agent_soulbound`
+    #[account(
+        init,
+        payer = creator,
+        mint::decimals = 0,
+        mint::authority = creator,
+        mint::freeze_authority = collection_authority,
+    )]
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = creator,
+        associated_token::mint = mint,
+        associated_token::authority = creator,
+    )]
+    pub token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(mut, seeds = [b"registry_config"], bump)]
+    pub registry_config: Box<Account<'info, RegistryConfig>>,
+
+    /// `AcceptedMint` of the pricing's payment mint, when it names one
+    #[account(seeds = [b"accepted_mint", accepted_mint.mint.as_ref()], bump)]
+    pub accepted_mint: Option<Box<Account<'info, AcceptedMint>>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + ListingStake::INIT_SPACE,
+        seeds = [b"listing_stake", agent_profile.key().as_ref()],
+        bump
+    )]
+    pub listing_stake: Box<Account<'info, ListingStake>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + AgentIndex::INIT_SPACE,
+        seeds = [b"agent_index", registry_config.agent_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub agent_index: Box<Account<'info, AgentIndex>>,
+
+    /// CHECK: `NameClaim` of the agent's normalized name, created in the handler
+    #[account(mut)]
+    pub name_claim: UncheckedAccount<'info>,
+
+    /// CHECK: System-owned PDA collecting registration fees until they are swept
+    #[account(mut, seeds = [b"fee_vault"], bump)]
+    pub fee_vault: UncheckedAccount<'info>,
+
+    /// CHECK: The agent's `AgentReputationProfile`, created by the reputation system
+    #[account(
+        mut,
+        seeds = [b"agent_reputation", agent_profile.key().as_ref()],
+        bump,
+        seeds::program = REPUTATION_SYSTEM_ID
+    )]
+    pub agent_reputation: UncheckedAccount<'info>,
+
+    pub reputation_program: Program<'info, ReputationSystem>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, anchor_spl::associated_token::AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub token_metadata_program: UncheckedAccount<'info>,
+    pub rent: Sysvar<'info, Rent>,
+
+    /// CHECK: PDA that signs as the collection's update authority
+    #[account(mut, seeds = [b"collection_authority"], bump)]
+    pub collection_authority: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"collection_mint"], bump)]
+    pub collection_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Collection metadata, validated by the metadata program
+    pub collection_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Collection master edition, validated by the metadata program
+    pub collection_master_edition: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterAgentSoulbound<'info> {
+    pub register: RegisterAgent<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterAgentWithManifest<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AgentProfile::INIT_SPACE,
+        seeds = [b"agent", creator.key().as_ref()],
+        bump
+    )]
+    pub agent_profile: Box<Account<'info, AgentProfile>>,
+
+    /// Minted by the metadata authority, since the creator does not sign
+    #[account(
+        init,
+        payer = payer,
+        mint::decimals = 0,
+        mint::authority = metadata_authority,
+        mint::freeze_authority = collection_authority,
+    )]
+    pub mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = creator,
+    )]
+    pub token_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Creator the manifest registers the agent for; does not sign
+    pub creator: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"operator", creator.key().as_ref(), operator_key.operator.as_ref()],
+        bump,
+        has_one = creator
+    )]
+    pub operator_key: Box<Account<'info, OperatorKey>>,
+
+    /// Pays for the registration, e.g. the build pipeline's fee payer
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut, seeds = [b"registry_config"], bump)]
+    pub registry_config: Box<Account<'info, RegistryConfig>>,
+
+    /// `AcceptedMint` of the pricing's payment mint, when it names one
+    #[account(seeds = [b"accepted_mint", accepted_mint.mint.as_ref()], bump)]
+    pub accepted_mint: Option<Box<Account<'info, AcceptedMint>>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ListingStake::INIT_SPACE,
+        seeds = [b"listing_stake", agent_profile.key().as_ref()],
+        bump
+    )]
+    pub listing_stake: Box<Account<'info, ListingStake>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AgentIndex::INIT_SPACE,
+        seeds = [b"agent_index", registry_config.agent_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub agent_index: Box<Account<'info, AgentIndex>>,
+
+    /// CHECK: `NameClaim` of the agent's normalized name, created in the handler
+    #[account(mut)]
+    pub name_claim: UncheckedAccount<'info>,
+
+    /// CHECK: System-owned PDA collecting registration fees until they are swept
+    #[account(mut, seeds = [b"fee_vault"], bump)]
+    pub fee_vault: UncheckedAccount<'info>,
+
+    /// CHECK: The agent's `AgentReputationProfile`, created by the reputation system
+    #[account(
+        mut,
+        seeds = [b"agent_reputation", agent_profile.key().as_ref()],
+        bump,
+        seeds::program = REPUTATION_SYSTEM_ID
+    )]
+    pub agent_reputation: UncheckedAccount<'info>,
+
+    pub reputation_program: Program<'info, ReputationSystem>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, anchor_spl::associated_token::AssociatedToken>,
+    /// CHECK: This is not dangerous because we don't read or write from this account
+    pub token_metadata_program: UncheckedAccount<'info>,
+    pub rent: Sysvar<'info, Rent>,
+
+    /// CHECK: PDA that mints the NFT and becomes its metadata's update authority
+    #[account(seeds = [b"metadata_authority"], bump)]
+    pub metadata_authority: UncheckedAccount<'info>,
+
+    /// CHECK: PDA that signs as the collection's update authority
+    #[account(mut, seeds = [b"collection_authority"], bump)]
+    pub collection_authority: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"collection_mint"], bump)]
+    pub collection_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Collection metadata, validated by the metadata program
+    pub collection_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Collection master edition, validated by the metadata program
+    pub collection_master_edition: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, used to read the ed25519 manifest signature
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(operator: Pubkey)]
+pub struct RegisterOperator<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + OperatorKey::INIT_SPACE,
+        seeds = [b"operator", creator.key().as_ref(), operator.as_ref()],
+        bump
+    )]
+    pub operator_key: Account<'info, OperatorKey>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeOperator<'info> {
+    #[account(
+        mut,
+        close = creator,
+        seeds = [b"operator", creator.key().as_ref(), operator_key.operator.as_ref()],
+        bump,
+        has_one = creator
+    )]
+    pub operator_key: Account<'info, OperatorKey>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ForkAgent<'info> {
+    pub register: RegisterAgent<'info>,
+
+    /// Agent the new one derives from
+    #[account(constraint = parent_profile.is_active @ ErrorCode::ParentAgentInactive)]
+    pub parent_profile: Box<Account<'info, AgentProfile>>,
+
+    #[account(
+        init,
+        payer = register.creator,
+        space = 8 + AgentLineage::INIT_SPACE,
+        seeds = [b"lineage", register.agent_profile.key().as_ref()],
+        bump
+    )]
+    pub agent_lineage: Box<Account<'info, AgentLineage>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterAgentCompressed<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + AgentProfile::INIT_SPACE,
+        seeds = [b"agent", creator.key().as_ref()],
+        bump
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(mut, seeds = [b"registry_config"], bump)]
+    pub registry_config: Box<Account<'info, RegistryConfig>>,
+
+    /// `AcceptedMint` of the pricing's payment mint, when it names one
+    #[account(seeds = [b"accepted_mint", accepted_mint.mint.as_ref()], bump)]
+    pub accepted_mint: Option<Box<Account<'info, AcceptedMint>>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + ListingStake::INIT_SPACE,
+        seeds = [b"listing_stake", agent_profile.key().as_ref()],
+        bump
+    )]
+    pub listing_stake: Box<Account<'info, ListingStake>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + AgentIndex::INIT_SPACE,
+        seeds = [b"agent_index", registry_config.agent_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub agent_index: Box<Account<'info, AgentIndex>>,
+
+    /// CHECK: `NameClaim` of the agent's normalized name, created in the handler
+    #[account(mut)]
+    pub name_claim: UncheckedAccount<'info>,
+
+    /// CHECK: System-owned PDA collecting registration fees until they are swept
+    #[account(mut, seeds = [b"fee_vault"], bump)]
+    pub fee_vault: UncheckedAccount<'info>,
+
+    /// CHECK: The agent's `AgentReputationProfile`, created by the reputation system
+    #[account(
+        mut,
+        seeds = [b"agent_reputation", agent_profile.key().as_ref()],
+        bump,
+        seeds::program = REPUTATION_SYSTEM_ID
+    )]
+    pub agent_reputation: UncheckedAccount<'info>,
+
+    pub reputation_program: Program<'info, ReputationSystem>,
+
+    /// CHECK: Bubblegum tree config, created by `initialize_agent_tree`
+    #[account(
+        mut,
+        seeds = [merkle_tree.key().as_ref()],
+        bump,
+        seeds::program = BUBBLEGUM_PROGRAM_ID,
+        owner = BUBBLEGUM_PROGRAM_ID @ ErrorCode::InvalidAgentTree
+    )]
+    pub tree_config: UncheckedAccount<'info>,
+
+    /// CHECK: Concurrent Merkle tree, validated by the compression program
+    #[account(mut, owner = ACCOUNT_COMPRESSION_PROGRAM_ID @ ErrorCode::InvalidAgentTree)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    /// CHECK: PDA that signs as tree creator and collection authority
+    #[account(seeds = [b"collection_authority"], bump)]
+    pub collection_authority: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"collection_mint"], bump)]
+    pub collection_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Collection metadata, validated by the metadata program
+    #[account(mut)]
+    pub collection_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Collection master edition, validated by the metadata program
+    pub collection_master_edition: UncheckedAccount<'info>,
+
+    /// CHECK: Bubblegum's signer for collection CPIs
+    #[account(seeds = [b"collection_cpi"], bump, seeds::program = BUBBLEGUM_PROGRAM_ID)]
+    pub bubblegum_signer: UncheckedAccount<'info>,
+
+    /// CHECK: The SPL noop program
+    #[account(address = NOOP_PROGRAM_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    /// CHECK: The SPL account compression program
+    #[account(address = ACCOUNT_COMPRESSION_PROGRAM_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    /// CHECK: The Metaplex token metadata program
+    #[account(address = mpl_token_metadata::ID)]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    /// CHECK: The Bubblegum program
+    #[account(address = BUBBLEGUM_PROGRAM_ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterAgentProgrammable<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + AgentProfile::INIT_SPACE,
+        seeds = [b"agent", creator.key().as_ref()],
+        bump
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    /// Fresh mint keypair; the metadata program initializes it
+    #[account(mut)]
+    pub mint: Signer<'info>,
+
+    /// CHECK: The creator's associated token account, created by the metadata program
+    #[account(mut, address = get_associated_token_address(&creator.key(), &mint.key()))]
+    pub token_account: UncheckedAccount<'info>,
+
+    /// CHECK: Token record of the creator's token, created by the metadata program
+    #[account(
+        mut,
+        seeds = [
+            b"metadata",
+            mpl_token_metadata::ID.as_ref(),
+            mint.key().as_ref(),
+            b"token_record",
+            token_account.key().as_ref()
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub token_record: UncheckedAccount<'info>,
+
+    /// CHECK: Created by the metadata program
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Created by the metadata program
+    #[account(mut)]
+    pub master_edition: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(mut, seeds = [b"registry_config"], bump)]
+    pub registry_config: Box<Account<'info, RegistryConfig>>,
+
+    /// `AcceptedMint` of the pricing's payment mint, when it names one
+    #[account(seeds = [b"accepted_mint", accepted_mint.mint.as_ref()], bump)]
+    pub accepted_mint: Option<Box<Account<'info, AcceptedMint>>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + ListingStake::INIT_SPACE,
+        seeds = [b"listing_stake", agent_profile.key().as_ref()],
+        bump
+    )]
+    pub listing_stake: Box<Account<'info, ListingStake>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + AgentIndex::INIT_SPACE,
+        seeds = [b"agent_index", registry_config.agent_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub agent_index: Box<Account<'info, AgentIndex>>,
+
+    /// CHECK: `NameClaim` of the agent's normalized name, created in the handler
+    #[account(mut)]
+    pub name_claim: UncheckedAccount<'info>,
+
+    /// CHECK: System-owned PDA collecting registration fees until they are swept
+    #[account(mut, seeds = [b"fee_vault"], bump)]
+    pub fee_vault: UncheckedAccount<'info>,
+
+    /// CHECK: The agent's `AgentReputationProfile`, created by the reputation system
+    #[account(
+        mut,
+        seeds = [b"agent_reputation", agent_profile.key().as_ref()],
+        bump,
+        seeds::program = REPUTATION_SYSTEM_ID
+    )]
+    pub agent_reputation: UncheckedAccount<'info>,
+
+    pub reputation_program: Program<'info, ReputationSystem>,
+
+    /// CHECK: PDA that signs as the collection's update authority
+    #[account(seeds = [b"collection_authority"], bump)]
+    pub collection_authority: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"collection_mint"], bump)]
+    pub collection_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Collection metadata, validated by the metadata program
+    #[account(mut)]
+    pub collection_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Collection master edition, validated by the metadata program
+    pub collection_master_edition: UncheckedAccount<'info>,
+
+    /// CHECK: The royalty-enforcing rule set the pNFT is bound to
+    #[account(address = ROYALTY_RULE_SET)]
+    pub authorization_rules: UncheckedAccount<'info>,
+
+    /// CHECK: The Metaplex token auth rules program
+    #[account(address = TOKEN_AUTH_RULES_PROGRAM_ID)]
+    pub authorization_rules_program: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, required by the metadata program
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+
+    /// CHECK: The Metaplex token metadata program
+    #[account(address = mpl_token_metadata::ID)]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, anchor_spl::associated_token::AssociatedToken>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAgentTree<'info> {
+    /// CHECK: Created by Bubblegum
+    #[account(mut, seeds = [merkle_tree.key().as_ref()], bump, seeds::program = BUBBLEGUM_PROGRAM_ID)]
+    pub tree_config: UncheckedAccount<'info>,
+
+    /// CHECK: Allocated by the client for the compression program, which initializes it
+    #[account(mut, owner = ACCOUNT_COMPRESSION_PROGRAM_ID @ ErrorCode::InvalidAgentTree)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    /// CHECK: PDA that becomes the tree's creator
+    #[account(seeds = [b"collection_authority"], bump)]
+    pub collection_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        has_one = authority @ ErrorCode::UnauthorizedCollectionAdmin
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    /// CHECK: The SPL noop program
+    #[account(address = NOOP_PROGRAM_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+    /// CHECK: The SPL account compression program
+    #[account(address = ACCOUNT_COMPRESSION_PROGRAM_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+    /// CHECK: The Bubblegum program
+    #[account(address = BUBBLEGUM_PROGRAM_ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeCollection<'info> {
+    /// CHECK: PDA that owns the collection NFT and signs for it
+    #[account(seeds = [b"collection_authority"], bump)]
+    pub collection_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"collection_mint"],
+        bump,
+        mint::decimals = 0,
+        mint::authority = collection_authority,
+        mint::freeze_authority = collection_authority,
+    )]
+    pub collection_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = collection_mint,
+        associated_token::authority = collection_authority,
+    )]
+    pub collection_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Created by the metadata program
+    #[account(mut)]
+    pub collection_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: Created by the metadata program
+    #[account(mut)]
+    pub collection_master_edition: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        has_one = authority @ ErrorCode::UnauthorizedCollectionAdmin
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, anchor_spl::associated_token::AssociatedToken>,
+    /// CHECK: The Metaplex token metadata program
+    #[account(address = mpl_token_metadata::ID)]
+    pub token_metadata_program: UncheckedAccount<'info>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Whoever holds the agent NFT controls the profile, whether or not
+/// `transfer_ownership` has caught `creator` up yet
+#[derive(Accounts)]
+pub struct InitializeRegistryConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RegistryConfig::INIT_SPACE,
+        seeds = [b"registry_config"],
+        bump
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        has_one = authority @ ErrorCode::UnauthorizedConfigAdmin
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRegistryConfig<'info> {
+    #[account(mut, seeds = [b"registry_config"], bump)]
+    pub registry_config: Account<'info, RegistryConfig>,
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        has_one = authority @ ErrorCode::UnauthorizedConfigAdmin
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SweepRegistrationFees<'info> {
+    /// CHECK: System-owned PDA holding the collected registration fees
+    #[account(mut, seeds = [b"fee_vault"], bump)]
+    pub fee_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Royalty config, validated by the royalty splitter
+    #[account(mut)]
+    pub royalty_config: UncheckedAccount<'info>,
+
+    /// CHECK: Distribution record the royalty splitter creates
+    #[account(mut)]
+    pub distribution_record: UncheckedAccount<'info>,
+
+    /// CHECK: Royalty platform wallet, validated by the royalty splitter
+    #[account(mut)]
+    pub platform_account: UncheckedAccount<'info>,
+
+    /// CHECK: Royalty treasury wallet, validated by the royalty splitter
+    #[account(mut)]
+    pub treasury_account: UncheckedAccount<'info>,
+
+    /// Pays the distribution record's rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub royalty_program: Program<'info, RoyaltySplitter>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(code: String)]
+pub struct CreateReferralCode<'info> {
+    /// Any agent of the referrer, proving they are an existing creator
+    #[account(seeds = [b"agent", referrer.key().as_ref()], bump)]
+    pub referrer_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        init,
+        payer = referrer,
+        space = 8 + ReferralCode::INIT_SPACE,
+        seeds = [b"referral_code", code.as_bytes()],
+        bump
+    )]
+    pub referral_code: Account<'info, ReferralCode>,
+
+    /// CHECK: System-owned PDA accruing the code's share of registration fees
+    #[account(mut, seeds = [b"referral_vault", referral_code.key().as_ref()], bump)]
+    pub referral_vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterAgentWithReferral<'info> {
+    pub register: RegisterAgent<'info>,
+
+    #[account(
+        mut,
+        constraint = referral_code.referrer != register.creator.key() @ ErrorCode::SelfReferral
+    )]
+    pub referral_code: Box<Account<'info, ReferralCode>>,
+
+    /// CHECK: System-owned PDA accruing the code's share of registration fees
+    #[account(mut, seeds = [b"referral_vault", referral_code.key().as_ref()], bump)]
+    pub referral_vault: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SweepReferralFees<'info> {
+    #[account(has_one = referrer @ ErrorCode::InvalidReferrer)]
+    pub referral_code: Account<'info, ReferralCode>,
+
+    /// CHECK: System-owned PDA holding the code's accrued fees
+    #[account(mut, seeds = [b"referral_vault", referral_code.key().as_ref()], bump)]
+    pub referral_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Referrer receiving the creator share, checked against the code
+    #[account(mut)]
+    pub referrer: UncheckedAccount<'info>,
+
+    /// CHECK: Royalty config, validated by the royalty splitter
+    #[account(mut)]
+    pub royalty_config: UncheckedAccount<'info>,
+
+    /// CHECK: Distribution record the royalty splitter creates
+    #[account(mut)]
+    pub distribution_record: UncheckedAccount<'info>,
+
+    /// CHECK: Royalty platform wallet, validated by the royalty splitter
+    #[account(mut)]
+    pub platform_account: UncheckedAccount<'info>,
+
+    /// CHECK: Royalty treasury wallet, validated by the royalty splitter
+    #[account(mut)]
+    pub treasury_account: UncheckedAccount<'info>,
+
+    /// Pays the distribution record's rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub royalty_program: Program<'info, RoyaltySplitter>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetAgentFreeze<'info> {
+    #[account(
+        seeds = [b"registry_config"],
+        bump,
+        has_one = slash_authority @ ErrorCode::UnauthorizedSlash
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    /// CHECK: Created or updated by the escrow program
+    #[account(
+        mut,
+        seeds = [b"agent_requests", agent_profile.key().as_ref()],
+        bump,
+        seeds::program = marketplace_escrow::ID
+    )]
+    pub agent_requests: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"capacity_hook"], bump)]
+    /// CHECK: PDA that authorizes the freeze in the escrow program
+    pub capacity_hook: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub slash_authority: Signer<'info>,
+
+    pub escrow_program: Program<'info, MarketplaceEscrow>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetAgentVerification<'info> {
+    #[account(
+        seeds = [b"registry_config"],
+        bump,
+        has_one = verifier @ ErrorCode::UnauthorizedVerifier
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    pub verifier: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(schema: [u8; 32])]
+pub struct IssueAgentAttestation<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = issuer,
+        space = 8 + AgentAttestation::INIT_SPACE,
+        seeds = [b"agent_attestation", agent_profile.key().as_ref(), issuer.key().as_ref(), schema.as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, AgentAttestation>,
+
+    #[account(mut)]
+    pub issuer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAgentAttestation<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent_attestation", attestation.agent_id.as_ref(), attestation.issuer.as_ref(), attestation.schema.as_ref()],
+        bump,
+        has_one = issuer @ ErrorCode::UnauthorizedIssuer
+    )]
+    pub attestation: Account<'info, AgentAttestation>,
+
+    pub issuer: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SlashStake<'info> {
+    #[account(
+        seeds = [b"registry_config"],
+        bump,
+        has_one = slash_authority @ ErrorCode::UnauthorizedSlash
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+
+    pub service_request: Account<'info, ServiceRequest>,
+
+    #[account(
+        mut,
+        seeds = [b"listing_stake", service_request.agent_id.as_ref()],
+        bump
+    )]
+    pub listing_stake: Account<'info, ListingStake>,
+
+    #[account(
+        init,
+        payer = slash_authority,
+        space = 8 + StakeSlash::INIT_SPACE,
+        seeds = [
+            b"stake_slash",
+            service_request.key().as_ref(),
+            service_request.created_at.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub stake_slash: Account<'info, StakeSlash>,
+
+    /// CHECK: The user who disputed the request, receiving the slashed stake
+    #[account(mut, address = service_request.user)]
+    pub user: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub slash_authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct PromoteAgent<'info> {
+    #[account(
+        mut,
+        constraint = !agent_profile.frozen @ ErrorCode::AgentFrozen
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(seeds = [b"registry_config"], bump)]
+    pub registry_config: Account<'info, RegistryConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = 8 + FeaturedIndex::INIT_SPACE,
+        seeds = [b"featured_index"],
+        bump
+    )]
+    pub featured_index: Box<Account<'info, FeaturedIndex>>,
+
+    /// CHECK: Royalty config, validated by the royalty splitter
+    #[account(mut)]
+    pub royalty_config: UncheckedAccount<'info>,
+
+    /// CHECK: Distribution record the royalty splitter creates
+    #[account(mut)]
+    pub distribution_record: UncheckedAccount<'info>,
+
+    /// CHECK: Royalty platform wallet, validated by the royalty splitter
+    #[account(mut)]
+    pub platform_account: UncheckedAccount<'info>,
+
+    /// CHECK: Royalty treasury wallet, validated by the royalty splitter
+    #[account(mut)]
+    pub treasury_account: UncheckedAccount<'info>,
+
+    pub royalty_program: Program<'info, RoyaltySplitter>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: String)]
+pub struct AddCapability<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Capability::INIT_SPACE,
+        seeds = [b"capability", id.as_bytes()],
+        bump
+    )]
+    pub capability: Account<'info, Capability>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        has_one = authority @ ErrorCode::UnauthorizedCapabilityAdmin,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DeprecateCapability<'info> {
+    #[account(mut, seeds = [b"capability", capability.id.as_bytes()], bump)]
+    pub capability: Account<'info, Capability>,
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        has_one = authority @ ErrorCode::UnauthorizedCapabilityAdmin,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct AddAcceptedMint<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AcceptedMint::INIT_SPACE,
+        seeds = [b"accepted_mint", mint.key().as_ref()],
+        bump
+    )]
+    pub accepted_mint: Account<'info, AcceptedMint>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        has_one = authority @ ErrorCode::UnauthorizedConfigAdmin,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveAcceptedMint<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"accepted_mint", accepted_mint.mint.as_ref()],
+        bump
+    )]
+    pub accepted_mint: Account<'info, AcceptedMint>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        has_one = authority @ ErrorCode::UnauthorizedConfigAdmin,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(checker: Pubkey)]
+pub struct AddHealthChecker<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + HealthChecker::INIT_SPACE,
+        seeds = [b"health_checker", checker.as_ref()],
+        bump
+    )]
+    pub health_checker: Account<'info, HealthChecker>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        has_one = authority @ ErrorCode::UnauthorizedConfigAdmin,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveHealthChecker<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"health_checker", health_checker.checker.as_ref()],
+        bump
+    )]
+    pub health_checker: Account<'info, HealthChecker>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        has_one = authority @ ErrorCode::UnauthorizedConfigAdmin,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitHealthCheck<'info> {
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = checker,
+        space = 8 + HealthLog::INIT_SPACE,
+        seeds = [b"health_log", agent_profile.key().as_ref()],
+        bump
+    )]
+    pub health_log: Box<Account<'info, HealthLog>>,
+
+    /// Registration of the signing checker
+    #[account(seeds = [b"health_checker", checker.key().as_ref()], bump)]
+    pub health_checker: Account<'info, HealthChecker>,
+
+    #[account(mut)]
+    pub checker: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetAgentAuthority<'info> {
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    /// CHECK: The manager's token account of the agent NFT, checked in the handler unless the agent has an authority
+    pub holder_token_account: UncheckedAccount<'info>,
+
+    /// NFT holder, or the agent's authority when it has one
+    pub manager: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAgent<'info> {
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    /// `AcceptedMint` of the new pricing's payment mint, when it names one
+    #[account(seeds = [b"accepted_mint", accepted_mint.mint.as_ref()], bump)]
+    pub accepted_mint: Option<Account<'info, AcceptedMint>>,
+
+    /// CHECK: `NameClaim` of the agent's current name, released on a rename
+    #[account(mut, seeds = [b"name", agent_name_seed(&agent_profile.name).as_ref()], bump)]
+    pub name_claim: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: `NameClaim` of the new name, checked and created in the handler
+    #[account(mut)]
+    pub new_name_claim: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The signer's token account of the agent NFT, checked in the handler unless the agent has an authority
+    pub holder_token_account: UncheckedAccount<'info>,
+
+    /// NFT holder, or the agent's authority when it has one
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateMetadataAuthority<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    /// CHECK: Metadata of the agent's NFT, validated by the metadata program
+    #[account(
+        mut,
+        address = Metadata::find_pda(&agent_profile.nft_mint).0 @ ErrorCode::InvalidAgentMetadata
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// CHECK: The agent's NFT mint
+    #[account(address = agent_profile.nft_mint)]
+    pub nft_mint: UncheckedAccount<'info>,
+
+    /// Current update authority of the metadata
+    #[account(mut)]
+    pub update_authority: Signer<'info>,
+
+    /// CHECK: PDA that becomes the metadata's update authority
+    #[account(seeds = [b"metadata_authority"], bump)]
+    pub metadata_authority: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, required by the metadata program
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+
+    /// CHECK: The Metaplex token metadata program
+    #[account(address = mpl_token_metadata::ID)]
+    pub token_metadata_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAgentMetadata<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    /// CHECK: Metadata of the agent's NFT, read in the handler
+    #[account(
+        mut,
+        address = Metadata::find_pda(&agent_profile.nft_mint).0 @ ErrorCode::InvalidAgentMetadata
+    )]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// CHECK: The agent's NFT mint
+    #[account(address = agent_profile.nft_mint)]
+    pub nft_mint: UncheckedAccount<'info>,
+
+    /// CHECK: PDA that signs as the metadata's update authority
+    #[account(seeds = [b"metadata_authority"], bump)]
+    pub metadata_authority: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, required by the metadata program
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+
+    /// CHECK: The Metaplex token metadata program
+    #[account(address = mpl_token_metadata::ID)]
+    pub token_metadata_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetLanguages<'info> {
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetDependencies<'info> {
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    pub holder: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct Heartbeat<'info> {
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    pub holder: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RotateEndpoints<'info> {
+    #[account(
+        mut,
+        constraint = !agent_profile.frozen @ ErrorCode::AgentFrozen
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    pub holder: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetPayoutWallet<'info> {
+    #[account(
+        mut,
+        constraint = !agent_profile.frozen @ ErrorCode::AgentFrozen
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    pub holder: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(capability_limit: u8, description_limit: u16)]
+pub struct ResizeProfile<'info> {
+    #[account(
+        mut,
+        realloc = profile_space(capability_limit, description_limit),
+        realloc::payer = holder,
+        realloc::zero = false
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct AddAgentCapabilities<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = agent_capabilities_space(&[]),
+        seeds = [b"agent_capabilities", agent_profile.key().as_ref()],
+        bump
+    )]
+    pub agent_capabilities: Account<'info, AgentCapabilities>,
+
+    /// CHECK: The signer's token account of the agent NFT, checked in the handler unless the agent has an authority
+    pub holder_token_account: UncheckedAccount<'info>,
+
+    /// NFT holder, or the agent's authority when it has one
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveAgentCapabilities<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        mut,
+        seeds = [b"agent_capabilities", agent_profile.key().as_ref()],
+        bump
+    )]
+    pub agent_capabilities: Account<'info, AgentCapabilities>,
+
+    /// CHECK: The signer's token account of the agent NFT, checked in the handler unless the agent has an authority
+    pub holder_token_account: UncheckedAccount<'info>,
+
+    /// NFT holder, or the agent's authority when it has one
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct TopUpRent<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    /// CHECK: The agent's account named by the `RentTarget`, checked in the handler
+    #[account(mut)]
+    pub account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MarkStale<'info> {
+    #[account(seeds = [b"registry_config"], bump)]
+    pub registry_config: Account<'info, RegistryConfig>,
+
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetMaxConcurrentRequests<'info> {
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    /// CHECK: Created or updated by the escrow program
+    #[account(
+        mut,
+        seeds = [b"agent_requests", agent_profile.key().as_ref()],
+        bump,
+        seeds::program = marketplace_escrow::ID
+    )]
+    pub agent_requests: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"capacity_hook"], bump)]
+    /// CHECK: PDA that authorizes capacity updates in the escrow program
+    pub capacity_hook: UncheckedAccount<'info>,
+
+    pub escrow_program: Program<'info, MarketplaceEscrow>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetCapabilitySchema<'info> {
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    pub holder: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(capability_id: String)]
+pub struct SetCapabilityPrice<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = 8 + CapabilityPrice::INIT_SPACE,
+        seeds = [b"capability_price", agent_profile.key().as_ref(), capability_id.as_bytes()],
+        bump
+    )]
+    pub capability_price: Account<'info, CapabilityPrice>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(capability_id: String)]
+pub struct RemoveCapabilityPrice<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        mut,
+        close = holder,
+        seeds = [b"capability_price", agent_profile.key().as_ref(), capability_id.as_bytes()],
+        bump
+    )]
+    pub capability_price: Account<'info, CapabilityPrice>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetListingPause<'info> {
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    /// CHECK: Created or updated by the escrow program
+    #[account(
+        mut,
+        seeds = [b"agent_requests", agent_profile.key().as_ref()],
+        bump,
+        seeds::program = marketplace_escrow::ID
+    )]
+    pub agent_requests: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"capacity_hook"], bump)]
+    /// CHECK: PDA that authorizes pause updates in the escrow program
+    pub capacity_hook: UncheckedAccount<'info>,
+
+    pub escrow_program: Program<'info, MarketplaceEscrow>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetVisibility<'info> {
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    /// CHECK: Created or updated by the escrow program
+    #[account(
+        mut,
+        seeds = [b"agent_requests", agent_profile.key().as_ref()],
+        bump,
+        seeds::program = marketplace_escrow::ID
+    )]
+    pub agent_requests: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"capacity_hook"], bump)]
+    /// CHECK: PDA that authorizes visibility updates in the escrow program
+    pub capacity_hook: UncheckedAccount<'info>,
+
+    pub escrow_program: Program<'info, MarketplaceEscrow>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetTrialRequests<'info> {
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    /// CHECK: Created or updated by the escrow program
+    #[account(
+        mut,
+        seeds = [b"agent_requests", agent_profile.key().as_ref()],
+        bump,
+        seeds::program = marketplace_escrow::ID
+    )]
+    pub agent_requests: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"capacity_hook"], bump)]
+    /// CHECK: PDA that authorizes trial updates in the escrow program
+    pub capacity_hook: UncheckedAccount<'info>,
+
+    pub escrow_program: Program<'info, MarketplaceEscrow>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct AddAllowlistEntry<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        init,
+        payer = holder,
+        space = 8 + AllowlistEntry::INIT_SPACE,
+        seeds = [b"allowlist", agent_profile.key().as_ref(), user.as_ref()],
+        bump
+    )]
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveAllowlistEntry<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        mut,
+        close = holder,
+        seeds = [b"allowlist", agent_profile.key().as_ref(), allowlist_entry.user.as_ref()],
+        bump
+    )]
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct BlockUser<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        init,
+        payer = holder,
+        space = 8 + BlockEntry::INIT_SPACE,
+        seeds = [b"blocklist", agent_profile.key().as_ref(), user.as_ref()],
+        bump
+    )]
+    pub block_entry: Account<'info, BlockEntry>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct UnblockUser<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        mut,
+        close = holder,
+        seeds = [b"blocklist", agent_profile.key().as_ref(), block_entry.user.as_ref()],
+        bump
+    )]
+    pub block_entry: Account<'info, BlockEntry>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct TransferOwnership<'info> {
+    #[account(
+        mut,
+        constraint = !agent_profile.frozen @ ErrorCode::AgentFrozen
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    pub holder: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ListAgentForSale<'info> {
+    #[account(
+        has_one = nft_mint,
+        constraint = !agent_profile.soulbound @ ErrorCode::AgentNotTransferable,
+        constraint = !agent_profile.frozen @ ErrorCode::AgentFrozen
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + AgentSale::INIT_SPACE,
+        seeds = [b"agent_sale", agent_profile.key().as_ref()],
+        bump
+    )]
+    pub agent_sale: Account<'info, AgentSale>,
+
+    pub nft_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = nft_mint,
+        token::authority = seller,
+        constraint = seller_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = seller,
+        associated_token::mint = nft_mint,
+        associated_token::authority = agent_sale,
+    )]
+    pub sale_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    /// The agent's authority, which must co-sign while one is set
+    pub authority: Option<Signer<'info>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, anchor_spl::associated_token::AssociatedToken>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAgentSale<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent_sale", agent_sale.agent_id.as_ref()],
+        bump,
+        has_one = seller @ ErrorCode::NotAgentSeller,
+        close = seller
+    )]
+    pub agent_sale: Account<'info, AgentSale>,
+
+    #[account(
+        mut,
+        associated_token::mint = agent_sale.nft_mint,
+        associated_token::authority = agent_sale,
+    )]
+    pub sale_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = agent_sale.nft_mint,
+        token::authority = seller,
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct BuyAgent<'info> {
+    #[account(
+        mut,
+        address = agent_sale.agent_id,
+        constraint = !agent_profile.frozen @ ErrorCode::AgentFrozen
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        mut,
+        seeds = [b"agent_sale", agent_sale.agent_id.as_ref()],
+        bump,
+        has_one = seller @ ErrorCode::NotAgentSeller,
+        close = seller
+    )]
+    pub agent_sale: Account<'info, AgentSale>,
+
+    #[account(address = agent_sale.nft_mint)]
+    pub nft_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = agent_sale,
+    )]
+    pub sale_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = nft_mint,
+        associated_token::authority = buyer,
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Paid the price and refunded the sale's rent; checked against the sale
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, anchor_spl::associated_token::AssociatedToken>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RetireAgent<'info> {
+    #[account(
+        mut,
+        constraint = !agent_profile.frozen @ ErrorCode::AgentFrozen
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    /// Profile of the successor, when the agent names one
+    pub successor_profile: Option<Account<'info, AgentProfile>>,
+
+    /// CHECK: Created or updated by the escrow program
+    #[account(
+        mut,
+        seeds = [b"agent_requests", agent_profile.key().as_ref()],
+        bump,
+        seeds::program = marketplace_escrow::ID
+    )]
+    pub agent_requests: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"capacity_hook"], bump)]
+    /// CHECK: PDA that authorizes retirement in the escrow program
+    pub capacity_hook: UncheckedAccount<'info>,
+
+    pub escrow_program: Program<'info, MarketplaceEscrow>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct DeregisterAgent<'info> {
+    #[account(
+        mut,
+        has_one = nft_mint,
+        close = holder,
+        constraint = !agent_profile.frozen @ ErrorCode::AgentFrozen
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(mut)]
+    pub nft_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = nft_mint,
+        token::authority = holder,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: The escrow program's open request count for this agent, read in the handler
+    #[account(
+        seeds = [b"agent_requests", agent_profile.key().as_ref()],
+        bump,
+        seeds::program = marketplace_escrow::ID
+    )]
+    pub agent_requests: UncheckedAccount<'info>,
+
+    /// CHECK: Listing stake returned to the holder, absent for agents registered before stakes
+    #[account(mut, seeds = [b"listing_stake", agent_profile.key().as_ref()], bump)]
+    pub listing_stake: UncheckedAccount<'info>,
+
+    /// CHECK: The agent's extra capabilities, closed if they were all removed; absent if never used
+    #[account(mut, seeds = [b"agent_capabilities", agent_profile.key().as_ref()], bump)]
+    pub agent_capabilities: UncheckedAccount<'info>,
+
+    /// CHECK: `NameClaim` of the agent's name, released if the agent holds it
+    #[account(mut, seeds = [b"name", agent_name_seed(&agent_profile.name).as_ref()], bump)]
+    pub name_claim: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    /// The agent's authority, which must co-sign while one is set
+    pub authority: Option<Signer<'info>>,
+
+    /// CHECK: PDA that freezes soulbound NFTs and thaws them to be burned
+    #[account(seeds = [b"collection_authority"], bump)]
+    pub collection_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(major: u16, minor: u16, patch: u16)]
+pub struct PublishAgentVersion<'info> {
+    #[account(
+        mut,
+        constraint = !agent_profile.frozen @ ErrorCode::AgentFrozen
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        init,
+        payer = holder,
+        space = 8 + AgentVersion::INIT_SPACE,
+        seeds = [
+            b"agent_version",
+            agent_profile.key().as_ref(),
+            &major.to_le_bytes(),
+            &minor.to_le_bytes(),
+            &patch.to_le_bytes()
+        ],
+        bump
+    )]
+    pub agent_version: Account<'info, AgentVersion>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RequestEndpointChallenge<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = 8 + EndpointChallenge::INIT_SPACE,
+        seeds = [b"endpoint_challenge", agent_profile.key().as_ref()],
+        bump
+    )]
+    pub endpoint_challenge: Account<'info, EndpointChallenge>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct AttestEndpoint<'info> {
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        mut,
+        seeds = [b"endpoint_challenge", agent_profile.key().as_ref()],
+        bump,
+        close = holder
+    )]
+    pub endpoint_challenge: Account<'info, EndpointChallenge>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    /// CHECK: Instructions sysvar, used to read the ed25519 endpoint signature
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(offering_id: u16)]
+pub struct AddServiceOffering<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        init,
+        payer = holder,
+        space = 8 + ServiceOffering::INIT_SPACE,
+        seeds = [b"service_offering", agent_profile.key().as_ref(), &offering_id.to_le_bytes()],
+        bump
+    )]
+    pub service_offering: Account<'info, ServiceOffering>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateServiceOffering<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        mut,
+        seeds = [b"service_offering", agent_profile.key().as_ref(), &service_offering.offering_id.to_le_bytes()],
+        bump
+    )]
+    pub service_offering: Account<'info, ServiceOffering>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    pub holder: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveServiceOffering<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        mut,
+        close = holder,
+        seeds = [b"service_offering", agent_profile.key().as_ref(), &service_offering.offering_id.to_le_bytes()],
+        bump
+    )]
+    pub service_offering: Account<'info, ServiceOffering>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(bundle_id: u16)]
+pub struct CreateBundle<'info> {
+    #[account(
+        init,
+        payer = curator,
+        space = 8 + Bundle::INIT_SPACE,
+        seeds = [b"bundle", curator.key().as_ref(), &bundle_id.to_le_bytes()],
+        bump
+    )]
+    pub bundle: Account<'info, Bundle>,
+
+    #[account(mut)]
+    pub curator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateBundle<'info> {
+    #[account(
+        mut,
+        seeds = [b"bundle", curator.key().as_ref(), &bundle.bundle_id.to_le_bytes()],
+        bump,
+        has_one = curator
+    )]
+    pub bundle: Account<'info, Bundle>,
+
+    pub curator: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(tier_id: u16)]
+pub struct CreateSubscriptionTier<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        init,
+        payer = holder,
+        space = 8 + SubscriptionTier::INIT_SPACE,
+        seeds = [b"subscription_tier", agent_profile.key().as_ref(), &tier_id.to_le_bytes()],
+        bump
+    )]
+    pub subscription_tier: Account<'info, SubscriptionTier>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RetireSubscriptionTier<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        mut,
+        seeds = [b"subscription_tier", agent_profile.key().as_ref(), &subscription_tier.tier_id.to_le_bytes()],
+        bump
+    )]
+    pub subscription_tier: Account<'info, SubscriptionTier>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    pub holder: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct IssueAccessGrant<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        seeds = [b"subscription_tier", agent_profile.key().as_ref(), &subscription_tier.tier_id.to_le_bytes()],
+        bump,
+        constraint = subscription_tier.is_active @ ErrorCode::TierRetired
+    )]
+    pub subscription_tier: Account<'info, SubscriptionTier>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + AccessGrant::INIT_SPACE,
+        seeds = [b"access_grant", agent_profile.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: The agent's payout wallet, paid the creator share
+    #[account(mut, address = agent_profile.payout_wallet @ ErrorCode::PaymentRecipientMismatch)]
+    pub creator_account: UncheckedAccount<'info>,
+
+    /// CHECK: Royalty config, validated by the royalty splitter
+    #[account(mut)]
+    pub royalty_config: UncheckedAccount<'info>,
+
+    /// CHECK: Distribution record the royalty splitter creates
+    #[account(mut)]
+    pub distribution_record: UncheckedAccount<'info>,
+
+    /// CHECK: Royalty platform wallet, validated by the royalty splitter
+    #[account(mut)]
+    pub platform_account: UncheckedAccount<'info>,
+
+    /// CHECK: Royalty treasury wallet, validated by the royalty splitter
+    #[account(mut)]
+    pub treasury_account: UncheckedAccount<'info>,
+
+    pub royalty_program: Program<'info, RoyaltySplitter>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ConsumeAccess<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        mut,
+        seeds = [b"access_grant", agent_profile.key().as_ref(), access_grant.user.as_ref()],
+        bump
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    pub holder: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAccessGrant<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        mut,
+        seeds = [b"access_grant", agent_profile.key().as_ref(), access_grant.user.as_ref()],
+        bump
+    )]
+    pub access_grant: Account<'info, AccessGrant>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    pub holder: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetCoCreators<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = 8 + CoCreatorSplit::INIT_SPACE,
+        seeds = [b"co_creators", agent_profile.key().as_ref()],
+        bump
+    )]
+    pub co_creator_split: Account<'info, CoCreatorSplit>,
+
+    #[account(
+        constraint = holder_token_account.mint == agent_profile.nft_mint @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::AgentNftNotHeld,
+        constraint = holder_token_account.amount == 1 @ ErrorCode::AgentNftNotHeld
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveUsdPrice<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    /// CHECK: Pyth price account, checked against the agent's pricing in the handler
+    pub price_feed: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveSurgePrice<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    /// CHECK: The escrow program's open request count for this agent, read in the handler
+    #[account(
+        seeds = [b"agent_requests", agent_profile.key().as_ref()],
+        bump,
+        seeds::program = marketplace_escrow::ID
+    )]
+    pub agent_requests: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct GetAgentProfile<'info> {
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    /// CHECK: The escrow program's open request count for this agent, read in the handler
+    #[account(
+        seeds = [b"agent_requests", agent_profile.key().as_ref()],
+        bump,
+        seeds::program = marketplace_escrow::ID
+    )]
+    pub agent_requests: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateReputation<'info> {
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    /// Reputation hook PDA; only the reputation system can sign for it
+    #[account(
+        seeds = [b"reputation_hook"],
+        bump,
+        seeds::program = REPUTATION_SYSTEM_ID
+    )]
+    pub reputation_hook: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RecordEarnings<'info> {
+    #[account(mut)]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    /// Escrow payout hook PDA; only the escrow program can sign for it
+    #[account(
+        seeds = [b"payout_hook"],
+        bump,
+        seeds::program = marketplace_escrow::ID
+    )]
+    pub payout_hook: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateProfile<'info> {
+    /// CHECK: Possibly in an older layout; upgraded and rewritten by the handler
+    #[account(mut, owner = crate::ID)]
+    pub agent_profile: UncheckedAccount<'info>,
+
+    /// Pays the rent for the grown account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAgentProfile<'info> {
+    /// CHECK: Possibly in an older layout; upgraded and rewritten by the handler
+    #[account(mut, owner = crate::ID)]
+    pub agent_profile: UncheckedAccount<'info>,
+
+    /// Pays the rent for the grown account
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        has_one = authority @ ErrorCode::UnauthorizedMigration,
+        constraint = guardian_config.is_paused @ ErrorCode::ProtocolNotPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AgentProfile {
+    pub agent_id: Pubkey,
+    pub creator: Pubkey,
+    /// Wallet escrow and royalty payouts go to; starts as the creator. Kept
+    /// right after `creator` so other programs read it at a fixed offset
+    pub payout_wallet: Pubkey,
+    #[max_len(50)]
+    pub name: String,
+    /// Sized for `DEFAULT_DESCRIPTION_LIMIT`; `resize_profile` makes room for more
+    #[max_len(DEFAULT_DESCRIPTION_LIMIT)]
+    pub description: String,
+    /// Sized for `DEFAULT_CAPABILITY_LIMIT`; `resize_profile` makes room for more
+    #[max_len(DEFAULT_CAPABILITY_LIMIT, MAX_CAPABILITY_ID_LEN)]
+    pub capabilities: Vec<String>,
+    pub pricing_model: PricingModel,
+    /// Ordered by ascending priority, the order orchestrators fail over in
+    #[max_len(MAX_ENDPOINTS)]
+    pub endpoints: Vec<AgentEndpoint>,
+    #[max_len(100)]
+    pub ipfs_hash: String,
+    pub reputation_score: u32,
+    pub total_services: u64,
+    pub total_earnings: u64,
+    pub created_at: i64,
+    pub is_active: bool,
+    pub nft_mint: Pubkey,
+    /// Set once the endpoints answer a challenge; cleared when they change
+    pub endpoint_verified: bool,
+    /// End of the agent's paid promotion; featured while it is in the future
+    pub featured_until: i64,
+    /// Set by the registry's verifier once the agent has been audited
+    pub is_verified: bool,
+    /// Last heartbeat from the agent's operator
+    pub last_seen_at: i64,
+    /// Requests the agent serves at once; zero means no limit. Mirrored to
+    /// the escrow's `AgentRequestCount`, which enforces it
+    pub max_concurrent_requests: u32,
+    /// Listings are offline until this time, when set. Mirrored to the
+    /// escrow's `AgentRequestCount`, which enforces it
+    pub paused_until: Option<i64>,
+    /// Input and output formats declared for listed capabilities
+    #[max_len(DEFAULT_CAPABILITY_LIMIT)]
+    pub capability_schemas: Vec<CapabilitySchema>,
+    /// Agent this one was forked from, if any
+    pub parent_agent: Option<Pubkey>,
+    /// Who may hire the agent. Mirrored to the escrow's `AgentRequestCount`,
+    /// which enforces it
+    pub visibility: Visibility,
+    /// Most capabilities the profile account has room for
+    pub capability_limit: u8,
+    /// Longest description the profile account has room for
+    pub description_limit: u16,
+    /// Requests each wallet can make for free before paying `pricing_model`.
+    /// Mirrored to the escrow's `AgentRequestCount`, which enforces it
+    pub trial_requests: u8,
+    /// Languages the agent serves, each with a `LanguageIndex` entry
+    #[max_len(MAX_LANGUAGES, MAX_LANGUAGE_CODE_LEN)]
+    pub languages: Vec<String>,
+    /// Other agents this one calls into while serving a request
+    #[max_len(MAX_DEPENDENCIES)]
+    pub dependencies: Vec<Pubkey>,
+    /// Whether the NFT is frozen in the creator's token account for good
+    pub soulbound: bool,
+    /// Set by `retire_agent`; a retired agent stays inactive
+    pub retired: bool,
+    /// Agent that replaces this one since it retired, if it named one
+    pub successor: Option<Pubkey>,
+    /// Account that manages the agent in place of the NFT holder, e.g. a
+    /// multisig vault; `None` leaves it to the holder
+    pub authority: Option<Pubkey>,
+    /// Frozen by the slash authority; the profile can't be updated and the
+    /// escrow takes no new requests for it
+    pub frozen: bool,
+    /// Layout the profile was last written in; zero for profiles from before
+    /// versioning. See `PROFILE_VERSION`
+    pub version: u8,
+    /// Share of the agent's logged health checks that passed, in bps; `None`
+    /// until a checker has posted one. See `HealthLog`
+    pub uptime_bps: Option<u16>,
+}
+
+/// Outstanding challenge an agent's endpoint signs to prove control of it
+#[account]
+#[derive(InitSpace)]
+pub struct EndpointChallenge {
+    pub agent_id: Pubkey,
+    pub nonce: [u8; 32],
+    /// Endpoints the challenge was issued for; it lapses if they are rotated
+    #[max_len(MAX_ENDPOINTS)]
+    pub endpoints: Vec<AgentEndpoint>,
+    pub issued_at: i64,
+}
+
+/// One published release of an agent: the model or endpoint build that
+/// served requests pinned to it
+#[account]
+#[derive(InitSpace)]
+pub struct AgentVersion {
+    pub agent_id: Pubkey,
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+    #[max_len(100)]
+    pub ipfs_hash: String,
+    /// SHA-256 of the release notes, which live off-chain
+    pub changelog_hash: [u8; 32],
+    pub publisher: Pubkey,
+    pub published_at: i64,
+}
+
+/// One service in an agent's catalog, priced on its own. The fixed-size
+/// fields come first so the escrow program can read them at fixed offsets
+#[account]
+#[derive(InitSpace)]
+pub struct ServiceOffering {
+    pub agent_id: Pubkey,
+    pub offering_id: u16,
+    /// Lamports a request for this service escrows at least
+    pub price: u64,
+    /// Seconds the agent commits to deliver a result within
+    pub turnaround_secs: u32,
+    pub is_active: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+    #[max_len(50)]
+    pub name: String,
+    #[max_len(200)]
+    pub description: String,
+}
+
+/// Price of one of an agent's capabilities, overriding the agent's pricing
+/// for requests that select it. The fixed-size fields come first so the
+/// escrow program can read them at fixed offsets
+#[account]
+#[derive(InitSpace)]
+pub struct CapabilityPrice {
+    pub agent_id: Pubkey,
+    /// Lamports a request for this capability escrows at least
+    pub price: u64,
+    pub updated_at: i64,
+    #[max_len(MAX_CAPABILITY_ID_LEN)]
+    pub capability_id: String,
+}
+
+/// Capabilities an agent lists past its profile's limit. Sized to fit the
+/// list exactly, growing and shrinking as ids are added and removed
+#[account]
+pub struct AgentCapabilities {
+    pub agent_id: Pubkey,
+    pub capabilities: Vec<String>,
+}
+
+/// Code an existing creator hands out; agents registered with it send part
+/// of their registration fee to the referrer
+#[account]
+#[derive(InitSpace)]
+pub struct ReferralCode {
+    pub referrer: Pubkey,
+    /// Agents registered with the code
+    pub referrals: u64,
+    /// Lamports of registration fees credited to the code's vault so far
+    pub earned: u64,
+    pub created_at: i64,
+    #[max_len(MAX_REFERRAL_CODE_LEN)]
+    pub code: String,
+}
+
+/// Several agents sold together, listed by a curator with each agent's
+/// creator agreeing to the discount. Each agent gets its own request for an
+/// even share of the discounted price. The fixed-size fields and the agent
+/// list come first so the escrow program can read them at fixed offsets
+#[account]
+#[derive(InitSpace)]
+pub struct Bundle {
+    pub curator: Pubkey,
+    pub bundle_id: u16,
+    /// Combined list price of the bundle, in lamports
+    pub price: u64,
+    /// Discount off `price` for buying the agents together
+    pub discount_bps: u16,
+    pub is_active: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+    #[max_len(MAX_BUNDLE_AGENTS)]
+    pub agents: Vec<Pubkey>,
+    #[max_len(50)]
+    pub name: String,
+}
+
+impl Bundle {
+    /// Lamports each agent's request must escrow: the discounted price split
+    /// evenly across the agents, rounded up
+    pub fn share_price(&self) -> u64 {
+        let discounted = self.price as u128 * (10_000 - self.discount_bps as u128) / 10_000;
+        discounted.div_ceil(self.agents.len().max(1) as u128) as u64
+    }
+}
+
+/// One named plan of a subscription-priced agent. Fixed-size fields come
+/// first so the escrow program can read them at fixed offsets
+#[account]
+#[derive(InitSpace)]
+pub struct SubscriptionTier {
+    pub agent_id: Pubkey,
+    pub tier_id: u16,
+    /// Lamports per month, in place of the pricing model's `monthly`
+    pub monthly_price: u64,
+    /// Requests a subscriber may open per month
+    pub requests_per_month: u32,
+    /// False once retired; no new subscriptions start on it
+    pub is_active: bool,
+    /// Hash of the off-chain feature list
+    pub features_hash: [u8; 32],
+    pub created_at: i64,
+    /// 0 while active
+    pub retired_at: i64,
+    #[max_len(32)]
+    pub name: String,
+}
+
+/// An agent offered for sale; holds the agent's NFT in escrow until it is
+/// bought or the listing is cancelled
+#[account]
+#[derive(InitSpace)]
+pub struct AgentSale {
+    pub agent_id: Pubkey,
+    pub seller: Pubkey,
+    pub nft_mint: Pubkey,
+    /// Asking price, in lamports
+    pub price: u64,
+    pub listed_at: i64,
+}
+
+/// Operational key a creator lets sign agent manifests and submit escrow
+/// results on their behalf
+#[account]
+#[derive(InitSpace)]
+pub struct OperatorKey {
+    pub creator: Pubkey,
+    pub operator: Pubkey,
+    /// Nonce the operator's next manifest must carry
+    pub next_nonce: u64,
+    pub registered_at: i64,
+}
+
+/// Independent key the guardian authority lets post endpoint health checks
+#[account]
+#[derive(InitSpace)]
+pub struct HealthChecker {
+    pub checker: Pubkey,
+    pub added_at: i64,
+}
+
+/// Ring buffer of an agent's latest health checks. It fills up to
+/// `HEALTH_LOG_LEN` entries, then `cursor` marks the oldest, which the next
+/// check overwrites
+#[account]
+#[derive(InitSpace)]
+pub struct HealthLog {
+    pub agent_id: Pubkey,
+    pub cursor: u8,
+    #[max_len(HEALTH_LOG_LEN)]
+    pub checks: Vec<HealthCheck>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct HealthCheck {
+    pub checker: Pubkey,
+    pub healthy: bool,
+    pub latency_ms: u32,
+    pub checked_at: i64,
+}
+
+/// A user the agent's holder allows to hire it while it is allowlist-only
+#[account]
+#[derive(InitSpace)]
+pub struct AllowlistEntry {
+    pub agent_id: Pubkey,
+    pub user: Pubkey,
+    pub added_at: i64,
+}
+
+/// A user the agent's holder refuses to serve
+#[account]
+#[derive(InitSpace)]
+pub struct BlockEntry {
+    pub agent_id: Pubkey,
+    pub user: Pubkey,
+    pub blocked_at: i64,
+}
+
+/// A user's paid access to an agent on one subscription tier. Agents check
+/// it instead of an off-chain API key database
+#[account]
+#[derive(InitSpace)]
+pub struct AccessGrant {
+    pub agent_id: Pubkey,
+    pub user: Pubkey,
+    pub subscription_tier: Pubkey,
+    pub issued_at: i64,
+    pub expires_at: i64,
+    /// Requests the user may still make before the grant expires
+    pub remaining_quota: u32,
+    pub is_revoked: bool,
+}
+
+impl AccessGrant {
+    /// Whether the grant is unrevoked and unexpired at `now`
+    pub fn is_valid(&self, now: i64) -> bool {
+        !self.is_revoked && now < self.expires_at
+    }
+}
+
+/// An issuer's attestation about an agent under one schema. `agent_id`,
+/// `issuer`, and `schema` come first so clients can filter on them
+#[account]
+#[derive(InitSpace)]
+pub struct AgentAttestation {
+    pub agent_id: Pubkey,
+    pub issuer: Pubkey,
+    /// Identifies what is attested, e.g. the hash of a schema URI
+    pub schema: [u8; 32],
+    /// Hash of the off-chain attestation data
+    pub data_hash: [u8; 32],
+    pub issued_at: i64,
+    pub is_revoked: bool,
+    pub expires_at: Option<i64>,
+    pub revoked_at: Option<i64>,
+}
+
+impl AgentAttestation {
+    /// Whether the attestation is unrevoked and unexpired at `now`
+    pub fn is_valid(&self, now: i64) -> bool {
+        let unexpired = match self.expires_at {
+            Some(expires_at) => now < expires_at,
+            None => true,
+        };
+        !self.is_revoked && unexpired
+    }
+}
+
+/// The co-creators an agent's creator share is split with. Kept beside the
+/// profile rather than in it so the escrow can read the list at fixed offsets
+#[account]
+#[derive(InitSpace)]
+pub struct CoCreatorSplit {
+    pub agent_id: Pubkey,
+    #[max_len(MAX_CO_CREATORS)]
+    pub co_creators: Vec<CoCreator>,
+}
+
+/// Parent and lineage royalty of a forked agent. Kept beside the profile
+/// rather than in it so the royalty splitter can read it at fixed offsets
+#[account]
+#[derive(InitSpace)]
+pub struct AgentLineage {
+    pub agent_id: Pubkey,
+    pub parent_agent: Pubkey,
+    /// Share of lineage payments routed to the parent's creator
+    pub royalty_bps: u16,
+}
+
+/// Input and output formats of one capability an agent lists, as IPFS CIDs
+/// of their schemas
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct CapabilitySchema {
+    #[max_len(MAX_CAPABILITY_ID_LEN)]
+    pub capability_id: String,
+    #[max_len(MAX_SCHEMA_CID_LEN)]
+    pub input_schema: String,
+    #[max_len(MAX_SCHEMA_CID_LEN)]
+    pub output_schema: String,
+}
+
+/// What an operational key signs to publish an agent: the `register_agent`
+/// arguments, bound to the creator the agent is registered for. `nonce`
+/// must be the operator's next nonce, so each manifest registers once
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AgentManifest {
+    pub creator: Pubkey,
+    pub nonce: u64,
+    /// Last moment the manifest can be submitted
+    pub expires_at: i64,
+    pub name: String,
+    pub description: String,
+    pub capabilities: Vec<String>,
+    pub pricing: PricingModel,
+    pub endpoints: Vec<AgentEndpoint>,
+    pub ipfs_hash: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+/// One place an agent serves requests; lower priorities are tried first
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub struct AgentEndpoint {
+    #[max_len(200)]
+    pub url: String,
+    #[max_len(MAX_REGION_LEN)]
+    pub region: String,
+    pub priority: u8,
+}
+
+/// A wallet taking `bps` of an agent's creator share
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct CoCreator {
+    pub key: Pubkey,
+    pub bps: u16,
+}
+
+/// An SPL token agents may price their services in
+#[account]
+#[derive(InitSpace)]
+pub struct AcceptedMint {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    pub added_at: i64,
+}
+
+/// Registry-wide settings
+#[account]
+#[derive(InitSpace)]
+pub struct RegistryConfig {
+    /// Lamports each new agent locks for as long as it is listed
+    pub listing_stake: u64,
+    /// Key that rules on disputes, may slash stakes, and freezes malicious
+    /// agents
+    pub slash_authority: Pubkey,
+    /// Lamports a featured listing costs per day; zero disables promotions
+    pub featured_price_per_day: u64,
+    /// Key that grants and revokes the verified-agent badge
+    pub verifier: Pubkey,
+    /// Seconds an agent may go without a heartbeat before anyone can mark it
+    /// stale; zero disables the check
+    pub heartbeat_window: i64,
+    /// Lamports each new agent pays once into the fee vault, which is swept
+    /// through the royalty splitter; zero for free registration
+    pub registration_fee: u64,
+    /// Agents registered so far; the next one takes this index
+    pub agent_count: u64,
+}
+
+/// Agents with a paid promotion, so clients can list them without scanning
+/// every profile. Lapsed entries are pruned on the next promotion
+#[account]
+#[derive(InitSpace)]
+pub struct FeaturedIndex {
+    #[max_len(20)]
+    pub entries: Vec<FeaturedEntry>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct FeaturedEntry {
+    pub agent_id: Pubkey,
+    pub featured_until: i64,
+}
+
+/// One entry of the registry's sequential agent list, written at
+/// registration so clients can walk `0..agent_count` without scanning
+/// program accounts. Entries outlive deregistration; the profile is gone then
+#[account]
+#[derive(InitSpace)]
+pub struct AgentIndex {
+    pub index: u64,
+    pub agent_id: Pubkey,
+}
+
+/// Stake an agent locked at registration, held as lamports above the
+/// account's rent and returned on deregistration
+#[account]
+#[derive(InitSpace)]
+pub struct ListingStake {
+    pub agent_id: Pubkey,
+    /// Stake still locked, after slashes
+    pub amount: u64,
+    pub staked_at: i64,
+    pub total_slashed: u64,
+}
+
+/// Record of a slash ruled over one disputed request. Keyed by the request's
+/// creation time as well as its address, which the user reuses for their
+/// next request of the agent once this one is closed
+#[account]
+#[derive(InitSpace)]
+pub struct StakeSlash {
+    pub service_request: Pubkey,
+    pub agent_id: Pubkey,
+    pub amount: u64,
+    pub slashed_at: i64,
+}
+
+/// An entry in the admin-curated capability taxonomy
+#[account]
+#[derive(InitSpace)]
+pub struct Capability {
+    #[max_len(20)]
+    pub id: String,
+    #[max_len(50)]
+    pub name: String,
+    #[max_len(200)]
+    pub description: String,
+    pub is_deprecated: bool,
+    pub created_at: i64,
+    pub deprecated_at: Option<i64>,
+}
+
+/// Claim on a normalized agent name, at `[b"name", agent_name_seed(name)]`,
+/// so no two agents hold names that read the same. Released when the agent
+/// is renamed or deregistered
+#[account]
+#[derive(InitSpace)]
+pub struct NameClaim {
+    pub agent_id: Pubkey,
+    pub claimed_at: i64,
+}
+
+/// Discovery index entry for one capability an agent lists. Clients find
+/// every agent with a capability by filtering on `capability` at offset 8
+#[account]
+#[derive(InitSpace)]
+pub struct CapabilityIndex {
+    /// The capability's taxonomy account
+    pub capability: Pubkey,
+    pub agent_id: Pubkey,
+    pub created_at: i64,
+}
+
+/// Discovery index entry for one language an agent serves. Clients find
+/// every agent serving a language by filtering on the encoded `language` at
+/// offset 8
+#[account]
+#[derive(InitSpace)]
+pub struct LanguageIndex {
+    #[max_len(MAX_LANGUAGE_CODE_LEN)]
+    pub language: String,
+    pub agent_id: Pubkey,
+    pub created_at: i64,
+}
+
+// Bubblegum `TokenStandard::NonFungible` and `TokenProgramVersion::Original`
+const TOKEN_STANDARD_NON_FUNGIBLE: u8 = 0;
+const TOKEN_PROGRAM_VERSION_ORIGINAL: u8 = 0;
+
+// Bubblegum's `MetadataArgs` wire layout, with its enums as their variant index
+#[derive(AnchorSerialize)]
+struct CompressedMetadataArgs {
+    name: String,
+    symbol: String,
+    uri: String,
+    seller_fee_basis_points: u16,
+    primary_sale_happened: bool,
+    is_mutable: bool,
+    edition_nonce: Option<u8>,
+    token_standard: Option<u8>,
+    collection: Option<CompressedCollection>,
+    uses: Option<u8>,
+    token_program_version: u8,
+    creators: Vec<CompressedCreator>,
+}
+
+#[derive(AnchorSerialize)]
+struct CompressedCollection {
+    verified: bool,
+    key: Pubkey,
+}
+
+#[derive(AnchorSerialize)]
+struct CompressedCreator {
+    address: Pubkey,
+    verified: bool,
+    share: u8,
+}
+
+/// Who may open requests against an agent
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum Visibility {
+    Public,
+    /// Only users with an `AllowlistEntry` for the agent
+    Allowlist,
+}
+
+/// Which of an agent's accounts `top_up_rent` funds
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RentTarget {
+    Profile,
+    /// The agent's `AgentCapabilities`
+    Capabilities,
+    /// The agent's `HealthLog`
+    HealthLog,
+    /// The analytics program's `AgentAnalytics` for the agent
+    Analytics,
+}
+
+/// How an agent charges. Prices are in lamports, or in base units of
+/// `payment_mint` when it names an accepted SPL token. `UsdPegged` charges a
+/// fixed USD amount in lamports, resolved against a Pyth SOL/USD `feed`.
+/// `Surge` moves `base` by `step_bps` for each open request above or below
+/// `target_open`, within `min..=max` lamports
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub enum PricingModel {
+    PerQuery { price: u64, payment_mint: Option<Pubkey> },
+    Subscription { monthly: u64, payment_mint: Option<Pubkey> },
+    Custom { base: u64, variable: u8, payment_mint: Option<Pubkey> },
+    UsdPegged { usd_cents: u64, feed: Pubkey },
+    Surge { base: u64, min: u64, max: u64, target_open: u32, step_bps: u16 },
+}
+
+impl PricingModel {
+    /// SPL token the agent is paid in, or `None` for SOL
+    pub fn payment_mint(&self) -> Option<Pubkey> {
+        match self {
+            PricingModel::PerQuery { payment_mint, .. }
+            | PricingModel::Subscription { payment_mint, .. }
+            | PricingModel::Custom { payment_mint, .. } => *payment_mint,
+            PricingModel::UsdPegged { .. } | PricingModel::Surge { .. } => None,
+        }
+    }
+}
+
+/// What `get_agent_profile` returns: the profile's fixed-size fields, then
+/// fields derived from it. Bounded by `INIT_SPACE`, which fits in the
+/// transaction's return data whatever the profile holds
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct AgentProfileView {
+    pub profile: AgentProfileSummary,
+    /// Price of the next request in lamports or `payment_mint` base units:
+    /// the monthly price for subscriptions, the base for custom pricing, and
+    /// the current surge price. `None` for USD-pegged agents
+    pub effective_price: Option<u64>,
+    pub payment_mint: Option<Pubkey>,
+    pub open_requests: u32,
+    /// Listed, not retired, frozen, or paused
+    pub accepting_requests: bool,
+    /// Holds the verified badge and has proven control of its endpoints
+    pub verified: bool,
+}
+
+const _: () = assert!(
+    AgentProfileView::INIT_SPACE <= anchor_lang::solana_program::program::MAX_RETURN_DATA
+);
+
+/// `AgentProfile` without its variable-length lists and description, which
+/// are counted instead
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct AgentProfileSummary {
+    pub agent_id: Pubkey,
+    pub creator: Pubkey,
+    pub payout_wallet: Pubkey,
+    #[max_len(50)]
+    pub name: String,
+    pub pricing_model: PricingModel,
+    #[max_len(100)]
+    pub ipfs_hash: String,
+    pub reputation_score: u32,
+    pub total_services: u64,
+    pub total_earnings: u64,
+    pub created_at: i64,
+    pub is_active: bool,
+    pub nft_mint: Pubkey,
+    pub endpoint_verified: bool,
+    pub featured_until: i64,
+    pub is_verified: bool,
+    pub last_seen_at: i64,
+    pub max_concurrent_requests: u32,
+    pub paused_until: Option<i64>,
+    pub parent_agent: Option<Pubkey>,
+    pub visibility: Visibility,
+    pub trial_requests: u8,
+    pub soulbound: bool,
+    pub retired: bool,
+    pub successor: Option<Pubkey>,
+    pub authority: Option<Pubkey>,
+    pub frozen: bool,
+    pub version: u8,
+    pub uptime_bps: Option<u16>,
+    /// Length of the description in bytes
+    pub description_len: u16,
+    pub capability_count: u8,
+    pub endpoint_count: u8,
+    pub capability_schema_count: u8,
+}
+
+#[event]
+pub struct AgentRegistered {
+    pub agent_id: Pubkey,
+    pub creator: Pubkey,
+    pub name: String,
+    pub capabilities: Vec<String>,
+    pub nft_mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CollectionInitialized {
+    pub collection_mint: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentTreeInitialized {
+    pub merkle_tree: Pubkey,
+    pub max_depth: u32,
+    pub max_buffer_size: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentUpdated {
+    pub agent_id: Pubkey,
+    pub creator: Pubkey,
+    pub name: String,
+    pub is_active: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentOwnershipTransferred {
+    pub agent_id: Pubkey,
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+    pub nft_mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentVersionPublished {
+    pub agent_id: Pubkey,
+    pub agent_version: Pubkey,
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+    pub ipfs_hash: String,
+    pub changelog_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ServiceOfferingAdded {
+    pub agent_id: Pubkey,
+    pub service_offering: Pubkey,
+    pub offering_id: u16,
+    pub name: String,
+    pub price: u64,
+    pub turnaround_secs: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ServiceOfferingUpdated {
+    pub agent_id: Pubkey,
+    pub service_offering: Pubkey,
+    pub price: u64,
+    pub turnaround_secs: u32,
+    pub is_active: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ServiceOfferingRemoved {
+    pub agent_id: Pubkey,
+    pub service_offering: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CapabilityPriceSet {
+    pub agent_id: Pubkey,
+    pub capability_price: Pubkey,
+    pub capability_id: String,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CapabilityPriceRemoved {
+    pub agent_id: Pubkey,
+    pub capability_price: Pubkey,
+    pub capability_id: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BundleCreated {
+    pub bundle: Pubkey,
+    pub curator: Pubkey,
+    pub bundle_id: u16,
+    pub name: String,
+    pub agents: Vec<Pubkey>,
+    pub price: u64,
+    pub discount_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BundleUpdated {
+    pub bundle: Pubkey,
+    pub curator: Pubkey,
+    pub price: u64,
+    pub discount_bps: u16,
+    pub is_active: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionTierCreated {
+    pub agent_id: Pubkey,
+    pub subscription_tier: Pubkey,
+    pub tier_id: u16,
+    pub name: String,
+    pub monthly_price: u64,
+    pub requests_per_month: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionTierRetired {
+    pub agent_id: Pubkey,
+    pub subscription_tier: Pubkey,
+    pub tier_id: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessGrantIssued {
+    pub agent_id: Pubkey,
+    pub user: Pubkey,
+    pub subscription_tier: Pubkey,
+    pub months: u32,
+    pub amount: u64,
+    pub expires_at: i64,
+    pub remaining_quota: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessConsumed {
+    pub agent_id: Pubkey,
+    pub user: Pubkey,
+    pub units: u32,
+    pub remaining_quota: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessGrantRevoked {
+    pub agent_id: Pubkey,
+    pub user: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RegistryConfigUpdated {
+    pub listing_stake: u64,
+    pub slash_authority: Pubkey,
+    pub featured_price_per_day: u64,
+    pub verifier: Pubkey,
+    pub heartbeat_window: i64,
+    pub registration_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RegistrationFeesSwept {
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralCodeCreated {
+    pub referral_code: Pubkey,
+    pub referrer: Pubkey,
+    pub code: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentReferred {
+    pub agent_id: Pubkey,
+    pub referral_code: Pubkey,
+    pub referrer: Pubkey,
+    pub share: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralFeesSwept {
+    pub referral_code: Pubkey,
+    pub referrer: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ListingStaked {
+    pub agent_id: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakeSlashed {
+    pub agent_id: Pubkey,
+    pub request_id: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentPromoted {
+    pub agent_id: Pubkey,
+    pub holder: Pubkey,
+    pub days: u32,
+    pub amount: u64,
+    pub featured_until: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AcceptedMintAdded {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AcceptedMintRemoved {
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UsdPriceResolved {
+    pub agent_id: Pubkey,
+    pub usd_cents: u64,
+    pub price: i64,
+    pub expo: i32,
+    pub lamports: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SurgePriceResolved {
+    pub agent_id: Pubkey,
+    pub open_requests: u32,
+    pub lamports: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CapabilityAdded {
+    pub capability: Pubkey,
+    pub id: String,
+    pub name: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CapabilityDeprecated {
+    pub capability: Pubkey,
+    pub id: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EndpointChallengeIssued {
+    pub agent_id: Pubkey,
+    pub endpoints: Vec<AgentEndpoint>,
+    pub nonce: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EndpointsRotated {
+    pub agent_id: Pubkey,
+    pub endpoints: Vec<AgentEndpoint>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PayoutWalletSet {
+    pub agent_id: Pubkey,
+    pub payout_wallet: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProfileResized {
+    pub agent_id: Pubkey,
+    pub capability_limit: u8,
+    pub description_limit: u16,
+    /// Account size after the reallocation, in bytes
+    pub size: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentCapabilitiesAdded {
+    pub agent_id: Pubkey,
+    pub capabilities: Vec<String>,
+    /// Capabilities in the agent's `AgentCapabilities` account afterwards
+    pub count: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentCapabilitiesRemoved {
+    pub agent_id: Pubkey,
+    pub capabilities: Vec<String>,
+    /// Capabilities in the agent's `AgentCapabilities` account afterwards
+    pub count: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RentToppedUp {
+    pub agent_id: Pubkey,
+    pub account: Pubkey,
+    pub target: RentTarget,
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HealthCheckerAdded {
+    pub checker: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HealthCheckerRemoved {
+    pub checker: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HealthCheckSubmitted {
+    pub agent_id: Pubkey,
+    pub checker: Pubkey,
+    pub healthy: bool,
+    pub latency_ms: u32,
+    pub uptime_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentListedForSale {
+    pub agent_id: Pubkey,
+    pub seller: Pubkey,
+    pub nft_mint: Pubkey,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentSaleCancelled {
+    pub agent_id: Pubkey,
+    pub seller: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentSold {
+    pub agent_id: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProfileMigrated {
+    pub agent_id: Pubkey,
+    pub from_version: u8,
+    pub version: u8,
+    /// Account size after the upgrade, in bytes
+    pub size: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentVerified {
+    pub agent_id: Pubkey,
+    pub verifier: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentVerificationRevoked {
+    pub agent_id: Pubkey,
+    pub verifier: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentAttestationIssued {
+    pub attestation: Pubkey,
+    pub agent_id: Pubkey,
+    pub issuer: Pubkey,
+    pub schema: [u8; 32],
+    pub data_hash: [u8; 32],
+    pub expires_at: Option<i64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentAttestationRevoked {
+    pub attestation: Pubkey,
+    pub agent_id: Pubkey,
+    pub issuer: Pubkey,
+    pub schema: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CoCreatorsUpdated {
+    pub agent_id: Pubkey,
+    pub co_creators: Vec<CoCreator>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentHeartbeat {
+    pub agent_id: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentSoulbound {
+    pub agent_id: Pubkey,
+    pub token_account: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentForked {
+    pub agent_id: Pubkey,
+    pub parent_agent: Pubkey,
+    pub lineage_royalty_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CapabilitySchemaSet {
+    pub agent_id: Pubkey,
+    pub capability_id: String,
+    pub input_schema: String,
+    pub output_schema: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CapabilitySchemaCleared {
+    pub agent_id: Pubkey,
+    pub capability_id: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentListingsPaused {
+    pub agent_id: Pubkey,
+    pub paused_until: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentListingsResumed {
+    pub agent_id: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentVisibilitySet {
+    pub agent_id: Pubkey,
+    pub visibility: Visibility,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TrialRequestsSet {
+    pub agent_id: Pubkey,
+    pub trial_requests: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MetadataAuthorityMigrated {
+    pub agent_id: Pubkey,
+    pub previous_authority: Pubkey,
+    pub metadata_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentMetadataUpdated {
+    pub agent_id: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LanguagesSet {
+    pub agent_id: Pubkey,
+    pub languages: Vec<String>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DependenciesSet {
+    pub agent_id: Pubkey,
+    pub dependencies: Vec<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AllowlistEntryAdded {
+    pub agent_id: Pubkey,
+    pub user: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AllowlistEntryRemoved {
+    pub agent_id: Pubkey,
+    pub user: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UserBlocked {
+    pub agent_id: Pubkey,
+    pub user: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UserUnblocked {
+    pub agent_id: Pubkey,
+    pub user: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentCapacityUpdated {
+    pub agent_id: Pubkey,
+    pub max_concurrent_requests: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentMarkedStale {
+    pub agent_id: Pubkey,
+    pub last_seen_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EndpointVerified {
+    pub agent_id: Pubkey,
+    pub endpoints: Vec<AgentEndpoint>,
+    pub endpoint_key: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentManifestRegistered {
+    pub agent_id: Pubkey,
+    pub operator: Pubkey,
+    pub manifest_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OperatorRegistered {
+    pub creator: Pubkey,
+    pub operator: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OperatorRevoked {
+    pub creator: Pubkey,
+    pub operator: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentAuthoritySet {
+    pub agent_id: Pubkey,
+    pub authority: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentFrozen {
+    pub agent_id: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentUnfrozen {
+    pub agent_id: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentRetired {
+    pub agent_id: Pubkey,
+    pub successor: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentDeregistered {
+    pub agent_id: Pubkey,
+    pub creator: Pubkey,
+    pub nft_mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub size: u32,
     pub timestamp: i64,
 }
 
@@ -275,10 +8801,258 @@ pub struct AgentUpdated {
 pub enum ErrorCode {
     #[msg("Agent name is too long (max 50 characters)")]
     NameTooLong,
-    #[msg("Agent description is too long (max 500 characters)")]
+    #[msg("Agent description is too long for the profile (max 500 characters unless resized)")]
     DescriptionTooLong,
     #[msg("Endpoint URL is too long (max 200 characters)")]
     EndpointTooLong,
-    #[msg("Too many capabilities (max 10)")]
+    #[msg("Too many capabilities for the profile (max 10 unless resized)")]
     TooManyCapabilities,
+    #[msg("Protocol is paused by the guardian")]
+    ProtocolPaused,
+    #[msg("Only the guardian authority can migrate accounts")]
+    UnauthorizedMigration,
+    #[msg("Migrations require the protocol to be paused")]
+    ProtocolNotPaused,
+    #[msg("Account is not of the type being migrated")]
+    MigrationTargetMismatch,
+    #[msg("Migration chunk extends past the end of the account")]
+    MigrationChunkOutOfBounds,
+    #[msg("Migrated account does not match its address")]
+    MigrationIdentityMismatch,
+    #[msg("Agent still has open escrow requests")]
+    AgentHasOpenRequests,
+    #[msg("Signer does not hold the agent NFT")]
+    AgentNftNotHeld,
+    #[msg("Signer already owns this agent")]
+    AlreadyOwner,
+    #[msg("Only the guardian authority can create the agent collection")]
+    UnauthorizedCollectionAdmin,
+    #[msg("Not the platform's compressed agent tree")]
+    InvalidAgentTree,
+    #[msg("IPFS hash is too long (max 100 characters)")]
+    IpfsHashTooLong,
+    #[msg("Only the guardian authority can curate capabilities")]
+    UnauthorizedCapabilityAdmin,
+    #[msg("Capability id must be 1-20 characters")]
+    CapabilityIdTooLong,
+    #[msg("Capability is not in the taxonomy")]
+    UnknownCapability,
+    #[msg("Capability is deprecated")]
+    CapabilityDeprecated,
+    #[msg("Capability index account does not match the agent and capability")]
+    InvalidCapabilityIndex,
+    #[msg("Agent has no endpoint to verify")]
+    NoEndpoint,
+    #[msg("Endpoint challenge has expired or the endpoint has changed")]
+    EndpointChallengeExpired,
+    #[msg("Missing ed25519 instruction carrying the endpoint signature")]
+    MissingEndpointSignature,
+    #[msg("Endpoint signature does not match the challenge")]
+    InvalidEndpointSignature,
+    #[msg("Only the guardian authority can change the registry config")]
+    UnauthorizedConfigAdmin,
+    #[msg("Only the slash authority can slash listing stakes")]
+    UnauthorizedSlash,
+    #[msg("Stakes are only slashed over disputed requests")]
+    RequestNotDisputed,
+    #[msg("Slash amount must be positive and within the remaining stake")]
+    SlashExceedsStake,
+    #[msg("Promotions must last 1-90 days")]
+    InvalidPromotionDays,
+    #[msg("Featured listings are not for sale")]
+    PromotionsDisabled,
+    #[msg("Promotion fee overflows")]
+    PromotionFeeOverflow,
+    #[msg("Featured index is full")]
+    FeaturedIndexFull,
+    #[msg("Offering description is too long (max 200 characters)")]
+    OfferingDescriptionTooLong,
+    #[msg("Offering price must be positive")]
+    InvalidOfferingPrice,
+    #[msg("Payment mint is not an accepted token")]
+    PaymentMintNotAccepted,
+    #[msg("Agent is not priced in USD")]
+    NotUsdPegged,
+    #[msg("Price feed is not the agent's Pyth price account")]
+    InvalidPriceFeed,
+    #[msg("Pyth price is not currently trading")]
+    PriceUnavailable,
+    #[msg("Pyth price is too old")]
+    StalePrice,
+    #[msg("USD price overflows in lamports")]
+    UsdPriceOverflow,
+    #[msg("Agent is not surge priced")]
+    NotSurgePriced,
+    #[msg("Surge pricing needs 0 < min <= base <= max")]
+    InvalidSurgeBounds,
+    #[msg("Agent is not subscription priced")]
+    NotSubscriptionPriced,
+    #[msg("Tier must allow at least one request per month")]
+    InvalidTierQuota,
+    #[msg("Subscription tier is retired")]
+    TierRetired,
+    #[msg("Only the verifier can verify agents")]
+    UnauthorizedVerifier,
+    #[msg("Agent is already verified")]
+    AgentAlreadyVerified,
+    #[msg("Agent is not verified")]
+    AgentNotVerified,
+    #[msg("Attestation expiry must be in the future")]
+    InvalidAttestationExpiry,
+    #[msg("Attestation is already revoked")]
+    AttestationAlreadyRevoked,
+    #[msg("Only the issuer can revoke an attestation")]
+    UnauthorizedIssuer,
+    #[msg("Too many co-creators (max 5)")]
+    TooManyCoCreators,
+    #[msg("Co-creator shares must be non-zero and total at most 10000 bps")]
+    InvalidCoCreatorShares,
+    #[msg("Co-creator is listed twice")]
+    DuplicateCoCreator,
+    #[msg("Heartbeat window cannot be negative")]
+    InvalidHeartbeatWindow,
+    #[msg("Heartbeat checks are disabled")]
+    HeartbeatsDisabled,
+    #[msg("Agent is inactive or has sent a heartbeat within the window")]
+    AgentNotStale,
+    #[msg("Pause must end in the future")]
+    InvalidPauseWindow,
+    #[msg("Agent listings are not paused")]
+    ListingsNotPaused,
+    #[msg("Schema CID must be 1-64 characters")]
+    InvalidSchemaCid,
+    #[msg("Capability is not listed by the agent")]
+    CapabilityNotListed,
+    #[msg("Capability has no declared schema")]
+    CapabilitySchemaNotSet,
+    #[msg("Lineage royalty cannot exceed 1000 bps")]
+    LineageRoyaltyTooHigh,
+    #[msg("Only active agents can be forked")]
+    ParentAgentInactive,
+    #[msg("Bundles combine 2-5 agents")]
+    InvalidBundleSize,
+    #[msg("Bundle discount must be below 10000 bps")]
+    InvalidBundleDiscount,
+    #[msg("Agent is listed twice in the bundle")]
+    DuplicateBundleAgent,
+    #[msg("Bundle agent profiles must be passed in order")]
+    BundleAgentMismatch,
+    #[msg("Bundles can only list active agents")]
+    BundleAgentInactive,
+    #[msg("Every bundled agent's creator must sign")]
+    BundleAgentNotApproved,
+    #[msg("Access is bought for 1-12 months")]
+    InvalidAccessTerm,
+    #[msg("Access price or quota overflows")]
+    AccessPriceOverflow,
+    #[msg("Access payment must go to the agent's creator")]
+    PaymentRecipientMismatch,
+    #[msg("Access grant is on another tier until it expires")]
+    AccessTierMismatch,
+    #[msg("Access units must be non-zero")]
+    InvalidAccessUnits,
+    #[msg("Access grant is expired or revoked")]
+    AccessGrantInactive,
+    #[msg("Access grant has too few requests left")]
+    AccessQuotaExceeded,
+    #[msg("Registration fee must be zero or cover the fee vault's rent exemption")]
+    RegistrationFeeBelowRent,
+    #[msg("No registration fees to sweep")]
+    NoRegistrationFees,
+    #[msg("Too many endpoints")]
+    TooManyEndpoints,
+    #[msg("Endpoint URL is empty")]
+    EmptyEndpointUrl,
+    #[msg("Endpoint region too long")]
+    RegionTooLong,
+    #[msg("Two endpoints share a priority")]
+    DuplicateEndpointPriority,
+    #[msg("Profile limits can grow to at most 32 capabilities and 2000 description characters")]
+    ProfileLimitTooLarge,
+    #[msg("Profile limits can only grow")]
+    ProfileLimitShrunk,
+    #[msg("Payout wallet must be set")]
+    InvalidPayoutWallet,
+    #[msg("Too many languages")]
+    TooManyLanguages,
+    #[msg("Language must be an ISO 639-1 code, optionally with an ISO 3166 region (`pt-BR`)")]
+    InvalidLanguageCode,
+    #[msg("Language listed twice")]
+    DuplicateLanguage,
+    #[msg("Language index account does not match the agent and language")]
+    InvalidLanguageIndex,
+    #[msg("Too many dependencies")]
+    TooManyDependencies,
+    #[msg("An agent cannot depend on itself")]
+    SelfDependency,
+    #[msg("Dependency listed twice")]
+    DuplicateDependency,
+    #[msg("Dependency profiles do not match the dependency list")]
+    DependencyMismatch,
+    #[msg("Dependency is not active")]
+    DependencyInactive,
+    #[msg("Metadata account is not the agent NFT's metadata")]
+    InvalidAgentMetadata,
+    #[msg("The agent's metadata update authority has not been migrated to the registry")]
+    MetadataAuthorityNotMigrated,
+    #[msg("Agent is retired")]
+    AgentRetired,
+    #[msg("Successor must be another active agent that is not retired")]
+    InvalidSuccessor,
+    #[msg("Agent is managed by its authority, which must sign")]
+    NotAgentAuthority,
+    #[msg("Agent is frozen pending review")]
+    AgentFrozen,
+    #[msg("Agent is not frozen")]
+    AgentNotFrozen,
+    #[msg("Profile is already in the current layout")]
+    ProfileUpToDate,
+    #[msg("Missing ed25519 instruction carrying the manifest signature")]
+    MissingManifestSignature,
+    #[msg("Manifest signature does not match the operator or manifest")]
+    InvalidManifestSignature,
+    #[msg("Manifest could not be decoded")]
+    InvalidManifest,
+    #[msg("Manifest names a different creator")]
+    ManifestCreatorMismatch,
+    #[msg("Referral code must be 1 to 32 bytes")]
+    InvalidReferralCode,
+    #[msg("Creators cannot register with their own referral code")]
+    SelfReferral,
+    #[msg("Account is not the referral code's referrer")]
+    InvalidReferrer,
+    #[msg("No referral fees to sweep")]
+    NoReferralFees,
+    #[msg("Agent profile is too large to return; read the account instead")]
+    ProfileTooLargeForReturnData,
+    #[msg("This checker checked the agent too recently")]
+    HealthCheckTooSoon,
+    #[msg("Sale price must be non-zero")]
+    InvalidSalePrice,
+    #[msg("Soulbound agents cannot be sold")]
+    AgentNotTransferable,
+    #[msg("Account is not the agent's seller")]
+    NotAgentSeller,
+    #[msg("Agent's asking price has changed")]
+    SalePriceMismatch,
+    #[msg("Capability is already listed by the agent")]
+    DuplicateCapability,
+    #[msg("Remove the agent's extra capabilities before deregistering it")]
+    AgentCapabilitiesNotEmpty,
+    #[msg("Account is not the agent's account of that kind")]
+    InvalidRentTarget,
+    #[msg("Account is already rent-exempt")]
+    RentAlreadyExempt,
+    #[msg("An agent with a name that reads the same is already registered")]
+    NameTaken,
+    #[msg("Name claim account missing or not the claim for that name")]
+    InvalidNameClaim,
+    #[msg("Agent name needs at least one letter or digit")]
+    InvalidAgentName,
+    #[msg("Manifest has expired")]
+    ManifestExpired,
+    #[msg("Manifest nonce is not the operator's next nonce")]
+    StaleManifestNonce,
+    #[msg("Agent names may only use printable ASCII characters")]
+    AgentNameNotAscii,
 }
\ No newline at end of file
@@ -0,0 +1,475 @@
+use anchor_lang::prelude::*;
+use protocol_guardian::GuardianConfig;
+
+declare_id!("66Ab7gExYpbUsHnEvS9RduvUSjcjyFiRPRcUYocDexpb");
+
+/// Maximum number of agents chained in a single workflow
+pub const MAX_STEPS: usize = 8;
+
+#[program]
+pub mod agent_workflow {
+    use super::*;
+
+    /// Create a multi-step pipeline and escrow every step's allocation up front
+    pub fn create_workflow(
+        ctx: Context<CreateWorkflow>,
+        workflow_id: u64,
+        steps: Vec<StepSpec>,
+        input_hash: [u8; 32],
+        step_timeout: i64,
+    ) -> Result<()> {
+        require!(
+            !steps.is_empty() && steps.len() <= MAX_STEPS,
+            WorkflowError::InvalidStepCount
+        );
+        require!(step_timeout > 0, WorkflowError::InvalidTimeout);
+
+        let mut total_amount: u64 = 0;
+        for step in steps.iter() {
+            require!(step.amount > 0, WorkflowError::InvalidAmount);
+            total_amount = total_amount
+                .checked_add(step.amount)
+                .ok_or(WorkflowError::MathOverflow)?;
+        }
+
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.user.key(),
+            &ctx.accounts.workflow.key(),
+            total_amount,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_instruction,
+            &[
+                ctx.accounts.user.to_account_info(),
+                ctx.accounts.workflow.to_account_info(),
+            ],
+        )?;
+
+        let clock = Clock::get()?;
+        let workflow_key = ctx.accounts.workflow.key();
+        let workflow = &mut ctx.accounts.workflow;
+
+        workflow.user = ctx.accounts.user.key();
+        workflow.workflow_id = workflow_id;
+        workflow.status = WorkflowStatus::Active;
+        workflow.current_step = 0;
+        workflow.total_amount = total_amount;
+        workflow.released_amount = 0;
+        workflow.refunded_amount = 0;
+        workflow.step_timeout = step_timeout;
+        workflow.created_at = clock.unix_timestamp;
+        workflow.steps = steps
+            .iter()
+            .map(|spec| WorkflowStep {
+                agent_id: spec.agent_id,
+                agent_authority: spec.agent_authority,
+                amount: spec.amount,
+                status: StepStatus::Pending,
+                input_hash: [0; 32],
+                output_hash: [0; 32],
+                output_uri: String::new(),
+                started_at: 0,
+            })
+            .collect();
+
+        let first = &mut workflow.steps[0];
+        first.status = StepStatus::Running;
+        first.input_hash = input_hash;
+        first.started_at = clock.unix_timestamp;
+
+        emit!(WorkflowCreated {
+            workflow: workflow_key,
+            user: workflow.user,
+            workflow_id,
+            step_count: workflow.steps.len() as u8,
+            total_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Submit the result of the running step (step agent only)
+    pub fn submit_step_result(
+        ctx: Context<SubmitStepResult>,
+        output_hash: [u8; 32],
+        output_uri: String,
+    ) -> Result<()> {
+        require!(output_uri.len() <= 128, WorkflowError::OutputUriTooLong);
+
+        let workflow_key = ctx.accounts.workflow.key();
+        let workflow = &mut ctx.accounts.workflow;
+        require!(workflow.status == WorkflowStatus::Active, WorkflowError::WorkflowNotActive);
+
+        let index = workflow.current_step as usize;
+        let step = &mut workflow.steps[index];
+        require!(
+            step.agent_authority == ctx.accounts.agent_authority.key(),
+            WorkflowError::UnauthorizedAgent
+        );
+        require!(step.status == StepStatus::Running, WorkflowError::StepNotRunning);
+
+        step.output_hash = output_hash;
+        step.output_uri = output_uri;
+        step.status = StepStatus::Submitted;
+
+        emit!(StepResultSubmitted {
+            workflow: workflow_key,
+            step_index: index as u8,
+            agent_id: step.agent_id,
+            output_hash,
+        });
+
+        Ok(())
+    }
+
+    /// Accept the current step's result, pay its agent, and hand its output to the next step
+    pub fn approve_step(
+        ctx: Context<ApproveStep>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let workflow_info = ctx.accounts.workflow.to_account_info();
+        let workflow_key = ctx.accounts.workflow.key();
+        let workflow = &mut ctx.accounts.workflow;
+        require!(workflow.status == WorkflowStatus::Active, WorkflowError::WorkflowNotActive);
+
+        let index = workflow.current_step as usize;
+        let step = &mut workflow.steps[index];
+        require!(step.status == StepStatus::Submitted, WorkflowError::StepNotSubmitted);
+        require!(
+            step.agent_authority == ctx.accounts.agent_authority.key(),
+            WorkflowError::UnauthorizedAgent
+        );
+
+        step.status = StepStatus::Completed;
+        let amount = step.amount;
+        let agent_id = step.agent_id;
+        let output_hash = step.output_hash;
+
+        **workflow_info.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.agent_authority.try_borrow_mut_lamports()? += amount;
+
+        workflow.released_amount += amount;
+
+        emit!(StepCompleted {
+            workflow: workflow_key,
+            step_index: index as u8,
+            agent_id,
+            amount,
+        });
+
+        if index + 1 < workflow.steps.len() {
+            workflow.current_step += 1;
+            let next = &mut workflow.steps[index + 1];
+            next.status = StepStatus::Running;
+            next.input_hash = output_hash;
+            next.started_at = clock.unix_timestamp;
+        } else {
+            workflow.status = WorkflowStatus::Completed;
+
+            emit!(WorkflowCompleted {
+                workflow: workflow_key,
+                user: workflow.user,
+                released_amount: workflow.released_amount,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Fail the current step and refund every unreleased allocation to the user.
+    /// The user or the step's agent may fail a step at any time; anyone may once it times out.
+    pub fn rollback_workflow(
+        ctx: Context<RollbackWorkflow>,
+        reason: String,
+    ) -> Result<()> {
+        require!(reason.len() <= 200, WorkflowError::ReasonTooLong);
+
+        let clock = Clock::get()?;
+        let workflow_info = ctx.accounts.workflow.to_account_info();
+        let workflow_key = ctx.accounts.workflow.key();
+        let caller = ctx.accounts.caller.key();
+        let workflow = &mut ctx.accounts.workflow;
+        require!(workflow.status == WorkflowStatus::Active, WorkflowError::WorkflowNotActive);
+
+        let index = workflow.current_step as usize;
+        let step = &workflow.steps[index];
+        let timed_out = clock.unix_timestamp >= step.started_at.saturating_add(workflow.step_timeout);
+        require!(
+            caller == workflow.user || caller == step.agent_authority || timed_out,
+            WorkflowError::UnauthorizedRollback
+        );
+
+        workflow.steps[index].status = StepStatus::Failed;
+        for step in workflow.steps.iter_mut().skip(index + 1) {
+            step.status = StepStatus::Refunded;
+        }
+
+        let refund = workflow.total_amount - workflow.released_amount;
+        workflow.refunded_amount = refund;
+        workflow.status = WorkflowStatus::RolledBack;
+
+        **workflow_info.try_borrow_mut_lamports()? -= refund;
+        **ctx.accounts.user.try_borrow_mut_lamports()? += refund;
+
+        emit!(WorkflowRolledBack {
+            workflow: workflow_key,
+            failed_step: index as u8,
+            refunded_amount: refund,
+            caller,
+            reason,
+        });
+
+        Ok(())
+    }
+
+    /// Close a finished workflow and reclaim its rent
+    pub fn close_workflow(
+        ctx: Context<CloseWorkflow>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.workflow.status != WorkflowStatus::Active,
+            WorkflowError::WorkflowStillActive
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(workflow_id: u64)]
+pub struct CreateWorkflow<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Workflow::INIT_SPACE,
+        seeds = [b"workflow", user.key().as_ref(), workflow_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub workflow: Account<'info, Workflow>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ WorkflowError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitStepResult<'info> {
+    #[account(
+        mut,
+        seeds = [b"workflow", workflow.user.as_ref(), workflow.workflow_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub workflow: Account<'info, Workflow>,
+
+    pub agent_authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ WorkflowError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveStep<'info> {
+    #[account(
+        mut,
+        seeds = [b"workflow", user.key().as_ref(), workflow.workflow_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = user @ WorkflowError::UnauthorizedUser
+    )]
+    pub workflow: Account<'info, Workflow>,
+
+    pub user: Signer<'info>,
+
+    /// CHECK: Step agent receives payment; verified against the current step
+    #[account(mut)]
+    pub agent_authority: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ WorkflowError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RollbackWorkflow<'info> {
+    #[account(
+        mut,
+        seeds = [b"workflow", user.key().as_ref(), workflow.workflow_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = user @ WorkflowError::UnauthorizedUser
+    )]
+    pub workflow: Account<'info, Workflow>,
+
+    /// CHECK: Workflow owner receives the refund; verified against the workflow
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+
+    pub caller: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ WorkflowError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct CloseWorkflow<'info> {
+    #[account(
+        mut,
+        seeds = [b"workflow", user.key().as_ref(), workflow.workflow_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = user @ WorkflowError::UnauthorizedUser,
+        close = user
+    )]
+    pub workflow: Account<'info, Workflow>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Workflow {
+    pub user: Pubkey,
+    pub workflow_id: u64,
+    pub status: WorkflowStatus,
+    pub current_step: u8,
+    pub total_amount: u64,
+    pub released_amount: u64,
+    pub refunded_amount: u64,
+    pub step_timeout: i64,
+    pub created_at: i64,
+    #[max_len(MAX_STEPS)]
+    pub steps: Vec<WorkflowStep>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct WorkflowStep {
+    pub agent_id: Pubkey,
+    pub agent_authority: Pubkey,
+    pub amount: u64,
+    pub status: StepStatus,
+    /// Output hash of the previous step (or the user's input for step 0)
+    pub input_hash: [u8; 32],
+    pub output_hash: [u8; 32],
+    #[max_len(128)]
+    pub output_uri: String,
+    pub started_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct StepSpec {
+    pub agent_id: Pubkey,
+    pub agent_authority: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum WorkflowStatus {
+    Active,
+    Completed,
+    RolledBack,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum StepStatus {
+    Pending,
+    Running,
+    Submitted,
+    Completed,
+    Failed,
+    Refunded,
+}
+
+#[event]
+pub struct WorkflowCreated {
+    pub workflow: Pubkey,
+    pub user: Pubkey,
+    pub workflow_id: u64,
+    pub step_count: u8,
+    pub total_amount: u64,
+}
+
+#[event]
+pub struct StepResultSubmitted {
+    pub workflow: Pubkey,
+    pub step_index: u8,
+    pub agent_id: Pubkey,
+    pub output_hash: [u8; 32],
+}
+
+#[event]
+pub struct StepCompleted {
+    pub workflow: Pubkey,
+    pub step_index: u8,
+    pub agent_id: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct WorkflowCompleted {
+    pub workflow: Pubkey,
+    pub user: Pubkey,
+    pub released_amount: u64,
+}
+
+#[event]
+pub struct WorkflowRolledBack {
+    pub workflow: Pubkey,
+    pub failed_step: u8,
+    pub refunded_amount: u64,
+    pub caller: Pubkey,
+    pub reason: String,
+}
+
+#[error_code]
+pub enum WorkflowError {
+    #[msg("Workflow must have between 1 and 8 steps")]
+    InvalidStepCount,
+    #[msg("Step timeout must be positive")]
+    InvalidTimeout,
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    #[msg("Output URI is too long (max 128 characters)")]
+    OutputUriTooLong,
+    #[msg("Reason is too long (max 200 characters)")]
+    ReasonTooLong,
+    #[msg("Workflow is not active")]
+    WorkflowNotActive,
+    #[msg("Workflow is still active")]
+    WorkflowStillActive,
+    #[msg("Current step is not running")]
+    StepNotRunning,
+    #[msg("Current step has no submitted result")]
+    StepNotSubmitted,
+    #[msg("Only the user, the step agent, or anyone after timeout may roll back")]
+    UnauthorizedRollback,
+    #[msg("Unauthorized user")]
+    UnauthorizedUser,
+    #[msg("Unauthorized step agent")]
+    UnauthorizedAgent,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Protocol is paused by the guardian")]
+    ProtocolPaused,
+}
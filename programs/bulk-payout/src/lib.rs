@@ -0,0 +1,383 @@
+use anchor_lang::prelude::*;
+use protocol_guardian::GuardianConfig;
+use solana_sha256_hasher::hashv;
+
+declare_id!("9dpKrYyADPCPLPoa3KgqFWQ57omB1FePMZSGErkhSE2P");
+
+/// Domain prefix for merkle leaves
+pub const LEAF_PREFIX: &[u8] = &[0];
+/// Domain prefix for merkle interior nodes
+pub const NODE_PREFIX: &[u8] = &[1];
+
+#[program]
+pub mod bulk_payout {
+    use super::*;
+
+    /// Create a payout batch from a merkle root of (index, recipient, amount) leaves and fund its vault
+    pub fn create_batch(
+        ctx: Context<CreateBatch>,
+        batch_id: u64,
+        merkle_root: [u8; 32],
+        total_amount: u64,
+        num_recipients: u32,
+        purpose: PayoutPurpose,
+        reclaim_after: i64,
+    ) -> Result<()> {
+        require!(total_amount > 0, PayoutError::InvalidAmount);
+        require!(num_recipients > 0, PayoutError::InvalidRecipients);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(reclaim_after > now, PayoutError::InvalidReclaimTime);
+
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.payout_vault.key(),
+            total_amount,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_instruction,
+            &[
+                ctx.accounts.authority.to_account_info(),
+                ctx.accounts.payout_vault.to_account_info(),
+            ],
+        )?;
+
+        let batch = &mut ctx.accounts.batch;
+        batch.batch_id = batch_id;
+        batch.authority = ctx.accounts.authority.key();
+        batch.merkle_root = merkle_root;
+        batch.purpose = purpose.clone();
+        batch.total_amount = total_amount;
+        batch.claimed_amount = 0;
+        batch.num_recipients = num_recipients;
+        batch.claimed_count = 0;
+        batch.created_at = now;
+        batch.reclaim_after = reclaim_after;
+
+        emit!(BatchCreated {
+            batch: batch.key(),
+            authority: batch.authority,
+            purpose,
+            merkle_root,
+            total_amount,
+            num_recipients,
+        });
+
+        Ok(())
+    }
+
+    /// Pay one leaf of a batch to its recipient (permissionless, so a crank can drain large batches)
+    pub fn claim(
+        ctx: Context<Claim>,
+        index: u32,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let batch = &ctx.accounts.batch;
+        require!(index < batch.num_recipients, PayoutError::InvalidProof);
+        require!(proof.len() <= 32, PayoutError::InvalidProof);
+
+        let leaf = leaf_hash(index, &ctx.accounts.recipient.key(), amount);
+        require!(
+            verify_proof(&proof, batch.merkle_root, leaf),
+            PayoutError::InvalidProof
+        );
+        require!(
+            batch.claimed_amount + amount <= batch.total_amount,
+            PayoutError::BatchExhausted
+        );
+
+        let batch_key = batch.key();
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.payout_vault.key(),
+            &ctx.accounts.recipient.key(),
+            amount,
+        );
+
+        anchor_lang::solana_program::program::invoke_signed(
+            &transfer_instruction,
+            &[
+                ctx.accounts.payout_vault.to_account_info(),
+                ctx.accounts.recipient.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            &[&[b"payout_vault", batch_key.as_ref(), &[ctx.bumps.payout_vault]]],
+        )?;
+
+        let receipt = &mut ctx.accounts.claim_receipt;
+        receipt.batch = batch_key;
+        receipt.index = index;
+        receipt.recipient = ctx.accounts.recipient.key();
+        receipt.amount = amount;
+        receipt.claimed_at = Clock::get()?.unix_timestamp;
+
+        let batch = &mut ctx.accounts.batch;
+        batch.claimed_amount += amount;
+        batch.claimed_count += 1;
+
+        emit!(PayoutClaimed {
+            batch: batch_key,
+            index,
+            recipient: receipt.recipient,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Return unclaimed funds to the authority after the reclaim time and close the batch
+    pub fn reclaim_batch(
+        ctx: Context<ReclaimBatch>,
+    ) -> Result<()> {
+        let batch = &ctx.accounts.batch;
+        require!(
+            Clock::get()?.unix_timestamp >= batch.reclaim_after,
+            PayoutError::ReclaimTooEarly
+        );
+
+        let batch_key = batch.key();
+        let remaining = ctx.accounts.payout_vault.lamports();
+
+        if remaining > 0 {
+            let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.payout_vault.key(),
+                &ctx.accounts.authority.key(),
+                remaining,
+            );
+
+            anchor_lang::solana_program::program::invoke_signed(
+                &transfer_instruction,
+                &[
+                    ctx.accounts.payout_vault.to_account_info(),
+                    ctx.accounts.authority.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+                &[&[b"payout_vault", batch_key.as_ref(), &[ctx.bumps.payout_vault]]],
+            )?;
+        }
+
+        emit!(BatchReclaimed {
+            batch: batch_key,
+            authority: batch.authority,
+            claimed_count: batch.claimed_count,
+            reclaimed_amount: remaining,
+        });
+
+        Ok(())
+    }
+}
+
+/// Merkle leaf for a payout entry
+pub fn leaf_hash(index: u32, recipient: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[
+        LEAF_PREFIX,
+        &index.to_le_bytes(),
+        recipient.as_ref(),
+        &amount.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Verify a merkle proof, hashing each pair in sorted order
+pub fn verify_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let mut node = leaf;
+    for sibling in proof {
+        node = if node <= *sibling {
+            hashv(&[NODE_PREFIX, &node, sibling]).to_bytes()
+        } else {
+            hashv(&[NODE_PREFIX, sibling, &node]).to_bytes()
+        };
+    }
+    node == root
+}
+
+#[derive(Accounts)]
+#[instruction(batch_id: u64)]
+pub struct CreateBatch<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PayoutBatch::INIT_SPACE,
+        seeds = [b"payout_batch", authority.key().as_ref(), batch_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub batch: Account<'info, PayoutBatch>,
+
+    #[account(
+        mut,
+        seeds = [b"payout_vault", batch.key().as_ref()],
+        bump
+    )]
+    pub payout_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ PayoutError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(index: u32)]
+pub struct Claim<'info> {
+    #[account(
+        mut,
+        seeds = [b"payout_batch", batch.authority.as_ref(), batch.batch_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub batch: Account<'info, PayoutBatch>,
+
+    #[account(
+        mut,
+        seeds = [b"payout_vault", batch.key().as_ref()],
+        bump
+    )]
+    pub payout_vault: SystemAccount<'info>,
+
+    /// Replay guard: one claim per leaf index
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ClaimReceipt::INIT_SPACE,
+        seeds = [b"claim", batch.key().as_ref(), index.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub claim_receipt: Account<'info, ClaimReceipt>,
+
+    /// CHECK: Recipient committed to by the merkle leaf
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ PayoutError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimBatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"payout_batch", authority.key().as_ref(), batch.batch_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = authority @ PayoutError::UnauthorizedAuthority,
+        close = authority
+    )]
+    pub batch: Account<'info, PayoutBatch>,
+
+    #[account(
+        mut,
+        seeds = [b"payout_vault", batch.key().as_ref()],
+        bump
+    )]
+    pub payout_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ PayoutError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PayoutBatch {
+    pub batch_id: u64,
+    pub authority: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub purpose: PayoutPurpose,
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    pub num_recipients: u32,
+    pub claimed_count: u32,
+    pub created_at: i64,
+    /// After this time the authority may reclaim unclaimed funds
+    pub reclaim_after: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ClaimReceipt {
+    pub batch: Pubkey,
+    pub index: u32,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub claimed_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum PayoutPurpose {
+    EpochRewards,
+    ReferralSettlement,
+    InsurancePayout,
+    Other,
+}
+
+#[event]
+pub struct BatchCreated {
+    pub batch: Pubkey,
+    pub authority: Pubkey,
+    pub purpose: PayoutPurpose,
+    pub merkle_root: [u8; 32],
+    pub total_amount: u64,
+    pub num_recipients: u32,
+}
+
+#[event]
+pub struct PayoutClaimed {
+    pub batch: Pubkey,
+    pub index: u32,
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct BatchReclaimed {
+    pub batch: Pubkey,
+    pub authority: Pubkey,
+    pub claimed_count: u32,
+    pub reclaimed_amount: u64,
+}
+
+#[error_code]
+pub enum PayoutError {
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Batch must have at least one recipient")]
+    InvalidRecipients,
+    #[msg("Reclaim time must be in the future")]
+    InvalidReclaimTime,
+    #[msg("Invalid merkle proof")]
+    InvalidProof,
+    #[msg("Claim exceeds the batch's funded amount")]
+    BatchExhausted,
+    #[msg("Batch cannot be reclaimed yet")]
+    ReclaimTooEarly,
+    #[msg("Unauthorized batch authority")]
+    UnauthorizedAuthority,
+    #[msg("Protocol is paused by the guardian")]
+    ProtocolPaused,
+}
@@ -0,0 +1,461 @@
+use anchor_lang::prelude::*;
+use protocol_guardian::GuardianConfig;
+use solana_sha256_hasher::hashv;
+
+declare_id!("87sHa2M5wrMGvctgW2JtNhdrSSsmzAxMM8EtYFtKdA4z");
+
+#[program]
+pub mod credential_escrow {
+    use super::*;
+
+    /// List upstream API access for sale
+    pub fn create_listing(
+        ctx: Context<CreateListing>,
+        listing_id: u64,
+        price: u64,
+        description_uri: String,
+        delivery_window: i64,
+    ) -> Result<()> {
+        require!(price > 0, CredentialError::InvalidPrice);
+        require!(description_uri.len() <= 200, CredentialError::UriTooLong);
+        require!(delivery_window > 0, CredentialError::InvalidDeliveryWindow);
+
+        let listing = &mut ctx.accounts.listing;
+        listing.seller = ctx.accounts.seller.key();
+        listing.listing_id = listing_id;
+        listing.price = price;
+        listing.description_uri = description_uri;
+        listing.delivery_window = delivery_window;
+        listing.is_active = true;
+        listing.total_sales = 0;
+        listing.created_at = Clock::get()?.unix_timestamp;
+
+        emit!(ListingCreated {
+            listing: listing.key(),
+            seller: listing.seller,
+            listing_id,
+            price,
+        });
+
+        Ok(())
+    }
+
+    /// Reprice or pause a listing (seller only)
+    pub fn update_listing(
+        ctx: Context<UpdateListing>,
+        price: Option<u64>,
+        is_active: Option<bool>,
+    ) -> Result<()> {
+        let listing = &mut ctx.accounts.listing;
+
+        if let Some(price) = price {
+            require!(price > 0, CredentialError::InvalidPrice);
+            listing.price = price;
+        }
+        if let Some(is_active) = is_active {
+            listing.is_active = is_active;
+        }
+
+        emit!(ListingUpdated {
+            listing: listing.key(),
+            price: listing.price,
+            is_active: listing.is_active,
+        });
+
+        Ok(())
+    }
+
+    /// Fund a purchase; the buyer's encryption key tells the seller who to encrypt for
+    pub fn purchase(
+        ctx: Context<Purchase>,
+        buyer_encryption_key: [u8; 32],
+    ) -> Result<()> {
+        let listing = &ctx.accounts.listing;
+        require!(listing.is_active, CredentialError::ListingInactive);
+
+        let price = listing.price;
+
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.buyer.key(),
+            &ctx.accounts.purchase.key(),
+            price,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_instruction,
+            &[
+                ctx.accounts.buyer.to_account_info(),
+                ctx.accounts.purchase.to_account_info(),
+            ],
+        )?;
+
+        let clock = Clock::get()?;
+        let purchase = &mut ctx.accounts.purchase;
+        purchase.listing = listing.key();
+        purchase.seller = listing.seller;
+        purchase.buyer = ctx.accounts.buyer.key();
+        purchase.amount = price;
+        purchase.buyer_encryption_key = buyer_encryption_key;
+        purchase.ciphertext_hash = [0; 32];
+        purchase.ciphertext_uri = String::new();
+        purchase.key_hash = [0; 32];
+        purchase.revealed_key = None;
+        purchase.status = PurchaseStatus::Funded;
+        purchase.created_at = clock.unix_timestamp;
+        purchase.delivery_deadline = clock.unix_timestamp + listing.delivery_window;
+
+        emit!(CredentialPurchased {
+            purchase: purchase.key(),
+            listing: purchase.listing,
+            buyer: purchase.buyer,
+            amount: price,
+        });
+
+        Ok(())
+    }
+
+    /// Commit the encrypted credential and the hash of the key that decrypts it (seller only)
+    pub fn commit_credential(
+        ctx: Context<SellerAction>,
+        ciphertext_hash: [u8; 32],
+        ciphertext_uri: String,
+        key_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(ciphertext_uri.len() <= 200, CredentialError::UriTooLong);
+
+        let purchase = &mut ctx.accounts.purchase;
+        require!(purchase.status == PurchaseStatus::Funded, CredentialError::InvalidStatus);
+        require!(
+            Clock::get()?.unix_timestamp < purchase.delivery_deadline,
+            CredentialError::DeliveryDeadlinePassed
+        );
+
+        purchase.ciphertext_hash = ciphertext_hash;
+        purchase.ciphertext_uri = ciphertext_uri;
+        purchase.key_hash = key_hash;
+        purchase.status = PurchaseStatus::Committed;
+
+        emit!(CredentialCommitted {
+            purchase: purchase.key(),
+            ciphertext_hash,
+            key_hash,
+        });
+
+        Ok(())
+    }
+
+    /// Reveal the decryption key; a key matching the committed hash releases payment
+    pub fn reveal_key(
+        ctx: Context<SellerAction>,
+        decryption_key: [u8; 32],
+    ) -> Result<()> {
+        let purchase_info = ctx.accounts.purchase.to_account_info();
+        let purchase = &mut ctx.accounts.purchase;
+        require!(purchase.status == PurchaseStatus::Committed, CredentialError::InvalidStatus);
+        require!(
+            hash_key(&decryption_key) == purchase.key_hash,
+            CredentialError::KeyHashMismatch
+        );
+
+        let amount = purchase.amount;
+        purchase.revealed_key = Some(decryption_key);
+        purchase.status = PurchaseStatus::Completed;
+
+        **purchase_info.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.seller.try_borrow_mut_lamports()? += amount;
+
+        let listing = &mut ctx.accounts.listing;
+        listing.total_sales += 1;
+
+        emit!(CredentialDelivered {
+            purchase: purchase.key(),
+            seller: purchase.seller,
+            buyer: purchase.buyer,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Refund the buyer when the seller missed the delivery deadline
+    pub fn refund_expired(
+        ctx: Context<RefundExpired>,
+    ) -> Result<()> {
+        let purchase_info = ctx.accounts.purchase.to_account_info();
+        let purchase = &mut ctx.accounts.purchase;
+        require!(
+            purchase.status == PurchaseStatus::Funded || purchase.status == PurchaseStatus::Committed,
+            CredentialError::InvalidStatus
+        );
+        require!(
+            Clock::get()?.unix_timestamp >= purchase.delivery_deadline,
+            CredentialError::DeliveryWindowOpen
+        );
+
+        let amount = purchase.amount;
+        purchase.status = PurchaseStatus::Refunded;
+
+        **purchase_info.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.buyer.try_borrow_mut_lamports()? += amount;
+
+        emit!(PurchaseRefunded {
+            purchase: purchase.key(),
+            buyer: purchase.buyer,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Close a settled purchase so the buyer can purchase the listing again
+    pub fn close_purchase(
+        ctx: Context<ClosePurchase>,
+    ) -> Result<()> {
+        let status = &ctx.accounts.purchase.status;
+        require!(
+            *status == PurchaseStatus::Completed || *status == PurchaseStatus::Refunded,
+            CredentialError::InvalidStatus
+        );
+
+        Ok(())
+    }
+}
+
+/// Hash a decryption key the same way sellers commit it off-chain
+pub fn hash_key(decryption_key: &[u8; 32]) -> [u8; 32] {
+    hashv(&[decryption_key]).to_bytes()
+}
+
+#[derive(Accounts)]
+#[instruction(listing_id: u64)]
+pub struct CreateListing<'info> {
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + CredentialListing::INIT_SPACE,
+        seeds = [b"credential_listing", seller.key().as_ref(), listing_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, CredentialListing>,
+
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateListing<'info> {
+    #[account(
+        mut,
+        seeds = [b"credential_listing", seller.key().as_ref(), listing.listing_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = seller @ CredentialError::UnauthorizedSeller
+    )]
+    pub listing: Account<'info, CredentialListing>,
+
+    pub seller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Purchase<'info> {
+    #[account(
+        seeds = [b"credential_listing", listing.seller.as_ref(), listing.listing_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, CredentialListing>,
+
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + CredentialPurchase::INIT_SPACE,
+        seeds = [b"credential_purchase", listing.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub purchase: Account<'info, CredentialPurchase>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ CredentialError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SellerAction<'info> {
+    #[account(
+        mut,
+        seeds = [b"credential_listing", seller.key().as_ref(), listing.listing_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = seller @ CredentialError::UnauthorizedSeller
+    )]
+    pub listing: Account<'info, CredentialListing>,
+
+    #[account(
+        mut,
+        seeds = [b"credential_purchase", listing.key().as_ref(), purchase.buyer.as_ref()],
+        bump,
+        has_one = listing,
+        has_one = seller @ CredentialError::UnauthorizedSeller
+    )]
+    pub purchase: Account<'info, CredentialPurchase>,
+
+    #[account(mut)]
+    pub seller: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ CredentialError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RefundExpired<'info> {
+    #[account(
+        mut,
+        seeds = [b"credential_purchase", purchase.listing.as_ref(), buyer.key().as_ref()],
+        bump,
+        has_one = buyer @ CredentialError::UnauthorizedBuyer
+    )]
+    pub purchase: Account<'info, CredentialPurchase>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePurchase<'info> {
+    #[account(
+        mut,
+        seeds = [b"credential_purchase", purchase.listing.as_ref(), buyer.key().as_ref()],
+        bump,
+        has_one = buyer @ CredentialError::UnauthorizedBuyer,
+        close = buyer
+    )]
+    pub purchase: Account<'info, CredentialPurchase>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct CredentialListing {
+    pub seller: Pubkey,
+    pub listing_id: u64,
+    pub price: u64,
+    #[max_len(200)]
+    pub description_uri: String,
+    pub delivery_window: i64,
+    pub is_active: bool,
+    pub total_sales: u64,
+    pub created_at: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct CredentialPurchase {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+    /// x25519 public key the credential must be encrypted to
+    pub buyer_encryption_key: [u8; 32],
+    pub ciphertext_hash: [u8; 32],
+    #[max_len(200)]
+    pub ciphertext_uri: String,
+    pub key_hash: [u8; 32],
+    pub revealed_key: Option<[u8; 32]>,
+    pub status: PurchaseStatus,
+    pub created_at: i64,
+    pub delivery_deadline: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum PurchaseStatus {
+    Funded,
+    Committed,
+    Completed,
+    Refunded,
+}
+
+#[event]
+pub struct ListingCreated {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub listing_id: u64,
+    pub price: u64,
+}
+
+#[event]
+pub struct ListingUpdated {
+    pub listing: Pubkey,
+    pub price: u64,
+    pub is_active: bool,
+}
+
+#[event]
+pub struct CredentialPurchased {
+    pub purchase: Pubkey,
+    pub listing: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct CredentialCommitted {
+    pub purchase: Pubkey,
+    pub ciphertext_hash: [u8; 32],
+    pub key_hash: [u8; 32],
+}
+
+#[event]
+pub struct CredentialDelivered {
+    pub purchase: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PurchaseRefunded {
+    pub purchase: Pubkey,
+    pub buyer: Pubkey,
+    pub amount: u64,
+}
+
+#[error_code]
+pub enum CredentialError {
+    #[msg("Price must be greater than zero")]
+    InvalidPrice,
+    #[msg("URI is too long (max 200 characters)")]
+    UriTooLong,
+    #[msg("Delivery window must be positive")]
+    InvalidDeliveryWindow,
+    #[msg("Listing is not active")]
+    ListingInactive,
+    #[msg("Purchase is not in the expected status")]
+    InvalidStatus,
+    #[msg("Delivery deadline has passed")]
+    DeliveryDeadlinePassed,
+    #[msg("Delivery window is still open")]
+    DeliveryWindowOpen,
+    #[msg("Decryption key does not match the committed hash")]
+    KeyHashMismatch,
+    #[msg("Unauthorized seller")]
+    UnauthorizedSeller,
+    #[msg("Unauthorized buyer")]
+    UnauthorizedBuyer,
+    #[msg("Protocol is paused by the guardian")]
+    ProtocolPaused,
+}
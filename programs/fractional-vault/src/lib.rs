@@ -0,0 +1,654 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount, Transfer};
+use agent_registry::AgentProfile;
+use protocol_guardian::GuardianConfig;
+
+declare_id!("4ruBEinMVrF6Zb4prt1BSRs4R113pzz42WTHJ7ybRDk1");
+
+/// Fixed-point scale for revenue-per-share accounting
+pub const REVENUE_SCALE: u128 = 1_000_000_000_000;
+
+/// Decimals of the fungible share mint
+pub const SHARE_DECIMALS: u8 = 6;
+
+#[program]
+pub mod fractional_vault {
+    use super::*;
+
+    /// Lock an agent NFT in a vault and mint fungible shares to its creator
+    pub fn fractionalize(
+        ctx: Context<Fractionalize>,
+        total_shares: u64,
+    ) -> Result<()> {
+        require!(total_shares > 0, VaultError::InvalidShareAmount);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.creator_nft_account.to_account_info(),
+                    to: ctx.accounts.vault_nft_account.to_account_info(),
+                    authority: ctx.accounts.creator.to_account_info(),
+                },
+            ),
+            1,
+        )?;
+
+        let nft_mint = ctx.accounts.nft_mint.key();
+        let vault_seeds: &[&[&[u8]]] = &[&[b"fractional_vault", nft_mint.as_ref(), &[ctx.bumps.vault]]];
+
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    to: ctx.accounts.creator_share_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                vault_seeds,
+            ),
+            total_shares,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.agent_id = ctx.accounts.agent_profile.agent_id;
+        vault.curator = ctx.accounts.creator.key();
+        vault.nft_mint = nft_mint;
+        vault.share_mint = ctx.accounts.share_mint.key();
+        vault.total_shares = total_shares;
+        vault.total_staked = 0;
+        vault.acc_revenue_per_share = 0;
+        vault.accounted_lamports = 0;
+        vault.undistributed = 0;
+        vault.total_revenue = 0;
+        vault.is_redeemed = false;
+        vault.bump = ctx.bumps.vault;
+
+        emit!(AgentFractionalized {
+            vault: vault.key(),
+            agent_id: vault.agent_id,
+            nft_mint,
+            share_mint: vault.share_mint,
+            total_shares,
+        });
+
+        Ok(())
+    }
+
+    /// Account for royalty payouts credited to the vault since the last sync (permissionless)
+    pub fn sync_revenue(
+        ctx: Context<SyncRevenue>,
+    ) -> Result<()> {
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let rent_reserve = Rent::get()?.minimum_balance(vault_info.data_len());
+        let vault = &mut ctx.accounts.vault;
+
+        let received = accrue_revenue(vault, vault_info.lamports(), rent_reserve)?;
+
+        emit!(RevenueSynced {
+            vault: vault.key(),
+            received,
+            acc_revenue_per_share: vault.acc_revenue_per_share,
+        });
+
+        Ok(())
+    }
+
+    /// Stake shares to earn a pro-rata cut of the agent's revenue
+    pub fn stake_shares(
+        ctx: Context<StakeShares>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidShareAmount);
+        require!(!ctx.accounts.vault.is_redeemed, VaultError::VaultRedeemed);
+
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let rent_reserve = Rent::get()?.minimum_balance(vault_info.data_len());
+        accrue_revenue(&mut ctx.accounts.vault, vault_info.lamports(), rent_reserve)?;
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner_share_account.to_account_info(),
+                    to: ctx.accounts.vault_share_account.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        let position = &mut ctx.accounts.position;
+        position.vault = vault.key();
+        position.owner = ctx.accounts.owner.key();
+        settle_position(position, vault.acc_revenue_per_share);
+
+        position.staked += amount;
+        position.reward_debt = reward_debt(position.staked, vault.acc_revenue_per_share);
+        vault.total_staked += amount;
+
+        emit!(SharesStaked {
+            vault: vault.key(),
+            owner: position.owner,
+            amount,
+            total_staked: vault.total_staked,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw staked shares back to the owner's wallet
+    pub fn unstake_shares(
+        ctx: Context<UnstakeShares>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(
+            amount > 0 && amount <= ctx.accounts.position.staked,
+            VaultError::InvalidShareAmount
+        );
+
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let rent_reserve = Rent::get()?.minimum_balance(vault_info.data_len());
+        accrue_revenue(&mut ctx.accounts.vault, vault_info.lamports(), rent_reserve)?;
+
+        let nft_mint = ctx.accounts.vault.nft_mint;
+        let vault_seeds: &[&[&[u8]]] = &[&[b"fractional_vault", nft_mint.as_ref(), &[ctx.accounts.vault.bump]]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault_share_account.to_account_info(),
+                    to: ctx.accounts.owner_share_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                vault_seeds,
+            ),
+            amount,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        let position = &mut ctx.accounts.position;
+        settle_position(position, vault.acc_revenue_per_share);
+
+        position.staked -= amount;
+        position.reward_debt = reward_debt(position.staked, vault.acc_revenue_per_share);
+        vault.total_staked -= amount;
+
+        emit!(SharesUnstaked {
+            vault: vault.key(),
+            owner: position.owner,
+            amount,
+            total_staked: vault.total_staked,
+        });
+
+        Ok(())
+    }
+
+    /// Claim accrued revenue for a staked position
+    pub fn claim_revenue(
+        ctx: Context<ClaimRevenue>,
+    ) -> Result<()> {
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let rent_reserve = Rent::get()?.minimum_balance(vault_info.data_len());
+        accrue_revenue(&mut ctx.accounts.vault, vault_info.lamports(), rent_reserve)?;
+
+        let vault = &mut ctx.accounts.vault;
+        let position = &mut ctx.accounts.position;
+        settle_position(position, vault.acc_revenue_per_share);
+        position.reward_debt = reward_debt(position.staked, vault.acc_revenue_per_share);
+
+        let amount = position.unclaimed;
+        require!(amount > 0, VaultError::NothingToClaim);
+
+        position.unclaimed = 0;
+        position.total_claimed = position.total_claimed.saturating_add(amount);
+        vault.accounted_lamports -= amount;
+
+        **vault_info.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.owner.try_borrow_mut_lamports()? += amount;
+
+        emit!(RevenueClaimed {
+            vault: vault.key(),
+            owner: position.owner,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Burn every share to take the agent NFT back out of the vault
+    pub fn redeem(
+        ctx: Context<Redeem>,
+    ) -> Result<()> {
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let rent_reserve = Rent::get()?.minimum_balance(vault_info.data_len());
+        accrue_revenue(&mut ctx.accounts.vault, vault_info.lamports(), rent_reserve)?;
+
+        let total_shares = ctx.accounts.vault.total_shares;
+        let nft_mint = ctx.accounts.vault.nft_mint;
+        let vault_seeds: &[&[&[u8]]] = &[&[b"fractional_vault", nft_mint.as_ref(), &[ctx.accounts.vault.bump]]];
+
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.share_mint.to_account_info(),
+                    from: ctx.accounts.redeemer_share_account.to_account_info(),
+                    authority: ctx.accounts.redeemer.to_account_info(),
+                },
+            ),
+            total_shares,
+        )?;
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault_nft_account.to_account_info(),
+                    to: ctx.accounts.redeemer_nft_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                vault_seeds,
+            ),
+            1,
+        )?;
+
+        // Revenue that arrived while nothing was staked belongs to the sole remaining holder
+        let vault = &mut ctx.accounts.vault;
+        let leftover = vault.undistributed;
+        vault.undistributed = 0;
+        vault.accounted_lamports -= leftover;
+        vault.is_redeemed = true;
+
+        if leftover > 0 {
+            **vault_info.try_borrow_mut_lamports()? -= leftover;
+            **ctx.accounts.redeemer.try_borrow_mut_lamports()? += leftover;
+        }
+
+        emit!(AgentRedeemed {
+            vault: vault.key(),
+            redeemer: ctx.accounts.redeemer.key(),
+            nft_mint,
+            leftover_revenue: leftover,
+        });
+
+        Ok(())
+    }
+}
+
+// Helper function to fold newly credited lamports into the revenue-per-share index
+fn accrue_revenue(vault: &mut FractionalVault, balance: u64, rent_reserve: u64) -> Result<u64> {
+    let received = balance
+        .saturating_sub(rent_reserve)
+        .saturating_sub(vault.accounted_lamports);
+
+    vault.accounted_lamports += received;
+    vault.total_revenue = vault.total_revenue.saturating_add(received);
+
+    let distributable = received
+        .checked_add(vault.undistributed)
+        .ok_or(VaultError::MathOverflow)?;
+
+    if vault.total_staked == 0 || vault.is_redeemed {
+        vault.undistributed = distributable;
+        return Ok(received);
+    }
+
+    vault.acc_revenue_per_share += (distributable as u128) * REVENUE_SCALE / (vault.total_staked as u128);
+    vault.undistributed = 0;
+
+    Ok(received)
+}
+
+// Helper function to move a position's pending revenue into its unclaimed balance
+fn settle_position(position: &mut SharePosition, acc_revenue_per_share: u128) {
+    let accrued = reward_debt(position.staked, acc_revenue_per_share);
+    let pending = accrued.saturating_sub(position.reward_debt);
+    position.unclaimed = position.unclaimed.saturating_add(pending as u64);
+}
+
+// Helper function to compute revenue already accounted for a staked amount
+fn reward_debt(staked: u64, acc_revenue_per_share: u128) -> u128 {
+    (staked as u128) * acc_revenue_per_share / REVENUE_SCALE
+}
+
+#[derive(Accounts)]
+pub struct Fractionalize<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + FractionalVault::INIT_SPACE,
+        seeds = [b"fractional_vault", nft_mint.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, FractionalVault>,
+
+    // Not re-derived from `creator`: the profile keeps its address when the NFT changes hands
+    #[account(
+        has_one = creator @ VaultError::UnauthorizedCurator,
+        constraint = agent_profile.nft_mint == nft_mint.key() @ VaultError::NftMismatch
+    )]
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    pub nft_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = creator,
+    )]
+    pub creator_nft_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = creator,
+        associated_token::mint = nft_mint,
+        associated_token::authority = vault,
+    )]
+    pub vault_nft_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"share_mint", vault.key().as_ref()],
+        bump,
+        mint::decimals = SHARE_DECIMALS,
+        mint::authority = vault,
+    )]
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = creator,
+        associated_token::mint = share_mint,
+        associated_token::authority = creator,
+    )]
+    pub creator_share_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = creator,
+        associated_token::mint = share_mint,
+        associated_token::authority = vault,
+    )]
+    pub vault_share_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ VaultError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SyncRevenue<'info> {
+    #[account(
+        mut,
+        seeds = [b"fractional_vault", vault.nft_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, FractionalVault>,
+}
+
+#[derive(Accounts)]
+pub struct StakeShares<'info> {
+    #[account(
+        mut,
+        seeds = [b"fractional_vault", vault.nft_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, FractionalVault>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + SharePosition::INIT_SPACE,
+        seeds = [b"share_position", vault.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, SharePosition>,
+
+    #[account(
+        mut,
+        token::mint = vault.share_mint,
+        token::authority = owner,
+    )]
+    pub owner_share_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault.share_mint,
+        associated_token::authority = vault,
+    )]
+    pub vault_share_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakeShares<'info> {
+    #[account(
+        mut,
+        seeds = [b"fractional_vault", vault.nft_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, FractionalVault>,
+
+    #[account(
+        mut,
+        seeds = [b"share_position", vault.key().as_ref(), owner.key().as_ref()],
+        bump,
+        has_one = owner @ VaultError::UnauthorizedOwner
+    )]
+    pub position: Account<'info, SharePosition>,
+
+    #[account(
+        mut,
+        token::mint = vault.share_mint,
+        token::authority = owner,
+    )]
+    pub owner_share_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault.share_mint,
+        associated_token::authority = vault,
+    )]
+    pub vault_share_account: Account<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRevenue<'info> {
+    #[account(
+        mut,
+        seeds = [b"fractional_vault", vault.nft_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, FractionalVault>,
+
+    #[account(
+        mut,
+        seeds = [b"share_position", vault.key().as_ref(), owner.key().as_ref()],
+        bump,
+        has_one = owner @ VaultError::UnauthorizedOwner
+    )]
+    pub position: Account<'info, SharePosition>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ VaultError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct Redeem<'info> {
+    #[account(
+        mut,
+        seeds = [b"fractional_vault", vault.nft_mint.as_ref()],
+        bump = vault.bump,
+        has_one = share_mint,
+        constraint = !vault.is_redeemed @ VaultError::VaultRedeemed
+    )]
+    pub vault: Account<'info, FractionalVault>,
+
+    #[account(mut)]
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = redeemer,
+    )]
+    pub redeemer_share_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = vault.nft_mint,
+        associated_token::authority = vault,
+    )]
+    pub vault_nft_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = vault.nft_mint,
+        token::authority = redeemer,
+    )]
+    pub redeemer_nft_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub redeemer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ VaultError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+/// Custodies an agent NFT; royalty-splitter payouts for the agent are sent to this address
+#[account]
+#[derive(InitSpace)]
+pub struct FractionalVault {
+    pub agent_id: Pubkey,
+    pub curator: Pubkey,
+    pub nft_mint: Pubkey,
+    pub share_mint: Pubkey,
+    pub total_shares: u64,
+    pub total_staked: u64,
+    pub acc_revenue_per_share: u128,
+    /// Revenue lamports held by the vault on top of its rent reserve
+    pub accounted_lamports: u64,
+    /// Revenue received while no shares were staked
+    pub undistributed: u64,
+    pub total_revenue: u64,
+    pub is_redeemed: bool,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct SharePosition {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub staked: u64,
+    pub reward_debt: u128,
+    pub unclaimed: u64,
+    pub total_claimed: u64,
+}
+
+#[event]
+pub struct AgentFractionalized {
+    pub vault: Pubkey,
+    pub agent_id: Pubkey,
+    pub nft_mint: Pubkey,
+    pub share_mint: Pubkey,
+    pub total_shares: u64,
+}
+
+#[event]
+pub struct RevenueSynced {
+    pub vault: Pubkey,
+    pub received: u64,
+    pub acc_revenue_per_share: u128,
+}
+
+#[event]
+pub struct SharesStaked {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub total_staked: u64,
+}
+
+#[event]
+pub struct SharesUnstaked {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub total_staked: u64,
+}
+
+#[event]
+pub struct RevenueClaimed {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AgentRedeemed {
+    pub vault: Pubkey,
+    pub redeemer: Pubkey,
+    pub nft_mint: Pubkey,
+    pub leftover_revenue: u64,
+}
+
+#[error_code]
+pub enum VaultError {
+    #[msg("Invalid share amount")]
+    InvalidShareAmount,
+    #[msg("NFT mint does not belong to this agent")]
+    NftMismatch,
+    #[msg("Only the agent creator can fractionalize")]
+    UnauthorizedCurator,
+    #[msg("Unauthorized position owner")]
+    UnauthorizedOwner,
+    #[msg("No revenue to claim")]
+    NothingToClaim,
+    #[msg("Vault has already been redeemed")]
+    VaultRedeemed,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Protocol is paused by the guardian")]
+    ProtocolPaused,
+}
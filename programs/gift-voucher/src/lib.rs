@@ -0,0 +1,380 @@
+use anchor_lang::prelude::*;
+use protocol_guardian::GuardianConfig;
+use usage_metering::program::UsageMetering;
+use usage_metering::UsageMeter;
+
+declare_id!("FaKyqhz3tt3QkppS46FpGnWuMMxT49acyJ7UAiSF9rbT");
+
+#[program]
+pub mod gift_voucher {
+    use super::*;
+
+    /// Mint a prepaid voucher for a recipient, funded by the issuer
+    pub fn mint_voucher(
+        ctx: Context<MintVoucher>,
+        voucher_id: u64,
+        recipient: Pubkey,
+        amount: u64,
+        expires_at: i64,
+        memo: String,
+    ) -> Result<()> {
+        require!(amount > 0, VoucherError::InvalidAmount);
+        require!(memo.len() <= 100, VoucherError::MemoTooLong);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(expires_at > now, VoucherError::InvalidExpiry);
+
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.issuer.key(),
+            &ctx.accounts.voucher_vault.key(),
+            amount,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_instruction,
+            &[
+                ctx.accounts.issuer.to_account_info(),
+                ctx.accounts.voucher_vault.to_account_info(),
+            ],
+        )?;
+
+        let voucher = &mut ctx.accounts.voucher;
+        voucher.voucher_id = voucher_id;
+        voucher.issuer = ctx.accounts.issuer.key();
+        voucher.recipient = recipient;
+        voucher.amount = amount;
+        voucher.remaining = amount;
+        voucher.memo = memo;
+        voucher.created_at = now;
+        voucher.expires_at = expires_at;
+
+        emit!(VoucherMinted {
+            voucher: voucher.key(),
+            issuer: voucher.issuer,
+            recipient,
+            amount,
+            expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Hand an unredeemed voucher to another wallet (recipient only)
+    pub fn transfer_voucher(
+        ctx: Context<TransferVoucher>,
+        new_recipient: Pubkey,
+    ) -> Result<()> {
+        let voucher = &mut ctx.accounts.voucher;
+        require!(
+            Clock::get()?.unix_timestamp < voucher.expires_at,
+            VoucherError::VoucherExpired
+        );
+
+        let previous_recipient = voucher.recipient;
+        voucher.recipient = new_recipient;
+
+        emit!(VoucherTransferred {
+            voucher: voucher.key(),
+            previous_recipient,
+            new_recipient,
+        });
+
+        Ok(())
+    }
+
+    /// Redeem part or all of a voucher into the recipient's prepaid usage meter
+    pub fn redeem_credits(
+        ctx: Context<RedeemCredits>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, VoucherError::InvalidAmount);
+
+        let voucher = &ctx.accounts.voucher;
+        require!(
+            Clock::get()?.unix_timestamp < voucher.expires_at,
+            VoucherError::VoucherExpired
+        );
+        require!(amount <= voucher.remaining, VoucherError::InsufficientBalance);
+
+        let voucher_key = voucher.key();
+        let vault_seeds: &[&[&[u8]]] = &[&[
+            b"voucher_vault",
+            voucher_key.as_ref(),
+            &[ctx.bumps.voucher_vault],
+        ]];
+
+        usage_metering::cpi::deposit_credits(
+            CpiContext::new_with_signer(
+                ctx.accounts.usage_metering_program.to_account_info(),
+                usage_metering::cpi::accounts::DepositCredits {
+                    meter: ctx.accounts.meter.to_account_info(),
+                    depositor: ctx.accounts.voucher_vault.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                },
+                vault_seeds,
+            ),
+            amount,
+        )?;
+
+        let voucher = &mut ctx.accounts.voucher;
+        voucher.remaining -= amount;
+
+        emit!(VoucherRedeemed {
+            voucher: voucher_key,
+            recipient: voucher.recipient,
+            meter: ctx.accounts.meter.key(),
+            amount,
+            remaining: voucher.remaining,
+        });
+
+        Ok(())
+    }
+
+    /// Refund the unredeemed balance of an expired voucher to its issuer and close it
+    pub fn refund_expired(
+        ctx: Context<CloseVoucher>,
+    ) -> Result<()> {
+        let voucher = &ctx.accounts.voucher;
+        require!(
+            Clock::get()?.unix_timestamp >= voucher.expires_at,
+            VoucherError::VoucherNotExpired
+        );
+
+        let refund = sweep_vault(&ctx, voucher.remaining)?;
+
+        emit!(VoucherRefunded {
+            voucher: voucher.key(),
+            issuer: voucher.issuer,
+            amount: refund,
+        });
+
+        Ok(())
+    }
+
+    /// Close a fully redeemed voucher and return its rent to the issuer
+    pub fn close_voucher(
+        ctx: Context<CloseVoucher>,
+    ) -> Result<()> {
+        require!(ctx.accounts.voucher.remaining == 0, VoucherError::VoucherNotEmpty);
+
+        sweep_vault(&ctx, 0)?;
+
+        Ok(())
+    }
+}
+
+// Helper function to return the vault's lamports to the issuer, at least `expected`
+fn sweep_vault(ctx: &Context<CloseVoucher>, expected: u64) -> Result<u64> {
+    let balance = ctx.accounts.voucher_vault.lamports();
+    require!(balance >= expected, VoucherError::InsufficientBalance);
+    if balance == 0 {
+        return Ok(0);
+    }
+
+    let voucher_key = ctx.accounts.voucher.key();
+    let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+        &ctx.accounts.voucher_vault.key(),
+        &ctx.accounts.issuer.key(),
+        balance,
+    );
+
+    anchor_lang::solana_program::program::invoke_signed(
+        &transfer_instruction,
+        &[
+            ctx.accounts.voucher_vault.to_account_info(),
+            ctx.accounts.issuer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+        &[&[b"voucher_vault", voucher_key.as_ref(), &[ctx.bumps.voucher_vault]]],
+    )?;
+
+    Ok(balance)
+}
+
+#[derive(Accounts)]
+#[instruction(voucher_id: u64)]
+pub struct MintVoucher<'info> {
+    #[account(
+        init,
+        payer = issuer,
+        space = 8 + Voucher::INIT_SPACE,
+        seeds = [b"voucher", issuer.key().as_ref(), voucher_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub voucher: Account<'info, Voucher>,
+
+    #[account(
+        mut,
+        seeds = [b"voucher_vault", voucher.key().as_ref()],
+        bump
+    )]
+    pub voucher_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub issuer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ VoucherError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct TransferVoucher<'info> {
+    #[account(
+        mut,
+        seeds = [b"voucher", voucher.issuer.as_ref(), voucher.voucher_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = recipient @ VoucherError::UnauthorizedRecipient
+    )]
+    pub voucher: Account<'info, Voucher>,
+
+    pub recipient: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemCredits<'info> {
+    #[account(
+        mut,
+        seeds = [b"voucher", voucher.issuer.as_ref(), voucher.voucher_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = recipient @ VoucherError::UnauthorizedRecipient
+    )]
+    pub voucher: Account<'info, Voucher>,
+
+    #[account(
+        mut,
+        seeds = [b"voucher_vault", voucher.key().as_ref()],
+        bump
+    )]
+    pub voucher_vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = meter.user == recipient.key() @ VoucherError::MeterMismatch
+    )]
+    pub meter: Account<'info, UsageMeter>,
+
+    pub recipient: Signer<'info>,
+
+    pub usage_metering_program: Program<'info, UsageMetering>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ VoucherError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVoucher<'info> {
+    #[account(
+        mut,
+        seeds = [b"voucher", issuer.key().as_ref(), voucher.voucher_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = issuer @ VoucherError::UnauthorizedIssuer,
+        close = issuer
+    )]
+    pub voucher: Account<'info, Voucher>,
+
+    #[account(
+        mut,
+        seeds = [b"voucher_vault", voucher.key().as_ref()],
+        bump
+    )]
+    pub voucher_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub issuer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ VoucherError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Voucher {
+    pub voucher_id: u64,
+    pub issuer: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    /// Unredeemed balance held in the voucher vault
+    pub remaining: u64,
+    #[max_len(100)]
+    pub memo: String,
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct VoucherMinted {
+    pub voucher: Pubkey,
+    pub issuer: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct VoucherTransferred {
+    pub voucher: Pubkey,
+    pub previous_recipient: Pubkey,
+    pub new_recipient: Pubkey,
+}
+
+#[event]
+pub struct VoucherRedeemed {
+    pub voucher: Pubkey,
+    pub recipient: Pubkey,
+    pub meter: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+}
+
+#[event]
+pub struct VoucherRefunded {
+    pub voucher: Pubkey,
+    pub issuer: Pubkey,
+    pub amount: u64,
+}
+
+#[error_code]
+pub enum VoucherError {
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Memo is too long (max 100 characters)")]
+    MemoTooLong,
+    #[msg("Expiry must be in the future")]
+    InvalidExpiry,
+    #[msg("Voucher has expired")]
+    VoucherExpired,
+    #[msg("Voucher has not expired yet")]
+    VoucherNotExpired,
+    #[msg("Voucher still holds an unredeemed balance")]
+    VoucherNotEmpty,
+    #[msg("Insufficient voucher balance")]
+    InsufficientBalance,
+    #[msg("Usage meter does not belong to the voucher recipient")]
+    MeterMismatch,
+    #[msg("Unauthorized voucher recipient")]
+    UnauthorizedRecipient,
+    #[msg("Unauthorized voucher issuer")]
+    UnauthorizedIssuer,
+    #[msg("Protocol is paused by the guardian")]
+    ProtocolPaused,
+}
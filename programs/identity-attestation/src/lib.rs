@@ -0,0 +1,422 @@
+use anchor_lang::prelude::*;
+
+declare_id!("2hWoY8qzngsyk4yQTo25wzAbb5Pi6iX7eQsbc22NpchE");
+
+#[program]
+pub mod identity_attestation {
+    use super::*;
+
+    /// Initialize the attestation authority
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.attestation_config;
+        config.admin = ctx.accounts.admin.key();
+        config.total_verifiers = 0;
+
+        emit!(AttestationAdminChanged {
+            previous_admin: Pubkey::default(),
+            new_admin: config.admin,
+        });
+
+        Ok(())
+    }
+
+    /// Hand attestation administration to a new key, e.g. the admin multisig (admin only)
+    pub fn transfer_admin(
+        ctx: Context<UpdateConfig>,
+        new_admin: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.attestation_config;
+        let previous_admin = config.admin;
+        config.admin = new_admin;
+
+        emit!(AttestationAdminChanged {
+            previous_admin,
+            new_admin,
+        });
+
+        Ok(())
+    }
+
+    /// Approve a verifier allowed to issue attestations (admin only)
+    pub fn add_verifier(
+        ctx: Context<AddVerifier>,
+        authority: Pubkey,
+        name: String,
+    ) -> Result<()> {
+        require!(name.len() <= 50, AttestationError::NameTooLong);
+
+        let verifier = &mut ctx.accounts.verifier;
+        verifier.authority = authority;
+        verifier.name = name;
+        verifier.is_active = true;
+        verifier.attestations_issued = 0;
+        verifier.added_at = Clock::get()?.unix_timestamp;
+
+        ctx.accounts.attestation_config.total_verifiers += 1;
+
+        emit!(VerifierUpdated {
+            verifier: verifier.key(),
+            authority,
+            is_active: true,
+        });
+
+        Ok(())
+    }
+
+    /// Suspend or reinstate a verifier (admin only)
+    pub fn set_verifier_status(
+        ctx: Context<SetVerifierStatus>,
+        is_active: bool,
+    ) -> Result<()> {
+        let verifier = &mut ctx.accounts.verifier;
+        verifier.is_active = is_active;
+
+        emit!(VerifierUpdated {
+            verifier: verifier.key(),
+            authority: verifier.authority,
+            is_active,
+        });
+
+        Ok(())
+    }
+
+    /// Issue an identity attestation bound to a creator wallet (active verifier only)
+    pub fn issue_attestation(
+        ctx: Context<IssueAttestation>,
+        subject: Pubkey,
+        level: AttestationLevel,
+        claims_hash: [u8; 32],
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        let verifier = &mut ctx.accounts.verifier;
+        require!(verifier.is_active, AttestationError::VerifierInactive);
+
+        let clock = Clock::get()?;
+        if let Some(expires_at) = expires_at {
+            require!(expires_at > clock.unix_timestamp, AttestationError::InvalidExpiry);
+        }
+
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.subject = subject;
+        attestation.verifier = verifier.key();
+        attestation.level = level.clone();
+        attestation.claims_hash = claims_hash;
+        attestation.issued_at = clock.unix_timestamp;
+        attestation.expires_at = expires_at;
+        attestation.is_revoked = false;
+        attestation.revoked_at = None;
+
+        verifier.attestations_issued += 1;
+
+        emit!(AttestationIssued {
+            attestation: attestation.key(),
+            subject,
+            verifier: attestation.verifier,
+            level,
+            expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Revoke an attestation (issuing verifier or admin)
+    pub fn revoke_attestation(
+        ctx: Context<RevokeAttestation>,
+    ) -> Result<()> {
+        let caller = ctx.accounts.authority.key();
+        require!(
+            caller == ctx.accounts.verifier.authority
+                || caller == ctx.accounts.attestation_config.admin,
+            AttestationError::UnauthorizedRevoker
+        );
+
+        let attestation = &mut ctx.accounts.attestation;
+        require!(!attestation.is_revoked, AttestationError::AlreadyRevoked);
+
+        attestation.is_revoked = true;
+        attestation.revoked_at = Some(Clock::get()?.unix_timestamp);
+
+        emit!(AttestationRevoked {
+            attestation: attestation.key(),
+            subject: attestation.subject,
+            revoked_by: caller,
+        });
+
+        Ok(())
+    }
+
+    /// Close a revoked or expired attestation so the verifier can reissue it
+    pub fn close_attestation(
+        ctx: Context<CloseAttestation>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            !ctx.accounts.attestation.is_valid(now),
+            AttestationError::AttestationStillValid
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AttestationConfig::INIT_SPACE,
+        seeds = [b"attestation_config"],
+        bump
+    )]
+    pub attestation_config: Account<'info, AttestationConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"attestation_config"],
+        bump,
+        has_one = admin @ AttestationError::UnauthorizedAdmin
+    )]
+    pub attestation_config: Account<'info, AttestationConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(authority: Pubkey)]
+pub struct AddVerifier<'info> {
+    #[account(
+        mut,
+        seeds = [b"attestation_config"],
+        bump,
+        has_one = admin @ AttestationError::UnauthorizedAdmin
+    )]
+    pub attestation_config: Account<'info, AttestationConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Verifier::INIT_SPACE,
+        seeds = [b"verifier", authority.as_ref()],
+        bump
+    )]
+    pub verifier: Account<'info, Verifier>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetVerifierStatus<'info> {
+    #[account(
+        seeds = [b"attestation_config"],
+        bump,
+        has_one = admin @ AttestationError::UnauthorizedAdmin
+    )]
+    pub attestation_config: Account<'info, AttestationConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"verifier", verifier.authority.as_ref()],
+        bump
+    )]
+    pub verifier: Account<'info, Verifier>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(subject: Pubkey)]
+pub struct IssueAttestation<'info> {
+    #[account(
+        mut,
+        seeds = [b"verifier", authority.key().as_ref()],
+        bump,
+        has_one = authority @ AttestationError::UnauthorizedVerifier
+    )]
+    pub verifier: Account<'info, Verifier>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Attestation::INIT_SPACE,
+        seeds = [b"attestation", subject.as_ref(), verifier.key().as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, Attestation>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAttestation<'info> {
+    #[account(
+        seeds = [b"attestation_config"],
+        bump
+    )]
+    pub attestation_config: Account<'info, AttestationConfig>,
+
+    #[account(
+        seeds = [b"verifier", verifier.authority.as_ref()],
+        bump
+    )]
+    pub verifier: Account<'info, Verifier>,
+
+    #[account(
+        mut,
+        seeds = [b"attestation", attestation.subject.as_ref(), verifier.key().as_ref()],
+        bump,
+        has_one = verifier
+    )]
+    pub attestation: Account<'info, Attestation>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAttestation<'info> {
+    #[account(
+        seeds = [b"verifier", authority.key().as_ref()],
+        bump,
+        has_one = authority @ AttestationError::UnauthorizedVerifier
+    )]
+    pub verifier: Account<'info, Verifier>,
+
+    #[account(
+        mut,
+        seeds = [b"attestation", attestation.subject.as_ref(), verifier.key().as_ref()],
+        bump,
+        has_one = verifier,
+        close = authority
+    )]
+    pub attestation: Account<'info, Attestation>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AttestationConfig {
+    pub admin: Pubkey,
+    pub total_verifiers: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Verifier {
+    pub authority: Pubkey,
+    #[max_len(50)]
+    pub name: String,
+    pub is_active: bool,
+    pub attestations_issued: u64,
+    pub added_at: i64,
+}
+
+/// Identity attestation consumed by the registry and escrow
+#[account]
+#[derive(InitSpace)]
+pub struct Attestation {
+    pub subject: Pubkey,
+    pub verifier: Pubkey,
+    pub level: AttestationLevel,
+    /// Hash of the off-chain KYC/KYB evidence bundle
+    pub claims_hash: [u8; 32],
+    pub issued_at: i64,
+    pub expires_at: Option<i64>,
+    pub is_revoked: bool,
+    pub revoked_at: Option<i64>,
+}
+
+impl Attestation {
+    pub fn is_valid(&self, now: i64) -> bool {
+        let unexpired = match self.expires_at {
+            Some(expires_at) => now < expires_at,
+            None => true,
+        };
+        !self.is_revoked && unexpired
+    }
+
+    /// Whether this attestation proves at least the given level
+    pub fn satisfies(&self, level: &AttestationLevel, now: i64) -> bool {
+        self.is_valid(now) && self.level.rank() >= level.rank()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum AttestationLevel {
+    Individual,
+    Business,
+}
+
+impl AttestationLevel {
+    pub fn rank(&self) -> u8 {
+        match self {
+            AttestationLevel::Individual => 1,
+            AttestationLevel::Business => 2,
+        }
+    }
+}
+
+#[event]
+pub struct AttestationAdminChanged {
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
+#[event]
+pub struct VerifierUpdated {
+    pub verifier: Pubkey,
+    pub authority: Pubkey,
+    pub is_active: bool,
+}
+
+#[event]
+pub struct AttestationIssued {
+    pub attestation: Pubkey,
+    pub subject: Pubkey,
+    pub verifier: Pubkey,
+    pub level: AttestationLevel,
+    pub expires_at: Option<i64>,
+}
+
+#[event]
+pub struct AttestationRevoked {
+    pub attestation: Pubkey,
+    pub subject: Pubkey,
+    pub revoked_by: Pubkey,
+}
+
+#[error_code]
+pub enum AttestationError {
+    #[msg("Verifier name is too long (max 50 characters)")]
+    NameTooLong,
+    #[msg("Verifier is not active")]
+    VerifierInactive,
+    #[msg("Expiry must be in the future")]
+    InvalidExpiry,
+    #[msg("Attestation is already revoked")]
+    AlreadyRevoked,
+    #[msg("Attestation is still valid")]
+    AttestationStillValid,
+    #[msg("Only the issuing verifier or admin can revoke")]
+    UnauthorizedRevoker,
+    #[msg("Unauthorized verifier")]
+    UnauthorizedVerifier,
+    #[msg("Unauthorized admin access")]
+    UnauthorizedAdmin,
+}
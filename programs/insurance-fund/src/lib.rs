@@ -0,0 +1,384 @@
+use anchor_lang::prelude::*;
+
+declare_id!("2MpEEMujwGeLpCNsH91S8oGWMRWfuKYHcum5KtJfjy1o");
+
+/// Upper bound on the premium taken from payouts (10%)
+pub const MAX_PREMIUM_BPS: u16 = 1_000;
+
+#[program]
+pub mod insurance_fund {
+    use super::*;
+
+    /// Initialize the insurance fund
+    pub fn initialize_fund(
+        ctx: Context<InitializeFund>,
+        premium_bps: u16,
+        approver: Pubkey,
+    ) -> Result<()> {
+        require!(premium_bps <= MAX_PREMIUM_BPS, InsuranceError::InvalidPremium);
+
+        let fund = &mut ctx.accounts.insurance_fund;
+        let clock = Clock::get()?;
+
+        fund.admin = ctx.accounts.admin.key();
+        fund.approver = approver;
+        fund.premium_bps = premium_bps;
+        fund.total_premiums = 0;
+        fund.total_paid = 0;
+        fund.open_claims = 0;
+        fund.created_at = clock.unix_timestamp;
+
+        emit!(InsuranceFundInitialized {
+            admin: fund.admin,
+            approver,
+            premium_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Update the premium rate or claim approver (admin only)
+    pub fn update_fund(
+        ctx: Context<UpdateFund>,
+        premium_bps: Option<u16>,
+        approver: Option<Pubkey>,
+    ) -> Result<()> {
+        let fund = &mut ctx.accounts.insurance_fund;
+
+        if let Some(premium_bps) = premium_bps {
+            require!(premium_bps <= MAX_PREMIUM_BPS, InsuranceError::InvalidPremium);
+            fund.premium_bps = premium_bps;
+        }
+        if let Some(approver) = approver {
+            fund.approver = approver;
+        }
+
+        emit!(InsuranceFundUpdated {
+            premium_bps: fund.premium_bps,
+            approver: fund.approver,
+        });
+
+        Ok(())
+    }
+
+    /// Pay the insurance premium owed on a payout into the fund
+    pub fn deposit_premium(
+        ctx: Context<DepositPremium>,
+        payout_amount: u64,
+    ) -> Result<()> {
+        let premium = calculate_premium(payout_amount, ctx.accounts.insurance_fund.premium_bps);
+        require!(premium > 0, InsuranceError::InvalidAmount);
+
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.payer.key(),
+            &ctx.accounts.insurance_fund.key(),
+            premium,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_instruction,
+            &[
+                ctx.accounts.payer.to_account_info(),
+                ctx.accounts.insurance_fund.to_account_info(),
+            ],
+        )?;
+
+        let fund = &mut ctx.accounts.insurance_fund;
+        fund.total_premiums = fund.total_premiums.saturating_add(premium);
+
+        emit!(PremiumDeposited {
+            payer: ctx.accounts.payer.key(),
+            payout_amount,
+            premium,
+        });
+
+        Ok(())
+    }
+
+    /// File a claim for an upheld dispute that agent bonds could not cover
+    pub fn file_claim(
+        ctx: Context<FileClaim>,
+        request_id: Pubkey,
+        amount: u64,
+        evidence_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(amount > 0, InsuranceError::InvalidAmount);
+
+        let claim_key = ctx.accounts.claim.key();
+        let claim = &mut ctx.accounts.claim;
+        let clock = Clock::get()?;
+
+        claim.claimant = ctx.accounts.claimant.key();
+        claim.request_id = request_id;
+        claim.requested_amount = amount;
+        claim.approved_amount = 0;
+        claim.evidence_hash = evidence_hash;
+        claim.status = ClaimStatus::Pending;
+        claim.filed_at = clock.unix_timestamp;
+        claim.resolved_at = None;
+
+        let fund = &mut ctx.accounts.insurance_fund;
+        fund.open_claims += 1;
+
+        emit!(ClaimFiled {
+            claim: claim_key,
+            claimant: claim.claimant,
+            request_id,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Approve a claim and pay it out of the fund (approver only)
+    pub fn approve_claim(
+        ctx: Context<ResolveClaim>,
+        approved_amount: u64,
+    ) -> Result<()> {
+        let claim = &mut ctx.accounts.claim;
+        require!(claim.status == ClaimStatus::Pending, InsuranceError::ClaimNotPending);
+        require!(
+            approved_amount > 0 && approved_amount <= claim.requested_amount,
+            InsuranceError::InvalidAmount
+        );
+
+        let fund_info = ctx.accounts.insurance_fund.to_account_info();
+        let rent_reserve = Rent::get()?.minimum_balance(fund_info.data_len());
+        let available = fund_info.lamports().saturating_sub(rent_reserve);
+        require!(available >= approved_amount, InsuranceError::InsufficientFundBalance);
+
+        claim.status = ClaimStatus::Paid;
+        claim.approved_amount = approved_amount;
+        claim.resolved_at = Some(Clock::get()?.unix_timestamp);
+
+        **fund_info.try_borrow_mut_lamports()? -= approved_amount;
+        **ctx.accounts.claimant.try_borrow_mut_lamports()? += approved_amount;
+
+        let fund = &mut ctx.accounts.insurance_fund;
+        fund.total_paid = fund.total_paid.saturating_add(approved_amount);
+        fund.open_claims -= 1;
+
+        emit!(ClaimPaid {
+            claim: claim.key(),
+            claimant: claim.claimant,
+            amount: approved_amount,
+            approver: ctx.accounts.approver.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Reject a claim (approver only)
+    pub fn reject_claim(
+        ctx: Context<ResolveClaim>,
+    ) -> Result<()> {
+        let claim = &mut ctx.accounts.claim;
+        require!(claim.status == ClaimStatus::Pending, InsuranceError::ClaimNotPending);
+
+        claim.status = ClaimStatus::Rejected;
+        claim.resolved_at = Some(Clock::get()?.unix_timestamp);
+
+        let fund = &mut ctx.accounts.insurance_fund;
+        fund.open_claims -= 1;
+
+        emit!(ClaimRejected {
+            claim: claim.key(),
+            claimant: claim.claimant,
+            approver: ctx.accounts.approver.key(),
+        });
+
+        Ok(())
+    }
+}
+
+/// Premium owed on a payout at the given rate
+pub fn calculate_premium(payout_amount: u64, premium_bps: u16) -> u64 {
+    ((payout_amount as u128) * (premium_bps as u128) / 10_000) as u64
+}
+
+#[derive(Accounts)]
+pub struct InitializeFund<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + InsuranceFund::INIT_SPACE,
+        seeds = [b"insurance_fund"],
+        bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFund<'info> {
+    #[account(
+        mut,
+        seeds = [b"insurance_fund"],
+        bump,
+        has_one = admin @ InsuranceError::UnauthorizedAdmin
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DepositPremium<'info> {
+    #[account(
+        mut,
+        seeds = [b"insurance_fund"],
+        bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(request_id: Pubkey)]
+pub struct FileClaim<'info> {
+    #[account(
+        mut,
+        seeds = [b"insurance_fund"],
+        bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    #[account(
+        init,
+        payer = claimant,
+        space = 8 + InsuranceClaim::INIT_SPACE,
+        seeds = [b"claim", request_id.as_ref(), claimant.key().as_ref()],
+        bump
+    )]
+    pub claim: Account<'info, InsuranceClaim>,
+
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveClaim<'info> {
+    #[account(
+        mut,
+        seeds = [b"insurance_fund"],
+        bump,
+        has_one = approver @ InsuranceError::UnauthorizedApprover
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    #[account(
+        mut,
+        seeds = [b"claim", claim.request_id.as_ref(), claim.claimant.as_ref()],
+        bump,
+        has_one = claimant
+    )]
+    pub claim: Account<'info, InsuranceClaim>,
+
+    /// CHECK: Claimant receives the payout; verified against the claim
+    #[account(mut)]
+    pub claimant: UncheckedAccount<'info>,
+
+    /// Governance or arbitration authority
+    pub approver: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct InsuranceFund {
+    pub admin: Pubkey,
+    pub approver: Pubkey,
+    pub premium_bps: u16,
+    pub total_premiums: u64,
+    pub total_paid: u64,
+    pub open_claims: u64,
+    pub created_at: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct InsuranceClaim {
+    pub claimant: Pubkey,
+    pub request_id: Pubkey,
+    pub requested_amount: u64,
+    pub approved_amount: u64,
+    pub evidence_hash: [u8; 32],
+    pub status: ClaimStatus,
+    pub filed_at: i64,
+    pub resolved_at: Option<i64>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum ClaimStatus {
+    Pending,
+    Paid,
+    Rejected,
+}
+
+#[event]
+pub struct InsuranceFundInitialized {
+    pub admin: Pubkey,
+    pub approver: Pubkey,
+    pub premium_bps: u16,
+}
+
+#[event]
+pub struct InsuranceFundUpdated {
+    pub premium_bps: u16,
+    pub approver: Pubkey,
+}
+
+#[event]
+pub struct PremiumDeposited {
+    pub payer: Pubkey,
+    pub payout_amount: u64,
+    pub premium: u64,
+}
+
+#[event]
+pub struct ClaimFiled {
+    pub claim: Pubkey,
+    pub claimant: Pubkey,
+    pub request_id: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ClaimPaid {
+    pub claim: Pubkey,
+    pub claimant: Pubkey,
+    pub amount: u64,
+    pub approver: Pubkey,
+}
+
+#[event]
+pub struct ClaimRejected {
+    pub claim: Pubkey,
+    pub claimant: Pubkey,
+    pub approver: Pubkey,
+}
+
+#[error_code]
+pub enum InsuranceError {
+    #[msg("Premium must be at most 1000 bps")]
+    InvalidPremium,
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    #[msg("Claim has already been resolved")]
+    ClaimNotPending,
+    #[msg("Insurance fund balance is insufficient for this claim")]
+    InsufficientFundBalance,
+    #[msg("Unauthorized admin access")]
+    UnauthorizedAdmin,
+    #[msg("Unauthorized claim approver")]
+    UnauthorizedApprover,
+}
@@ -0,0 +1,671 @@
+use anchor_lang::prelude::*;
+
+declare_id!("Dj3TpoK88osZiCYREQNE5aw3unj3phrazFYfbt83djdB");
+
+/// Maximum number of ranked agents (and prize tiers) per epoch
+pub const MAX_WINNERS: usize = 10;
+/// Maximum number of programs allowed to report performance
+pub const MAX_REPORTERS: usize = 4;
+
+#[program]
+pub mod leaderboard {
+    use super::*;
+
+    /// Initialize the leaderboard configuration
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        epoch_duration: i64,
+        finalization_window: i64,
+        prize_weights_bps: Vec<u16>,
+        reporters: Vec<Pubkey>,
+    ) -> Result<()> {
+        validate_settings(epoch_duration, finalization_window, &prize_weights_bps, &reporters)?;
+
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.epoch_duration = epoch_duration;
+        config.finalization_window = finalization_window;
+        config.prize_weights_bps = prize_weights_bps;
+        config.reporters = reporters;
+        config.current_epoch = 0;
+        config.current_epoch_end = 0;
+
+        emit!(LeaderboardConfigInitialized {
+            admin: config.admin,
+            epoch_duration,
+            finalization_window,
+        });
+
+        Ok(())
+    }
+
+    /// Update leaderboard settings (admin only); applies to epochs started afterwards
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        epoch_duration: Option<i64>,
+        finalization_window: Option<i64>,
+        prize_weights_bps: Option<Vec<u16>>,
+        reporters: Option<Vec<Pubkey>>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        if let Some(epoch_duration) = epoch_duration {
+            config.epoch_duration = epoch_duration;
+        }
+        if let Some(finalization_window) = finalization_window {
+            config.finalization_window = finalization_window;
+        }
+        if let Some(prize_weights_bps) = prize_weights_bps {
+            config.prize_weights_bps = prize_weights_bps;
+        }
+        if let Some(reporters) = reporters {
+            config.reporters = reporters;
+        }
+
+        validate_settings(
+            config.epoch_duration,
+            config.finalization_window,
+            &config.prize_weights_bps,
+            &config.reporters,
+        )?;
+
+        emit!(LeaderboardConfigUpdated {
+            epoch_duration: config.epoch_duration,
+            finalization_window: config.finalization_window,
+            winners: config.prize_weights_bps.len() as u8,
+        });
+
+        Ok(())
+    }
+
+    /// Start the next epoch once the current one has ended (permissionless)
+    pub fn start_epoch(
+        ctx: Context<StartEpoch>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let epoch_key = ctx.accounts.epoch.key();
+        let config = &mut ctx.accounts.config;
+
+        require!(
+            clock.unix_timestamp >= config.current_epoch_end,
+            LeaderboardError::EpochStillActive
+        );
+
+        config.current_epoch += 1;
+        config.current_epoch_end = clock.unix_timestamp + config.epoch_duration;
+
+        let epoch = &mut ctx.accounts.epoch;
+        epoch.index = config.current_epoch;
+        epoch.start_ts = clock.unix_timestamp;
+        epoch.end_ts = config.current_epoch_end;
+        epoch.finalize_after = config.current_epoch_end + config.finalization_window;
+        epoch.prize_weights_bps = config.prize_weights_bps.clone();
+        epoch.prize_pool = 0;
+        epoch.leaders = Vec::new();
+        epoch.is_finalized = false;
+
+        emit!(EpochStarted {
+            epoch: epoch_key,
+            index: epoch.index,
+            start_ts: epoch.start_ts,
+            end_ts: epoch.end_ts,
+        });
+
+        Ok(())
+    }
+
+    /// Add lamports to an epoch's prize pool
+    pub fn fund_prize_pool(
+        ctx: Context<FundPrizePool>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, LeaderboardError::InvalidAmount);
+        require!(!ctx.accounts.epoch.is_finalized, LeaderboardError::EpochFinalized);
+
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.funder.key(),
+            &ctx.accounts.epoch.key(),
+            amount,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_instruction,
+            &[
+                ctx.accounts.funder.to_account_info(),
+                ctx.accounts.epoch.to_account_info(),
+            ],
+        )?;
+
+        let epoch = &mut ctx.accounts.epoch;
+        epoch.prize_pool = epoch
+            .prize_pool
+            .checked_add(amount)
+            .ok_or(LeaderboardError::MathOverflow)?;
+
+        emit!(PrizePoolFunded {
+            epoch: epoch.key(),
+            funder: ctx.accounts.funder.key(),
+            amount,
+            prize_pool: epoch.prize_pool,
+        });
+
+        Ok(())
+    }
+
+    /// Record completed service volume for an agent (reporter CPI from escrow)
+    pub fn record_volume(
+        ctx: Context<RecordPerformance>,
+        agent_id: Pubkey,
+        beneficiary: Pubkey,
+        amount: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            clock.unix_timestamp < ctx.accounts.epoch.end_ts,
+            LeaderboardError::EpochEnded
+        );
+
+        let stats = &mut ctx.accounts.agent_stats;
+        init_agent_stats(stats, ctx.accounts.epoch.key(), agent_id, beneficiary);
+        stats.volume = stats.volume.saturating_add(amount);
+        stats.completed_requests = stats.completed_requests.saturating_add(1);
+
+        emit!(PerformanceRecorded {
+            epoch: stats.epoch,
+            agent_id,
+            volume: stats.volume,
+            rating_count: stats.rating_count,
+        });
+
+        Ok(())
+    }
+
+    /// Record a satisfaction rating for an agent (reporter CPI from reputation)
+    pub fn record_rating(
+        ctx: Context<RecordPerformance>,
+        agent_id: Pubkey,
+        beneficiary: Pubkey,
+        stars: u8,
+    ) -> Result<()> {
+        require!((1..=5).contains(&stars), LeaderboardError::InvalidRating);
+
+        let clock = Clock::get()?;
+        require!(
+            clock.unix_timestamp < ctx.accounts.epoch.end_ts,
+            LeaderboardError::EpochEnded
+        );
+
+        let stats = &mut ctx.accounts.agent_stats;
+        init_agent_stats(stats, ctx.accounts.epoch.key(), agent_id, beneficiary);
+        stats.rating_sum = stats.rating_sum.saturating_add(stars as u64);
+        stats.rating_count = stats.rating_count.saturating_add(1);
+
+        emit!(PerformanceRecorded {
+            epoch: stats.epoch,
+            agent_id,
+            volume: stats.volume,
+            rating_count: stats.rating_count,
+        });
+
+        Ok(())
+    }
+
+    /// Rank an agent's closed-epoch performance onto the leaderboard (permissionless)
+    pub fn submit_score(
+        ctx: Context<SubmitScore>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let epoch = &mut ctx.accounts.epoch;
+        let stats = &mut ctx.accounts.agent_stats;
+
+        require!(clock.unix_timestamp >= epoch.end_ts, LeaderboardError::EpochStillActive);
+        require!(!epoch.is_finalized, LeaderboardError::EpochFinalized);
+        require!(!stats.is_submitted, LeaderboardError::ScoreAlreadySubmitted);
+
+        let score = calculate_score(stats.volume, stats.rating_sum, stats.rating_count);
+        stats.is_submitted = true;
+
+        let position = epoch
+            .leaders
+            .iter()
+            .position(|leader| score > leader.score)
+            .unwrap_or(epoch.leaders.len());
+
+        let winners = epoch.prize_weights_bps.len();
+        if position < winners && score > 0 {
+            epoch.leaders.insert(position, LeaderEntry {
+                agent_id: stats.agent_id,
+                beneficiary: stats.beneficiary,
+                score,
+                is_claimed: false,
+            });
+            epoch.leaders.truncate(winners);
+        }
+
+        emit!(ScoreSubmitted {
+            epoch: epoch.key(),
+            agent_id: stats.agent_id,
+            score,
+            rank: epoch
+                .leaders
+                .iter()
+                .position(|leader| leader.agent_id == stats.agent_id)
+                .map(|rank| rank as u8),
+        });
+
+        Ok(())
+    }
+
+    /// Lock the leaderboard once the submission window has passed (permissionless)
+    pub fn finalize_epoch(
+        ctx: Context<FinalizeEpoch>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let epoch = &mut ctx.accounts.epoch;
+
+        require!(!epoch.is_finalized, LeaderboardError::EpochFinalized);
+        require!(
+            clock.unix_timestamp >= epoch.finalize_after,
+            LeaderboardError::SubmissionWindowOpen
+        );
+
+        epoch.is_finalized = true;
+
+        emit!(EpochFinalized {
+            epoch: epoch.key(),
+            index: epoch.index,
+            prize_pool: epoch.prize_pool,
+            winners: epoch.leaders.iter().map(|leader| leader.agent_id).collect(),
+        });
+
+        Ok(())
+    }
+
+    /// Pay out the prize for a finalized leaderboard rank
+    pub fn claim_prize(
+        ctx: Context<ClaimPrize>,
+        rank: u8,
+    ) -> Result<()> {
+        let epoch = &mut ctx.accounts.epoch;
+        require!(epoch.is_finalized, LeaderboardError::EpochNotFinalized);
+
+        let rank = rank as usize;
+        require!(rank < epoch.leaders.len(), LeaderboardError::InvalidRank);
+        require!(!epoch.leaders[rank].is_claimed, LeaderboardError::PrizeAlreadyClaimed);
+        require_keys_eq!(
+            epoch.leaders[rank].beneficiary,
+            ctx.accounts.beneficiary.key(),
+            LeaderboardError::InvalidBeneficiary
+        );
+
+        let prize = calculate_prize(
+            epoch.prize_pool,
+            &epoch.prize_weights_bps,
+            epoch.leaders.len(),
+            rank,
+        );
+
+        epoch.leaders[rank].is_claimed = true;
+        let agent_id = epoch.leaders[rank].agent_id;
+
+        **epoch.to_account_info().try_borrow_mut_lamports()? -= prize;
+        **ctx.accounts.beneficiary.try_borrow_mut_lamports()? += prize;
+
+        emit!(PrizeClaimed {
+            epoch: epoch.key(),
+            agent_id,
+            beneficiary: ctx.accounts.beneficiary.key(),
+            rank: rank as u8,
+            amount: prize,
+        });
+
+        Ok(())
+    }
+}
+
+// Helper function to validate leaderboard settings
+fn validate_settings(
+    epoch_duration: i64,
+    finalization_window: i64,
+    prize_weights_bps: &[u16],
+    reporters: &[Pubkey],
+) -> Result<()> {
+    require!(epoch_duration > 0, LeaderboardError::InvalidDuration);
+    require!(finalization_window >= 0, LeaderboardError::InvalidDuration);
+    require!(
+        !prize_weights_bps.is_empty() && prize_weights_bps.len() <= MAX_WINNERS,
+        LeaderboardError::InvalidPrizeWeights
+    );
+    require!(
+        prize_weights_bps.iter().map(|weight| *weight as u32).sum::<u32>() == 10_000,
+        LeaderboardError::InvalidPrizeWeights
+    );
+    require!(reporters.len() <= MAX_REPORTERS, LeaderboardError::TooManyReporters);
+    Ok(())
+}
+
+// Helper function to populate a freshly created stats account
+fn init_agent_stats(stats: &mut AgentEpochStats, epoch: Pubkey, agent_id: Pubkey, beneficiary: Pubkey) {
+    if stats.epoch == Pubkey::default() {
+        stats.epoch = epoch;
+        stats.agent_id = agent_id;
+        stats.beneficiary = beneficiary;
+    }
+}
+
+// Helper function to score volume scaled by satisfaction (unrated agents count as 3 stars)
+fn calculate_score(volume: u64, rating_sum: u64, rating_count: u64) -> u64 {
+    let satisfaction_pct = (rating_sum * 20).checked_div(rating_count).unwrap_or(60) as u128;
+    ((volume as u128) * satisfaction_pct / 100) as u64
+}
+
+/// Prize for `rank` when `filled` ranks have leaders
+///
+/// Weights of unfilled ranks are shared among the filled ones.
+pub fn calculate_prize(
+    prize_pool: u64,
+    prize_weights_bps: &[u16],
+    filled: usize,
+    rank: usize,
+) -> u64 {
+    let filled_weight: u64 = prize_weights_bps[..filled]
+        .iter()
+        .map(|weight| *weight as u64)
+        .sum();
+    ((prize_pool as u128) * (prize_weights_bps[rank] as u128) / (filled_weight as u128)) as u64
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + LeaderboardConfig::INIT_SPACE,
+        seeds = [b"leaderboard_config"],
+        bump
+    )]
+    pub config: Account<'info, LeaderboardConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"leaderboard_config"],
+        bump,
+        has_one = admin @ LeaderboardError::UnauthorizedAdmin
+    )]
+    pub config: Account<'info, LeaderboardConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct StartEpoch<'info> {
+    #[account(
+        mut,
+        seeds = [b"leaderboard_config"],
+        bump
+    )]
+    pub config: Account<'info, LeaderboardConfig>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Epoch::INIT_SPACE,
+        seeds = [b"epoch", (config.current_epoch + 1).to_le_bytes().as_ref()],
+        bump
+    )]
+    pub epoch: Account<'info, Epoch>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundPrizePool<'info> {
+    #[account(
+        mut,
+        seeds = [b"epoch", epoch.index.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub epoch: Account<'info, Epoch>,
+
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(agent_id: Pubkey)]
+pub struct RecordPerformance<'info> {
+    #[account(
+        seeds = [b"leaderboard_config"],
+        bump,
+        constraint = config.reporters.contains(&reporter.key()) @ LeaderboardError::UnauthorizedReporter
+    )]
+    pub config: Account<'info, LeaderboardConfig>,
+
+    #[account(
+        seeds = [b"epoch", config.current_epoch.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub epoch: Account<'info, Epoch>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + AgentEpochStats::INIT_SPACE,
+        seeds = [b"agent_epoch", epoch.key().as_ref(), agent_id.as_ref()],
+        bump
+    )]
+    pub agent_stats: Account<'info, AgentEpochStats>,
+
+    /// Reporting program's CPI signer (escrow or reputation PDA)
+    pub reporter: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitScore<'info> {
+    #[account(
+        mut,
+        seeds = [b"epoch", epoch.index.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub epoch: Account<'info, Epoch>,
+
+    #[account(
+        mut,
+        seeds = [b"agent_epoch", epoch.key().as_ref(), agent_stats.agent_id.as_ref()],
+        bump
+    )]
+    pub agent_stats: Account<'info, AgentEpochStats>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeEpoch<'info> {
+    #[account(
+        mut,
+        seeds = [b"epoch", epoch.index.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub epoch: Account<'info, Epoch>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPrize<'info> {
+    #[account(
+        mut,
+        seeds = [b"epoch", epoch.index.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub epoch: Account<'info, Epoch>,
+
+    /// CHECK: Verified against the leaderboard entry's beneficiary
+    #[account(mut)]
+    pub beneficiary: UncheckedAccount<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct LeaderboardConfig {
+    pub admin: Pubkey,
+    pub epoch_duration: i64,
+    pub finalization_window: i64,
+    #[max_len(MAX_WINNERS)]
+    pub prize_weights_bps: Vec<u16>,
+    #[max_len(MAX_REPORTERS)]
+    pub reporters: Vec<Pubkey>,
+    pub current_epoch: u64,
+    pub current_epoch_end: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Epoch {
+    pub index: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub finalize_after: i64,
+    #[max_len(MAX_WINNERS)]
+    pub prize_weights_bps: Vec<u16>,
+    pub prize_pool: u64,
+    #[max_len(MAX_WINNERS)]
+    pub leaders: Vec<LeaderEntry>,
+    pub is_finalized: bool,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AgentEpochStats {
+    pub epoch: Pubkey,
+    pub agent_id: Pubkey,
+    pub beneficiary: Pubkey,
+    pub volume: u64,
+    pub completed_requests: u64,
+    pub rating_sum: u64,
+    pub rating_count: u64,
+    pub is_submitted: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct LeaderEntry {
+    pub agent_id: Pubkey,
+    pub beneficiary: Pubkey,
+    pub score: u64,
+    pub is_claimed: bool,
+}
+
+#[event]
+pub struct LeaderboardConfigInitialized {
+    pub admin: Pubkey,
+    pub epoch_duration: i64,
+    pub finalization_window: i64,
+}
+
+#[event]
+pub struct LeaderboardConfigUpdated {
+    pub epoch_duration: i64,
+    pub finalization_window: i64,
+    pub winners: u8,
+}
+
+#[event]
+pub struct EpochStarted {
+    pub epoch: Pubkey,
+    pub index: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+}
+
+#[event]
+pub struct PrizePoolFunded {
+    pub epoch: Pubkey,
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub prize_pool: u64,
+}
+
+#[event]
+pub struct PerformanceRecorded {
+    pub epoch: Pubkey,
+    pub agent_id: Pubkey,
+    pub volume: u64,
+    pub rating_count: u64,
+}
+
+#[event]
+pub struct ScoreSubmitted {
+    pub epoch: Pubkey,
+    pub agent_id: Pubkey,
+    pub score: u64,
+    pub rank: Option<u8>,
+}
+
+#[event]
+pub struct EpochFinalized {
+    pub epoch: Pubkey,
+    pub index: u64,
+    pub prize_pool: u64,
+    pub winners: Vec<Pubkey>,
+}
+
+#[event]
+pub struct PrizeClaimed {
+    pub epoch: Pubkey,
+    pub agent_id: Pubkey,
+    pub beneficiary: Pubkey,
+    pub rank: u8,
+    pub amount: u64,
+}
+
+#[error_code]
+pub enum LeaderboardError {
+    #[msg("Epoch duration and finalization window must be positive")]
+    InvalidDuration,
+    #[msg("Prize weights must have 1-10 entries totalling 10000 bps")]
+    InvalidPrizeWeights,
+    #[msg("Too many reporters (max 4)")]
+    TooManyReporters,
+    #[msg("Unauthorized admin access")]
+    UnauthorizedAdmin,
+    #[msg("Reporter is not authorized to record performance")]
+    UnauthorizedReporter,
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    #[msg("Rating must be between 1 and 5")]
+    InvalidRating,
+    #[msg("Epoch is still active")]
+    EpochStillActive,
+    #[msg("Epoch has already ended")]
+    EpochEnded,
+    #[msg("Epoch has already been finalized")]
+    EpochFinalized,
+    #[msg("Epoch has not been finalized yet")]
+    EpochNotFinalized,
+    #[msg("Score submission window is still open")]
+    SubmissionWindowOpen,
+    #[msg("Score already submitted for this epoch")]
+    ScoreAlreadySubmitted,
+    #[msg("Invalid leaderboard rank")]
+    InvalidRank,
+    #[msg("Prize already claimed")]
+    PrizeAlreadyClaimed,
+    #[msg("Beneficiary does not match the leaderboard entry")]
+    InvalidBeneficiary,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+}
@@ -1,25 +1,106 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_spl::associated_token::{self, get_associated_token_address, AssociatedToken};
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+use program_utils::migration::{read_upgraded, write_upgraded};
+use promo_raffle::program::PromoRaffle;
+use protocol_analytics::program::ProtocolAnalytics;
+use protocol_analytics::{AnalyticsConfig, RequestEvent};
+use protocol_guardian::GuardianConfig;
+use royalty_splitter::RoyaltyConfig;
+use solana_sha256_hasher::hashv;
 
 declare_id!("2ZuJbvYqvhXq7N7WjKw3r4YqkU3r7CmLGjXXvKhGz3xF");
 
+// The agent registry depends on this program, so its id and the `AgentProfile`, `AgentVersion`,
+// `ServiceOffering`, `CoCreatorSplit`, `Bundle`, and `CapabilityPrice` layouts are mirrored here
+// rather than imported
+pub const AGENT_REGISTRY_ID: Pubkey = pubkey!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+// Anchor discriminator of `AgentProfile`: sha256("account:AgentProfile")[..8]
+const AGENT_PROFILE_DISCRIMINATOR: [u8; 8] = [60, 227, 42, 24, 0, 87, 86, 205];
+// Anchor discriminator of the registry's `record_earnings`: sha256("global:record_earnings")[..8]
+const RECORD_EARNINGS_IX: [u8; 8] = [217, 224, 183, 102, 227, 210, 189, 82];
+// Anchor discriminator of `AgentVersion`: sha256("account:AgentVersion")[..8]
+const AGENT_VERSION_DISCRIMINATOR: [u8; 8] = [228, 76, 35, 176, 117, 149, 154, 147];
+// Anchor discriminator of `ServiceOffering`: sha256("account:ServiceOffering")[..8]
+const SERVICE_OFFERING_DISCRIMINATOR: [u8; 8] = [232, 90, 32, 37, 242, 220, 174, 241];
+// Anchor discriminator of `CoCreatorSplit`: sha256("account:CoCreatorSplit")[..8]
+const CO_CREATOR_SPLIT_DISCRIMINATOR: [u8; 8] = [63, 229, 240, 16, 175, 142, 27, 54];
+// Anchor discriminator of `Bundle`: sha256("account:Bundle")[..8]
+const BUNDLE_DISCRIMINATOR: [u8; 8] = [15, 82, 167, 230, 37, 214, 82, 80];
+// Anchor discriminator of `CapabilityPrice`: sha256("account:CapabilityPrice")[..8]
+const CAPABILITY_PRICE_DISCRIMINATOR: [u8; 8] = [150, 125, 129, 75, 164, 135, 12, 192];
+
+// The SLA oracle depends on the agent registry, which depends on this program, so its id and
+// the `AgentSla` layout are mirrored here as well
+pub const SLA_ORACLE_ID: Pubkey = pubkey!("5iLc8vyi8WPUBVgJcqrCZ6yzSPU77mhe8b74SiWUFrep");
+// Anchor discriminator of `AgentSla`: sha256("account:AgentSla")[..8]
+const AGENT_SLA_DISCRIMINATOR: [u8; 8] = [28, 40, 105, 70, 152, 206, 241, 131];
+
+/// Seconds a user has to approve or dispute a submitted result before anyone
+/// can release the payment with `auto_release`
+pub const REVIEW_WINDOW: i64 = 7 * 24 * 60 * 60;
+
+/// Lamports a juror stakes to join the jury pool
+pub const JUROR_STAKE: u64 = 1_000_000_000;
+/// Most jurors the pool holds
+pub const MAX_JURORS: usize = 64;
+/// Jurors drawn to vote on a dispute; odd so a full vote can't tie
+pub const PANEL_SIZE: usize = 3;
+/// Slots after a dispute whose hash seeds its juror panel, so the draw is
+/// unknown when the result is disputed and can't be timed afterwards
+pub const PANEL_SLOT_DELAY: u64 = 10;
+/// Seconds a drawn panel has to vote
+pub const VOTING_WINDOW: i64 = 3 * 24 * 60 * 60;
+/// Stake a drawn juror forfeits for not voting; jurors with less can't be drawn
+pub const NON_VOTER_PENALTY: u64 = JUROR_STAKE / 10;
+/// Cut of a disputed lamport payment the jurors in the majority share
+pub const JUROR_FEE_BPS: u64 = 500;
+
 #[program]
 pub mod marketplace_escrow {
     use super::*;
 
+    /// Open a request against an agent, escrowing `amount` in lamports, or in
+    /// base units of `payment_mint` when the token accounts are supplied.
+    /// Once `deadline` passes without a result, anyone can expire the request
+    /// and refund the user; without one, the request is due within the
+    /// timeout the SLA oracle recommends for the agent, if it has observed it
     pub fn create_service_request(
         ctx: Context<CreateServiceRequest>,
         agent_id: Pubkey,
         amount: u64,
         request_data: String,
+        deadline: Option<i64>,
     ) -> Result<()> {
-        require!(amount > 0, ErrorCode::InvalidAmount);
         require!(request_data.len() <= 1000, ErrorCode::RequestDataTooLong);
 
+        // A wallet still within the agent's free trial deposits nothing
+        let trial_requests = ctx.accounts.agent_requests.trial_requests;
+        let is_trial = match ctx.accounts.trial_counter.as_deref_mut() {
+            Some(trial_counter) if trial_counter.used < trial_requests => {
+                trial_counter.agent_id = agent_id;
+                trial_counter.user = ctx.accounts.user.key();
+                trial_counter.used += 1;
+                true
+            }
+            _ => false,
+        };
+        let amount = if is_trial { 0 } else { amount };
+        require!(is_trial || amount > 0, ErrorCode::InvalidAmount);
+
     let request_key = ctx.accounts.service_request.key();
     let user_key = ctx.accounts.user.key();
     let escrow_key = ctx.accounts.escrow_account.key();
     let service_request = &mut ctx.accounts.service_request;
     let clock = Clock::get()?;
+        let deadline = match deadline {
+            Some(deadline) => {
+                require!(deadline > clock.unix_timestamp, ErrorCode::InvalidDeadline);
+                Some(deadline)
+            }
+            None => sla_deadline(ctx.accounts.agent_sla.as_ref(), agent_id, clock.unix_timestamp)?,
+        };
 
     service_request.request_id = request_key;
     service_request.agent_id = agent_id;
@@ -31,39 +112,295 @@ pub mod marketplace_escrow {
         service_request.created_at = clock.unix_timestamp;
         service_request.completed_at = None;
     service_request.escrow_account = escrow_key;
-
-        // Transfer payment to escrow PDA
-        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
-            &user_key,
-            &escrow_key,
-            amount,
+        service_request.agent_version = None;
+        service_request.service_offering = None;
+        service_request.bundle = None;
+        service_request.capability_price = None;
+        service_request.payment_mint = match &ctx.accounts.payment_mint {
+            Some(mint) if !is_trial => Some(mint.key()),
+            _ => None,
+        };
+        service_request.deadline = deadline;
+        service_request.panel_slot = None;
+
+        let agent_requests = &mut ctx.accounts.agent_requests;
+        require!(
+            agent_requests.max_open_requests == 0
+                || agent_requests.open_requests < agent_requests.max_open_requests,
+            ErrorCode::AgentAtCapacity
         );
-
-        anchor_lang::solana_program::program::invoke(
-            &transfer_instruction,
-            &[
-                ctx.accounts.user.to_account_info(),
-                ctx.accounts.escrow_account.to_account_info(),
-            ],
-        )?;
+        require!(
+            agent_requests.paused_until <= clock.unix_timestamp,
+            ErrorCode::AgentPaused
+        );
+        require!(!agent_requests.retired, ErrorCode::AgentRetired);
+        require!(!agent_requests.frozen, ErrorCode::AgentFrozen);
+        if agent_requests.allowlist_only {
+            require_allowlisted(ctx.accounts.allowlist_entry.as_ref())?;
+        }
+        require_not_blocked(&ctx.accounts.block_entry)?;
+        agent_requests.agent_id = agent_id;
+        agent_requests.open_requests = agent_requests
+            .open_requests
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        // Transfer payment to the escrow PDA's token account when paid in an SPL token
+        if let Some(mint) = service_request.payment_mint {
+            let (
+                Some(payment_mint),
+                Some(user_token_account),
+                Some(escrow_token_account),
+                Some(token_program),
+                Some(associated_token_program),
+            ) = (
+                &ctx.accounts.payment_mint,
+                &ctx.accounts.user_token_account,
+                &ctx.accounts.escrow_token_account,
+                &ctx.accounts.token_program,
+                &ctx.accounts.associated_token_program,
+            )
+            else {
+                return err!(ErrorCode::MissingTokenAccounts);
+            };
+            require_accepted_mint(ctx.accounts.accepted_mint.as_ref(), &mint)?;
+            require_keys_eq!(
+                escrow_token_account.key(),
+                get_associated_token_address(&escrow_key, &mint),
+                ErrorCode::InvalidTokenAccount
+            );
+
+            associated_token::create_idempotent(CpiContext::new(
+                associated_token_program.to_account_info(),
+                associated_token::Create {
+                    payer: ctx.accounts.user.to_account_info(),
+                    associated_token: escrow_token_account.to_account_info(),
+                    authority: ctx.accounts.escrow_account.to_account_info(),
+                    mint: payment_mint.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program: token_program.to_account_info(),
+                },
+            ))?;
+            token::transfer(
+                CpiContext::new(
+                    token_program.to_account_info(),
+                    token::Transfer {
+                        from: user_token_account.to_account_info(),
+                        to: escrow_token_account.to_account_info(),
+                        authority: ctx.accounts.user.to_account_info(),
+                    },
+                ),
+                amount,
+            )?;
+        } else if !is_trial {
+            // Transfer payment to escrow PDA
+            let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+                &user_key,
+                &escrow_key,
+                amount,
+            );
+
+            anchor_lang::solana_program::program::invoke(
+                &transfer_instruction,
+                &[
+                    ctx.accounts.user.to_account_info(),
+                    ctx.accounts.escrow_account.to_account_info(),
+                ],
+            )?;
+        }
 
         emit!(ServiceRequestCreated {
             request_id: service_request.request_id,
             agent_id,
             user: user_key,
             amount,
+            payment_mint: service_request.payment_mint,
             timestamp: clock.unix_timestamp,
         });
 
+        // Analytics volumes are kept in lamports, so token payments only count the request
+        let volume = if service_request.payment_mint.is_some() { 0 } else { amount };
+        report_analytics(
+            &ctx.accounts.analytics,
+            ctx.bumps.analytics.analytics_hook,
+            ctx.accounts.user.to_account_info(),
+            agent_id,
+            RequestEvent::Created,
+            volume,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Pin the agent release that should serve a pending request (requester
+    /// only), so the request records which model or endpoint version ran
+    pub fn pin_agent_version(ctx: Context<PinAgentVersion>) -> Result<()> {
+        let service_request = &mut ctx.accounts.service_request;
+        require!(
+            service_request.status == RequestStatus::Pending,
+            ErrorCode::InvalidRequestStatus
+        );
+        require!(
+            service_request.user == ctx.accounts.user.key(),
+            ErrorCode::UnauthorizedUser
+        );
+
+        let agent_version = &ctx.accounts.agent_version;
+        require!(
+            version_agent_id(agent_version)? == service_request.agent_id,
+            ErrorCode::InvalidAgentVersion
+        );
+        service_request.agent_version = Some(agent_version.key());
+
+        emit!(AgentVersionPinned {
+            request_id: service_request.request_id,
+            agent_id: service_request.agent_id,
+            agent_version: agent_version.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Order a specific service from the agent's catalog for a pending request
+    /// (requester only). The escrowed amount must cover the offering's price,
+    /// which is quoted in lamports, so the request can't be paid in a token
+    pub fn select_service_offering(ctx: Context<SelectServiceOffering>) -> Result<()> {
+        let service_request = &mut ctx.accounts.service_request;
+        require!(
+            service_request.status == RequestStatus::Pending,
+            ErrorCode::InvalidRequestStatus
+        );
+        require!(
+            service_request.user == ctx.accounts.user.key(),
+            ErrorCode::UnauthorizedUser
+        );
+
+        let service_offering = &ctx.accounts.service_offering;
+        let offering = read_service_offering(service_offering)?;
+        require!(
+            offering.agent_id == service_request.agent_id,
+            ErrorCode::InvalidServiceOffering
+        );
+        require!(offering.is_active, ErrorCode::ServiceOfferingInactive);
+        require_lamport_payment(service_request)?;
+        require!(
+            service_request.amount >= offering.price,
+            ErrorCode::OfferingPriceNotMet
+        );
+        service_request.service_offering = Some(service_offering.key());
+
+        emit!(ServiceOfferingSelected {
+            request_id: service_request.request_id,
+            agent_id: service_request.agent_id,
+            service_offering: service_offering.key(),
+            price: offering.price,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Order one of the agent's capabilities at its own price for a pending
+    /// request (requester only). The escrowed lamports must cover the
+    /// capability's price override
+    pub fn select_capability(ctx: Context<SelectCapability>) -> Result<()> {
+        let service_request = &mut ctx.accounts.service_request;
+        require!(
+            service_request.status == RequestStatus::Pending,
+            ErrorCode::InvalidRequestStatus
+        );
+        require!(
+            service_request.user == ctx.accounts.user.key(),
+            ErrorCode::UnauthorizedUser
+        );
+
+        let capability_price = &ctx.accounts.capability_price;
+        let terms = read_capability_price(capability_price)?;
+        require!(
+            terms.agent_id == service_request.agent_id,
+            ErrorCode::InvalidCapabilityPrice
+        );
+        require_lamport_payment(service_request)?;
+        require!(
+            service_request.amount >= terms.price,
+            ErrorCode::CapabilityPriceNotMet
+        );
+        service_request.capability_price = Some(capability_price.key());
+
+        emit!(CapabilitySelected {
+            request_id: service_request.request_id,
+            agent_id: service_request.agent_id,
+            capability_price: capability_price.key(),
+            price: terms.price,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Place a pending request as one agent's part of a bundle purchase
+    /// (requester only). A bundle purchase opens one request per agent in the
+    /// bundle; each must escrow at least its even share of the discounted
+    /// price, in lamports
+    pub fn select_bundle(ctx: Context<SelectBundle>) -> Result<()> {
+        let service_request = &mut ctx.accounts.service_request;
+        require!(
+            service_request.status == RequestStatus::Pending,
+            ErrorCode::InvalidRequestStatus
+        );
+        require!(
+            service_request.user == ctx.accounts.user.key(),
+            ErrorCode::UnauthorizedUser
+        );
+
+        let bundle = &ctx.accounts.bundle;
+        let terms = read_bundle(bundle)?;
+        require!(
+            terms.agents.contains(&service_request.agent_id),
+            ErrorCode::AgentNotInBundle
+        );
+        require!(terms.is_active, ErrorCode::BundleInactive);
+        require_lamport_payment(service_request)?;
+        require!(
+            service_request.amount >= terms.share_price,
+            ErrorCode::BundlePriceNotMet
+        );
+        service_request.bundle = Some(bundle.key());
+
+        emit!(BundleSelected {
+            request_id: service_request.request_id,
+            agent_id: service_request.agent_id,
+            bundle: bundle.key(),
+            share_price: terms.share_price,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
         Ok(())
     }
 
+    /// Submit the result of an open request (agent's manager or one of its
+    /// operators). The manager is the authority on the agent's registry
+    /// profile when it has one, otherwise the NFT holder, who passes their
+    /// token account for it. An operator passes the registry `OperatorKey`
+    /// its manager registered for it
     pub fn submit_result(
         ctx: Context<SubmitResult>,
         result_data: String,
     ) -> Result<()> {
         require!(result_data.len() <= 2000, ErrorCode::ResultDataTooLong);
 
+        let managers = read_agent_managers(&ctx.accounts.agent_profile)?;
+        let manager = match managers.authority {
+            Some(authority) => authority,
+            None => read_nft_holder(ctx.accounts.holder_token_account.as_ref(), &managers.nft_mint)?,
+        };
+        let submitter = ctx.accounts.agent_authority.key();
+        if submitter != manager {
+            require_operator(ctx.accounts.operator_key.as_ref(), &manager, &submitter)?;
+        }
+
         let service_request = &mut ctx.accounts.service_request;
         let clock = Clock::get()?;
 
@@ -72,6 +409,10 @@ pub mod marketplace_escrow {
             service_request.status == RequestStatus::InProgress,
             ErrorCode::InvalidRequestStatus
         );
+        // Past its deadline the request is only waiting to be expired
+        if let Some(deadline) = service_request.deadline {
+            require!(clock.unix_timestamp <= deadline, ErrorCode::DeadlinePassed);
+        }
 
         service_request.result_data = result_data;
         service_request.status = RequestStatus::Completed;
@@ -86,8 +427,15 @@ pub mod marketplace_escrow {
         Ok(())
     }
 
-    pub fn approve_result(
-        ctx: Context<ApproveResult>,
+    /// Approve a completed request and release its payment to the payout
+    /// wallet on the agent's registry profile, which is credited with the
+    /// creator share. When the agent has
+    /// co-creators, their wallets follow the listed accounts, in the order the
+    /// registry lists them, and each is paid its share of the creator amount.
+    /// A request paid in an SPL token pays each party's token account of the
+    /// payment mint instead, co-creators included
+    pub fn approve_result<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ApproveResult<'info>>,
     ) -> Result<()> {
         let service_request = &mut ctx.accounts.service_request;
 
@@ -102,40 +450,127 @@ pub mod marketplace_escrow {
         );
 
         service_request.status = RequestStatus::Approved;
+        close_open_request(&mut ctx.accounts.agent_requests, service_request.agent_id);
+
+        let accounts = &ctx.accounts;
+        pay_out_request(
+            Payout {
+                service_request: &accounts.service_request,
+                amount: accounts.service_request.amount,
+                escrow_account: &accounts.escrow_account,
+                escrow_bump: ctx.bumps.escrow_account,
+                agent_profile: &accounts.agent_profile,
+                payout_hook: &accounts.payout_hook,
+                payout_hook_bump: ctx.bumps.payout_hook,
+                registry_program: &accounts.registry_program,
+                payout_wallet: &accounts.payout_wallet,
+                co_creator_split: &accounts.co_creator_split,
+                platform_wallet: &accounts.platform_wallet,
+                treasury_wallet: &accounts.treasury_wallet,
+                guardian_config: &accounts.guardian_config,
+                system_program: &accounts.system_program,
+                analytics: &accounts.analytics,
+                analytics_hook_bump: ctx.bumps.analytics.analytics_hook,
+                escrow_token_account: &accounts.escrow_token_account,
+                payout_token_account: &accounts.payout_token_account,
+                platform_token_account: &accounts.platform_token_account,
+                treasury_token_account: &accounts.treasury_token_account,
+                token_program: &accounts.token_program,
+                user: accounts.user.to_account_info(),
+                payer: accounts.user.to_account_info(),
+            },
+            ctx.remaining_accounts,
+        )?;
 
-        // Calculate payment splits (85% creator, 10% platform, 5% treasury)
-        let total_amount = service_request.amount;
-        let creator_amount = (total_amount * 85) / 100;
-        let platform_amount = (total_amount * 10) / 100;
-        let treasury_amount = total_amount - creator_amount - platform_amount;
-
-        let escrow_account = &mut ctx.accounts.escrow_account;
-        let creator = &mut ctx.accounts.creator;
-        let platform_wallet = &mut ctx.accounts.platform_wallet;
-        let treasury_wallet = &mut ctx.accounts.treasury_wallet;
-
-        // Transfer to creator (85%)
-        **escrow_account.try_borrow_mut_lamports()? -= creator_amount;
-        **creator.try_borrow_mut_lamports()? += creator_amount;
+        // Award raffle tickets for the completed request when the raffle accounts are supplied
+        if let (
+            Some(raffle_program),
+            Some(raffle_config),
+            Some(raffle_round),
+            Some(ticket_batch),
+            Some(raffle_hook),
+        ) = (
+            &ctx.accounts.raffle_program,
+            &ctx.accounts.raffle_config,
+            &ctx.accounts.raffle_round,
+            &ctx.accounts.ticket_batch,
+            &ctx.accounts.raffle_hook,
+        ) {
+            let hook_bump = ctx.bumps.raffle_hook.ok_or(ErrorCode::MissingRaffleAccounts)?;
+            let hook_seeds: &[&[&[u8]]] = &[&[b"raffle_hook", &[hook_bump]]];
+
+            promo_raffle::cpi::record_tickets(
+                CpiContext::new_with_signer(
+                    raffle_program.to_account_info(),
+                    promo_raffle::cpi::accounts::RecordTickets {
+                        raffle_config: raffle_config.to_account_info(),
+                        round: raffle_round.to_account_info(),
+                        ticket_batch: ticket_batch.to_account_info(),
+                        escrow_hook: raffle_hook.to_account_info(),
+                        payer: ctx.accounts.user.to_account_info(),
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                    },
+                    hook_seeds,
+                ),
+                ctx.accounts.service_request.user,
+                ctx.accounts.service_request.request_id,
+            )?;
+        }
 
-        // Transfer to platform (10%)
-        **escrow_account.try_borrow_mut_lamports()? -= platform_amount;
-        **platform_wallet.try_borrow_mut_lamports()? += platform_amount;
+        Ok(())
+    }
 
-        // Transfer to treasury (5%)
-        **escrow_account.try_borrow_mut_lamports()? -= treasury_amount;
-        **treasury_wallet.try_borrow_mut_lamports()? += treasury_amount;
+    /// Release a completed request's payment exactly as `approve_result`
+    /// would once `REVIEW_WINDOW` has passed since the result was submitted
+    /// without the user approving or disputing it. Anyone can call this; the
+    /// fees go to the royalty config's platform and treasury wallets
+    pub fn auto_release<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AutoRelease<'info>>,
+    ) -> Result<()> {
+        let service_request = &mut ctx.accounts.service_request;
 
-        emit!(PaymentReleased {
-            request_id: service_request.request_id,
-            creator: creator.key(),
-            creator_amount,
-            platform_amount,
-            treasury_amount,
-            timestamp: Clock::get()?.unix_timestamp,
-        });
+        require!(
+            service_request.status == RequestStatus::Completed,
+            ErrorCode::InvalidRequestStatus
+        );
+        let completed_at = service_request.completed_at.ok_or(ErrorCode::InvalidRequestStatus)?;
+        require!(
+            Clock::get()?.unix_timestamp >= completed_at.saturating_add(REVIEW_WINDOW),
+            ErrorCode::ReviewWindowOpen
+        );
 
-        Ok(())
+        service_request.status = RequestStatus::Approved;
+        close_open_request(&mut ctx.accounts.agent_requests, service_request.agent_id);
+
+        let accounts = &ctx.accounts;
+        pay_out_request(
+            Payout {
+                service_request: &accounts.service_request,
+                amount: accounts.service_request.amount,
+                escrow_account: &accounts.escrow_account,
+                escrow_bump: ctx.bumps.escrow_account,
+                agent_profile: &accounts.agent_profile,
+                payout_hook: &accounts.payout_hook,
+                payout_hook_bump: ctx.bumps.payout_hook,
+                registry_program: &accounts.registry_program,
+                payout_wallet: &accounts.payout_wallet,
+                co_creator_split: &accounts.co_creator_split,
+                platform_wallet: &accounts.platform_wallet,
+                treasury_wallet: &accounts.treasury_wallet,
+                guardian_config: &accounts.guardian_config,
+                system_program: &accounts.system_program,
+                analytics: &accounts.analytics,
+                analytics_hook_bump: ctx.bumps.analytics.analytics_hook,
+                escrow_token_account: &accounts.escrow_token_account,
+                payout_token_account: &accounts.payout_token_account,
+                platform_token_account: &accounts.platform_token_account,
+                treasury_token_account: &accounts.treasury_token_account,
+                token_program: &accounts.token_program,
+                user: accounts.user.to_account_info(),
+                payer: accounts.caller.to_account_info(),
+            },
+            ctx.remaining_accounts,
+        )
     }
 
     pub fn dispute_result(
@@ -156,18 +591,419 @@ pub mod marketplace_escrow {
             ErrorCode::UnauthorizedUser
         );
 
+        let clock = Clock::get()?;
         service_request.status = RequestStatus::Disputed;
+        service_request.panel_slot = Some(clock.slot + PANEL_SLOT_DELAY);
 
         emit!(ResultDisputed {
             request_id: service_request.request_id,
             user: ctx.accounts.user.key(),
             reason,
+            timestamp: clock.unix_timestamp,
+        });
+
+        report_analytics(
+            &ctx.accounts.analytics,
+            ctx.bumps.analytics.analytics_hook,
+            ctx.accounts.user.to_account_info(),
+            service_request.agent_id,
+            RequestEvent::Disputed,
+            service_request.amount,
+            0,
+        )?;
+
+        Ok(())
+    }
+
+    /// Name the arbiter who rules on disputed requests (guardian authority)
+    pub fn set_arbiter(ctx: Context<SetArbiter>, arbiter: Pubkey) -> Result<()> {
+        ctx.accounts.arbitration_config.arbiter = arbiter;
+
+        emit!(ArbiterSet {
+            arbiter,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Rule on a disputed request (arbiter only): award the agent its payment,
+    /// split as on approval, refund the user, or pay the agent `agent_bps` of
+    /// it and refund the rest. Platform and treasury fees come out of the
+    /// released portion only. Fee wallets are the royalty config's and
+    /// co-creators are listed as for `approve_result`
+    pub fn resolve_dispute<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ResolveDispute<'info>>,
+        outcome: DisputeOutcome,
+    ) -> Result<()> {
+        let service_request = &mut ctx.accounts.service_request;
+
+        require!(
+            service_request.status == RequestStatus::Disputed,
+            ErrorCode::RequestNotDisputed
+        );
+        require!(ctx.accounts.dispute_panel.data_is_empty(), ErrorCode::DisputeHasPanel);
+        let agent_bps = match outcome {
+            DisputeOutcome::AwardAgent => 10_000,
+            DisputeOutcome::RefundUser => 0,
+            DisputeOutcome::Split { agent_bps } => {
+                require!(agent_bps <= 10_000, ErrorCode::InvalidDisputeSplit);
+                agent_bps
+            }
+        };
+
+        service_request.status = RequestStatus::Resolved;
+        close_open_request(&mut ctx.accounts.agent_requests, service_request.agent_id);
+        let arbitration_config = &mut ctx.accounts.arbitration_config;
+        arbitration_config.disputes_resolved = arbitration_config
+            .disputes_resolved
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let accounts = &ctx.accounts;
+        let service_request = &accounts.service_request;
+        let (agent_amount, refund_amount) = settle_dispute(
+            Payout {
+                service_request,
+                amount: service_request.amount,
+                escrow_account: &accounts.escrow_account,
+                escrow_bump: ctx.bumps.escrow_account,
+                agent_profile: &accounts.agent_profile,
+                payout_hook: &accounts.payout_hook,
+                payout_hook_bump: ctx.bumps.payout_hook,
+                registry_program: &accounts.registry_program,
+                payout_wallet: &accounts.payout_wallet,
+                co_creator_split: &accounts.co_creator_split,
+                platform_wallet: &accounts.platform_wallet,
+                treasury_wallet: &accounts.treasury_wallet,
+                guardian_config: &accounts.guardian_config,
+                system_program: &accounts.system_program,
+                analytics: &accounts.analytics,
+                analytics_hook_bump: ctx.bumps.analytics.analytics_hook,
+                escrow_token_account: &accounts.escrow_token_account,
+                payout_token_account: &accounts.payout_token_account,
+                platform_token_account: &accounts.platform_token_account,
+                treasury_token_account: &accounts.treasury_token_account,
+                token_program: &accounts.token_program,
+                user: accounts.user.to_account_info(),
+                payer: accounts.arbiter.to_account_info(),
+            },
+            accounts.user_token_account.as_ref(),
+            agent_bps,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(DisputeResolved {
+            request_id: service_request.request_id,
+            agent_id: service_request.agent_id,
+            arbiter: accounts.arbiter.key(),
+            outcome,
+            agent_amount,
+            refund_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Join the jury pool, staking `JUROR_STAKE` lamports
+    pub fn register_juror(ctx: Context<RegisterJuror>) -> Result<()> {
+        let juror = ctx.accounts.juror.key();
+        let juror_pool = &mut ctx.accounts.juror_pool;
+        require!(
+            !juror_pool.jurors.iter().any(|seat| seat.juror == juror),
+            ErrorCode::JurorAlreadyRegistered
+        );
+        require!(juror_pool.jurors.len() < MAX_JURORS, ErrorCode::JurorPoolFull);
+
+        juror_pool.jurors.push(JurorSeat {
+            juror,
+            stake: JUROR_STAKE,
+            active_panels: 0,
+        });
+
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            &juror,
+            &juror_pool.key(),
+            JUROR_STAKE,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &transfer_instruction,
+            &[
+                ctx.accounts.juror.to_account_info(),
+                ctx.accounts.juror_pool.to_account_info(),
+            ],
+        )?;
+
+        emit!(JurorRegistered {
+            juror,
+            stake: JUROR_STAKE,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Leave the jury pool and withdraw the stake, with any rewards and less
+    /// any penalties; not while on an unsettled panel
+    pub fn leave_jury(ctx: Context<LeaveJury>) -> Result<()> {
+        let juror = ctx.accounts.juror.key();
+        let juror_pool = &mut ctx.accounts.juror_pool;
+        let seat = juror_pool
+            .jurors
+            .iter()
+            .position(|seat| seat.juror == juror)
+            .ok_or(ErrorCode::NotAJuror)?;
+        require!(juror_pool.jurors[seat].active_panels == 0, ErrorCode::JurorOnPanel);
+
+        let stake = juror_pool.jurors.swap_remove(seat).stake;
+        **juror_pool.to_account_info().try_borrow_mut_lamports()? -= stake;
+        **ctx.accounts.juror.try_borrow_mut_lamports()? += stake;
+
+        emit!(JurorLeft {
+            juror,
+            stake,
             timestamp: Clock::get()?.unix_timestamp,
         });
 
         Ok(())
     }
 
+    /// Draw a panel of `PANEL_SIZE` jurors to vote on a disputed request,
+    /// seeded from the hash of the slot committed to when it was disputed.
+    /// Anyone can call this once that slot has passed, while the `SlotHashes`
+    /// sysvar still holds it; jurors who are the user, or the agent's creator
+    /// or payout wallet, are skipped
+    pub fn draw_panel(ctx: Context<DrawPanel>) -> Result<()> {
+        let service_request = &ctx.accounts.service_request;
+        require!(
+            service_request.status == RequestStatus::Disputed,
+            ErrorCode::RequestNotDisputed
+        );
+        let panel_slot = service_request.panel_slot.ok_or(ErrorCode::RequestNotDisputed)?;
+        require!(Clock::get()?.slot > panel_slot, ErrorCode::PanelSlotNotReached);
+
+        let request_key = service_request.key();
+        let slot_hash = read_slot_hash(&ctx.accounts.slot_hashes, panel_slot)?
+            .ok_or(ErrorCode::PanelSlotExpired)?;
+        let seed = hashv(&[&slot_hash, request_key.as_ref()]).to_bytes();
+        let excluded = [
+            service_request.user,
+            read_creator(&ctx.accounts.agent_profile)?,
+            read_payout_wallet(&ctx.accounts.agent_profile)?,
+        ];
+        let juror_pool = &mut ctx.accounts.juror_pool;
+        let mut jurors = Vec::with_capacity(PANEL_SIZE);
+        for seat in draw_jurors(&juror_pool.jurors, &seed, &excluded)? {
+            let seat = &mut juror_pool.jurors[seat];
+            seat.active_panels = seat.active_panels.checked_add(1).ok_or(ErrorCode::MathOverflow)?;
+            jurors.push(seat.juror);
+        }
+
+        let voting_ends_at = Clock::get()?.unix_timestamp + VOTING_WINDOW;
+        let dispute_panel = &mut ctx.accounts.dispute_panel;
+        dispute_panel.service_request = request_key;
+        dispute_panel.payer = ctx.accounts.caller.key();
+        dispute_panel.jurors = jurors.clone();
+        dispute_panel.votes = vec![None; PANEL_SIZE];
+        dispute_panel.voting_ends_at = voting_ends_at;
+
+        emit!(PanelDrawn {
+            request_id: service_request.request_id,
+            agent_id: service_request.agent_id,
+            jurors,
+            voting_ends_at,
+        });
+
+        Ok(())
+    }
+
+    /// Commit a disputed request's panel draw to a new slot once the committed
+    /// one has left the `SlotHashes` sysvar without a panel being drawn.
+    /// Anyone can call this
+    pub fn renew_panel_slot(ctx: Context<RenewPanelSlot>) -> Result<()> {
+        let service_request = &mut ctx.accounts.service_request;
+        require!(
+            service_request.status == RequestStatus::Disputed,
+            ErrorCode::RequestNotDisputed
+        );
+        let panel_slot = service_request.panel_slot.ok_or(ErrorCode::RequestNotDisputed)?;
+        let slot = Clock::get()?.slot;
+        require!(
+            slot > panel_slot && read_slot_hash(&ctx.accounts.slot_hashes, panel_slot)?.is_none(),
+            ErrorCode::PanelSlotNotExpired
+        );
+
+        service_request.panel_slot = Some(slot + PANEL_SLOT_DELAY);
+
+        Ok(())
+    }
+
+    /// Vote on a dispute as one of its panel's jurors, before `VOTING_WINDOW` ends
+    pub fn cast_vote(ctx: Context<CastVote>, verdict: Verdict) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let juror = ctx.accounts.juror.key();
+        let dispute_panel = &mut ctx.accounts.dispute_panel;
+        require!(now < dispute_panel.voting_ends_at, ErrorCode::VotingClosed);
+        let seat = dispute_panel
+            .jurors
+            .iter()
+            .position(|panelist| *panelist == juror)
+            .ok_or(ErrorCode::NotOnPanel)?;
+        require!(dispute_panel.votes[seat].is_none(), ErrorCode::AlreadyVoted);
+
+        dispute_panel.votes[seat] = Some(verdict);
+
+        emit!(JurorVoted {
+            request_id: dispute_panel.service_request,
+            juror,
+            verdict,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Settle a disputed request by its panel's vote once every juror has
+    /// voted or `VOTING_WINDOW` has ended. The majority's side gets the payment,
+    /// paid out as on approval or refunded; a tie splits it in half. Jurors
+    /// who didn't vote forfeit `NON_VOTER_PENALTY` of their stake, which the
+    /// majority shares with a `JUROR_FEE_BPS` cut of lamport payments; with
+    /// no votes at all the forfeits go to the treasury. Anyone can call this;
+    /// fee wallets are the royalty config's and co-creators are listed as for
+    /// `approve_result`
+    pub fn settle_panel<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettlePanel<'info>>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let dispute_panel = &ctx.accounts.dispute_panel;
+        require!(
+            ctx.accounts.service_request.status == RequestStatus::Disputed,
+            ErrorCode::RequestNotDisputed
+        );
+        require!(
+            now >= dispute_panel.voting_ends_at || dispute_panel.votes.iter().all(Option::is_some),
+            ErrorCode::VotingOpen
+        );
+
+        let votes_for = |verdict| {
+            dispute_panel.votes.iter().filter(|vote| **vote == Some(verdict)).count() as u8
+        };
+        let (votes_for_agent, votes_for_user) = (votes_for(Verdict::Agent), votes_for(Verdict::User));
+        let (agent_bps, majority) = match votes_for_agent.cmp(&votes_for_user) {
+            std::cmp::Ordering::Greater => (10_000, Some(Verdict::Agent)),
+            std::cmp::Ordering::Less => (0, Some(Verdict::User)),
+            std::cmp::Ordering::Equal => (5_000, None),
+        };
+        // On a tie everyone who voted shares the rewards
+        let winners: Vec<Pubkey> = dispute_panel
+            .jurors
+            .iter()
+            .zip(&dispute_panel.votes)
+            .filter(|(_, vote)| vote.is_some() && (majority.is_none() || **vote == majority))
+            .map(|(juror, _)| *juror)
+            .collect();
+        let panel: Vec<(Pubkey, bool)> = dispute_panel
+            .jurors
+            .iter()
+            .zip(&dispute_panel.votes)
+            .map(|(juror, vote)| (*juror, vote.is_some()))
+            .collect();
+
+        // Free the panel's jurors and take the non-voters' forfeits
+        let mut forfeited = 0;
+        for (juror, voted) in panel {
+            let seat = juror_pool_seat(&mut ctx.accounts.juror_pool, &juror)?;
+            seat.active_panels = seat.active_panels.saturating_sub(1);
+            if !voted {
+                let forfeit = NON_VOTER_PENALTY.min(seat.stake);
+                seat.stake -= forfeit;
+                forfeited += forfeit;
+            }
+        }
+
+        let service_request = &ctx.accounts.service_request;
+        let request_key = service_request.key();
+        let juror_fee = if winners.is_empty() || service_request.payment_mint.is_some() {
+            0
+        } else {
+            ((service_request.amount as u128) * (JUROR_FEE_BPS as u128) / 10_000) as u64
+        };
+        let juror_rewards = if winners.is_empty() {
+            **ctx.accounts.juror_pool.to_account_info().try_borrow_mut_lamports()? -= forfeited;
+            **ctx.accounts.treasury_wallet.try_borrow_mut_lamports()? += forfeited;
+            0
+        } else {
+            release_escrow(
+                &ctx.accounts.escrow_account,
+                &ctx.accounts.juror_pool.to_account_info(),
+                &ctx.accounts.system_program,
+                &request_key,
+                ctx.bumps.escrow_account,
+                juror_fee,
+            )?;
+            let rewards = juror_fee + forfeited;
+            let share = rewards / winners.len() as u64;
+            for (i, winner) in winners.iter().enumerate() {
+                let seat = juror_pool_seat(&mut ctx.accounts.juror_pool, winner)?;
+                // The first winner also takes the rounding remainder
+                seat.stake += if i == 0 { share + rewards % winners.len() as u64 } else { share };
+            }
+            rewards
+        };
+
+        let service_request = &mut ctx.accounts.service_request;
+        service_request.status = RequestStatus::Resolved;
+        close_open_request(&mut ctx.accounts.agent_requests, service_request.agent_id);
+
+        let accounts = &ctx.accounts;
+        let service_request = &accounts.service_request;
+        let (agent_amount, refund_amount) = settle_dispute(
+            Payout {
+                service_request,
+                amount: service_request.amount - juror_fee,
+                escrow_account: &accounts.escrow_account,
+                escrow_bump: ctx.bumps.escrow_account,
+                agent_profile: &accounts.agent_profile,
+                payout_hook: &accounts.payout_hook,
+                payout_hook_bump: ctx.bumps.payout_hook,
+                registry_program: &accounts.registry_program,
+                payout_wallet: &accounts.payout_wallet,
+                co_creator_split: &accounts.co_creator_split,
+                platform_wallet: &accounts.platform_wallet,
+                treasury_wallet: &accounts.treasury_wallet,
+                guardian_config: &accounts.guardian_config,
+                system_program: &accounts.system_program,
+                analytics: &accounts.analytics,
+                analytics_hook_bump: ctx.bumps.analytics.analytics_hook,
+                escrow_token_account: &accounts.escrow_token_account,
+                payout_token_account: &accounts.payout_token_account,
+                platform_token_account: &accounts.platform_token_account,
+                treasury_token_account: &accounts.treasury_token_account,
+                token_program: &accounts.token_program,
+                user: accounts.user.to_account_info(),
+                payer: accounts.caller.to_account_info(),
+            },
+            accounts.user_token_account.as_ref(),
+            agent_bps,
+            ctx.remaining_accounts,
+        )?;
+
+        emit!(PanelSettled {
+            request_id: service_request.request_id,
+            agent_id: service_request.agent_id,
+            votes_for_agent,
+            votes_for_user,
+            agent_amount,
+            refund_amount,
+            juror_rewards,
+            forfeited,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
     pub fn cancel_request(
         ctx: Context<CancelRequest>,
     ) -> Result<()> {
@@ -184,13 +1020,32 @@ pub mod marketplace_escrow {
         );
 
         service_request.status = RequestStatus::Cancelled;
+        close_open_request(&mut ctx.accounts.agent_requests, service_request.agent_id);
 
         // Refund the user
-        let escrow_account = &mut ctx.accounts.escrow_account;
-        let user = &mut ctx.accounts.user;
-
-        **escrow_account.try_borrow_mut_lamports()? -= service_request.amount;
-        **user.try_borrow_mut_lamports()? += service_request.amount;
+        let request_key = service_request.key();
+        let escrow_account = &ctx.accounts.escrow_account;
+        let token_escrow = token_escrow(
+            service_request.payment_mint,
+            &escrow_account.key(),
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        let user = &ctx.accounts.user;
+        let recipient = payout_recipient(user, ctx.accounts.user_token_account.as_ref(), token_escrow.is_some())?;
+        release_payment(
+            escrow_account,
+            &ctx.accounts.system_program,
+            token_escrow.as_ref(),
+            &request_key,
+            ctx.bumps.escrow_account,
+            &user.key(),
+            &recipient,
+            service_request.amount,
+        )?;
+        if let Some(token_escrow) = &token_escrow {
+            close_token_escrow(escrow_account, token_escrow, &request_key, ctx.bumps.escrow_account, user)?;
+        }
 
         emit!(RequestCancelled {
             request_id: service_request.request_id,
@@ -201,46 +1056,1992 @@ pub mod marketplace_escrow {
 
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(agent_id: Pubkey)]
-pub struct CreateServiceRequest<'info> {
-    #[account(
-        init,
-        payer = user,
-        space = 8 + ServiceRequest::INIT_SPACE,
-        seeds = [b"request", user.key().as_ref(), agent_id.as_ref()],
-        bump
-    )]
-    pub service_request: Account<'info, ServiceRequest>,
+    /// Refund the user of a request whose deadline passed before the agent
+    /// submitted a result. Anyone can call this; the request is cancelled
+    pub fn expire_request(ctx: Context<ExpireRequest>) -> Result<()> {
+        let service_request = &mut ctx.accounts.service_request;
+        let now = Clock::get()?.unix_timestamp;
 
-    #[account(
-        mut,
-        seeds = [b"escrow", service_request.key().as_ref()],
-        bump
-    )]
-    /// CHECK: This is a PDA used for escrow
-    pub escrow_account: UncheckedAccount<'info>,
+        require!(
+            service_request.status == RequestStatus::Pending
+                || service_request.status == RequestStatus::InProgress,
+            ErrorCode::InvalidRequestStatus
+        );
+        require!(
+            matches!(service_request.deadline, Some(deadline) if now > deadline),
+            ErrorCode::RequestNotExpired
+        );
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+        service_request.status = RequestStatus::Cancelled;
+        close_open_request(&mut ctx.accounts.agent_requests, service_request.agent_id);
+
+        let request_key = service_request.key();
+        let escrow_account = &ctx.accounts.escrow_account;
+        let token_escrow = token_escrow(
+            service_request.payment_mint,
+            &escrow_account.key(),
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        let user = &ctx.accounts.user;
+        let recipient = payout_recipient(user, ctx.accounts.user_token_account.as_ref(), token_escrow.is_some())?;
+        release_payment(
+            escrow_account,
+            &ctx.accounts.system_program,
+            token_escrow.as_ref(),
+            &request_key,
+            ctx.bumps.escrow_account,
+            &user.key(),
+            &recipient,
+            service_request.amount,
+        )?;
+        if let Some(token_escrow) = &token_escrow {
+            close_token_escrow(escrow_account, token_escrow, &request_key, ctx.bumps.escrow_account, user)?;
+        }
 
-    pub system_program: Program<'info, System>,
-}
+        emit!(RequestExpired {
+            request_id: service_request.request_id,
+            agent_id: service_request.agent_id,
+            user: user.key(),
+            refund_amount: service_request.amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Close a settled request (approved, cancelled, or resolved), returning
+    /// its rent and anything left in its escrow to the user, who can then
+    /// open a new request of the agent. A request paid in tokens also sweeps
+    /// tokens sent to its escrow token account after settlement to the user
+    /// and closes it, so the next request at the same escrow starts empty
+    pub fn close_request(ctx: Context<CloseRequest>) -> Result<()> {
+        let service_request = &ctx.accounts.service_request;
+        require!(
+            matches!(
+                service_request.status,
+                RequestStatus::Approved | RequestStatus::Cancelled | RequestStatus::Resolved
+            ),
+            ErrorCode::RequestNotSettled
+        );
+
+        let request_key = service_request.key();
+        let escrow_account = &ctx.accounts.escrow_account;
+        let token_escrow = token_escrow(
+            service_request.payment_mint,
+            &escrow_account.key(),
+            &ctx.accounts.escrow_token_account,
+            &ctx.accounts.token_program,
+        )?;
+        // Settlement closes an emptied escrow token account, so one still open holds stray tokens
+        if let Some(token_escrow) = token_escrow.filter(|escrow| !escrow.escrow_token_account.data_is_empty()) {
+            let user = &ctx.accounts.user;
+            let recipient = payout_recipient(user, ctx.accounts.user_token_account.as_ref(), true)?;
+            let stray = token::accessor::amount(&token_escrow.escrow_token_account.to_account_info())?;
+            release_payment(
+                escrow_account,
+                &ctx.accounts.system_program,
+                Some(&token_escrow),
+                &request_key,
+                ctx.bumps.escrow_account,
+                &user.key(),
+                &recipient,
+                stray,
+            )?;
+            close_token_escrow(escrow_account, &token_escrow, &request_key, ctx.bumps.escrow_account, user)?;
+        }
+
+        release_escrow(
+            escrow_account,
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.system_program,
+            &request_key,
+            ctx.bumps.escrow_account,
+            escrow_account.lamports(),
+        )?;
+
+        emit!(RequestClosed {
+            request_id: service_request.request_id,
+            agent_id: service_request.agent_id,
+            user: ctx.accounts.user.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Set how many open requests an agent accepts at once, zero for no limit.
+    /// Only the registry can call this, signing with its capacity hook, when
+    /// the agent's holder changes its declared capacity
+    pub fn set_request_capacity(
+        ctx: Context<SetRequestCapacity>,
+        agent_id: Pubkey,
+        max_open_requests: u32,
+    ) -> Result<()> {
+        let agent_requests = &mut ctx.accounts.agent_requests;
+        agent_requests.agent_id = agent_id;
+        agent_requests.max_open_requests = max_open_requests;
+        Ok(())
+    }
+
+    /// Refuse new requests for the agent until `paused_until`, zero to resume
+    /// at once. Only the registry can call this, signing with its capacity
+    /// hook, when the agent's holder pauses or resumes its listings
+    pub fn set_request_pause(
+        ctx: Context<SetRequestPause>,
+        agent_id: Pubkey,
+        paused_until: i64,
+    ) -> Result<()> {
+        let agent_requests = &mut ctx.accounts.agent_requests;
+        agent_requests.agent_id = agent_id;
+        agent_requests.paused_until = paused_until;
+        Ok(())
+    }
+
+    /// Accept requests for the agent only from users on its registry
+    /// allowlist, or from anyone again. Only the registry can call this,
+    /// signing with its capacity hook, when the agent's holder changes its
+    /// visibility
+    pub fn set_request_visibility(
+        ctx: Context<SetRequestVisibility>,
+        agent_id: Pubkey,
+        allowlist_only: bool,
+    ) -> Result<()> {
+        let agent_requests = &mut ctx.accounts.agent_requests;
+        agent_requests.agent_id = agent_id;
+        agent_requests.allowlist_only = allowlist_only;
+        Ok(())
+    }
+
+    /// Set how many requests each wallet can make of the agent for free. Only
+    /// the registry can call this, signing with its capacity hook, when the
+    /// agent's holder changes its trial
+    pub fn set_request_trials(
+        ctx: Context<SetRequestTrials>,
+        agent_id: Pubkey,
+        trial_requests: u8,
+    ) -> Result<()> {
+        let agent_requests = &mut ctx.accounts.agent_requests;
+        agent_requests.agent_id = agent_id;
+        agent_requests.trial_requests = trial_requests;
+        Ok(())
+    }
+
+    /// Refuse new requests for a retired agent for good, recording its
+    /// successor. Only the registry can call this, signing with its capacity
+    /// hook, when the agent's holder retires it
+    pub fn retire_requests(
+        ctx: Context<RetireRequests>,
+        agent_id: Pubkey,
+        successor: Option<Pubkey>,
+    ) -> Result<()> {
+        let agent_requests = &mut ctx.accounts.agent_requests;
+        agent_requests.agent_id = agent_id;
+        agent_requests.retired = true;
+        agent_requests.successor = successor;
+        Ok(())
+    }
+
+    /// Refuse new requests for an agent the registry froze, or accept them
+    /// again once unfrozen. Only the registry can call this, signing with its
+    /// capacity hook, when its slash authority freezes or unfreezes the agent
+    pub fn set_request_freeze(
+        ctx: Context<SetRequestFreeze>,
+        agent_id: Pubkey,
+        frozen: bool,
+    ) -> Result<()> {
+        let agent_requests = &mut ctx.accounts.agent_requests;
+        agent_requests.agent_id = agent_id;
+        agent_requests.frozen = frozen;
+        Ok(())
+    }
+
+    /// Upgrade a service request written under an older layout to the
+    /// current one during a schema migration (guardian authority, protocol
+    /// paused). Fields are only ever appended, so every field the old layout
+    /// held is kept and the rest take what they would have been set to
+    pub fn migrate_service_request(ctx: Context<MigrateServiceRequest>) -> Result<()> {
+        let target = &ctx.accounts.service_request;
+        let space = 8 + ServiceRequest::INIT_SPACE;
+        require!(target.data_len() < space, ErrorCode::AccountUpToDate);
+
+        let mut service_request: ServiceRequest =
+            read_upgraded(target, space, ErrorCode::MigrationTargetMismatch)?;
+        require_keys_eq!(
+            service_request.request_id,
+            target.key(),
+            ErrorCode::MigrationIdentityMismatch
+        );
+        upgrade_service_request(&mut service_request)?;
+        write_upgraded(
+            target,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            space,
+            &service_request,
+        )?;
+
+        emit!(AccountMigrated {
+            account: target.key(),
+            size: space as u32,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// Helper function to fill in the fields an older service request layout
+// lacked with what the current instructions would have set. A request
+// disputed before panels were seeded from a committed slot commits to one now
+fn upgrade_service_request(service_request: &mut ServiceRequest) -> Result<()> {
+    if service_request.status == RequestStatus::Disputed && service_request.panel_slot.is_none() {
+        service_request.panel_slot = Some(Clock::get()?.slot + PANEL_SLOT_DELAY);
+    }
+    Ok(())
+}
+
+/// Accounts a request is paid out with, by `approve_result`, `auto_release`, or `resolve_dispute`
+struct Payout<'a, 'info> {
+    service_request: &'a Account<'info, ServiceRequest>,
+    /// Part of the escrowed payment to split
+    amount: u64,
+    escrow_account: &'a UncheckedAccount<'info>,
+    escrow_bump: u8,
+    agent_profile: &'a UncheckedAccount<'info>,
+    payout_hook: &'a UncheckedAccount<'info>,
+    payout_hook_bump: u8,
+    registry_program: &'a UncheckedAccount<'info>,
+    payout_wallet: &'a UncheckedAccount<'info>,
+    co_creator_split: &'a UncheckedAccount<'info>,
+    platform_wallet: &'a UncheckedAccount<'info>,
+    treasury_wallet: &'a UncheckedAccount<'info>,
+    guardian_config: &'a Account<'info, GuardianConfig>,
+    system_program: &'a Program<'info, System>,
+    analytics: &'a AnalyticsHook<'info>,
+    analytics_hook_bump: Option<u8>,
+    escrow_token_account: &'a Option<UncheckedAccount<'info>>,
+    payout_token_account: &'a Option<UncheckedAccount<'info>>,
+    platform_token_account: &'a Option<UncheckedAccount<'info>>,
+    treasury_token_account: &'a Option<UncheckedAccount<'info>>,
+    token_program: &'a Option<Program<'info, Token>>,
+    /// Requester, who gets the escrow token account's rent back
+    user: AccountInfo<'info>,
+    /// Pays for any analytics accounts the report creates
+    payer: AccountInfo<'info>,
+}
+
+// Helper function to split a request's payment between the agent's payout wallet and
+// co-creators, the platform, and the treasury, and record the earnings. Co-creator wallets, or
+// their token accounts for token payments, are `remaining_accounts`. Closes a token escrow, so
+// any refund from it must come first
+fn pay_out_request<'info>(
+    payout: Payout<'_, 'info>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+) -> Result<()> {
+    let service_request = payout.service_request;
+
+    // Calculate payment splits (85% creator, 10% platform, 5% treasury)
+    let total_amount = payout.amount;
+    let creator_amount = (total_amount * 85) / 100;
+    let platform_amount = (total_amount * 10) / 100;
+    let treasury_amount = total_amount - creator_amount - platform_amount;
+
+    let request_key = service_request.key();
+    let escrow_account = payout.escrow_account;
+    let escrow_bump = payout.escrow_bump;
+    let system_program = payout.system_program;
+
+    require_keys_eq!(
+        payout.payout_wallet.key(),
+        read_payout_wallet(payout.agent_profile)?,
+        ErrorCode::PayoutWalletMismatch
+    );
+    let token_escrow = token_escrow(
+        service_request.payment_mint,
+        &escrow_account.key(),
+        payout.escrow_token_account,
+        payout.token_program,
+    )?;
+    let paid_in_tokens = token_escrow.is_some();
+
+    // Co-creators take their shares out of the creator's 85%
+    let co_creators = read_co_creators(payout.co_creator_split)?;
+    require!(
+        remaining_accounts.len() >= co_creators.len(),
+        ErrorCode::CoCreatorMismatch
+    );
+    let mut creator_remainder = creator_amount;
+    for ((co_creator, bps), account) in co_creators.into_iter().zip(remaining_accounts) {
+        // Token payments list the co-creator's token account rather than its wallet
+        if !paid_in_tokens {
+            require_keys_eq!(account.key(), co_creator, ErrorCode::CoCreatorMismatch);
+        }
+        let share = ((creator_amount as u128) * (bps as u128) / 10_000) as u64;
+        release_payment(escrow_account, system_program, token_escrow.as_ref(), &request_key, escrow_bump, &co_creator, account, share)?;
+        creator_remainder -= share;
+
+        emit!(CoCreatorPaid {
+            request_id: service_request.request_id,
+            co_creator,
+            amount: share,
+        });
+    }
+
+    // Transfer to the payout wallet (85%, less co-creator shares)
+    let payout_wallet = payout.payout_wallet;
+    let recipient = payout_recipient(payout_wallet, payout.payout_token_account.as_ref(), paid_in_tokens)?;
+    release_payment(escrow_account, system_program, token_escrow.as_ref(), &request_key, escrow_bump, &payout_wallet.key(), &recipient, creator_remainder)?;
+
+    // Transfer to platform (10%)
+    let platform_wallet = payout.platform_wallet;
+    let recipient = payout_recipient(platform_wallet, payout.platform_token_account.as_ref(), paid_in_tokens)?;
+    release_payment(escrow_account, system_program, token_escrow.as_ref(), &request_key, escrow_bump, &platform_wallet.key(), &recipient, platform_amount)?;
+
+    // Transfer to treasury (5%)
+    let treasury_wallet = payout.treasury_wallet;
+    let recipient = payout_recipient(treasury_wallet, payout.treasury_token_account.as_ref(), paid_in_tokens)?;
+    release_payment(escrow_account, system_program, token_escrow.as_ref(), &request_key, escrow_bump, &treasury_wallet.key(), &recipient, treasury_amount)?;
+
+    // The emptied escrow token account returns its rent to the user who opened it
+    if let Some(token_escrow) = &token_escrow {
+        close_token_escrow(escrow_account, token_escrow, &request_key, escrow_bump, &payout.user)?;
+    }
+
+    // Earnings and analytics volumes are kept in lamports, so token payments only count the service
+    let (earned, volume, fees) = if paid_in_tokens {
+        (0, 0, 0)
+    } else {
+        (creator_amount, total_amount, platform_amount + treasury_amount)
+    };
+    record_earnings(
+        payout.agent_profile,
+        payout.payout_hook,
+        payout.payout_hook_bump,
+        payout.registry_program,
+        payout.guardian_config,
+        earned,
+    )?;
+
+    emit!(PaymentReleased {
+        request_id: service_request.request_id,
+        creator: payout.payout_wallet.key(),
+        creator_amount,
+        platform_amount,
+        treasury_amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    report_analytics(
+        payout.analytics,
+        payout.analytics_hook_bump,
+        payout.payer.clone(),
+        service_request.agent_id,
+        RequestEvent::Approved,
+        volume,
+        fees,
+    )?;
+
+    Ok(())
+}
+
+// Helper function to settle a disputed request: refund the user all but `agent_bps` of
+// `payout.amount`, then pay the agent's share out as on approval. Returns the agent's share and
+// the refund
+fn settle_dispute<'info>(
+    mut payout: Payout<'_, 'info>,
+    user_token_account: Option<&UncheckedAccount<'info>>,
+    agent_bps: u16,
+    remaining_accounts: &'info [AccountInfo<'info>],
+) -> Result<(u64, u64)> {
+    let agent_amount = ((payout.amount as u128) * (agent_bps as u128) / 10_000) as u64;
+    let refund_amount = payout.amount - agent_amount;
+
+    // Refund the user's part first; paying the agent closes a token escrow
+    let request_key = payout.service_request.key();
+    let escrow_account = payout.escrow_account;
+    let escrow_bump = payout.escrow_bump;
+    let token_escrow = token_escrow(
+        payout.service_request.payment_mint,
+        &escrow_account.key(),
+        payout.escrow_token_account,
+        payout.token_program,
+    )?;
+    let user = payout.user.clone();
+    if refund_amount > 0 {
+        let recipient = payout_recipient(&user, user_token_account, token_escrow.is_some())?;
+        release_payment(
+            escrow_account,
+            payout.system_program,
+            token_escrow.as_ref(),
+            &request_key,
+            escrow_bump,
+            &user.key(),
+            &recipient,
+            refund_amount,
+        )?;
+    }
+
+    if agent_amount > 0 {
+        payout.amount = agent_amount;
+        pay_out_request(payout, remaining_accounts)?;
+    } else if let Some(token_escrow) = &token_escrow {
+        close_token_escrow(escrow_account, token_escrow, &request_key, escrow_bump, &user)?;
+    }
+
+    Ok((agent_amount, refund_amount))
+}
+
+// Helper function to find a juror's seat in the pool
+fn juror_pool_seat<'a>(juror_pool: &'a mut JurorPool, juror: &Pubkey) -> Result<&'a mut JurorSeat> {
+    juror_pool
+        .jurors
+        .iter_mut()
+        .find(|seat| seat.juror == *juror)
+        .ok_or(error!(ErrorCode::NotAJuror))
+}
+
+// Helper function to draw `PANEL_SIZE` distinct seats from the pool by a partial shuffle driven by
+// `seed`, skipping `excluded` wallets and jurors whose stake can't cover a missed vote
+fn draw_jurors(seats: &[JurorSeat], seed: &[u8; 32], excluded: &[Pubkey]) -> Result<Vec<usize>> {
+    let mut eligible: Vec<usize> = seats
+        .iter()
+        .enumerate()
+        .filter(|(_, seat)| seat.stake >= NON_VOTER_PENALTY && !excluded.contains(&seat.juror))
+        .map(|(index, _)| index)
+        .collect();
+    require!(eligible.len() >= PANEL_SIZE, ErrorCode::NotEnoughJurors);
+
+    for i in 0..PANEL_SIZE {
+        let draw = hashv(&[seed, &(i as u64).to_le_bytes()]).to_bytes();
+        let remaining = (eligible.len() - i) as u64;
+        let j = i + (u64::from_le_bytes(draw[..8].try_into().unwrap()) % remaining) as usize;
+        eligible.swap(i, j);
+    }
+    eligible.truncate(PANEL_SIZE);
+    Ok(eligible)
+}
+
+// Helper function to read `slot`'s hash from the `SlotHashes` sysvar: an entry count, then (slot, hash) pairs
+// newest first. `None` once the slot is older than the sysvar reaches back, or if it was skipped
+fn read_slot_hash(slot_hashes: &UncheckedAccount, slot: u64) -> Result<Option<[u8; 32]>> {
+    let data = slot_hashes.try_borrow_data()?;
+    require!(data.len() >= 8, ErrorCode::InvalidSlotHashes);
+    let count = u64::from_le_bytes(data[..8].try_into().unwrap()) as usize;
+    require!(data.len() >= 8 + count * 40, ErrorCode::InvalidSlotHashes);
+    Ok(data[8..8 + count * 40]
+        .chunks_exact(40)
+        .find(|entry| u64::from_le_bytes(entry[..8].try_into().unwrap()) == slot)
+        .map(|entry| entry[8..].try_into().unwrap()))
+}
+
+// Helper function to stop counting a settled request against its agent
+fn close_open_request(agent_requests: &mut AgentRequestCount, agent_id: Pubkey) {
+    agent_requests.agent_id = agent_id;
+    // Requests opened before the count existed were never added to it
+    agent_requests.open_requests = agent_requests.open_requests.saturating_sub(1);
+}
+
+// Helper function to check the requester's registry `AllowlistEntry`. Its
+// address is derived from the agent and user, and only the registry can
+// create accounts there, so an account it owns is the entry
+fn require_allowlisted(allowlist_entry: Option<&UncheckedAccount>) -> Result<()> {
+    let entry = allowlist_entry.ok_or(ErrorCode::NotAllowlisted)?;
+    require_keys_eq!(*entry.owner, AGENT_REGISTRY_ID, ErrorCode::NotAllowlisted);
+    Ok(())
+}
+
+// Helper function to check the requester has no registry `BlockEntry`, by
+// the same reasoning as `require_allowlisted`
+fn require_not_blocked(block_entry: &UncheckedAccount) -> Result<()> {
+    require_keys_neq!(*block_entry.owner, AGENT_REGISTRY_ID, ErrorCode::UserBlocked);
+    Ok(())
+}
+
+// Helper function to check `operator_key` is the registry's `OperatorKey` through which `manager`
+// lets `operator` act for it, by the same reasoning as `require_allowlisted`
+fn require_operator(operator_key: Option<&UncheckedAccount>, manager: &Pubkey, operator: &Pubkey) -> Result<()> {
+    let operator_key = operator_key.ok_or(ErrorCode::NotAgentManager)?;
+    let (expected, _) = Pubkey::find_program_address(
+        &[b"operator", manager.as_ref(), operator.as_ref()],
+        &AGENT_REGISTRY_ID,
+    );
+    require_keys_eq!(operator_key.key(), expected, ErrorCode::NotAgentManager);
+    require_keys_eq!(*operator_key.owner, AGENT_REGISTRY_ID, ErrorCode::NotAgentManager);
+    Ok(())
+}
+
+// Helper function to read who holds an agent's NFT from their token account for it
+fn read_nft_holder(holder_token_account: Option<&UncheckedAccount>, nft_mint: &Pubkey) -> Result<Pubkey> {
+    let holder_token_account = holder_token_account.ok_or(ErrorCode::NotAgentManager)?;
+    require_keys_eq!(*holder_token_account.owner, token::ID, ErrorCode::NotAgentManager);
+    let holder = TokenAccount::try_deserialize(&mut &holder_token_account.try_borrow_data()?[..])?;
+    require!(holder.mint == *nft_mint && holder.amount == 1, ErrorCode::NotAgentManager);
+    Ok(holder.owner)
+}
+
+// Helper function to check `accepted_mint` is the registry's `AcceptedMint` of `mint`, by the
+// same reasoning as `require_allowlisted`. Mirrors the registry's `check_accepted_mint`, which
+// this program can't call since the registry depends on it
+fn require_accepted_mint(accepted_mint: Option<&UncheckedAccount>, mint: &Pubkey) -> Result<()> {
+    let accepted_mint = accepted_mint.ok_or(ErrorCode::PaymentMintNotAccepted)?;
+    let (expected, _) = Pubkey::find_program_address(&[b"accepted_mint", mint.as_ref()], &AGENT_REGISTRY_ID);
+    require_keys_eq!(accepted_mint.key(), expected, ErrorCode::PaymentMintNotAccepted);
+    require_keys_eq!(*accepted_mint.owner, AGENT_REGISTRY_ID, ErrorCode::PaymentMintNotAccepted);
+    Ok(())
+}
+
+// Helper function to check a request escrows lamports, the unit registry offerings, capability
+// prices and bundles are priced in
+fn require_lamport_payment(service_request: &ServiceRequest) -> Result<()> {
+    require!(service_request.payment_mint.is_none(), ErrorCode::PaymentMintMismatch);
+    Ok(())
+}
+
+// Helper function to work out when a request is due from the agent's `AgentSla`. The oracle
+// only creates accounts at the agent's address, so one it owns is the record; agents it
+// hasn't observed yet have none and get no deadline
+fn sla_deadline(agent_sla: Option<&UncheckedAccount>, agent_id: Pubkey, now: i64) -> Result<Option<i64>> {
+    let Some(agent_sla) = agent_sla.filter(|account| *account.owner == SLA_ORACLE_ID) else {
+        return Ok(None);
+    };
+    let data = agent_sla.try_borrow_data()?;
+    require!(
+        data.len() >= 82 && data[..8] == AGENT_SLA_DISCRIMINATOR && data[8..40] == agent_id.to_bytes(),
+        ErrorCode::InvalidAgentSla
+    );
+    // agent_id, last_round: u64, uptime_bps: u16, latency_ms: u32, rounds_observed: u64,
+    // breach_count: u64, consecutive_breaches: u32, recommended_timeout: i64
+    let timeout = i64::from_le_bytes(data[74..82].try_into().unwrap());
+    if timeout <= 0 {
+        return Ok(None);
+    }
+    Ok(Some(now.checked_add(timeout).ok_or(ErrorCode::MathOverflow)?))
+}
+
+// Helper function to read the agent an `AgentVersion` belongs to, after checking the account really is one
+fn version_agent_id(agent_version: &UncheckedAccount) -> Result<Pubkey> {
+    let data = agent_version.try_borrow_data()?;
+    require!(
+        data.len() >= 40 && data[..8] == AGENT_VERSION_DISCRIMINATOR,
+        ErrorCode::InvalidAgentVersion
+    );
+    Ok(Pubkey::new_from_array(data[8..40].try_into().unwrap()))
+}
+
+// The fixed-size head of a registry `ServiceOffering`
+struct OfferingTerms {
+    agent_id: Pubkey,
+    price: u64,
+    is_active: bool,
+}
+
+// Helper function to read the terms of a `ServiceOffering`, after checking the account really is one
+fn read_service_offering(service_offering: &UncheckedAccount) -> Result<OfferingTerms> {
+    let data = service_offering.try_borrow_data()?;
+    require!(
+        data.len() >= 55 && data[..8] == SERVICE_OFFERING_DISCRIMINATOR,
+        ErrorCode::InvalidServiceOffering
+    );
+    // agent_id, offering_id: u16, price: u64, turnaround_secs: u32, is_active
+    Ok(OfferingTerms {
+        agent_id: Pubkey::new_from_array(data[8..40].try_into().unwrap()),
+        price: u64::from_le_bytes(data[42..50].try_into().unwrap()),
+        is_active: data[54] != 0,
+    })
+}
+
+// The terms of a registry `CapabilityPrice` a request escrows against
+struct CapabilityPriceTerms {
+    agent_id: Pubkey,
+    price: u64,
+}
+
+// Helper function to read a `CapabilityPrice`, after checking the account really is one
+fn read_capability_price(capability_price: &UncheckedAccount) -> Result<CapabilityPriceTerms> {
+    let data = capability_price.try_borrow_data()?;
+    require!(
+        data.len() >= 48 && data[..8] == CAPABILITY_PRICE_DISCRIMINATOR,
+        ErrorCode::InvalidCapabilityPrice
+    );
+    // agent_id, price: u64, then the rest
+    Ok(CapabilityPriceTerms {
+        agent_id: Pubkey::new_from_array(data[8..40].try_into().unwrap()),
+        price: u64::from_le_bytes(data[40..48].try_into().unwrap()),
+    })
+}
+
+// The terms of a registry `Bundle` a request escrows against
+struct BundleTerms {
+    agents: Vec<Pubkey>,
+    share_price: u64,
+    is_active: bool,
+}
+
+// Helper function to read the terms of a `Bundle`, after checking the account really is one
+fn read_bundle(bundle: &UncheckedAccount) -> Result<BundleTerms> {
+    let data = bundle.try_borrow_data()?;
+    require!(
+        data.len() >= 73 && data[..8] == BUNDLE_DISCRIMINATOR,
+        ErrorCode::InvalidBundle
+    );
+    // curator, bundle_id: u16, price: u64, discount_bps: u16, is_active,
+    // created_at, updated_at, then a Vec of agent ids
+    let price = u64::from_le_bytes(data[42..50].try_into().unwrap());
+    let discount_bps = u16::from_le_bytes(data[50..52].try_into().unwrap());
+    let count = u32::from_le_bytes(data[69..73].try_into().unwrap()) as usize;
+    require!(
+        count > 0 && data.len() >= 73 + count * 32,
+        ErrorCode::InvalidBundle
+    );
+    let agents = data[73..73 + count * 32]
+        .chunks_exact(32)
+        .map(|key| Pubkey::new_from_array(key.try_into().unwrap()))
+        .collect();
+    // Mirrors `Bundle::share_price` in the registry
+    let discounted = price as u128 * 10_000u128.saturating_sub(discount_bps as u128) / 10_000;
+    Ok(BundleTerms {
+        agents,
+        share_price: discounted.div_ceil(count as u128) as u64,
+        is_active: data[52] != 0,
+    })
+}
+
+// Helper function to credit the agent's registry profile with its creator
+// share, signing as the payout hook
+fn record_earnings<'info>(
+    agent_profile: &UncheckedAccount<'info>,
+    payout_hook: &UncheckedAccount<'info>,
+    hook_bump: u8,
+    registry_program: &UncheckedAccount<'info>,
+    guardian_config: &Account<'info, GuardianConfig>,
+    amount: u64,
+) -> Result<()> {
+    let mut data = RECORD_EARNINGS_IX.to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    let instruction = Instruction {
+        program_id: AGENT_REGISTRY_ID,
+        accounts: vec![
+            AccountMeta::new(agent_profile.key(), false),
+            AccountMeta::new_readonly(payout_hook.key(), true),
+            AccountMeta::new_readonly(guardian_config.key(), false),
+        ],
+        data,
+    };
+    anchor_lang::solana_program::program::invoke_signed(
+        &instruction,
+        &[
+            agent_profile.to_account_info(),
+            payout_hook.to_account_info(),
+            guardian_config.to_account_info(),
+            registry_program.to_account_info(),
+        ],
+        &[&[b"payout_hook", &[hook_bump]]],
+    )?;
+
+    Ok(())
+}
+
+// Helper function to read the payout wallet of a registry `AgentProfile`,
+// stored right after its agent id and creator
+fn read_payout_wallet(agent_profile: &UncheckedAccount) -> Result<Pubkey> {
+    let data = agent_profile.try_borrow_data()?;
+    require!(
+        data.len() >= 104 && data[..8] == AGENT_PROFILE_DISCRIMINATOR,
+        ErrorCode::PayoutWalletMismatch
+    );
+    Ok(Pubkey::new_from_array(data[72..104].try_into().unwrap()))
+}
+
+// Helper function to read an agent's creator from its registry profile
+fn read_creator(agent_profile: &UncheckedAccount) -> Result<Pubkey> {
+    let data = agent_profile.try_borrow_data()?;
+    require!(
+        data.len() >= 72 && data[..8] == AGENT_PROFILE_DISCRIMINATOR,
+        ErrorCode::PayoutWalletMismatch
+    );
+    Ok(Pubkey::new_from_array(data[40..72].try_into().unwrap()))
+}
+
+// Who an agent's registry profile lets manage it: its `authority` when set,
+// otherwise whoever holds `nft_mint`
+struct AgentManagers {
+    nft_mint: Pubkey,
+    authority: Option<Pubkey>,
+}
+
+// Helper function to read an agent's NFT mint and authority from its registry
+// profile. Both follow variable-length fields, so the profile is walked field
+// by field in the registry's layout
+fn read_agent_managers(agent_profile: &UncheckedAccount) -> Result<AgentManagers> {
+    let data = agent_profile.try_borrow_data()?;
+    require!(
+        data.len() >= 8 && data[..8] == AGENT_PROFILE_DISCRIMINATOR,
+        ErrorCode::InvalidAgentProfile
+    );
+    let mut profile = ProfileReader { data: &data, offset: 8 };
+
+    // agent_id, creator, payout_wallet, name, description, capabilities
+    profile.skip(96)?;
+    profile.skip_string()?;
+    profile.skip_string()?;
+    profile.skip_strings()?;
+    // pricing_model: PerQuery, Subscription, Custom, UsdPegged, Surge
+    match profile.read_u8()? {
+        0 | 1 => {
+            profile.skip(8)?;
+            profile.skip_option(32)?;
+        }
+        2 => {
+            profile.skip(9)?;
+            profile.skip_option(32)?;
+        }
+        3 => profile.skip(40)?,
+        4 => profile.skip(30)?,
+        _ => return err!(ErrorCode::InvalidAgentProfile),
+    }
+    // endpoints: url, region, priority
+    for _ in 0..profile.read_len()? {
+        profile.skip_string()?;
+        profile.skip_string()?;
+        profile.skip(1)?;
+    }
+    // ipfs_hash, reputation_score, total_services, total_earnings, created_at, is_active
+    profile.skip_string()?;
+    profile.skip(29)?;
+    let nft_mint = profile.read_pubkey()?;
+    // endpoint_verified, featured_until, is_verified, last_seen_at,
+    // max_concurrent_requests, paused_until
+    profile.skip(22)?;
+    profile.skip_option(8)?;
+    // capability_schemas: capability_id, input_schema, output_schema
+    for _ in 0..profile.read_len()? {
+        profile.skip_string()?;
+        profile.skip_string()?;
+        profile.skip_string()?;
+    }
+    // parent_agent, visibility, capability_limit, description_limit, trial_requests
+    profile.skip_option(32)?;
+    profile.skip(5)?;
+    // languages, dependencies, soulbound, retired, successor
+    profile.skip_strings()?;
+    let dependencies = profile.read_len()?;
+    profile.skip(dependencies * 32 + 2)?;
+    profile.skip_option(32)?;
+    let authority = match profile.read_u8()? {
+        0 => None,
+        _ => Some(profile.read_pubkey()?),
+    };
+
+    Ok(AgentManagers { nft_mint, authority })
+}
+
+// Cursor over a registry `AgentProfile`; running out of data means the
+// account isn't one
+struct ProfileReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> ProfileReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(ErrorCode::InvalidAgentProfile)?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<()> {
+        self.take(len).map(|_| ())
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_len(&mut self) -> Result<usize> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()) as usize)
+    }
+
+    fn read_pubkey(&mut self) -> Result<Pubkey> {
+        Ok(Pubkey::new_from_array(self.take(32)?.try_into().unwrap()))
+    }
+
+    fn skip_string(&mut self) -> Result<()> {
+        let len = self.read_len()?;
+        self.skip(len)
+    }
+
+    fn skip_strings(&mut self) -> Result<()> {
+        for _ in 0..self.read_len()? {
+            self.skip_string()?;
+        }
+        Ok(())
+    }
+
+    fn skip_option(&mut self, len: usize) -> Result<()> {
+        match self.read_u8()? {
+            0 => Ok(()),
+            _ => self.skip(len),
+        }
+    }
+}
+
+// Helper function to read an agent's co-creators and their shares in bps, if the registry has a list for it
+fn read_co_creators(co_creator_split: &UncheckedAccount) -> Result<Vec<(Pubkey, u16)>> {
+    if co_creator_split.data_is_empty() {
+        return Ok(Vec::new());
+    }
+    require_keys_eq!(
+        *co_creator_split.owner,
+        AGENT_REGISTRY_ID,
+        anchor_lang::error::ErrorCode::AccountOwnedByWrongProgram
+    );
+    let data = co_creator_split.try_borrow_data()?;
+    require!(
+        data.len() >= 44 && data[..8] == CO_CREATOR_SPLIT_DISCRIMINATOR,
+        ErrorCode::CoCreatorMismatch
+    );
+    // agent_id, then a Vec of (key, bps: u16)
+    let count = u32::from_le_bytes(data[40..44].try_into().unwrap()) as usize;
+    require!(data.len() >= 44 + count * 34, ErrorCode::CoCreatorMismatch);
+    Ok((0..count)
+        .map(|index| {
+            let at = 44 + index * 34;
+            (
+                Pubkey::new_from_array(data[at..at + 32].try_into().unwrap()),
+                u16::from_le_bytes(data[at + 32..at + 34].try_into().unwrap()),
+            )
+        })
+        .collect())
+}
+
+// Helper function to pay out of the system-owned escrow PDA, which only the system program can debit
+fn release_escrow<'info>(
+    escrow_account: &UncheckedAccount<'info>,
+    recipient: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    service_request: &Pubkey,
+    escrow_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let escrow_seeds: &[&[&[u8]]] = &[&[b"escrow", service_request.as_ref(), &[escrow_bump]]];
+    let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+        &escrow_account.key(),
+        &recipient.key(),
+        amount,
+    );
+
+    anchor_lang::solana_program::program::invoke_signed(
+        &transfer_instruction,
+        &[
+            escrow_account.to_account_info(),
+            recipient.clone(),
+            system_program.to_account_info(),
+        ],
+        escrow_seeds,
+    )?;
+
+    Ok(())
+}
+
+/// Token accounts a request paid in an SPL token releases its deposit through
+struct TokenEscrow<'a, 'info> {
+    mint: Pubkey,
+    /// The escrow PDA's associated token account of `mint`
+    escrow_token_account: &'a UncheckedAccount<'info>,
+    token_program: &'a Program<'info, Token>,
+}
+
+// Helper function to collect the token accounts of a request paid in `payment_mint`; requests paid in lamports have none
+fn token_escrow<'a, 'info>(
+    payment_mint: Option<Pubkey>,
+    escrow_account: &Pubkey,
+    escrow_token_account: &'a Option<UncheckedAccount<'info>>,
+    token_program: &'a Option<Program<'info, Token>>,
+) -> Result<Option<TokenEscrow<'a, 'info>>> {
+    let Some(mint) = payment_mint else {
+        return Ok(None);
+    };
+    let (Some(escrow_token_account), Some(token_program)) = (escrow_token_account, token_program)
+    else {
+        return err!(ErrorCode::MissingTokenAccounts);
+    };
+    require_keys_eq!(
+        escrow_token_account.key(),
+        get_associated_token_address(escrow_account, &mint),
+        ErrorCode::InvalidTokenAccount
+    );
+    Ok(Some(TokenEscrow {
+        mint,
+        escrow_token_account,
+        token_program,
+    }))
+}
+
+// Helper function to pick the account a payout lands in: the wallet itself, or its token account of the payment mint for a request paid in tokens
+fn payout_recipient<'info>(
+    wallet: &AccountInfo<'info>,
+    token_account: Option<&UncheckedAccount<'info>>,
+    paid_in_tokens: bool,
+) -> Result<AccountInfo<'info>> {
+    if !paid_in_tokens {
+        return Ok(wallet.clone());
+    }
+    token_account
+        .map(|account| account.to_account_info())
+        .ok_or(error!(ErrorCode::MissingTokenAccounts))
+}
+
+// Helper function to pay `amount` of a request's deposit to `owner`: lamports straight to `recipient`, or tokens to `recipient` as `owner`'s token account of the payment mint
+#[allow(clippy::too_many_arguments)]
+fn release_payment<'info>(
+    escrow_account: &UncheckedAccount<'info>,
+    system_program: &Program<'info, System>,
+    token_escrow: Option<&TokenEscrow<'_, 'info>>,
+    service_request: &Pubkey,
+    escrow_bump: u8,
+    owner: &Pubkey,
+    recipient: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let Some(token_escrow) = token_escrow else {
+        return release_escrow(escrow_account, recipient, system_program, service_request, escrow_bump, amount);
+    };
+
+    require_keys_eq!(*recipient.owner, token::ID, ErrorCode::InvalidTokenAccount);
+    let token_account = TokenAccount::try_deserialize(&mut &recipient.try_borrow_data()?[..])
+        .map_err(|_| error!(ErrorCode::InvalidTokenAccount))?;
+    require!(
+        token_account.owner == *owner && token_account.mint == token_escrow.mint,
+        ErrorCode::InvalidTokenAccount
+    );
+    if amount == 0 {
+        return Ok(());
+    }
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_escrow.token_program.to_account_info(),
+            token::Transfer {
+                from: token_escrow.escrow_token_account.to_account_info(),
+                to: recipient.clone(),
+                authority: escrow_account.to_account_info(),
+            },
+            &[&[b"escrow", service_request.as_ref(), &[escrow_bump]]],
+        ),
+        amount,
+    )
+}
+
+// Helper function to close a token-paid request's emptied escrow token account, returning its rent to `destination`.
+// Anyone can send tokens to the account's address, so one still holding a balance is left open rather than
+// failing the settlement
+fn close_token_escrow<'info>(
+    escrow_account: &UncheckedAccount<'info>,
+    token_escrow: &TokenEscrow<'_, 'info>,
+    service_request: &Pubkey,
+    escrow_bump: u8,
+    destination: &AccountInfo<'info>,
+) -> Result<()> {
+    if token::accessor::amount(&token_escrow.escrow_token_account.to_account_info())? > 0 {
+        return Ok(());
+    }
+    token::close_account(CpiContext::new_with_signer(
+        token_escrow.token_program.to_account_info(),
+        token::CloseAccount {
+            account: token_escrow.escrow_token_account.to_account_info(),
+            destination: destination.clone(),
+            authority: escrow_account.to_account_info(),
+        },
+        &[&[b"escrow", service_request.as_ref(), &[escrow_bump]]],
+    ))
+}
+
+// Helper function to report a request event to the analytics aggregator when its accounts are supplied
+fn report_analytics<'info>(
+    analytics: &AnalyticsHook<'info>,
+    hook_bump: Option<u8>,
+    payer: AccountInfo<'info>,
+    agent_id: Pubkey,
+    kind: RequestEvent,
+    amount: u64,
+    fee: u64,
+) -> Result<()> {
+    let (
+        Some(analytics_program),
+        Some(analytics_config),
+        Some(epoch_metrics),
+        Some(agent_activity),
+        Some(agent_analytics),
+        Some(analytics_hook),
+        Some(system_program),
+        Some(hook_bump),
+    ) = (
+        &analytics.analytics_program,
+        &analytics.analytics_config,
+        &analytics.epoch_metrics,
+        &analytics.agent_activity,
+        &analytics.agent_analytics,
+        &analytics.analytics_hook,
+        &analytics.system_program,
+        hook_bump,
+    ) else {
+        return Ok(());
+    };
+
+    let epoch = analytics_config.epoch_at(Clock::get()?.unix_timestamp);
+    let hook_seeds: &[&[&[u8]]] = &[&[b"analytics_hook", &[hook_bump]]];
+
+    protocol_analytics::cpi::record_request_event(
+        CpiContext::new_with_signer(
+            analytics_program.to_account_info(),
+            protocol_analytics::cpi::accounts::RecordRequestEvent {
+                analytics_config: analytics_config.to_account_info(),
+                epoch_metrics: epoch_metrics.to_account_info(),
+                agent_activity: agent_activity.to_account_info(),
+                agent_analytics: agent_analytics.to_account_info(),
+                reporter: analytics_hook.to_account_info(),
+                payer,
+                system_program: system_program.to_account_info(),
+            },
+            hook_seeds,
+        ),
+        epoch,
+        agent_id,
+        kind,
+        amount,
+        fee,
+    )
+}
+
+#[derive(Accounts)]
+#[instruction(agent_id: Pubkey)]
+pub struct CreateServiceRequest<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + ServiceRequest::INIT_SPACE,
+        seeds = [b"request", user.key().as_ref(), agent_id.as_ref()],
+        bump
+    )]
+    pub service_request: Account<'info, ServiceRequest>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", service_request.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA used for escrow
+    pub escrow_account: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + AgentRequestCount::INIT_SPACE,
+        seeds = [b"agent_requests", agent_id.as_ref()],
+        bump
+    )]
+    pub agent_requests: Account<'info, AgentRequestCount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub analytics: AnalyticsHook<'info>,
+
+    /// CHECK: Registry `BlockEntry` of the user, which must not exist;
+    /// checked in the handler
+    #[account(
+        seeds = [b"blocklist", agent_id.as_ref(), user.key().as_ref()],
+        bump,
+        seeds::program = AGENT_REGISTRY_ID
+    )]
+    pub block_entry: UncheckedAccount<'info>,
+
+    /// CHECK: Registry `AllowlistEntry` of the user, required when the agent
+    /// only accepts allowlisted users; checked in the handler
+    #[account(
+        seeds = [b"allowlist", agent_id.as_ref(), user.key().as_ref()],
+        bump,
+        seeds::program = AGENT_REGISTRY_ID
+    )]
+    pub allowlist_entry: Option<UncheckedAccount<'info>>,
+
+    /// Free requests the user has made of the agent; omit it to pay even
+    /// while the agent offers a trial
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + TrialCounter::INIT_SPACE,
+        seeds = [b"trial", agent_id.as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub trial_counter: Option<Account<'info, TrialCounter>>,
+
+    /// SPL mint to pay in, with the token accounts below; omit them all to
+    /// pay in lamports
+    pub payment_mint: Option<Account<'info, Mint>>,
+
+    /// CHECK: The user's token account of `payment_mint`, checked by the token program
+    #[account(mut)]
+    pub user_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The escrow PDA's associated token account of `payment_mint`,
+    /// created if needed; checked in the handler
+    #[account(mut)]
+    pub escrow_token_account: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+    /// CHECK: Registry `AcceptedMint` of `payment_mint`, required to pay in
+    /// tokens; checked in the handler
+    pub accepted_mint: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: SLA oracle `AgentSla` of the agent, setting the deadline when
+    /// none is given; checked in the handler
+    #[account(
+        seeds = [b"agent_sla", agent_id.as_ref()],
+        bump,
+        seeds::program = SLA_ORACLE_ID
+    )]
+    pub agent_sla: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct PinAgentVersion<'info> {
+    #[account(mut)]
+    pub service_request: Account<'info, ServiceRequest>,
+
+    /// CHECK: Registry `AgentVersion`, checked against the request's agent in the handler
+    #[account(owner = AGENT_REGISTRY_ID @ ErrorCode::InvalidAgentVersion)]
+    pub agent_version: UncheckedAccount<'info>,
+
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SelectServiceOffering<'info> {
+    #[account(mut)]
+    pub service_request: Account<'info, ServiceRequest>,
+
+    /// CHECK: Registry `ServiceOffering`, checked against the request's agent in the handler
+    #[account(owner = AGENT_REGISTRY_ID @ ErrorCode::InvalidServiceOffering)]
+    pub service_offering: UncheckedAccount<'info>,
+
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SelectCapability<'info> {
+    #[account(mut)]
+    pub service_request: Account<'info, ServiceRequest>,
+
+    /// CHECK: Registry `CapabilityPrice`, checked against the request's agent in the handler
+    #[account(owner = AGENT_REGISTRY_ID @ ErrorCode::InvalidCapabilityPrice)]
+    pub capability_price: UncheckedAccount<'info>,
+
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SelectBundle<'info> {
+    #[account(mut)]
+    pub service_request: Account<'info, ServiceRequest>,
+
+    /// CHECK: Registry `Bundle`, checked against the request's agent in the handler
+    #[account(owner = AGENT_REGISTRY_ID @ ErrorCode::InvalidBundle)]
+    pub bundle: UncheckedAccount<'info>,
+
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitResult<'info> {
+    #[account(mut)]
+    pub service_request: Account<'info, ServiceRequest>,
+
+    /// The agent's manager, or an operator it registered
+    pub agent_authority: Signer<'info>,
+
+    /// CHECK: The agent's registry profile; its NFT mint and authority are
+    /// read by walking its layout
+    #[account(
+        address = service_request.agent_id,
+        owner = AGENT_REGISTRY_ID @ ErrorCode::InvalidAgentProfile
+    )]
+    pub agent_profile: UncheckedAccount<'info>,
+
+    /// CHECK: The manager's token account for the agent's NFT; only read
+    /// for agents without an authority, so it may not exist otherwise
+    pub holder_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The registry `OperatorKey` of `agent_authority`, when it
+    /// submits for the manager; checked in the handler
+    pub operator_key: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveResult<'info> {
+    #[account(mut)]
+    pub service_request: Account<'info, ServiceRequest>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", service_request.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA used for escrow
+    pub escrow_account: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + AgentRequestCount::INIT_SPACE,
+        seeds = [b"agent_requests", service_request.agent_id.as_ref()],
+        bump
+    )]
+    pub agent_requests: Account<'info, AgentRequestCount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: The agent's registry profile; its payout wallet is read at a
+    /// fixed offset and the registry credits it with the earnings
+    #[account(
+        mut,
+        address = service_request.agent_id,
+        owner = AGENT_REGISTRY_ID @ ErrorCode::PayoutWalletMismatch
+    )]
+    pub agent_profile: UncheckedAccount<'info>,
+
+    /// CHECK: Signer the registry accepts earnings from; holds no data
+    #[account(seeds = [b"payout_hook"], bump)]
+    pub payout_hook: UncheckedAccount<'info>,
+
+    /// CHECK: The agent registry, invoked to record the earnings
+    #[account(address = AGENT_REGISTRY_ID)]
+    pub registry_program: UncheckedAccount<'info>,
+
+    /// CHECK: Payout wallet will receive payment; checked against the profile in the handler
+    #[account(mut)]
+    pub payout_wallet: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"co_creators", service_request.agent_id.as_ref()],
+        bump,
+        seeds::program = AGENT_REGISTRY_ID
+    )]
+    /// CHECK: The agent's registry co-creator list, absent when it has none; read at fixed offsets
+    pub co_creator_split: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"royalty_config"],
+        bump,
+        seeds::program = royalty_splitter::ID,
+        has_one = platform_wallet @ ErrorCode::FeeWalletMismatch,
+        has_one = treasury_wallet @ ErrorCode::FeeWalletMismatch
+    )]
+    pub royalty_config: Account<'info, RoyaltyConfig>,
+
+    /// CHECK: Platform wallet will receive fee; checked against the royalty config
+    #[account(mut)]
+    pub platform_wallet: UncheckedAccount<'info>,
+
+    /// CHECK: Treasury wallet will receive fee; checked against the royalty config
+    #[account(mut)]
+    pub treasury_wallet: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    /// CHECK: Raffle config, validated by the raffle program
+    #[account(mut)]
+    pub raffle_config: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Current raffle round, validated by the raffle program
+    #[account(mut)]
+    pub raffle_round: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Ticket batch created by the raffle program
+    #[account(mut)]
+    pub ticket_batch: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        seeds = [b"raffle_hook"],
+        bump
+    )]
+    /// CHECK: PDA that authorizes ticket awards in the raffle program
+    pub raffle_hook: Option<UncheckedAccount<'info>>,
+
+    pub raffle_program: Option<Program<'info, PromoRaffle>>,
+
+    pub system_program: Program<'info, System>,
+
+    pub analytics: AnalyticsHook<'info>,
+
+    /// CHECK: The escrow PDA's token account of a request paid in an SPL
+    /// token, checked in the handler; omit it and the accounts below for
+    /// requests paid in lamports
+    #[account(mut)]
+    pub escrow_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The payout wallet's token account of the payment mint, checked in the handler
+    #[account(mut)]
+    pub payout_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The platform wallet's token account of the payment mint, checked in the handler
+    #[account(mut)]
+    pub platform_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The treasury wallet's token account of the payment mint, checked in the handler
+    #[account(mut)]
+    pub treasury_token_account: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct AutoRelease<'info> {
+    #[account(mut, has_one = user)]
+    pub service_request: Account<'info, ServiceRequest>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", service_request.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA used for escrow
+    pub escrow_account: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = caller,
+        space = 8 + AgentRequestCount::INIT_SPACE,
+        seeds = [b"agent_requests", service_request.agent_id.as_ref()],
+        bump
+    )]
+    pub agent_requests: Account<'info, AgentRequestCount>,
+
+    /// CHECK: The requester, matched against the request; gets back the
+    /// rent of a token escrow
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub caller: Signer<'info>,
+
+    /// CHECK: The agent's registry profile; its payout wallet is read at a
+    /// fixed offset and the registry credits it with the earnings
+    #[account(
+        mut,
+        address = service_request.agent_id,
+        owner = AGENT_REGISTRY_ID @ ErrorCode::PayoutWalletMismatch
+    )]
+    pub agent_profile: UncheckedAccount<'info>,
+
+    /// CHECK: Signer the registry accepts earnings from; holds no data
+    #[account(seeds = [b"payout_hook"], bump)]
+    pub payout_hook: UncheckedAccount<'info>,
+
+    /// CHECK: The agent registry, invoked to record the earnings
+    #[account(address = AGENT_REGISTRY_ID)]
+    pub registry_program: UncheckedAccount<'info>,
+
+    /// CHECK: Payout wallet will receive payment; checked against the profile in the handler
+    #[account(mut)]
+    pub payout_wallet: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"co_creators", service_request.agent_id.as_ref()],
+        bump,
+        seeds::program = AGENT_REGISTRY_ID
+    )]
+    /// CHECK: The agent's registry co-creator list, absent when it has none; read at fixed offsets
+    pub co_creator_split: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"royalty_config"],
+        bump,
+        seeds::program = royalty_splitter::ID,
+        has_one = platform_wallet @ ErrorCode::FeeWalletMismatch,
+        has_one = treasury_wallet @ ErrorCode::FeeWalletMismatch
+    )]
+    pub royalty_config: Account<'info, RoyaltyConfig>,
+
+    /// CHECK: Platform wallet will receive fee; checked against the royalty config
+    #[account(mut)]
+    pub platform_wallet: UncheckedAccount<'info>,
+
+    /// CHECK: Treasury wallet will receive fee; checked against the royalty config
+    #[account(mut)]
+    pub treasury_wallet: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub system_program: Program<'info, System>,
+
+    pub analytics: AnalyticsHook<'info>,
+
+    /// CHECK: The escrow PDA's token account of a request paid in an SPL
+    /// token, checked in the handler; omit it and the accounts below for
+    /// requests paid in lamports
+    #[account(mut)]
+    pub escrow_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The payout wallet's token account of the payment mint, checked in the handler
+    #[account(mut)]
+    pub payout_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The platform wallet's token account of the payment mint, checked in the handler
+    #[account(mut)]
+    pub platform_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The treasury wallet's token account of the payment mint, checked in the handler
+    #[account(mut)]
+    pub treasury_token_account: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
 
 #[derive(Accounts)]
-pub struct SubmitResult<'info> {
+pub struct DisputeResult<'info> {
+    #[account(mut)]
+    pub service_request: Account<'info, ServiceRequest>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub analytics: AnalyticsHook<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetArbiter<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ArbitrationConfig::INIT_SPACE,
+        seeds = [b"arbitration_config"],
+        bump
+    )]
+    pub arbitration_config: Account<'info, ArbitrationConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        has_one = authority @ ErrorCode::UnauthorizedArbiterUpdate
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    #[account(mut, has_one = user)]
+    pub service_request: Account<'info, ServiceRequest>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", service_request.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA used for escrow
+    pub escrow_account: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = arbiter,
+        space = 8 + AgentRequestCount::INIT_SPACE,
+        seeds = [b"agent_requests", service_request.agent_id.as_ref()],
+        bump
+    )]
+    pub agent_requests: Account<'info, AgentRequestCount>,
+
+    #[account(
+        mut,
+        seeds = [b"arbitration_config"],
+        bump,
+        has_one = arbiter @ ErrorCode::UnauthorizedArbiter
+    )]
+    pub arbitration_config: Account<'info, ArbitrationConfig>,
+
+    /// CHECK: The request's juror panel, which must not have been drawn
+    #[account(seeds = [b"dispute_panel", service_request.key().as_ref()], bump)]
+    pub dispute_panel: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub arbiter: Signer<'info>,
+
+    /// CHECK: The requester, matched against the request; receives any refund
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+
+    /// CHECK: The agent's registry profile; its payout wallet is read at a
+    /// fixed offset and the registry credits it with the earnings
+    #[account(
+        mut,
+        address = service_request.agent_id,
+        owner = AGENT_REGISTRY_ID @ ErrorCode::PayoutWalletMismatch
+    )]
+    pub agent_profile: UncheckedAccount<'info>,
+
+    /// CHECK: Signer the registry accepts earnings from; holds no data
+    #[account(seeds = [b"payout_hook"], bump)]
+    pub payout_hook: UncheckedAccount<'info>,
+
+    /// CHECK: The agent registry, invoked to record the earnings
+    #[account(address = AGENT_REGISTRY_ID)]
+    pub registry_program: UncheckedAccount<'info>,
+
+    /// CHECK: Payout wallet will receive payment; checked against the profile in the handler
+    #[account(mut)]
+    pub payout_wallet: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"co_creators", service_request.agent_id.as_ref()],
+        bump,
+        seeds::program = AGENT_REGISTRY_ID
+    )]
+    /// CHECK: The agent's registry co-creator list, absent when it has none; read at fixed offsets
+    pub co_creator_split: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"royalty_config"],
+        bump,
+        seeds::program = royalty_splitter::ID,
+        has_one = platform_wallet @ ErrorCode::FeeWalletMismatch,
+        has_one = treasury_wallet @ ErrorCode::FeeWalletMismatch
+    )]
+    pub royalty_config: Account<'info, RoyaltyConfig>,
+
+    /// CHECK: Platform wallet will receive fee; checked against the royalty config
+    #[account(mut)]
+    pub platform_wallet: UncheckedAccount<'info>,
+
+    /// CHECK: Treasury wallet will receive fee; checked against the royalty config
+    #[account(mut)]
+    pub treasury_wallet: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub system_program: Program<'info, System>,
+
+    pub analytics: AnalyticsHook<'info>,
+
+    /// CHECK: The escrow PDA's token account of a request paid in an SPL
+    /// token, checked in the handler; omit it and the accounts below for
+    /// requests paid in lamports
+    #[account(mut)]
+    pub escrow_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The user's token account of the payment mint, checked in the handler
+    #[account(mut)]
+    pub user_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The payout wallet's token account of the payment mint, checked in the handler
+    #[account(mut)]
+    pub payout_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The platform wallet's token account of the payment mint, checked in the handler
+    #[account(mut)]
+    pub platform_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The treasury wallet's token account of the payment mint, checked in the handler
+    #[account(mut)]
+    pub treasury_token_account: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct SettlePanel<'info> {
+    #[account(mut, has_one = user)]
+    pub service_request: Account<'info, ServiceRequest>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", service_request.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA used for escrow
+    pub escrow_account: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = caller,
+        space = 8 + AgentRequestCount::INIT_SPACE,
+        seeds = [b"agent_requests", service_request.agent_id.as_ref()],
+        bump
+    )]
+    pub agent_requests: Account<'info, AgentRequestCount>,
+
+    #[account(
+        mut,
+        seeds = [b"dispute_panel", service_request.key().as_ref()],
+        bump,
+        has_one = payer,
+        close = payer
+    )]
+    pub dispute_panel: Account<'info, DisputePanel>,
+
+    /// CHECK: Whoever drew the panel, who gets its rent back
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+
+    #[account(mut, seeds = [b"juror_pool"], bump)]
+    pub juror_pool: Account<'info, JurorPool>,
+
+    #[account(mut)]
+    pub caller: Signer<'info>,
+
+    /// CHECK: The requester, matched against the request; receives any refund
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+
+    /// CHECK: The agent's registry profile; its payout wallet is read at a
+    /// fixed offset and the registry credits it with the earnings
+    #[account(
+        mut,
+        address = service_request.agent_id,
+        owner = AGENT_REGISTRY_ID @ ErrorCode::PayoutWalletMismatch
+    )]
+    pub agent_profile: UncheckedAccount<'info>,
+
+    /// CHECK: Signer the registry accepts earnings from; holds no data
+    #[account(seeds = [b"payout_hook"], bump)]
+    pub payout_hook: UncheckedAccount<'info>,
+
+    /// CHECK: The agent registry, invoked to record the earnings
+    #[account(address = AGENT_REGISTRY_ID)]
+    pub registry_program: UncheckedAccount<'info>,
+
+    /// CHECK: Payout wallet will receive payment; checked against the profile in the handler
+    #[account(mut)]
+    pub payout_wallet: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"co_creators", service_request.agent_id.as_ref()],
+        bump,
+        seeds::program = AGENT_REGISTRY_ID
+    )]
+    /// CHECK: The agent's registry co-creator list, absent when it has none; read at fixed offsets
+    pub co_creator_split: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"royalty_config"],
+        bump,
+        seeds::program = royalty_splitter::ID,
+        has_one = platform_wallet @ ErrorCode::FeeWalletMismatch,
+        has_one = treasury_wallet @ ErrorCode::FeeWalletMismatch
+    )]
+    pub royalty_config: Account<'info, RoyaltyConfig>,
+
+    /// CHECK: Platform wallet will receive fee; checked against the royalty config
+    #[account(mut)]
+    pub platform_wallet: UncheckedAccount<'info>,
+
+    /// CHECK: Treasury wallet will receive fee; checked against the royalty config
+    #[account(mut)]
+    pub treasury_wallet: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub system_program: Program<'info, System>,
+
+    pub analytics: AnalyticsHook<'info>,
+
+    /// CHECK: The escrow PDA's token account of a request paid in an SPL
+    /// token, checked in the handler; omit it and the accounts below for
+    /// requests paid in lamports
+    #[account(mut)]
+    pub escrow_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The user's token account of the payment mint, checked in the handler
+    #[account(mut)]
+    pub user_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The payout wallet's token account of the payment mint, checked in the handler
+    #[account(mut)]
+    pub payout_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The platform wallet's token account of the payment mint, checked in the handler
+    #[account(mut)]
+    pub platform_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The treasury wallet's token account of the payment mint, checked in the handler
+    #[account(mut)]
+    pub treasury_token_account: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterJuror<'info> {
+    #[account(
+        init_if_needed,
+        payer = juror,
+        space = 8 + JurorPool::INIT_SPACE,
+        seeds = [b"juror_pool"],
+        bump
+    )]
+    pub juror_pool: Account<'info, JurorPool>,
+
+    #[account(mut)]
+    pub juror: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LeaveJury<'info> {
+    #[account(mut, seeds = [b"juror_pool"], bump)]
+    pub juror_pool: Account<'info, JurorPool>,
+
+    #[account(mut)]
+    pub juror: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DrawPanel<'info> {
+    pub service_request: Account<'info, ServiceRequest>,
+
+    #[account(
+        init,
+        payer = caller,
+        space = 8 + DisputePanel::INIT_SPACE,
+        seeds = [b"dispute_panel", service_request.key().as_ref()],
+        bump
+    )]
+    pub dispute_panel: Account<'info, DisputePanel>,
+
+    #[account(mut, seeds = [b"juror_pool"], bump)]
+    pub juror_pool: Account<'info, JurorPool>,
+
+    /// CHECK: The agent's registry profile; its creator and payout wallet are read at fixed offsets
+    #[account(
+        address = service_request.agent_id,
+        owner = AGENT_REGISTRY_ID @ ErrorCode::PayoutWalletMismatch
+    )]
+    pub agent_profile: UncheckedAccount<'info>,
+
+    /// CHECK: The `SlotHashes` sysvar, read for the committed slot's hash
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub caller: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RenewPanelSlot<'info> {
+    #[account(mut)]
+    pub service_request: Account<'info, ServiceRequest>,
+
+    /// CHECK: The `SlotHashes` sysvar, checked for the committed slot's hash
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    pub slot_hashes: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CastVote<'info> {
+    #[account(
+        mut,
+        seeds = [b"dispute_panel", dispute_panel.service_request.as_ref()],
+        bump
+    )]
+    pub dispute_panel: Account<'info, DisputePanel>,
+
+    pub juror: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelRequest<'info> {
+    #[account(mut)]
+    pub service_request: Account<'info, ServiceRequest>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", service_request.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA used for escrow
+    pub escrow_account: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + AgentRequestCount::INIT_SPACE,
+        seeds = [b"agent_requests", service_request.agent_id.as_ref()],
+        bump
+    )]
+    pub agent_requests: Account<'info, AgentRequestCount>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    /// CHECK: The escrow PDA's token account of a request paid in an SPL
+    /// token, checked in the handler; omit it and the accounts below for
+    /// requests paid in lamports
+    #[account(mut)]
+    pub escrow_token_account: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The user's token account of the payment mint, checked in the handler
+    #[account(mut)]
+    pub user_token_account: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRequest<'info> {
+    #[account(mut, has_one = user @ ErrorCode::UnauthorizedUser, close = user)]
+    pub service_request: Account<'info, ServiceRequest>,
+
+    #[account(
+        mut,
+        seeds = [b"escrow", service_request.key().as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA used for escrow
+    pub escrow_account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    /// CHECK: The escrow PDA's token account of a request paid in an SPL
+    /// token, checked in the handler; omit it and the accounts below for
+    /// requests paid in lamports
     #[account(mut)]
-    pub service_request: Account<'info, ServiceRequest>,
+    pub escrow_token_account: Option<UncheckedAccount<'info>>,
 
-    /// CHECK: Agent authority will be verified by the client
-    pub agent_authority: Signer<'info>,
+    /// CHECK: The user's token account of the payment mint, checked in the handler
+    #[account(mut)]
+    pub user_token_account: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Option<Program<'info, Token>>,
 }
 
 #[derive(Accounts)]
-pub struct ApproveResult<'info> {
-    #[account(mut)]
+pub struct ExpireRequest<'info> {
+    #[account(mut, has_one = user)]
     pub service_request: Account<'info, ServiceRequest>,
 
     #[account(
@@ -251,45 +3052,298 @@ pub struct ApproveResult<'info> {
     /// CHECK: This is a PDA used for escrow
     pub escrow_account: UncheckedAccount<'info>,
 
+    #[account(
+        init_if_needed,
+        payer = caller,
+        space = 8 + AgentRequestCount::INIT_SPACE,
+        seeds = [b"agent_requests", service_request.agent_id.as_ref()],
+        bump
+    )]
+    pub agent_requests: Account<'info, AgentRequestCount>,
+
+    /// CHECK: The requester being refunded, matched against the request
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub user: UncheckedAccount<'info>,
 
-    /// CHECK: Creator will receive payment
     #[account(mut)]
-    pub creator: UncheckedAccount<'info>,
+    pub caller: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
 
-    /// CHECK: Platform wallet will receive fee
+    /// CHECK: The escrow PDA's token account of a request paid in an SPL
+    /// token, checked in the handler; omit it and the accounts below for
+    /// requests paid in lamports
     #[account(mut)]
-    pub platform_wallet: UncheckedAccount<'info>,
+    pub escrow_token_account: Option<UncheckedAccount<'info>>,
 
-    /// CHECK: Treasury wallet will receive fee
+    /// CHECK: The user's token account of the payment mint, checked in the handler
     #[account(mut)]
-    pub treasury_wallet: UncheckedAccount<'info>,
+    pub user_token_account: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Option<Program<'info, Token>>,
 }
 
+/// Optional accounts for reporting request events to the analytics aggregator
 #[derive(Accounts)]
-pub struct DisputeResult<'info> {
+pub struct AnalyticsHook<'info> {
+    pub analytics_config: Option<Account<'info, AnalyticsConfig>>,
+
+    /// CHECK: Epoch rollup, validated by the analytics program
     #[account(mut)]
-    pub service_request: Account<'info, ServiceRequest>,
+    pub epoch_metrics: Option<UncheckedAccount<'info>>,
 
-    pub user: Signer<'info>,
+    /// CHECK: Agent activity marker, validated by the analytics program
+    #[account(mut)]
+    pub agent_activity: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Agent's rolling daily analytics, validated by the analytics program
+    #[account(mut)]
+    pub agent_analytics: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        seeds = [b"analytics_hook"],
+        bump
+    )]
+    /// CHECK: PDA registered as this program's reporter in the analytics program
+    pub analytics_hook: Option<UncheckedAccount<'info>>,
+
+    pub analytics_program: Option<Program<'info, ProtocolAnalytics>>,
+
+    pub system_program: Option<Program<'info, System>>,
 }
 
 #[derive(Accounts)]
-pub struct CancelRequest<'info> {
+#[instruction(agent_id: Pubkey)]
+pub struct SetRequestCapacity<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + AgentRequestCount::INIT_SPACE,
+        seeds = [b"agent_requests", agent_id.as_ref()],
+        bump
+    )]
+    pub agent_requests: Account<'info, AgentRequestCount>,
+
+    /// Registry capacity hook PDA; only the agent registry can sign for it
+    #[account(
+        seeds = [b"capacity_hook"],
+        bump,
+        seeds::program = AGENT_REGISTRY_ID
+    )]
+    pub capacity_hook: Signer<'info>,
+
     #[account(mut)]
-    pub service_request: Account<'info, ServiceRequest>,
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 
     #[account(
-        mut,
-        seeds = [b"escrow", service_request.key().as_ref()],
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(agent_id: Pubkey)]
+pub struct SetRequestPause<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + AgentRequestCount::INIT_SPACE,
+        seeds = [b"agent_requests", agent_id.as_ref()],
         bump
     )]
-    /// CHECK: This is a PDA used for escrow
-    pub escrow_account: UncheckedAccount<'info>,
+    pub agent_requests: Account<'info, AgentRequestCount>,
+
+    /// Registry capacity hook PDA; only the agent registry can sign for it
+    #[account(
+        seeds = [b"capacity_hook"],
+        bump,
+        seeds::program = AGENT_REGISTRY_ID
+    )]
+    pub capacity_hook: Signer<'info>,
 
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(agent_id: Pubkey)]
+pub struct SetRequestVisibility<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + AgentRequestCount::INIT_SPACE,
+        seeds = [b"agent_requests", agent_id.as_ref()],
+        bump
+    )]
+    pub agent_requests: Account<'info, AgentRequestCount>,
+
+    /// Registry capacity hook PDA; only the agent registry can sign for it
+    #[account(
+        seeds = [b"capacity_hook"],
+        bump,
+        seeds::program = AGENT_REGISTRY_ID
+    )]
+    pub capacity_hook: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(agent_id: Pubkey)]
+pub struct SetRequestTrials<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + AgentRequestCount::INIT_SPACE,
+        seeds = [b"agent_requests", agent_id.as_ref()],
+        bump
+    )]
+    pub agent_requests: Account<'info, AgentRequestCount>,
+
+    /// Registry capacity hook PDA; only the agent registry can sign for it
+    #[account(
+        seeds = [b"capacity_hook"],
+        bump,
+        seeds::program = AGENT_REGISTRY_ID
+    )]
+    pub capacity_hook: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(agent_id: Pubkey)]
+pub struct RetireRequests<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + AgentRequestCount::INIT_SPACE,
+        seeds = [b"agent_requests", agent_id.as_ref()],
+        bump
+    )]
+    pub agent_requests: Account<'info, AgentRequestCount>,
+
+    /// Registry capacity hook PDA; only the agent registry can sign for it
+    #[account(
+        seeds = [b"capacity_hook"],
+        bump,
+        seeds::program = AGENT_REGISTRY_ID
+    )]
+    pub capacity_hook: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(agent_id: Pubkey)]
+pub struct SetRequestFreeze<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + AgentRequestCount::INIT_SPACE,
+        seeds = [b"agent_requests", agent_id.as_ref()],
+        bump
+    )]
+    pub agent_requests: Account<'info, AgentRequestCount>,
+
+    /// Registry capacity hook PDA; only the agent registry can sign for it
+    #[account(
+        seeds = [b"capacity_hook"],
+        bump,
+        seeds::program = AGENT_REGISTRY_ID
+    )]
+    pub capacity_hook: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ErrorCode::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateServiceRequest<'info> {
+    /// CHECK: Possibly in an older layout; upgraded and rewritten by the handler
+    #[account(mut, owner = crate::ID)]
+    pub service_request: UncheckedAccount<'info>,
+
+    /// Pays the rent for the grown account
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        has_one = authority @ ErrorCode::UnauthorizedMigration,
+        constraint = guardian_config.is_paused @ ErrorCode::ProtocolNotPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[account]
@@ -307,6 +3361,55 @@ pub struct ServiceRequest {
     pub created_at: i64,
     pub completed_at: Option<i64>,
     pub escrow_account: Pubkey,
+    /// Registry `AgentVersion` the requester pinned, if any
+    pub agent_version: Option<Pubkey>,
+    /// Registry `ServiceOffering` the requester ordered, if any
+    pub service_offering: Option<Pubkey>,
+    /// Registry `Bundle` this request was bought through, if any
+    pub bundle: Option<Pubkey>,
+    /// Registry `CapabilityPrice` of the capability ordered, if any
+    pub capability_price: Option<Pubkey>,
+    /// SPL mint `amount` is escrowed in; `None` for lamports
+    pub payment_mint: Option<Pubkey>,
+    /// Result is due by this time, after which anyone can expire the request
+    /// and refund the user; `None` for no deadline
+    pub deadline: Option<i64>,
+    /// Slot whose hash seeds the juror panel, committed when the result is
+    /// disputed; `None` until then
+    pub panel_slot: Option<u64>,
+}
+
+/// Requests against an agent that still hold funds in escrow; the registry
+/// refuses to deregister an agent while this is non-zero
+#[account]
+#[derive(InitSpace)]
+pub struct AgentRequestCount {
+    pub agent_id: Pubkey,
+    pub open_requests: u32,
+    /// Capacity the agent declared in the registry; zero means no limit
+    pub max_open_requests: u32,
+    /// Agent refuses new requests until this time; zero when not paused
+    pub paused_until: i64,
+    /// Agent only accepts requests from users on its registry allowlist
+    pub allowlist_only: bool,
+    /// Requests each wallet can make for free, as set in the registry
+    pub trial_requests: u8,
+    /// Agent was retired in the registry and takes no new requests
+    pub retired: bool,
+    /// Agent new requests should go to instead, named at retirement
+    pub successor: Option<Pubkey>,
+    /// Agent was frozen in the registry and takes no new requests until
+    /// unfrozen
+    pub frozen: bool,
+}
+
+/// Free requests one wallet has made of an agent
+#[account]
+#[derive(InitSpace)]
+pub struct TrialCounter {
+    pub agent_id: Pubkey,
+    pub user: Pubkey,
+    pub used: u8,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
@@ -317,6 +3420,69 @@ pub enum RequestStatus {
     Approved,
     Disputed,
     Cancelled,
+    /// Disputed and ruled on by the arbiter
+    Resolved,
+}
+
+/// How the arbiter rules on a disputed request
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq)]
+pub enum DisputeOutcome {
+    /// Pay the agent as if the user had approved the result
+    AwardAgent,
+    /// Return the whole payment to the user
+    RefundUser,
+    /// Release `agent_bps` of the payment, split between the agent, platform,
+    /// and treasury as on approval, and refund the rest; 4_000 refunds 60%
+    Split { agent_bps: u16 },
+}
+
+/// A juror's side in a dispute
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum Verdict {
+    /// Pay the agent
+    Agent,
+    /// Refund the user
+    User,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct JurorSeat {
+    pub juror: Pubkey,
+    /// Lamports held for the juror: `JUROR_STAKE`, plus rewards, less forfeits
+    pub stake: u64,
+    /// Unsettled panels the juror sits on; it can't leave until they settle
+    pub active_panels: u16,
+}
+
+/// Stakers disputes draw their panels from; holds their stakes
+#[account]
+#[derive(InitSpace)]
+pub struct JurorPool {
+    #[max_len(MAX_JURORS)]
+    pub jurors: Vec<JurorSeat>,
+}
+
+/// Jurors drawn to vote on one disputed request, and their votes
+#[account]
+#[derive(InitSpace)]
+pub struct DisputePanel {
+    pub service_request: Pubkey,
+    /// Drew the panel and gets its rent back on settlement
+    pub payer: Pubkey,
+    #[max_len(PANEL_SIZE)]
+    pub jurors: Vec<Pubkey>,
+    /// In `jurors` order
+    #[max_len(PANEL_SIZE)]
+    pub votes: Vec<Option<Verdict>>,
+    pub voting_ends_at: i64,
+}
+
+/// Who rules on disputed requests, named by the guardian authority
+#[account]
+#[derive(InitSpace)]
+pub struct ArbitrationConfig {
+    pub arbiter: Pubkey,
+    pub disputes_resolved: u64,
 }
 
 #[event]
@@ -325,6 +3491,8 @@ pub struct ServiceRequestCreated {
     pub agent_id: Pubkey,
     pub user: Pubkey,
     pub amount: u64,
+    /// SPL mint `amount` is in; `None` for lamports
+    pub payment_mint: Option<Pubkey>,
     pub timestamp: i64,
 }
 
@@ -345,6 +3513,13 @@ pub struct PaymentReleased {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct CoCreatorPaid {
+    pub request_id: Pubkey,
+    pub co_creator: Pubkey,
+    pub amount: u64,
+}
+
 #[event]
 pub struct ResultDisputed {
     pub request_id: Pubkey,
@@ -361,6 +3536,127 @@ pub struct RequestCancelled {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct RequestExpired {
+    pub request_id: Pubkey,
+    pub agent_id: Pubkey,
+    pub user: Pubkey,
+    pub refund_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RequestClosed {
+    pub request_id: Pubkey,
+    pub agent_id: Pubkey,
+    pub user: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ArbiterSet {
+    pub arbiter: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeResolved {
+    pub request_id: Pubkey,
+    pub agent_id: Pubkey,
+    pub arbiter: Pubkey,
+    pub outcome: DisputeOutcome,
+    pub agent_amount: u64,
+    pub refund_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct JurorRegistered {
+    pub juror: Pubkey,
+    pub stake: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct JurorLeft {
+    pub juror: Pubkey,
+    pub stake: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PanelDrawn {
+    pub request_id: Pubkey,
+    pub agent_id: Pubkey,
+    pub jurors: Vec<Pubkey>,
+    pub voting_ends_at: i64,
+}
+
+#[event]
+pub struct JurorVoted {
+    pub request_id: Pubkey,
+    pub juror: Pubkey,
+    pub verdict: Verdict,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PanelSettled {
+    pub request_id: Pubkey,
+    pub agent_id: Pubkey,
+    pub votes_for_agent: u8,
+    pub votes_for_user: u8,
+    pub agent_amount: u64,
+    pub refund_amount: u64,
+    /// Shared by the majority: the juror fee plus forfeits
+    pub juror_rewards: u64,
+    /// Taken from jurors who didn't vote
+    pub forfeited: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentVersionPinned {
+    pub request_id: Pubkey,
+    pub agent_id: Pubkey,
+    pub agent_version: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ServiceOfferingSelected {
+    pub request_id: Pubkey,
+    pub agent_id: Pubkey,
+    pub service_offering: Pubkey,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CapabilitySelected {
+    pub request_id: Pubkey,
+    pub agent_id: Pubkey,
+    pub capability_price: Pubkey,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BundleSelected {
+    pub request_id: Pubkey,
+    pub agent_id: Pubkey,
+    pub bundle: Pubkey,
+    pub share_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub size: u32,
+    pub timestamp: i64,
+}
+
 #[error_code]
 pub enum ErrorCode {
     #[msg("Invalid payment amount")]
@@ -377,4 +3673,120 @@ pub enum ErrorCode {
     DisputeReasonTooLong,
     #[msg("Cannot cancel request in current status")]
     CannotCancelRequest,
-}
\ No newline at end of file
+    #[msg("Protocol is paused by the guardian")]
+    ProtocolPaused,
+    #[msg("Raffle accounts are incomplete")]
+    MissingRaffleAccounts,
+    #[msg("Only the guardian authority can migrate accounts")]
+    UnauthorizedMigration,
+    #[msg("Migrations require the protocol to be paused")]
+    ProtocolNotPaused,
+    #[msg("Account is not of the type being migrated")]
+    MigrationTargetMismatch,
+    #[msg("Migration chunk extends past the end of the account")]
+    MigrationChunkOutOfBounds,
+    #[msg("Migrated account does not match its address")]
+    MigrationIdentityMismatch,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Not a published version of this agent")]
+    InvalidAgentVersion,
+    #[msg("Not a service offering of this agent")]
+    InvalidServiceOffering,
+    #[msg("Service offering is not available")]
+    ServiceOfferingInactive,
+    #[msg("Escrowed amount does not cover the offering's price")]
+    OfferingPriceNotMet,
+    #[msg("Co-creator wallets do not match the agent's co-creators")]
+    CoCreatorMismatch,
+    #[msg("Agent is serving as many requests as it accepts")]
+    AgentAtCapacity,
+    #[msg("Agent has paused its listings")]
+    AgentPaused,
+    #[msg("Account is not a registry bundle")]
+    InvalidBundle,
+    #[msg("Agent is not part of the bundle")]
+    AgentNotInBundle,
+    #[msg("Bundle is not available")]
+    BundleInactive,
+    #[msg("Escrowed amount does not cover the agent's share of the bundle price")]
+    BundlePriceNotMet,
+    #[msg("Agent only accepts requests from allowlisted users")]
+    NotAllowlisted,
+    #[msg("Agent has blocked this user")]
+    UserBlocked,
+    #[msg("Payment must go to the agent's payout wallet")]
+    PayoutWalletMismatch,
+    #[msg("Agent is retired; hire its successor instead")]
+    AgentRetired,
+    #[msg("Agent is frozen pending review")]
+    AgentFrozen,
+    #[msg("Not a capability price of this agent")]
+    InvalidCapabilityPrice,
+    #[msg("Escrowed amount does not cover the capability's price")]
+    CapabilityPriceNotMet,
+    #[msg("Token accounts are required for a request paid in an SPL token")]
+    MissingTokenAccounts,
+    #[msg("Token account is not the expected owner's account of the payment mint")]
+    InvalidTokenAccount,
+    #[msg("Deadline must be in the future")]
+    InvalidDeadline,
+    #[msg("Request is past its deadline")]
+    DeadlinePassed,
+    #[msg("Request has no deadline or it has not passed yet")]
+    RequestNotExpired,
+    #[msg("Account is not the agent's SLA record")]
+    InvalidAgentSla,
+    #[msg("The user can still approve or dispute this result")]
+    ReviewWindowOpen,
+    #[msg("Fee wallets do not match the royalty config")]
+    FeeWalletMismatch,
+    #[msg("Only the guardian authority can name the arbiter")]
+    UnauthorizedArbiterUpdate,
+    #[msg("Only the arbiter can resolve disputes")]
+    UnauthorizedArbiter,
+    #[msg("Only disputed requests can be resolved")]
+    RequestNotDisputed,
+    #[msg("Agent share of a split must be at most 10000 bps")]
+    InvalidDisputeSplit,
+    #[msg("Disputes with a juror panel are settled by its vote")]
+    DisputeHasPanel,
+    #[msg("Juror is already in the pool")]
+    JurorAlreadyRegistered,
+    #[msg("Jury pool is full")]
+    JurorPoolFull,
+    #[msg("Not in the jury pool")]
+    NotAJuror,
+    #[msg("Jurors can't leave while on an unsettled panel")]
+    JurorOnPanel,
+    #[msg("Not enough eligible jurors to draw a panel")]
+    NotEnoughJurors,
+    #[msg("SlotHashes sysvar has no entries")]
+    InvalidSlotHashes,
+    #[msg("Juror is not on this panel")]
+    NotOnPanel,
+    #[msg("Juror has already voted")]
+    AlreadyVoted,
+    #[msg("Voting window has closed")]
+    VotingClosed,
+    #[msg("Panel is still voting")]
+    VotingOpen,
+    #[msg("Only approved, cancelled, or resolved requests can be closed")]
+    RequestNotSettled,
+    #[msg("Payment mint is not accepted by the registry")]
+    PaymentMintNotAccepted,
+    #[msg("Request is paid in a different currency than the price is quoted in")]
+    PaymentMintMismatch,
+    #[msg("Juror panel can't be drawn until the slot committed to at dispute has passed")]
+    PanelSlotNotReached,
+    #[msg("Committed panel slot has left the SlotHashes sysvar; renew it")]
+    PanelSlotExpired,
+    #[msg("Committed panel slot can still be drawn from")]
+    PanelSlotNotExpired,
+    #[msg("Account already has the current layout")]
+    AccountUpToDate,
+    #[msg("Account is not the agent's registry profile")]
+    InvalidAgentProfile,
+    #[msg("Signer does not manage the agent and is not one of its operators")]
+    NotAgentManager,
+}
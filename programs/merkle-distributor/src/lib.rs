@@ -0,0 +1,290 @@
+use anchor_lang::prelude::*;
+use solana_sha256_hasher::hashv;
+
+declare_id!("E4aVYfS7HEKJLMNjCUsxxSL1PZ9gotygC2XdZ5FAB8je");
+
+/// Maximum number of leaves tracked by a distributor's claimed bitmap
+pub const MAX_LEAVES: u64 = 65_536;
+
+#[program]
+pub mod merkle_distributor {
+    use super::*;
+
+    /// Create a distributor for a merkle root and fund it with the full claim amount
+    pub fn new_distributor(
+        ctx: Context<NewDistributor>,
+        campaign_id: u64,
+        merkle_root: [u8; 32],
+        max_total_claim: u64,
+        num_leaves: u64,
+        clawback_after: i64,
+    ) -> Result<()> {
+        require!(max_total_claim > 0, DistributorError::InvalidAmount);
+        require!(
+            num_leaves > 0 && num_leaves <= MAX_LEAVES,
+            DistributorError::InvalidLeafCount
+        );
+
+        let clock = Clock::get()?;
+        require!(clawback_after > clock.unix_timestamp, DistributorError::InvalidDeadline);
+
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.admin.key(),
+            &ctx.accounts.distributor.key(),
+            max_total_claim,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_instruction,
+            &[
+                ctx.accounts.admin.to_account_info(),
+                ctx.accounts.distributor.to_account_info(),
+            ],
+        )?;
+
+        let distributor_key = ctx.accounts.distributor.key();
+        let distributor = &mut ctx.accounts.distributor;
+        distributor.admin = ctx.accounts.admin.key();
+        distributor.campaign_id = campaign_id;
+        distributor.merkle_root = merkle_root;
+        distributor.max_total_claim = max_total_claim;
+        distributor.total_claimed = 0;
+        distributor.num_leaves = num_leaves;
+        distributor.num_claimed = 0;
+        distributor.clawback_after = clawback_after;
+        distributor.is_clawed_back = false;
+        distributor.claimed_bitmap = vec![0; bitmap_len(num_leaves)];
+
+        emit!(DistributorCreated {
+            distributor: distributor_key,
+            admin: distributor.admin,
+            campaign_id,
+            merkle_root,
+            max_total_claim,
+            num_leaves,
+            clawback_after,
+        });
+
+        Ok(())
+    }
+
+    /// Claim an allocation by proving the leaf is in the merkle tree
+    pub fn claim(
+        ctx: Context<Claim>,
+        index: u64,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let distributor = &mut ctx.accounts.distributor;
+        let claimant = ctx.accounts.claimant.key();
+
+        require!(!distributor.is_clawed_back, DistributorError::ClawedBack);
+        require!(index < distributor.num_leaves, DistributorError::InvalidLeafIndex);
+        require!(!distributor.is_claimed(index), DistributorError::AlreadyClaimed);
+
+        let leaf = hash_leaf(index, &claimant, amount);
+        require!(
+            verify_proof(&proof, distributor.merkle_root, leaf),
+            DistributorError::InvalidProof
+        );
+
+        let total_claimed = distributor
+            .total_claimed
+            .checked_add(amount)
+            .ok_or(DistributorError::MathOverflow)?;
+        require!(
+            total_claimed <= distributor.max_total_claim,
+            DistributorError::ExceededMaxClaim
+        );
+
+        distributor.set_claimed(index);
+        distributor.total_claimed = total_claimed;
+        distributor.num_claimed += 1;
+
+        **distributor.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.claimant.try_borrow_mut_lamports()? += amount;
+
+        emit!(Claimed {
+            distributor: distributor.key(),
+            index,
+            claimant,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Return unclaimed funds to the admin once the claim deadline has passed
+    pub fn clawback(
+        ctx: Context<Clawback>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let distributor = &mut ctx.accounts.distributor;
+
+        require!(!distributor.is_clawed_back, DistributorError::ClawedBack);
+        require!(
+            clock.unix_timestamp >= distributor.clawback_after,
+            DistributorError::ClawbackNotStarted
+        );
+
+        let unclaimed = distributor.max_total_claim - distributor.total_claimed;
+        distributor.is_clawed_back = true;
+
+        **distributor.to_account_info().try_borrow_mut_lamports()? -= unclaimed;
+        **ctx.accounts.admin.try_borrow_mut_lamports()? += unclaimed;
+
+        emit!(ClawedBack {
+            distributor: distributor.key(),
+            admin: ctx.accounts.admin.key(),
+            amount: unclaimed,
+        });
+
+        Ok(())
+    }
+}
+
+// Helper function to size the claimed bitmap for a leaf count
+fn bitmap_len(num_leaves: u64) -> usize {
+    num_leaves.div_ceil(8) as usize
+}
+
+/// Hash a claim leaf; the 0x00 prefix separates leaves from inner nodes
+pub fn hash_leaf(index: u64, claimant: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[&[0u8], &index.to_le_bytes(), claimant.as_ref(), &amount.to_le_bytes()]).to_bytes()
+}
+
+/// Fold a proof into a root using sorted-pair hashing
+pub fn verify_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            hashv(&[&[1u8], &node, sibling]).to_bytes()
+        } else {
+            hashv(&[&[1u8], sibling, &node]).to_bytes()
+        }
+    });
+    computed == root
+}
+
+#[derive(Accounts)]
+#[instruction(campaign_id: u64, merkle_root: [u8; 32], max_total_claim: u64, num_leaves: u64)]
+pub struct NewDistributor<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + MerkleDistributor::INIT_SPACE + bitmap_len(num_leaves),
+        seeds = [b"distributor", admin.key().as_ref(), campaign_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub distributor: Account<'info, MerkleDistributor>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Claim<'info> {
+    #[account(
+        mut,
+        seeds = [b"distributor", distributor.admin.as_ref(), distributor.campaign_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub distributor: Account<'info, MerkleDistributor>,
+
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Clawback<'info> {
+    #[account(
+        mut,
+        seeds = [b"distributor", admin.key().as_ref(), distributor.campaign_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = admin @ DistributorError::UnauthorizedAdmin
+    )]
+    pub distributor: Account<'info, MerkleDistributor>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MerkleDistributor {
+    pub admin: Pubkey,
+    pub campaign_id: u64,
+    pub merkle_root: [u8; 32],
+    pub max_total_claim: u64,
+    pub total_claimed: u64,
+    pub num_leaves: u64,
+    pub num_claimed: u64,
+    pub clawback_after: i64,
+    pub is_clawed_back: bool,
+    /// One bit per leaf; sized at creation from `num_leaves`
+    #[max_len(0)]
+    pub claimed_bitmap: Vec<u8>,
+}
+
+impl MerkleDistributor {
+    pub fn is_claimed(&self, index: u64) -> bool {
+        self.claimed_bitmap[(index / 8) as usize] & (1 << (index % 8)) != 0
+    }
+
+    pub fn set_claimed(&mut self, index: u64) {
+        self.claimed_bitmap[(index / 8) as usize] |= 1 << (index % 8);
+    }
+}
+
+#[event]
+pub struct DistributorCreated {
+    pub distributor: Pubkey,
+    pub admin: Pubkey,
+    pub campaign_id: u64,
+    pub merkle_root: [u8; 32],
+    pub max_total_claim: u64,
+    pub num_leaves: u64,
+    pub clawback_after: i64,
+}
+
+#[event]
+pub struct Claimed {
+    pub distributor: Pubkey,
+    pub index: u64,
+    pub claimant: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ClawedBack {
+    pub distributor: Pubkey,
+    pub admin: Pubkey,
+    pub amount: u64,
+}
+
+#[error_code]
+pub enum DistributorError {
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    #[msg("Leaf count must be between 1 and 65536")]
+    InvalidLeafCount,
+    #[msg("Clawback deadline must be in the future")]
+    InvalidDeadline,
+    #[msg("Leaf index is out of range")]
+    InvalidLeafIndex,
+    #[msg("Leaf has already been claimed")]
+    AlreadyClaimed,
+    #[msg("Invalid merkle proof")]
+    InvalidProof,
+    #[msg("Claim exceeds the distributor's total allocation")]
+    ExceededMaxClaim,
+    #[msg("Unclaimed funds have been clawed back")]
+    ClawedBack,
+    #[msg("Clawback deadline has not passed")]
+    ClawbackNotStarted,
+    #[msg("Unauthorized admin access")]
+    UnauthorizedAdmin,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+}
@@ -0,0 +1,98 @@
+//! Reading what the native ed25519 program verified earlier in the transaction.
+//!
+//! Programs don't verify ed25519 signatures themselves; the client places an
+//! ed25519 program instruction right before the one that needs the signature,
+//! and the program checks through the instructions sysvar that it covered the
+//! expected signer and message.
+
+use anchor_lang::prelude::*;
+use solana_instructions_sysvar::{load_current_index_checked, load_instruction_at_checked};
+
+/// Native ed25519 signature verification program
+pub const ED25519_PROGRAM_ID: Pubkey = pubkey!("Ed25519SigVerify111111111111111111111111111");
+
+/// One signature the ed25519 program verified
+pub struct VerifiedSignature {
+    pub signer: Pubkey,
+    pub message: Vec<u8>,
+    pub signature: [u8; 64],
+}
+
+/// Read the signature verified by the ed25519 instruction preceding the
+/// current one, failing with `missing` when there is no such instruction and
+/// `invalid` when it isn't a single signature carried in its own data
+pub fn preceding_signature<E: Into<Error> + Copy>(
+    instructions: &AccountInfo,
+    missing: E,
+    invalid: E,
+) -> Result<VerifiedSignature> {
+    let current_index = load_current_index_checked(instructions)?;
+    if current_index == 0 {
+        return Err(missing.into());
+    }
+
+    let ed25519_ix = load_instruction_at_checked(current_index as usize - 1, instructions)?;
+    if ed25519_ix.program_id != ED25519_PROGRAM_ID {
+        return Err(missing.into());
+    }
+
+    // Layout: [num_signatures: u8, padding: u8, offsets: 7 x u16, ...data]
+    let data = &ed25519_ix.data;
+    if data.len() < 16 || data[0] != 1 {
+        return Err(invalid.into());
+    }
+
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]) as usize;
+    let signature_offset = read_u16(2);
+    let signature_ix_index = read_u16(4);
+    let public_key_offset = read_u16(6);
+    let public_key_ix_index = read_u16(8);
+    let message_offset = read_u16(10);
+    let message_size = read_u16(12);
+    let message_ix_index = read_u16(14);
+
+    // All signature inputs must live in the ed25519 instruction itself
+    let own_data = u16::MAX as usize;
+    if signature_ix_index != own_data
+        || public_key_ix_index != own_data
+        || message_ix_index != own_data
+    {
+        return Err(invalid.into());
+    }
+
+    let signer = data
+        .get(public_key_offset..public_key_offset + 32)
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| invalid.into())?;
+    let message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or_else(|| invalid.into())?;
+    let signature = data
+        .get(signature_offset..signature_offset + 64)
+        .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+        .ok_or_else(|| invalid.into())?;
+
+    Ok(VerifiedSignature {
+        signer: Pubkey::new_from_array(signer),
+        message: message.to_vec(),
+        signature,
+    })
+}
+
+/// Check the ed25519 instruction preceding the current one signed `message`
+/// with `signer`, returning the signature; fails with `missing` when there is
+/// no such instruction and `invalid` when it verifies anything else
+pub fn verify_signature<E: Into<Error> + Copy>(
+    instructions: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+    missing: E,
+    invalid: E,
+) -> Result<[u8; 64]> {
+    let verified = preceding_signature(instructions, missing, invalid)?;
+    if verified.signer != *signer || verified.message != message {
+        return Err(invalid.into());
+    }
+
+    Ok(verified.signature)
+}
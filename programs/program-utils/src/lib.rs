@@ -0,0 +1,6 @@
+//! Helpers shared by the AgentMarket programs. Anything here reports failures
+//! through error codes its caller passes in, so each program keeps its own
+//! error enum.
+
+pub mod ed25519;
+pub mod migration;
@@ -0,0 +1,70 @@
+//! In-place upgrades of accounts written under an older layout.
+//!
+//! Account fields are only ever appended, so an account in an older layout
+//! reads as the current one once zero padding stands in for the fields it
+//! lacks. Upgrading decodes it that way, lets the program fill in those
+//! fields, and writes it back at the current size; nothing the old layout
+//! held can change along the way.
+
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+/// Decode `target` as the current layout of `T`, reading the fields an older
+/// layout lacks from `space` bytes of zero padding, at least the most the
+/// current layout can take up. Fails with `mismatch` if `target` holds some
+/// other account type
+pub fn read_upgraded<T: AccountDeserialize + Discriminator>(
+    target: &AccountInfo,
+    space: usize,
+    mismatch: impl Into<Error>,
+) -> Result<T> {
+    let mut data = target.try_borrow_data()?.to_vec();
+    if !data.starts_with(T::DISCRIMINATOR) {
+        return Err(mismatch.into());
+    }
+    data.resize(data.len() + space, 0);
+    T::try_deserialize(&mut &data[..])
+}
+
+/// Write `account` over `target` in the current layout, growing it to
+/// `space` first and clearing whatever the old layout left behind
+pub fn write_upgraded<'info, T: AccountSerialize>(
+    target: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    account: &T,
+) -> Result<()> {
+    grow_account(target, payer, system_program, space)?;
+    let mut data = target.try_borrow_mut_data()?;
+    data.fill(0);
+    account.try_serialize(&mut &mut data[..])
+}
+
+/// Grow `target` to `space`, with `payer` topping up its rent
+pub fn grow_account<'info>(
+    target: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+) -> Result<()> {
+    if target.data_len() >= space {
+        return Ok(());
+    }
+    let shortfall = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(target.lamports());
+    if shortfall > 0 {
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            payer.key,
+            target.key,
+            shortfall,
+        );
+        anchor_lang::solana_program::program::invoke(
+            &transfer_instruction,
+            &[payer.clone(), target.clone(), system_program.clone()],
+        )?;
+    }
+    target.resize(space)?;
+    Ok(())
+}
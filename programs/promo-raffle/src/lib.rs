@@ -0,0 +1,657 @@
+use anchor_lang::prelude::*;
+use program_utils::ed25519::verify_signature;
+use protocol_guardian::GuardianConfig;
+use solana_sha256_hasher::hashv;
+
+declare_id!("7bkp1LY6xLGMuWvituZ1jSyDGzHC22ShHyPbjYQnoqJT");
+
+/// Native ed25519 signature verification program
+pub use program_utils::ed25519::ED25519_PROGRAM_ID;
+
+/// Escrow program allowed to award tickets through its hook PDA
+pub const ESCROW_PROGRAM_ID: Pubkey = pubkey!("2ZuJbvYqvhXq7N7WjKw3r4YqkU3r7CmLGjXXvKhGz3xF");
+
+#[program]
+pub mod promo_raffle {
+    use super::*;
+
+    /// Initialize the raffle and open the first round
+    pub fn initialize_raffle(
+        ctx: Context<InitializeRaffle>,
+        draw_oracle: Pubkey,
+        tickets_per_request: u32,
+        round_duration: i64,
+        prize_amount: u64,
+    ) -> Result<()> {
+        require!(tickets_per_request > 0, RaffleError::InvalidParams);
+        require!(round_duration > 0, RaffleError::InvalidParams);
+
+        let now = Clock::get()?.unix_timestamp;
+        let config = &mut ctx.accounts.raffle_config;
+        config.admin = ctx.accounts.admin.key();
+        config.draw_oracle = draw_oracle;
+        config.tickets_per_request = tickets_per_request;
+        config.round_duration = round_duration;
+        config.prize_amount = prize_amount;
+        config.current_round = 0;
+        config.total_tickets_awarded = 0;
+        config.total_prizes_paid = 0;
+
+        open_round(&mut ctx.accounts.round, 0, now, round_duration);
+
+        emit!(RaffleConfigured {
+            admin: config.admin,
+            draw_oracle,
+            tickets_per_request,
+            round_duration,
+            prize_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Update raffle parameters (admin only)
+    pub fn update_raffle(
+        ctx: Context<UpdateRaffle>,
+        draw_oracle: Option<Pubkey>,
+        tickets_per_request: Option<u32>,
+        round_duration: Option<i64>,
+        prize_amount: Option<u64>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.raffle_config;
+
+        if let Some(draw_oracle) = draw_oracle {
+            config.draw_oracle = draw_oracle;
+        }
+        if let Some(tickets_per_request) = tickets_per_request {
+            require!(tickets_per_request > 0, RaffleError::InvalidParams);
+            config.tickets_per_request = tickets_per_request;
+        }
+        if let Some(round_duration) = round_duration {
+            require!(round_duration > 0, RaffleError::InvalidParams);
+            config.round_duration = round_duration;
+        }
+        if let Some(prize_amount) = prize_amount {
+            config.prize_amount = prize_amount;
+        }
+
+        emit!(RaffleConfigured {
+            admin: config.admin,
+            draw_oracle: config.draw_oracle,
+            tickets_per_request: config.tickets_per_request,
+            round_duration: config.round_duration,
+            prize_amount: config.prize_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Add lamports to the marketing vault that pays prizes
+    pub fn fund_vault(
+        ctx: Context<FundVault>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, RaffleError::InvalidParams);
+
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.funder.key(),
+            &ctx.accounts.marketing_vault.key(),
+            amount,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_instruction,
+            &[
+                ctx.accounts.funder.to_account_info(),
+                ctx.accounts.marketing_vault.to_account_info(),
+            ],
+        )?;
+
+        emit!(VaultFunded {
+            funder: ctx.accounts.funder.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Award tickets for a completed request (escrow CPI only)
+    pub fn record_tickets(
+        ctx: Context<RecordTickets>,
+        user: Pubkey,
+        request_id: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.raffle_config;
+        let round = &mut ctx.accounts.round;
+        require!(round.status == RoundStatus::Open, RaffleError::RoundNotOpen);
+
+        let count = config.tickets_per_request as u64;
+        let batch = &mut ctx.accounts.ticket_batch;
+        batch.round = round.round;
+        batch.batch_index = round.total_batches;
+        batch.user = user;
+        batch.request_id = request_id;
+        batch.first_ticket = round.total_tickets;
+        batch.count = count;
+
+        round.total_batches += 1;
+        round.total_tickets += count;
+        config.total_tickets_awarded = config.total_tickets_awarded.saturating_add(count);
+
+        emit!(TicketsAwarded {
+            round: round.round,
+            user,
+            request_id,
+            first_ticket: batch.first_ticket,
+            count,
+        });
+
+        Ok(())
+    }
+
+    /// Close an ended round, fix its drawing seed and prize, and open the next round (permissionless)
+    ///
+    /// The seed is not a source of randomness: it only binds the draw
+    /// oracle's signature to this round and its final ticket count, so it is
+    /// derived from nothing the caller could pick by choosing when to call
+    pub fn request_draw(
+        ctx: Context<RequestDraw>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let round_key = ctx.accounts.round.key();
+        let round = &mut ctx.accounts.round;
+        require!(round.status == RoundStatus::Open, RaffleError::RoundNotOpen);
+        require!(clock.unix_timestamp >= round.ends_at, RaffleError::RoundStillOpen);
+
+        let vault = &ctx.accounts.marketing_vault;
+        let config = &mut ctx.accounts.raffle_config;
+
+        round.status = RoundStatus::Drawing;
+        round.prize = config.prize_amount.min(vault.lamports());
+        round.seed = hashv(&[round_key.as_ref(), &round.total_tickets.to_le_bytes()]).to_bytes();
+
+        config.current_round += 1;
+        open_round(
+            &mut ctx.accounts.next_round,
+            config.current_round,
+            clock.unix_timestamp,
+            config.round_duration,
+        );
+
+        emit!(DrawRequested {
+            round: round.round,
+            seed: round.seed,
+            total_tickets: round.total_tickets,
+            prize: round.prize,
+        });
+
+        Ok(())
+    }
+
+    /// Settle a drawing with the draw oracle's signature over the round seed
+    ///
+    /// The oracle signature must be verified by an ed25519 program instruction
+    /// placed immediately before this one; the winning ticket is drawn from
+    /// its hash. This is not a VRF: ed25519 signatures aren't unique, so the
+    /// oracle can produce many valid ones for a seed and in effect chooses the
+    /// winner. The draw is only as fair as the oracle is trusted to be.
+    pub fn settle_draw(
+        ctx: Context<SettleDraw>,
+    ) -> Result<()> {
+        let round = &mut ctx.accounts.round;
+        require!(round.status == RoundStatus::Drawing, RaffleError::RoundNotDrawing);
+
+        let signature = verify_signature(
+            &ctx.accounts.instructions.to_account_info(),
+            &ctx.accounts.raffle_config.draw_oracle,
+            &round.seed,
+            RaffleError::MissingOracleSignature,
+            RaffleError::InvalidOracleSignature,
+        )?;
+
+        let draw_hash = hashv(&[&signature]).to_bytes();
+        round.draw_hash = draw_hash;
+        round.status = RoundStatus::Drawn;
+
+        if round.total_tickets > 0 {
+            let mut value = [0u8; 8];
+            value.copy_from_slice(&draw_hash[..8]);
+            round.winning_ticket = Some(u64::from_le_bytes(value) % round.total_tickets);
+        }
+
+        emit!(DrawSettled {
+            round: round.round,
+            draw_hash,
+            winning_ticket: round.winning_ticket,
+        });
+
+        Ok(())
+    }
+
+    /// Pay the round's prize to the holder of the winning ticket
+    pub fn claim_prize(
+        ctx: Context<ClaimPrize>,
+    ) -> Result<()> {
+        let round = &mut ctx.accounts.round;
+        require!(round.status == RoundStatus::Drawn, RaffleError::RoundNotDrawn);
+        require!(round.winner.is_none(), RaffleError::PrizeClaimed);
+
+        let batch = &ctx.accounts.ticket_batch;
+        let winning_ticket = round.winning_ticket.ok_or(RaffleError::NotWinningTicket)?;
+        require!(batch.contains(winning_ticket), RaffleError::NotWinningTicket);
+
+        let prize = round.prize.min(ctx.accounts.marketing_vault.lamports());
+        round.winner = Some(batch.user);
+
+        if prize > 0 {
+            let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.marketing_vault.key(),
+                &ctx.accounts.winner.key(),
+                prize,
+            );
+
+            anchor_lang::solana_program::program::invoke_signed(
+                &transfer_instruction,
+                &[
+                    ctx.accounts.marketing_vault.to_account_info(),
+                    ctx.accounts.winner.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+                &[&[b"marketing_vault", &[ctx.bumps.marketing_vault]]],
+            )?;
+        }
+
+        let config = &mut ctx.accounts.raffle_config;
+        config.total_prizes_paid = config.total_prizes_paid.saturating_add(prize);
+
+        emit!(PrizeClaimed {
+            round: round.round,
+            winner: batch.user,
+            ticket: winning_ticket,
+            prize,
+        });
+
+        Ok(())
+    }
+
+    /// Close a ticket batch from a finished round and return its rent
+    pub fn close_ticket_batch(
+        ctx: Context<CloseTicketBatch>,
+    ) -> Result<()> {
+        let round = &ctx.accounts.round;
+        require!(round.status == RoundStatus::Drawn, RaffleError::RoundNotDrawn);
+
+        // The winning batch stays open until its prize is claimed
+        if let Some(winning_ticket) = round.winning_ticket {
+            require!(
+                round.winner.is_some() || !ctx.accounts.ticket_batch.contains(winning_ticket),
+                RaffleError::PrizeUnclaimed
+            );
+        }
+
+        Ok(())
+    }
+}
+
+// Helper function to reset a round account for a fresh ticket window
+fn open_round(round: &mut RaffleRound, index: u64, now: i64, duration: i64) {
+    round.round = index;
+    round.status = RoundStatus::Open;
+    round.starts_at = now;
+    round.ends_at = now + duration;
+    round.total_tickets = 0;
+    round.total_batches = 0;
+    round.prize = 0;
+    round.seed = [0; 32];
+    round.draw_hash = [0; 32];
+    round.winning_ticket = None;
+    round.winner = None;
+}
+
+#[derive(Accounts)]
+pub struct InitializeRaffle<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + RaffleConfig::INIT_SPACE,
+        seeds = [b"raffle_config"],
+        bump
+    )]
+    pub raffle_config: Account<'info, RaffleConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + RaffleRound::INIT_SPACE,
+        seeds = [b"raffle_round", 0u64.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub round: Account<'info, RaffleRound>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRaffle<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle_config"],
+        bump,
+        has_one = admin @ RaffleError::UnauthorizedAdmin
+    )]
+    pub raffle_config: Account<'info, RaffleConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FundVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"marketing_vault"],
+        bump
+    )]
+    pub marketing_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecordTickets<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle_config"],
+        bump
+    )]
+    pub raffle_config: Account<'info, RaffleConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"raffle_round", raffle_config.current_round.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub round: Account<'info, RaffleRound>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + TicketBatch::INIT_SPACE,
+        seeds = [b"ticket_batch", round.round.to_le_bytes().as_ref(), round.total_batches.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub ticket_batch: Account<'info, TicketBatch>,
+
+    /// Escrow hook PDA; only the escrow program can sign for it
+    #[account(
+        seeds = [b"raffle_hook"],
+        bump,
+        seeds::program = ESCROW_PROGRAM_ID
+    )]
+    pub escrow_hook: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RequestDraw<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle_config"],
+        bump
+    )]
+    pub raffle_config: Account<'info, RaffleConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"raffle_round", raffle_config.current_round.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub round: Account<'info, RaffleRound>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RaffleRound::INIT_SPACE,
+        seeds = [b"raffle_round", (raffle_config.current_round + 1).to_le_bytes().as_ref()],
+        bump
+    )]
+    pub next_round: Account<'info, RaffleRound>,
+
+    #[account(
+        seeds = [b"marketing_vault"],
+        bump
+    )]
+    pub marketing_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleDraw<'info> {
+    #[account(
+        seeds = [b"raffle_config"],
+        bump
+    )]
+    pub raffle_config: Account<'info, RaffleConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"raffle_round", round.round.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub round: Account<'info, RaffleRound>,
+
+    /// CHECK: Instructions sysvar, used to read the draw oracle's ed25519 signature
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPrize<'info> {
+    #[account(
+        mut,
+        seeds = [b"raffle_config"],
+        bump
+    )]
+    pub raffle_config: Account<'info, RaffleConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"raffle_round", round.round.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub round: Account<'info, RaffleRound>,
+
+    #[account(
+        seeds = [b"ticket_batch", round.round.to_le_bytes().as_ref(), ticket_batch.batch_index.to_le_bytes().as_ref()],
+        bump,
+        constraint = ticket_batch.user == winner.key() @ RaffleError::NotWinningTicket
+    )]
+    pub ticket_batch: Account<'info, TicketBatch>,
+
+    #[account(
+        mut,
+        seeds = [b"marketing_vault"],
+        bump
+    )]
+    pub marketing_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub winner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ RaffleError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTicketBatch<'info> {
+    #[account(
+        seeds = [b"raffle_round", round.round.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub round: Account<'info, RaffleRound>,
+
+    #[account(
+        mut,
+        seeds = [b"ticket_batch", round.round.to_le_bytes().as_ref(), ticket_batch.batch_index.to_le_bytes().as_ref()],
+        bump,
+        constraint = ticket_batch.user == user.key() @ RaffleError::UnauthorizedUser,
+        close = user
+    )]
+    pub ticket_batch: Account<'info, TicketBatch>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RaffleConfig {
+    pub admin: Pubkey,
+    /// ed25519 key of the oracle that picks each round's winning ticket
+    pub draw_oracle: Pubkey,
+    pub tickets_per_request: u32,
+    pub round_duration: i64,
+    pub prize_amount: u64,
+    pub current_round: u64,
+    pub total_tickets_awarded: u64,
+    pub total_prizes_paid: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RaffleRound {
+    pub round: u64,
+    pub status: RoundStatus,
+    pub starts_at: i64,
+    pub ends_at: i64,
+    pub total_tickets: u64,
+    pub total_batches: u64,
+    pub prize: u64,
+    /// Message the draw oracle must sign, fixed when the drawing is requested
+    pub seed: [u8; 32],
+    /// Hash of the oracle's signature over `seed`; the winning ticket is drawn from it
+    pub draw_hash: [u8; 32],
+    pub winning_ticket: Option<u64>,
+    pub winner: Option<Pubkey>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TicketBatch {
+    pub round: u64,
+    pub batch_index: u64,
+    pub user: Pubkey,
+    pub request_id: Pubkey,
+    pub first_ticket: u64,
+    pub count: u64,
+}
+
+impl TicketBatch {
+    pub fn contains(&self, ticket: u64) -> bool {
+        ticket >= self.first_ticket && ticket < self.first_ticket + self.count
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum RoundStatus {
+    Open,
+    Drawing,
+    Drawn,
+}
+
+#[event]
+pub struct RaffleConfigured {
+    pub admin: Pubkey,
+    pub draw_oracle: Pubkey,
+    pub tickets_per_request: u32,
+    pub round_duration: i64,
+    pub prize_amount: u64,
+}
+
+#[event]
+pub struct VaultFunded {
+    pub funder: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct TicketsAwarded {
+    pub round: u64,
+    pub user: Pubkey,
+    pub request_id: Pubkey,
+    pub first_ticket: u64,
+    pub count: u64,
+}
+
+#[event]
+pub struct DrawRequested {
+    pub round: u64,
+    pub seed: [u8; 32],
+    pub total_tickets: u64,
+    pub prize: u64,
+}
+
+#[event]
+pub struct DrawSettled {
+    pub round: u64,
+    pub draw_hash: [u8; 32],
+    pub winning_ticket: Option<u64>,
+}
+
+#[event]
+pub struct PrizeClaimed {
+    pub round: u64,
+    pub winner: Pubkey,
+    pub ticket: u64,
+    pub prize: u64,
+}
+
+#[error_code]
+pub enum RaffleError {
+    #[msg("Invalid raffle parameters")]
+    InvalidParams,
+    #[msg("Round is not open")]
+    RoundNotOpen,
+    #[msg("Round has not ended yet")]
+    RoundStillOpen,
+    #[msg("Round is not awaiting a drawing")]
+    RoundNotDrawing,
+    #[msg("Round has not been drawn")]
+    RoundNotDrawn,
+    #[msg("Missing ed25519 instruction with the draw oracle's signature")]
+    MissingOracleSignature,
+    #[msg("Draw oracle signature does not cover the round seed")]
+    InvalidOracleSignature,
+    #[msg("Ticket batch does not hold the winning ticket")]
+    NotWinningTicket,
+    #[msg("Prize has already been claimed")]
+    PrizeClaimed,
+    #[msg("Winning batch cannot be closed before its prize is claimed")]
+    PrizeUnclaimed,
+    #[msg("Unauthorized user")]
+    UnauthorizedUser,
+    #[msg("Unauthorized admin access")]
+    UnauthorizedAdmin,
+    #[msg("Protocol is paused by the guardian")]
+    ProtocolPaused,
+}
@@ -0,0 +1,393 @@
+use anchor_lang::prelude::*;
+
+declare_id!("4Cgr3BBZMZ9D5Hp5L6jwPn2teQfxyDsKEXoaEqSrLe1v");
+
+/// Maximum number of hook PDAs allowed to report metrics
+pub const MAX_REPORTERS: usize = 10;
+
+/// Days of history each agent's rolling analytics keep
+pub const AGENT_ANALYTICS_DAYS: usize = 30;
+
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+#[program]
+pub mod protocol_analytics {
+    use super::*;
+
+    /// Initialize the aggregator; epoch 0 starts now
+    pub fn initialize_analytics(
+        ctx: Context<InitializeAnalytics>,
+        epoch_length: i64,
+    ) -> Result<()> {
+        require!(epoch_length > 0, AnalyticsError::InvalidEpochLength);
+
+        let config = &mut ctx.accounts.analytics_config;
+        config.admin = ctx.accounts.admin.key();
+        config.epoch_length = epoch_length;
+        config.genesis_timestamp = Clock::get()?.unix_timestamp;
+        config.reporters = Vec::new();
+
+        Ok(())
+    }
+
+    /// Allow a program's hook PDA to report metrics (admin only)
+    pub fn add_reporter(
+        ctx: Context<UpdateAnalytics>,
+        reporter: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.analytics_config;
+        require!(
+            !config.reporters.contains(&reporter),
+            AnalyticsError::ReporterExists
+        );
+        require!(
+            config.reporters.len() < MAX_REPORTERS,
+            AnalyticsError::TooManyReporters
+        );
+
+        config.reporters.push(reporter);
+
+        emit!(ReporterUpdated {
+            reporter,
+            is_active: true,
+        });
+
+        Ok(())
+    }
+
+    /// Revoke a reporter (admin only)
+    pub fn remove_reporter(
+        ctx: Context<UpdateAnalytics>,
+        reporter: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.analytics_config;
+        let position = config
+            .reporters
+            .iter()
+            .position(|r| *r == reporter)
+            .ok_or(AnalyticsError::ReporterNotFound)?;
+
+        config.reporters.remove(position);
+
+        emit!(ReporterUpdated {
+            reporter,
+            is_active: false,
+        });
+
+        Ok(())
+    }
+
+    /// Roll a request lifecycle event into the current epoch (registered reporters only)
+    pub fn record_request_event(
+        ctx: Context<RecordRequestEvent>,
+        epoch: u64,
+        agent_id: Pubkey,
+        kind: RequestEvent,
+        amount: u64,
+        fee: u64,
+    ) -> Result<()> {
+        let config = &ctx.accounts.analytics_config;
+        let now = Clock::get()?.unix_timestamp;
+        require!(epoch == config.epoch_at(now), AnalyticsError::WrongEpoch);
+
+        let metrics = &mut ctx.accounts.epoch_metrics;
+        if metrics.started_at == 0 {
+            metrics.epoch = epoch;
+            metrics.started_at = config.epoch_start(epoch);
+        }
+
+        let agent_analytics = &mut ctx.accounts.agent_analytics;
+        agent_analytics.agent_id = agent_id;
+        agent_analytics.updated_at = now;
+        let bucket = agent_analytics.bucket_mut(AgentAnalytics::day_at(now));
+
+        match kind {
+            RequestEvent::Created => {
+                metrics.requests_created += 1;
+                metrics.volume = metrics.volume.saturating_add(amount);
+                bucket.requests += 1;
+            }
+            RequestEvent::Approved => {
+                metrics.requests_approved += 1;
+                metrics.settled_volume = metrics.settled_volume.saturating_add(amount);
+                metrics.fees = metrics.fees.saturating_add(fee);
+                bucket.completions += 1;
+                bucket.earnings = bucket.earnings.saturating_add(amount.saturating_sub(fee));
+            }
+            RequestEvent::Disputed => {
+                metrics.requests_disputed += 1;
+                bucket.disputes += 1;
+            }
+        }
+
+        // First event for this agent in the epoch counts it as active
+        let activity = &mut ctx.accounts.agent_activity;
+        if activity.first_seen_at == 0 {
+            activity.agent_id = agent_id;
+            activity.epoch = epoch;
+            activity.payer = ctx.accounts.payer.key();
+            activity.first_seen_at = now;
+            metrics.active_agents += 1;
+        }
+        activity.events += 1;
+        metrics.updated_at = now;
+
+        emit!(RequestEventRecorded {
+            epoch,
+            agent_id,
+            kind,
+            amount,
+            fee,
+            reporter: ctx.accounts.reporter.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Close an agent activity marker from a finished epoch and refund its payer (permissionless)
+    pub fn close_agent_activity(
+        ctx: Context<CloseAgentActivity>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            ctx.accounts.agent_activity.epoch < ctx.accounts.analytics_config.epoch_at(now),
+            AnalyticsError::EpochNotFinished
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeAnalytics<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AnalyticsConfig::INIT_SPACE,
+        seeds = [b"analytics_config"],
+        bump
+    )]
+    pub analytics_config: Account<'info, AnalyticsConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAnalytics<'info> {
+    #[account(
+        mut,
+        seeds = [b"analytics_config"],
+        bump,
+        has_one = admin @ AnalyticsError::UnauthorizedAdmin
+    )]
+    pub analytics_config: Account<'info, AnalyticsConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch: u64, agent_id: Pubkey)]
+pub struct RecordRequestEvent<'info> {
+    #[account(
+        seeds = [b"analytics_config"],
+        bump,
+        constraint = analytics_config.reporters.contains(&reporter.key()) @ AnalyticsError::UnauthorizedReporter
+    )]
+    pub analytics_config: Account<'info, AnalyticsConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + EpochMetrics::INIT_SPACE,
+        seeds = [b"epoch_metrics", epoch.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub epoch_metrics: Account<'info, EpochMetrics>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + AgentActivity::INIT_SPACE,
+        seeds = [b"agent_activity", agent_id.as_ref(), epoch.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub agent_activity: Account<'info, AgentActivity>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + AgentAnalytics::INIT_SPACE,
+        seeds = [b"agent_analytics", agent_id.as_ref()],
+        bump
+    )]
+    pub agent_analytics: Box<Account<'info, AgentAnalytics>>,
+
+    /// Hook PDA of the reporting program
+    pub reporter: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAgentActivity<'info> {
+    #[account(
+        seeds = [b"analytics_config"],
+        bump
+    )]
+    pub analytics_config: Account<'info, AnalyticsConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"agent_activity", agent_activity.agent_id.as_ref(), agent_activity.epoch.to_le_bytes().as_ref()],
+        bump,
+        has_one = payer,
+        close = payer
+    )]
+    pub agent_activity: Account<'info, AgentActivity>,
+
+    /// CHECK: Original rent payer, refunded on close
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AnalyticsConfig {
+    pub admin: Pubkey,
+    pub epoch_length: i64,
+    pub genesis_timestamp: i64,
+    #[max_len(10)]
+    pub reporters: Vec<Pubkey>,
+}
+
+impl AnalyticsConfig {
+    pub fn epoch_at(&self, timestamp: i64) -> u64 {
+        (timestamp.saturating_sub(self.genesis_timestamp).max(0) / self.epoch_length) as u64
+    }
+
+    pub fn epoch_start(&self, epoch: u64) -> i64 {
+        self.genesis_timestamp + epoch as i64 * self.epoch_length
+    }
+}
+
+/// Per-epoch protocol rollup read by dashboards and governance reports
+#[account]
+#[derive(InitSpace)]
+pub struct EpochMetrics {
+    pub epoch: u64,
+    pub started_at: i64,
+    pub requests_created: u64,
+    pub requests_approved: u64,
+    pub requests_disputed: u64,
+    /// Escrowed amount of requests created in the epoch
+    pub volume: u64,
+    /// Amount released to agents by approvals in the epoch
+    pub settled_volume: u64,
+    pub fees: u64,
+    pub active_agents: u32,
+    pub updated_at: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AgentActivity {
+    pub agent_id: Pubkey,
+    pub epoch: u64,
+    pub payer: Pubkey,
+    pub first_seen_at: i64,
+    pub events: u32,
+}
+
+/// Rolling daily trend of one agent's requests, read by dashboards. Buckets
+/// are indexed by day modulo `AGENT_ANALYTICS_DAYS` and reset when reused
+#[account]
+#[derive(InitSpace)]
+pub struct AgentAnalytics {
+    pub agent_id: Pubkey,
+    pub buckets: [DailyBucket; AGENT_ANALYTICS_DAYS],
+    pub updated_at: i64,
+}
+
+impl AgentAnalytics {
+    /// Day number of a timestamp, counted from the Unix epoch
+    pub fn day_at(timestamp: i64) -> u64 {
+        (timestamp.max(0) / SECONDS_PER_DAY) as u64
+    }
+
+    /// Activity on `day`, if it is still within the rolling window
+    pub fn bucket(&self, day: u64) -> Option<&DailyBucket> {
+        let bucket = &self.buckets[day as usize % AGENT_ANALYTICS_DAYS];
+        (bucket.day == day).then_some(bucket)
+    }
+
+    // Helper function to get the bucket for `day`, clearing it if it still holds an older day
+    fn bucket_mut(&mut self, day: u64) -> &mut DailyBucket {
+        let bucket = &mut self.buckets[day as usize % AGENT_ANALYTICS_DAYS];
+        if bucket.day != day {
+            *bucket = DailyBucket {
+                day,
+                ..Default::default()
+            };
+        }
+        bucket
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, InitSpace)]
+pub struct DailyBucket {
+    pub day: u64,
+    pub requests: u32,
+    pub completions: u32,
+    pub disputes: u32,
+    /// Approved amounts net of protocol fees
+    pub earnings: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum RequestEvent {
+    Created,
+    Approved,
+    Disputed,
+}
+
+#[event]
+pub struct ReporterUpdated {
+    pub reporter: Pubkey,
+    pub is_active: bool,
+}
+
+#[event]
+pub struct RequestEventRecorded {
+    pub epoch: u64,
+    pub agent_id: Pubkey,
+    pub kind: RequestEvent,
+    pub amount: u64,
+    pub fee: u64,
+    pub reporter: Pubkey,
+}
+
+#[error_code]
+pub enum AnalyticsError {
+    #[msg("Epoch length must be greater than zero")]
+    InvalidEpochLength,
+    #[msg("Reporter is already registered")]
+    ReporterExists,
+    #[msg("Reporter not found")]
+    ReporterNotFound,
+    #[msg("Too many reporters (max 10)")]
+    TooManyReporters,
+    #[msg("Event does not belong to the current epoch")]
+    WrongEpoch,
+    #[msg("Epoch has not finished yet")]
+    EpochNotFinished,
+    #[msg("Reporter is not registered")]
+    UnauthorizedReporter,
+    #[msg("Unauthorized admin access")]
+    UnauthorizedAdmin,
+}
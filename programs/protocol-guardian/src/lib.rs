@@ -0,0 +1,207 @@
+use anchor_lang::prelude::*;
+
+declare_id!("BSatmAWNZ9oD5bABgVhRYXU2JCSB7QSv6vYgXVtwovnP");
+
+/// Maximum number of guardians allowed to trip the circuit breaker
+pub const MAX_GUARDIANS: usize = 5;
+
+#[program]
+pub mod protocol_guardian {
+    use super::*;
+
+    /// Initialize the protocol-wide guardian config
+    pub fn initialize_guardian(
+        ctx: Context<InitializeGuardian>,
+        guardians: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(guardians.len() <= MAX_GUARDIANS, GuardianError::TooManyGuardians);
+
+        let config = &mut ctx.accounts.guardian_config;
+        let clock = Clock::get()?;
+
+        config.authority = ctx.accounts.authority.key();
+        config.guardians = guardians;
+        config.is_paused = false;
+        config.last_changed_at = clock.unix_timestamp;
+        config.last_changed_by = config.authority;
+
+        emit!(GuardianInitialized {
+            authority: config.authority,
+            guardians: config.guardians.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Halt every AgentMarket program (authority or any guardian)
+    pub fn pause_protocol(
+        ctx: Context<PauseProtocol>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.guardian_config;
+        let caller = ctx.accounts.caller.key();
+
+        require!(
+            caller == config.authority || config.guardians.contains(&caller),
+            GuardianError::UnauthorizedGuardian
+        );
+        require!(!config.is_paused, GuardianError::AlreadyPaused);
+
+        config.is_paused = true;
+        config.last_changed_at = Clock::get()?.unix_timestamp;
+        config.last_changed_by = caller;
+
+        emit!(ProtocolPauseChanged {
+            is_paused: true,
+            changed_by: caller,
+            timestamp: config.last_changed_at,
+        });
+
+        Ok(())
+    }
+
+    /// Resume the protocol (authority only)
+    pub fn unpause_protocol(
+        ctx: Context<UpdateGuardian>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.guardian_config;
+        require!(config.is_paused, GuardianError::NotPaused);
+
+        config.is_paused = false;
+        config.last_changed_at = Clock::get()?.unix_timestamp;
+        config.last_changed_by = ctx.accounts.authority.key();
+
+        emit!(ProtocolPauseChanged {
+            is_paused: false,
+            changed_by: config.last_changed_by,
+            timestamp: config.last_changed_at,
+        });
+
+        Ok(())
+    }
+
+    /// Replace the guardian set (authority only)
+    pub fn set_guardians(
+        ctx: Context<UpdateGuardian>,
+        guardians: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(guardians.len() <= MAX_GUARDIANS, GuardianError::TooManyGuardians);
+
+        let config = &mut ctx.accounts.guardian_config;
+        config.guardians = guardians;
+
+        emit!(GuardiansUpdated {
+            guardians: config.guardians.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Hand the guardian authority to a new key (authority only)
+    pub fn transfer_authority(
+        ctx: Context<UpdateGuardian>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.guardian_config;
+        let previous_authority = config.authority;
+        config.authority = new_authority;
+
+        emit!(GuardianAuthorityTransferred {
+            previous_authority,
+            new_authority,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeGuardian<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GuardianConfig::INIT_SPACE,
+        seeds = [b"guardian_config"],
+        bump
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PauseProtocol<'info> {
+    #[account(
+        mut,
+        seeds = [b"guardian_config"],
+        bump
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateGuardian<'info> {
+    #[account(
+        mut,
+        seeds = [b"guardian_config"],
+        bump,
+        has_one = authority @ GuardianError::UnauthorizedAuthority
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+/// Shared circuit-breaker state consulted by every AgentMarket program
+#[account]
+#[derive(InitSpace)]
+pub struct GuardianConfig {
+    pub authority: Pubkey,
+    #[max_len(MAX_GUARDIANS)]
+    pub guardians: Vec<Pubkey>,
+    pub is_paused: bool,
+    pub last_changed_at: i64,
+    pub last_changed_by: Pubkey,
+}
+
+#[event]
+pub struct GuardianInitialized {
+    pub authority: Pubkey,
+    pub guardians: Vec<Pubkey>,
+}
+
+#[event]
+pub struct ProtocolPauseChanged {
+    pub is_paused: bool,
+    pub changed_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GuardiansUpdated {
+    pub guardians: Vec<Pubkey>,
+}
+
+#[event]
+pub struct GuardianAuthorityTransferred {
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
+
+#[error_code]
+pub enum GuardianError {
+    #[msg("Too many guardians (max 5)")]
+    TooManyGuardians,
+    #[msg("Caller is not a guardian")]
+    UnauthorizedGuardian,
+    #[msg("Unauthorized guardian authority")]
+    UnauthorizedAuthority,
+    #[msg("Protocol is already paused")]
+    AlreadyPaused,
+    #[msg("Protocol is not paused")]
+    NotPaused,
+}
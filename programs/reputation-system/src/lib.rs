@@ -1,7 +1,16 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use program_utils::migration::{read_upgraded, write_upgraded};
+use protocol_guardian::GuardianConfig;
 
 declare_id!("8L8pDf3jutdpdr4m3np68CL9ZroLActrqwxi6s9Sk5ML");
 
+// The agent registry is not a dependency, so its id and the `update_reputation`
+// instruction it exposes to this program are mirrored here
+const AGENT_REGISTRY_ID: Pubkey = pubkey!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+// Anchor discriminator of `update_reputation`: sha256("global:update_reputation")[..8]
+const UPDATE_REPUTATION_IX: [u8; 8] = [194, 220, 43, 201, 54, 209, 49, 178];
+
 #[program]
 pub mod reputation_system {
     use super::*;
@@ -16,10 +25,10 @@ pub mod reputation_system {
         value: u8,
         review_text: String,
     ) -> Result<()> {
-        require!(stars >= 1 && stars <= 5, ReputationError::InvalidRating);
-        require!(quality >= 1 && quality <= 5, ReputationError::InvalidRating);
-        require!(speed >= 1 && speed <= 5, ReputationError::InvalidRating);
-        require!(value >= 1 && value <= 5, ReputationError::InvalidRating);
+        require!((1..=5).contains(&stars), ReputationError::InvalidRating);
+        require!((1..=5).contains(&quality), ReputationError::InvalidRating);
+        require!((1..=5).contains(&speed), ReputationError::InvalidRating);
+        require!((1..=5).contains(&value), ReputationError::InvalidRating);
         require!(review_text.len() <= 1000, ReputationError::ReviewTooLong);
 
     let rating_id = ctx.accounts.rating.key();
@@ -78,6 +87,15 @@ pub mod reputation_system {
             new_average: agent_profile.average_rating,
         });
 
+        push_reputation(
+            agent_profile,
+            &ctx.accounts.registry_profile,
+            &ctx.accounts.reputation_hook,
+            ctx.bumps.reputation_hook,
+            &ctx.accounts.registry_program,
+            &ctx.accounts.guardian_config,
+        )?;
+
         Ok(())
     }
 
@@ -141,6 +159,39 @@ pub mod reputation_system {
         Ok(())
     }
 
+    /// Initialize the reputation config holding the moderation admin, which
+    /// starts as the guardian authority (guardian authority)
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.reputation_config;
+        config.admin = ctx.accounts.authority.key();
+
+        emit!(ReputationAdminChanged {
+            previous_admin: Pubkey::default(),
+            new_admin: config.admin,
+        });
+
+        Ok(())
+    }
+
+    /// Hand the moderation admin role to a new key, e.g. a multisig signer (admin only)
+    pub fn transfer_admin(
+        ctx: Context<TransferAdmin>,
+        new_admin: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.reputation_config;
+        let previous_admin = config.admin;
+        config.admin = new_admin;
+
+        emit!(ReputationAdminChanged {
+            previous_admin,
+            new_admin,
+        });
+
+        Ok(())
+    }
+
     /// Admin function to moderate ratings
     pub fn moderate_rating(
         ctx: Context<ModerateRating>,
@@ -168,6 +219,15 @@ pub mod reputation_system {
                 agent_profile.total_ratings = 0;
                 agent_profile.average_rating = 0;
             }
+
+            push_reputation(
+                agent_profile,
+                &ctx.accounts.registry_profile,
+                &ctx.accounts.reputation_hook,
+                ctx.bumps.reputation_hook,
+                &ctx.accounts.registry_program,
+                &ctx.accounts.guardian_config,
+            )?;
         }
 
         emit!(RatingModerated {
@@ -178,6 +238,72 @@ pub mod reputation_system {
 
         Ok(())
     }
+
+    /// Upgrade a rating written under an older layout to the current one
+    /// during a schema migration (guardian authority, protocol paused).
+    /// Fields are only ever appended, so every field the old layout held is
+    /// kept and the rest read as zero
+    pub fn migrate_rating(ctx: Context<MigrateRating>) -> Result<()> {
+        let target = &ctx.accounts.rating;
+        let space = 8 + Rating::INIT_SPACE;
+        require!(target.data_len() < space, ReputationError::AccountUpToDate);
+
+        let rating: Rating = read_upgraded(target, space, ReputationError::MigrationTargetMismatch)?;
+        require_keys_eq!(
+            rating.rating_id,
+            target.key(),
+            ReputationError::MigrationIdentityMismatch
+        );
+        write_upgraded(
+            target,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+            space,
+            &rating,
+        )?;
+
+        emit!(AccountMigrated {
+            account: target.key(),
+            size: space as u32,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+// Helper function to mirror an agent's aggregate onto its registry profile, signing as the reputation hook
+fn push_reputation<'info>(
+    aggregate: &AgentReputationProfile,
+    registry_profile: &UncheckedAccount<'info>,
+    reputation_hook: &UncheckedAccount<'info>,
+    hook_bump: u8,
+    registry_program: &UncheckedAccount<'info>,
+    guardian_config: &Account<'info, GuardianConfig>,
+) -> Result<()> {
+    let mut data = UPDATE_REPUTATION_IX.to_vec();
+    data.extend_from_slice(&aggregate.average_rating.to_le_bytes());
+    let instruction = Instruction {
+        program_id: AGENT_REGISTRY_ID,
+        accounts: vec![
+            AccountMeta::new(registry_profile.key(), false),
+            AccountMeta::new_readonly(reputation_hook.key(), true),
+            AccountMeta::new_readonly(guardian_config.key(), false),
+        ],
+        data,
+    };
+    anchor_lang::solana_program::program::invoke_signed(
+        &instruction,
+        &[
+            registry_profile.to_account_info(),
+            reputation_hook.to_account_info(),
+            guardian_config.to_account_info(),
+            registry_program.to_account_info(),
+        ],
+        &[&[b"reputation_hook", &[hook_bump]]],
+    )?;
+
+    Ok(())
 }
 
 // Helper function to calculate weighted average
@@ -209,10 +335,30 @@ pub struct SubmitRating<'info> {
     )]
     pub agent_profile: Account<'info, AgentReputationProfile>,
 
+    /// CHECK: The agent's registry profile; the registry checks its type
+    #[account(mut, address = agent_profile.agent_id @ ReputationError::RegistryProfileMismatch)]
+    pub registry_profile: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"reputation_hook"], bump)]
+    /// CHECK: PDA that authorizes reputation updates in the agent registry
+    pub reputation_hook: UncheckedAccount<'info>,
+
+    /// CHECK: The agent registry program
+    #[account(address = AGENT_REGISTRY_ID)]
+    pub registry_program: UncheckedAccount<'info>,
+
     #[account(mut)]
     pub user: Signer<'info>,
 
     pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ReputationError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
 }
 
 #[derive(Accounts)]
@@ -231,6 +377,14 @@ pub struct InitializeAgentReputation<'info> {
     pub creator: Signer<'info>,
 
     pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ReputationError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
 }
 
 #[derive(Accounts)]
@@ -252,6 +406,52 @@ pub struct ReportRating<'info> {
     pub rating: Account<'info, Rating>,
 
     pub reporter: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ReputationError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ReputationConfig::INIT_SPACE,
+        seeds = [b"reputation_config"],
+        bump
+    )]
+    pub reputation_config: Account<'info, ReputationConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        has_one = authority @ ReputationError::UnauthorizedAdmin
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TransferAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [b"reputation_config"],
+        bump,
+        has_one = admin @ ReputationError::UnauthorizedAdmin
+    )]
+    pub reputation_config: Account<'info, ReputationConfig>,
+
+    pub admin: Signer<'info>,
 }
 
 #[derive(Accounts)]
@@ -270,8 +470,65 @@ pub struct ModerateRating<'info> {
     )]
     pub agent_profile: Account<'info, AgentReputationProfile>,
 
-    /// CHECK: Admin authority - would be verified off-chain
+    /// CHECK: The agent's registry profile; the registry checks its type
+    #[account(mut, address = agent_profile.agent_id @ ReputationError::RegistryProfileMismatch)]
+    pub registry_profile: UncheckedAccount<'info>,
+
+    #[account(seeds = [b"reputation_hook"], bump)]
+    /// CHECK: PDA that authorizes reputation updates in the agent registry
+    pub reputation_hook: UncheckedAccount<'info>,
+
+    /// CHECK: The agent registry program
+    #[account(address = AGENT_REGISTRY_ID)]
+    pub registry_program: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"reputation_config"],
+        bump,
+        has_one = admin @ ReputationError::UnauthorizedAdmin
+    )]
+    pub reputation_config: Account<'info, ReputationConfig>,
+
     pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ ReputationError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[account]
+pub struct ReputationConfig {
+    pub admin: Pubkey,              // 32 bytes
+}
+
+impl ReputationConfig {
+    pub const INIT_SPACE: usize = 32;
+}
+
+#[derive(Accounts)]
+pub struct MigrateRating<'info> {
+    /// CHECK: Possibly in an older layout; upgraded and rewritten by the handler
+    #[account(mut, owner = crate::ID)]
+    pub rating: UncheckedAccount<'info>,
+
+    /// Pays the rent for the grown account
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        has_one = authority @ ReputationError::UnauthorizedMigration,
+        constraint = guardian_config.is_paused @ ReputationError::ProtocolNotPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[account]
@@ -344,6 +601,12 @@ pub struct RatingReported {
     pub reason: String,
 }
 
+#[event]
+pub struct ReputationAdminChanged {
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
 #[event]
 pub struct RatingModerated {
     pub rating_id: Pubkey,
@@ -351,6 +614,13 @@ pub struct RatingModerated {
     pub moderator: Pubkey,
 }
 
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub size: u32,
+    pub timestamp: i64,
+}
+
 #[error_code]
 pub enum ReputationError {
     #[msg("Rating must be between 1 and 5")]
@@ -361,4 +631,22 @@ pub enum ReputationError {
     ReasonTooLong,
     #[msg("Admin note is too long (max 500 characters)")]
     NoteTooLong,
-}
\ No newline at end of file
+    #[msg("Protocol is paused by the guardian")]
+    ProtocolPaused,
+    #[msg("Unauthorized admin access")]
+    UnauthorizedAdmin,
+    #[msg("Only the guardian authority can migrate accounts")]
+    UnauthorizedMigration,
+    #[msg("Migrations require the protocol to be paused")]
+    ProtocolNotPaused,
+    #[msg("Account is not of the type being migrated")]
+    MigrationTargetMismatch,
+    #[msg("Migration chunk extends past the end of the account")]
+    MigrationChunkOutOfBounds,
+    #[msg("Migrated account does not match its address")]
+    MigrationIdentityMismatch,
+    #[msg("Registry profile does not match the agent")]
+    RegistryProfileMismatch,
+    #[msg("Account already has the current layout")]
+    AccountUpToDate,
+}
@@ -1,7 +1,16 @@
 use anchor_lang::prelude::*;
+use protocol_guardian::GuardianConfig;
 
 declare_id!("5xot9PVkphiX2adznghwrAuxGs2zeWisNSxMW6hU6Hkj");
 
+// The agent registry depends on this program, so its id and the `AgentLineage`
+// and `AgentProfile` layouts are mirrored here rather than imported
+const AGENT_REGISTRY_ID: Pubkey = pubkey!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+// Anchor discriminator of `AgentLineage`: sha256("account:AgentLineage")[..8]
+const AGENT_LINEAGE_DISCRIMINATOR: [u8; 8] = [239, 49, 15, 7, 250, 164, 44, 170];
+// Anchor discriminator of `AgentProfile`: sha256("account:AgentProfile")[..8]
+const AGENT_PROFILE_DISCRIMINATOR: [u8; 8] = [60, 227, 42, 24, 0, 87, 86, 205];
+
 #[program]
 pub mod royalty_splitter {
     use super::*;
@@ -66,17 +75,17 @@ pub mod royalty_splitter {
             RoyaltyError::InsufficientFunds
         );
 
+        let source = &ctx.accounts.source_account;
+        let system_program = &ctx.accounts.system_program;
+
         // Transfer to creator
-        **ctx.accounts.source_account.try_borrow_mut_lamports()? -= creator_amount;
-        **ctx.accounts.creator_account.try_borrow_mut_lamports()? += creator_amount;
+        transfer_from_source(source, &ctx.accounts.creator_account, system_program, creator_amount)?;
 
         // Transfer to platform
-        **ctx.accounts.source_account.try_borrow_mut_lamports()? -= platform_amount;
-        **ctx.accounts.platform_account.try_borrow_mut_lamports()? += platform_amount;
+        transfer_from_source(source, &ctx.accounts.platform_account, system_program, platform_amount)?;
 
         // Transfer to treasury
-        **ctx.accounts.source_account.try_borrow_mut_lamports()? -= treasury_amount;
-        **ctx.accounts.treasury_account.try_borrow_mut_lamports()? += treasury_amount;
+        transfer_from_source(source, &ctx.accounts.treasury_account, system_program, treasury_amount)?;
 
         // Update statistics
         config.total_distributed += amount;
@@ -108,6 +117,54 @@ pub mod royalty_splitter {
         Ok(())
     }
 
+    /// Pay a forked agent: its lineage royalty goes to the parent agent's
+    /// payout wallet and the rest is distributed as `distribute_payment` would
+    pub fn distribute_lineage_payment<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DistributeLineagePayment<'info>>,
+        amount: u64,
+        creator: Pubkey,
+    ) -> Result<()> {
+        require!(amount > 0, RoyaltyError::InvalidAmount);
+
+        let (agent_id, parent_agent, royalty_bps) = read_lineage(&ctx.accounts.agent_lineage)?;
+        require_keys_eq!(
+            ctx.accounts.parent_profile.key(),
+            parent_agent,
+            RoyaltyError::InvalidLineage
+        );
+        require_keys_eq!(
+            ctx.accounts.parent_payout_wallet.key(),
+            read_profile_payout_wallet(&ctx.accounts.parent_profile)?,
+            RoyaltyError::InvalidLineage
+        );
+
+        let lineage_amount = (amount as u128 * royalty_bps as u128 / 10_000) as u64;
+        transfer_from_source(
+            &ctx.accounts.distribution.source_account,
+            &ctx.accounts.parent_payout_wallet.to_account_info(),
+            &ctx.accounts.distribution.system_program,
+            lineage_amount,
+        )?;
+
+        emit!(LineageRoyaltyPaid {
+            agent_id,
+            parent_agent,
+            parent_creator: ctx.accounts.parent_payout_wallet.key(),
+            amount: lineage_amount,
+        });
+
+        distribute_payment(
+            Context::new(
+                ctx.program_id,
+                &mut ctx.accounts.distribution,
+                ctx.remaining_accounts,
+                ctx.bumps.distribution,
+            ),
+            amount - lineage_amount,
+            creator,
+        )
+    }
+
     /// Update royalty configuration (admin only)
     pub fn update_config(
         ctx: Context<UpdateConfig>,
@@ -158,6 +215,26 @@ pub mod royalty_splitter {
         Ok(())
     }
 
+    /// Hand the admin role to a new key, e.g. a multisig signer (admin only)
+    pub fn transfer_admin(
+        ctx: Context<UpdateConfig>,
+        new_admin: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.royalty_config;
+        let previous_admin = config.admin;
+        config.admin = new_admin;
+
+        let clock = Clock::get()?;
+        config.updated_at = clock.unix_timestamp;
+
+        emit!(RoyaltyAdminChanged {
+            previous_admin,
+            new_admin,
+        });
+
+        Ok(())
+    }
+
     /// Withdraw accumulated platform fees
     pub fn withdraw_platform_fees(
         ctx: Context<WithdrawPlatformFees>,
@@ -217,6 +294,65 @@ pub mod royalty_splitter {
     }
 }
 
+// Helper function to read a fork's agent, parent agent, and lineage royalty from its registry `AgentLineage`
+fn read_lineage(agent_lineage: &UncheckedAccount) -> Result<(Pubkey, Pubkey, u16)> {
+    let data = agent_lineage.try_borrow_data()?;
+    require!(
+        data.len() >= 74 && data[..8] == AGENT_LINEAGE_DISCRIMINATOR,
+        RoyaltyError::InvalidLineage
+    );
+    // agent_id, parent_agent, royalty_bps: u16
+    let agent_id = Pubkey::new_from_array(data[8..40].try_into().unwrap());
+    let (lineage_pda, _) =
+        Pubkey::find_program_address(&[b"lineage", agent_id.as_ref()], &AGENT_REGISTRY_ID);
+    require_keys_eq!(agent_lineage.key(), lineage_pda, RoyaltyError::InvalidLineage);
+    Ok((
+        agent_id,
+        Pubkey::new_from_array(data[40..72].try_into().unwrap()),
+        u16::from_le_bytes(data[72..74].try_into().unwrap()),
+    ))
+}
+
+// Helper function to read the payout wallet of a registry `AgentProfile`,
+// stored right after its agent id and creator
+fn read_profile_payout_wallet(agent_profile: &UncheckedAccount) -> Result<Pubkey> {
+    let data = agent_profile.try_borrow_data()?;
+    require!(
+        data.len() >= 104 && data[..8] == AGENT_PROFILE_DISCRIMINATOR,
+        RoyaltyError::InvalidLineage
+    );
+    Ok(Pubkey::new_from_array(data[72..104].try_into().unwrap()))
+}
+
+// Helper function to move lamports out of the signing source wallet via the system program
+fn transfer_from_source<'info>(
+    source: &Signer<'info>,
+    recipient: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+        &source.key(),
+        &recipient.key(),
+        amount,
+    );
+
+    anchor_lang::solana_program::program::invoke(
+        &transfer_instruction,
+        &[
+            source.to_account_info(),
+            recipient.clone(),
+            system_program.to_account_info(),
+        ],
+    )?;
+
+    Ok(())
+}
+
 #[derive(Accounts)]
 pub struct InitializeConfig<'info> {
     #[account(
@@ -253,9 +389,9 @@ pub struct DistributePayment<'info> {
     )]
     pub distribution_record: Account<'info, DistributionRecord>,
 
-    /// CHECK: Source account holding the funds to distribute
+    /// Wallet funding the distribution; must sign so the system program can debit it
     #[account(mut)]
-    pub source_account: UncheckedAccount<'info>,
+    pub source_account: Signer<'info>,
 
     /// CHECK: Creator's account to receive their share
     #[account(mut)]
@@ -279,6 +415,31 @@ pub struct DistributePayment<'info> {
     pub payer: Signer<'info>,
 
     pub system_program: Program<'info, System>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ RoyaltyError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeLineagePayment<'info> {
+    pub distribution: DistributePayment<'info>,
+
+    /// CHECK: Registry `AgentLineage` of the paid agent, read at fixed offsets in the handler
+    #[account(owner = AGENT_REGISTRY_ID @ RoyaltyError::InvalidLineage)]
+    pub agent_lineage: UncheckedAccount<'info>,
+
+    /// CHECK: Registry `AgentProfile` of the parent agent, checked against the lineage in the handler
+    #[account(owner = AGENT_REGISTRY_ID @ RoyaltyError::InvalidLineage)]
+    pub parent_profile: UncheckedAccount<'info>,
+
+    /// CHECK: Parent agent's payout wallet, checked against its profile in the handler
+    #[account(mut)]
+    pub parent_payout_wallet: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
@@ -312,6 +473,14 @@ pub struct WithdrawPlatformFees<'info> {
     pub destination: UncheckedAccount<'info>,
 
     pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ RoyaltyError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
 }
 
 #[derive(Accounts)]
@@ -407,6 +576,12 @@ pub struct RoyaltyConfigUpdated {
     pub treasury_wallet: Pubkey,
 }
 
+#[event]
+pub struct RoyaltyAdminChanged {
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
 #[event]
 pub struct PlatformFeesWithdrawn {
     pub amount: u64,
@@ -420,6 +595,14 @@ pub struct PauseStateChanged {
     pub changed_by: Pubkey,
 }
 
+#[event]
+pub struct LineageRoyaltyPaid {
+    pub agent_id: Pubkey,
+    pub parent_agent: Pubkey,
+    pub parent_creator: Pubkey,
+    pub amount: u64,
+}
+
 #[error_code]
 pub enum RoyaltyError {
     #[msg("Share percentages must total 100")]
@@ -436,4 +619,8 @@ pub enum RoyaltyError {
     InvalidTreasuryWallet,
     #[msg("Contract is currently paused")]
     ContractPaused,
+    #[msg("Protocol is paused by the guardian")]
+    ProtocolPaused,
+    #[msg("Lineage accounts do not match the forked agent")]
+    InvalidLineage,
 }
\ No newline at end of file
@@ -0,0 +1,419 @@
+use anchor_lang::prelude::*;
+use agent_registry::AgentProfile;
+
+declare_id!("5iLc8vyi8WPUBVgJcqrCZ6yzSPU77mhe8b74SiWUFrep");
+
+/// Maximum number of monitor nodes in the oracle set
+pub const MAX_MONITORS: usize = 10;
+
+#[program]
+pub mod sla_oracle {
+    use super::*;
+
+    /// Initialize the oracle's monitor set and SLA thresholds
+    pub fn initialize_oracle(
+        ctx: Context<InitializeOracle>,
+        monitors: Vec<Pubkey>,
+        params: SlaParams,
+    ) -> Result<()> {
+        require!(monitors.len() <= MAX_MONITORS, SlaError::TooManyMonitors);
+        validate_params(&params, monitors.len())?;
+
+        let config = &mut ctx.accounts.oracle_config;
+        config.admin = ctx.accounts.admin.key();
+        config.monitors = monitors;
+        config.params = params;
+
+        emit!(OracleConfigured {
+            admin: config.admin,
+            monitors: config.monitors.clone(),
+            params: config.params.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Replace the monitor set and thresholds (admin only)
+    pub fn update_oracle(
+        ctx: Context<UpdateOracle>,
+        monitors: Option<Vec<Pubkey>>,
+        params: Option<SlaParams>,
+        new_admin: Option<Pubkey>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.oracle_config;
+
+        if let Some(monitors) = monitors {
+            require!(monitors.len() <= MAX_MONITORS, SlaError::TooManyMonitors);
+            config.monitors = monitors;
+        }
+        if let Some(params) = params {
+            config.params = params;
+        }
+        if let Some(new_admin) = new_admin {
+            config.admin = new_admin;
+        }
+        validate_params(&config.params, config.monitors.len())?;
+
+        emit!(OracleConfigured {
+            admin: config.admin,
+            monitors: config.monitors.clone(),
+            params: config.params.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Post one monitor's uptime/latency observation of an agent endpoint for the current round
+    pub fn submit_observation(
+        ctx: Context<SubmitObservation>,
+        round: u64,
+        uptime_bps: u16,
+        latency_ms: u32,
+    ) -> Result<()> {
+        require!(uptime_bps <= 10_000, SlaError::InvalidUptime);
+
+        let config = &ctx.accounts.oracle_config;
+        let monitor = ctx.accounts.monitor.key();
+        require!(config.monitors.contains(&monitor), SlaError::UnauthorizedMonitor);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            round == current_round(now, config.params.round_duration),
+            SlaError::WrongRound
+        );
+
+        let sla_round = &mut ctx.accounts.sla_round;
+        require!(!sla_round.is_finalized, SlaError::RoundFinalized);
+        require!(
+            !sla_round.observations.iter().any(|o| o.monitor == monitor),
+            SlaError::DuplicateObservation
+        );
+        require!(
+            sla_round.observations.len() < MAX_MONITORS,
+            SlaError::TooManyMonitors
+        );
+
+        sla_round.agent_id = ctx.accounts.agent_profile.key();
+        sla_round.round = round;
+        sla_round.observations.push(Observation {
+            monitor,
+            uptime_bps,
+            latency_ms,
+            observed_at: now,
+        });
+
+        emit!(ObservationSubmitted {
+            agent_id: sla_round.agent_id,
+            round,
+            monitor,
+            uptime_bps,
+            latency_ms,
+        });
+
+        Ok(())
+    }
+
+    /// Aggregate a closed round into the agent's SLA record (permissionless)
+    pub fn finalize_round(
+        ctx: Context<FinalizeRound>,
+    ) -> Result<()> {
+        let params = &ctx.accounts.oracle_config.params;
+        let sla_round = &mut ctx.accounts.sla_round;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(!sla_round.is_finalized, SlaError::RoundFinalized);
+        require!(
+            sla_round.round < current_round(now, params.round_duration),
+            SlaError::RoundStillOpen
+        );
+        require!(
+            sla_round.observations.len() >= params.min_observations as usize,
+            SlaError::QuorumNotReached
+        );
+
+        let mut uptimes: Vec<u16> = sla_round.observations.iter().map(|o| o.uptime_bps).collect();
+        let mut latencies: Vec<u32> = sla_round.observations.iter().map(|o| o.latency_ms).collect();
+        uptimes.sort_unstable();
+        latencies.sort_unstable();
+        let median_uptime_bps = uptimes[uptimes.len() / 2];
+        let median_latency_ms = latencies[latencies.len() / 2];
+
+        let breached = median_uptime_bps < params.min_uptime_bps
+            || median_latency_ms > params.max_latency_ms;
+
+        sla_round.is_finalized = true;
+        sla_round.median_uptime_bps = median_uptime_bps;
+        sla_round.median_latency_ms = median_latency_ms;
+        sla_round.breached = breached;
+
+        let agent_sla = &mut ctx.accounts.agent_sla;
+        agent_sla.agent_id = sla_round.agent_id;
+        agent_sla.last_round = sla_round.round;
+        agent_sla.uptime_bps = median_uptime_bps;
+        agent_sla.latency_ms = median_latency_ms;
+        agent_sla.rounds_observed += 1;
+        if breached {
+            agent_sla.breach_count += 1;
+            agent_sla.consecutive_breaches += 1;
+        } else {
+            agent_sla.consecutive_breaches = 0;
+        }
+        agent_sla.recommended_timeout = recommended_timeout(params, median_latency_ms);
+        agent_sla.updated_at = now;
+
+        emit!(SlaRoundFinalized {
+            agent_id: agent_sla.agent_id,
+            round: agent_sla.last_round,
+            uptime_bps: median_uptime_bps,
+            latency_ms: median_latency_ms,
+            observations: sla_round.observations.len() as u8,
+            recommended_timeout: agent_sla.recommended_timeout,
+        });
+
+        if breached {
+            emit!(SlaBreached {
+                agent_id: agent_sla.agent_id,
+                round: agent_sla.last_round,
+                uptime_bps: median_uptime_bps,
+                latency_ms: median_latency_ms,
+                consecutive_breaches: agent_sla.consecutive_breaches,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+// Helper function to sanity-check oracle thresholds against the monitor set
+fn validate_params(params: &SlaParams, monitor_count: usize) -> Result<()> {
+    require!(params.round_duration > 0, SlaError::InvalidParams);
+    require!(params.min_uptime_bps <= 10_000, SlaError::InvalidParams);
+    require!(
+        params.min_observations > 0 && params.min_observations as usize <= monitor_count.max(1),
+        SlaError::InvalidParams
+    );
+    require!(
+        params.min_timeout <= params.max_timeout,
+        SlaError::InvalidParams
+    );
+    Ok(())
+}
+
+/// Round index containing a timestamp
+pub fn current_round(now: i64, round_duration: i64) -> u64 {
+    (now / round_duration) as u64
+}
+
+/// Escrow timeout suggested for an agent given its median latency
+pub fn recommended_timeout(params: &SlaParams, latency_ms: u32) -> i64 {
+    let scaled = (latency_ms as i64 * params.timeout_multiplier as i64) / 1_000;
+    scaled.clamp(params.min_timeout, params.max_timeout)
+}
+
+#[derive(Accounts)]
+pub struct InitializeOracle<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + OracleConfig::INIT_SPACE,
+        seeds = [b"sla_oracle_config"],
+        bump
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateOracle<'info> {
+    #[account(
+        mut,
+        seeds = [b"sla_oracle_config"],
+        bump,
+        has_one = admin @ SlaError::UnauthorizedAdmin
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(round: u64)]
+pub struct SubmitObservation<'info> {
+    #[account(
+        seeds = [b"sla_oracle_config"],
+        bump
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+
+    /// Registered agent being monitored
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        init_if_needed,
+        payer = monitor,
+        space = 8 + SlaRound::INIT_SPACE,
+        seeds = [b"sla_round", agent_profile.key().as_ref(), round.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub sla_round: Account<'info, SlaRound>,
+
+    #[account(mut)]
+    pub monitor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeRound<'info> {
+    #[account(
+        seeds = [b"sla_oracle_config"],
+        bump
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"sla_round", sla_round.agent_id.as_ref(), sla_round.round.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub sla_round: Account<'info, SlaRound>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + AgentSla::INIT_SPACE,
+        seeds = [b"agent_sla", sla_round.agent_id.as_ref()],
+        bump
+    )]
+    pub agent_sla: Account<'info, AgentSla>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct OracleConfig {
+    pub admin: Pubkey,
+    #[max_len(MAX_MONITORS)]
+    pub monitors: Vec<Pubkey>,
+    pub params: SlaParams,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct SlaParams {
+    /// Length of an observation round in seconds
+    pub round_duration: i64,
+    /// Observations required before a round can be finalized
+    pub min_observations: u8,
+    /// Uptime below this (in bps) counts as a breach
+    pub min_uptime_bps: u16,
+    /// Median latency above this counts as a breach
+    pub max_latency_ms: u32,
+    /// Multiplier applied to median latency (ms) to derive a timeout in seconds
+    pub timeout_multiplier: u32,
+    pub min_timeout: i64,
+    pub max_timeout: i64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct SlaRound {
+    pub agent_id: Pubkey,
+    pub round: u64,
+    #[max_len(MAX_MONITORS)]
+    pub observations: Vec<Observation>,
+    pub is_finalized: bool,
+    pub median_uptime_bps: u16,
+    pub median_latency_ms: u32,
+    pub breached: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Observation {
+    pub monitor: Pubkey,
+    pub uptime_bps: u16,
+    pub latency_ms: u32,
+    pub observed_at: i64,
+}
+
+/// Rolling SLA record read by the registry and escrow
+#[account]
+#[derive(InitSpace)]
+pub struct AgentSla {
+    pub agent_id: Pubkey,
+    pub last_round: u64,
+    pub uptime_bps: u16,
+    pub latency_ms: u32,
+    pub rounds_observed: u64,
+    pub breach_count: u64,
+    pub consecutive_breaches: u32,
+    /// Suggested escrow timeout in seconds
+    pub recommended_timeout: i64,
+    pub updated_at: i64,
+}
+
+#[event]
+pub struct OracleConfigured {
+    pub admin: Pubkey,
+    pub monitors: Vec<Pubkey>,
+    pub params: SlaParams,
+}
+
+#[event]
+pub struct ObservationSubmitted {
+    pub agent_id: Pubkey,
+    pub round: u64,
+    pub monitor: Pubkey,
+    pub uptime_bps: u16,
+    pub latency_ms: u32,
+}
+
+#[event]
+pub struct SlaRoundFinalized {
+    pub agent_id: Pubkey,
+    pub round: u64,
+    pub uptime_bps: u16,
+    pub latency_ms: u32,
+    pub observations: u8,
+    pub recommended_timeout: i64,
+}
+
+#[event]
+pub struct SlaBreached {
+    pub agent_id: Pubkey,
+    pub round: u64,
+    pub uptime_bps: u16,
+    pub latency_ms: u32,
+    pub consecutive_breaches: u32,
+}
+
+#[error_code]
+pub enum SlaError {
+    #[msg("Too many monitors (max 10)")]
+    TooManyMonitors,
+    #[msg("Invalid oracle parameters")]
+    InvalidParams,
+    #[msg("Uptime must be at most 10000 bps")]
+    InvalidUptime,
+    #[msg("Observation is not for the current round")]
+    WrongRound,
+    #[msg("Monitor already submitted an observation this round")]
+    DuplicateObservation,
+    #[msg("Round has already been finalized")]
+    RoundFinalized,
+    #[msg("Round is still open")]
+    RoundStillOpen,
+    #[msg("Not enough observations to finalize the round")]
+    QuorumNotReached,
+    #[msg("Signer is not a registered monitor")]
+    UnauthorizedMonitor,
+    #[msg("Unauthorized admin access")]
+    UnauthorizedAdmin,
+}
@@ -0,0 +1,877 @@
+use anchor_lang::prelude::*;
+use marketplace_escrow::program::MarketplaceEscrow;
+use marketplace_escrow::{AgentRequestCount, ServiceRequest, AGENT_REGISTRY_ID};
+use protocol_guardian::GuardianConfig;
+
+declare_id!("5YNi15dxNGW5syx6ErphZymLoyYiQcaVdxfB2N6EpmdG");
+
+#[program]
+pub mod task_orderbook {
+    use super::*;
+
+    /// Create an order book for a standardized task type
+    pub fn create_market(
+        ctx: Context<CreateMarket>,
+        task_type: String,
+    ) -> Result<()> {
+        require!(
+            !task_type.is_empty() && task_type.len() <= 32,
+            OrderbookError::InvalidTaskType
+        );
+
+        let market = &mut ctx.accounts.market;
+        market.task_type = task_type;
+        market.creator = ctx.accounts.creator.key();
+        market.next_bid_id = 0;
+        market.total_matches = 0;
+        market.total_volume = 0;
+        market.last_match_price = 0;
+
+        emit!(MarketCreated {
+            market: market.key(),
+            task_type: market.task_type.clone(),
+            creator: market.creator,
+        });
+
+        Ok(())
+    }
+
+    /// Post a standing offer to perform the market's task at a fixed price
+    pub fn post_offer(
+        ctx: Context<PostOffer>,
+        agent_id: Pubkey,
+        price: u64,
+        capacity: u32,
+    ) -> Result<()> {
+        require!(price > 0, OrderbookError::InvalidPrice);
+        require!(capacity > 0, OrderbookError::InvalidCapacity);
+
+        let offer = &mut ctx.accounts.offer;
+        offer.market = ctx.accounts.market.key();
+        offer.agent_id = agent_id;
+        offer.agent_authority = ctx.accounts.agent_authority.key();
+        offer.price = price;
+        offer.capacity = capacity;
+        offer.filled = 0;
+        offer.is_active = true;
+
+        emit!(OfferPosted {
+            market: offer.market,
+            offer: offer.key(),
+            agent_id,
+            price,
+            capacity,
+        });
+
+        Ok(())
+    }
+
+    /// Reprice, resize, or withdraw a standing offer
+    pub fn update_offer(
+        ctx: Context<UpdateOffer>,
+        price: Option<u64>,
+        capacity: Option<u32>,
+        is_active: Option<bool>,
+    ) -> Result<()> {
+        let offer = &mut ctx.accounts.offer;
+
+        if let Some(price) = price {
+            require!(price > 0, OrderbookError::InvalidPrice);
+            offer.price = price;
+        }
+        if let Some(capacity) = capacity {
+            offer.capacity = capacity;
+        }
+        if let Some(is_active) = is_active {
+            offer.is_active = is_active;
+        }
+
+        emit!(OfferPosted {
+            market: offer.market,
+            offer: offer.key(),
+            agent_id: offer.agent_id,
+            price: offer.price,
+            capacity: offer.capacity,
+        });
+
+        Ok(())
+    }
+
+    /// Post a bid, escrowing the maximum price plus request rent in the bid vault
+    pub fn post_bid(
+        ctx: Context<PostBid>,
+        max_price: u64,
+        request_data: String,
+    ) -> Result<()> {
+        require!(max_price > 0, OrderbookError::InvalidPrice);
+        require!(request_data.len() <= 1000, OrderbookError::RequestDataTooLong);
+
+        // Also covers the agent's request count, which the first request against it creates
+        let rent = Rent::get()?;
+        let request_rent = rent.minimum_balance(8 + ServiceRequest::INIT_SPACE)
+            + rent.minimum_balance(8 + AgentRequestCount::INIT_SPACE);
+        let deposit = max_price
+            .checked_add(request_rent)
+            .ok_or(OrderbookError::MathOverflow)?;
+
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.user.key(),
+            &ctx.accounts.bid_vault.key(),
+            deposit,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_instruction,
+            &[
+                ctx.accounts.user.to_account_info(),
+                ctx.accounts.bid_vault.to_account_info(),
+            ],
+        )?;
+
+        let market = &mut ctx.accounts.market;
+        let bid = &mut ctx.accounts.bid;
+        bid.market = market.key();
+        bid.bid_id = market.next_bid_id;
+        bid.user = ctx.accounts.user.key();
+        bid.max_price = max_price;
+        bid.request_data = request_data;
+        bid.status = BidStatus::Open;
+        bid.agent_id = None;
+        bid.service_request = None;
+        bid.created_at = Clock::get()?.unix_timestamp;
+
+        market.next_bid_id += 1;
+
+        emit!(BidPosted {
+            market: bid.market,
+            bid: bid.key(),
+            user: bid.user,
+            max_price,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel an unfilled bid and return the deposit
+    pub fn cancel_bid(
+        ctx: Context<CancelBid>,
+    ) -> Result<()> {
+        require!(ctx.accounts.bid.status == BidStatus::Open, OrderbookError::BidNotOpen);
+
+        let bid_key = ctx.accounts.bid.key();
+        sweep_vault(
+            &ctx.accounts.bid_vault,
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.system_program,
+            &bid_key,
+            ctx.bumps.bid_vault,
+        )?;
+
+        ctx.accounts.bid.status = BidStatus::Cancelled;
+
+        emit!(BidCancelled {
+            bid: bid_key,
+            user: ctx.accounts.user.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Match a bid against a standing offer and settle it into an escrow request (permissionless)
+    pub fn fill_bid(
+        ctx: Context<FillBid>,
+    ) -> Result<()> {
+        let offer = &ctx.accounts.offer;
+        let bid = &ctx.accounts.bid;
+
+        require!(bid.status == BidStatus::Open, OrderbookError::BidNotOpen);
+        require!(offer.is_active, OrderbookError::OfferInactive);
+        require!(offer.filled < offer.capacity, OrderbookError::OfferAtCapacity);
+        require!(offer.price <= bid.max_price, OrderbookError::PriceNotCrossed);
+
+        let bid_key = bid.key();
+        let price = offer.price;
+        let agent_id = offer.agent_id;
+        let vault_seeds: &[&[&[u8]]] = &[&[b"bid_vault", bid_key.as_ref(), &[ctx.bumps.bid_vault]]];
+
+        // The escrow sees the bid vault as the requester, so check the bid owner here
+        require_keys_neq!(
+            *ctx.accounts.user_block_entry.owner,
+            AGENT_REGISTRY_ID,
+            OrderbookError::UserBlocked
+        );
+
+        marketplace_escrow::cpi::create_service_request(
+            CpiContext::new_with_signer(
+                ctx.accounts.escrow_program.to_account_info(),
+                marketplace_escrow::cpi::accounts::CreateServiceRequest {
+                    service_request: ctx.accounts.service_request.to_account_info(),
+                    escrow_account: ctx.accounts.escrow_account.to_account_info(),
+                    agent_requests: ctx.accounts.agent_requests.to_account_info(),
+                    user: ctx.accounts.bid_vault.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    guardian_config: ctx.accounts.guardian_config.to_account_info(),
+                    analytics: marketplace_escrow::cpi::accounts::AnalyticsHook {
+                        analytics_config: None,
+                        epoch_metrics: None,
+                        agent_activity: None,
+                        agent_analytics: None,
+                        analytics_hook: None,
+                        analytics_program: None,
+                        system_program: None,
+                    },
+                    block_entry: ctx.accounts.vault_block_entry.to_account_info(),
+                    // Bid vaults are never allowlisted, so allowlist-only agents can't be filled here
+                    allowlist_entry: None,
+                    // Bid vaults pay every request; a trial would leave the bid stranded
+                    trial_counter: None,
+                    // Bids are escrowed in lamports
+                    payment_mint: None,
+                    user_token_account: None,
+                    escrow_token_account: None,
+                    token_program: None,
+                    associated_token_program: None,
+                    accepted_mint: None,
+                    // An expired request would refund the vault and leave the bid filled
+                    agent_sla: None,
+                },
+                vault_seeds,
+            ),
+            agent_id,
+            price,
+            bid.request_data.clone(),
+            None,
+        )?;
+
+        // Return price improvement and unused rent reserve to the user
+        sweep_vault(
+            &ctx.accounts.bid_vault,
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.system_program,
+            &bid_key,
+            ctx.bumps.bid_vault,
+        )?;
+
+        let offer = &mut ctx.accounts.offer;
+        offer.filled += 1;
+
+        let bid = &mut ctx.accounts.bid;
+        bid.status = BidStatus::Filled;
+        bid.agent_id = Some(agent_id);
+        bid.service_request = Some(ctx.accounts.service_request.key());
+
+        let market = &mut ctx.accounts.market;
+        market.total_matches += 1;
+        market.total_volume = market.total_volume.saturating_add(price);
+        market.last_match_price = price;
+
+        emit!(OrderMatched {
+            market: market.key(),
+            bid: bid_key,
+            offer: offer.key(),
+            agent_id,
+            user: bid.user,
+            price,
+            service_request: ctx.accounts.service_request.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Approve the result of a filled bid, releasing escrow to the agent.
+    /// Co-creator wallets are passed on to the escrow as remaining accounts
+    pub fn approve_fill<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ApproveFill<'info>>,
+    ) -> Result<()> {
+        let bid_key = ctx.accounts.bid.key();
+        let vault_seeds: &[&[&[u8]]] = &[&[b"bid_vault", bid_key.as_ref(), &[ctx.bumps.bid_vault]]];
+
+        marketplace_escrow::cpi::approve_result(CpiContext::new_with_signer(
+            ctx.accounts.escrow_program.to_account_info(),
+            marketplace_escrow::cpi::accounts::ApproveResult {
+                service_request: ctx.accounts.service_request.to_account_info(),
+                escrow_account: ctx.accounts.escrow_account.to_account_info(),
+                agent_requests: ctx.accounts.agent_requests.to_account_info(),
+                user: ctx.accounts.bid_vault.to_account_info(),
+                agent_profile: ctx.accounts.agent_profile.to_account_info(),
+                payout_hook: ctx.accounts.payout_hook.to_account_info(),
+                registry_program: ctx.accounts.registry_program.to_account_info(),
+                payout_wallet: ctx.accounts.payout_wallet.to_account_info(),
+                co_creator_split: ctx.accounts.co_creator_split.to_account_info(),
+                platform_wallet: ctx.accounts.platform_wallet.to_account_info(),
+                treasury_wallet: ctx.accounts.treasury_wallet.to_account_info(),
+                guardian_config: ctx.accounts.guardian_config.to_account_info(),
+                raffle_config: None,
+                raffle_round: None,
+                ticket_batch: None,
+                raffle_hook: None,
+                raffle_program: None,
+                system_program: ctx.accounts.system_program.to_account_info(),
+                analytics: marketplace_escrow::cpi::accounts::AnalyticsHook {
+                    analytics_config: None,
+                    epoch_metrics: None,
+                    agent_activity: None,
+                    agent_analytics: None,
+                    analytics_hook: None,
+                    analytics_program: None,
+                    system_program: None,
+                },
+                escrow_token_account: None,
+                payout_token_account: None,
+                platform_token_account: None,
+                treasury_token_account: None,
+                token_program: None,
+            },
+            vault_seeds,
+        )
+        .with_remaining_accounts(ctx.remaining_accounts.to_vec()))?;
+
+        emit!(FillSettled {
+            bid: bid_key,
+            service_request: ctx.accounts.service_request.key(),
+            approved: true,
+        });
+
+        Ok(())
+    }
+
+    /// Dispute the result of a filled bid
+    pub fn dispute_fill(
+        ctx: Context<DisputeFill>,
+        reason: String,
+    ) -> Result<()> {
+        let bid_key = ctx.accounts.bid.key();
+        let vault_seeds: &[&[&[u8]]] = &[&[b"bid_vault", bid_key.as_ref(), &[ctx.bumps.bid_vault]]];
+
+        marketplace_escrow::cpi::dispute_result(
+            CpiContext::new_with_signer(
+                ctx.accounts.escrow_program.to_account_info(),
+                marketplace_escrow::cpi::accounts::DisputeResult {
+                    service_request: ctx.accounts.service_request.to_account_info(),
+                    user: ctx.accounts.bid_vault.to_account_info(),
+                    guardian_config: ctx.accounts.guardian_config.to_account_info(),
+                    analytics: marketplace_escrow::cpi::accounts::AnalyticsHook {
+                        analytics_config: None,
+                        epoch_metrics: None,
+                        agent_activity: None,
+                        agent_analytics: None,
+                        analytics_hook: None,
+                        analytics_program: None,
+                        system_program: None,
+                    },
+                },
+                vault_seeds,
+            ),
+            reason,
+        )?;
+
+        emit!(FillSettled {
+            bid: bid_key,
+            service_request: ctx.accounts.service_request.key(),
+            approved: false,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a filled bid's request before the agent starts, refunding the user
+    pub fn cancel_fill(
+        ctx: Context<CancelFill>,
+    ) -> Result<()> {
+        let bid_key = ctx.accounts.bid.key();
+        let vault_seeds: &[&[&[u8]]] = &[&[b"bid_vault", bid_key.as_ref(), &[ctx.bumps.bid_vault]]];
+
+        marketplace_escrow::cpi::cancel_request(CpiContext::new_with_signer(
+            ctx.accounts.escrow_program.to_account_info(),
+            marketplace_escrow::cpi::accounts::CancelRequest {
+                service_request: ctx.accounts.service_request.to_account_info(),
+                escrow_account: ctx.accounts.escrow_account.to_account_info(),
+                agent_requests: ctx.accounts.agent_requests.to_account_info(),
+                user: ctx.accounts.bid_vault.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                guardian_config: ctx.accounts.guardian_config.to_account_info(),
+                escrow_token_account: None,
+                user_token_account: None,
+                token_program: None,
+            },
+            vault_seeds,
+        ))?;
+
+        sweep_vault(
+            &ctx.accounts.bid_vault,
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.system_program,
+            &bid_key,
+            ctx.bumps.bid_vault,
+        )?;
+
+        ctx.accounts.bid.status = BidStatus::Cancelled;
+
+        emit!(BidCancelled {
+            bid: bid_key,
+            user: ctx.accounts.user.key(),
+        });
+
+        Ok(())
+    }
+}
+
+// Helper function to move every lamport held by a bid vault back to the user
+fn sweep_vault<'info>(
+    bid_vault: &SystemAccount<'info>,
+    user: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    bid_key: &Pubkey,
+    bump: u8,
+) -> Result<()> {
+    let amount = bid_vault.lamports();
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+        &bid_vault.key(),
+        &user.key(),
+        amount,
+    );
+
+    anchor_lang::solana_program::program::invoke_signed(
+        &transfer_instruction,
+        &[
+            bid_vault.to_account_info(),
+            user.clone(),
+            system_program.to_account_info(),
+        ],
+        &[&[b"bid_vault", bid_key.as_ref(), &[bump]]],
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(task_type: String)]
+pub struct CreateMarket<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + TaskMarket::INIT_SPACE,
+        seeds = [b"task_market", task_type.as_bytes()],
+        bump
+    )]
+    pub market: Account<'info, TaskMarket>,
+
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(agent_id: Pubkey)]
+pub struct PostOffer<'info> {
+    #[account(
+        seeds = [b"task_market", market.task_type.as_bytes()],
+        bump
+    )]
+    pub market: Account<'info, TaskMarket>,
+
+    #[account(
+        init,
+        payer = agent_authority,
+        space = 8 + Offer::INIT_SPACE,
+        seeds = [b"offer", market.key().as_ref(), agent_id.as_ref()],
+        bump
+    )]
+    pub offer: Account<'info, Offer>,
+
+    #[account(mut)]
+    pub agent_authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateOffer<'info> {
+    #[account(
+        mut,
+        seeds = [b"offer", offer.market.as_ref(), offer.agent_id.as_ref()],
+        bump,
+        has_one = agent_authority @ OrderbookError::UnauthorizedAgent
+    )]
+    pub offer: Account<'info, Offer>,
+
+    pub agent_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PostBid<'info> {
+    #[account(
+        mut,
+        seeds = [b"task_market", market.task_type.as_bytes()],
+        bump
+    )]
+    pub market: Account<'info, TaskMarket>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Bid::INIT_SPACE,
+        seeds = [b"bid", market.key().as_ref(), market.next_bid_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub bid: Account<'info, Bid>,
+
+    /// System-owned vault that acts as the escrow "user" for this bid
+    #[account(
+        mut,
+        seeds = [b"bid_vault", bid.key().as_ref()],
+        bump
+    )]
+    pub bid_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelBid<'info> {
+    #[account(
+        mut,
+        seeds = [b"bid", bid.market.as_ref(), bid.bid_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = user @ OrderbookError::UnauthorizedUser
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        mut,
+        seeds = [b"bid_vault", bid.key().as_ref()],
+        bump
+    )]
+    pub bid_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FillBid<'info> {
+    #[account(
+        mut,
+        seeds = [b"task_market", market.task_type.as_bytes()],
+        bump
+    )]
+    pub market: Account<'info, TaskMarket>,
+
+    #[account(
+        mut,
+        seeds = [b"offer", market.key().as_ref(), offer.agent_id.as_ref()],
+        bump
+    )]
+    pub offer: Account<'info, Offer>,
+
+    #[account(
+        mut,
+        seeds = [b"bid", market.key().as_ref(), bid.bid_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = user @ OrderbookError::UnauthorizedUser
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        mut,
+        seeds = [b"bid_vault", bid.key().as_ref()],
+        bump
+    )]
+    pub bid_vault: SystemAccount<'info>,
+
+    /// CHECK: Bid owner receiving price improvement; verified against the bid
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+
+    /// CHECK: Created and validated by the escrow program
+    #[account(mut)]
+    pub service_request: UncheckedAccount<'info>,
+
+    /// CHECK: Escrow PDA validated by the escrow program
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+
+    /// CHECK: Agent's open request count, validated by the escrow program
+    #[account(mut)]
+    pub agent_requests: UncheckedAccount<'info>,
+
+    /// CHECK: Registry `BlockEntry` of the bid owner, which must not exist
+    #[account(
+        seeds = [b"blocklist", offer.agent_id.as_ref(), user.key().as_ref()],
+        bump,
+        seeds::program = AGENT_REGISTRY_ID
+    )]
+    pub user_block_entry: UncheckedAccount<'info>,
+
+    /// CHECK: Registry `BlockEntry` of the bid vault, validated by the escrow program
+    pub vault_block_entry: UncheckedAccount<'info>,
+
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub escrow_program: Program<'info, MarketplaceEscrow>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveFill<'info> {
+    #[account(
+        seeds = [b"bid", bid.market.as_ref(), bid.bid_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = user @ OrderbookError::UnauthorizedUser,
+        constraint = bid.service_request == Some(service_request.key()) @ OrderbookError::RequestMismatch
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        mut,
+        seeds = [b"bid_vault", bid.key().as_ref()],
+        bump
+    )]
+    pub bid_vault: SystemAccount<'info>,
+
+    pub user: Signer<'info>,
+
+    /// CHECK: Validated by the escrow program
+    #[account(mut)]
+    pub service_request: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the escrow program
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the escrow program
+    #[account(mut)]
+    pub agent_requests: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the escrow program
+    #[account(mut)]
+    pub agent_profile: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the escrow program
+    pub payout_hook: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the escrow program
+    pub registry_program: UncheckedAccount<'info>,
+
+    /// CHECK: Agent's payout wallet, validated by the escrow program
+    #[account(mut)]
+    pub payout_wallet: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the escrow program
+    pub co_creator_split: UncheckedAccount<'info>,
+
+    /// CHECK: Platform wallet will receive fee
+    #[account(mut)]
+    pub platform_wallet: UncheckedAccount<'info>,
+
+    /// CHECK: Treasury wallet will receive fee
+    #[account(mut)]
+    pub treasury_wallet: UncheckedAccount<'info>,
+
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub escrow_program: Program<'info, MarketplaceEscrow>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DisputeFill<'info> {
+    #[account(
+        seeds = [b"bid", bid.market.as_ref(), bid.bid_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = user @ OrderbookError::UnauthorizedUser,
+        constraint = bid.service_request == Some(service_request.key()) @ OrderbookError::RequestMismatch
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        mut,
+        seeds = [b"bid_vault", bid.key().as_ref()],
+        bump
+    )]
+    pub bid_vault: SystemAccount<'info>,
+
+    pub user: Signer<'info>,
+
+    /// CHECK: Validated by the escrow program
+    #[account(mut)]
+    pub service_request: UncheckedAccount<'info>,
+
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub escrow_program: Program<'info, MarketplaceEscrow>,
+}
+
+#[derive(Accounts)]
+pub struct CancelFill<'info> {
+    #[account(
+        mut,
+        seeds = [b"bid", bid.market.as_ref(), bid.bid_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = user @ OrderbookError::UnauthorizedUser,
+        constraint = bid.service_request == Some(service_request.key()) @ OrderbookError::RequestMismatch
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        mut,
+        seeds = [b"bid_vault", bid.key().as_ref()],
+        bump
+    )]
+    pub bid_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: Validated by the escrow program
+    #[account(mut)]
+    pub service_request: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the escrow program
+    #[account(mut)]
+    pub escrow_account: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the escrow program
+    #[account(mut)]
+    pub agent_requests: UncheckedAccount<'info>,
+
+    pub guardian_config: Account<'info, GuardianConfig>,
+
+    pub escrow_program: Program<'info, MarketplaceEscrow>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TaskMarket {
+    #[max_len(32)]
+    pub task_type: String,
+    pub creator: Pubkey,
+    pub next_bid_id: u64,
+    pub total_matches: u64,
+    pub total_volume: u64,
+    pub last_match_price: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Offer {
+    pub market: Pubkey,
+    pub agent_id: Pubkey,
+    pub agent_authority: Pubkey,
+    pub price: u64,
+    pub capacity: u32,
+    pub filled: u32,
+    pub is_active: bool,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Bid {
+    pub market: Pubkey,
+    pub bid_id: u64,
+    pub user: Pubkey,
+    pub max_price: u64,
+    #[max_len(1000)]
+    pub request_data: String,
+    pub status: BidStatus,
+    pub agent_id: Option<Pubkey>,
+    pub service_request: Option<Pubkey>,
+    pub created_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum BidStatus {
+    Open,
+    Filled,
+    Cancelled,
+}
+
+#[event]
+pub struct MarketCreated {
+    pub market: Pubkey,
+    pub task_type: String,
+    pub creator: Pubkey,
+}
+
+#[event]
+pub struct OfferPosted {
+    pub market: Pubkey,
+    pub offer: Pubkey,
+    pub agent_id: Pubkey,
+    pub price: u64,
+    pub capacity: u32,
+}
+
+#[event]
+pub struct BidPosted {
+    pub market: Pubkey,
+    pub bid: Pubkey,
+    pub user: Pubkey,
+    pub max_price: u64,
+}
+
+#[event]
+pub struct BidCancelled {
+    pub bid: Pubkey,
+    pub user: Pubkey,
+}
+
+#[event]
+pub struct OrderMatched {
+    pub market: Pubkey,
+    pub bid: Pubkey,
+    pub offer: Pubkey,
+    pub agent_id: Pubkey,
+    pub user: Pubkey,
+    pub price: u64,
+    pub service_request: Pubkey,
+}
+
+#[event]
+pub struct FillSettled {
+    pub bid: Pubkey,
+    pub service_request: Pubkey,
+    pub approved: bool,
+}
+
+#[error_code]
+pub enum OrderbookError {
+    #[msg("Task type must be 1-32 characters")]
+    InvalidTaskType,
+    #[msg("Price must be greater than zero")]
+    InvalidPrice,
+    #[msg("Capacity must be greater than zero")]
+    InvalidCapacity,
+    #[msg("Request data is too long (max 1000 characters)")]
+    RequestDataTooLong,
+    #[msg("Bid is not open")]
+    BidNotOpen,
+    #[msg("Offer is not active")]
+    OfferInactive,
+    #[msg("Offer has no remaining capacity")]
+    OfferAtCapacity,
+    #[msg("Offer price exceeds the bid's maximum price")]
+    PriceNotCrossed,
+    #[msg("Service request does not belong to this bid")]
+    RequestMismatch,
+    #[msg("Unauthorized user")]
+    UnauthorizedUser,
+    #[msg("Unauthorized agent authority")]
+    UnauthorizedAgent,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Agent has blocked this user")]
+    UserBlocked,
+}
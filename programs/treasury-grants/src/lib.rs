@@ -0,0 +1,499 @@
+use anchor_lang::prelude::*;
+use protocol_guardian::GuardianConfig;
+
+declare_id!("RxoUJ8m3dUFCTQi21NKKoFN25SSyagkx3DAQkiCM9Pv");
+
+/// Maximum number of milestones in a single grant agreement
+pub const MAX_MILESTONES: usize = 10;
+
+#[program]
+pub mod treasury_grants {
+    use super::*;
+
+    /// Initialize the grants program under a governance authority (e.g. the DAO multisig signer)
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        governance: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.grants_config;
+        config.governance = governance;
+        config.grant_count = 0;
+        config.total_committed = 0;
+        config.total_paid = 0;
+
+        emit!(GovernanceChanged {
+            previous_governance: Pubkey::default(),
+            new_governance: governance,
+        });
+
+        Ok(())
+    }
+
+    /// Hand grant governance to a new authority (governance only)
+    pub fn set_governance(
+        ctx: Context<UpdateConfig>,
+        new_governance: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.grants_config;
+        let previous_governance = config.governance;
+        config.governance = new_governance;
+
+        emit!(GovernanceChanged {
+            previous_governance,
+            new_governance,
+        });
+
+        Ok(())
+    }
+
+    /// Escrow treasury funds against a milestone-based grant agreement (governance only)
+    pub fn create_grant(
+        ctx: Context<CreateGrant>,
+        grantee: Pubkey,
+        title: String,
+        milestones: Vec<MilestoneSpec>,
+    ) -> Result<()> {
+        require!(title.len() <= 100, GrantsError::TitleTooLong);
+        require!(
+            !milestones.is_empty() && milestones.len() <= MAX_MILESTONES,
+            GrantsError::InvalidMilestoneCount
+        );
+
+        let mut total_amount: u64 = 0;
+        for milestone in milestones.iter() {
+            require!(milestone.amount > 0, GrantsError::InvalidAmount);
+            total_amount = total_amount
+                .checked_add(milestone.amount)
+                .ok_or(GrantsError::MathOverflow)?;
+        }
+
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.governance.key(),
+            &ctx.accounts.grant.key(),
+            total_amount,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_instruction,
+            &[
+                ctx.accounts.governance.to_account_info(),
+                ctx.accounts.grant.to_account_info(),
+            ],
+        )?;
+
+        let config = &mut ctx.accounts.grants_config;
+        let grant = &mut ctx.accounts.grant;
+        grant.grant_id = config.grant_count;
+        grant.grantee = grantee;
+        grant.title = title;
+        grant.total_amount = total_amount;
+        grant.paid_amount = 0;
+        grant.status = GrantStatus::Active;
+        grant.created_at = Clock::get()?.unix_timestamp;
+        grant.milestones = milestones
+            .iter()
+            .map(|spec| Milestone {
+                amount: spec.amount,
+                description_hash: spec.description_hash,
+                evidence_hash: [0; 32],
+                status: MilestoneStatus::Pending,
+                submitted_at: None,
+                paid_at: None,
+            })
+            .collect();
+
+        config.grant_count += 1;
+        config.total_committed = config.total_committed.saturating_add(total_amount);
+
+        emit!(GrantCreated {
+            grant: grant.key(),
+            grant_id: grant.grant_id,
+            grantee,
+            total_amount,
+            milestone_count: grant.milestones.len() as u8,
+        });
+
+        Ok(())
+    }
+
+    /// Submit evidence that a milestone is complete (grantee only)
+    pub fn submit_milestone(
+        ctx: Context<SubmitMilestone>,
+        index: u8,
+        evidence_hash: [u8; 32],
+    ) -> Result<()> {
+        let grant_key = ctx.accounts.grant.key();
+        let grant = &mut ctx.accounts.grant;
+        require!(grant.status == GrantStatus::Active, GrantsError::GrantNotActive);
+
+        let milestone = grant
+            .milestones
+            .get_mut(index as usize)
+            .ok_or(GrantsError::InvalidMilestoneIndex)?;
+        require!(
+            milestone.status == MilestoneStatus::Pending,
+            GrantsError::MilestoneNotPending
+        );
+
+        milestone.evidence_hash = evidence_hash;
+        milestone.status = MilestoneStatus::Submitted;
+        milestone.submitted_at = Some(Clock::get()?.unix_timestamp);
+
+        emit!(MilestoneSubmitted {
+            grant: grant_key,
+            index,
+            evidence_hash,
+        });
+
+        Ok(())
+    }
+
+    /// Approve a submitted milestone and release its payout (governance only)
+    pub fn approve_milestone(
+        ctx: Context<ReviewMilestone>,
+        index: u8,
+    ) -> Result<()> {
+        let grant_info = ctx.accounts.grant.to_account_info();
+        let grant_key = ctx.accounts.grant.key();
+        let grant = &mut ctx.accounts.grant;
+        require!(grant.status == GrantStatus::Active, GrantsError::GrantNotActive);
+
+        let milestone = grant
+            .milestones
+            .get_mut(index as usize)
+            .ok_or(GrantsError::InvalidMilestoneIndex)?;
+        require!(
+            milestone.status == MilestoneStatus::Submitted,
+            GrantsError::MilestoneNotSubmitted
+        );
+
+        let amount = milestone.amount;
+        milestone.status = MilestoneStatus::Paid;
+        milestone.paid_at = Some(Clock::get()?.unix_timestamp);
+
+        **grant_info.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.grantee.try_borrow_mut_lamports()? += amount;
+
+        grant.paid_amount += amount;
+        if grant.milestones.iter().all(|m| m.status == MilestoneStatus::Paid) {
+            grant.status = GrantStatus::Completed;
+        }
+
+        let config = &mut ctx.accounts.grants_config;
+        config.total_paid = config.total_paid.saturating_add(amount);
+
+        emit!(MilestonePaid {
+            grant: grant_key,
+            index,
+            grantee: grant.grantee,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Send a submitted milestone back to the grantee for rework (governance only)
+    pub fn reject_milestone(
+        ctx: Context<ReviewMilestone>,
+        index: u8,
+    ) -> Result<()> {
+        let grant_key = ctx.accounts.grant.key();
+        let grant = &mut ctx.accounts.grant;
+        require!(grant.status == GrantStatus::Active, GrantsError::GrantNotActive);
+
+        let milestone = grant
+            .milestones
+            .get_mut(index as usize)
+            .ok_or(GrantsError::InvalidMilestoneIndex)?;
+        require!(
+            milestone.status == MilestoneStatus::Submitted,
+            GrantsError::MilestoneNotSubmitted
+        );
+
+        milestone.status = MilestoneStatus::Pending;
+        milestone.submitted_at = None;
+
+        emit!(MilestoneRejected {
+            grant: grant_key,
+            index,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a grant and return unpaid milestone funds to governance (governance only)
+    pub fn cancel_grant(
+        ctx: Context<CancelGrant>,
+    ) -> Result<()> {
+        let grant_info = ctx.accounts.grant.to_account_info();
+        let grant = &mut ctx.accounts.grant;
+        require!(grant.status == GrantStatus::Active, GrantsError::GrantNotActive);
+
+        let refund = grant.total_amount - grant.paid_amount;
+        grant.status = GrantStatus::Cancelled;
+        for milestone in grant.milestones.iter_mut() {
+            if milestone.status != MilestoneStatus::Paid {
+                milestone.status = MilestoneStatus::Cancelled;
+            }
+        }
+
+        **grant_info.try_borrow_mut_lamports()? -= refund;
+        **ctx.accounts.governance.try_borrow_mut_lamports()? += refund;
+
+        let config = &mut ctx.accounts.grants_config;
+        config.total_committed = config.total_committed.saturating_sub(refund);
+
+        emit!(GrantCancelled {
+            grant: grant.key(),
+            refunded_amount: refund,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + GrantsConfig::INIT_SPACE,
+        seeds = [b"grants_config"],
+        bump
+    )]
+    pub grants_config: Account<'info, GrantsConfig>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"grants_config"],
+        bump,
+        has_one = governance @ GrantsError::UnauthorizedGovernance
+    )]
+    pub grants_config: Account<'info, GrantsConfig>,
+
+    pub governance: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateGrant<'info> {
+    #[account(
+        mut,
+        seeds = [b"grants_config"],
+        bump,
+        has_one = governance @ GrantsError::UnauthorizedGovernance
+    )]
+    pub grants_config: Account<'info, GrantsConfig>,
+
+    #[account(
+        init,
+        payer = governance,
+        space = 8 + Grant::INIT_SPACE,
+        seeds = [b"grant", grants_config.grant_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub grant: Account<'info, Grant>,
+
+    /// Governance treasury that funds the grant
+    #[account(mut)]
+    pub governance: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitMilestone<'info> {
+    #[account(
+        mut,
+        seeds = [b"grant", grant.grant_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = grantee @ GrantsError::UnauthorizedGrantee
+    )]
+    pub grant: Account<'info, Grant>,
+
+    pub grantee: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReviewMilestone<'info> {
+    #[account(
+        mut,
+        seeds = [b"grants_config"],
+        bump,
+        has_one = governance @ GrantsError::UnauthorizedGovernance
+    )]
+    pub grants_config: Account<'info, GrantsConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"grant", grant.grant_id.to_le_bytes().as_ref()],
+        bump,
+        has_one = grantee @ GrantsError::UnauthorizedGrantee
+    )]
+    pub grant: Account<'info, Grant>,
+
+    /// CHECK: Grantee receives milestone payouts; verified against the grant
+    #[account(mut)]
+    pub grantee: UncheckedAccount<'info>,
+
+    pub governance: Signer<'info>,
+
+    #[account(
+        seeds = [b"guardian_config"],
+        bump,
+        seeds::program = protocol_guardian::ID,
+        constraint = !guardian_config.is_paused @ GrantsError::ProtocolPaused
+    )]
+    pub guardian_config: Account<'info, GuardianConfig>,
+}
+
+#[derive(Accounts)]
+pub struct CancelGrant<'info> {
+    #[account(
+        mut,
+        seeds = [b"grants_config"],
+        bump,
+        has_one = governance @ GrantsError::UnauthorizedGovernance
+    )]
+    pub grants_config: Account<'info, GrantsConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"grant", grant.grant_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub grant: Account<'info, Grant>,
+
+    #[account(mut)]
+    pub governance: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct GrantsConfig {
+    pub governance: Pubkey,
+    pub grant_count: u64,
+    pub total_committed: u64,
+    pub total_paid: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Grant {
+    pub grant_id: u64,
+    pub grantee: Pubkey,
+    #[max_len(100)]
+    pub title: String,
+    pub total_amount: u64,
+    pub paid_amount: u64,
+    pub status: GrantStatus,
+    pub created_at: i64,
+    #[max_len(MAX_MILESTONES)]
+    pub milestones: Vec<Milestone>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Milestone {
+    pub amount: u64,
+    pub description_hash: [u8; 32],
+    pub evidence_hash: [u8; 32],
+    pub status: MilestoneStatus,
+    pub submitted_at: Option<i64>,
+    pub paid_at: Option<i64>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MilestoneSpec {
+    pub amount: u64,
+    pub description_hash: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum GrantStatus {
+    Active,
+    Completed,
+    Cancelled,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, InitSpace)]
+pub enum MilestoneStatus {
+    Pending,
+    Submitted,
+    Paid,
+    Cancelled,
+}
+
+#[event]
+pub struct GovernanceChanged {
+    pub previous_governance: Pubkey,
+    pub new_governance: Pubkey,
+}
+
+#[event]
+pub struct GrantCreated {
+    pub grant: Pubkey,
+    pub grant_id: u64,
+    pub grantee: Pubkey,
+    pub total_amount: u64,
+    pub milestone_count: u8,
+}
+
+#[event]
+pub struct MilestoneSubmitted {
+    pub grant: Pubkey,
+    pub index: u8,
+    pub evidence_hash: [u8; 32],
+}
+
+#[event]
+pub struct MilestonePaid {
+    pub grant: Pubkey,
+    pub index: u8,
+    pub grantee: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct MilestoneRejected {
+    pub grant: Pubkey,
+    pub index: u8,
+}
+
+#[event]
+pub struct GrantCancelled {
+    pub grant: Pubkey,
+    pub refunded_amount: u64,
+}
+
+#[error_code]
+pub enum GrantsError {
+    #[msg("Title is too long (max 100 characters)")]
+    TitleTooLong,
+    #[msg("Grant must have between 1 and 10 milestones")]
+    InvalidMilestoneCount,
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    #[msg("Milestone index is out of range")]
+    InvalidMilestoneIndex,
+    #[msg("Grant is not active")]
+    GrantNotActive,
+    #[msg("Milestone is not pending")]
+    MilestoneNotPending,
+    #[msg("Milestone has not been submitted")]
+    MilestoneNotSubmitted,
+    #[msg("Unauthorized governance authority")]
+    UnauthorizedGovernance,
+    #[msg("Unauthorized grantee")]
+    UnauthorizedGrantee,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Protocol is paused by the guardian")]
+    ProtocolPaused,
+}
@@ -0,0 +1,506 @@
+use anchor_lang::prelude::*;
+use program_utils::ed25519::preceding_signature;
+
+declare_id!("H9AnMRc5x7M817Hzt27erYHd6vGNHkknFpFgV17LxCUW");
+
+/// Native ed25519 signature verification program
+pub use program_utils::ed25519::ED25519_PROGRAM_ID;
+
+#[program]
+pub mod usage_metering {
+    use super::*;
+
+    /// Open a metered billing account between a user and an agent
+    pub fn open_meter(
+        ctx: Context<OpenMeter>,
+        agent_id: Pubkey,
+        receipt_signer: Pubkey,
+        plan: BillingPlan,
+    ) -> Result<()> {
+        if let BillingPlan::Subscription { period_seconds, query_quota, .. } = plan {
+            require!(period_seconds > 0, MeteringError::InvalidPlan);
+            require!(query_quota > 0, MeteringError::InvalidPlan);
+        }
+
+        let meter_key = ctx.accounts.meter.key();
+        let meter = &mut ctx.accounts.meter;
+        let clock = Clock::get()?;
+
+        meter.user = ctx.accounts.user.key();
+        meter.agent_id = agent_id;
+        meter.agent_authority = ctx.accounts.agent_authority.key();
+        meter.receipt_signer = receipt_signer;
+        meter.plan = plan;
+        meter.credit_balance = 0;
+        meter.period_end = 0;
+        meter.period_queries = 0;
+        meter.last_sequence = 0;
+        meter.total_queries = 0;
+        meter.total_bytes = 0;
+        meter.total_billed = 0;
+        meter.created_at = clock.unix_timestamp;
+
+        emit!(MeterOpened {
+            meter: meter_key,
+            user: meter.user,
+            agent_id,
+            agent_authority: meter.agent_authority,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit prepaid credits into the meter (anyone may top up a meter)
+    pub fn deposit_credits(
+        ctx: Context<DepositCredits>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, MeteringError::InvalidAmount);
+        require!(
+            matches!(ctx.accounts.meter.plan, BillingPlan::Prepaid { .. }),
+            MeteringError::PlanMismatch
+        );
+
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.depositor.key(),
+            &ctx.accounts.meter.key(),
+            amount,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_instruction,
+            &[
+                ctx.accounts.depositor.to_account_info(),
+                ctx.accounts.meter.to_account_info(),
+            ],
+        )?;
+
+        let meter = &mut ctx.accounts.meter;
+        meter.credit_balance = meter
+            .credit_balance
+            .checked_add(amount)
+            .ok_or(MeteringError::MathOverflow)?;
+
+        emit!(CreditsDeposited {
+            meter: meter.key(),
+            depositor: ctx.accounts.depositor.key(),
+            amount,
+            credit_balance: meter.credit_balance,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw unused prepaid credits back to the user
+    pub fn withdraw_credits(
+        ctx: Context<WithdrawCredits>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, MeteringError::InvalidAmount);
+
+        let meter = &mut ctx.accounts.meter;
+        require!(meter.credit_balance >= amount, MeteringError::InsufficientCredits);
+        meter.credit_balance -= amount;
+
+        **meter.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.user.try_borrow_mut_lamports()? += amount;
+
+        emit!(CreditsWithdrawn {
+            meter: meter.key(),
+            amount,
+            credit_balance: meter.credit_balance,
+        });
+
+        Ok(())
+    }
+
+    /// Pay the period fee and start a fresh subscription period
+    pub fn renew_subscription(
+        ctx: Context<RenewSubscription>,
+    ) -> Result<()> {
+        let (period_fee, period_seconds) = match ctx.accounts.meter.plan {
+            BillingPlan::Subscription { period_fee, period_seconds, .. } => (period_fee, period_seconds),
+            _ => return err!(MeteringError::PlanMismatch),
+        };
+
+        let clock = Clock::get()?;
+        require!(
+            clock.unix_timestamp >= ctx.accounts.meter.period_end,
+            MeteringError::SubscriptionStillActive
+        );
+
+        if period_fee > 0 {
+            let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.user.key(),
+                &ctx.accounts.agent_authority.key(),
+                period_fee,
+            );
+
+            anchor_lang::solana_program::program::invoke(
+                &transfer_instruction,
+                &[
+                    ctx.accounts.user.to_account_info(),
+                    ctx.accounts.agent_authority.to_account_info(),
+                ],
+            )?;
+        }
+
+        let meter = &mut ctx.accounts.meter;
+        meter.period_end = clock.unix_timestamp + period_seconds;
+        meter.period_queries = 0;
+        meter.total_billed = meter
+            .total_billed
+            .checked_add(period_fee)
+            .ok_or(MeteringError::MathOverflow)?;
+
+        emit!(SubscriptionRenewed {
+            meter: meter.key(),
+            period_fee,
+            period_end: meter.period_end,
+        });
+
+        Ok(())
+    }
+
+    /// Post a batch of user-signed usage receipts and settle the charge
+    ///
+    /// The receipt must be signed by the meter's `receipt_signer` through an
+    /// ed25519 program instruction placed immediately before this one.
+    pub fn post_usage(
+        ctx: Context<PostUsage>,
+        receipt: UsageReceipt,
+    ) -> Result<()> {
+        let meter_key = ctx.accounts.meter.key();
+        require_keys_eq!(receipt.meter, meter_key, MeteringError::ReceiptMeterMismatch);
+        require!(receipt.query_count > 0, MeteringError::EmptyReceipt);
+        require!(
+            receipt.sequence > ctx.accounts.meter.last_sequence,
+            MeteringError::StaleReceipt
+        );
+
+        verify_receipt_signature(
+            &ctx.accounts.instructions.to_account_info(),
+            &ctx.accounts.meter.receipt_signer,
+            &receipt.try_to_vec()?,
+        )?;
+
+        let meter = &mut ctx.accounts.meter;
+        let clock = Clock::get()?;
+
+        let charge = match meter.plan {
+            BillingPlan::Prepaid { price_per_query, price_per_kilobyte } => {
+                let charge = calculate_usage_charge(
+                    receipt.query_count,
+                    receipt.bytes_processed,
+                    price_per_query,
+                    price_per_kilobyte,
+                )
+                .ok_or(MeteringError::MathOverflow)?;
+                require!(meter.credit_balance >= charge, MeteringError::InsufficientCredits);
+                meter.credit_balance -= charge;
+                charge
+            }
+            BillingPlan::Subscription { query_quota, .. } => {
+                require!(
+                    clock.unix_timestamp < meter.period_end,
+                    MeteringError::SubscriptionExpired
+                );
+                let period_queries = meter
+                    .period_queries
+                    .checked_add(receipt.query_count)
+                    .ok_or(MeteringError::MathOverflow)?;
+                require!(period_queries <= query_quota, MeteringError::QuotaExceeded);
+                meter.period_queries = period_queries;
+                0
+            }
+        };
+
+        meter.last_sequence = receipt.sequence;
+        meter.total_queries = meter.total_queries.saturating_add(receipt.query_count);
+        meter.total_bytes = meter.total_bytes.saturating_add(receipt.bytes_processed);
+        meter.total_billed = meter.total_billed.saturating_add(charge);
+
+        if charge > 0 {
+            **meter.to_account_info().try_borrow_mut_lamports()? -= charge;
+            **ctx.accounts.agent_authority.try_borrow_mut_lamports()? += charge;
+        }
+
+        emit!(UsageSettled {
+            meter: meter_key,
+            agent_id: meter.agent_id,
+            sequence: receipt.sequence,
+            query_count: receipt.query_count,
+            bytes_processed: receipt.bytes_processed,
+            charge,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Close the meter and refund remaining credits and rent to the user
+    pub fn close_meter(
+        ctx: Context<CloseMeter>,
+    ) -> Result<()> {
+        emit!(MeterClosed {
+            meter: ctx.accounts.meter.key(),
+            user: ctx.accounts.user.key(),
+            refunded_credits: ctx.accounts.meter.credit_balance,
+        });
+
+        Ok(())
+    }
+}
+
+// Helper function to price a batch of prepaid usage
+fn calculate_usage_charge(
+    query_count: u64,
+    bytes_processed: u64,
+    price_per_query: u64,
+    price_per_kilobyte: u64,
+) -> Option<u64> {
+    let query_charge = query_count.checked_mul(price_per_query)?;
+    let byte_charge = bytes_processed.div_ceil(1024).checked_mul(price_per_kilobyte)?;
+    query_charge.checked_add(byte_charge)
+}
+
+// Helper function to check the ed25519 instruction preceding the current one
+// signed `message` with `signer`
+fn verify_receipt_signature(
+    instructions: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let verified = preceding_signature(
+        instructions,
+        MeteringError::MissingReceiptSignature,
+        MeteringError::InvalidReceiptSignature,
+    )?;
+    require_keys_eq!(verified.signer, *signer, MeteringError::InvalidReceiptSigner);
+    require!(verified.message == message, MeteringError::InvalidReceiptSignature);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(agent_id: Pubkey)]
+pub struct OpenMeter<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + UsageMeter::INIT_SPACE,
+        seeds = [b"meter", user.key().as_ref(), agent_id.as_ref()],
+        bump
+    )]
+    pub meter: Account<'info, UsageMeter>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// Agent operator co-signs to accept the billing plan
+    pub agent_authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCredits<'info> {
+    #[account(
+        mut,
+        seeds = [b"meter", meter.user.as_ref(), meter.agent_id.as_ref()],
+        bump
+    )]
+    pub meter: Account<'info, UsageMeter>,
+
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawCredits<'info> {
+    #[account(
+        mut,
+        seeds = [b"meter", user.key().as_ref(), meter.agent_id.as_ref()],
+        bump,
+        has_one = user @ MeteringError::UnauthorizedUser
+    )]
+    pub meter: Account<'info, UsageMeter>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RenewSubscription<'info> {
+    #[account(
+        mut,
+        seeds = [b"meter", user.key().as_ref(), meter.agent_id.as_ref()],
+        bump,
+        has_one = user @ MeteringError::UnauthorizedUser,
+        has_one = agent_authority @ MeteringError::UnauthorizedAgent
+    )]
+    pub meter: Account<'info, UsageMeter>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: Agent authority will receive the period fee
+    #[account(mut)]
+    pub agent_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PostUsage<'info> {
+    #[account(
+        mut,
+        seeds = [b"meter", meter.user.as_ref(), meter.agent_id.as_ref()],
+        bump,
+        has_one = agent_authority @ MeteringError::UnauthorizedAgent
+    )]
+    pub meter: Account<'info, UsageMeter>,
+
+    #[account(mut)]
+    pub agent_authority: Signer<'info>,
+
+    /// CHECK: Instructions sysvar used to introspect the ed25519 receipt signature
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseMeter<'info> {
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"meter", user.key().as_ref(), meter.agent_id.as_ref()],
+        bump,
+        has_one = user @ MeteringError::UnauthorizedUser
+    )]
+    pub meter: Account<'info, UsageMeter>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct UsageMeter {
+    pub user: Pubkey,
+    pub agent_id: Pubkey,
+    pub agent_authority: Pubkey,
+    pub receipt_signer: Pubkey,
+    pub plan: BillingPlan,
+    pub credit_balance: u64,
+    pub period_end: i64,
+    pub period_queries: u64,
+    pub last_sequence: u64,
+    pub total_queries: u64,
+    pub total_bytes: u64,
+    pub total_billed: u64,
+    pub created_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub enum BillingPlan {
+    Prepaid { price_per_query: u64, price_per_kilobyte: u64 },
+    Subscription { period_fee: u64, period_seconds: i64, query_quota: u64 },
+}
+
+/// Usage batch signed off-chain by the meter's receipt signer
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct UsageReceipt {
+    pub meter: Pubkey,
+    pub sequence: u64,
+    pub query_count: u64,
+    pub bytes_processed: u64,
+}
+
+#[event]
+pub struct MeterOpened {
+    pub meter: Pubkey,
+    pub user: Pubkey,
+    pub agent_id: Pubkey,
+    pub agent_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CreditsDeposited {
+    pub meter: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub credit_balance: u64,
+}
+
+#[event]
+pub struct CreditsWithdrawn {
+    pub meter: Pubkey,
+    pub amount: u64,
+    pub credit_balance: u64,
+}
+
+#[event]
+pub struct SubscriptionRenewed {
+    pub meter: Pubkey,
+    pub period_fee: u64,
+    pub period_end: i64,
+}
+
+#[event]
+pub struct UsageSettled {
+    pub meter: Pubkey,
+    pub agent_id: Pubkey,
+    pub sequence: u64,
+    pub query_count: u64,
+    pub bytes_processed: u64,
+    pub charge: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MeterClosed {
+    pub meter: Pubkey,
+    pub user: Pubkey,
+    pub refunded_credits: u64,
+}
+
+#[error_code]
+pub enum MeteringError {
+    #[msg("Invalid billing plan")]
+    InvalidPlan,
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    #[msg("Operation does not match the meter's billing plan")]
+    PlanMismatch,
+    #[msg("Insufficient prepaid credits")]
+    InsufficientCredits,
+    #[msg("Current subscription period is still active")]
+    SubscriptionStillActive,
+    #[msg("Subscription period has expired")]
+    SubscriptionExpired,
+    #[msg("Subscription query quota exceeded")]
+    QuotaExceeded,
+    #[msg("Receipt is for a different meter")]
+    ReceiptMeterMismatch,
+    #[msg("Receipt contains no usage")]
+    EmptyReceipt,
+    #[msg("Receipt sequence has already been settled")]
+    StaleReceipt,
+    #[msg("Missing ed25519 receipt signature instruction")]
+    MissingReceiptSignature,
+    #[msg("Receipt signature instruction is malformed or does not match")]
+    InvalidReceiptSignature,
+    #[msg("Receipt was not signed by the meter's receipt signer")]
+    InvalidReceiptSigner,
+    #[msg("Unauthorized user")]
+    UnauthorizedUser,
+    #[msg("Unauthorized agent authority")]
+    UnauthorizedAgent,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+}
@@ -0,0 +1,645 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use agent_registry::AgentProfile;
+use marketplace_escrow::{RequestStatus, ServiceRequest};
+use usage_metering::program::UsageMetering;
+use usage_metering::UsageMeter;
+
+declare_id!("ADv1CpxRR8APXqWGJSsCJCbPoTVLdv4dWCx34e8Wiget");
+
+/// Payload type for a mirrored agent registration
+pub const PAYLOAD_AGENT_REGISTERED: u8 = 1;
+/// Payload type for a mirrored escrow payout
+pub const PAYLOAD_PAYOUT: u8 = 2;
+/// Payload type for an inbound cross-chain credit
+pub const PAYLOAD_CREDIT: u8 = 3;
+
+/// Wormhole finality level for outbound messages
+pub const CONSISTENCY_FINALIZED: u8 = 32;
+
+// Core bridge `PostMessage` instruction discriminant
+const POST_MESSAGE_IX: u8 = 1;
+// Offset of the message fee inside the core bridge config account
+const BRIDGE_FEE_OFFSET: usize = 16;
+// Offsets inside a core bridge PostedVAA account
+const VAA_MAGIC: &[u8; 3] = b"vaa";
+const VAA_SEQUENCE_OFFSET: usize = 49;
+const VAA_EMITTER_CHAIN_OFFSET: usize = 57;
+const VAA_EMITTER_ADDRESS_OFFSET: usize = 59;
+const VAA_PAYLOAD_OFFSET: usize = 91;
+
+#[program]
+pub mod wormhole_adapter {
+    use super::*;
+
+    /// Initialize the adapter against a Wormhole core bridge deployment
+    pub fn initialize_adapter(
+        ctx: Context<InitializeAdapter>,
+        wormhole_program: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.adapter_config;
+        config.admin = ctx.accounts.admin.key();
+        config.wormhole_program = wormhole_program;
+        config.message_count = 0;
+        config.total_credited = 0;
+
+        emit!(AdapterInitialized {
+            admin: config.admin,
+            wormhole_program,
+        });
+
+        Ok(())
+    }
+
+    /// Trust a remote chain's AgentMarket emitter for inbound credits (admin only)
+    pub fn register_emitter(
+        ctx: Context<RegisterEmitter>,
+        chain: u16,
+        address: [u8; 32],
+    ) -> Result<()> {
+        let emitter = &mut ctx.accounts.foreign_emitter;
+        emitter.chain = chain;
+        emitter.address = address;
+
+        emit!(EmitterRegistered {
+            chain,
+            address,
+        });
+
+        Ok(())
+    }
+
+    /// Publish an agent registration to other chains (permissionless, once per agent)
+    pub fn mirror_agent_registration(
+        ctx: Context<MirrorAgentRegistration>,
+        nonce: u32,
+    ) -> Result<()> {
+        let profile = &ctx.accounts.agent_profile;
+        let payload = encode_agent_registered(
+            &profile.agent_id,
+            &profile.creator,
+            &profile.nft_mint,
+            profile.created_at,
+            &profile.name,
+        );
+
+        let sequence = post_message(
+            &mut ctx.accounts.wormhole,
+            ctx.bumps.wormhole,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            nonce,
+            payload,
+        )?;
+
+        let mirror = &mut ctx.accounts.mirror_record;
+        mirror.source = profile.key();
+        mirror.sequence = sequence;
+
+        emit!(MessageMirrored {
+            source: mirror.source,
+            payload_type: PAYLOAD_AGENT_REGISTERED,
+            sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Publish an approved escrow payout to other chains (permissionless, once per request)
+    pub fn mirror_payout(
+        ctx: Context<MirrorPayout>,
+        nonce: u32,
+    ) -> Result<()> {
+        let request = &ctx.accounts.service_request;
+        require!(request.status == RequestStatus::Approved, AdapterError::PayoutNotApproved);
+
+        let payload = encode_payout(
+            &request.request_id,
+            &request.agent_id,
+            &request.user,
+            request.amount,
+            request.completed_at.unwrap_or_default(),
+        );
+
+        let sequence = post_message(
+            &mut ctx.accounts.wormhole,
+            ctx.bumps.wormhole,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            nonce,
+            payload,
+        )?;
+
+        let mirror = &mut ctx.accounts.mirror_record;
+        mirror.source = request.key();
+        mirror.sequence = sequence;
+
+        emit!(MessageMirrored {
+            source: mirror.source,
+            payload_type: PAYLOAD_PAYOUT,
+            sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Redeem a verified VAA from a trusted emitter into a user's usage-metering credits
+    pub fn receive_credit(
+        ctx: Context<ReceiveCredit>,
+        chain: u16,
+        sequence: u64,
+    ) -> Result<()> {
+        let config = &ctx.accounts.adapter_config;
+        let posted_vaa = &ctx.accounts.posted_vaa;
+        require!(
+            *posted_vaa.owner == config.wormhole_program,
+            AdapterError::InvalidVaa
+        );
+
+        let data = posted_vaa.try_borrow_data()?;
+        let vaa = parse_posted_vaa(&data)?;
+        require!(
+            vaa.emitter_chain == chain && vaa.sequence == sequence,
+            AdapterError::InvalidVaa
+        );
+        require!(
+            vaa.emitter_address == ctx.accounts.foreign_emitter.address,
+            AdapterError::UnknownEmitter
+        );
+
+        let credit = decode_credit(vaa.payload)?;
+        drop(data);
+
+        let meter = &ctx.accounts.meter;
+        require!(
+            meter.user == credit.user && meter.agent_id == credit.agent_id,
+            AdapterError::MeterMismatch
+        );
+
+        let vault_seeds: &[&[&[u8]]] = &[&[b"bridge_vault", &[ctx.bumps.bridge_vault]]];
+
+        usage_metering::cpi::deposit_credits(
+            CpiContext::new_with_signer(
+                ctx.accounts.usage_metering_program.to_account_info(),
+                usage_metering::cpi::accounts::DepositCredits {
+                    meter: ctx.accounts.meter.to_account_info(),
+                    depositor: ctx.accounts.bridge_vault.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                },
+                vault_seeds,
+            ),
+            credit.amount,
+        )?;
+
+        let received = &mut ctx.accounts.received;
+        received.chain = chain;
+        received.sequence = sequence;
+        received.amount = credit.amount;
+
+        let config = &mut ctx.accounts.adapter_config;
+        config.total_credited = config.total_credited.saturating_add(credit.amount);
+
+        emit!(CreditReceived {
+            chain,
+            sequence,
+            user: credit.user,
+            agent_id: credit.agent_id,
+            amount: credit.amount,
+        });
+
+        Ok(())
+    }
+}
+
+// Helper function to pay the bridge fee and CPI the core bridge `PostMessage` instruction
+fn post_message<'info>(
+    accounts: &mut WormholeMessage<'info>,
+    bumps: WormholeMessageBumps,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    nonce: u32,
+    payload: Vec<u8>,
+) -> Result<u64> {
+    let fee = {
+        let data = accounts.wormhole_bridge.try_borrow_data()?;
+        data.get(BRIDGE_FEE_OFFSET..BRIDGE_FEE_OFFSET + 8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+            .ok_or(AdapterError::InvalidBridgeAccount)?
+    };
+
+    if fee > 0 {
+        let transfer_instruction = anchor_lang::solana_program::system_instruction::transfer(
+            &payer.key(),
+            &accounts.wormhole_fee_collector.key(),
+            fee,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_instruction,
+            &[
+                payer.to_account_info(),
+                accounts.wormhole_fee_collector.to_account_info(),
+            ],
+        )?;
+    }
+
+    let mut data = vec![POST_MESSAGE_IX];
+    data.extend_from_slice(&nonce.to_le_bytes());
+    data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    data.extend_from_slice(&payload);
+    data.push(CONSISTENCY_FINALIZED);
+
+    let instruction = Instruction {
+        program_id: accounts.wormhole_program.key(),
+        accounts: vec![
+            AccountMeta::new(accounts.wormhole_bridge.key(), false),
+            AccountMeta::new(accounts.wormhole_message.key(), true),
+            AccountMeta::new_readonly(accounts.emitter.key(), true),
+            AccountMeta::new(accounts.wormhole_sequence.key(), false),
+            AccountMeta::new(payer.key(), true),
+            AccountMeta::new(accounts.wormhole_fee_collector.key(), false),
+            AccountMeta::new_readonly(accounts.clock.key(), false),
+            AccountMeta::new_readonly(accounts.rent.key(), false),
+            AccountMeta::new_readonly(system_program.key(), false),
+        ],
+        data,
+    };
+
+    let sequence = accounts.adapter_config.message_count;
+    let sequence_bytes = sequence.to_le_bytes();
+
+    anchor_lang::solana_program::program::invoke_signed(
+        &instruction,
+        &[
+            accounts.wormhole_bridge.to_account_info(),
+            accounts.wormhole_message.to_account_info(),
+            accounts.emitter.to_account_info(),
+            accounts.wormhole_sequence.to_account_info(),
+            payer.to_account_info(),
+            accounts.wormhole_fee_collector.to_account_info(),
+            accounts.clock.to_account_info(),
+            accounts.rent.to_account_info(),
+            system_program.to_account_info(),
+        ],
+        &[
+            &[b"emitter", &[bumps.emitter]],
+            &[b"sent", sequence_bytes.as_ref(), &[bumps.wormhole_message]],
+        ],
+    )?;
+
+    accounts.adapter_config.message_count += 1;
+
+    Ok(sequence)
+}
+
+/// Fields read from a core bridge PostedVAA account
+pub struct PostedVaa<'a> {
+    pub sequence: u64,
+    pub emitter_chain: u16,
+    pub emitter_address: [u8; 32],
+    pub payload: &'a [u8],
+}
+
+/// Parse a core bridge PostedVAA account; only the bridge can create accounts with this layout
+pub fn parse_posted_vaa(data: &[u8]) -> Result<PostedVaa<'_>> {
+    require!(
+        data.len() >= VAA_PAYLOAD_OFFSET + 4 && &data[..3] == VAA_MAGIC,
+        AdapterError::InvalidVaa
+    );
+
+    let sequence = u64::from_le_bytes(data[VAA_SEQUENCE_OFFSET..VAA_SEQUENCE_OFFSET + 8].try_into().unwrap());
+    let emitter_chain = u16::from_le_bytes(
+        data[VAA_EMITTER_CHAIN_OFFSET..VAA_EMITTER_CHAIN_OFFSET + 2].try_into().unwrap(),
+    );
+    let emitter_address: [u8; 32] = data[VAA_EMITTER_ADDRESS_OFFSET..VAA_EMITTER_ADDRESS_OFFSET + 32]
+        .try_into()
+        .unwrap();
+    let payload_len = u32::from_le_bytes(data[VAA_PAYLOAD_OFFSET..VAA_PAYLOAD_OFFSET + 4].try_into().unwrap()) as usize;
+    let payload = data
+        .get(VAA_PAYLOAD_OFFSET + 4..VAA_PAYLOAD_OFFSET + 4 + payload_len)
+        .ok_or(AdapterError::InvalidVaa)?;
+
+    Ok(PostedVaa {
+        sequence,
+        emitter_chain,
+        emitter_address,
+        payload,
+    })
+}
+
+/// Encode an agent registration payload (big-endian, Wormhole convention)
+pub fn encode_agent_registered(
+    agent_id: &Pubkey,
+    creator: &Pubkey,
+    nft_mint: &Pubkey,
+    created_at: i64,
+    name: &str,
+) -> Vec<u8> {
+    let mut payload = vec![PAYLOAD_AGENT_REGISTERED];
+    payload.extend_from_slice(agent_id.as_ref());
+    payload.extend_from_slice(creator.as_ref());
+    payload.extend_from_slice(nft_mint.as_ref());
+    payload.extend_from_slice(&created_at.to_be_bytes());
+    payload.extend_from_slice(&(name.len() as u16).to_be_bytes());
+    payload.extend_from_slice(name.as_bytes());
+    payload
+}
+
+/// Encode an escrow payout payload (big-endian, Wormhole convention)
+pub fn encode_payout(
+    request_id: &Pubkey,
+    agent_id: &Pubkey,
+    user: &Pubkey,
+    amount: u64,
+    completed_at: i64,
+) -> Vec<u8> {
+    let mut payload = vec![PAYLOAD_PAYOUT];
+    payload.extend_from_slice(request_id.as_ref());
+    payload.extend_from_slice(agent_id.as_ref());
+    payload.extend_from_slice(user.as_ref());
+    payload.extend_from_slice(&amount.to_be_bytes());
+    payload.extend_from_slice(&completed_at.to_be_bytes());
+    payload
+}
+
+/// Inbound credit instruction carried by a VAA payload
+pub struct CreditPayload {
+    pub user: Pubkey,
+    pub agent_id: Pubkey,
+    pub amount: u64,
+}
+
+/// Decode a `PAYLOAD_CREDIT` payload: type, user, agent_id, amount (big-endian)
+pub fn decode_credit(payload: &[u8]) -> Result<CreditPayload> {
+    require!(
+        payload.len() == 73 && payload[0] == PAYLOAD_CREDIT,
+        AdapterError::InvalidPayload
+    );
+
+    let user = Pubkey::new_from_array(payload[1..33].try_into().unwrap());
+    let agent_id = Pubkey::new_from_array(payload[33..65].try_into().unwrap());
+    let amount = u64::from_be_bytes(payload[65..73].try_into().unwrap());
+    require!(amount > 0, AdapterError::InvalidPayload);
+
+    Ok(CreditPayload {
+        user,
+        agent_id,
+        amount,
+    })
+}
+
+#[derive(Accounts)]
+pub struct InitializeAdapter<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AdapterConfig::INIT_SPACE,
+        seeds = [b"bridge_config"],
+        bump
+    )]
+    pub adapter_config: Account<'info, AdapterConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain: u16)]
+pub struct RegisterEmitter<'info> {
+    #[account(
+        seeds = [b"bridge_config"],
+        bump,
+        has_one = admin @ AdapterError::UnauthorizedAdmin
+    )]
+    pub adapter_config: Account<'info, AdapterConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + ForeignEmitter::INIT_SPACE,
+        seeds = [b"foreign_emitter", chain.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub foreign_emitter: Account<'info, ForeignEmitter>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts required to post a message through the Wormhole core bridge
+#[derive(Accounts)]
+pub struct WormholeMessage<'info> {
+    #[account(
+        mut,
+        seeds = [b"bridge_config"],
+        bump
+    )]
+    pub adapter_config: Account<'info, AdapterConfig>,
+
+    /// CHECK: Core bridge config; owned by the configured Wormhole program
+    #[account(mut, owner = adapter_config.wormhole_program @ AdapterError::InvalidBridgeAccount)]
+    pub wormhole_bridge: UncheckedAccount<'info>,
+
+    /// CHECK: Created by the core bridge; PDA signer of this program
+    #[account(
+        mut,
+        seeds = [b"sent", adapter_config.message_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub wormhole_message: UncheckedAccount<'info>,
+
+    /// CHECK: Emitter PDA that signs every outbound message
+    #[account(
+        seeds = [b"emitter"],
+        bump
+    )]
+    pub emitter: UncheckedAccount<'info>,
+
+    /// CHECK: Emitter sequence tracker; validated by the core bridge
+    #[account(mut)]
+    pub wormhole_sequence: UncheckedAccount<'info>,
+
+    /// CHECK: Bridge fee collector; validated by the core bridge
+    #[account(mut)]
+    pub wormhole_fee_collector: UncheckedAccount<'info>,
+
+    /// CHECK: Must be the configured Wormhole core bridge
+    #[account(
+        executable,
+        constraint = wormhole_program.key() == adapter_config.wormhole_program @ AdapterError::InvalidBridgeAccount
+    )]
+    pub wormhole_program: UncheckedAccount<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MirrorAgentRegistration<'info> {
+    pub wormhole: WormholeMessage<'info>,
+
+    pub agent_profile: Account<'info, AgentProfile>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MirrorRecord::INIT_SPACE,
+        seeds = [b"mirrored", agent_profile.key().as_ref()],
+        bump
+    )]
+    pub mirror_record: Account<'info, MirrorRecord>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MirrorPayout<'info> {
+    pub wormhole: WormholeMessage<'info>,
+
+    pub service_request: Account<'info, ServiceRequest>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MirrorRecord::INIT_SPACE,
+        seeds = [b"mirrored", service_request.key().as_ref()],
+        bump
+    )]
+    pub mirror_record: Account<'info, MirrorRecord>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(chain: u16, sequence: u64)]
+pub struct ReceiveCredit<'info> {
+    #[account(
+        mut,
+        seeds = [b"bridge_config"],
+        bump
+    )]
+    pub adapter_config: Account<'info, AdapterConfig>,
+
+    #[account(
+        seeds = [b"foreign_emitter", chain.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub foreign_emitter: Account<'info, ForeignEmitter>,
+
+    /// CHECK: Verified VAA; owner and layout checked in the handler
+    pub posted_vaa: UncheckedAccount<'info>,
+
+    /// Replay guard: one credit per (chain, sequence)
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ReceivedMessage::INIT_SPACE,
+        seeds = [b"received", chain.to_le_bytes().as_ref(), sequence.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub received: Account<'info, ReceivedMessage>,
+
+    /// Liquidity vault that fronts bridged credits on Solana
+    #[account(
+        mut,
+        seeds = [b"bridge_vault"],
+        bump
+    )]
+    pub bridge_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub meter: Account<'info, UsageMeter>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub usage_metering_program: Program<'info, UsageMetering>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AdapterConfig {
+    pub admin: Pubkey,
+    pub wormhole_program: Pubkey,
+    pub message_count: u64,
+    pub total_credited: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ForeignEmitter {
+    pub chain: u16,
+    pub address: [u8; 32],
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MirrorRecord {
+    pub source: Pubkey,
+    pub sequence: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ReceivedMessage {
+    pub chain: u16,
+    pub sequence: u64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AdapterInitialized {
+    pub admin: Pubkey,
+    pub wormhole_program: Pubkey,
+}
+
+#[event]
+pub struct EmitterRegistered {
+    pub chain: u16,
+    pub address: [u8; 32],
+}
+
+#[event]
+pub struct MessageMirrored {
+    pub source: Pubkey,
+    pub payload_type: u8,
+    pub sequence: u64,
+}
+
+#[event]
+pub struct CreditReceived {
+    pub chain: u16,
+    pub sequence: u64,
+    pub user: Pubkey,
+    pub agent_id: Pubkey,
+    pub amount: u64,
+}
+
+#[error_code]
+pub enum AdapterError {
+    #[msg("Account is not the configured Wormhole core bridge")]
+    InvalidBridgeAccount,
+    #[msg("Invalid or unverified VAA")]
+    InvalidVaa,
+    #[msg("VAA emitter is not registered for its chain")]
+    UnknownEmitter,
+    #[msg("Invalid message payload")]
+    InvalidPayload,
+    #[msg("Meter does not match the credited user and agent")]
+    MeterMismatch,
+    #[msg("Service request has not been approved")]
+    PayoutNotApproved,
+    #[msg("Unauthorized admin access")]
+    UnauthorizedAdmin,
+}